@@ -1,4 +1,7 @@
-use std::{ffi::CStr, os::raw::c_char};
+use std::{
+    ffi::{CStr, CString},
+    os::raw::c_char,
+};
 
 /// No error.
 pub const ERR_OK: i32 = 0;
@@ -18,6 +21,8 @@ pub const ERR_SYNC_CHANNEL_RECV: i32 = 6;
 pub const ERR_RUNTIME_MANAGER: i32 = 7;
 /// No associated config file.
 pub const ERR_NO_CONFIG_FILE: i32 = 8;
+/// Invalid string argument or insufficient output buffer.
+pub const ERR_INVALID_ARGUMENT: i32 = 9;
 
 fn to_errno(e: leaf::Error) -> i32 {
     match e {
@@ -119,6 +124,87 @@ pub extern "C" fn leaf_reload(rt_id: u16) -> i32 {
     ERR_OK
 }
 
+/// Reloads the routing rules from the config file, DNS servers, outbounds
+/// and connections are kept.
+///
+/// @param rt_id The ID of the leaf instance to reload.
+///
+/// @return Returns ERR_OK on success.
+#[no_mangle]
+pub extern "C" fn leaf_reload_router(rt_id: u16) -> i32 {
+    if let Err(e) = leaf::reload_router(rt_id) {
+        return to_errno(e);
+    }
+    ERR_OK
+}
+
+/// Changes the outbound a `select` outbound uses, the selection is persisted
+/// and restored when leaf starts again.
+///
+/// @param rt_id The ID of the leaf instance.
+/// @param outbound The tag of the select outbound.
+/// @param select The tag of the outbound to use, must be one of the actors of
+///               the select outbound.
+/// @return Returns ERR_OK on success.
+#[no_mangle]
+pub extern "C" fn leaf_set_outbound_selected(
+    rt_id: u16,
+    outbound: *const c_char,
+    select: *const c_char,
+) -> i32 {
+    let (outbound, select) = match unsafe {
+        (
+            CStr::from_ptr(outbound).to_str(),
+            CStr::from_ptr(select).to_str(),
+        )
+    } {
+        (Ok(outbound), Ok(select)) => (outbound, select),
+        _ => return ERR_INVALID_ARGUMENT,
+    };
+    if let Err(e) = leaf::set_outbound_selected(rt_id, outbound, select) {
+        return to_errno(e);
+    }
+    ERR_OK
+}
+
+/// Gets the outbound a `select` outbound uses.
+///
+/// @param rt_id The ID of the leaf instance.
+/// @param outbound The tag of the select outbound.
+/// @param buf The buffer the NUL-terminated tag of the selected outbound is
+///            written to.
+/// @param buf_len The size of the buffer.
+/// @return Returns ERR_OK on success, ERR_INVALID_ARGUMENT if the buffer is too
+///         small.
+#[no_mangle]
+pub extern "C" fn leaf_get_outbound_selected(
+    rt_id: u16,
+    outbound: *const c_char,
+    buf: *mut c_char,
+    buf_len: i32,
+) -> i32 {
+    let outbound = match unsafe { CStr::from_ptr(outbound).to_str() } {
+        Ok(outbound) => outbound,
+        Err(_) => return ERR_INVALID_ARGUMENT,
+    };
+    let selected = match leaf::get_outbound_selected(rt_id, outbound) {
+        Ok(selected) => selected,
+        Err(e) => return to_errno(e),
+    };
+    let selected = match CString::new(selected) {
+        Ok(selected) => selected,
+        Err(_) => return ERR_INVALID_ARGUMENT,
+    };
+    let bytes = selected.as_bytes_with_nul();
+    if buf.is_null() || buf_len < 0 || bytes.len() > buf_len as usize {
+        return ERR_INVALID_ARGUMENT;
+    }
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr() as *const c_char, buf, bytes.len());
+    }
+    ERR_OK
+}
+
 /// Shuts down leaf.
 ///
 /// @param rt_id The ID of the leaf instance to reload.
@@ -145,3 +231,83 @@ pub extern "C" fn leaf_test_config(config_path: *const c_char) -> i32 {
         ERR_CONFIG_PATH
     }
 }
+
+/// Sets a callback to find the UID of the app owning a connection, APP rules
+/// rely on it on Android 10 and above, where /proc/net is not accessible. A
+/// typical implementation calls ConnectivityManager.getConnectionOwnerUid.
+///
+/// @param resolver A function taking the IP protocol number (6 for TCP, 17 for UDP),
+///                 the local address and the remote address of the connection in
+///                 the form of "ip:port", returns the UID or -1 if not found. Passing
+///                 NULL removes the callback.
+#[no_mangle]
+pub extern "C" fn leaf_set_uid_resolver(
+    resolver: Option<extern "C" fn(i32, *const c_char, *const c_char) -> i32>,
+) {
+    let resolver = resolver.map(|resolver| {
+        Box::new(
+            move |network: leaf::session::Network,
+                  source: &std::net::SocketAddr,
+                  destination: &std::net::SocketAddr| {
+                let protocol = match network {
+                    leaf::session::Network::Tcp => 6,
+                    leaf::session::Network::Udp => 17,
+                };
+                let source = CString::new(source.to_string()).ok()?;
+                let destination = CString::new(destination.to_string()).ok()?;
+                let uid = resolver(protocol, source.as_ptr(), destination.as_ptr());
+                if uid < 0 {
+                    None
+                } else {
+                    Some(uid as u32)
+                }
+            },
+        ) as leaf::common::process::UidResolver
+    });
+    leaf::common::process::set_uid_resolver(resolver);
+}
+
+/// Sets a callback to find the executable path of the process owning a
+/// connection, PROCESS-NAME and PROCESS-PATH rules rely on it on platforms
+/// without procfs, e.g. with proc_pidfdinfo and proc_pidpath on macOS.
+///
+/// @param resolver A function taking the IP protocol number (6 for TCP, 17 for UDP),
+///                 the local address and the remote address of the connection in
+///                 the form of "ip:port", and a buffer with its size, which it fills
+///                 with the NUL-terminated path, returns 0 on success or -1 if not
+///                 found. Passing NULL removes the callback.
+#[no_mangle]
+pub extern "C" fn leaf_set_process_resolver(
+    resolver: Option<extern "C" fn(i32, *const c_char, *const c_char, *mut c_char, i32) -> i32>,
+) {
+    let resolver = resolver.map(|resolver| {
+        Box::new(
+            move |network: leaf::session::Network,
+                  source: &std::net::SocketAddr,
+                  destination: &std::net::SocketAddr| {
+                let protocol = match network {
+                    leaf::session::Network::Tcp => 6,
+                    leaf::session::Network::Udp => 17,
+                };
+                let source = CString::new(source.to_string()).ok()?;
+                let destination = CString::new(destination.to_string()).ok()?;
+                let mut buf = vec![0 as c_char; 4096];
+                let res = resolver(
+                    protocol,
+                    source.as_ptr(),
+                    destination.as_ptr(),
+                    buf.as_mut_ptr(),
+                    buf.len() as i32,
+                );
+                if res < 0 {
+                    return None;
+                }
+                // Always terminated, in case the buffer is filled up.
+                *buf.last_mut().unwrap() = 0;
+                let path = unsafe { CStr::from_ptr(buf.as_ptr()) };
+                Some(std::path::PathBuf::from(path.to_string_lossy().to_string()))
+            },
+        ) as leaf::common::process::ProcessResolver
+    });
+    leaf::common::process::set_process_resolver(resolver);
+}
@@ -35,6 +35,55 @@ mod models {
         pub send_completed: bool,
         pub recv_completed: bool,
     }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Latency {
+        pub tag: String,
+        // in millis, null if the last probe failed or hasn't been done
+        pub latency: Option<u64>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct HealthReport {
+        pub outbound: String,
+        pub selected: Option<String>,
+        pub latencies: Vec<Latency>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct RuleHits {
+        pub index: usize,
+        pub target: String,
+        pub rule: String,
+        pub hits: u64,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct OutboundHits {
+        pub tag: String,
+        pub hits: u64,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct RouteReport {
+        pub rules: Vec<RuleHits>,
+        // sessions routed by the script
+        pub script: u64,
+        // sessions matching no rules
+        pub unmatched: u64,
+        pub outbounds: Vec<OutboundHits>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct DnsCacheReport {
+        pub size: usize,
+        pub capacity: usize,
+        pub hits: u64,
+        // lookups answered by cached NXDOMAIN or empty responses
+        pub negative_hits: u64,
+        pub misses: u64,
+        pub hit_rate: f64,
+    }
 }
 
 mod handlers {
@@ -83,6 +132,14 @@ mod handlers {
         }
     }
 
+    pub async fn router_reload(rm: Arc<RuntimeManager>) -> Result<impl warp::Reply, Infallible> {
+        if rm.reload_router().await.is_ok() {
+            Ok(StatusCode::OK)
+        } else {
+            Ok(StatusCode::ACCEPTED)
+        }
+    }
+
     pub async fn runtime_shutdown(rm: Arc<RuntimeManager>) -> Result<impl warp::Reply, Infallible> {
         if rm.shutdown().await {
             Ok(StatusCode::OK)
@@ -113,6 +170,60 @@ mod handlers {
         Ok(warp::reply::json(&stats))
     }
 
+    #[cfg(feature = "stat")]
+    pub async fn stat_latency(rm: Arc<RuntimeManager>) -> Result<impl warp::Reply, Infallible> {
+        let mut reports = Vec::new();
+        for (outbound, selected, latencies) in rm.get_outbound_latencies().await {
+            reports.push(models::HealthReport {
+                outbound,
+                selected,
+                latencies: latencies
+                    .into_iter()
+                    .map(|(tag, latency)| models::Latency { tag, latency })
+                    .collect(),
+            });
+        }
+        Ok(warp::reply::json(&reports))
+    }
+
+    #[cfg(feature = "stat")]
+    pub async fn stat_route(rm: Arc<RuntimeManager>) -> Result<impl warp::Reply, Infallible> {
+        let stats = rm.get_route_stats().await;
+        Ok(warp::reply::json(&models::RouteReport {
+            rules: stats
+                .rules
+                .into_iter()
+                .enumerate()
+                .map(|(index, r)| models::RuleHits {
+                    index,
+                    target: r.target,
+                    rule: r.desc,
+                    hits: r.hits,
+                })
+                .collect(),
+            script: stats.script_hits,
+            unmatched: stats.misses,
+            outbounds: stats
+                .outbounds
+                .into_iter()
+                .map(|(tag, hits)| models::OutboundHits { tag, hits })
+                .collect(),
+        }))
+    }
+
+    #[cfg(feature = "stat")]
+    pub async fn stat_dns(rm: Arc<RuntimeManager>) -> Result<impl warp::Reply, Infallible> {
+        let stats = rm.get_dns_cache_stats().await;
+        Ok(warp::reply::json(&models::DnsCacheReport {
+            size: stats.size,
+            capacity: stats.capacity,
+            hits: stats.hits,
+            negative_hits: stats.negative_hits,
+            misses: stats.misses,
+            hit_rate: stats.hit_rate(),
+        }))
+    }
+
     #[cfg(feature = "stat")]
     pub async fn stat_html(rm: Arc<RuntimeManager>) -> Result<impl warp::Reply, Infallible> {
         let mut body = String::from(
@@ -222,6 +333,16 @@ mod filters {
             .and_then(handlers::runtime_reload)
     }
 
+    // POST /api/v1/app/router/reload
+    pub fn router_reload(
+        rm: Arc<RuntimeManager>,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("api" / "v1" / "app" / "router" / "reload")
+            .and(warp::post())
+            .and(with_runtime_manager(rm))
+            .and_then(handlers::router_reload)
+    }
+
     // POST /api/v1/runtime/shutdown
     pub fn runtime_shutdown(
         rm: Arc<RuntimeManager>,
@@ -253,6 +374,39 @@ mod filters {
             .and(with_runtime_manager(rm))
             .and_then(handlers::stat_json)
     }
+
+    // GET /api/v1/runtime/stat/latency
+    #[cfg(feature = "stat")]
+    pub fn stat_latency(
+        rm: Arc<RuntimeManager>,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("api" / "v1" / "runtime" / "stat" / "latency")
+            .and(warp::get())
+            .and(with_runtime_manager(rm))
+            .and_then(handlers::stat_latency)
+    }
+
+    // GET /api/v1/runtime/stat/route
+    #[cfg(feature = "stat")]
+    pub fn stat_route(
+        rm: Arc<RuntimeManager>,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("api" / "v1" / "runtime" / "stat" / "route")
+            .and(warp::get())
+            .and(with_runtime_manager(rm))
+            .and_then(handlers::stat_route)
+    }
+
+    // GET /api/v1/runtime/stat/dns
+    #[cfg(feature = "stat")]
+    pub fn stat_dns(
+        rm: Arc<RuntimeManager>,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("api" / "v1" / "runtime" / "stat" / "dns")
+            .and(warp::get())
+            .and(with_runtime_manager(rm))
+            .and_then(handlers::stat_dns)
+    }
 }
 
 pub struct ApiServer {
@@ -268,12 +422,16 @@ impl ApiServer {
         let routes = filters::select_update(self.runtime_manager.clone())
             .or(filters::select_get(self.runtime_manager.clone()))
             .or(filters::runtime_reload(self.runtime_manager.clone()))
+            .or(filters::router_reload(self.runtime_manager.clone()))
             .or(filters::runtime_shutdown(self.runtime_manager.clone()));
 
         #[cfg(feature = "stat")]
         let routes = routes
             .or(filters::stat_html(self.runtime_manager.clone()))
-            .or(filters::stat_json(self.runtime_manager.clone()));
+            .or(filters::stat_json(self.runtime_manager.clone()))
+            .or(filters::stat_latency(self.runtime_manager.clone()))
+            .or(filters::stat_route(self.runtime_manager.clone()))
+            .or(filters::stat_dns(self.runtime_manager.clone()));
 
         log::info!("api server listening tcp {}", &listen_addr);
         Box::pin(warp::serve(routes).bind(listen_addr))
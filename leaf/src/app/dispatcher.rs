@@ -1,6 +1,9 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::io::{self, ErrorKind};
-use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock as SyncRwLock};
 use std::time::Duration;
 
 use log::*;
@@ -8,9 +11,10 @@ use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use tokio::sync::RwLock;
 
 use crate::{
+    app::fake_dns::{FakeDns, FakeIpGuard},
     app::SyncDnsClient,
     common::{self, sniff},
-    option,
+    config, option,
     proxy::{OutboundDatagram, ProxyStream, TcpOutboundHandler, UdpOutboundHandler},
     session::{Network, Session, SocksAddr},
 };
@@ -51,10 +55,93 @@ fn log_request(
     }
 }
 
+/// Destination sniffing options of an inbound.
+struct Sniffing {
+    protocols: Vec<sniff::Protocol>,
+    // All ports if empty.
+    ports: Vec<u16>,
+    route_only: bool,
+}
+
+impl Default for Sniffing {
+    fn default() -> Self {
+        Sniffing {
+            protocols: vec![
+                sniff::Protocol::Tls,
+                sniff::Protocol::Http,
+                sniff::Protocol::Quic,
+            ],
+            ports: vec![80, 443],
+            route_only: false,
+        }
+    }
+}
+
+impl Sniffing {
+    fn new(settings: &config::Inbound_Sniffing) -> Option<Self> {
+        if !settings.enabled {
+            return None;
+        }
+        let mut protocols = Vec::new();
+        for p in settings.protocols.iter() {
+            match p.parse() {
+                Ok(p) => protocols.push(p),
+                Err(e) => warn!("{}", e),
+            }
+        }
+        if settings.protocols.is_empty() {
+            protocols = vec![
+                sniff::Protocol::Tls,
+                sniff::Protocol::Http,
+                sniff::Protocol::Quic,
+            ];
+        }
+        Some(Sniffing {
+            protocols,
+            ports: settings.ports.iter().map(|p| *p as u16).collect(),
+            route_only: settings.route_only,
+        })
+    }
+
+    fn applies(&self, sess: &Session, protocol: sniff::Protocol) -> bool {
+        !sess.destination.is_domain()
+            && self.protocols.contains(&protocol)
+            && (self.ports.is_empty() || self.ports.contains(&sess.destination.port()))
+    }
+
+    fn apply(&self, sess: &mut Session, domain: &str) -> io::Result<()> {
+        let addr = SocksAddr::try_from((domain, sess.destination.port()))?;
+        if self.route_only {
+            sess.route_destination = Some(addr);
+        } else {
+            sess.destination = addr;
+        }
+        Ok(())
+    }
+}
+
+// Returns the session rules should be matched against.
+fn routing_session(sess: &Session) -> Cow<'_, Session> {
+    match &sess.route_destination {
+        Some(dst) => {
+            let mut sess = sess.clone();
+            sess.destination = dst.clone();
+            Cow::Owned(sess)
+        }
+        None => Cow::Borrowed(sess),
+    }
+}
+
 pub struct Dispatcher {
     outbound_manager: Arc<RwLock<OutboundManager>>,
-    router: Arc<RwLock<Router>>,
+    router: Arc<RwLock<Arc<Router>>>,
     dns_client: SyncDnsClient,
+    // Sniffing options by inbound tags, None if disabled.
+    sniffing: HashMap<String, Option<Sniffing>>,
+    default_sniffing: Sniffing,
+    // Fake DNS answering queries for other inbounds, connections to its fake
+    // IPs are made to the domains.
+    fake_dns: SyncRwLock<Vec<Arc<FakeDns>>>,
     #[cfg(feature = "stat")]
     stat_manager: SyncStatManager,
 }
@@ -62,68 +149,145 @@ pub struct Dispatcher {
 impl Dispatcher {
     pub fn new(
         outbound_manager: Arc<RwLock<OutboundManager>>,
-        router: Arc<RwLock<Router>>,
+        router: Arc<RwLock<Arc<Router>>>,
         dns_client: SyncDnsClient,
+        inbounds: &[config::Inbound],
         #[cfg(feature = "stat")] stat_manager: SyncStatManager,
     ) -> Self {
+        let mut sniffing = HashMap::new();
+        for inbound in inbounds.iter() {
+            if let Some(settings) = inbound.sniffing.as_ref() {
+                sniffing.insert(inbound.tag.clone(), Sniffing::new(settings));
+            }
+        }
         Dispatcher {
             outbound_manager,
             router,
             dns_client,
+            sniffing,
+            default_sniffing: Sniffing::default(),
+            fake_dns: SyncRwLock::new(Vec::new()),
             #[cfg(feature = "stat")]
             stat_manager,
         }
     }
 
+    /// Registers a fake DNS whose fake IPs are connected to by other inbounds,
+    /// e.g. the one of a DNS inbound.
+    pub fn add_fake_dns(&self, fakedns: Arc<FakeDns>) {
+        self.fake_dns.write().unwrap().push(fakedns);
+    }
+
+    /// Replaces a fake IP destination with its domain, the returned guard
+    /// keeps the IP from being recycled.
+    pub async fn restore_fake_domain(&self, sess: &mut Session) -> Option<FakeIpGuard> {
+        let ip = match &sess.destination {
+            SocksAddr::Ip(a) => a.ip(),
+            _ => return None,
+        };
+        let fakedns = self
+            .fake_dns
+            .read()
+            .unwrap()
+            .iter()
+            .find(|f| f.is_fake_ip(&ip))
+            .cloned()?;
+        let (domain, guard) = fakedns.acquire(&ip).await?;
+        sess.destination = SocksAddr::try_from((&domain, sess.destination.port())).ok()?;
+        Some(guard)
+    }
+
+    pub fn dns_client(&self) -> &SyncDnsClient {
+        &self.dns_client
+    }
+
+    fn sniffing(&self, inbound_tag: &str) -> Option<&Sniffing> {
+        match self.sniffing.get(inbound_tag) {
+            Some(s) => s.as_ref(),
+            None => Some(&self.default_sniffing),
+        }
+    }
+
+    /// Returns a sniffer for the first datagrams of a UDP session if QUIC
+    /// sniffing is enabled for it.
+    pub fn datagram_sniffer(&self, sess: &Session) -> Option<sniff::QuicSniffer> {
+        match self.sniffing(&sess.inbound_tag) {
+            Some(s) if s.applies(sess, sniff::Protocol::Quic) => {
+                Some(sniff::QuicSniffer::default())
+            }
+            _ => None,
+        }
+    }
+
+    /// Applies a domain sniffed from the datagrams of a UDP session as the
+    /// sniffing options of its inbound say, either as the destination or for
+    /// routing only.
+    pub fn apply_sniffed_domain(&self, sess: &mut Session, domain: &str) -> io::Result<()> {
+        match self.sniffing(&sess.inbound_tag) {
+            Some(s) => s.apply(sess, domain),
+            None => Ok(()),
+        }
+    }
+
     pub async fn dispatch_tcp<T>(&self, mut sess: Session, lhs: T)
     where
         T: 'static + AsyncRead + AsyncWrite + Unpin + Send + Sync,
     {
-        let mut lhs: Box<dyn ProxyStream> =
-            if !sess.destination.is_domain() && sess.destination.port() == 443 {
-                let mut lhs = sniff::SniffingStream::new(lhs);
-                match lhs.sniff().await {
-                    Ok(res) => {
-                        if let Some(domain) = res {
-                            debug!(
-                                "sniffed domain {} for tcp link {} <-> {}",
-                                &domain, &sess.source, &sess.destination,
-                            );
-                            sess.destination =
-                                match SocksAddr::try_from((&domain, sess.destination.port())) {
-                                    Ok(a) => a,
-                                    Err(e) => {
-                                        warn!(
-                                            "convert sniffed domain {} to destination failed: {}",
-                                            &domain, e,
-                                        );
-                                        return;
-                                    }
-                                };
-                        }
-                    }
-                    Err(e) => {
+        let _fake_ip = self.restore_fake_domain(&mut sess).await;
+        let sniffing = self.sniffing(&sess.inbound_tag).filter(|s| {
+            s.applies(&sess, sniff::Protocol::Tls) || s.applies(&sess, sniff::Protocol::Http)
+        });
+        let mut lhs: Box<dyn ProxyStream> = if let Some(sniffing) = sniffing {
+            let mut lhs = sniff::SniffingStream::new(lhs);
+            match lhs.sniff(&sniffing.protocols).await {
+                Ok(res) => {
+                    if let Some(domain) = res {
                         debug!(
-                            "sniff tcp uplink {} -> {} failed: {}",
-                            &sess.source, &sess.destination, e,
+                            "sniffed domain {} for tcp link {} <-> {}",
+                            &domain, &sess.source, &sess.destination,
                         );
-                        return;
+                        if let Err(e) = sniffing.apply(&mut sess, &domain) {
+                            warn!(
+                                "convert sniffed domain {} to destination failed: {}",
+                                &domain, e,
+                            );
+                            return;
+                        }
+                        sess.sniffed_protocol = lhs.protocol();
                     }
                 }
-                Box::new(lhs)
-            } else {
-                Box::new(lhs)
-            };
+                Err(e) => {
+                    debug!(
+                        "sniff tcp uplink {} -> {} failed: {}",
+                        &sess.source, &sess.destination, e,
+                    );
+                    return;
+                }
+            }
+            Box::new(lhs)
+        } else {
+            Box::new(lhs)
+        };
 
-        let outbound = {
-            let router = self.router.read().await;
-            match router.pick_route(&sess).await {
+        let outbound = if !sess.outbound_tag.is_empty() {
+            // The outbound has been chosen by the inbound.
+            debug!(
+                "use route [{}] for {} -> {}",
+                &sess.outbound_tag, &sess.source, &sess.destination
+            );
+            sess.outbound_tag.clone()
+        } else {
+            // Routing may wait for DNS, the router isn't locked meanwhile so
+            // that reloads aren't blocked.
+            let router = self.router.read().await.clone();
+            router.resolve_process(&mut sess).await;
+            match router.pick_route(&routing_session(&sess)).await {
                 Ok(tag) => {
                     debug!(
                         "picked route [{}] for {} -> {}",
                         tag, &sess.source, &sess.destination
                     );
-                    tag.to_owned()
+                    tag
                 }
                 Err(err) => {
                     trace!("pick route failed: {}", err);
@@ -132,6 +296,7 @@ impl Dispatcher {
                             "picked default route [{}] for {} -> {}",
                             tag, &sess.source, &sess.destination
                         );
+                        router.hit_outbound(&tag);
                         tag
                     } else {
                         warn!("can not find any handlers");
@@ -235,6 +400,15 @@ impl Dispatcher {
 
                 log_request(&sess, h.tag(), h.color(), None);
 
+                // The session is reset, e.g. rejected by the drop outbound,
+                // so is the inbound connection if it can be.
+                if e.kind() == ErrorKind::ConnectionReset {
+                    if let Some(reset) = sess.inbound_reset.as_ref() {
+                        reset.store(true, Ordering::Relaxed);
+                        return;
+                    }
+                }
+
                 if let Err(e) = lhs.shutdown().await {
                     debug!(
                         "tcp downlink {} <- {} error: {} [{}]",
@@ -249,15 +423,25 @@ impl Dispatcher {
     }
 
     pub async fn dispatch_udp(&self, mut sess: Session) -> io::Result<Box<dyn OutboundDatagram>> {
-        let outbound = {
-            let router = self.router.read().await;
-            match router.pick_route(&sess).await {
+        let outbound = if !sess.outbound_tag.is_empty() {
+            // The outbound has been chosen by the inbound.
+            debug!(
+                "use route [{}] for {} -> {}",
+                &sess.outbound_tag, &sess.source, &sess.destination
+            );
+            sess.outbound_tag.clone()
+        } else {
+            // Routing may wait for DNS, the router isn't locked meanwhile so
+            // that reloads aren't blocked.
+            let router = self.router.read().await.clone();
+            router.resolve_process(&mut sess).await;
+            match router.pick_route(&routing_session(&sess)).await {
                 Ok(tag) => {
                     debug!(
                         "picked route [{}] for {} -> {}",
                         tag, &sess.source, &sess.destination
                     );
-                    tag.to_owned()
+                    tag
                 }
                 Err(err) => {
                     trace!("pick route failed: {}", err);
@@ -266,6 +450,7 @@ impl Dispatcher {
                             "picked default route [{}] for {} -> {}",
                             tag, &sess.source, &sess.destination
                         );
+                        router.hit_outbound(&tag);
                         tag
                     } else {
                         warn!("no handler found");
@@ -0,0 +1,243 @@
+//! DNS over HTTPS (RFC 8484). Queries are POSTed as `application/dns-message`
+//! in the streams of a single HTTP/2 connection, a new one is made when it's
+//! closed.
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use ::http::{Method, Request, StatusCode, Uri};
+use anyhow::{anyhow, Result};
+use bytes::Bytes;
+use h2::client::SendRequest;
+use log::*;
+use tokio::time::timeout;
+
+use crate::{
+    common::h2::{spawn_connection, SharedConnection},
+    option,
+    proxy::{tls, TcpOutboundHandler},
+    session::{Session, SocksAddr},
+};
+
+use super::DnsClient;
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const DNS_MESSAGE: &str = "application/dns-message";
+
+pub struct Client {
+    uri: Uri,
+    host: String,
+    port: u16,
+    // The address of a server given by IP, others are looked up on
+    // connecting.
+    ip: Option<IpAddr>,
+    tls: tls::outbound::TcpHandler,
+    // The outbound connections are made through, if any.
+    outbound: Option<String>,
+    connection: SharedConnection,
+}
+
+impl Client {
+    /// Creates a client of the server at `url`, e.g.
+    /// `https://dns.google/dns-query`.
+    pub fn new(url: &str) -> Result<Self> {
+        let uri = url.parse::<Uri>()?;
+        if uri.scheme_str() != Some("https") {
+            return Err(anyhow!("invalid doh url {}", url));
+        }
+        let host = uri
+            .host()
+            .ok_or_else(|| anyhow!("missing host in doh url {}", url))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+        let port = uri.port_u16().unwrap_or(443);
+        let ip = host.parse::<IpAddr>().ok();
+        let tls = tls::outbound::TcpHandler::new(host.clone(), vec!["h2".to_string()], None, None)?;
+        Ok(Client {
+            uri,
+            host,
+            port,
+            ip,
+            tls,
+            outbound: None,
+            connection: SharedConnection::default(),
+        })
+    }
+
+    /// Makes connections through the outbound of the tag, which resolves the
+    /// domain of the server.
+    pub fn with_outbound(mut self, outbound: Option<String>) -> Self {
+        self.outbound = outbound;
+        self
+    }
+
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// Returns the domain of the server to look up, if not given by IP nor
+    /// resolved by an outbound.
+    pub fn domain(&self) -> Option<&str> {
+        if self.ip.is_some() || self.outbound.is_some() {
+            None
+        } else {
+            Some(&self.host)
+        }
+    }
+
+    async fn connect(
+        &self,
+        addr: &SocksAddr,
+        dns_client: &DnsClient,
+    ) -> Result<SendRequest<Bytes>> {
+        let handshake = async {
+            let stream = dns_client.dial_tcp(addr, self.outbound.as_deref()).await?;
+            // The server name of the handler is set, the session is unused.
+            let stream =
+                TcpOutboundHandler::handle(&self.tls, &Session::default(), Some(stream)).await?;
+            Ok::<_, anyhow::Error>(h2::client::handshake(stream).await?)
+        };
+        let (send_request, connection) = timeout(HANDSHAKE_TIMEOUT, handshake)
+            .await
+            .map_err(|_| anyhow!("doh handshake with {} timed out", addr))??;
+        debug!("doh connected to {} ({})", &self.host, addr);
+        spawn_connection(connection, "doh", self.host.clone());
+        Ok(send_request)
+    }
+
+    async fn send_request(&self, dns_client: &DnsClient) -> Result<SendRequest<Bytes>> {
+        self.connection.get(|| self.connect_any(dns_client)).await
+    }
+
+    async fn connect_any(&self, dns_client: &DnsClient) -> Result<SendRequest<Bytes>> {
+        let addrs = match (self.ip, &self.outbound) {
+            (Some(ip), _) => vec![SocksAddr::from((ip, self.port))],
+            (None, Some(_)) => vec![SocksAddr::Domain(self.host.clone(), self.port)],
+            (None, None) => dns_client
+                .bootstrap(&self.host)
+                .await?
+                .into_iter()
+                .map(|ip| SocksAddr::from((ip, self.port)))
+                .collect(),
+        };
+        let mut last_err = None;
+        for addr in addrs {
+            match self.connect(&addr, dns_client).await {
+                Ok(c) => return Ok(c),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| anyhow!("no addresses of {}", &self.host)))
+    }
+
+    /// Sends a query in wire format and returns the response. Addresses of
+    /// the server are looked up by `dns_client` on the static hosts and the
+    /// plain servers.
+    pub async fn exchange(&self, query: Vec<u8>, dns_client: &DnsClient) -> Result<Vec<u8>> {
+        let send_request = self.send_request(dns_client).await?;
+        let res = timeout(
+            Duration::from_secs(*option::DNS_TIMEOUT),
+            post(send_request, &self.uri, query),
+        )
+        .await
+        .map_err(|_| anyhow!("doh query to {} timed out", &self.uri))?;
+        if res.is_err() {
+            // E.g. the server has sent a GOAWAY.
+            self.connection.reset().await;
+        }
+        res
+    }
+}
+
+async fn post(mut send_request: SendRequest<Bytes>, uri: &Uri, query: Vec<u8>) -> Result<Vec<u8>> {
+    let req = Request::builder()
+        .method(Method::POST)
+        .uri(uri.clone())
+        .header("content-type", DNS_MESSAGE)
+        .header("accept", DNS_MESSAGE)
+        .header("content-length", query.len())
+        .body(())?;
+    let (response, mut send) = send_request.send_request(req, false)?;
+    send.send_data(Bytes::from(query), true)?;
+    let response = response.await?;
+    if response.status() != StatusCode::OK {
+        return Err(anyhow!("doh response status {}", response.status()));
+    }
+    let mut body = response.into_body();
+    let mut buf = Vec::new();
+    while let Some(data) = body.data().await {
+        let data = data?;
+        let _ = body.flow_control().release_capacity(data.len());
+        buf.extend_from_slice(&data);
+        if buf.len() > u16::MAX as usize {
+            return Err(anyhow!("doh response too large"));
+        }
+    }
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use ::http::Response;
+
+    use super::*;
+
+    #[test]
+    fn test_new_client() {
+        let client = Client::new("https://dns.google/dns-query").unwrap();
+        assert_eq!(client.domain(), Some("dns.google"));
+        assert_eq!(client.port, 443);
+        let client = Client::new("https://[2606:4700:4700::1111]:8443/dns-query").unwrap();
+        assert_eq!(client.domain(), None);
+        assert_eq!(client.ip, Some("2606:4700:4700::1111".parse().unwrap()));
+        assert_eq!(client.port, 8443);
+        assert!(Client::new("http://dns.google/dns-query").is_err());
+        assert!(Client::new("8.8.8.8").is_err());
+    }
+
+    #[test]
+    fn test_post() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let (client_io, server_io) = tokio::io::duplex(65536);
+            tokio::spawn(async move {
+                let mut conn = h2::server::handshake(server_io).await.unwrap();
+                while let Some(Ok((req, mut respond))) = conn.accept().await {
+                    assert_eq!(req.method(), Method::POST);
+                    assert_eq!(req.uri().path(), "/dns-query");
+                    assert_eq!(req.headers()["content-type"], DNS_MESSAGE);
+                    // The connection is polled by accept while the streams
+                    // are served.
+                    tokio::spawn(async move {
+                        let mut body = req.into_body();
+                        let mut query = Vec::new();
+                        while let Some(data) = body.data().await {
+                            query.extend_from_slice(&data.unwrap());
+                        }
+                        let resp = Response::builder()
+                            .header("content-type", DNS_MESSAGE)
+                            .body(())
+                            .unwrap();
+                        let mut send = respond.send_response(resp, false).unwrap();
+                        query.reverse();
+                        send.send_data(Bytes::from(query), true).unwrap();
+                    });
+                }
+            });
+
+            let (send_request, conn) = h2::client::handshake(client_io).await.unwrap();
+            tokio::spawn(conn);
+            let uri = "https://dns.example/dns-query".parse::<Uri>().unwrap();
+            // Streams of the same connection.
+            for _ in 0..2 {
+                let send_request = send_request.clone().ready().await.unwrap();
+                let resp = post(send_request, &uri, vec![1, 2, 3]).await.unwrap();
+                assert_eq!(resp, vec![3, 2, 1]);
+            }
+        });
+    }
+}
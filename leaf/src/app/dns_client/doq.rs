@@ -0,0 +1,241 @@
+//! DNS over QUIC (RFC 9250). Each query is sent in a bidirectional stream of
+//! a single QUIC connection to the server, a new one is made when it's
+//! closed. Reconnections resume the TLS session and send the query with
+//! 0-RTT.
+
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use log::*;
+use rustls::{OwnedTrustAnchor, RootCertStore};
+use tokio::sync::Mutex;
+use tokio::time::timeout;
+
+use crate::{config::external_rule::asset_path, option, proxy::new_udp_socket};
+
+use super::{parse_url, DnsClient};
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_PORT: u16 = 853;
+const IDLE_TIMEOUT: u32 = 60_000; // ms
+
+struct Connection {
+    // The endpoint is driven while there're connections or handles of it.
+    _endpoint: quinn::Endpoint,
+    connection: quinn::Connection,
+}
+
+pub struct Client {
+    url: String,
+    host: String,
+    port: u16,
+    // The address of a server given by IP, others are looked up on
+    // connecting.
+    ip: Option<IpAddr>,
+    server_name: String,
+    client_config: quinn::ClientConfig,
+    connection: Mutex<Option<Connection>>,
+}
+
+impl Client {
+    /// Creates a client of the server at `url`, e.g. `quic://dns.adguard-dns.com`.
+    /// Options are given in the query:
+    /// - `sni`, the server name, the host by default,
+    /// - `tls-cert`, a CA certificate to trust instead of the bundled roots.
+    pub fn new(url: &str) -> Result<Self> {
+        let (host, port, opts) = parse_url(url, "quic://", DEFAULT_PORT)?;
+        let mut server_name = host.clone();
+        let mut roots = RootCertStore::empty();
+        for (k, v) in opts {
+            match k {
+                "sni" => server_name = v.to_string(),
+                "tls-cert" => {
+                    let pem = fs::read(asset_path(v))?;
+                    for cert in rustls_pemfile::certs(&mut &*pem)? {
+                        roots
+                            .add(&rustls::Certificate(cert))
+                            .map_err(|e| anyhow!("invalid certificate {}: {}", v, e))?;
+                    }
+                }
+                _ => return Err(anyhow!("unknown option {} of {}", k, url)),
+            }
+        }
+        if roots.is_empty() {
+            roots.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|ta| {
+                OwnedTrustAnchor::from_subject_spki_name_constraints(
+                    ta.subject,
+                    ta.spki,
+                    ta.name_constraints,
+                )
+            }));
+        }
+        let mut client_crypto = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        client_crypto.alpn_protocols = vec![b"doq".to_vec()];
+        // Sessions are resumed with 0-RTT, the session cache is shared by all
+        // the connections made with this config.
+        client_crypto.enable_early_data = true;
+        let mut client_config = quinn::ClientConfig::new(Arc::new(client_crypto));
+        let mut transport_config = quinn::TransportConfig::default();
+        transport_config.max_idle_timeout(Some(quinn::IdleTimeout::from(quinn::VarInt::from_u32(
+            IDLE_TIMEOUT,
+        ))));
+        client_config.transport = Arc::new(transport_config);
+        Ok(Client {
+            url: url.to_string(),
+            ip: host.parse::<IpAddr>().ok(),
+            host,
+            port,
+            server_name,
+            client_config,
+            connection: Mutex::new(None),
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the domain of the server, if not given by IP.
+    pub fn domain(&self) -> Option<&str> {
+        if self.ip.is_some() {
+            None
+        } else {
+            Some(&self.host)
+        }
+    }
+
+    async fn connect_addr(&self, addr: SocketAddr) -> Result<Connection> {
+        let socket = new_udp_socket(&addr).await?;
+        let (mut endpoint, _) =
+            quinn::Endpoint::new(quinn::EndpointConfig::default(), None, socket.into_std()?)?;
+        endpoint.set_default_client_config(self.client_config.clone());
+        let connecting = endpoint.connect(addr, &self.server_name)?;
+        // Streams opened before the handshake completes are sent as 0-RTT
+        // data if the session is resumed, they're reset if the server rejects
+        // it and fail the queries in them.
+        let new_conn = match connecting.into_0rtt() {
+            Ok((new_conn, zero_rtt_accepted)) => {
+                let host = self.host.clone();
+                tokio::spawn(async move {
+                    if !zero_rtt_accepted.await {
+                        debug!("doq 0-rtt rejected by {}", host);
+                    }
+                });
+                new_conn
+            }
+            Err(connecting) => timeout(HANDSHAKE_TIMEOUT, connecting)
+                .await
+                .map_err(|_| anyhow!("doq handshake with {} timed out", addr))??,
+        };
+        debug!("doq connected to {} ({})", &self.host, addr);
+        Ok(Connection {
+            _endpoint: endpoint,
+            connection: new_conn.connection,
+        })
+    }
+
+    // Returns the connection to the server and whether it's a new one.
+    async fn connect(&self, dns_client: &DnsClient) -> Result<(quinn::Connection, bool)> {
+        let mut connection = self.connection.lock().await;
+        if let Some(c) = connection.as_ref() {
+            return Ok((c.connection.clone(), false));
+        }
+        let ips = match self.ip {
+            Some(ip) => vec![ip],
+            None => dns_client.bootstrap(&self.host).await?,
+        };
+        let mut last_err = None;
+        for ip in ips {
+            match self.connect_addr(SocketAddr::new(ip, self.port)).await {
+                Ok(c) => {
+                    let conn = c.connection.clone();
+                    *connection = Some(c);
+                    return Ok((conn, true));
+                }
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| anyhow!("no addresses of {}", &self.host)))
+    }
+
+    /// Sends a query in wire format and returns the response. Addresses of
+    /// the server are looked up by `dns_client` on the static hosts and the
+    /// plain servers.
+    pub async fn exchange(&self, query: Vec<u8>, dns_client: &DnsClient) -> Result<Vec<u8>> {
+        loop {
+            let (conn, fresh) = self.connect(dns_client).await?;
+            let res = timeout(
+                Duration::from_secs(*option::DNS_TIMEOUT),
+                send_query(&conn, query.clone()),
+            )
+            .await
+            .map_err(|_| anyhow!("doq query to {} timed out", &self.url))
+            .and_then(|res| res);
+            if let Err(e) = &res {
+                // E.g. the connection has timed out idle, the query is sent
+                // again in a new one.
+                trace!("doq query to {} failed: {}", &self.url, e);
+                let mut connection = self.connection.lock().await;
+                if let Some(c) = connection.as_ref() {
+                    if c.connection.stable_id() == conn.stable_id() {
+                        connection.take();
+                    }
+                }
+                if !fresh {
+                    continue;
+                }
+            }
+            return res;
+        }
+    }
+}
+
+// Sends a query in a new stream of the connection, the ID of the query is
+// 0 on the wire as RFC 9250 requires and restored in the response.
+async fn send_query(conn: &quinn::Connection, mut query: Vec<u8>) -> Result<Vec<u8>> {
+    if query.len() < 2 {
+        return Err(anyhow!("invalid query"));
+    }
+    let id = [query[0], query[1]];
+    query[0] = 0;
+    query[1] = 0;
+    let (mut send, mut recv) = conn.open_bi().await?;
+    let mut buf = (query.len() as u16).to_be_bytes().to_vec();
+    buf.extend_from_slice(&query);
+    send.write_all(&buf).await?;
+    send.finish().await?;
+    let mut len = [0u8; 2];
+    recv.read_exact(&mut len).await?;
+    let mut resp = vec![0u8; u16::from_be_bytes(len) as usize];
+    recv.read_exact(&mut resp).await?;
+    if resp.len() < 2 {
+        return Err(anyhow!("invalid response"));
+    }
+    resp[..2].copy_from_slice(&id);
+    Ok(resp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_client() {
+        let client = Client::new("quic://dns.adguard-dns.com").unwrap();
+        assert_eq!(client.domain(), Some("dns.adguard-dns.com"));
+        assert_eq!(client.server_name, "dns.adguard-dns.com");
+        assert_eq!(client.port, 853);
+        let client = Client::new("quic://94.140.14.14:784?sni=dns.adguard-dns.com").unwrap();
+        assert_eq!(client.domain(), None);
+        assert_eq!(client.server_name, "dns.adguard-dns.com");
+        assert_eq!(client.port, 784);
+        assert!(Client::new("quic://dns.adguard-dns.com?tls-cert-sha256=abc").is_err());
+        assert!(Client::new("tls://dns.adguard-dns.com").is_err());
+    }
+}
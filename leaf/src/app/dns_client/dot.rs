@@ -0,0 +1,197 @@
+//! DNS over TLS (RFC 7858). Queries are sent in the TLS connections to the
+//! server one at a time, connections are kept open and reused by later
+//! queries.
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use log::*;
+use tokio::sync::Mutex;
+use tokio::time::timeout;
+
+use crate::{
+    config::external_rule::asset_path,
+    option,
+    proxy::{tls, AnyStream, TcpOutboundHandler},
+    session::{Session, SocksAddr},
+};
+
+use super::{parse_url, tcp_exchange, DnsClient};
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_PORT: u16 = 853;
+// Connections kept open for later queries.
+const MAX_IDLE_CONNECTIONS: usize = 4;
+
+pub struct Client {
+    url: String,
+    host: String,
+    port: u16,
+    // The address of a server given by IP, others are looked up on
+    // connecting.
+    ip: Option<IpAddr>,
+    tls: tls::outbound::TcpHandler,
+    // The outbound connections are made through, if any.
+    outbound: Option<String>,
+    idle: Mutex<Vec<AnyStream>>,
+}
+
+impl Client {
+    /// Creates a client of the server at `url`, e.g. `tls://dns.google` or
+    /// `tls://8.8.8.8:853?sni=dns.google`. Options are given in the query:
+    /// - `sni`, the server name, the host by default,
+    /// - `tls-cert`, a CA certificate to trust instead of the bundled roots,
+    /// - `tls-cert-sha256`, the accepted hashes of the certificate chain,
+    ///   separated by `|`.
+    pub fn new(url: &str) -> Result<Self> {
+        let (host, port, opts) = parse_url(url, "tls://", DEFAULT_PORT)?;
+        let mut sni = host.clone();
+        let mut certificate = None;
+        let mut pins = Vec::new();
+        for (k, v) in opts {
+            match k {
+                "sni" => sni = v.to_string(),
+                "tls-cert" => certificate = Some(asset_path(v)),
+                "tls-cert-sha256" => {
+                    for pin in v.split('|') {
+                        match base64::decode(pin.trim()) {
+                            Ok(hash) if hash.len() == 32 => pins.push(hash),
+                            _ => return Err(anyhow!("invalid certificate hash {}", pin)),
+                        }
+                    }
+                }
+                _ => return Err(anyhow!("unknown option {} of {}", k, url)),
+            }
+        }
+        let tls = tls::outbound::TcpHandler::new(sni, Vec::new(), certificate, None)?
+            .with_pinned_certificates(pins);
+        Ok(Client {
+            url: url.to_string(),
+            ip: host.parse::<IpAddr>().ok(),
+            host,
+            port,
+            tls,
+            outbound: None,
+            idle: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Makes connections through the outbound of the tag, which resolves the
+    /// domain of the server.
+    pub fn with_outbound(mut self, outbound: Option<String>) -> Self {
+        self.outbound = outbound;
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the domain of the server to look up, if not given by IP nor
+    /// resolved by an outbound.
+    pub fn domain(&self) -> Option<&str> {
+        if self.ip.is_some() || self.outbound.is_some() {
+            None
+        } else {
+            Some(&self.host)
+        }
+    }
+
+    async fn connect(&self, dns_client: &DnsClient) -> Result<AnyStream> {
+        let addrs = match (self.ip, &self.outbound) {
+            (Some(ip), _) => vec![SocksAddr::from((ip, self.port))],
+            (None, Some(_)) => vec![SocksAddr::Domain(self.host.clone(), self.port)],
+            (None, None) => dns_client
+                .bootstrap(&self.host)
+                .await?
+                .into_iter()
+                .map(|ip| SocksAddr::from((ip, self.port)))
+                .collect(),
+        };
+        let mut last_err = None;
+        for addr in addrs {
+            let handshake = async {
+                let stream = dns_client.dial_tcp(&addr, self.outbound.as_deref()).await?;
+                // The server name of the handler is set, the session is unused.
+                let stream =
+                    TcpOutboundHandler::handle(&self.tls, &Session::default(), Some(stream))
+                        .await?;
+                Ok::<_, anyhow::Error>(stream)
+            };
+            match timeout(HANDSHAKE_TIMEOUT, handshake).await {
+                Ok(Ok(stream)) => {
+                    debug!("dot connected to {} ({})", &self.host, addr);
+                    return Ok(stream);
+                }
+                Ok(Err(e)) => last_err = Some(anyhow!("dot connect to {} failed: {}", addr, e)),
+                Err(_) => last_err = Some(anyhow!("dot handshake with {} timed out", addr)),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| anyhow!("no addresses of {}", &self.host)))
+    }
+
+    async fn query(&self, stream: &mut AnyStream, query: &[u8]) -> Result<Vec<u8>> {
+        timeout(
+            Duration::from_secs(*option::DNS_TIMEOUT),
+            tcp_exchange(stream, query),
+        )
+        .await
+        .map_err(|_| anyhow!("dot query to {} timed out", &self.url))?
+    }
+
+    /// Sends a query in wire format and returns the response. Addresses of
+    /// the server are looked up by `dns_client` on the static hosts and the
+    /// plain servers.
+    pub async fn exchange(&self, query: Vec<u8>, dns_client: &DnsClient) -> Result<Vec<u8>> {
+        let idle = self.idle.lock().await.pop();
+        let resp = match idle {
+            Some(mut stream) => match self.query(&mut stream, &query).await {
+                Ok(resp) => Some((resp, stream)),
+                Err(e) => {
+                    // Servers close idle connections after a while.
+                    trace!("idle dot connection to {} failed: {}", &self.host, e);
+                    None
+                }
+            },
+            None => None,
+        };
+        let (resp, stream) = match resp {
+            Some(v) => v,
+            None => {
+                let mut stream = self.connect(dns_client).await?;
+                let resp = self.query(&mut stream, &query).await?;
+                (resp, stream)
+            }
+        };
+        let mut idle = self.idle.lock().await;
+        if idle.len() < MAX_IDLE_CONNECTIONS {
+            idle.push(stream);
+        }
+        Ok(resp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_client() {
+        let client = Client::new("tls://dns.google").unwrap();
+        assert_eq!(client.domain(), Some("dns.google"));
+        assert_eq!(client.port, 853);
+        let client = Client::new("tls://1.1.1.1:8853?sni=one.one.one.one").unwrap();
+        assert_eq!(client.domain(), None);
+        assert_eq!(client.ip, Some("1.1.1.1".parse().unwrap()));
+        assert_eq!(client.port, 8853);
+        let client = Client::new("tls://[2001:4860:4860::8888]").unwrap();
+        assert_eq!(client.ip, Some("2001:4860:4860::8888".parse().unwrap()));
+        assert_eq!(client.port, 853);
+        let pin = base64::encode([1u8; 32]);
+        assert!(Client::new(&format!("tls://dns.google?tls-cert-sha256={}", pin)).is_ok());
+        assert!(Client::new("tls://dns.google?tls-cert-sha256=abc").is_err());
+        assert!(Client::new("tls://dns.google?foo=bar").is_err());
+        assert!(Client::new("https://dns.google").is_err());
+    }
+}
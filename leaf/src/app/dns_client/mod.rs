@@ -0,0 +1,1600 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use futures::future::select_ok;
+use log::*;
+use lru::LruCache;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::{Mutex as TokioMutex, RwLock};
+use tokio::time::timeout;
+use trust_dns_proto::{
+    op::{
+        header::MessageType, op_code::OpCode, query::Query, response_code::ResponseCode, Edns,
+        Message,
+    },
+    rr::{
+        rdata::opt::{EdnsCode, EdnsOption},
+        record_data::RData,
+        record_type::RecordType,
+        Name, Record,
+    },
+};
+
+#[cfg(feature = "outbound-ech")]
+use trust_dns_proto::rr::rdata::svcb::SvcParamValue;
+
+use crate::{
+    app::{outbound::manager::OutboundManager, router::DomainSet, SyncDnsClient},
+    option,
+    proxy::{
+        connect_tcp_outbound, dial_tcp_stream, AnyStream, DialOptions, TcpOutboundHandler,
+        UdpConnector,
+    },
+    session::{Network, Session, SocksAddr},
+};
+
+#[cfg(feature = "dns-over-https")]
+pub mod doh;
+#[cfg(feature = "dns-over-quic")]
+pub mod doq;
+#[cfg(feature = "dns-over-tls")]
+pub mod dot;
+
+// Splits a `scheme://host[:port][?k=v&...]` URL of an encrypted server into
+// the host, the port and the options.
+#[cfg(any(feature = "dns-over-tls", feature = "dns-over-quic"))]
+fn parse_url<'a>(
+    url: &'a str,
+    scheme: &str,
+    default_port: u16,
+) -> Result<(String, u16, Vec<(&'a str, &'a str)>)> {
+    let rest = url
+        .strip_prefix(scheme)
+        .ok_or_else(|| anyhow!("invalid url {}", url))?;
+    let (authority, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let authority = authority.trim_end_matches('/');
+    let (host, port) = if let Ok(addr) = authority.parse::<SocketAddr>() {
+        (addr.ip().to_string(), addr.port())
+    } else if let Ok(ip) = authority
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    {
+        (ip.to_string(), default_port)
+    } else if let Some((host, port)) = authority.rsplit_once(':') {
+        (host.to_string(), port.parse::<u16>()?)
+    } else {
+        (authority.to_string(), default_port)
+    };
+    if host.is_empty() {
+        return Err(anyhow!("missing host in url {}", url));
+    }
+    let mut opts = Vec::new();
+    for opt in query.split('&').filter(|x| !x.is_empty()) {
+        let kv = opt
+            .split_once('=')
+            .ok_or_else(|| anyhow!("invalid option {} of {}", opt, url))?;
+        opts.push(kv);
+    }
+    Ok((host, port, opts))
+}
+
+// Takes the `outbound` option off a server, e.g. `8.8.8.8?outbound=Proxy`,
+// the other options are kept.
+fn take_outbound(s: &str) -> (String, Option<String>) {
+    let (base, query) = match s.split_once('?') {
+        Some(v) => v,
+        None => return (s.to_string(), None),
+    };
+    let mut outbound = None;
+    let mut opts = Vec::new();
+    for opt in query.split('&') {
+        match opt.strip_prefix("outbound=") {
+            Some(tag) => outbound = Some(tag.to_string()),
+            None => opts.push(opt),
+        }
+    }
+    if opts.is_empty() {
+        (base.to_string(), outbound)
+    } else {
+        (format!("{}?{}", base, opts.join("&")), outbound)
+    }
+}
+
+// Sends a query prefixed with its length in a stream and reads the response.
+async fn tcp_exchange(stream: &mut AnyStream, query: &[u8]) -> Result<Vec<u8>> {
+    let mut buf = (query.len() as u16).to_be_bytes().to_vec();
+    buf.extend_from_slice(query);
+    stream.write_all(&buf).await?;
+    let mut len = [0u8; 2];
+    stream.read_exact(&mut len).await?;
+    let mut resp = vec![0u8; u16::from_be_bytes(len) as usize];
+    stream.read_exact(&mut resp).await?;
+    Ok(resp)
+}
+
+/// A server queries are sent to.
+#[derive(Clone)]
+pub enum Upstream {
+    /// A plain server given by `IP` or `IP:port`, port 53 by default.
+    Udp(SocketAddr),
+    /// A plain server queried over TCP through the outbound of the tag, one
+    /// connection per query.
+    Outbound(SocketAddr, String),
+    /// A DNS over HTTPS server given by an `https://` URL.
+    #[cfg(feature = "dns-over-https")]
+    Https(Arc<doh::Client>),
+    /// A DNS over TLS server given by a `tls://` URL.
+    #[cfg(feature = "dns-over-tls")]
+    Tls(Arc<dot::Client>),
+    /// A DNS over QUIC server given by a `quic://` URL.
+    #[cfg(feature = "dns-over-quic")]
+    Quic(Arc<doq::Client>),
+}
+
+impl Upstream {
+    /// Parses a server, queries are sent through the outbound of the tag in
+    /// the `outbound` option if given, e.g. `8.8.8.8?outbound=Proxy` or
+    /// `https://dns.google/dns-query?outbound=Proxy`.
+    pub fn parse(s: &str) -> Result<Self> {
+        let (s, outbound) = take_outbound(s);
+        let s = s.as_str();
+        #[cfg(feature = "dns-over-https")]
+        if s.starts_with("https://") {
+            let client = doh::Client::new(s)?.with_outbound(outbound);
+            return Ok(Upstream::Https(Arc::new(client)));
+        }
+        #[cfg(feature = "dns-over-tls")]
+        if s.starts_with("tls://") {
+            let client = dot::Client::new(s)?.with_outbound(outbound);
+            return Ok(Upstream::Tls(Arc::new(client)));
+        }
+        #[cfg(feature = "dns-over-quic")]
+        if s.starts_with("quic://") {
+            if outbound.is_some() {
+                return Err(anyhow!("dns over quic through outbounds is not supported"));
+            }
+            return Ok(Upstream::Quic(Arc::new(doq::Client::new(s)?)));
+        }
+        let addr = match s.parse::<SocketAddr>() {
+            Ok(addr) => addr,
+            Err(_) => SocketAddr::new(s.parse::<IpAddr>()?, 53),
+        };
+        match outbound {
+            Some(tag) => Ok(Upstream::Outbound(addr, tag)),
+            None => Ok(Upstream::Udp(addr)),
+        }
+    }
+
+    /// Returns the address of a plain server queried directly.
+    pub fn udp_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Upstream::Udp(addr) => Some(*addr),
+            Upstream::Outbound(..) => None,
+            #[cfg(feature = "dns-over-https")]
+            Upstream::Https(..) => None,
+            #[cfg(feature = "dns-over-tls")]
+            Upstream::Tls(..) => None,
+            #[cfg(feature = "dns-over-quic")]
+            Upstream::Quic(..) => None,
+        }
+    }
+
+    // Returns the domain of an encrypted server, which is looked up on
+    // connecting.
+    fn domain(&self) -> Option<&str> {
+        match self {
+            Upstream::Udp(..) | Upstream::Outbound(..) => None,
+            #[cfg(feature = "dns-over-https")]
+            Upstream::Https(client) => client.domain(),
+            #[cfg(feature = "dns-over-tls")]
+            Upstream::Tls(client) => client.domain(),
+            #[cfg(feature = "dns-over-quic")]
+            Upstream::Quic(client) => client.domain(),
+        }
+    }
+}
+
+impl fmt::Display for Upstream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Upstream::Udp(addr) => write!(f, "{}", addr),
+            Upstream::Outbound(addr, tag) => write!(f, "{} via {}", addr, tag),
+            #[cfg(feature = "dns-over-https")]
+            Upstream::Https(client) => write!(f, "{}", client.uri()),
+            #[cfg(feature = "dns-over-tls")]
+            Upstream::Tls(client) => write!(f, "{}", client.url()),
+            #[cfg(feature = "dns-over-quic")]
+            Upstream::Quic(client) => write!(f, "{}", client.url()),
+        }
+    }
+}
+
+/// The subnet of the client sent in queries as the EDNS Client Subnet option
+/// (RFC 7871), e.g. `203.0.113.0/24`, so servers answer with the addresses
+/// close to the client rather than to the server or the proxy. The prefix is
+/// 24 for IPv4 and 56 for IPv6 by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClientSubnet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl FromStr for ClientSubnet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>()?, Some(prefix.parse::<u8>()?)),
+            None => (s.parse::<IpAddr>()?, None),
+        };
+        let (default_prefix, max_prefix) = if addr.is_ipv4() { (24, 32) } else { (56, 128) };
+        let prefix = prefix.unwrap_or(default_prefix);
+        if prefix > max_prefix {
+            return Err(anyhow!("invalid client subnet {}", s));
+        }
+        Ok(ClientSubnet { addr, prefix })
+    }
+}
+
+impl ClientSubnet {
+    // Returns the data of the option, the address is truncated to the prefix.
+    fn encode(&self) -> Vec<u8> {
+        let (family, octets) = match self.addr {
+            IpAddr::V4(ip) => (1u16, ip.octets().to_vec()),
+            IpAddr::V6(ip) => (2u16, ip.octets().to_vec()),
+        };
+        let mut data = family.to_be_bytes().to_vec();
+        data.push(self.prefix);
+        // The scope prefix length, 0 in queries.
+        data.push(0);
+        data.extend_from_slice(&octets[..(self.prefix as usize + 7) / 8]);
+        if self.prefix % 8 != 0 {
+            if let Some(last) = data.last_mut() {
+                *last &= 0xffu8 << (8 - self.prefix % 8);
+            }
+        }
+        data
+    }
+
+    // Adds the option to a query unless it has one.
+    fn apply(&self, msg: &mut Message) {
+        let mut edns = match msg.edns() {
+            Some(edns) => edns.clone(),
+            None => {
+                let mut edns = Edns::new();
+                // Responses to leaf's own queries are read in 512-byte
+                // buffers.
+                edns.set_max_payload(512);
+                edns
+            }
+        };
+        if edns.option(EdnsCode::Subnet).is_some() {
+            return;
+        }
+        edns.options_mut()
+            .insert(EdnsOption::Unknown(EdnsCode::Subnet.into(), self.encode()));
+        msg.set_edns(edns);
+    }
+}
+
+/// Which address families domains resolve to, and the order they're tried in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DomainStrategy {
+    /// Follows `ENABLE_IPV6` and `PREFER_IPV6`.
+    #[default]
+    AsIs,
+    PreferIpv4,
+    PreferIpv6,
+    Ipv4Only,
+    Ipv6Only,
+}
+
+impl DomainStrategy {
+    fn record_types(&self) -> &'static [RecordType] {
+        match self {
+            DomainStrategy::AsIs => match (*option::ENABLE_IPV6, *option::PREFER_IPV6) {
+                (true, true) => &[RecordType::AAAA, RecordType::A],
+                (true, false) => &[RecordType::A, RecordType::AAAA],
+                _ => &[RecordType::A],
+            },
+            DomainStrategy::PreferIpv4 => &[RecordType::A, RecordType::AAAA],
+            DomainStrategy::PreferIpv6 => &[RecordType::AAAA, RecordType::A],
+            DomainStrategy::Ipv4Only => &[RecordType::A],
+            DomainStrategy::Ipv6Only => &[RecordType::AAAA],
+        }
+    }
+
+    /// Whether domains may resolve to IPv6 addresses.
+    pub fn allows_ipv6(&self) -> bool {
+        self.record_types().contains(&RecordType::AAAA)
+    }
+
+    // Filters and orders static IPs the way the strategy resolves domains.
+    fn sort(&self, ips: &[IpAddr]) -> Vec<IpAddr> {
+        if *self == DomainStrategy::AsIs {
+            return ips.to_vec();
+        }
+        let mut sorted = Vec::new();
+        for ty in self.record_types() {
+            let v6 = *ty == RecordType::AAAA;
+            sorted.extend(ips.iter().filter(|ip| ip.is_ipv6() == v6));
+        }
+        sorted
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CacheEntry {
+    // Empty for a negative response, i.e. the name or the records of the
+    // type don't exist.
+    pub ips: Vec<IpAddr>,
+    // The deadline this entry should be considered expired.
+    pub deadline: Instant,
+}
+
+/// Statistics of the cache of looked up addresses, the lookups are counted
+/// per record type.
+#[derive(Clone, Debug, Default)]
+pub struct CacheStats {
+    /// Entries in the cache.
+    pub size: usize,
+    /// Maximum entries in the cache, i.e. `DNS_CACHE_SIZE`.
+    pub capacity: usize,
+    /// Lookups answered by cached addresses.
+    pub hits: u64,
+    /// Lookups answered by cached negative responses.
+    pub negative_hits: u64,
+    /// Lookups sent to the servers.
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// The ratio of the lookups answered by the cache.
+    pub fn hit_rate(&self) -> f64 {
+        let hits = self.hits + self.negative_hits;
+        if hits + self.misses == 0 {
+            return 0.0;
+        }
+        hits as f64 / (hits + self.misses) as f64
+    }
+}
+
+// Returns the deadline of an entry cached for `ttl` seconds.
+fn cache_deadline(ttl: u32) -> Result<Instant> {
+    Instant::now()
+        .checked_add(Duration::from_secs(ttl.into()))
+        .ok_or_else(|| anyhow!("invalid ttl"))
+}
+
+#[cfg(feature = "outbound-ech")]
+#[derive(Clone, Debug)]
+struct EchCacheEntry {
+    pub config_list: Vec<u8>,
+    pub deadline: Instant,
+}
+
+// The TTL of the answers of the static hosts to the DNS inbound.
+const HOSTS_TTL: u32 = 60;
+
+/// Static addresses of domains, consulted before the servers. A `*.` prefix
+/// matches the subdomains, e.g. `*.example.com` matches `www.example.com`
+/// unless it has an entry of its own, and `block` in place of the addresses
+/// blocks the domain.
+#[derive(Default)]
+struct Hosts(HashMap<String, Vec<IpAddr>>);
+
+impl Hosts {
+    fn load(dns: &crate::config::Dns) -> Self {
+        let mut hosts = HashMap::new();
+        for (name, static_ips) in dns.hosts.iter() {
+            let name = name.trim_end_matches('.').to_lowercase();
+            if static_ips.values.iter().any(|x| x == "block") {
+                hosts.insert(name, Vec::new());
+                continue;
+            }
+            let mut ips = Vec::new();
+            for ip in static_ips.values.iter() {
+                if let Ok(parsed_ip) = ip.parse::<IpAddr>() {
+                    ips.push(parsed_ip);
+                }
+            }
+            if !ips.is_empty() {
+                hosts.insert(name, ips);
+            }
+        }
+        Hosts(hosts)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // Returns the addresses of the domain, which are empty if it's blocked.
+    fn get(&self, host: &str) -> Option<&Vec<IpAddr>> {
+        if let Some(ips) = self.0.get(host) {
+            return Some(ips);
+        }
+        let mut domain = host;
+        while let Some(i) = domain.find('.') {
+            domain = &domain[i + 1..];
+            if let Some(ips) = self.0.get(&format!("*.{}", domain)) {
+                return Some(ips);
+            }
+        }
+        None
+    }
+}
+
+// Domains matching the conditions are looked up on the servers of the rule.
+struct Rule {
+    domains: DomainSet,
+    servers: Vec<Upstream>,
+}
+
+pub struct DnsClient {
+    servers: Vec<Upstream>,
+    // The first matching rule chooses the servers, `servers` are for the
+    // domains matching no rules.
+    rules: Vec<Rule>,
+    hosts: Hosts,
+    // Keyed by the name and the record type.
+    cache: Arc<TokioMutex<LruCache<(String, RecordType), CacheEntry>>>,
+    cache_hits: AtomicU64,
+    cache_negative_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    #[cfg(feature = "outbound-ech")]
+    ech_cache: Arc<TokioMutex<LruCache<String, EchCacheEntry>>>,
+    // Outbounds queries are sent through, set once the outbounds are loaded.
+    outbound_manager: Option<Weak<RwLock<OutboundManager>>>,
+    // Resolves the proxy servers of the outbounds queries are sent through,
+    // on the servers not given an outbound.
+    direct: Option<SyncDnsClient>,
+    client_subnet: Option<ClientSubnet>,
+}
+
+impl DnsClient {
+    fn load_servers(dns: &crate::config::Dns, hosts: &Hosts) -> Result<(Vec<Upstream>, Vec<Rule>)> {
+        let mut servers = Vec::new();
+        for server in dns.servers.iter() {
+            servers.push(Upstream::parse(server)?);
+        }
+        if servers.is_empty() {
+            return Err(anyhow!("no dns servers"));
+        }
+        let mut rules = Vec::new();
+        for rule in dns.rules.iter() {
+            let mut rule_servers = Vec::new();
+            for server in rule.servers.iter() {
+                rule_servers.push(Upstream::parse(server)?);
+            }
+            if rule_servers.is_empty() {
+                return Err(anyhow!("no servers in dns rule"));
+            }
+            rules.push(Rule {
+                domains: DomainSet::new(&mut rule.domains.clone()),
+                servers: rule_servers,
+            });
+        }
+        // Domains of encrypted servers are looked up on the static hosts or
+        // the plain servers.
+        let has_plain = servers.iter().any(|s| s.udp_addr().is_some());
+        let rule_servers = rules.iter().flat_map(|r| r.servers.iter());
+        for server in servers.iter().chain(rule_servers) {
+            if let Some(domain) = server.domain() {
+                if !has_plain && hosts.get(domain).map_or(true, |ips| ips.is_empty()) {
+                    return Err(anyhow!("no bootstrap addresses of {}", domain));
+                }
+            }
+        }
+        Ok((servers, rules))
+    }
+
+    fn rule_of(&self, host: &str) -> Option<&Rule> {
+        self.rules.iter().find(|r| r.domains.matches(host))
+    }
+
+    // Returns the servers `host` is looked up on.
+    fn servers_of(&self, host: &str) -> &[Upstream] {
+        match self.rule_of(host) {
+            Some(rule) => &rule.servers,
+            None => &self.servers,
+        }
+    }
+
+    /// Returns the first server of the DNS rule `host` matches, if any.
+    pub fn rule_server(&self, host: &str) -> Option<Upstream> {
+        self.rule_of(host).map(|r| r.servers[0].clone())
+    }
+
+    /// Answers an A or AAAA query of a domain in the static hosts, and any
+    /// query of a blocked domain with NXDOMAIN.
+    pub fn answer_static(&self, query: &[u8]) -> Option<Vec<u8>> {
+        if self.hosts.is_empty() {
+            return None;
+        }
+        let req = Message::from_vec(query).ok()?;
+        let q = req.queries().first()?;
+        let name = q.name().to_ascii().to_lowercase();
+        let ips = self.hosts.get(name.trim_end_matches('.'))?;
+        let mut resp = Message::new();
+        resp.set_id(req.id());
+        resp.set_message_type(MessageType::Response);
+        resp.set_op_code(req.op_code());
+        resp.set_recursion_desired(req.recursion_desired());
+        resp.set_recursion_available(true);
+        resp.add_query(q.clone());
+        if ips.is_empty() {
+            resp.set_response_code(ResponseCode::NXDomain);
+        }
+        for ip in ips {
+            let rdata = match (q.query_type(), ip) {
+                (RecordType::A, IpAddr::V4(ip)) => RData::A(*ip),
+                (RecordType::AAAA, IpAddr::V6(ip)) => RData::AAAA(*ip),
+                (RecordType::A, _) | (RecordType::AAAA, _) => continue,
+                // Other records of the domain are looked up on the servers.
+                _ => return None,
+            };
+            resp.add_answer(Record::from_rdata(q.name().clone(), HOSTS_TTL, rdata));
+        }
+        resp.to_vec().ok()
+    }
+
+    pub fn new(dns: &protobuf::SingularPtrField<crate::config::Dns>) -> Result<Self> {
+        let dns = if let Some(dns) = dns.as_ref() {
+            dns
+        } else {
+            return Err(anyhow!("empty dns config"));
+        };
+        let hosts = Hosts::load(dns);
+        let (servers, rules) = Self::load_servers(dns, &hosts)?;
+        let cache = Arc::new(TokioMutex::new(LruCache::new(*option::DNS_CACHE_SIZE)));
+
+        Ok(DnsClient {
+            servers,
+            rules,
+            hosts,
+            cache,
+            cache_hits: AtomicU64::new(0),
+            cache_negative_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+            #[cfg(feature = "outbound-ech")]
+            ech_cache: Arc::new(TokioMutex::new(LruCache::new(*option::DNS_CACHE_SIZE))),
+            outbound_manager: None,
+            direct: Self::new_direct(dns)?,
+            client_subnet: Self::load_client_subnet(dns)?,
+        })
+    }
+
+    fn load_client_subnet(dns: &crate::config::Dns) -> Result<Option<ClientSubnet>> {
+        if dns.client_subnet.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(dns.client_subnet.parse()?))
+    }
+
+    /// Adds the EDNS Client Subnet option to a query in wire format if a
+    /// subnet is configured and the query has no such option.
+    pub fn with_client_subnet(&self, query: Vec<u8>) -> Vec<u8> {
+        let client_subnet = match self.client_subnet {
+            Some(v) => v,
+            None => return query,
+        };
+        let mut msg = match Message::from_vec(&query) {
+            Ok(msg) => msg,
+            Err(_) => return query,
+        };
+        client_subnet.apply(&mut msg);
+        msg.to_vec().unwrap_or(query)
+    }
+
+    // Returns a query of the name and the type with the client subnet.
+    fn new_lookup(&self, name: Name, ty: RecordType) -> Result<Vec<u8>> {
+        let mut msg = Self::new_query(name, ty);
+        if let Some(client_subnet) = self.client_subnet {
+            client_subnet.apply(&mut msg);
+        }
+        msg.to_vec()
+            .map_err(|e| anyhow!("encode message to buffer failed: {}", e))
+    }
+
+    // Returns a client of the servers in the config without outbounds if any
+    // server has one.
+    fn new_direct(dns: &crate::config::Dns) -> Result<Option<SyncDnsClient>> {
+        let rule_servers = dns.rules.iter().flat_map(|r| r.servers.iter());
+        if !dns
+            .servers
+            .iter()
+            .chain(rule_servers)
+            .any(|s| take_outbound(s).1.is_some())
+        {
+            return Ok(None);
+        }
+        let mut dns = dns.clone();
+        for server in dns.servers.iter_mut() {
+            *server = take_outbound(server).0;
+        }
+        dns.rules.clear();
+        let dns = protobuf::SingularPtrField::some(dns);
+        Ok(Some(Arc::new(RwLock::new(Self::new(&dns)?))))
+    }
+
+    /// Sets the outbounds queries are sent through.
+    pub fn set_outbound_manager(&mut self, outbound_manager: Weak<RwLock<OutboundManager>>) {
+        self.outbound_manager = Some(outbound_manager);
+    }
+
+    /// Applies a new config, cached addresses are dropped as they may come
+    /// from hosts, rules or servers no longer in use.
+    pub fn reload(&mut self, dns: &protobuf::SingularPtrField<crate::config::Dns>) -> Result<()> {
+        let dns = if let Some(dns) = dns.as_ref() {
+            dns
+        } else {
+            return Err(anyhow!("empty dns config"));
+        };
+        let hosts = Hosts::load(dns);
+        let (servers, rules) = Self::load_servers(dns, &hosts)?;
+        self.client_subnet = Self::load_client_subnet(dns)?;
+        self.direct = Self::new_direct(dns)?;
+        self.servers = servers;
+        self.rules = rules;
+        self.hosts = hosts;
+        self.cache = Arc::new(TokioMutex::new(LruCache::new(*option::DNS_CACHE_SIZE)));
+        Ok(())
+    }
+
+    /// Updates the cache according to the IP address successfully connected.
+    pub async fn optimize_cache(&self, address: String, connected_ip: IpAddr) {
+        // Nothing to do if the target address is an IP address.
+        if address.parse::<IpAddr>().is_ok() {
+            return;
+        }
+
+        let ty = match connected_ip {
+            IpAddr::V4(..) => RecordType::A,
+            IpAddr::V6(..) => RecordType::AAAA,
+        };
+        let key = (address, ty);
+
+        // If the connected IP is not in the first place, we should optimize it.
+        let mut new_entry = if let Some(entry) = self.cache.lock().await.get(&key) {
+            if !entry.ips.starts_with(&[connected_ip]) && entry.ips.contains(&connected_ip) {
+                entry.clone()
+            } else {
+                return;
+            }
+        } else {
+            return;
+        };
+
+        // Move failed IPs to the end, the optimized vector starts with the connected IP.
+        if let Ok(idx) = new_entry.ips.binary_search(&connected_ip) {
+            trace!("updates DNS cache item from\n{:#?}", &new_entry);
+            new_entry.ips.rotate_left(idx);
+            trace!("to\n{:#?}", &new_entry);
+            self.cache.lock().await.put(key, new_entry);
+            trace!("updated cache");
+        }
+    }
+
+    /// Returns the statistics of the cache.
+    pub async fn cache_stats(&self) -> CacheStats {
+        let cache = self.cache.lock().await;
+        CacheStats {
+            size: cache.len(),
+            capacity: cache.cap(),
+            hits: self.cache_hits.load(Ordering::Relaxed),
+            negative_hits: self.cache_negative_hits.load(Ordering::Relaxed),
+            misses: self.cache_misses.load(Ordering::Relaxed),
+        }
+    }
+
+    // Returns the entry of the addresses in a response.
+    fn parse_response(
+        buf: &[u8],
+        host: &str,
+        server: &Upstream,
+        start: tokio::time::Instant,
+    ) -> Result<CacheEntry> {
+        let resp = match Message::from_vec(buf) {
+            Ok(resp) => resp,
+            Err(err) => return Err(anyhow!("parse message failed: {:?}", err)),
+        };
+        if resp.response_code() != ResponseCode::NoError
+            && resp.response_code() != ResponseCode::NXDomain
+        {
+            return Err(anyhow!("response error {}", resp.response_code()));
+        }
+        let mut ips = Vec::new();
+        for ans in resp.answers() {
+            // TODO checks?
+            match ans.rdata() {
+                RData::A(ip) => {
+                    ips.push(IpAddr::V4(ip.to_owned()));
+                }
+                RData::AAAA(ip) => {
+                    ips.push(IpAddr::V6(ip.to_owned()));
+                }
+                _ => (),
+            }
+        }
+        let elapsed = tokio::time::Instant::now().duration_since(start);
+        if resp.response_code() == ResponseCode::NXDomain || ips.is_empty() {
+            // A negative response is cached for the TTL of the SOA record in
+            // the authority section, or the minimum field of the SOA if it's
+            // less (RFC 2308). It's unknown how long it lasts without one.
+            let ttl = resp
+                .name_servers()
+                .iter()
+                .find_map(|r| match r.rdata() {
+                    RData::SOA(soa) => Some(r.ttl().min(soa.minimum())),
+                    _ => None,
+                })
+                .ok_or_else(|| anyhow!("no records"))?;
+            let ttl = ttl
+                .max(*option::DNS_MIN_TTL)
+                .min(*option::DNS_MAX_NEGATIVE_TTL);
+            debug!(
+                "return no ips (ttl {}, {}) for {} from {} in {}ms",
+                ttl,
+                resp.response_code(),
+                host,
+                server,
+                elapsed.as_millis(),
+            );
+            return Ok(CacheEntry {
+                ips,
+                deadline: cache_deadline(ttl)?,
+            });
+        }
+        // The records of a CNAME chain expire with the shortest-lived one.
+        let ttl = resp
+            .answers()
+            .iter()
+            .map(|r| r.ttl())
+            .min()
+            .unwrap_or_default()
+            .max(*option::DNS_MIN_TTL)
+            .min(*option::DNS_MAX_TTL);
+        debug!(
+            "return {} ips (ttl {}) for {} from {} in {}ms",
+            ips.len(),
+            ttl,
+            host,
+            server,
+            elapsed.as_millis(),
+        );
+        let entry = CacheEntry {
+            ips,
+            deadline: cache_deadline(ttl)?,
+        };
+        trace!("ips for {}:\n{:#?}", host, &entry);
+        Ok(entry)
+    }
+
+    async fn query_task(
+        &self,
+        request: Vec<u8>,
+        host: &str,
+        server: &Upstream,
+    ) -> Result<CacheEntry> {
+        if let Some(addr) = server.udp_addr() {
+            return self.udp_query_task(request, host, &addr).await;
+        }
+        debug!("looking up host {} on {}", host, server);
+        let start = tokio::time::Instant::now();
+        let resp = self.send_query(request, server).await?;
+        Self::parse_response(&resp, host, server, start)
+    }
+
+    async fn udp_query_task(
+        &self,
+        request: Vec<u8>,
+        host: &str,
+        server: &SocketAddr,
+    ) -> Result<CacheEntry> {
+        let socket = self.new_udp_socket(server).await?;
+        let mut last_err = None;
+        for _i in 0..*option::MAX_DNS_RETRIES {
+            debug!("looking up host {} on {}", host, server);
+            let start = tokio::time::Instant::now();
+            match socket.send_to(&request, server).await {
+                Ok(_) => {
+                    let mut buf = vec![0u8; 512];
+                    match timeout(
+                        Duration::from_secs(*option::DNS_TIMEOUT),
+                        socket.recv_from(&mut buf),
+                    )
+                    .await
+                    {
+                        Ok(res) => match res {
+                            Ok((n, _)) => {
+                                let upstream = Upstream::Udp(*server);
+                                match Self::parse_response(&buf[..n], host, &upstream, start) {
+                                    Ok(entry) => return Ok(entry),
+                                    Err(e) => {
+                                        last_err = Some(e);
+                                        // broken or error response, no retry
+                                        break;
+                                    }
+                                }
+                            }
+                            Err(err) => {
+                                last_err = Some(anyhow!("recv failed: {:?}", err));
+                                // socket recv_from error, retry
+                            }
+                        },
+                        Err(e) => {
+                            last_err = Some(anyhow!("recv timeout: {}", e));
+                            // timeout, retry
+                        }
+                    }
+                }
+                Err(err) => {
+                    last_err = Some(anyhow!("send failed: {:?}", err));
+                    // socket send_to error, retry
+                }
+            }
+        }
+        Err(last_err.unwrap_or_else(|| anyhow!("all lookup attempts failed")))
+    }
+
+    fn new_query(name: Name, ty: RecordType) -> Message {
+        let mut msg = Message::new();
+        msg.add_query(Query::query(name, ty));
+        let mut rng = StdRng::from_entropy();
+        let id: u16 = rng.gen();
+        msg.set_id(id);
+        msg.set_op_code(OpCode::Query);
+        msg.set_message_type(MessageType::Query);
+        msg.set_recursion_desired(true);
+        msg
+    }
+
+    async fn cache_insert(&self, host: &str, ty: RecordType, entry: CacheEntry) {
+        self.cache.lock().await.put((host.to_owned(), ty), entry);
+    }
+
+    // Returns the cached addresses of the type, expired entries are removed.
+    async fn get_cached(&self, host: &str, ty: RecordType) -> Option<Vec<IpAddr>> {
+        let key = (host.to_owned(), ty);
+        let mut cache = self.cache.lock().await;
+        let entry = cache.get(&key)?;
+        if entry
+            .deadline
+            .checked_duration_since(Instant::now())
+            .is_none()
+        {
+            cache.pop(&key);
+            return None;
+        }
+        if entry.ips.is_empty() {
+            self.cache_negative_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.cache_hits.fetch_add(1, Ordering::Relaxed);
+        }
+        Some(entry.ips.clone())
+    }
+
+    // Looks up the addresses of the domain of an encrypted server on the
+    // static hosts and the plain servers.
+    #[cfg(any(
+        feature = "dns-over-https",
+        feature = "dns-over-tls",
+        feature = "dns-over-quic"
+    ))]
+    async fn bootstrap(&self, host: &str) -> Result<Vec<IpAddr>> {
+        if let Some(ips) = self.hosts.get(host) {
+            if !ips.is_empty() {
+                return Ok(ips.clone());
+            }
+        }
+        let name = Name::from_str(&format!("{}.", host))
+            .map_err(|e| anyhow!("invalid domain name [{}]: {}", host, e))?;
+        let mut ips = Vec::new();
+        let mut last_err = None;
+        for ty in DomainStrategy::AsIs.record_types() {
+            let msg_buf = Self::new_query(name.clone(), *ty)
+                .to_vec()
+                .map_err(|e| anyhow!("encode message to buffer failed: {}", e))?;
+            let mut tasks = Vec::new();
+            for server in &self.servers {
+                if let Upstream::Udp(addr) = server {
+                    tasks.push(Box::pin(self.udp_query_task(msg_buf.clone(), host, addr)));
+                }
+            }
+            if tasks.is_empty() {
+                return Err(anyhow!("no bootstrap servers for {}", host));
+            }
+            match select_ok(tasks.into_iter()).await {
+                Ok((mut entry, _)) => ips.append(&mut entry.ips),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        if !ips.is_empty() {
+            return Ok(ips);
+        }
+        Err(last_err.unwrap_or_else(|| anyhow!("could not resolve {}", host)))
+    }
+
+    pub async fn lookup(&self, host: &String) -> Result<Vec<IpAddr>> {
+        self.lookup_with_strategy(host, DomainStrategy::AsIs).await
+    }
+
+    /// Looks up the addresses of `host` of the families `strategy` allows, in
+    /// the order it prefers.
+    pub async fn lookup_with_strategy(
+        &self,
+        host: &String,
+        strategy: DomainStrategy,
+    ) -> Result<Vec<IpAddr>> {
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(vec![ip]);
+        }
+
+        if self.hosts.get(host).map_or(false, |ips| ips.is_empty()) {
+            return Err(anyhow!("{} is blocked by the static hosts", host));
+        }
+
+        // The answers of each record type in the order of the strategy, the
+        // types not in the cache are queried.
+        let mut answers = Vec::new();
+        for ty in strategy.record_types() {
+            answers.push((*ty, self.get_cached(host, *ty).await));
+        }
+        if answers.iter().all(|(_, ips)| ips.is_some()) {
+            let ips: Vec<IpAddr> = answers
+                .into_iter()
+                .flat_map(|(_, ips)| ips.unwrap_or_default())
+                .collect();
+            if ips.is_empty() {
+                return Err(anyhow!("no records"));
+            }
+            return Ok(ips);
+        }
+
+        // Making cache lookup a priority rather than static hosts lookup
+        // and insert the static IPs to the cache because there's a chance
+        // for the IPs in the cache to be re-ordered.
+        if !self.hosts.is_empty() {
+            if let Some(ips) = self.hosts.get(host) {
+                let ips = strategy.sort(ips);
+                if !ips.is_empty() {
+                    if ips.len() > 1 {
+                        let deadline = Instant::now()
+                            .checked_add(Duration::from_secs(6000))
+                            .unwrap();
+                        for ty in [RecordType::A, RecordType::AAAA] {
+                            let family: Vec<IpAddr> = ips
+                                .iter()
+                                .filter(|ip| ip.is_ipv6() == (ty == RecordType::AAAA))
+                                .cloned()
+                                .collect();
+                            if !family.is_empty() {
+                                self.cache_insert(
+                                    host,
+                                    ty,
+                                    CacheEntry {
+                                        ips: family,
+                                        deadline,
+                                    },
+                                )
+                                .await;
+                            }
+                        }
+                    }
+                    return Ok(ips.to_vec());
+                }
+            }
+        }
+
+        let mut fqdn = host.to_owned();
+        fqdn.push('.');
+        let name = match Name::from_str(&fqdn) {
+            Ok(n) => n,
+            Err(e) => return Err(anyhow!("invalid domain name [{}]: {}", host, e)),
+        };
+
+        let mut query_tasks = Vec::new();
+
+        let missing: Vec<RecordType> = answers
+            .iter()
+            .filter(|(_, ips)| ips.is_none())
+            .map(|(ty, _)| *ty)
+            .collect();
+        for ty in missing {
+            self.cache_misses.fetch_add(1, Ordering::Relaxed);
+            let msg_buf = self.new_lookup(name.clone(), ty)?;
+            let mut tasks = Vec::new();
+            for server in self.servers_of(host) {
+                let t = self.query_task(msg_buf.clone(), host, server);
+                tasks.push(Box::pin(t));
+            }
+            let query_task = async move { (ty, select_ok(tasks.into_iter()).await) };
+            query_tasks.push(query_task);
+        }
+
+        let mut last_err = None;
+
+        for (ty, v) in futures::future::join_all(query_tasks).await {
+            match v {
+                Ok((entry, _)) => {
+                    self.cache_insert(host, ty, entry.clone()).await;
+                    if let Some(answer) = answers.iter_mut().find(|(t, _)| *t == ty) {
+                        answer.1 = Some(entry.ips);
+                    }
+                }
+                Err(e) => last_err = Some(anyhow!("all dns servers failed, last error: {}", e)),
+            }
+        }
+
+        let ips: Vec<IpAddr> = answers
+            .into_iter()
+            .flat_map(|(_, ips)| ips.unwrap_or_default())
+            .collect();
+        if !ips.is_empty() {
+            return Ok(ips);
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow!("could not resolve to any address")))
+    }
+
+    /// Sends a query in wire format to `server` and returns the response.
+    pub async fn send_query(&self, request: Vec<u8>, server: &Upstream) -> Result<Vec<u8>> {
+        match server {
+            Upstream::Udp(addr) => self.udp_exchange(request, addr).await,
+            Upstream::Outbound(addr, tag) => {
+                let mut stream = self.dial_tcp(&SocksAddr::Ip(*addr), Some(tag)).await?;
+                timeout(
+                    Duration::from_secs(*option::DNS_TIMEOUT),
+                    tcp_exchange(&mut stream, &request),
+                )
+                .await
+                .map_err(|_| anyhow!("dns query to {} timed out", server))?
+            }
+            #[cfg(feature = "dns-over-https")]
+            Upstream::Https(client) => client.exchange(request, self).await,
+            #[cfg(feature = "dns-over-tls")]
+            Upstream::Tls(client) => client.exchange(request, self).await,
+            #[cfg(feature = "dns-over-quic")]
+            Upstream::Quic(client) => client.exchange(request, self).await,
+        }
+    }
+
+    /// Dials a TCP stream to a server through the outbound of the tag if
+    /// given, domains are resolved by the outbound.
+    pub async fn dial_tcp(&self, addr: &SocksAddr, outbound: Option<&str>) -> Result<AnyStream> {
+        let tag = match (outbound, addr) {
+            (Some(tag), _) => tag,
+            (None, SocksAddr::Ip(addr)) => return Ok(dial_tcp_stream(addr).await?),
+            (None, SocksAddr::Domain(..)) => return Err(anyhow!("unresolved address {}", addr)),
+        };
+        let outbound_manager = self
+            .outbound_manager
+            .as_ref()
+            .and_then(Weak::upgrade)
+            .ok_or_else(|| anyhow!("no outbounds for dns queries"))?;
+        let handler = outbound_manager
+            .read()
+            .await
+            .get(tag)
+            .ok_or_else(|| anyhow!("outbound {} not found", tag))?;
+        let direct = self
+            .direct
+            .clone()
+            .ok_or_else(|| anyhow!("no servers to resolve outbound {}", tag))?;
+        let sess = Session {
+            network: Network::Tcp,
+            destination: addr.clone(),
+            outbound_tag: tag.to_string(),
+            // Groups dial their actors with the client they're given, which
+            // would be this one.
+            dns_client: Some(direct.clone()),
+            ..Default::default()
+        };
+        let stream = connect_tcp_outbound(&sess, direct, &handler).await?;
+        Ok(TcpOutboundHandler::handle(handler.as_ref(), &sess, stream).await?)
+    }
+
+    // Sends a query to `server` and returns the response.
+    #[cfg(feature = "outbound-ech")]
+    async fn exchange(&self, request: Vec<u8>, server: &Upstream) -> Result<Message> {
+        let buf = self.send_query(request, server).await?;
+        let resp =
+            Message::from_vec(&buf).map_err(|err| anyhow!("parse message failed: {:?}", err))?;
+        if resp.response_code() != ResponseCode::NoError {
+            return Err(anyhow!("response error {}", resp.response_code()));
+        }
+        Ok(resp)
+    }
+
+    async fn udp_exchange(&self, request: Vec<u8>, server: &SocketAddr) -> Result<Vec<u8>> {
+        let socket = self.new_udp_socket(server).await?;
+        let mut last_err = None;
+        for _i in 0..*option::MAX_DNS_RETRIES {
+            if let Err(err) = socket.send_to(&request, server).await {
+                last_err = Some(anyhow!("send failed: {:?}", err));
+                continue;
+            }
+            let mut buf = vec![0u8; 512];
+            match timeout(
+                Duration::from_secs(*option::DNS_TIMEOUT),
+                socket.recv_from(&mut buf),
+            )
+            .await
+            {
+                Ok(Ok((n, _))) => {
+                    buf.truncate(n);
+                    return Ok(buf);
+                }
+                Ok(Err(err)) => last_err = Some(anyhow!("recv failed: {:?}", err)),
+                Err(e) => last_err = Some(anyhow!("recv timeout: {}", e)),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| anyhow!("all lookup attempts failed")))
+    }
+
+    /// Looks up the ECHConfigList in the HTTPS record of `host`.
+    #[cfg(feature = "outbound-ech")]
+    pub async fn lookup_ech_config(&self, host: &str) -> Result<Vec<u8>> {
+        if let Some(entry) = self.ech_cache.lock().await.get(host) {
+            if entry
+                .deadline
+                .checked_duration_since(Instant::now())
+                .is_some()
+            {
+                return Ok(entry.config_list.clone());
+            }
+        }
+
+        let name = match Name::from_str(&format!("{}.", host)) {
+            Ok(n) => n,
+            Err(e) => return Err(anyhow!("invalid domain name [{}]: {}", host, e)),
+        };
+        let msg_buf = self.new_lookup(name, RecordType::HTTPS)?;
+        let mut tasks = Vec::new();
+        for server in self.servers_of(host) {
+            tasks.push(Box::pin(self.exchange(msg_buf.clone(), server)));
+        }
+        let (resp, _) = select_ok(tasks.into_iter()).await?;
+        for ans in resp.answers() {
+            if let RData::HTTPS(svcb) = ans.rdata() {
+                for (_, value) in svcb.svc_params() {
+                    if let SvcParamValue::EchConfig(config) = value {
+                        // The length of the list is stripped by the parser.
+                        let mut config_list = (config.0.len() as u16).to_be_bytes().to_vec();
+                        config_list.extend_from_slice(&config.0);
+                        let deadline = Instant::now()
+                            .checked_add(Duration::from_secs(ans.ttl().into()))
+                            .ok_or_else(|| anyhow!("invalid ttl"))?;
+                        let entry = EchCacheEntry {
+                            config_list: config_list.clone(),
+                            deadline,
+                        };
+                        self.ech_cache.lock().await.put(host.to_owned(), entry);
+                        return Ok(config_list);
+                    }
+                }
+            }
+        }
+        Err(anyhow!("no ech config in the https records"))
+    }
+}
+
+// Queries are sent from sockets bound the way `OUTBOUND_INTERFACE` says.
+static DIAL_OPTIONS: DialOptions = DialOptions {
+    bind: None,
+    domain_strategy: DomainStrategy::AsIs,
+    tcp_fast_open: false,
+    connect_timeout: None,
+};
+
+impl UdpConnector for DnsClient {
+    fn dial_options(&self) -> &DialOptions {
+        &DIAL_OPTIONS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use trust_dns_proto::rr::rdata::SOA;
+
+    use super::*;
+
+    fn response(code: ResponseCode) -> Message {
+        let mut msg = DnsClient::new_query(Name::from_str("example.com.").unwrap(), RecordType::A);
+        msg.set_message_type(MessageType::Response);
+        msg.set_response_code(code);
+        msg
+    }
+
+    fn soa(ttl: u32, minimum: u32) -> Record {
+        let name = Name::from_str("example.com.").unwrap();
+        let soa = SOA::new(name.clone(), name.clone(), 1, 7200, 3600, 1209600, minimum);
+        Record::from_rdata(name, ttl, RData::SOA(soa))
+    }
+
+    fn ttl_of(entry: &CacheEntry) -> u64 {
+        // Rounded up for the time passed since the entry was made.
+        entry
+            .deadline
+            .duration_since(Instant::now())
+            .as_secs_f64()
+            .ceil() as u64
+    }
+
+    #[test]
+    fn test_hosts() {
+        let mut dns = crate::config::Dns::new();
+        for (name, values) in [
+            ("example.com", vec!["1.1.1.1", "::1"]),
+            ("*.example.com", vec!["2.2.2.2"]),
+            ("www.example.com", vec!["3.3.3.3"]),
+            ("*.ads.example.org", vec!["block"]),
+            ("invalid.example.org", vec!["invalid"]),
+        ] {
+            let mut ips = crate::config::Dns_Ips::new();
+            ips.values = values.into_iter().map(str::to_string).collect();
+            dns.hosts.insert(name.to_string(), ips);
+        }
+        let hosts = Hosts::load(&dns);
+        let get = |host: &str| hosts.get(host).map(|ips| ips.len());
+        assert_eq!(get("example.com"), Some(2));
+        assert_eq!(
+            hosts.get("a.b.example.com").unwrap()[0].to_string(),
+            "2.2.2.2"
+        );
+        assert_eq!(
+            hosts.get("www.example.com").unwrap()[0].to_string(),
+            "3.3.3.3"
+        );
+        assert_eq!(get("x.ads.example.org"), Some(0));
+        assert_eq!(get("ads.example.org"), None);
+        assert_eq!(get("invalid.example.org"), None);
+        assert_eq!(get("example.net"), None);
+
+        let client = DnsClient {
+            servers: vec![Upstream::parse("8.8.8.8").unwrap()],
+            rules: Vec::new(),
+            hosts,
+            cache: Arc::new(TokioMutex::new(LruCache::new(1))),
+            cache_hits: AtomicU64::new(0),
+            cache_negative_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+            #[cfg(feature = "outbound-ech")]
+            ech_cache: Arc::new(TokioMutex::new(LruCache::new(1))),
+            outbound_manager: None,
+            direct: None,
+            client_subnet: None,
+        };
+        let answer = |name: &str, ty: RecordType| {
+            let query = DnsClient::new_query(Name::from_str(name).unwrap(), ty);
+            let resp = client.answer_static(&query.to_vec().unwrap())?;
+            let resp = Message::from_vec(&resp).unwrap();
+            assert_eq!(resp.id(), query.id());
+            Some((resp.response_code(), resp.answers().len()))
+        };
+        assert_eq!(
+            answer("Example.com.", RecordType::A),
+            Some((ResponseCode::NoError, 1))
+        );
+        assert_eq!(
+            answer("www.example.com.", RecordType::AAAA),
+            Some((ResponseCode::NoError, 0))
+        );
+        assert_eq!(
+            answer("x.ads.example.org.", RecordType::TXT),
+            Some((ResponseCode::NXDomain, 0))
+        );
+        assert_eq!(answer("example.com.", RecordType::MX), None);
+        assert_eq!(answer("example.net.", RecordType::A), None);
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let upstream = Upstream::parse("8.8.8.8").unwrap();
+        let start = tokio::time::Instant::now();
+        let name = Name::from_str("example.com.").unwrap();
+
+        // The shortest TTL of the answers.
+        let mut msg = response(ResponseCode::NoError);
+        msg.add_answer(Record::from_rdata(
+            name.clone(),
+            300,
+            RData::A(Ipv4Addr::new(1, 1, 1, 1)),
+        ));
+        msg.add_answer(Record::from_rdata(
+            name.clone(),
+            60,
+            RData::A(Ipv4Addr::new(1, 0, 0, 1)),
+        ));
+        let buf = msg.to_vec().unwrap();
+        let entry = DnsClient::parse_response(&buf, "example.com", &upstream, start).unwrap();
+        assert_eq!(entry.ips.len(), 2);
+        assert_eq!(ttl_of(&entry), 60);
+
+        // Clamped by the maximum TTL.
+        let mut msg = response(ResponseCode::NoError);
+        msg.add_answer(Record::from_rdata(
+            name,
+            *option::DNS_MAX_TTL + 100,
+            RData::A(Ipv4Addr::new(1, 1, 1, 1)),
+        ));
+        let buf = msg.to_vec().unwrap();
+        let entry = DnsClient::parse_response(&buf, "example.com", &upstream, start).unwrap();
+        assert_eq!(ttl_of(&entry), *option::DNS_MAX_TTL as u64);
+
+        // Negative responses last the TTL or the minimum of the SOA.
+        let mut msg = response(ResponseCode::NXDomain);
+        msg.add_name_server(soa(120, 30));
+        let buf = msg.to_vec().unwrap();
+        let entry = DnsClient::parse_response(&buf, "example.com", &upstream, start).unwrap();
+        assert!(entry.ips.is_empty());
+        assert_eq!(ttl_of(&entry), 30);
+
+        let mut msg = response(ResponseCode::NoError);
+        msg.add_name_server(soa(3600, 3600));
+        let buf = msg.to_vec().unwrap();
+        let entry = DnsClient::parse_response(&buf, "example.com", &upstream, start).unwrap();
+        assert!(entry.ips.is_empty());
+        assert_eq!(ttl_of(&entry), *option::DNS_MAX_NEGATIVE_TTL as u64);
+
+        // Not cached without a SOA.
+        let buf = response(ResponseCode::NXDomain).to_vec().unwrap();
+        assert!(DnsClient::parse_response(&buf, "example.com", &upstream, start).is_err());
+        let buf = response(ResponseCode::ServFail).to_vec().unwrap();
+        assert!(DnsClient::parse_response(&buf, "example.com", &upstream, start).is_err());
+    }
+
+    #[test]
+    fn test_outbound_servers() {
+        assert_eq!(
+            take_outbound("8.8.8.8?outbound=Proxy"),
+            ("8.8.8.8".to_string(), Some("Proxy".to_string()))
+        );
+        assert_eq!(
+            take_outbound("tls://dns.google?sni=dns.google&outbound=Proxy"),
+            (
+                "tls://dns.google?sni=dns.google".to_string(),
+                Some("Proxy".to_string())
+            )
+        );
+        assert_eq!(
+            take_outbound("8.8.8.8:53"),
+            ("8.8.8.8:53".to_string(), None)
+        );
+        match Upstream::parse("8.8.8.8?outbound=Proxy").unwrap() {
+            Upstream::Outbound(addr, tag) => {
+                assert_eq!(addr, "8.8.8.8:53".parse::<SocketAddr>().unwrap());
+                assert_eq!(tag, "Proxy");
+            }
+            _ => panic!("not an outbound server"),
+        }
+        assert!(Upstream::parse("8.8.8.8:53").unwrap().udp_addr().is_some());
+        assert!(Upstream::parse("8.8.8.8?foo=bar").is_err());
+
+        // Servers with outbounds are resolved by the outbounds, the others
+        // on the direct servers.
+        let mut dns = crate::config::Dns::new();
+        dns.servers.push("1.1.1.1".to_string());
+        let dns = protobuf::SingularPtrField::some(dns);
+        assert!(DnsClient::new(&dns).unwrap().direct.is_none());
+        let mut dns = dns.unwrap();
+        dns.servers = vec!["1.1.1.1?outbound=Proxy".to_string()];
+        let dns = protobuf::SingularPtrField::some(dns);
+        let client = DnsClient::new(&dns).unwrap();
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let direct = client.direct.clone().unwrap();
+            let direct = direct.read().await;
+            assert_eq!(
+                direct.servers[0].udp_addr(),
+                Some("1.1.1.1:53".parse().unwrap())
+            );
+            assert!(direct.direct.is_none());
+            // Outbounds are not set.
+            let addr = SocksAddr::Domain("dns.google".to_string(), 853);
+            assert!(client.dial_tcp(&addr, Some("Proxy")).await.is_err());
+            assert!(client.dial_tcp(&addr, None).await.is_err());
+        });
+    }
+
+    #[test]
+    fn test_reload() {
+        let hosts = |values: &[&str]| {
+            let mut dns = crate::config::Dns::new();
+            dns.servers.push("1.1.1.1".to_string());
+            let mut ips = crate::config::Dns_Ips::new();
+            ips.values = values.iter().map(|v| v.to_string()).collect();
+            dns.hosts.insert("example.com".to_string(), ips);
+            protobuf::SingularPtrField::some(dns)
+        };
+        let mut client = DnsClient::new(&hosts(&["1.1.1.1", "1.0.0.1"])).unwrap();
+        let host = "example.com".to_string();
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            // Static hosts of several addresses are cached.
+            let ips = client
+                .lookup_with_strategy(&host, DomainStrategy::Ipv4Only)
+                .await
+                .unwrap();
+            assert_eq!(ips.len(), 2);
+            assert_eq!(client.cache_stats().await.size, 1);
+
+            client.reload(&hosts(&["2.2.2.2", "2.0.0.2"])).unwrap();
+            assert_eq!(client.cache_stats().await.size, 0);
+            let ips = client
+                .lookup_with_strategy(&host, DomainStrategy::Ipv4Only)
+                .await
+                .unwrap();
+            assert!(ips.contains(&"2.2.2.2".parse().unwrap()));
+        });
+    }
+
+    #[cfg(all(
+        feature = "config-json",
+        feature = "outbound-static",
+        feature = "outbound-trojan",
+        feature = "outbound-mux"
+    ))]
+    #[test]
+    fn test_group_outbound_server() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            // Connections to the trojan server are accepted by the backlog.
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let json = format!(
+                r#"
+                {{
+                    "dns": {{
+                        "servers": ["127.0.0.1?outbound=Proxy"],
+                        "hosts": {{
+                            "mux.test": ["127.0.0.1"]
+                        }}
+                    }},
+                    "outbounds": [
+                        {{
+                            "protocol": "static",
+                            "tag": "Proxy",
+                            "settings": {{
+                                "actors": ["Server"]
+                            }}
+                        }},
+                        {{
+                            "protocol": "trojan",
+                            "tag": "Server",
+                            "settings": {{
+                                "address": "127.0.0.1",
+                                "port": {},
+                                "password": "password"
+                            }}
+                        }},
+                        {{
+                            "protocol": "mux",
+                            "tag": "Mux",
+                            "settings": {{
+                                "address": "mux.test",
+                                "port": {}
+                            }}
+                        }}
+                    ]
+                }}
+                "#,
+                port, port
+            );
+            let config = crate::config::json::from_string(&json).unwrap();
+            let dns_client = Arc::new(RwLock::new(DnsClient::new(&config.dns).unwrap()));
+            let outbound_manager = Arc::new(RwLock::new(
+                OutboundManager::new(&config.outbounds, dns_client.clone()).unwrap(),
+            ));
+            // The group must resolve its actor on the direct servers, the
+            // client is locked as a reload waiting on a query would do.
+            let mut client = dns_client.write().await;
+            client.set_outbound_manager(Arc::downgrade(&outbound_manager));
+            let addr = SocksAddr::Ip("127.0.0.1:53".parse().unwrap());
+            let res = timeout(
+                Duration::from_secs(2),
+                client.dial_tcp(&addr, Some("Proxy")),
+            )
+            .await;
+            assert!(res.expect("dial through the group timed out").is_ok());
+            // So must an outbound dialing its own connections.
+            let res = timeout(Duration::from_secs(2), client.dial_tcp(&addr, Some("Mux"))).await;
+            assert!(res.expect("dial through the mux timed out").is_ok());
+            drop(listener);
+        });
+    }
+
+    #[test]
+    fn test_client_subnet() {
+        let subnet = "203.0.113.77".parse::<ClientSubnet>().unwrap();
+        assert_eq!(subnet.encode(), vec![0, 1, 24, 0, 203, 0, 113]);
+        let subnet = "203.0.113.77/20".parse::<ClientSubnet>().unwrap();
+        assert_eq!(subnet.encode(), vec![0, 1, 20, 0, 203, 0, 112]);
+        let subnet = "2001:db8:1234:5678::1".parse::<ClientSubnet>().unwrap();
+        assert_eq!(
+            subnet.encode(),
+            vec![0, 2, 56, 0, 0x20, 0x01, 0x0d, 0xb8, 0x12, 0x34, 0x56]
+        );
+        assert_eq!(
+            "0.0.0.0/0".parse::<ClientSubnet>().unwrap().encode(),
+            vec![0, 1, 0, 0]
+        );
+        assert!("203.0.113.0/33".parse::<ClientSubnet>().is_err());
+        assert!("example.com".parse::<ClientSubnet>().is_err());
+
+        let query = DnsClient::new_query(Name::from_str("example.com.").unwrap(), RecordType::A);
+        let mut msg = query.clone();
+        subnet.apply(&mut msg);
+        let msg = Message::from_vec(&msg.to_vec().unwrap()).unwrap();
+        assert_eq!(msg.id(), query.id());
+        let edns = msg.edns().unwrap();
+        assert_eq!(edns.max_payload(), 512);
+        assert_eq!(
+            edns.option(EdnsCode::Subnet),
+            Some(&EdnsOption::Unknown(
+                EdnsCode::Subnet.into(),
+                subnet.encode()
+            ))
+        );
+        // The subnet given by the client is kept.
+        let mut msg = msg.clone();
+        "198.51.100.0/24"
+            .parse::<ClientSubnet>()
+            .unwrap()
+            .apply(&mut msg);
+        assert_eq!(
+            msg.edns().unwrap().option(EdnsCode::Subnet),
+            Some(&EdnsOption::Unknown(
+                EdnsCode::Subnet.into(),
+                subnet.encode()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_tcp_exchange() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let (client_io, mut server_io) = tokio::io::duplex(65536);
+            tokio::spawn(async move {
+                let mut len = [0u8; 2];
+                while server_io.read_exact(&mut len).await.is_ok() {
+                    let mut query = vec![0u8; u16::from_be_bytes(len) as usize];
+                    server_io.read_exact(&mut query).await.unwrap();
+                    query.reverse();
+                    server_io.write_all(&len).await.unwrap();
+                    server_io.write_all(&query).await.unwrap();
+                }
+            });
+            let mut stream: AnyStream = Box::new(client_io);
+            // Queries of the same connection.
+            for _ in 0..2 {
+                let resp = tcp_exchange(&mut stream, &[1, 2, 3]).await.unwrap();
+                assert_eq!(resp, vec![3, 2, 1]);
+            }
+        });
+    }
+}
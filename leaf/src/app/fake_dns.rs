@@ -1,9 +1,15 @@
 use std::collections::HashMap;
-use std::net::{IpAddr, Ipv4Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Result};
 use byteorder::{BigEndian, ByteOrder};
+use cidr::{Cidr, Ipv4Cidr, Ipv6Cidr};
 use log::*;
+use protobuf::Message as ProtobufMessage;
 use tokio::sync::RwLock;
 use trust_dns_proto::op::{
     header::MessageType, op_code::OpCode, response_code::ResponseCode, Message,
@@ -12,63 +18,456 @@ use trust_dns_proto::rr::{
     dns_class::DNSClass, record_data::RData, record_type::RecordType, resource::Record,
 };
 
+use super::fake_dns_cache::FakeDnsCache;
+
+const CACHE_FILE: &str = "fakedns.cache";
+// How often new mappings are written to the cache file.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
 pub enum FakeDnsMode {
     Include,
     Exclude,
 }
 
-pub struct FakeDns(RwLock<FakeDnsImpl>);
+/// What to do when all fake IPs in the pool have been allocated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FakeIpEviction {
+    /// Recycles the earliest allocated IP.
+    Fifo,
+    /// Recycles the least recently used IP.
+    Lru,
+    /// Stops allocating, queries are answered by the real DNS.
+    Reject,
+}
+
+/// The range of fake IPs.
+pub struct FakeIpPool {
+    pub ipv4: Ipv4Cidr,
+    // Fake IPv6 addresses are allocated together with the IPv4 ones, the low
+    // 32 bits of a fake IPv6 address are the same as the paired fake IPv4
+    // address, the prefix length must not exceed 96.
+    pub ipv6: Ipv6Cidr,
+    pub size: u32,
+    pub eviction: FakeIpEviction,
+    // TTL of fake DNS answers in seconds.
+    pub ttl: u32,
+    // Whether to answer AAAA queries with fake IPv6 addresses, or with no
+    // records so that clients fall back to IPv4.
+    pub answer_ipv6: bool,
+}
+
+impl Default for FakeIpPool {
+    fn default() -> Self {
+        Self {
+            ipv4: "198.18.0.0/15".parse().unwrap(),
+            ipv6: "fdfe:dcba:9876::/96".parse().unwrap(),
+            size: 1280,
+            eviction: FakeIpEviction::Fifo,
+            ttl: 1,
+            answer_ipv6: *crate::option::ENABLE_IPV6,
+        }
+    }
+}
+
+impl FakeIpPool {
+    /// Creates a pool from config values, defaults are used for empty values.
+    pub fn new(ipv4: &str, ipv6: &str, size: i32, eviction: &str) -> Result<Self> {
+        let mut pool = Self::default();
+        if !ipv4.is_empty() {
+            pool.ipv4 = ipv4
+                .parse()
+                .map_err(|e| anyhow!("invalid fake ip cidr {}: {}", ipv4, e))?;
+        }
+        if !ipv6.is_empty() {
+            pool.ipv6 = ipv6
+                .parse()
+                .map_err(|e| anyhow!("invalid fake ipv6 cidr {}: {}", ipv6, e))?;
+            if pool.ipv6.network_length() > 96 {
+                return Err(anyhow!("fake ipv6 cidr {} is too small", ipv6));
+            }
+        }
+        if size > 0 {
+            pool.size = size as u32;
+        }
+        pool.eviction = match eviction {
+            "" | "fifo" => FakeIpEviction::Fifo,
+            "lru" => FakeIpEviction::Lru,
+            "reject" => FakeIpEviction::Reject,
+            _ => return Err(anyhow!("unknown fake ip eviction policy {}", eviction)),
+        };
+        Ok(pool)
+    }
+}
+
+pub struct FakeDns {
+    inner: RwLock<FakeDnsImpl>,
+    // Copies of the pool range so that fake IPs can be checked without the
+    // lock.
+    min_ip: u32,
+    max_ip: u32,
+    ipv6_prefix: [u16; 6],
+    generation: Arc<AtomicU64>,
+}
 
 impl FakeDns {
-    pub fn new(mode: FakeDnsMode) -> Self {
-        Self(RwLock::new(FakeDnsImpl::new(mode)))
+    pub fn new(mode: FakeDnsMode, pool: FakeIpPool) -> Self {
+        let mut fakedns = FakeDnsImpl::new(mode, pool);
+        // Restores mappings from the last run, apps may still be using the
+        // fake IPs they got.
+        match crate::app::get_cache_file_path(CACHE_FILE) {
+            Ok(path) => fakedns.load(path),
+            Err(e) => warn!("fake dns cache unavailable: {}", e),
+        }
+        Self::from_impl(fakedns)
+    }
+
+    fn from_impl(fakedns: FakeDnsImpl) -> Self {
+        Self {
+            min_ip: fakedns.min_cursor,
+            max_ip: fakedns.max_cursor,
+            ipv6_prefix: fakedns.ipv6_prefix,
+            generation: fakedns.generation.clone(),
+            inner: RwLock::new(fakedns),
+        }
+    }
+
+    /// Writes new mappings to the cache file periodically, the rest are
+    /// written once the fake DNS is dropped.
+    pub fn spawn_flush(self: &Arc<Self>) {
+        let fakedns = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                match fakedns.upgrade() {
+                    Some(fakedns) => fakedns.flush().await,
+                    None => break,
+                }
+            }
+        });
+    }
+
+    async fn flush(&self) {
+        let cache = self.inner.write().await.take_cache();
+        let (cache_file, content) = match cache {
+            Some(cache) => cache,
+            None => return,
+        };
+        match tokio::task::spawn_blocking(move || write_cache(&cache_file, &content)).await {
+            Ok(Ok(())) => (),
+            Ok(Err(e)) => warn!("persist fake dns cache failed: {}", e),
+            Err(e) => warn!("persist fake dns cache failed: {}", e),
+        }
     }
 
     pub async fn add_filter(&self, filter: String) {
-        self.0.write().await.add_filter(filter)
+        self.inner.write().await.add_filter(filter)
     }
 
     pub async fn query_domain(&self, ip: &IpAddr) -> Option<String> {
-        self.0.read().await.query_domain(ip)
+        self.inner.read().await.query_domain(ip)
+    }
+
+    /// Returns the domain of the fake IP and a guard marking the IP in use by
+    /// a session, the IP is never recycled before the guard is dropped.
+    pub async fn acquire(&self, ip: &IpAddr) -> Option<(String, FakeIpGuard)> {
+        let (domain, usage) = self.inner.read().await.query_domain_usage(ip)?;
+        usage.active.fetch_add(1, Ordering::AcqRel);
+        usage.touch();
+        Some((domain, FakeIpGuard(usage)))
     }
 
     pub async fn query_fake_ip(&self, domain: &str) -> Option<IpAddr> {
-        self.0.read().await.query_fake_ip(domain)
+        self.inner.read().await.query_fake_ip(domain)
+    }
+
+    pub async fn query_fake_ipv6(&self, domain: &str) -> Option<IpAddr> {
+        self.inner.read().await.query_fake_ipv6(domain)
     }
 
     pub async fn generate_fake_response(&self, request: &[u8]) -> Result<Vec<u8>> {
-        self.0.write().await.generate_fake_response(request)
+        self.inner.write().await.generate_fake_response(request)
+    }
+
+    pub fn is_fake_ip(&self, ip: &IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V4(ip) => FakeDnsImpl::ip_to_u32(ip),
+            IpAddr::V6(ip) => match ipv6_suffix(&self.ipv6_prefix, ip) {
+                Some(ip) => ip,
+                None => return false,
+            },
+        };
+        ip >= self.min_ip && ip <= self.max_ip
     }
 
-    pub async fn is_fake_ip(&self, ip: &IpAddr) -> bool {
-        self.0.read().await.is_fake_ip(ip)
+    /// Increases whenever a fake IP is recycled, mappings looked up earlier
+    /// may be stale.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
     }
 }
 
+/// Tracks how a fake IP is being used, it's updated without the lock.
+struct FakeIpUsage {
+    epoch: Instant,
+    // Number of sessions holding the IP.
+    active: AtomicUsize,
+    // Seconds since the epoch plus one when the IP was last used by traffic,
+    // 0 if never used.
+    last_used: AtomicU64,
+    // Seconds since the epoch when the IP was last given out in a DNS answer.
+    last_answered: AtomicU64,
+}
+
+impl FakeIpUsage {
+    fn new(epoch: Instant) -> Self {
+        let usage = Self {
+            epoch,
+            active: AtomicUsize::new(0),
+            last_used: AtomicU64::new(0),
+            last_answered: AtomicU64::new(0),
+        };
+        usage.answer();
+        usage
+    }
+
+    fn now(&self) -> u64 {
+        self.epoch.elapsed().as_secs()
+    }
+
+    fn touch(&self) {
+        self.last_used.store(self.now() + 1, Ordering::Release);
+    }
+
+    fn answer(&self) {
+        self.last_answered.store(self.now(), Ordering::Release);
+    }
+
+    // Whether the IP must not be recycled. A UDP flow has no end, it's
+    // considered alive as long as its NAT entry would be.
+    fn busy(&self) -> bool {
+        if self.active.load(Ordering::Acquire) > 0 {
+            return true;
+        }
+        match self.last_used.load(Ordering::Acquire) {
+            0 => false,
+            t => self.now() + 1 - t < *crate::option::UDP_SESSION_TIMEOUT,
+        }
+    }
+
+    // When the IP was last used or answered, for LRU eviction.
+    fn recency(&self) -> u64 {
+        std::cmp::max(
+            self.last_used.load(Ordering::Acquire).saturating_sub(1),
+            self.last_answered.load(Ordering::Acquire),
+        )
+    }
+}
+
+/// Marks a fake IP in use by a session until dropped.
+pub struct FakeIpGuard(Arc<FakeIpUsage>);
+
+impl Drop for FakeIpGuard {
+    fn drop(&mut self) {
+        self.0.touch();
+        self.0.active.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+// Max number of entries of a lookup cache, it's cleared when full.
+const LOOKUP_CACHE_SIZE: usize = 4096;
+
+/// Caches fake DNS lookups of a single task, e.g. a UDP relay looking up for
+/// every packet, so that the lock is only taken on misses. The cache is
+/// cleared when any fake IP is recycled.
+pub struct FakeDnsLookup {
+    fakedns: Arc<FakeDns>,
+    generation: u64,
+    domains: HashMap<IpAddr, (String, Arc<FakeIpUsage>)>,
+    ips: HashMap<(String, bool), (IpAddr, Arc<FakeIpUsage>)>,
+}
+
+impl FakeDnsLookup {
+    pub fn new(fakedns: Arc<FakeDns>) -> Self {
+        Self {
+            generation: fakedns.generation(),
+            fakedns,
+            domains: HashMap::new(),
+            ips: HashMap::new(),
+        }
+    }
+
+    fn validate(&mut self) {
+        let generation = self.fakedns.generation();
+        if generation != self.generation
+            || self.domains.len() >= LOOKUP_CACHE_SIZE
+            || self.ips.len() >= LOOKUP_CACHE_SIZE
+        {
+            self.domains.clear();
+            self.ips.clear();
+            self.generation = generation;
+        }
+    }
+
+    /// Returns the domain of the fake IP, the IP is marked as used.
+    pub async fn query_domain(&mut self, ip: &IpAddr) -> Option<String> {
+        self.validate();
+        if let Some((domain, usage)) = self.domains.get(ip) {
+            usage.touch();
+            return Some(domain.clone());
+        }
+        let (domain, usage) = self.fakedns.inner.read().await.query_domain_usage(ip)?;
+        usage.touch();
+        self.domains.insert(*ip, (domain.clone(), usage));
+        Some(domain)
+    }
+
+    /// Returns the fake IPv6 address of the domain if `ipv6` is true, or the
+    /// fake IPv4 address.
+    pub async fn query_fake_ip(&mut self, domain: &str, ipv6: bool) -> Option<IpAddr> {
+        self.validate();
+        let key = (domain.to_owned(), ipv6);
+        if let Some((ip, usage)) = self.ips.get(&key) {
+            usage.touch();
+            return Some(*ip);
+        }
+        let fakedns = self.fakedns.inner.read().await;
+        let ip = if ipv6 {
+            fakedns.query_fake_ipv6(domain)?
+        } else {
+            fakedns.query_fake_ip(domain)?
+        };
+        let (_, usage) = fakedns.query_domain_usage(&ip)?;
+        drop(fakedns);
+        usage.touch();
+        self.ips.insert(key, (ip, usage));
+        Some(ip)
+    }
+}
+
+// Returns the low 32 bits of a fake IPv6 address with the given prefix.
+fn ipv6_suffix(prefix: &[u16; 6], ip: &Ipv6Addr) -> Option<u32> {
+    let segs = ip.segments();
+    if segs[..6] != *prefix {
+        return None;
+    }
+    Some(((segs[6] as u32) << 16) | segs[7] as u32)
+}
+
+// Replaces the cache file at once, so it's never left partially written.
+fn write_cache(cache_file: &Path, content: &[u8]) -> Result<()> {
+    let mut tmp_file = cache_file.as_os_str().to_owned();
+    tmp_file.push(".tmp");
+    std::fs::write(&tmp_file, content)?;
+    std::fs::rename(&tmp_file, cache_file)?;
+    Ok(())
+}
+
 pub(self) struct FakeDnsImpl {
     ip_to_domain: HashMap<u32, String>,
     domain_to_ip: HashMap<String, u32>,
+    usage: HashMap<u32, Arc<FakeIpUsage>>,
+    epoch: Instant,
     cursor: u32,
     min_cursor: u32,
     max_cursor: u32,
+    ipv6_prefix: [u16; 6],
+    eviction: FakeIpEviction,
     ttl: u32,
     filters: Vec<String>,
     mode: FakeDnsMode,
+    // Whether to answer AAAA queries with fake IPv6 addresses.
+    ipv6: bool,
+    // Where mappings are persisted.
+    cache_file: Option<PathBuf>,
+    // Whether there are mappings not persisted yet.
+    dirty: bool,
+    generation: Arc<AtomicU64>,
 }
 
 impl FakeDnsImpl {
-    pub(self) fn new(mode: FakeDnsMode) -> Self {
-        let min_cursor = Self::ip_to_u32(&Ipv4Addr::new(198, 18, 0, 0));
-        let max_cursor = Self::ip_to_u32(&Ipv4Addr::new(198, 18, 4, 255));
+    pub(self) fn new(mode: FakeDnsMode, pool: FakeIpPool) -> Self {
+        let min_cursor = Self::ip_to_u32(&pool.ipv4.first_address());
+        let max_cursor = std::cmp::min(
+            Self::ip_to_u32(&pool.ipv4.last_address()),
+            min_cursor.saturating_add(pool.size.max(1) - 1),
+        );
+        let mut ipv6_prefix = [0u16; 6];
+        ipv6_prefix.copy_from_slice(&pool.ipv6.first_address().segments()[..6]);
         Self {
             ip_to_domain: HashMap::new(),
             domain_to_ip: HashMap::new(),
+            usage: HashMap::new(),
+            epoch: Instant::now(),
             cursor: min_cursor,
             min_cursor,
             max_cursor,
-            ttl: 1,
+            ipv6_prefix,
+            eviction: pool.eviction,
+            ttl: pool.ttl,
             filters: Vec::new(),
             mode,
+            ipv6: pool.answer_ipv6,
+            cache_file: None,
+            dirty: false,
+            generation: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Loads mappings from the cache file, and persists mappings to it from
+    /// now on.
+    pub(self) fn load(&mut self, cache_file: PathBuf) {
+        if cache_file.exists() {
+            let cache = std::fs::read(&cache_file)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| Ok(FakeDnsCache::parse_from_bytes(&content)?));
+            match cache {
+                Ok(cache) => {
+                    for (ip, domain) in cache.ip_to_domain {
+                        // The pool may have changed.
+                        if ip < self.min_cursor || ip > self.max_cursor {
+                            continue;
+                        }
+                        self.domain_to_ip.insert(domain.clone(), ip);
+                        self.ip_to_domain.insert(ip, domain);
+                        self.usage
+                            .insert(ip, Arc::new(FakeIpUsage::new(self.epoch)));
+                    }
+                    if cache.cursor >= self.min_cursor && cache.cursor <= self.max_cursor {
+                        self.cursor = cache.cursor;
+                    }
+                    debug!(
+                        "loaded {} fake dns mappings from {}",
+                        self.ip_to_domain.len(),
+                        cache_file.display()
+                    );
+                }
+                Err(e) => warn!("load fake dns cache failed: {}", e),
+            }
+        }
+        self.cache_file = Some(cache_file);
+    }
+
+    // Returns the cache file and its content if there are mappings not
+    // persisted yet.
+    fn take_cache(&mut self) -> Option<(PathBuf, Vec<u8>)> {
+        if !self.dirty {
+            return None;
+        }
+        let cache_file = self.cache_file.clone()?;
+        let mut cache = FakeDnsCache::new();
+        cache.ip_to_domain = self.ip_to_domain.clone();
+        cache.cursor = self.cursor;
+        match cache.write_to_bytes() {
+            Ok(content) => {
+                self.dirty = false;
+                Some((cache_file, content))
+            }
+            Err(e) => {
+                warn!("encode fake dns cache failed: {}", e);
+                None
+            }
         }
     }
 
@@ -78,10 +477,21 @@ impl FakeDnsImpl {
 
     pub(self) fn query_domain(&self, ip: &IpAddr) -> Option<String> {
         let ip = match ip {
-            IpAddr::V4(ip) => ip,
-            _ => return None,
+            IpAddr::V4(ip) => Self::ip_to_u32(ip),
+            IpAddr::V6(ip) => self.ipv6_to_u32(ip)?,
         };
-        self.ip_to_domain.get(&Self::ip_to_u32(ip)).cloned()
+        self.ip_to_domain.get(&ip).cloned()
+    }
+
+    fn query_domain_usage(&self, ip: &IpAddr) -> Option<(String, Arc<FakeIpUsage>)> {
+        let ip = match ip {
+            IpAddr::V4(ip) => Self::ip_to_u32(ip),
+            IpAddr::V6(ip) => self.ipv6_to_u32(ip)?,
+        };
+        Some((
+            self.ip_to_domain.get(&ip)?.clone(),
+            self.usage.get(&ip)?.clone(),
+        ))
     }
 
     pub(self) fn query_fake_ip(&self, domain: &str) -> Option<IpAddr> {
@@ -90,6 +500,12 @@ impl FakeDnsImpl {
             .map(|v| IpAddr::V4(Self::u32_to_ip(v.to_owned())))
     }
 
+    pub(self) fn query_fake_ipv6(&self, domain: &str) -> Option<IpAddr> {
+        self.domain_to_ip
+            .get(domain)
+            .map(|v| IpAddr::V6(self.u32_to_ipv6(v.to_owned())))
+    }
+
     pub(self) fn generate_fake_response(&mut self, request: &[u8]) -> Result<Vec<u8>> {
         let req = Message::from_vec(request)?;
 
@@ -126,11 +542,18 @@ impl FakeDnsImpl {
 
         let ip = if let Some(ip) = self.query_fake_ip(&domain) {
             match ip {
-                IpAddr::V4(a) => a,
+                IpAddr::V4(a) => {
+                    if let Some(usage) = self.usage.get(&Self::ip_to_u32(&a)) {
+                        usage.answer();
+                    }
+                    a
+                }
                 _ => return Err(anyhow!("unexpected Ipv6 fake IP")),
             }
         } else {
-            let ip = self.allocate_ip(&domain);
+            let ip = self
+                .allocate_ip(&domain)
+                .ok_or_else(|| anyhow!("fake ip pool exhausted"))?;
             debug!("allocate {} for {}", &ip, &domain);
             ip
         };
@@ -162,31 +585,75 @@ impl FakeDnsImpl {
             resp.add_answer(ans);
         }
 
+        if query.query_type() == RecordType::AAAA && self.ipv6 {
+            let mut ans = Record::new();
+            ans.set_name(raw_name.clone())
+                .set_rr_type(RecordType::AAAA)
+                .set_ttl(self.ttl)
+                .set_dns_class(DNSClass::IN)
+                .set_rdata(RData::AAAA(self.u32_to_ipv6(Self::ip_to_u32(&ip))));
+            resp.add_answer(ans);
+        }
+
         Ok(resp.to_vec()?)
     }
 
-    pub(self) fn is_fake_ip(&self, ip: &IpAddr) -> bool {
-        let ip = match ip {
-            IpAddr::V4(ip) => ip,
-            _ => return false,
-        };
-        let ip = Self::ip_to_u32(ip);
-        ip >= self.min_cursor && ip <= self.max_cursor
+    // Returns whether the IP has no mapping or can be recycled.
+    fn is_free(&self, ip: u32) -> bool {
+        match self.usage.get(&ip) {
+            Some(usage) => !usage.busy(),
+            None => !self.ip_to_domain.contains_key(&ip),
+        }
     }
 
-    fn allocate_ip(&mut self, domain: &str) -> Ipv4Addr {
-        if let Some(prev_domain) = self.ip_to_domain.insert(self.cursor, domain.to_owned()) {
-            // Remove the entry in the reverse map to make sure we won't have
-            // multiple domains point to a same IP.
-            self.domain_to_ip.remove(&prev_domain);
-        }
-        self.domain_to_ip.insert(domain.to_owned(), self.cursor);
-        let ip = Self::u32_to_ip(self.cursor);
+    fn advance_cursor(&mut self) {
         self.cursor += 1;
         if self.cursor > self.max_cursor {
             self.cursor = self.min_cursor;
         }
-        ip
+    }
+
+    // Finds an IP to allocate, IPs in use by sessions are never recycled.
+    fn find_free_ip(&mut self) -> Option<u32> {
+        if !self.ip_to_domain.contains_key(&self.cursor) {
+            let ip = self.cursor;
+            self.advance_cursor();
+            return Some(ip);
+        }
+        match self.eviction {
+            FakeIpEviction::Reject => None,
+            FakeIpEviction::Fifo => {
+                for _ in self.min_cursor..=self.max_cursor {
+                    let ip = self.cursor;
+                    self.advance_cursor();
+                    if self.is_free(ip) {
+                        return Some(ip);
+                    }
+                }
+                None
+            }
+            FakeIpEviction::Lru => self
+                .usage
+                .iter()
+                .filter(|(_, usage)| !usage.busy())
+                .min_by_key(|(ip, usage)| (usage.recency(), **ip))
+                .map(|(ip, _)| *ip),
+        }
+    }
+
+    fn allocate_ip(&mut self, domain: &str) -> Option<Ipv4Addr> {
+        let ip = self.find_free_ip()?;
+        if let Some(prev_domain) = self.ip_to_domain.insert(ip, domain.to_owned()) {
+            // Remove the entry in the reverse map to make sure we won't have
+            // multiple domains point to a same IP.
+            self.domain_to_ip.remove(&prev_domain);
+            self.generation.fetch_add(1, Ordering::Release);
+        }
+        self.domain_to_ip.insert(domain.to_owned(), ip);
+        self.usage
+            .insert(ip, Arc::new(FakeIpUsage::new(self.epoch)));
+        self.dirty = true;
+        Some(Self::u32_to_ip(ip))
     }
 
     fn accept(&self, domain: &str) -> bool {
@@ -217,6 +684,34 @@ impl FakeDnsImpl {
     fn ip_to_u32(ip: &Ipv4Addr) -> u32 {
         BigEndian::read_u32(&ip.octets())
     }
+
+    fn u32_to_ipv6(&self, ip: u32) -> Ipv6Addr {
+        let p = self.ipv6_prefix;
+        Ipv6Addr::new(
+            p[0],
+            p[1],
+            p[2],
+            p[3],
+            p[4],
+            p[5],
+            (ip >> 16) as u16,
+            ip as u16,
+        )
+    }
+
+    fn ipv6_to_u32(&self, ip: &Ipv6Addr) -> Option<u32> {
+        ipv6_suffix(&self.ipv6_prefix, ip)
+    }
+}
+
+impl Drop for FakeDnsImpl {
+    fn drop(&mut self) {
+        if let Some((cache_file, content)) = self.take_cache() {
+            if let Err(e) = write_cache(&cache_file, &content) {
+                warn!("persist fake dns cache failed: {}", e);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -238,4 +733,150 @@ mod tests {
         let ip2 = 2130706433u32;
         assert_eq!(ip1, ip2);
     }
+
+    #[test]
+    fn test_ipv6_to_u32() {
+        let fakedns = FakeDnsImpl::new(FakeDnsMode::Exclude, FakeIpPool::default());
+        let ip = fakedns.u32_to_ipv6(2130706433u32);
+        assert_eq!(ip, "fdfe:dcba:9876::7f00:1".parse::<Ipv6Addr>().unwrap());
+        assert_eq!(fakedns.ipv6_to_u32(&ip), Some(2130706433u32));
+        let ip = "2001:db8::7f00:1".parse::<Ipv6Addr>().unwrap();
+        assert_eq!(fakedns.ipv6_to_u32(&ip), None);
+    }
+
+    #[test]
+    fn test_fake_ipv6_pairing() {
+        let mut fakedns = FakeDnsImpl::new(FakeDnsMode::Exclude, FakeIpPool::default());
+        fakedns.ipv6 = true;
+        let ip = IpAddr::V4(fakedns.allocate_ip("example.com").unwrap());
+        let ip6 = fakedns.query_fake_ipv6("example.com").unwrap();
+        assert_eq!(fakedns.query_domain(&ip6), fakedns.query_domain(&ip));
+        assert_eq!(fakedns.query_domain(&ip6).unwrap(), "example.com");
+        assert!(FakeDns::from_impl(fakedns).is_fake_ip(&ip6));
+    }
+
+    #[test]
+    fn test_persistence() {
+        let cache_file =
+            std::env::temp_dir().join(format!("leaf-fakedns-test-{}.cache", std::process::id()));
+        let _ = std::fs::remove_file(&cache_file);
+
+        let mut fakedns = FakeDnsImpl::new(FakeDnsMode::Exclude, FakeIpPool::default());
+        fakedns.load(cache_file.clone());
+        let ip1 = IpAddr::V4(fakedns.allocate_ip("example.com").unwrap());
+        let ip2 = IpAddr::V4(fakedns.allocate_ip("example.org").unwrap());
+        // Mappings are written later, or once dropped.
+        assert!(!cache_file.exists());
+        drop(fakedns);
+
+        let mut fakedns = FakeDnsImpl::new(FakeDnsMode::Exclude, FakeIpPool::default());
+        fakedns.load(cache_file.clone());
+        assert_eq!(fakedns.query_fake_ip("example.com"), Some(ip1));
+        assert_eq!(fakedns.query_domain(&ip2).unwrap(), "example.org");
+        // New allocations continue after the restored ones.
+        let ip3 = IpAddr::V4(fakedns.allocate_ip("example.net").unwrap());
+        assert_ne!(ip3, ip1);
+        assert_ne!(ip3, ip2);
+
+        drop(fakedns);
+        let _ = std::fs::remove_file(&cache_file);
+    }
+
+    #[test]
+    fn test_custom_pool() {
+        let pool = FakeIpPool::new("10.10.0.0/16", "fd00:1::/64", 2, "reject").unwrap();
+        let mut fakedns = FakeDnsImpl::new(FakeDnsMode::Exclude, pool);
+        let ip1 = fakedns.allocate_ip("a.com").unwrap();
+        let ip2 = fakedns.allocate_ip("b.com").unwrap();
+        assert_eq!(ip1, Ipv4Addr::new(10, 10, 0, 0));
+        assert_eq!(ip2, Ipv4Addr::new(10, 10, 0, 1));
+        assert!(fakedns.allocate_ip("c.com").is_none());
+        assert_eq!(
+            fakedns.query_fake_ipv6("b.com"),
+            Some(IpAddr::V6("fd00:1::a0a:1".parse().unwrap()))
+        );
+
+        let pool = FakeIpPool::new("10.10.0.0/16", "", 2, "fifo").unwrap();
+        let mut fakedns = FakeDnsImpl::new(FakeDnsMode::Exclude, pool);
+        fakedns.allocate_ip("a.com").unwrap();
+        fakedns.allocate_ip("b.com").unwrap();
+        assert_eq!(
+            fakedns.allocate_ip("c.com"),
+            Some(Ipv4Addr::new(10, 10, 0, 0))
+        );
+        assert!(fakedns.query_fake_ip("a.com").is_none());
+
+        assert!(FakeIpPool::new("", "fd00:1::/112", 0, "").is_err());
+        assert!(FakeIpPool::new("", "", 0, "random").is_err());
+    }
+
+    #[test]
+    fn test_lookup_cache() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let pool = FakeIpPool::new("10.10.0.0/16", "", 1, "fifo").unwrap();
+            let fakedns = Arc::new(FakeDns::from_impl(FakeDnsImpl::new(
+                FakeDnsMode::Exclude,
+                pool,
+            )));
+            let ip = IpAddr::V4(fakedns.inner.write().await.allocate_ip("a.com").unwrap());
+            assert!(fakedns.is_fake_ip(&ip));
+            assert!(!fakedns.is_fake_ip(&"10.10.0.1".parse().unwrap()));
+
+            let mut lookup = FakeDnsLookup::new(fakedns.clone());
+            assert_eq!(lookup.query_domain(&ip).await.as_deref(), Some("a.com"));
+            assert_eq!(lookup.query_fake_ip("a.com", false).await, Some(ip));
+
+            // The only fake IP is recycled after the UDP flow is gone.
+            let mut inner = fakedns.inner.write().await;
+            inner.usage[&FakeDnsImpl::ip_to_u32(&"10.10.0.0".parse().unwrap())]
+                .last_used
+                .store(0, Ordering::Release);
+            inner.allocate_ip("b.com").unwrap();
+            drop(inner);
+            assert_eq!(lookup.query_domain(&ip).await.as_deref(), Some("b.com"));
+            assert_eq!(lookup.query_fake_ip("a.com", false).await, None);
+        });
+    }
+
+    #[test]
+    fn test_eviction() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let pool = FakeIpPool::new("10.10.0.0/16", "", 2, "lru").unwrap();
+            let fakedns = FakeDns::from_impl(FakeDnsImpl::new(FakeDnsMode::Exclude, pool));
+            let mut inner = fakedns.inner.write().await;
+            let ip1 = IpAddr::V4(inner.allocate_ip("a.com").unwrap());
+            let ip2 = IpAddr::V4(inner.allocate_ip("b.com").unwrap());
+            // b.com is used later than a.com.
+            inner.usage[&FakeDnsImpl::ip_to_u32(&"10.10.0.0".parse().unwrap())]
+                .last_answered
+                .store(0, Ordering::Release);
+            inner.usage[&FakeDnsImpl::ip_to_u32(&"10.10.0.1".parse().unwrap())]
+                .last_answered
+                .store(10, Ordering::Release);
+            drop(inner);
+
+            // A session holds a.com, the least recently used, so b.com is
+            // recycled instead.
+            let (domain, guard) = fakedns.acquire(&ip1).await.unwrap();
+            assert_eq!(domain, "a.com");
+            let mut inner = fakedns.inner.write().await;
+            assert_eq!(inner.allocate_ip("c.com").map(IpAddr::V4), Some(ip2));
+            assert!(inner.query_fake_ip("b.com").is_none());
+            drop(inner);
+
+            // All IPs are in use.
+            let _guard = fakedns.acquire(&ip2).await.unwrap();
+            assert!(fakedns.inner.write().await.allocate_ip("d.com").is_none());
+
+            // The IP is still used by the session recently.
+            drop(guard);
+            assert!(fakedns.inner.write().await.allocate_ip("d.com").is_none());
+        });
+    }
 }
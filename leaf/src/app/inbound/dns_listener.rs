@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::app::dispatcher::Dispatcher;
+use crate::app::nat_manager::NatManager;
+use crate::config::Inbound;
+use crate::proxy::dns;
+use crate::Runner;
+
+pub struct DnsInboundListener {
+    pub inbound: Inbound,
+    pub dispatcher: Arc<Dispatcher>,
+    pub nat_manager: Arc<NatManager>,
+}
+
+impl DnsInboundListener {
+    pub fn listen(&self) -> Result<Runner> {
+        dns::inbound::new(
+            self.inbound.clone(),
+            self.dispatcher.clone(),
+            self.nat_manager.clone(),
+        )
+    }
+}
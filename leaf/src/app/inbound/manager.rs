@@ -6,6 +6,7 @@ use protobuf::Message;
 
 use crate::app::dispatcher::Dispatcher;
 use crate::app::nat_manager::NatManager;
+use crate::common::limiter::ConnectionLimiter;
 use crate::config;
 use crate::proxy;
 use crate::proxy::AnyInboundHandler;
@@ -15,6 +16,10 @@ use crate::Runner;
 use crate::proxy::amux;
 #[cfg(feature = "inbound-http")]
 use crate::proxy::http;
+#[cfg(feature = "inbound-mixed")]
+use crate::proxy::mixed;
+#[cfg(feature = "inbound-mux")]
+use crate::proxy::mux;
 #[cfg(feature = "inbound-quic")]
 use crate::proxy::quic;
 #[cfg(feature = "inbound-shadowsocks")]
@@ -25,6 +30,12 @@ use crate::proxy::socks;
 use crate::proxy::tls;
 #[cfg(feature = "inbound-trojan")]
 use crate::proxy::trojan;
+#[cfg(feature = "inbound-tunnel")]
+use crate::proxy::tunnel;
+#[cfg(feature = "inbound-vless")]
+use crate::proxy::vless;
+#[cfg(feature = "inbound-vmess")]
+use crate::proxy::vmess;
 #[cfg(feature = "inbound-ws")]
 use crate::proxy::ws;
 
@@ -33,8 +44,17 @@ use crate::proxy::chain;
 
 use super::network_listener::NetworkInboundListener;
 
+#[cfg(feature = "inbound-dns")]
+use super::dns_listener::DnsInboundListener;
+
+#[cfg(all(feature = "inbound-redirect", target_os = "linux"))]
+use super::redirect_listener::RedirectInboundListener;
+
+#[cfg(all(feature = "inbound-tproxy", target_os = "linux"))]
+use super::tproxy_listener::TproxyInboundListener;
+
 #[cfg(all(
-    feature = "inbound-tun",
+    any(feature = "inbound-tun", feature = "inbound-tun-smoltcp"),
     any(
         target_os = "ios",
         target_os = "android",
@@ -46,8 +66,14 @@ use super::tun_listener::TunInboundListener;
 
 pub struct InboundManager {
     network_listeners: HashMap<String, NetworkInboundListener>,
+    #[cfg(feature = "inbound-dns")]
+    dns_listeners: Vec<DnsInboundListener>,
+    #[cfg(all(feature = "inbound-redirect", target_os = "linux"))]
+    redirect_listeners: Vec<RedirectInboundListener>,
+    #[cfg(all(feature = "inbound-tproxy", target_os = "linux"))]
+    tproxy_listeners: Vec<TproxyInboundListener>,
     #[cfg(all(
-        feature = "inbound-tun",
+        any(feature = "inbound-tun", feature = "inbound-tun-smoltcp"),
         any(
             target_os = "ios",
             target_os = "android",
@@ -72,7 +98,36 @@ impl InboundManager {
             match inbound.protocol.as_str() {
                 #[cfg(feature = "inbound-socks")]
                 "socks" => {
-                    let tcp = Arc::new(socks::inbound::TcpHandler);
+                    let settings =
+                        config::SocksInboundSettings::parse_from_bytes(&inbound.settings)
+                            .map_err(|e| anyhow!("invalid [{}] inbound settings: {}", &tag, e))?;
+                    let users = settings
+                        .users
+                        .iter()
+                        .map(|u| (u.username.clone(), u.password.clone()))
+                        .collect();
+                    let tcp = Arc::new(socks::inbound::TcpHandler::new(users));
+                    let udp = Arc::new(socks::inbound::UdpHandler);
+                    let handler = Arc::new(proxy::inbound::Handler::new(
+                        tag.clone(),
+                        Some(tcp),
+                        Some(udp),
+                    ));
+                    handlers.insert(tag.clone(), handler);
+                }
+                #[cfg(feature = "inbound-mixed")]
+                "mixed" => {
+                    let settings =
+                        config::SocksInboundSettings::parse_from_bytes(&inbound.settings)
+                            .map_err(|e| anyhow!("invalid [{}] inbound settings: {}", &tag, e))?;
+                    let users = settings
+                        .users
+                        .iter()
+                        .map(|u| (u.username.clone(), u.password.clone()))
+                        .collect();
+                    let tcp = Arc::new(mixed::inbound::TcpHandler::new(
+                        socks::inbound::TcpHandler::new(users),
+                    ));
                     let udp = Arc::new(socks::inbound::UdpHandler);
                     let handler = Arc::new(proxy::inbound::Handler::new(
                         tag.clone(),
@@ -93,13 +148,16 @@ impl InboundManager {
                     let settings =
                         config::ShadowsocksInboundSettings::parse_from_bytes(&inbound.settings)
                             .map_err(|e| anyhow!("invalid [{}] inbound settings: {}", &tag, e))?;
+                    let replay_filter = Arc::new(shadowsocks::replay::ReplayFilter::new());
                     let tcp = Arc::new(shadowsocks::inbound::TcpHandler {
                         cipher: settings.method.clone(),
                         password: settings.password.clone(),
+                        replay_filter: replay_filter.clone(),
                     });
                     let udp = Arc::new(shadowsocks::inbound::UdpHandler {
                         cipher: settings.method.clone(),
                         password: settings.password.clone(),
+                        replay_filter,
                     });
                     let handler = Arc::new(proxy::inbound::Handler::new(
                         tag.clone(),
@@ -112,19 +170,75 @@ impl InboundManager {
                 "trojan" => {
                     let settings =
                         config::TrojanInboundSettings::parse_from_bytes(&inbound.settings).unwrap();
+                    let fallback = if settings.fallback.is_empty() {
+                        None
+                    } else {
+                        Some(settings.fallback.clone())
+                    };
                     let tcp = Arc::new(trojan::inbound::TcpHandler::new(
                         settings.passwords.to_vec(),
+                        fallback,
                     ));
                     let handler =
                         Arc::new(proxy::inbound::Handler::new(tag.clone(), Some(tcp), None));
                     handlers.insert(tag.clone(), handler);
                 }
+                #[cfg(feature = "inbound-vless")]
+                "vless" => {
+                    let settings =
+                        config::VLessInboundSettings::parse_from_bytes(&inbound.settings)
+                            .map_err(|e| anyhow!("invalid [{}] inbound settings: {}", &tag, e))?;
+                    let tcp = Arc::new(
+                        vless::inbound::TcpHandler::new(settings.uuids.to_vec())
+                            .map_err(|e| anyhow!("invalid [{}] inbound settings: {}", &tag, e))?,
+                    );
+                    let handler =
+                        Arc::new(proxy::inbound::Handler::new(tag.clone(), Some(tcp), None));
+                    handlers.insert(tag.clone(), handler);
+                }
+                #[cfg(feature = "inbound-vmess")]
+                "vmess" => {
+                    let settings =
+                        config::VMessInboundSettings::parse_from_bytes(&inbound.settings)
+                            .map_err(|e| anyhow!("invalid [{}] inbound settings: {}", &tag, e))?;
+                    let tcp = Arc::new(
+                        vmess::inbound::TcpHandler::new(settings.uuids.to_vec())
+                            .map_err(|e| anyhow!("invalid [{}] inbound settings: {}", &tag, e))?,
+                    );
+                    let handler =
+                        Arc::new(proxy::inbound::Handler::new(tag.clone(), Some(tcp), None));
+                    handlers.insert(tag.clone(), handler);
+                }
+                #[cfg(feature = "inbound-tunnel")]
+                "tunnel" => {
+                    let settings =
+                        config::TunnelInboundSettings::parse_from_bytes(&inbound.settings)
+                            .map_err(|e| anyhow!("invalid [{}] inbound settings: {}", &tag, e))?;
+                    let destination = crate::session::SocksAddr::try_from((
+                        &settings.address,
+                        settings.port as u16,
+                    ))
+                    .map_err(|e| anyhow!("invalid [{}] tunnel destination: {}", &tag, e))?;
+                    let tcp = Arc::new(tunnel::inbound::TcpHandler {
+                        destination: destination.clone(),
+                    });
+                    let udp = Arc::new(tunnel::inbound::UdpHandler { destination });
+                    let handler = Arc::new(proxy::inbound::Handler::new(
+                        tag.clone(),
+                        Some(tcp),
+                        Some(udp),
+                    ));
+                    handlers.insert(tag.clone(), handler);
+                }
                 #[cfg(feature = "inbound-ws")]
                 "ws" => {
                     let settings =
                         config::WebSocketInboundSettings::parse_from_bytes(&inbound.settings)
                             .unwrap();
-                    let tcp = Arc::new(ws::inbound::TcpHandler::new(settings.path.clone()));
+                    let tcp = Arc::new(ws::inbound::TcpHandler::new(
+                        settings.path.clone(),
+                        settings.early_data_header_name.clone(),
+                    ));
                     let handler =
                         Arc::new(proxy::inbound::Handler::new(tag.clone(), Some(tcp), None));
                     handlers.insert(tag.clone(), handler);
@@ -136,6 +250,7 @@ impl InboundManager {
                     let udp = Arc::new(quic::inbound::UdpHandler::new(
                         settings.certificate.clone(),
                         settings.certificate_key.clone(),
+                        settings.zero_rtt,
                     ));
                     let handler =
                         Arc::new(proxy::inbound::Handler::new(tag.clone(), None, Some(udp)));
@@ -182,6 +297,24 @@ impl InboundManager {
                             Arc::new(proxy::inbound::Handler::new(tag.clone(), Some(tcp), None));
                         handlers.insert(tag.clone(), handler);
                     }
+                    #[cfg(feature = "inbound-mux")]
+                    "mux" => {
+                        let mut actors = Vec::new();
+                        let settings =
+                            config::MuxInboundSettings::parse_from_bytes(&inbound.settings)
+                                .map_err(|e| {
+                                    anyhow!("invalid [{}] inbound settings: {}", &tag, e)
+                                })?;
+                        for actor in settings.actors.iter() {
+                            if let Some(a) = handlers.get(actor) {
+                                actors.push(a.clone());
+                            }
+                        }
+                        let tcp = Arc::new(mux::inbound::TcpHandler { actors });
+                        let handler =
+                            Arc::new(proxy::inbound::Handler::new(tag.clone(), Some(tcp), None));
+                        handlers.insert(tag.clone(), handler);
+                    }
                     #[cfg(feature = "inbound-chain")]
                     "chain" => {
                         let settings =
@@ -223,7 +356,7 @@ impl InboundManager {
         let mut network_listeners: HashMap<String, NetworkInboundListener> = HashMap::new();
 
         #[cfg(all(
-            feature = "inbound-tun",
+            any(feature = "inbound-tun", feature = "inbound-tun-smoltcp"),
             any(
                 target_os = "ios",
                 target_os = "android",
@@ -235,11 +368,18 @@ impl InboundManager {
 
         let mut tun_auto = false;
 
+        #[cfg(feature = "inbound-dns")]
+        let mut dns_listeners = Vec::new();
+        #[cfg(all(feature = "inbound-redirect", target_os = "linux"))]
+        let mut redirect_listeners = Vec::new();
+        #[cfg(all(feature = "inbound-tproxy", target_os = "linux"))]
+        let mut tproxy_listeners = Vec::new();
+
         for inbound in inbounds.iter() {
             let tag = String::from(&inbound.tag);
             match inbound.protocol.as_str() {
                 #[cfg(all(
-                    feature = "inbound-tun",
+                    any(feature = "inbound-tun", feature = "inbound-tun-smoltcp"),
                     any(
                         target_os = "ios",
                         target_os = "android",
@@ -258,12 +398,38 @@ impl InboundManager {
                         crate::config::TunInboundSettings::parse_from_bytes(&inbound.settings)?;
                     tun_auto = settings.auto;
                 }
+                #[cfg(feature = "inbound-dns")]
+                "dns" => {
+                    dns_listeners.push(DnsInboundListener {
+                        inbound: inbound.clone(),
+                        dispatcher: dispatcher.clone(),
+                        nat_manager: nat_manager.clone(),
+                    });
+                }
+                #[cfg(all(feature = "inbound-redirect", target_os = "linux"))]
+                "redirect" => {
+                    redirect_listeners.push(RedirectInboundListener {
+                        inbound: inbound.clone(),
+                        dispatcher: dispatcher.clone(),
+                    });
+                }
+                #[cfg(all(feature = "inbound-tproxy", target_os = "linux"))]
+                "tproxy" => {
+                    tproxy_listeners.push(TproxyInboundListener {
+                        inbound: inbound.clone(),
+                        dispatcher: dispatcher.clone(),
+                        nat_manager: nat_manager.clone(),
+                    });
+                }
                 _ => {
                     if inbound.port != 0 {
                         if let Some(h) = handlers.get(&tag) {
                             let listener = NetworkInboundListener {
                                 address: inbound.address.clone(),
                                 port: inbound.port as u16,
+                                proxy_protocol: inbound.proxy_protocol,
+                                tcp_fast_open: inbound.tcp_fast_open,
+                                limiter: Arc::new(ConnectionLimiter::from_inbound(inbound)),
                                 handler: h.clone(),
                                 dispatcher: dispatcher.clone(),
                                 nat_manager: nat_manager.clone(),
@@ -277,8 +443,14 @@ impl InboundManager {
 
         Ok(InboundManager {
             network_listeners,
+            #[cfg(feature = "inbound-dns")]
+            dns_listeners,
+            #[cfg(all(feature = "inbound-redirect", target_os = "linux"))]
+            redirect_listeners,
+            #[cfg(all(feature = "inbound-tproxy", target_os = "linux"))]
+            tproxy_listeners,
             #[cfg(all(
-                feature = "inbound-tun",
+                any(feature = "inbound-tun", feature = "inbound-tun-smoltcp"),
                 any(
                     target_os = "ios",
                     target_os = "android",
@@ -296,11 +468,23 @@ impl InboundManager {
         for (_, listener) in self.network_listeners.iter() {
             runners.append(&mut listener.listen()?);
         }
+        #[cfg(feature = "inbound-dns")]
+        for listener in self.dns_listeners.iter() {
+            runners.push(listener.listen()?);
+        }
+        #[cfg(all(feature = "inbound-redirect", target_os = "linux"))]
+        for listener in self.redirect_listeners.iter() {
+            runners.push(listener.listen()?);
+        }
+        #[cfg(all(feature = "inbound-tproxy", target_os = "linux"))]
+        for listener in self.tproxy_listeners.iter() {
+            runners.push(listener.listen()?);
+        }
         Ok(runners)
     }
 
     #[cfg(all(
-        feature = "inbound-tun",
+        any(feature = "inbound-tun", feature = "inbound-tun-smoltcp"),
         any(
             target_os = "ios",
             target_os = "android",
@@ -316,7 +500,7 @@ impl InboundManager {
     }
 
     #[cfg(all(
-        feature = "inbound-tun",
+        any(feature = "inbound-tun", feature = "inbound-tun-smoltcp"),
         any(
             target_os = "ios",
             target_os = "android",
@@ -1,7 +1,7 @@
 mod network_listener;
 
 #[cfg(all(
-    feature = "inbound-tun",
+    any(feature = "inbound-tun", feature = "inbound-tun-smoltcp"),
     any(
         target_os = "ios",
         target_os = "android",
@@ -11,4 +11,13 @@ mod network_listener;
 ))]
 mod tun_listener;
 
+#[cfg(feature = "inbound-dns")]
+mod dns_listener;
+
+#[cfg(all(feature = "inbound-redirect", target_os = "linux"))]
+mod redirect_listener;
+
+#[cfg(all(feature = "inbound-tproxy", target_os = "linux"))]
+mod tproxy_listener;
+
 pub mod manager;
@@ -11,6 +11,9 @@ use tokio::sync::mpsc::{Receiver as TokioReceiver, Sender as TokioSender};
 
 use crate::app::dispatcher::Dispatcher;
 use crate::app::nat_manager::{NatManager, UdpPacket};
+use crate::common::limiter::{ConnectionLimiter, ConnectionPermit};
+use crate::common::net::ResettableTcpStream;
+use crate::common::proxy_protocol;
 use crate::proxy::*;
 use crate::session::{Network, Session, SocksAddr};
 use crate::Runner;
@@ -67,14 +70,27 @@ async fn handle_inbound_datagram(
 }
 
 async fn handle_inbound_stream(
-    stream: TcpStream,
+    mut stream: TcpStream,
     h: AnyInboundHandler,
     dispatcher: Arc<Dispatcher>,
     nat_manager: Arc<NatManager>,
+    proxy_protocol: bool,
+    _permit: ConnectionPermit,
 ) {
-    let source = stream
+    let mut source = stream
         .peer_addr()
         .unwrap_or_else(|_| *crate::option::UNSPECIFIED_BIND_ADDR);
+    // The real client address is conveyed by the load balancer in front.
+    if proxy_protocol {
+        match proxy_protocol::read_header(&mut stream).await {
+            Ok(Some(addr)) => source = addr,
+            Ok(None) => (),
+            Err(e) => {
+                debug!("read proxy protocol header from {} failed: {}", &source, e);
+                return;
+            }
+        }
+    }
     let local_addr = stream
         .local_addr()
         .unwrap_or_else(|_| *crate::option::UNSPECIFIED_BIND_ADDR);
@@ -86,9 +102,13 @@ async fn handle_inbound_stream(
         ..Default::default()
     };
 
+    let (stream, reset) = ResettableTcpStream::new(stream);
     match TcpInboundHandler::handle(h.as_ref(), sess, Box::new(stream)).await {
         Ok(res) => match res {
-            InboundTransport::Stream(stream, sess) => {
+            // Only a single stream is carried by the connection, it can be
+            // reset for the session.
+            InboundTransport::Stream(stream, mut sess) => {
+                sess.inbound_reset = Some(reset);
                 dispatcher.dispatch_tcp(sess, stream).await;
             }
             InboundTransport::Datagram(socket, sess) => {
@@ -125,6 +145,11 @@ async fn handle_inbound_stream(
 pub struct NetworkInboundListener {
     pub address: String,
     pub port: u16,
+    /// Whether connections start with a PROXY protocol header.
+    pub proxy_protocol: bool,
+    /// Whether the TCP listener accepts TCP Fast Open connections.
+    pub tcp_fast_open: bool,
+    pub limiter: Arc<ConnectionLimiter>,
     pub handler: AnyInboundHandler,
     pub dispatcher: Arc<Dispatcher>,
     pub nat_manager: Arc<NatManager>,
@@ -138,20 +163,34 @@ impl NetworkInboundListener {
         let nat_manager = self.nat_manager.clone();
         let address = self.address.clone();
         let port = self.port;
+        let proxy_protocol = self.proxy_protocol;
+        let tcp_fast_open = self.tcp_fast_open;
+        let limiter = self.limiter.clone();
 
         if self.handler.has_tcp() {
             let listen_addr = SocketAddr::new(address.parse::<IpAddr>()?, port);
             let tcp_task = async move {
-                let listener = TcpListener::bind(&listen_addr).await.unwrap();
+                let listener = TcpListener::bind_with_fast_open(&listen_addr, tcp_fast_open)
+                    .await
+                    .unwrap();
                 info!("inbound listening tcp {}", &listen_addr);
                 loop {
                     match listener.accept().await {
-                        Ok((stream, _)) => {
+                        Ok((stream, source)) => {
+                            let permit = match limiter.try_acquire(source.ip()) {
+                                Some(p) => p,
+                                None => {
+                                    debug!("connection from {} rejected by limits", &source);
+                                    continue;
+                                }
+                            };
                             tokio::spawn(handle_inbound_stream(
                                 stream,
                                 handler.clone(),
                                 dispatcher.clone(),
                                 nat_manager.clone(),
+                                proxy_protocol,
+                                permit,
                             ));
                         }
                         Err(e) => {
@@ -0,0 +1,19 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::app::dispatcher::Dispatcher;
+use crate::config::Inbound;
+use crate::proxy::redirect;
+use crate::Runner;
+
+pub struct RedirectInboundListener {
+    pub inbound: Inbound,
+    pub dispatcher: Arc<Dispatcher>,
+}
+
+impl RedirectInboundListener {
+    pub fn listen(&self) -> Result<Runner> {
+        redirect::inbound::new(self.inbound.clone(), self.dispatcher.clone())
+    }
+}
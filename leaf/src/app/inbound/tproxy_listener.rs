@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::app::dispatcher::Dispatcher;
+use crate::app::nat_manager::NatManager;
+use crate::config::Inbound;
+use crate::proxy::tproxy;
+use crate::Runner;
+
+pub struct TproxyInboundListener {
+    pub inbound: Inbound,
+    pub dispatcher: Arc<Dispatcher>,
+    pub nat_manager: Arc<NatManager>,
+}
+
+impl TproxyInboundListener {
+    pub fn listen(&self) -> Result<Runner> {
+        tproxy::inbound::new(
+            self.inbound.clone(),
+            self.dispatcher.clone(),
+            self.nat_manager.clone(),
+        )
+    }
+}
@@ -1,5 +1,7 @@
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use anyhow::{anyhow, Result};
 use tokio::sync::RwLock;
 
 pub mod dispatcher;
@@ -10,6 +12,9 @@ pub mod nat_manager;
 pub mod outbound;
 pub mod router;
 
+#[cfg(feature = "router-script")]
+pub mod route_script;
+
 #[cfg(feature = "stat")]
 pub mod stat_manager;
 
@@ -24,7 +29,34 @@ pub mod api;
 ))]
 pub mod fake_dns;
 
+#[cfg(any(
+    target_os = "ios",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "linux"
+))]
+pub mod fake_dns_cache;
+
 pub type SyncDnsClient = Arc<RwLock<dns_client::DnsClient>>;
 
 #[cfg(feature = "stat")]
 pub type SyncStatManager = Arc<RwLock<stat_manager::StatManager>>;
+
+/// Returns the path of a cache file in the cache directory, the directory is
+/// created if not exists.
+pub fn get_cache_file_path(name: &str) -> Result<PathBuf> {
+    let cache_loc = if !(&*crate::option::CACHE_LOCATION).is_empty() {
+        Path::new(&*crate::option::CACHE_LOCATION).to_owned()
+    } else {
+        let proj_dirs = if let Some(d) = directories::ProjectDirs::from("com", "github", "leaf") {
+            d
+        } else {
+            return Err(anyhow!("no home directory"));
+        };
+        proj_dirs.cache_dir().to_owned()
+    };
+    if !cache_loc.exists() {
+        std::fs::create_dir_all(&cache_loc)?;
+    }
+    Ok(cache_loc.join(name))
+}
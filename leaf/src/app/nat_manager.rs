@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::task::Poll;
 use std::time::{Duration, Instant};
 
 use futures::future::{abortable, BoxFuture};
@@ -10,6 +11,7 @@ use tokio::sync::{
 };
 
 use crate::app::dispatcher::Dispatcher;
+use crate::common::sniff;
 use crate::option;
 use crate::session::{DatagramSource, Network, Session, SocksAddr};
 
@@ -42,8 +44,15 @@ impl std::fmt::Display for UdpPacket {
     }
 }
 
+// Max time to wait for the next datagram of a QUIC ClientHello spanning
+// several Initial packets.
+const SNIFFING_TIMEOUT: Duration = Duration::from_millis(100);
+
 type SessionMap = HashMap<DatagramSource, (Sender<UdpPacket>, oneshot::Sender<bool>, Instant)>;
 
+/// Relays UDP sessions as a full-cone NAT, every source is mapped to a single
+/// outbound datagram for all of its destinations, and packets from any peer
+/// are passed back to it.
 pub struct NatManager {
     sessions: Arc<Mutex<SessionMap>>,
     dispatcher: Arc<Dispatcher>,
@@ -181,6 +190,66 @@ impl NatManager {
         // because we have stream type transports for UDP traffic, establishing a
         // TCP stream would block the task.
         tokio::spawn(async move {
+            let mut sess = sess;
+            let original_destination = sess.destination.clone();
+            let fake_ip = dispatcher.restore_fake_domain(&mut sess).await;
+            // Packets read for sniffing, they're sent first.
+            let mut sniffed_pkts = Vec::new();
+            if let Some(mut sniffer) = dispatcher.datagram_sniffer(&sess) {
+                // The first packet is queued along with the session, the
+                // session is only held back while a ClientHello is incomplete.
+                let mut deadline = None;
+                loop {
+                    let pkt = match deadline {
+                        None => target_ch_rx.recv().await,
+                        Some(deadline) => {
+                            match tokio::time::timeout_at(deadline, target_ch_rx.recv()).await {
+                                Ok(pkt) => pkt,
+                                Err(_) => break,
+                            }
+                        }
+                    };
+                    let pkt = match pkt {
+                        Some(pkt) => pkt,
+                        None => return,
+                    };
+                    if !sniff::is_quic_initial(&pkt.data) {
+                        sniffed_pkts.push(pkt);
+                        break;
+                    }
+                    let res = sniffer.feed(&pkt.data);
+                    sniffed_pkts.push(pkt);
+                    match res {
+                        Poll::Ready(Some(domain)) => {
+                            debug!("sniffed domain {} for udp session {}", &domain, &raddr);
+                            match dispatcher.apply_sniffed_domain(&mut sess, &domain) {
+                                Ok(()) => sess.sniffed_protocol = Some(sniff::Protocol::Quic),
+                                Err(e) => debug!(
+                                    "convert sniffed domain {} to destination failed: {}",
+                                    &domain, e
+                                ),
+                            }
+                            break;
+                        }
+                        Poll::Ready(None) => break,
+                        Poll::Pending => {
+                            deadline.get_or_insert_with(|| {
+                                tokio::time::Instant::now() + SNIFFING_TIMEOUT
+                            });
+                        }
+                    }
+                }
+            }
+            // Datagrams to the fake or sniffed address are sent to the domain
+            // if it replaces the destination, and replies from the domain are
+            // passed back as from the address the client sent to.
+            let rewritten = if sess.destination != original_destination {
+                Some((original_destination, sess.destination.clone()))
+            } else {
+                None
+            };
+            let rewritten2 = rewritten.clone();
+
             // new socket to communicate with the target.
             let socket = match dispatcher.dispatch_udp(sess).await {
                 Ok(s) => s,
@@ -206,6 +275,10 @@ impl NatManager {
                             break;
                         }
                         Ok((n, addr)) => {
+                            let addr = match &rewritten2 {
+                                Some((ip, domain)) if &addr == domain => ip.clone(),
+                                _ => addr,
+                            };
                             let pkt = UdpPacket::new(
                                 (&buf[..n]).to_vec(),
                                 addr.clone(),
@@ -252,11 +325,28 @@ impl NatManager {
 
             // uplink
             tokio::spawn(async move {
+                // The fake IP is held as long as the session sends.
+                let _fake_ip = fake_ip;
+                let target = |dst: &SocksAddr| match &rewritten {
+                    Some((ip, domain)) if dst == ip => domain.clone(),
+                    _ => dst.clone(),
+                };
+                for pkt in sniffed_pkts {
+                    let dst = target(&pkt.dst_addr);
+                    if let Err(e) = target_sock_send.send_to(&pkt.data, &dst).await {
+                        debug!(
+                            "Failed to send uplink packets on session {} to {}: {:?}",
+                            &raddr, &dst, e
+                        );
+                        return;
+                    }
+                }
                 while let Some(pkt) = target_ch_rx.recv().await {
-                    if let Err(e) = target_sock_send.send_to(&pkt.data, &pkt.dst_addr).await {
+                    let dst = target(&pkt.dst_addr);
+                    if let Err(e) = target_sock_send.send_to(&pkt.data, &dst).await {
                         debug!(
                             "Failed to send uplink packets on session {} to {}: {:?}",
-                            &raddr, &pkt.dst_addr, e
+                            &raddr, &dst, e
                         );
                         break;
                     }
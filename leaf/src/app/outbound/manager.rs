@@ -2,6 +2,7 @@ use std::{
     collections::{hash_map, HashMap},
     convert::From,
     sync::Arc,
+    time::Duration,
 };
 
 use anyhow::{anyhow, Result};
@@ -12,16 +13,23 @@ use tokio::sync::RwLock;
 
 use crate::proxy::null;
 
+#[cfg(any(feature = "outbound-vless", feature = "outbound-vmess"))]
+use crate::common::uuid::parse_uuid;
+
 #[cfg(feature = "outbound-chain")]
 use crate::proxy::chain;
 #[cfg(feature = "outbound-failover")]
 use crate::proxy::failover;
 #[cfg(feature = "outbound-static")]
 use crate::proxy::r#static;
+#[cfg(feature = "outbound-retry")]
+use crate::proxy::retry;
 #[cfg(feature = "outbound-select")]
 use crate::proxy::select;
 #[cfg(feature = "outbound-tryall")]
 use crate::proxy::tryall;
+#[cfg(feature = "outbound-urltest")]
+use crate::proxy::urltest;
 
 #[cfg(feature = "outbound-amux")]
 use crate::proxy::amux;
@@ -29,40 +37,257 @@ use crate::proxy::amux;
 use crate::proxy::direct;
 #[cfg(feature = "outbound-drop")]
 use crate::proxy::drop;
+#[cfg(feature = "outbound-grpc")]
+use crate::proxy::grpc;
+#[cfg(feature = "outbound-http2")]
+use crate::proxy::http2;
+#[cfg(feature = "outbound-mux")]
+use crate::proxy::mux;
+#[cfg(feature = "outbound-naive")]
+use crate::proxy::naive;
+#[cfg(feature = "outbound-obfs")]
+use crate::proxy::obfs;
 #[cfg(feature = "outbound-quic")]
 use crate::proxy::quic;
+#[cfg(feature = "outbound-reality")]
+use crate::proxy::reality;
 #[cfg(feature = "outbound-redirect")]
 use crate::proxy::redirect;
 #[cfg(feature = "outbound-shadowsocks")]
 use crate::proxy::shadowsocks;
 #[cfg(feature = "outbound-socks")]
 use crate::proxy::socks;
+#[cfg(feature = "outbound-ssh")]
+use crate::proxy::ssh;
 #[cfg(feature = "outbound-tls")]
 use crate::proxy::tls;
 #[cfg(feature = "outbound-trojan")]
 use crate::proxy::trojan;
+#[cfg(feature = "outbound-vless")]
+use crate::proxy::vless;
 #[cfg(feature = "outbound-vmess")]
 use crate::proxy::vmess;
 #[cfg(feature = "outbound-ws")]
 use crate::proxy::ws;
 
 use crate::{
-    app::SyncDnsClient,
+    app::{dns_client::DomainStrategy, SyncDnsClient},
     config::{self, Outbound},
     proxy::{self, outbound::HandlerBuilder, *},
 };
 
 use super::selector::OutboundSelector;
+#[cfg(feature = "outbound-failover")]
+use super::urltest::HttpProbe;
+#[cfg(any(feature = "outbound-static", feature = "outbound-urltest"))]
+use super::urltest::UrlTester;
+
+// Protocols of the outbounds composed of other outbounds.
+#[cfg(any(
+    feature = "outbound-select",
+    feature = "outbound-failover",
+    feature = "outbound-urltest"
+))]
+const GROUP_PROTOCOLS: [&str; 6] = ["select", "failover", "urltest", "static", "tryall", "retry"];
 
 pub struct OutboundManager {
     handlers: HashMap<String, AnyOutboundHandler>,
     external_handlers: super::plugin::ExternalHandlers,
     selectors: Arc<super::Selectors>,
+    health_reports: Arc<super::HealthReports>,
     default_handler: Option<String>,
     abort_handles: Vec<AbortHandle>,
 }
 
 impl OutboundManager {
+    fn socket_bind(outbound: &Outbound) -> Result<Option<SocketBind>> {
+        if outbound.bind_interface.is_empty() && outbound.bind_addresses.is_empty() {
+            return Ok(None);
+        }
+        let mut addresses = Vec::new();
+        for addr in outbound.bind_addresses.iter() {
+            addresses.push(addr.parse().map_err(|e| {
+                anyhow!("invalid [{}] bind address {}: {}", &outbound.tag, addr, e)
+            })?);
+        }
+        Ok(Some(SocketBind {
+            interface: Some(outbound.bind_interface.clone()).filter(|v| !v.is_empty()),
+            addresses,
+        }))
+    }
+
+    fn domain_strategy(outbound: &Outbound) -> DomainStrategy {
+        match outbound.domain_strategy {
+            config::Outbound_DomainStrategy::AS_IS => DomainStrategy::AsIs,
+            config::Outbound_DomainStrategy::PREFER_IPV4 => DomainStrategy::PreferIpv4,
+            config::Outbound_DomainStrategy::PREFER_IPV6 => DomainStrategy::PreferIpv6,
+            config::Outbound_DomainStrategy::IPV4_ONLY => DomainStrategy::Ipv4Only,
+            config::Outbound_DomainStrategy::IPV6_ONLY => DomainStrategy::Ipv6Only,
+        }
+    }
+
+    // The options applied to the connections handlers of the outbound dial
+    // themselves, instead of through another handler.
+    fn dial_options(outbound: &Outbound) -> Result<DialOptions> {
+        Ok(DialOptions {
+            bind: Self::socket_bind(outbound)?,
+            domain_strategy: Self::domain_strategy(outbound),
+            tcp_fast_open: outbound.tcp_fast_open,
+            connect_timeout: Self::timeout(outbound.connect_timeout),
+        })
+    }
+
+    // A builder of the handler of the outbound, with its tag and options.
+    fn handler_builder(outbound: &Outbound) -> Result<HandlerBuilder> {
+        let opts = Self::dial_options(outbound)?;
+        Ok(HandlerBuilder::default()
+            .tag(outbound.tag.clone())
+            .bind(opts.bind)
+            .domain_strategy(opts.domain_strategy)
+            .tcp_fast_open(opts.tcp_fast_open)
+            .connect_timeout(opts.connect_timeout)
+            .handshake_timeout(Self::timeout(outbound.handshake_timeout)))
+    }
+
+    // Timeouts in seconds, 0 means the default.
+    fn timeout(secs: u32) -> Option<Duration> {
+        Some(secs)
+            .filter(|v| *v > 0)
+            .map(|v| Duration::from_secs(v.into()))
+    }
+
+    // Returns the tags of the actors of a group, the listed ones followed by
+    // the other outbounds with tags matching the filter. Groups and parts of
+    // chains are never matched.
+    #[cfg(any(
+        feature = "outbound-select",
+        feature = "outbound-failover",
+        feature = "outbound-urltest"
+    ))]
+    fn group_actors(
+        outbounds: &protobuf::RepeatedField<Outbound>,
+        tag: &str,
+        actors: &[String],
+        filter: &str,
+    ) -> Result<Vec<String>> {
+        let mut tags = actors.to_vec();
+        if filter.is_empty() {
+            return Ok(tags);
+        }
+        let re = regex::Regex::new(filter)
+            .map_err(|e| anyhow!("invalid [{}] outbound filter: {}", tag, e))?;
+        let mut chained = std::collections::HashSet::new();
+        for outbound in outbounds.iter().filter(|x| x.protocol == "chain") {
+            if let Ok(settings) =
+                config::ChainOutboundSettings::parse_from_bytes(&outbound.settings)
+            {
+                chained.extend(settings.actors.into_iter());
+            }
+        }
+        for outbound in outbounds.iter() {
+            if outbound.tag == tag
+                || GROUP_PROTOCOLS.contains(&outbound.protocol.as_str())
+                || chained.contains(&outbound.tag)
+                || tags.contains(&outbound.tag)
+            {
+                continue;
+            }
+            if re.is_match(&outbound.tag) {
+                tags.push(outbound.tag.clone());
+            }
+        }
+        Ok(tags)
+    }
+
+    // Returns the tags of the outbounds an outbound depends on.
+    fn outbound_deps(outbound: &Outbound) -> Vec<String> {
+        let s = &outbound.settings;
+        match outbound.protocol.as_str() {
+            "tryall" => config::TryAllOutboundSettings::parse_from_bytes(s)
+                .map(|x| x.actors.into_vec())
+                .unwrap_or_default(),
+            "retry" => config::RetryOutboundSettings::parse_from_bytes(s)
+                .map(|x| x.actors.into_vec())
+                .unwrap_or_default(),
+            "static" => config::StaticOutboundSettings::parse_from_bytes(s)
+                .map(|x| x.actors.into_vec())
+                .unwrap_or_default(),
+            "amux" => config::AMuxOutboundSettings::parse_from_bytes(s)
+                .map(|x| x.actors.into_vec())
+                .unwrap_or_default(),
+            "mux" => config::MuxOutboundSettings::parse_from_bytes(s)
+                .map(|x| x.actors.into_vec())
+                .unwrap_or_default(),
+            "grpc" => config::GrpcOutboundSettings::parse_from_bytes(s)
+                .map(|x| x.actors.into_vec())
+                .unwrap_or_default(),
+            "h2" => config::Http2OutboundSettings::parse_from_bytes(s)
+                .map(|x| x.actors.into_vec())
+                .unwrap_or_default(),
+            "chain" => config::ChainOutboundSettings::parse_from_bytes(s)
+                .map(|x| x.actors.into_vec())
+                .unwrap_or_default(),
+            "failover" => config::FailOverOutboundSettings::parse_from_bytes(s)
+                .map(|x| {
+                    let mut deps = x.actors.into_vec();
+                    if !x.last_resort.is_empty() {
+                        deps.push(x.last_resort);
+                    }
+                    deps
+                })
+                .unwrap_or_default(),
+            "urltest" => config::UrlTestOutboundSettings::parse_from_bytes(s)
+                .map(|x| x.actors.into_vec())
+                .unwrap_or_default(),
+            "select" => config::SelectOutboundSettings::parse_from_bytes(s)
+                .map(|x| x.actors.into_vec())
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
+    // Returns an error if some outbounds depend on each other, e.g. groups
+    // containing each other, which could never be loaded.
+    fn check_cycles(outbounds: &protobuf::RepeatedField<Outbound>) -> Result<()> {
+        let deps: HashMap<&str, Vec<String>> = outbounds
+            .iter()
+            .map(|x| (x.tag.as_str(), Self::outbound_deps(x)))
+            .collect();
+        // Outbounds without cycles in their dependencies.
+        let mut done = std::collections::HashSet::new();
+        for outbound in outbounds.iter() {
+            // Depth-first search with the path to the outbound being visited
+            // and the index of the next dependency to visit.
+            let mut path: Vec<(&str, usize)> = vec![(outbound.tag.as_str(), 0)];
+            while let Some((tag, i)) = path.last_mut() {
+                let next = deps.get(*tag).and_then(|x| x.get(*i));
+                *i += 1;
+                let next = match next {
+                    Some(next) => next.as_str(),
+                    None => {
+                        done.insert(*tag);
+                        path.pop();
+                        continue;
+                    }
+                };
+                if done.contains(next) {
+                    continue;
+                }
+                if let Some(start) = path.iter().position(|(x, _)| *x == next) {
+                    let mut cycle: Vec<&str> = path[start..].iter().map(|(x, _)| *x).collect();
+                    cycle.push(next);
+                    return Err(anyhow!(
+                        "outbound [{}] depends on itself: {}",
+                        next,
+                        cycle.join(" -> ")
+                    ));
+                }
+                path.push((next, 0));
+            }
+        }
+        Ok(())
+    }
+
     #[allow(clippy::type_complexity)]
     fn load_handlers(
         outbounds: &protobuf::RepeatedField<Outbound>,
@@ -71,6 +296,7 @@ impl OutboundManager {
         external_handlers: &mut super::plugin::ExternalHandlers,
         default_handler: &mut Option<String>,
         abort_handles: &mut Vec<AbortHandle>,
+        health_reports: &mut super::HealthReports,
     ) -> Result<()> {
         for outbound in outbounds.iter() {
             let tag = String::from(&outbound.tag);
@@ -86,8 +312,7 @@ impl OutboundManager {
                 "direct" => {
                     handlers.insert(
                         tag.clone(),
-                        HandlerBuilder::default()
-                            .tag(tag.clone())
+                        Self::handler_builder(outbound)?
                             .color(colored::Color::Green)
                             .tcp_handler(Box::new(direct::TcpHandler))
                             .udp_handler(Box::new(direct::UdpHandler))
@@ -97,13 +322,26 @@ impl OutboundManager {
                 }
                 #[cfg(feature = "outbound-drop")]
                 "drop" => {
+                    let settings = if outbound.settings.is_empty() {
+                        config::DropOutboundSettings::new()
+                    } else {
+                        config::DropOutboundSettings::parse_from_bytes(&outbound.settings)
+                            .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?
+                    };
+                    let tcp = Box::new(drop::TcpHandler {
+                        mode: drop::tcp::Mode::new(&settings.tcp)
+                            .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?,
+                    });
+                    let udp = Box::new(drop::UdpHandler {
+                        mode: drop::udp::Mode::new(&settings.udp)
+                            .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?,
+                    });
                     handlers.insert(
                         tag.clone(),
-                        HandlerBuilder::default()
-                            .tag(tag.clone())
+                        Self::handler_builder(outbound)?
                             .color(colored::Color::Red)
-                            .tcp_handler(Box::new(drop::TcpHandler))
-                            .udp_handler(Box::new(drop::UdpHandler))
+                            .tcp_handler(tcp)
+                            .udp_handler(udp)
                             .build(),
                     );
                     trace!("added handler [{}]", &tag);
@@ -121,8 +359,7 @@ impl OutboundManager {
                         address: settings.address,
                         port: settings.port as u16,
                     });
-                    let handler = HandlerBuilder::default()
-                        .tag(tag.clone())
+                    let handler = Self::handler_builder(outbound)?
                         .tcp_handler(tcp)
                         .udp_handler(udp)
                         .build();
@@ -142,9 +379,9 @@ impl OutboundManager {
                         address: settings.address.clone(),
                         port: settings.port as u16,
                         dns_client: dns_client.clone(),
+                        dial_options: Self::dial_options(outbound)?,
                     });
-                    let handler = HandlerBuilder::default()
-                        .tag(tag.clone())
+                    let handler = Self::handler_builder(outbound)?
                         .tcp_handler(tcp)
                         .udp_handler(udp)
                         .build();
@@ -156,20 +393,35 @@ impl OutboundManager {
                     let settings =
                         config::ShadowsocksOutboundSettings::parse_from_bytes(&outbound.settings)
                             .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?;
+                    let plugin = if !settings.plugin.is_empty() {
+                        Some(Arc::new(
+                            shadowsocks::outbound::plugin::Plugin::start(
+                                &settings.plugin,
+                                &settings.plugin_opts,
+                                &settings.address,
+                                settings.port as u16,
+                            )
+                            .map_err(|e| anyhow!("start [{}] plugin failed: {}", &tag, e))?,
+                        ))
+                    } else {
+                        None
+                    };
                     let tcp = Box::new(shadowsocks::outbound::TcpHandler {
                         address: settings.address.clone(),
                         port: settings.port as u16,
                         cipher: settings.method.clone(),
                         password: settings.password.clone(),
+                        plugin: plugin.clone(),
                     });
                     let udp = Box::new(shadowsocks::outbound::UdpHandler {
                         address: settings.address,
                         port: settings.port as u16,
                         cipher: settings.method,
                         password: settings.password,
+                        plugin,
+                        udp_over_tcp: settings.udp_over_tcp,
                     });
-                    let handler = HandlerBuilder::default()
-                        .tag(tag.clone())
+                    let handler = Self::handler_builder(outbound)?
                         .tcp_handler(tcp)
                         .udp_handler(udp)
                         .build();
@@ -191,33 +443,118 @@ impl OutboundManager {
                         port: settings.port as u16,
                         password: settings.password,
                     });
-                    let handler = HandlerBuilder::default()
-                        .tag(tag.clone())
+                    let handler = Self::handler_builder(outbound)?
                         .tcp_handler(tcp)
                         .udp_handler(udp)
                         .build();
                     handlers.insert(tag.clone(), handler);
                     trace!("added handler [{}]", &tag);
                 }
-                #[cfg(feature = "outbound-vmess")]
-                "vmess" => {
+                #[cfg(feature = "outbound-vless")]
+                "vless" => {
                     let settings =
-                        config::VMessOutboundSettings::parse_from_bytes(&outbound.settings)
+                        config::VLessOutboundSettings::parse_from_bytes(&outbound.settings)
                             .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?;
-                    let tcp = Box::new(vmess::TcpHandler {
+                    let id = parse_uuid(&settings.uuid)
+                        .ok_or_else(|| anyhow!("invalid [{}] uuid: {}", &tag, &settings.uuid))?;
+                    let tcp = Box::new(vless::outbound::TcpHandler {
                         address: settings.address.clone(),
                         port: settings.port as u16,
-                        uuid: settings.uuid.clone(),
-                        security: settings.security.clone(),
+                        id,
                     });
-                    let udp = Box::new(vmess::UdpHandler {
-                        address: settings.address.clone(),
+                    let udp = Box::new(vless::outbound::UdpHandler {
+                        address: settings.address,
                         port: settings.port as u16,
-                        uuid: settings.uuid.clone(),
-                        security: settings.security.clone(),
+                        id,
                     });
-                    let handler = HandlerBuilder::default()
-                        .tag(tag.clone())
+                    let handler = Self::handler_builder(outbound)?
+                        .tcp_handler(tcp)
+                        .udp_handler(udp)
+                        .build();
+                    handlers.insert(tag.clone(), handler);
+                    trace!("added handler [{}]", &tag);
+                }
+                #[cfg(feature = "outbound-ssh")]
+                "ssh" => {
+                    let settings =
+                        config::SshOutboundSettings::parse_from_bytes(&outbound.settings)
+                            .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?;
+                    let tcp = Box::new(
+                        ssh::outbound::TcpHandler::new(
+                            settings.address,
+                            settings.port as u16,
+                            settings.username,
+                            settings.password,
+                            settings.private_key,
+                            settings.host_key,
+                            settings.insecure_skip_host_key,
+                            dns_client.clone(),
+                            Self::dial_options(outbound)?,
+                        )
+                        .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?,
+                    );
+                    let udp = Box::new(null::outbound::UdpHandler {
+                        connect: None,
+                        transport_type: DatagramTransportType::Undefined,
+                    });
+                    let handler = Self::handler_builder(outbound)?
+                        .tcp_handler(tcp)
+                        .udp_handler(udp)
+                        .build();
+                    handlers.insert(tag.clone(), handler);
+                    trace!("added handler [{}]", &tag);
+                }
+                #[cfg(feature = "outbound-naive")]
+                "naive" => {
+                    let settings =
+                        config::NaiveOutboundSettings::parse_from_bytes(&outbound.settings)
+                            .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?;
+                    let tcp = Box::new(
+                        naive::outbound::TcpHandler::new(
+                            settings.address,
+                            settings.port as u16,
+                            settings.username,
+                            settings.password,
+                            dns_client.clone(),
+                            Self::dial_options(outbound)?,
+                        )
+                        .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?,
+                    );
+                    let udp = Box::new(null::outbound::UdpHandler {
+                        connect: None,
+                        transport_type: DatagramTransportType::Undefined,
+                    });
+                    let handler = Self::handler_builder(outbound)?
+                        .tcp_handler(tcp)
+                        .udp_handler(udp)
+                        .build();
+                    handlers.insert(tag.clone(), handler);
+                    trace!("added handler [{}]", &tag);
+                }
+                #[cfg(feature = "outbound-vmess")]
+                "vmess" => {
+                    let settings =
+                        config::VMessOutboundSettings::parse_from_bytes(&outbound.settings)
+                            .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?;
+                    let id = parse_uuid(&settings.uuid)
+                        .ok_or_else(|| anyhow!("invalid [{}] uuid: {}", &tag, &settings.uuid))?;
+                    let security = settings
+                        .security
+                        .parse::<vmess::Security>()
+                        .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?;
+                    let tcp = Box::new(vmess::outbound::TcpHandler::new(
+                        settings.address.clone(),
+                        settings.port as u16,
+                        id,
+                        security,
+                    ));
+                    let udp = Box::new(vmess::outbound::UdpHandler::new(
+                        settings.address,
+                        settings.port as u16,
+                        id,
+                        security,
+                    ));
+                    let handler = Self::handler_builder(outbound)?
                         .tcp_handler(tcp)
                         .udp_handler(udp)
                         .build();
@@ -238,17 +575,94 @@ impl OutboundManager {
                     } else {
                         Some(settings.certificate.clone())
                     };
-                    let tcp = Box::new(tls::outbound::TcpHandler::new(
+                    let client_certificate = match (
+                        settings.client_certificate.is_empty(),
+                        settings.client_certificate_key.is_empty(),
+                    ) {
+                        (true, true) => None,
+                        (false, false) => Some((
+                            settings.client_certificate.clone(),
+                            settings.client_certificate_key.clone(),
+                        )),
+                        _ => {
+                            return Err(anyhow!(
+                                "[{}] client certificate and key must be set together",
+                                &tag
+                            ));
+                        }
+                    };
+                    #[cfg(feature = "outbound-ech")]
+                    if client_certificate.is_some()
+                        && (settings.ech || !settings.ech_config.is_empty())
+                    {
+                        return Err(anyhow!(
+                            "[{}] client certificates aren't supported with ech",
+                            &tag
+                        ));
+                    }
+                    let mut pins = Vec::new();
+                    for pin in settings.pinned_peer_certificate_chain_sha256.iter() {
+                        match base64::decode(pin) {
+                            Ok(hash) if hash.len() == 32 => pins.push(hash),
+                            _ => {
+                                return Err(anyhow!(
+                                    "invalid [{}] pinned certificate hash {}",
+                                    &tag,
+                                    pin
+                                ));
+                            }
+                        }
+                    }
+                    let tcp = tls::outbound::TcpHandler::new(
                         settings.server_name.clone(),
                         alpns.clone(),
                         certificate,
+                        client_certificate,
+                    )?
+                    .with_pinned_certificates(pins);
+                    #[cfg(feature = "outbound-ech")]
+                    let tcp = if !settings.ech_config.is_empty() {
+                        let config_list = base64::decode(&settings.ech_config)
+                            .map_err(|e| anyhow!("invalid [{}] ech config: {}", &tag, e))?;
+                        let config = tls::outbound::EchConfig::select(&config_list)?;
+                        tcp.with_ech(tls::outbound::Ech::Config(config))
+                    } else if settings.ech {
+                        tcp.with_ech(tls::outbound::Ech::Dns(dns_client.clone()))
+                    } else {
+                        tcp
+                    };
+                    #[cfg(not(feature = "outbound-ech"))]
+                    if settings.ech || !settings.ech_config.is_empty() {
+                        return Err(anyhow!("[{}] ech isn't supported in this build", &tag));
+                    }
+                    let tcp = Box::new(tcp);
+                    let udp = Box::new(null::outbound::UdpHandler {
+                        connect: None,
+                        transport_type: proxy::DatagramTransportType::Stream,
+                    });
+                    let handler = Self::handler_builder(outbound)?
+                        .tcp_handler(tcp)
+                        .udp_handler(udp)
+                        .build();
+                    handlers.insert(tag.clone(), handler);
+                    trace!("added handler [{}]", &tag);
+                }
+                #[cfg(feature = "outbound-reality")]
+                "reality" => {
+                    let settings =
+                        config::RealityOutboundSettings::parse_from_bytes(&outbound.settings)
+                            .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?;
+                    let tcp = Box::new(reality::outbound::TcpHandler::new(
+                        settings.server_name.clone(),
+                        &settings.public_key,
+                        &settings.short_id,
+                        settings.alpn.to_vec(),
                     )?);
                     let udp = Box::new(null::outbound::UdpHandler {
                         connect: None,
                         transport_type: proxy::DatagramTransportType::Stream,
                     });
-                    let handler = HandlerBuilder::default()
-                        .tag(tag.clone())
+                    let handler = Self::handler_builder(outbound)?
                         .tcp_handler(tcp)
                         .udp_handler(udp)
                         .build();
@@ -263,13 +677,39 @@ impl OutboundManager {
                     let tcp = Box::new(ws::outbound::TcpHandler {
                         path: settings.path.clone(),
                         headers: settings.headers.clone(),
+                        max_early_data: settings.max_early_data as usize,
+                        early_data_header_name: settings.early_data_header_name.clone(),
                     });
                     let udp = Box::new(null::outbound::UdpHandler {
                         connect: None,
                         transport_type: proxy::DatagramTransportType::Stream,
                     });
-                    let handler = HandlerBuilder::default()
-                        .tag(tag.clone())
+                    let handler = Self::handler_builder(outbound)?
+                        .tcp_handler(tcp)
+                        .udp_handler(udp)
+                        .build();
+                    handlers.insert(tag.clone(), handler);
+                    trace!("added handler [{}]", &tag);
+                }
+                #[cfg(feature = "outbound-obfs")]
+                "obfs" => {
+                    let settings =
+                        config::ObfsOutboundSettings::parse_from_bytes(&outbound.settings)
+                            .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?;
+                    if settings.method != "http" && settings.method != "tls" {
+                        return Err(anyhow!(
+                            "invalid [{}] outbound settings: unknown obfs method {}",
+                            &tag,
+                            &settings.method
+                        ));
+                    }
+                    let tcp = Box::new(obfs::outbound::TcpHandler {
+                        method: settings.method,
+                        host: settings.host,
+                        path: settings.path,
+                    });
+                    let udp = Box::new(obfs::outbound::UdpHandler);
+                    let handler = Self::handler_builder(outbound)?
                         .tcp_handler(tcp)
                         .udp_handler(udp)
                         .build();
@@ -297,41 +737,19 @@ impl OutboundManager {
                         server_name,
                         certificate,
                         dns_client.clone(),
+                        Self::dial_options(outbound)?,
                     ));
                     let udp = Box::new(null::outbound::UdpHandler {
                         connect: Some(OutboundConnect::NoConnect),
                         transport_type: DatagramTransportType::Stream,
                     });
-                    let handler = HandlerBuilder::default()
-                        .tag(tag.clone())
+                    let handler = Self::handler_builder(outbound)?
                         .tcp_handler(tcp)
                         .udp_handler(udp)
                         .build();
                     handlers.insert(tag.clone(), handler);
                     trace!("added handler [{}]", &tag);
                 }
-                #[cfg(feature = "outbound-h2")]
-                "h2" => {
-                    let settings =
-                        config::HTTP2OutboundSettings::parse_from_bytes(&outbound.settings)
-                            .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?;
-                    let tcp = Box::new(crate::proxy::h2::TcpHandler {
-                        path: settings.path.clone(),
-                        host: settings.host.clone(),
-                    });
-                    let handler = proxy::outbound::Handler::new(
-                        tag.clone(),
-                        colored::Color::TrueColor {
-                            r: 252,
-                            g: 107,
-                            b: 3,
-                        },
-                        Some(tcp),
-                        None,
-                    );
-                    trace!("add handler [{}]", &tag);
-                    handlers.insert(tag.clone(), handler);
-                }
                 _ => continue,
             }
         }
@@ -344,6 +762,56 @@ impl OutboundManager {
                     continue;
                 }
                 match outbound.protocol.as_str() {
+                    #[cfg(feature = "outbound-retry")]
+                    "retry" => {
+                        let settings =
+                            config::RetryOutboundSettings::parse_from_bytes(&outbound.settings)
+                                .map_err(|e| {
+                                    anyhow!("invalid [{}] outbound settings: {}", &tag, e)
+                                })?;
+                        let mut actors = Vec::new();
+                        for actor in settings.actors.iter() {
+                            if let Some(a) = handlers.get(actor) {
+                                actors.push(a.clone());
+                            } else {
+                                continue 'outbounds;
+                            }
+                        }
+                        if actors.is_empty() {
+                            continue;
+                        }
+                        let retry = Arc::new(retry::Retry {
+                            attempts: settings.attempts,
+                            backoff_base: Duration::from_millis(settings.backoff_base as u64),
+                            max_backoff: Duration::from_millis(if settings.max_backoff > 0 {
+                                settings.max_backoff as u64
+                            } else {
+                                10000
+                            }),
+                            jitter: settings.jitter,
+                            rotate: settings.rotate,
+                        });
+                        let tcp = Box::new(retry::TcpHandler {
+                            actors: actors.clone(),
+                            retry: retry.clone(),
+                            dns_client: dns_client.clone(),
+                        });
+                        let udp = Box::new(retry::UdpHandler {
+                            actors,
+                            retry,
+                            dns_client: dns_client.clone(),
+                        });
+                        let handler = Self::handler_builder(outbound)?
+                            .tcp_handler(tcp)
+                            .udp_handler(udp)
+                            .build();
+                        handlers.insert(tag.clone(), handler);
+                        trace!(
+                            "added handler [{}] with actors: {}",
+                            &tag,
+                            settings.actors.join(",")
+                        );
+                    }
                     #[cfg(feature = "outbound-tryall")]
                     "tryall" => {
                         let settings =
@@ -365,15 +833,16 @@ impl OutboundManager {
                         let tcp = Box::new(tryall::TcpHandler {
                             actors: actors.clone(),
                             delay_base: settings.delay_base,
+                            attempt_timeout: Self::timeout(settings.attempt_timeout),
                             dns_client: dns_client.clone(),
                         });
                         let udp = Box::new(tryall::UdpHandler {
                             actors,
                             delay_base: settings.delay_base,
+                            attempt_timeout: Self::timeout(settings.attempt_timeout),
                             dns_client: dns_client.clone(),
                         });
-                        let handler = HandlerBuilder::default()
-                            .tag(tag.clone())
+                        let handler = Self::handler_builder(outbound)?
                             .tcp_handler(tcp)
                             .udp_handler(udp)
                             .build();
@@ -402,18 +871,45 @@ impl OutboundManager {
                         if actors.is_empty() {
                             continue;
                         }
+                        let tester = if settings.method == "least-rtt" {
+                            let url = if settings.url.is_empty() {
+                                super::urltest::DEFAULT_URL
+                            } else {
+                                &settings.url
+                            };
+                            let (tester, abort_handle) = UrlTester::new(
+                                tag.clone(),
+                                actors.clone(),
+                                url,
+                                Self::timeout(settings.interval)
+                                    .unwrap_or_else(|| Duration::from_secs(300)),
+                                0,
+                                Self::timeout(settings.timeout)
+                                    .unwrap_or_else(|| Duration::from_secs(5)),
+                                dns_client.clone(),
+                            )
+                            .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?;
+                            abort_handles.push(abort_handle);
+                            health_reports.insert(tag.clone(), tester.clone());
+                            Some(tester)
+                        } else {
+                            None
+                        };
+                        let balancer = Arc::new(
+                            r#static::Balancer::new(
+                                actors,
+                                &settings.method,
+                                &settings.weights,
+                                tester,
+                            )
+                            .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?,
+                        );
                         let tcp = Box::new(r#static::TcpHandler::new(
-                            actors.clone(),
-                            dns_client.clone(),
-                            &settings.method,
-                        )?);
-                        let udp = Box::new(r#static::UdpHandler::new(
-                            actors,
+                            balancer.clone(),
                             dns_client.clone(),
-                            &settings.method,
-                        )?);
-                        let handler = HandlerBuilder::default()
-                            .tag(tag.clone())
+                        ));
+                        let udp = Box::new(r#static::UdpHandler::new(balancer, dns_client.clone()));
+                        let handler = Self::handler_builder(outbound)?
                             .tcp_handler(tcp)
                             .udp_handler(udp)
                             .build();
@@ -431,8 +927,14 @@ impl OutboundManager {
                                 .map_err(|e| {
                                     anyhow!("invalid [{}] outbound settings: {}", &tag, e)
                                 })?;
+                        let tags = Self::group_actors(
+                            outbounds,
+                            &tag,
+                            &settings.actors,
+                            &settings.filter,
+                        )?;
                         let mut actors = Vec::new();
-                        for actor in settings.actors.iter() {
+                        for actor in tags.iter() {
                             if let Some(a) = handlers.get(actor) {
                                 actors.push(a.clone());
                             } else {
@@ -451,6 +953,30 @@ impl OutboundManager {
                                 None
                             }
                         };
+                        let size = actors.len();
+                        let ejector = || {
+                            failover::Ejector::new(
+                                size,
+                                settings.eject_threshold,
+                                Duration::from_secs(settings.eject_duration.into()),
+                                Duration::from_secs(settings.max_eject_duration.into()),
+                            )
+                        };
+                        let probe = Arc::new(
+                            HttpProbe::new(if settings.health_check_url.is_empty() {
+                                super::urltest::DEFAULT_URL
+                            } else {
+                                &settings.health_check_url
+                            })
+                            .map_err(|e| anyhow!("invalid [{}] health check url: {}", &tag, e))?,
+                        );
+                        let udp_server = if settings.udp_health_check_server.is_empty() {
+                            "8.8.8.8:53".parse().unwrap()
+                        } else {
+                            settings.udp_health_check_server.parse().map_err(|e| {
+                                anyhow!("invalid [{}] udp health check server: {}", &tag, e)
+                            })?
+                        };
                         let (tcp, mut tcp_abort_handles) = failover::TcpHandler::new(
                             actors.clone(),
                             settings.fail_timeout,
@@ -462,31 +988,93 @@ impl OutboundManager {
                             settings.cache_timeout as u64,
                             last_resort.clone(),
                             settings.health_check_timeout,
+                            probe,
+                            settings.health_check_status as u16,
+                            settings.health_check_concurrency as usize,
+                            ejector(),
                             dns_client.clone(),
                         );
                         let (udp, mut udp_abort_handles) = failover::UdpHandler::new(
                             actors,
                             settings.fail_timeout,
-                            settings.health_check,
+                            settings.health_check && settings.udp_health_check,
                             settings.check_interval,
                             settings.failover,
                             last_resort,
                             settings.health_check_timeout,
+                            udp_server,
+                            settings.health_check_concurrency as usize,
+                            ejector(),
                             dns_client.clone(),
                         );
-                        let handler = HandlerBuilder::default()
-                            .tag(tag.clone())
+                        if settings.health_check {
+                            health_reports.insert(tag.clone(), Arc::new(tcp.health_report()));
+                        }
+                        let handler = Self::handler_builder(outbound)?
                             .tcp_handler(Box::new(tcp))
                             .udp_handler(Box::new(udp))
                             .build();
                         handlers.insert(tag.clone(), handler);
                         abort_handles.append(&mut tcp_abort_handles);
                         abort_handles.append(&mut udp_abort_handles);
-                        trace!(
-                            "added handler [{}] with actors: {}",
+                        trace!("added handler [{}] with actors: {}", &tag, tags.join(","));
+                    }
+                    #[cfg(feature = "outbound-urltest")]
+                    "urltest" => {
+                        let settings = config::UrlTestOutboundSettings::parse_from_bytes(
+                            &outbound.settings,
+                        )
+                        .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?;
+                        let tags = Self::group_actors(
+                            outbounds,
                             &tag,
-                            settings.actors.join(",")
-                        );
+                            &settings.actors,
+                            &settings.filter,
+                        )?;
+                        let mut actors = Vec::new();
+                        for actor in tags.iter() {
+                            if let Some(a) = handlers.get(actor) {
+                                actors.push(a.clone());
+                            } else {
+                                continue 'outbounds;
+                            }
+                        }
+                        if actors.is_empty() {
+                            continue;
+                        }
+                        let url = if settings.url.is_empty() {
+                            super::urltest::DEFAULT_URL
+                        } else {
+                            &settings.url
+                        };
+                        let (tester, abort_handle) = UrlTester::new(
+                            tag.clone(),
+                            actors,
+                            url,
+                            Self::timeout(settings.interval)
+                                .unwrap_or_else(|| Duration::from_secs(300)),
+                            settings.tolerance as u64,
+                            Self::timeout(settings.timeout)
+                                .unwrap_or_else(|| Duration::from_secs(5)),
+                            dns_client.clone(),
+                        )
+                        .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?;
+                        let tcp = Box::new(urltest::TcpHandler {
+                            tester: tester.clone(),
+                            dns_client: dns_client.clone(),
+                        });
+                        let udp = Box::new(urltest::UdpHandler {
+                            tester: tester.clone(),
+                            dns_client: dns_client.clone(),
+                        });
+                        health_reports.insert(tag.clone(), tester);
+                        let handler = Self::handler_builder(outbound)?
+                            .tcp_handler(tcp)
+                            .udp_handler(udp)
+                            .build();
+                        handlers.insert(tag.clone(), handler);
+                        abort_handles.push(abort_handle);
+                        trace!("added handler [{}] with actors: {}", &tag, tags.join(","));
                     }
                     #[cfg(feature = "outbound-amux")]
                     "amux" => {
@@ -509,14 +1097,16 @@ impl OutboundManager {
                             actors.clone(),
                             settings.max_accepts as usize,
                             settings.concurrency as usize,
+                            std::time::Duration::from_secs(settings.keep_alive as u64),
+                            std::time::Duration::from_secs(settings.idle_timeout as u64),
                             dns_client.clone(),
+                            Self::dial_options(outbound)?,
                         );
                         let udp = Box::new(null::outbound::UdpHandler {
                             connect: Some(OutboundConnect::NoConnect),
                             transport_type: DatagramTransportType::Stream,
                         });
-                        let handler = HandlerBuilder::default()
-                            .tag(tag.clone())
+                        let handler = Self::handler_builder(outbound)?
                             .tcp_handler(Box::new(tcp))
                             .udp_handler(udp)
                             .build();
@@ -528,6 +1118,127 @@ impl OutboundManager {
                             settings.actors.join(",")
                         );
                     }
+                    #[cfg(feature = "outbound-mux")]
+                    "mux" => {
+                        let settings =
+                            config::MuxOutboundSettings::parse_from_bytes(&outbound.settings)
+                                .map_err(|e| {
+                                    anyhow!("invalid [{}] outbound settings: {}", &tag, e)
+                                })?;
+                        let mut actors = Vec::new();
+                        for actor in settings.actors.iter() {
+                            if let Some(a) = handlers.get(actor) {
+                                actors.push(a.clone());
+                            } else {
+                                continue 'outbounds;
+                            }
+                        }
+                        let tcp = Box::new(mux::outbound::TcpHandler::new(
+                            settings.address.clone(),
+                            settings.port as u16,
+                            actors,
+                            settings.max_streams as usize,
+                            settings.min_connections as usize,
+                            settings.max_connections as usize,
+                            settings.padding,
+                            dns_client.clone(),
+                            Self::dial_options(outbound)?,
+                        ));
+                        let udp = Box::new(null::outbound::UdpHandler {
+                            connect: Some(OutboundConnect::NoConnect),
+                            transport_type: DatagramTransportType::Stream,
+                        });
+                        let handler = Self::handler_builder(outbound)?
+                            .tcp_handler(tcp)
+                            .udp_handler(udp)
+                            .build();
+                        handlers.insert(tag.clone(), handler);
+                        trace!(
+                            "added handler [{}] with actors: {}",
+                            &tag,
+                            settings.actors.join(",")
+                        );
+                    }
+                    #[cfg(feature = "outbound-grpc")]
+                    "grpc" => {
+                        let settings =
+                            config::GrpcOutboundSettings::parse_from_bytes(&outbound.settings)
+                                .map_err(|e| {
+                                    anyhow!("invalid [{}] outbound settings: {}", &tag, e)
+                                })?;
+                        let mut actors = Vec::new();
+                        for actor in settings.actors.iter() {
+                            if let Some(a) = handlers.get(actor) {
+                                actors.push(a.clone());
+                            } else {
+                                continue 'outbounds;
+                            }
+                        }
+                        let tcp = grpc::outbound::TcpHandler::new(
+                            settings.address.clone(),
+                            settings.port as u16,
+                            settings.host.clone(),
+                            settings.service_name.clone(),
+                            actors,
+                            dns_client.clone(),
+                            Self::dial_options(outbound)?,
+                        )
+                        .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?;
+                        let udp = Box::new(null::outbound::UdpHandler {
+                            connect: Some(OutboundConnect::NoConnect),
+                            transport_type: DatagramTransportType::Stream,
+                        });
+                        let handler = Self::handler_builder(outbound)?
+                            .tcp_handler(Box::new(tcp))
+                            .udp_handler(udp)
+                            .build();
+                        handlers.insert(tag.clone(), handler);
+                        trace!(
+                            "added handler [{}] with actors: {}",
+                            &tag,
+                            settings.actors.join(",")
+                        );
+                    }
+                    #[cfg(feature = "outbound-http2")]
+                    "h2" => {
+                        let settings =
+                            config::Http2OutboundSettings::parse_from_bytes(&outbound.settings)
+                                .map_err(|e| {
+                                    anyhow!("invalid [{}] outbound settings: {}", &tag, e)
+                                })?;
+                        let mut actors = Vec::new();
+                        for actor in settings.actors.iter() {
+                            if let Some(a) = handlers.get(actor) {
+                                actors.push(a.clone());
+                            } else {
+                                continue 'outbounds;
+                            }
+                        }
+                        let tcp = http2::outbound::TcpHandler::new(
+                            settings.address.clone(),
+                            settings.port as u16,
+                            settings.host.clone(),
+                            settings.path.clone(),
+                            actors,
+                            dns_client.clone(),
+                            Self::dial_options(outbound)?,
+                        )
+                        .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &tag, e))?;
+                        let udp = Box::new(null::outbound::UdpHandler {
+                            connect: Some(OutboundConnect::NoConnect),
+                            transport_type: DatagramTransportType::Stream,
+                        });
+                        let handler = Self::handler_builder(outbound)?
+                            .tcp_handler(Box::new(tcp))
+                            .udp_handler(udp)
+                            .build();
+                        handlers.insert(tag.clone(), handler);
+                        trace!(
+                            "added handler [{}] with actors: {}",
+                            &tag,
+                            settings.actors.join(",")
+                        );
+                    }
                     #[cfg(feature = "outbound-chain")]
                     "chain" => {
                         let settings =
@@ -548,12 +1259,13 @@ impl OutboundManager {
                         }
                         let tcp = Box::new(chain::outbound::TcpHandler {
                             actors: actors.clone(),
+                            dns_client: dns_client.clone(),
                         });
                         let udp = Box::new(chain::outbound::UdpHandler {
                             actors: actors.clone(),
+                            dns_client: dns_client.clone(),
                         });
-                        let handler = HandlerBuilder::default()
-                            .tag(tag.clone())
+                        let handler = Self::handler_builder(outbound)?
                             .tcp_handler(tcp)
                             .udp_handler(udp)
                             .build();
@@ -581,8 +1293,7 @@ impl OutboundManager {
                         let udp = Box::new(super::plugin::ExternalUdpOutboundHandlerProxy(
                             external_handlers.get_udp_handler(&tag).unwrap(),
                         ));
-                        let handler = HandlerBuilder::default()
-                            .tag(tag.clone())
+                        let handler = Self::handler_builder(outbound)?
                             .tcp_handler(tcp)
                             .udp_handler(udp)
                             .build();
@@ -621,8 +1332,14 @@ impl OutboundManager {
                                 .map_err(|e| {
                                     anyhow!("invalid [{}] outbound settings: {}", &tag, e)
                                 })?;
+                        let tags = Self::group_actors(
+                            outbounds,
+                            &tag,
+                            &settings.actors,
+                            &settings.filter,
+                        )?;
                         let mut actors = HashMap::new();
-                        for actor in settings.actors.iter() {
+                        for actor in tags.iter() {
                             if let Some(a) = handlers.get(actor) {
                                 actors.insert(actor.to_owned(), a.clone());
                             } else {
@@ -638,7 +1355,7 @@ impl OutboundManager {
                             // FIXME handle error
                             let _ = selector.set_selected(&selected);
                         } else {
-                            let _ = selector.set_selected(&settings.actors[0]);
+                            let _ = selector.set_selected(&tags[0]);
                         }
                         let selector = Arc::new(RwLock::new(selector));
 
@@ -649,17 +1366,12 @@ impl OutboundManager {
                             selector: selector.clone(),
                         });
                         selectors.insert(tag.clone(), selector);
-                        let handler = HandlerBuilder::default()
-                            .tag(tag.clone())
+                        let handler = Self::handler_builder(outbound)?
                             .tcp_handler(tcp)
                             .udp_handler(udp)
                             .build();
                         handlers.insert(tag.clone(), handler);
-                        trace!(
-                            "added handler [{}] with actors: {}",
-                            &tag,
-                            settings.actors.join(",")
-                        );
+                        trace!("added handler [{}] with actors: {}", &tag, tags.join(","));
                     }
                     _ => continue,
                 }
@@ -681,6 +1393,8 @@ impl OutboundManager {
             selected_outbounds.insert(k.to_owned(), v.read().await.get_selected_tag());
         }
 
+        Self::check_cycles(outbounds)?;
+
         // Load new outbounds.
         let mut handlers: HashMap<String, AnyOutboundHandler> = HashMap::new();
 
@@ -688,7 +1402,11 @@ impl OutboundManager {
         let mut default_handler: Option<String> = None;
         let mut abort_handles: Vec<AbortHandle> = Vec::new();
         let mut selectors: super::Selectors = HashMap::new();
-        for _i in 0..4 {
+        let mut health_reports: super::HealthReports = HashMap::new();
+        // Loads until no more outbounds can be loaded, groups are loaded
+        // after their actors.
+        loop {
+            let loaded = handlers.len();
             Self::load_handlers(
                 outbounds,
                 dns_client.clone(),
@@ -696,6 +1414,7 @@ impl OutboundManager {
                 &mut external_handlers,
                 &mut default_handler,
                 &mut abort_handles,
+                &mut health_reports,
             )?;
             Self::load_selectors(
                 outbounds,
@@ -703,6 +1422,9 @@ impl OutboundManager {
                 &mut external_handlers,
                 &mut selectors,
             )?;
+            if handlers.len() == loaded {
+                break;
+            }
         }
 
         // Restore outbound select states.
@@ -724,6 +1446,7 @@ impl OutboundManager {
         self.handlers = handlers;
         self.external_handlers = external_handlers;
         self.selectors = Arc::new(selectors);
+        self.health_reports = Arc::new(health_reports);
         self.default_handler = default_handler;
         self.abort_handles = abort_handles;
         Ok(())
@@ -733,12 +1456,17 @@ impl OutboundManager {
         outbounds: &protobuf::RepeatedField<Outbound>,
         dns_client: SyncDnsClient,
     ) -> Result<Self> {
+        Self::check_cycles(outbounds)?;
         let mut handlers: HashMap<String, AnyOutboundHandler> = HashMap::new();
         let mut external_handlers = super::plugin::ExternalHandlers::new();
         let mut default_handler: Option<String> = None;
         let mut abort_handles: Vec<AbortHandle> = Vec::new();
         let mut selectors: super::Selectors = HashMap::new();
-        for _i in 0..4 {
+        let mut health_reports: super::HealthReports = HashMap::new();
+        // Loads until no more outbounds can be loaded, groups are loaded
+        // after their actors.
+        loop {
+            let loaded = handlers.len();
             Self::load_handlers(
                 outbounds,
                 dns_client.clone(),
@@ -746,6 +1474,7 @@ impl OutboundManager {
                 &mut external_handlers,
                 &mut default_handler,
                 &mut abort_handles,
+                &mut health_reports,
             )?;
             Self::load_selectors(
                 outbounds,
@@ -753,11 +1482,15 @@ impl OutboundManager {
                 &mut external_handlers,
                 &mut selectors,
             )?;
+            if handlers.len() == loaded {
+                break;
+            }
         }
         Ok(OutboundManager {
             handlers,
             external_handlers,
             selectors: Arc::new(selectors),
+            health_reports: Arc::new(health_reports),
             default_handler,
             abort_handles,
         })
@@ -784,6 +1517,29 @@ impl OutboundManager {
     pub fn get_selector(&self, tag: &str) -> Option<Arc<RwLock<OutboundSelector>>> {
         self.selectors.get(tag).map(Clone::clone)
     }
+
+    pub fn health_reports(&self) -> Arc<super::HealthReports> {
+        self.health_reports.clone()
+    }
+
+    /// Returns the addresses of the remote servers the outbounds connect to.
+    pub fn server_addrs(&self) -> Vec<(String, u16)> {
+        let mut addrs = Vec::new();
+        for h in self.handlers.values() {
+            let connects = [
+                TcpOutboundHandler::connect_addr(h.as_ref()),
+                UdpOutboundHandler::connect_addr(h.as_ref()),
+            ];
+            for connect in connects.into_iter().flatten() {
+                if let OutboundConnect::Proxy(addr, port) = connect {
+                    if !addrs.contains(&(addr.clone(), port)) {
+                        addrs.push((addr, port));
+                    }
+                }
+            }
+        }
+        addrs
+    }
 }
 
 pub struct Handlers<'a> {
@@ -797,3 +1553,75 @@ impl<'a> Iterator for Handlers<'a> {
         self.inner.next()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "outbound-select")]
+    #[test]
+    fn test_group_actors() {
+        let mut outbounds = protobuf::RepeatedField::new();
+        let mut add = |tag: &str, protocol: &str, settings: Vec<u8>| {
+            let mut outbound = Outbound::new();
+            outbound.tag = tag.to_string();
+            outbound.protocol = protocol.to_string();
+            outbound.settings = settings;
+            outbounds.push(outbound);
+        };
+        add("HK 1", "shadowsocks", Vec::new());
+        add("hk 2", "trojan", Vec::new());
+        add("SG 1", "trojan", Vec::new());
+        add("HK_tls_xxx", "tls", Vec::new());
+        let mut chain = config::ChainOutboundSettings::new();
+        chain.actors.push("HK_tls_xxx".to_string());
+        add("HK 3", "chain", chain.write_to_bytes().unwrap());
+        add("HK Auto", "urltest", Vec::new());
+
+        let actors = vec!["Direct".to_string(), "hk 2".to_string()];
+        assert_eq!(
+            OutboundManager::group_actors(&outbounds, "HK", &actors, "").unwrap(),
+            actors
+        );
+        assert_eq!(
+            OutboundManager::group_actors(&outbounds, "HK", &actors, "(?i)HK").unwrap(),
+            vec!["Direct", "hk 2", "HK 1", "HK 3"]
+        );
+        assert_eq!(
+            OutboundManager::group_actors(&outbounds, "HK", &[], "HK|SG").unwrap(),
+            vec!["HK 1", "SG 1", "HK 3"]
+        );
+        assert!(OutboundManager::group_actors(&outbounds, "HK", &[], "(").is_err());
+    }
+
+    #[test]
+    fn test_check_cycles() {
+        let group = |tag: &str, actors: &[&str]| {
+            let mut settings = config::SelectOutboundSettings::new();
+            for actor in actors {
+                settings.actors.push(actor.to_string());
+            }
+            let mut outbound = Outbound::new();
+            outbound.tag = tag.to_string();
+            outbound.protocol = "select".to_string();
+            outbound.settings = settings.write_to_bytes().unwrap();
+            outbound
+        };
+        let mut outbounds = protobuf::RepeatedField::new();
+        outbounds.push(group("Proxy", &["Auto", "Fallback", "Direct"]));
+        outbounds.push(group("Auto", &["p1", "p2"]));
+        outbounds.push(group("Fallback", &["Auto", "p3"]));
+        assert!(OutboundManager::check_cycles(&outbounds).is_ok());
+
+        outbounds.push(group("p3", &["Proxy"]));
+        let err = OutboundManager::check_cycles(&outbounds).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "outbound [Proxy] depends on itself: Proxy -> Fallback -> p3 -> Proxy"
+        );
+
+        let mut outbounds = protobuf::RepeatedField::new();
+        outbounds.push(group("Proxy", &["Proxy"]));
+        assert!(OutboundManager::check_cycles(&outbounds).is_err());
+    }
+}
@@ -1,11 +1,28 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use async_trait::async_trait;
 use tokio::sync::RwLock;
 
 pub mod manager;
 pub mod plugin;
+#[cfg(feature = "provider")]
+pub mod provider;
 pub mod selector;
 pub mod selector_cache;
+pub mod urltest;
 
 pub type Selectors = HashMap<String, Arc<RwLock<selector::OutboundSelector>>>;
+
+/// Results of the health checks of the actors of an outbound.
+#[async_trait]
+pub trait HealthReport: Send + Sync {
+    /// Returns the tag of the actor in use.
+    async fn selected(&self) -> Option<String>;
+
+    /// Returns the actors with their latencies in millis of the last checks,
+    /// None if failed or not checked yet.
+    async fn latencies(&self) -> Vec<(String, Option<u64>)>;
+}
+
+pub type HealthReports = HashMap<String, Arc<dyn HealthReport>>;
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use log::*;
+use tokio::time::timeout;
+
+use super::urltest::HttpProbe;
+use crate::{
+    app::SyncDnsClient,
+    config::{self, provider::local_path},
+    proxy::{direct, outbound::HandlerBuilder},
+};
+
+// Limit of the size of the content.
+const MAX_SIZE: u64 = 16 * 1024 * 1024;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Interval of checking whether the local copies of providers are expired.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+fn needs_update(provider: &config::Provider, only_missing: bool) -> bool {
+    // Local only.
+    if provider.url.is_empty() {
+        return false;
+    }
+    let path = match local_path(provider) {
+        Ok(p) => p,
+        Err(e) => {
+            warn!("invalid [{}] provider path: {}", &provider.tag, e);
+            return false;
+        }
+    };
+    let modified = match std::fs::metadata(path).and_then(|m| m.modified()) {
+        Ok(m) => m,
+        Err(_) => return true,
+    };
+    if only_missing || provider.interval == 0 {
+        return false;
+    }
+    modified
+        .elapsed()
+        .map(|d| d.as_secs() >= provider.interval as u64)
+        .unwrap_or(false)
+}
+
+// Returns whether the content has changed.
+async fn fetch(
+    provider: &config::Provider,
+    dns_client: SyncDnsClient,
+    check: Check,
+) -> Result<bool> {
+    let probe = HttpProbe::new(&provider.url)?;
+    // Fetches directly, the outbounds may depend on the provider itself.
+    let direct = HandlerBuilder::default()
+        .tag("direct".to_string())
+        .tcp_handler(Box::new(direct::TcpHandler))
+        .udp_handler(Box::new(direct::UdpHandler))
+        .build();
+    let (status, body) = timeout(FETCH_TIMEOUT, probe.get(&direct, dns_client, MAX_SIZE))
+        .await
+        .map_err(|_| anyhow!("timed out"))??;
+    if status != 200 {
+        return Err(anyhow!("unexpected status {}", status));
+    }
+    // Keeps the local copy if the new content isn't usable.
+    check(provider, &body)?;
+    let path = local_path(provider)?;
+    let changed = std::fs::read(&path).map(|c| c != body).unwrap_or(true);
+    // Always rewrites to renew the modification time.
+    std::fs::write(&path, body)?;
+    Ok(changed)
+}
+
+/// Checks whether fetched content is usable by a provider, e.g.
+/// `config::provider::check` for proxy providers.
+pub type Check = fn(&config::Provider, &[u8]) -> Result<()>;
+
+/// Fetches the providers without local copies, and also the ones with
+/// expired local copies unless `only_missing`. Returns whether any of the
+/// local copies has changed.
+pub async fn update(
+    providers: &[config::Provider],
+    dns_client: SyncDnsClient,
+    only_missing: bool,
+    check: Check,
+) -> bool {
+    let mut changed = false;
+    for provider in providers.iter() {
+        if !needs_update(provider, only_missing) {
+            continue;
+        }
+        match fetch(provider, dns_client.clone(), check).await {
+            Ok(c) => {
+                debug!("updated [{}] provider, changed: {}", &provider.tag, c);
+                changed |= c;
+            }
+            Err(e) => {
+                warn!("update [{}] provider failed: {}", &provider.tag, e);
+            }
+        }
+    }
+    changed
+}
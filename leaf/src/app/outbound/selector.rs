@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 use protobuf::Message;
 
@@ -7,20 +7,7 @@ use crate::proxy::AnyOutboundHandler;
 use anyhow::{anyhow, Result};
 
 fn get_cache_file_path() -> Result<PathBuf> {
-    let cache_loc = if !(&*crate::option::CACHE_LOCATION).is_empty() {
-        Path::new(&*crate::option::CACHE_LOCATION).to_owned()
-    } else {
-        let proj_dirs = if let Some(d) = directories::ProjectDirs::from("com", "github", "leaf") {
-            d
-        } else {
-            return Err(anyhow!("no home directory"));
-        };
-        proj_dirs.cache_dir().to_owned()
-    };
-    if !cache_loc.exists() {
-        std::fs::create_dir_all(&cache_loc)?;
-    }
-    Ok(cache_loc.join("selector.cache"))
+    crate::app::get_cache_file_path("selector.cache")
 }
 
 pub fn get_selected_from_cache(id: &str) -> Result<Option<String>> {
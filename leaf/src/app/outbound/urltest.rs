@@ -0,0 +1,379 @@
+use std::convert::TryFrom;
+use std::io;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::future::{abortable, AbortHandle, BoxFuture};
+use futures::FutureExt;
+use log::*;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::RwLock;
+use tokio::time::timeout;
+
+use super::HealthReport;
+#[cfg(feature = "outbound-tls")]
+use crate::proxy::tls;
+use crate::{
+    app::SyncDnsClient,
+    proxy::{AnyOutboundHandler, AnyStream, TcpOutboundHandler},
+    session::{Session, SocksAddr},
+};
+
+pub const DEFAULT_URL: &str = "http://www.gstatic.com/generate_204";
+
+#[derive(Debug, PartialEq, Eq)]
+struct ProbeUrl {
+    tls: bool,
+    host: String,
+    port: u16,
+    // host[:port] as in the URL, for the Host header
+    authority: String,
+    path: String,
+}
+
+impl FromStr for ProbeUrl {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (tls, rest) = if let Some(rest) = s.strip_prefix("http://") {
+            (false, rest)
+        } else if let Some(rest) = s.strip_prefix("https://") {
+            (true, rest)
+        } else {
+            return Err(anyhow!("unsupported url {}", s));
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let default_port = if tls { 443 } else { 80 };
+        let (host, port) = match authority.rfind(':') {
+            Some(i) if !authority.ends_with(']') => (
+                &authority[..i],
+                authority[i + 1..]
+                    .parse::<u16>()
+                    .map_err(|e| anyhow!("invalid port in url {}: {}", s, e))?,
+            ),
+            _ => (authority, default_port),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            return Err(anyhow!("missing host in url {}", s));
+        }
+        Ok(Self {
+            tls,
+            host: host.to_string(),
+            port,
+            authority: authority.to_string(),
+            path: path.to_string(),
+        })
+    }
+}
+
+// Returns the index of the actor to use, the current one is kept unless it
+// failed or another actor is faster by more than the tolerance.
+fn pick(latencies: &[Option<u64>], current: usize, tolerance: u64) -> usize {
+    let best = latencies
+        .iter()
+        .enumerate()
+        .filter_map(|(i, l)| l.map(|l| (i, l)))
+        .min_by_key(|(_, l)| *l);
+    match best {
+        Some((i, l)) => match latencies.get(current).copied().flatten() {
+            Some(c) if c <= l.saturating_add(tolerance) => current,
+            _ => i,
+        },
+        None => current,
+    }
+}
+
+struct State {
+    // in millis, None if the last probe failed or hasn't been done
+    latencies: Vec<Option<u64>>,
+    selected: usize,
+}
+
+/// HttpProbe requests an HTTP(S) URL through outbounds, for health checks and
+/// fetching providers.
+pub struct HttpProbe {
+    url: ProbeUrl,
+    #[cfg(feature = "outbound-tls")]
+    tls: Option<tls::outbound::TcpHandler>,
+}
+
+impl HttpProbe {
+    pub fn new(url: &str) -> Result<Self> {
+        let url = ProbeUrl::from_str(url)?;
+        #[cfg(feature = "outbound-tls")]
+        let tls = if url.tls {
+            Some(tls::outbound::TcpHandler::new(
+                url.host.clone(),
+                vec!["http/1.1".to_string()],
+                None,
+                None,
+            )?)
+        } else {
+            None
+        };
+        #[cfg(not(feature = "outbound-tls"))]
+        if url.tls {
+            return Err(anyhow!("https urls aren't supported in this build"));
+        }
+        Ok(Self {
+            url,
+            #[cfg(feature = "outbound-tls")]
+            tls,
+        })
+    }
+
+    async fn send(
+        &self,
+        actor: &AnyOutboundHandler,
+        dns_client: SyncDnsClient,
+        version: &str,
+    ) -> io::Result<AnyStream> {
+        let sess = Session {
+            destination: SocksAddr::try_from((&self.url.host, self.url.port))?,
+            ..Default::default()
+        };
+        let stream = crate::proxy::connect_tcp_outbound(&sess, dns_client, actor).await?;
+        let stream = TcpOutboundHandler::handle(actor.as_ref(), &sess, stream).await?;
+        #[cfg(feature = "outbound-tls")]
+        let stream = if let Some(tls) = self.tls.as_ref() {
+            TcpOutboundHandler::handle(tls, &sess, Some(stream)).await?
+        } else {
+            stream
+        };
+        let mut stream = stream;
+        let req = format!(
+            "GET {} HTTP/{}\r\nHost: {}\r\nUser-Agent: leaf\r\nConnection: close\r\n\r\n",
+            &self.url.path, version, &self.url.authority
+        );
+        stream.write_all(req.as_bytes()).await?;
+        Ok(stream)
+    }
+
+    /// Returns the status code of the response.
+    pub async fn probe(
+        &self,
+        actor: &AnyOutboundHandler,
+        dns_client: SyncDnsClient,
+    ) -> io::Result<u16> {
+        let mut stream = self.send(actor, dns_client, "1.1").await?;
+        // e.g. HTTP/1.1 204
+        let mut buf = [0u8; 12];
+        stream.read_exact(&mut buf).await?;
+        parse_status(&buf)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid http response"))
+    }
+
+    /// Returns the status code and the body of the response, which is read
+    /// until the connection closes and is limited to `max_size` bytes.
+    pub async fn get(
+        &self,
+        actor: &AnyOutboundHandler,
+        dns_client: SyncDnsClient,
+        max_size: u64,
+    ) -> io::Result<(u16, Vec<u8>)> {
+        // HTTP/1.0 to not have a chunked response.
+        let stream = self.send(actor, dns_client, "1.0").await?;
+        let mut buf = Vec::new();
+        stream.take(max_size).read_to_end(&mut buf).await?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid http response");
+        let status = buf
+            .get(..12)
+            .and_then(|b| parse_status(b.try_into().ok()?))
+            .ok_or_else(invalid)?;
+        let body = buf
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .ok_or_else(invalid)?
+            + 4;
+        Ok((status, buf.split_off(body)))
+    }
+}
+
+fn parse_status(buf: &[u8; 12]) -> Option<u16> {
+    if !buf.starts_with(b"HTTP/1.") || buf[8] != b' ' {
+        return None;
+    }
+    std::str::from_utf8(&buf[9..]).ok()?.parse().ok()
+}
+
+/// UrlTester typically associates to a `urltest` outbound, it periodically
+/// probes the actors with an HTTP(S) request and selects the fastest one.
+pub struct UrlTester {
+    tag: String,
+    actors: Vec<AnyOutboundHandler>,
+    probe: HttpProbe,
+    tolerance: u64,
+    timeout: Duration,
+    dns_client: SyncDnsClient,
+    state: RwLock<State>,
+    task: Mutex<Option<BoxFuture<'static, ()>>>,
+}
+
+impl UrlTester {
+    pub fn new(
+        tag: String,
+        actors: Vec<AnyOutboundHandler>,
+        url: &str,
+        interval: Duration,
+        tolerance: u64, // in millis
+        timeout: Duration,
+        dns_client: SyncDnsClient,
+    ) -> Result<(Arc<Self>, AbortHandle)> {
+        let probe = HttpProbe::new(url)?;
+        let latencies = vec![None; actors.len()];
+        let tester = Arc::new(Self {
+            tag,
+            actors,
+            probe,
+            tolerance,
+            timeout,
+            dns_client,
+            state: RwLock::new(State {
+                latencies,
+                selected: 0,
+            }),
+            task: Mutex::new(None),
+        });
+
+        // Holds a weak reference to not keep the tester alive by its own task.
+        let weak = Arc::downgrade(&tester);
+        let fut = async move {
+            loop {
+                if let Some(tester) = weak.upgrade() {
+                    tester.test_all().await;
+                } else {
+                    return;
+                }
+                tokio::time::sleep(interval).await;
+            }
+        };
+        let (abortable, abort_handle) = abortable(fut);
+        tester
+            .task
+            .lock()
+            .unwrap()
+            .replace(Box::pin(abortable.map(|_| ())));
+        Ok((tester, abort_handle))
+    }
+
+    /// Spawns the probing task if it hasn't been spawned.
+    pub fn start(&self) {
+        if let Some(task) = self.task.lock().unwrap().take() {
+            tokio::spawn(task);
+        }
+    }
+
+    pub async fn get_selected(&self) -> AnyOutboundHandler {
+        self.actors[self.state.read().await.selected].clone()
+    }
+
+    async fn probe(&self, actor: &AnyOutboundHandler) -> io::Result<Duration> {
+        let start = tokio::time::Instant::now();
+        self.probe.probe(actor, self.dns_client.clone()).await?;
+        Ok(tokio::time::Instant::now().duration_since(start))
+    }
+
+    /// Probes all actors and updates the selection.
+    pub async fn test_all(&self) {
+        let mut probes = Vec::new();
+        for a in self.actors.iter() {
+            probes.push(async move {
+                match timeout(self.timeout, self.probe(a)).await {
+                    Ok(Ok(d)) => Some(d.as_millis() as u64),
+                    Ok(Err(e)) => {
+                        debug!("[{}] url test for [{}] failed: {}", &self.tag, a.tag(), e);
+                        None
+                    }
+                    Err(_) => {
+                        debug!("[{}] url test for [{}] timed out", &self.tag, a.tag());
+                        None
+                    }
+                }
+            });
+        }
+        let latencies = futures::future::join_all(probes).await;
+        let mut state = self.state.write().await;
+        let selected = pick(&latencies, state.selected, self.tolerance);
+        if selected != state.selected {
+            debug!(
+                "[{}] switches from [{}] to [{}]",
+                &self.tag,
+                self.actors[state.selected].tag(),
+                self.actors[selected].tag()
+            );
+        }
+        state.selected = selected;
+        state.latencies = latencies;
+    }
+}
+
+#[async_trait]
+impl HealthReport for UrlTester {
+    async fn selected(&self) -> Option<String> {
+        Some(self.get_selected().await.tag().to_owned())
+    }
+
+    async fn latencies(&self) -> Vec<(String, Option<u64>)> {
+        let state = self.state.read().await;
+        self.actors
+            .iter()
+            .zip(state.latencies.iter())
+            .map(|(a, l)| (a.tag().to_owned(), *l))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_probe_url() {
+        let url = ProbeUrl::from_str(DEFAULT_URL).unwrap();
+        assert_eq!(
+            url,
+            ProbeUrl {
+                tls: false,
+                host: "www.gstatic.com".to_string(),
+                port: 80,
+                authority: "www.gstatic.com".to_string(),
+                path: "/generate_204".to_string(),
+            }
+        );
+        let url = ProbeUrl::from_str("https://[::1]:8443").unwrap();
+        assert!(url.tls);
+        assert_eq!(url.host, "::1");
+        assert_eq!(url.port, 8443);
+        assert_eq!(url.authority, "[::1]:8443");
+        assert_eq!(url.path, "/");
+        assert_eq!(ProbeUrl::from_str("https://[::1]").unwrap().port, 443);
+        assert!(ProbeUrl::from_str("ftp://example.com").is_err());
+        assert!(ProbeUrl::from_str("http://example.com:x/").is_err());
+    }
+
+    #[test]
+    fn test_parse_status() {
+        assert_eq!(parse_status(b"HTTP/1.1 204"), Some(204));
+        assert_eq!(parse_status(b"HTTP/1.0 404"), Some(404));
+        assert_eq!(parse_status(b"HTTP/1.1 2x4"), None);
+        assert_eq!(parse_status(b"SSH-2.0-Open"), None);
+    }
+
+    #[test]
+    fn test_pick() {
+        // Switches to the fastest one if the current one failed.
+        assert_eq!(pick(&[None, Some(200), Some(100)], 0, 50), 2);
+        // Keeps the current one within the tolerance.
+        assert_eq!(pick(&[Some(140), Some(200), Some(100)], 0, 50), 0);
+        assert_eq!(pick(&[Some(151), Some(200), Some(100)], 0, 50), 2);
+        // Keeps the current one if all failed.
+        assert_eq!(pick(&[None, None], 1, 50), 1);
+    }
+}
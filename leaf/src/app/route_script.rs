@@ -0,0 +1,159 @@
+use anyhow::{anyhow, Result};
+use log::*;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+
+use crate::session::Session;
+
+// Bounds the work of a call, so a looping script can't hang the dispatching.
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// A rhai script picking outbounds for sessions. The script defines a
+/// `route` function which takes a map of the session and returns the tag of
+/// an outbound, or an empty string or `()` to leave the session to the rules.
+///
+/// ```text
+/// fn route(sess) {
+///     if sess.network == "udp" && sess.port == 443 {
+///         return "Reject";
+///     }
+///     if sess.domain.ends_with(".cn") || sess.inbound_tag == "lan" {
+///         return "Direct";
+///     }
+/// }
+/// ```
+///
+/// The map has `network`, `domain`, `ip`, `port`, `inbound_tag`,
+/// `source_ip`, `source_port` and `sniffed_protocol`, with empty strings for
+/// the unknown ones.
+pub struct RouteScript {
+    engine: Engine,
+    ast: AST,
+}
+
+impl RouteScript {
+    pub fn new(script: &str) -> Result<Self> {
+        let engine = Self::engine();
+        let ast = engine
+            .compile(script)
+            .map_err(|e| anyhow!("compile script failed: {}", e))?;
+        if !ast.iter_functions().any(|f| f.name == "route") {
+            return Err(anyhow!("missing route function"));
+        }
+        Ok(RouteScript { engine, ast })
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let script = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("read script [{}] failed: {}", path, e))?;
+        Self::new(&script)
+    }
+
+    fn engine() -> Engine {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.on_print(|s| info!("[route script] {}", s));
+        engine.on_debug(|s, _, _| debug!("[route script] {}", s));
+        engine
+    }
+
+    fn session_map(sess: &Session) -> Map {
+        let mut map = Map::new();
+        map.insert("network".into(), Dynamic::from(sess.network.to_string()));
+        let domain = sess.destination.domain().cloned().unwrap_or_default();
+        map.insert("domain".into(), Dynamic::from(domain));
+        let ip = sess.destination.ip().map(|ip| ip.to_string());
+        map.insert("ip".into(), Dynamic::from(ip.unwrap_or_default()));
+        map.insert("port".into(), Dynamic::from(sess.destination.port() as i64));
+        map.insert(
+            "inbound_tag".into(),
+            Dynamic::from(sess.inbound_tag.clone()),
+        );
+        map.insert(
+            "source_ip".into(),
+            Dynamic::from(sess.source.ip().to_string()),
+        );
+        map.insert(
+            "source_port".into(),
+            Dynamic::from(sess.source.port() as i64),
+        );
+        let protocol = sess.sniffed_protocol.map(|p| p.to_string());
+        map.insert(
+            "sniffed_protocol".into(),
+            Dynamic::from(protocol.unwrap_or_default()),
+        );
+        map
+    }
+
+    /// Returns the outbound tag picked by the script, if any.
+    pub fn route(&self, sess: &Session) -> Option<String> {
+        let res = self.engine.call_fn::<Dynamic>(
+            &mut Scope::new(),
+            &self.ast,
+            "route",
+            (Self::session_map(sess),),
+        );
+        match res {
+            Ok(v) if v.is_unit() => None,
+            Ok(v) => match v.into_string() {
+                Ok(tag) if tag.is_empty() => None,
+                Ok(tag) => Some(tag),
+                Err(t) => {
+                    warn!("route script returned {} instead of a string", t);
+                    None
+                }
+            },
+            Err(e) => {
+                warn!("route script failed: {}", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::session::{Network, SocksAddr};
+
+    use super::*;
+
+    #[test]
+    fn test_route_script() {
+        let script = r#"
+            fn route(sess) {
+                if sess.network == "udp" && sess.port == 443 {
+                    return "block";
+                }
+                if sess.domain.ends_with(".cn") {
+                    return "direct";
+                }
+                if sess.ip != "" && sess.inbound_tag == "lan" {
+                    return "lan_out";
+                }
+                if sess.sniffed_protocol == "tls" {
+                    loop {}
+                }
+            }
+        "#;
+        let script = RouteScript::new(script).unwrap();
+        let mut sess = Session {
+            network: Network::Udp,
+            destination: SocksAddr::Domain("www.google.com".to_string(), 443),
+            ..Default::default()
+        };
+        assert_eq!(script.route(&sess).as_deref(), Some("block"));
+        sess.network = Network::Tcp;
+        assert_eq!(script.route(&sess), None);
+        sess.destination = SocksAddr::Domain("www.baidu.cn".to_string(), 443);
+        assert_eq!(script.route(&sess).as_deref(), Some("direct"));
+        sess.destination = SocksAddr::Ip("10.0.0.1:80".parse().unwrap());
+        sess.inbound_tag = "lan".to_string();
+        assert_eq!(script.route(&sess).as_deref(), Some("lan_out"));
+        // Stopped by the limit of operations.
+        sess.inbound_tag = "tun".to_string();
+        sess.sniffed_protocol = Some(crate::common::sniff::Protocol::Tls);
+        assert_eq!(script.route(&sess), None);
+
+        assert!(RouteScript::new("fn other() {}").is_err());
+        assert!(RouteScript::new("fn route(sess) {").is_err());
+    }
+}
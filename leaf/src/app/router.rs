@@ -1,15 +1,20 @@
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 
 use anyhow::anyhow;
 use anyhow::Result;
+use chrono::{Datelike, Timelike};
 use cidr::{Cidr, IpCidr};
-use futures::TryFutureExt;
 use log::*;
-use maxminddb::geoip2::Country;
+use maxminddb::geoip2::{Asn, Country};
 use memmap2::Mmap;
 
+#[cfg(feature = "router-script")]
+use crate::app::route_script::RouteScript;
 use crate::app::SyncDnsClient;
+use crate::common::process;
 use crate::config::{self, Router_Rule};
 use crate::session::{Network, Session, SocksAddr};
 
@@ -20,31 +25,96 @@ pub trait Condition: Send + Sync + Unpin {
 struct Rule {
     target: String,
     condition: Box<dyn Condition>,
+    // Whether domain destinations are resolved to match the IP conditions.
+    resolve: bool,
+    // A short description of the conditions, to tell the rules apart in the
+    // stats.
+    desc: String,
+    hits: AtomicU64,
 }
 
 impl Rule {
     fn new(target: String, condition: Box<dyn Condition>) -> Self {
-        Rule { target, condition }
+        Rule {
+            target,
+            condition,
+            resolve: false,
+            desc: String::new(),
+            hits: AtomicU64::new(0),
+        }
     }
 }
 
+/// The hits of a rule since the rules were loaded.
+pub struct RuleStat {
+    pub target: String,
+    pub desc: String,
+    pub hits: u64,
+}
+
+/// Routing decisions counted since the rules were loaded.
+pub struct RouteStats {
+    /// In the order of the rules.
+    pub rules: Vec<RuleStat>,
+    /// Sessions routed by the script.
+    pub script_hits: u64,
+    /// Sessions matching no rules, which go to the default outbound.
+    pub misses: u64,
+    /// Sessions routed to each outbound, sorted by the tags.
+    pub outbounds: Vec<(String, u64)>,
+}
+
 impl Condition for Rule {
     fn apply(&self, sess: &Session) -> bool {
         self.condition.apply(sess)
     }
 }
 
+// An mmdb file opened on the first lookup, so it's never mapped into memory
+// if no IP destination is matched against it.
+struct Mmdb {
+    file: String,
+    // None before the first lookup, then None inside if the file failed to open.
+    reader: RwLock<Option<Option<Arc<maxminddb::Reader<Mmap>>>>>,
+}
+
+impl Mmdb {
+    fn new(file: String) -> Self {
+        Mmdb {
+            file,
+            reader: RwLock::new(None),
+        }
+    }
+
+    fn reader(&self) -> Option<Arc<maxminddb::Reader<Mmap>>> {
+        if let Some(reader) = self.reader.read().unwrap().as_ref() {
+            return reader.clone();
+        }
+        let mut reader = self.reader.write().unwrap();
+        if reader.is_none() {
+            match maxminddb::Reader::open_mmap(&self.file) {
+                Ok(r) => {
+                    debug!("opened mmdb file {}", &self.file);
+                    reader.replace(Some(Arc::new(r)));
+                }
+                Err(e) => {
+                    warn!("open mmdb file {} failed: {:?}", &self.file, e);
+                    reader.replace(None);
+                }
+            }
+        }
+        reader.as_ref().unwrap().clone()
+    }
+}
+
 struct MmdbMatcher {
-    reader: Arc<maxminddb::Reader<Mmap>>,
+    mmdb: Arc<Mmdb>,
     country_code: String,
 }
 
 impl MmdbMatcher {
-    fn new(reader: Arc<maxminddb::Reader<Mmap>>, country_code: String) -> Self {
-        MmdbMatcher {
-            reader,
-            country_code,
-        }
+    fn new(mmdb: Arc<Mmdb>, country_code: String) -> Self {
+        MmdbMatcher { mmdb, country_code }
     }
 }
 
@@ -52,7 +122,12 @@ impl Condition for MmdbMatcher {
     fn apply(&self, sess: &Session) -> bool {
         if !sess.destination.is_domain() {
             if let Some(ip) = sess.destination.ip() {
-                if let Ok(country) = self.reader.lookup::<Country>(ip) {
+                let reader = if let Some(reader) = self.mmdb.reader() {
+                    reader
+                } else {
+                    return false;
+                };
+                if let Ok(country) = reader.lookup::<Country>(ip) {
                     if let Some(country) = country.country {
                         if let Some(iso_code) = country.iso_code {
                             if iso_code.to_lowercase() == self.country_code.to_lowercase() {
@@ -68,43 +143,182 @@ impl Condition for MmdbMatcher {
     }
 }
 
+struct AsnMatcher {
+    mmdb: Arc<Mmdb>,
+    asn: u32,
+}
+
+impl AsnMatcher {
+    fn new(mmdb: Arc<Mmdb>, asn: u32) -> Self {
+        AsnMatcher { mmdb, asn }
+    }
+}
+
+impl Condition for AsnMatcher {
+    fn apply(&self, sess: &Session) -> bool {
+        if sess.destination.is_domain() {
+            return false;
+        }
+        let ip = if let Some(ip) = sess.destination.ip() {
+            ip
+        } else {
+            return false;
+        };
+        let reader = if let Some(reader) = self.mmdb.reader() {
+            reader
+        } else {
+            return false;
+        };
+        if let Ok(Asn {
+            autonomous_system_number: Some(asn),
+            ..
+        }) = reader.lookup::<Asn>(ip)
+        {
+            if asn == self.asn {
+                debug!("[{}] matches asn [{}]", ip, asn);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+// A binary trie of the bits of network addresses, so an IP is looked up in
+// at most as many steps as the bits of its address, however many CIDRs the
+// rules have. Addresses are left-aligned in u128, IPv4 ones included.
+#[derive(Default)]
+struct CidrTrie {
+    // The children of the nodes for bits 0 and 1, 0 if none as the root is
+    // nobody's child.
+    nodes: Vec<[u32; 2]>,
+    // Whether the nodes end networks.
+    ends: Vec<bool>,
+}
+
+impl CidrTrie {
+    fn bit(addr: u128, i: u8) -> usize {
+        ((addr >> (127 - i)) & 1) as usize
+    }
+
+    fn insert(&mut self, addr: u128, len: u8) {
+        if self.nodes.is_empty() {
+            self.nodes.push([0, 0]);
+            self.ends.push(false);
+        }
+        let mut node = 0;
+        for i in 0..len {
+            if self.ends[node] {
+                return; // covered by a larger network
+            }
+            let bit = Self::bit(addr, i);
+            let mut child = self.nodes[node][bit] as usize;
+            if child == 0 {
+                child = self.nodes.len();
+                self.nodes.push([0, 0]);
+                self.ends.push(false);
+                self.nodes[node][bit] = child as u32;
+            }
+            node = child;
+        }
+        self.ends[node] = true;
+    }
+
+    fn contains(&self, addr: u128) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+        let mut node = 0;
+        for i in 0..128 {
+            if self.ends[node] {
+                return true;
+            }
+            node = self.nodes[node][Self::bit(addr, i)] as usize;
+            if node == 0 {
+                return false;
+            }
+        }
+        self.ends[node]
+    }
+}
+
 struct IpCidrMatcher {
-    values: Vec<IpCidr>,
+    v4: CidrTrie,
+    v6: CidrTrie,
 }
 
 impl IpCidrMatcher {
     fn new(ips: &mut protobuf::RepeatedField<String>) -> Self {
-        let mut cidrs = Vec::new();
+        let mut v4 = CidrTrie::default();
+        let mut v6 = CidrTrie::default();
         for ip in ips.iter_mut() {
             let ip = std::mem::take(ip);
             match ip.parse::<IpCidr>() {
-                Ok(cidr) => cidrs.push(cidr),
+                Ok(IpCidr::V4(cidr)) => v4.insert(
+                    (u32::from(cidr.first_address()) as u128) << 96,
+                    cidr.network_length(),
+                ),
+                Ok(IpCidr::V6(cidr)) => {
+                    v6.insert(u128::from(cidr.first_address()), cidr.network_length())
+                }
                 Err(err) => {
                     debug!("parsing cidr {} failed: {}", ip, err);
                 }
             }
-            drop(ip);
         }
-        IpCidrMatcher { values: cidrs }
+        IpCidrMatcher { v4, v6 }
+    }
+}
+
+impl IpCidrMatcher {
+    fn contains(&self, ip: &IpAddr) -> bool {
+        let matched = match ip {
+            IpAddr::V4(v4) => self.v4.contains((u32::from(*v4) as u128) << 96),
+            IpAddr::V6(v6) => self.v6.contains(u128::from(*v6)),
+        };
+        if matched {
+            debug!("[{}] matches ip-cidr rules", ip);
+        }
+        matched
     }
 }
 
 impl Condition for IpCidrMatcher {
     fn apply(&self, sess: &Session) -> bool {
         if !sess.destination.is_domain() {
-            for cidr in &self.values {
-                if let Some(ip) = sess.destination.ip() {
-                    if cidr.contains(&ip) {
-                        debug!("[{}] matches ip-cidr [{}]", ip, &cidr);
-                        return true;
-                    }
-                }
+            if let Some(ip) = sess.destination.ip() {
+                return self.contains(&ip);
             }
         }
         false
     }
 }
 
+struct SourceIpMatcher {
+    matcher: IpCidrMatcher,
+}
+
+impl SourceIpMatcher {
+    fn new(ips: &mut protobuf::RepeatedField<String>) -> Self {
+        Self {
+            matcher: IpCidrMatcher::new(ips),
+        }
+    }
+}
+
+impl Condition for SourceIpMatcher {
+    fn apply(&self, sess: &Session) -> bool {
+        // Dual-stack inbounds see IPv4 sources as IPv4-mapped addresses.
+        let ip = match sess.source.ip() {
+            IpAddr::V6(v6) => v6
+                .to_ipv4_mapped()
+                .map(IpAddr::V4)
+                .unwrap_or(sess.source.ip()),
+            ip => ip,
+        };
+        self.matcher.contains(&ip)
+    }
+}
+
 struct InboundTagMatcher {
     values: Vec<String>,
 }
@@ -131,6 +345,85 @@ impl Condition for InboundTagMatcher {
     }
 }
 
+struct AppMatcher {
+    uids: Vec<u32>,
+}
+
+impl AppMatcher {
+    fn new(apps: &mut protobuf::RepeatedField<String>) -> Self {
+        let mut uids = Vec::new();
+        let mut packages = None;
+        for app in apps.iter_mut() {
+            let app = std::mem::take(app);
+            if let Ok(uid) = app.parse::<u32>() {
+                uids.push(uid);
+                continue;
+            }
+            let packages = packages.get_or_insert_with(process::load_packages);
+            match packages.get(&app) {
+                Some(uid) => uids.push(*uid),
+                None => warn!("uid of app {} not found", &app),
+            }
+        }
+        Self { uids }
+    }
+}
+
+impl Condition for AppMatcher {
+    fn apply(&self, sess: &Session) -> bool {
+        if let Some(uid) = sess.process.as_ref().and_then(|p| p.uid) {
+            if self.uids.contains(&uid) {
+                debug!("[{}] matches app uid [{}]", &sess.source, uid);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+struct ProcessMatcher {
+    names: Vec<String>,
+    paths: Vec<String>,
+}
+
+impl ProcessMatcher {
+    fn new(
+        names: &mut protobuf::RepeatedField<String>,
+        paths: &mut protobuf::RepeatedField<String>,
+    ) -> Self {
+        Self {
+            names: names.iter_mut().map(std::mem::take).collect(),
+            paths: paths.iter_mut().map(std::mem::take).collect(),
+        }
+    }
+}
+
+impl Condition for ProcessMatcher {
+    fn apply(&self, sess: &Session) -> bool {
+        let path = match sess.process.as_ref().and_then(|p| p.path.as_ref()) {
+            Some(path) => path,
+            None => return false,
+        };
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        for v in &self.names {
+            if v == &name {
+                debug!("[{}] matches process name [{}]", &sess.source, v);
+                return true;
+            }
+        }
+        for v in &self.paths {
+            if path == std::path::Path::new(v) {
+                debug!("[{}] matches process path [{}]", &sess.source, v);
+                return true;
+            }
+        }
+        false
+    }
+}
+
 struct NetworkMatcher {
     values: Vec<Network>,
 }
@@ -138,8 +431,8 @@ struct NetworkMatcher {
 impl NetworkMatcher {
     fn new(networks: &mut protobuf::RepeatedField<String>) -> Self {
         let mut values = Vec::new();
-        for net in networks.iter_mut() {
-            match std::mem::take(net).to_uppercase().as_str() {
+        for net in networks.iter().flat_map(|x| x.split(',')) {
+            match net.trim().to_uppercase().as_str() {
                 "TCP" => values.push(Network::Tcp),
                 "UDP" => values.push(Network::Udp),
                 _ => (),
@@ -162,27 +455,48 @@ impl Condition for NetworkMatcher {
 }
 
 struct PortMatcher {
-    condition: Box<dyn Condition>,
+    ranges: Vec<PortRangeMatcher>,
 }
 
 impl PortMatcher {
     fn new(port_ranges: &protobuf::RepeatedField<String>) -> Self {
-        let mut cond_or = ConditionOr::new();
-        for pr in port_ranges.iter() {
-            match PortRangeMatcher::new(pr) {
-                Ok(m) => cond_or.add(Box::new(m)),
+        let mut ranges = Vec::new();
+        // Each may be a list of ranges, e.g. 1000-2000,8443.
+        for pr in port_ranges.iter().flat_map(|x| x.split(',')) {
+            match PortRangeMatcher::new(pr.trim()) {
+                Ok(m) => ranges.push(m),
                 Err(e) => warn!("failed to add port range matcher: {}", e),
             }
         }
-        PortMatcher {
-            condition: Box::new(cond_or),
-        }
+        PortMatcher { ranges }
+    }
+
+    fn contains(&self, port: u16) -> bool {
+        self.ranges.iter().any(|r| r.contains(port))
     }
 }
 
 impl Condition for PortMatcher {
     fn apply(&self, sess: &Session) -> bool {
-        self.condition.apply(sess)
+        self.contains(sess.destination.port())
+    }
+}
+
+struct SourcePortMatcher {
+    matcher: PortMatcher,
+}
+
+impl SourcePortMatcher {
+    fn new(port_ranges: &protobuf::RepeatedField<String>) -> Self {
+        Self {
+            matcher: PortMatcher::new(port_ranges),
+        }
+    }
+}
+
+impl Condition for SourcePortMatcher {
+    fn apply(&self, sess: &Session) -> bool {
+        self.matcher.contains(sess.source.port())
     }
 }
 
@@ -193,7 +507,11 @@ struct PortRangeMatcher {
 
 impl PortRangeMatcher {
     fn new(port_range: &str) -> Result<Self> {
-        let parts: Vec<&str> = port_range.split('-').collect();
+        let mut parts: Vec<&str> = port_range.split('-').collect();
+        // A single port.
+        if parts.len() == 1 {
+            parts.push(parts[0]);
+        }
         if parts.len() != 2 {
             return Err(anyhow!("invalid port range"));
         }
@@ -212,11 +530,8 @@ impl PortRangeMatcher {
         }
         Ok(PortRangeMatcher { start, end })
     }
-}
 
-impl Condition for PortRangeMatcher {
-    fn apply(&self, sess: &Session) -> bool {
-        let port = sess.destination.port();
+    fn contains(&self, port: u16) -> bool {
         if port >= self.start && port <= self.end {
             debug!(
                 "[{}] matches port range [{}-{}]",
@@ -229,6 +544,121 @@ impl Condition for PortRangeMatcher {
     }
 }
 
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+// Days of the week and a time range, e.g. `Mon-Fri 22:00-07:00`, where either
+// part can be omitted for all days or the whole day. A range ending past
+// midnight spans into the next day.
+struct Schedule {
+    // Bit 0 for Monday.
+    days: u8,
+    // In minutes of the day, the end is exclusive.
+    start: u32,
+    end: u32,
+}
+
+impl Schedule {
+    fn new(schedule: &str) -> Result<Self> {
+        let mut days = 0;
+        let (mut start, mut end) = (0, 24 * 60);
+        for part in schedule.split_whitespace() {
+            if let Some((s, e)) = part.split_once('-').filter(|_| part.contains(':')) {
+                start = Self::parse_time(s)?;
+                end = Self::parse_time(e)?;
+                if start == end {
+                    return Err(anyhow!("empty time range {}", part));
+                }
+                continue;
+            }
+            for d in part.split(',').filter(|x| !x.is_empty()) {
+                let (first, last) = d.split_once('-').unwrap_or((d, d));
+                let (first, last) = (Self::parse_day(first)?, Self::parse_day(last)?);
+                let mut i = first;
+                loop {
+                    days |= 1 << i;
+                    if i == last {
+                        break;
+                    }
+                    i = (i + 1) % 7;
+                }
+            }
+        }
+        if days == 0 {
+            days = 0x7f;
+        }
+        Ok(Schedule { days, start, end })
+    }
+
+    fn parse_day(day: &str) -> Result<u32> {
+        let day = day.trim().to_lowercase();
+        WEEKDAYS
+            .iter()
+            .position(|x| day.get(..3) == Some(*x))
+            .map(|x| x as u32)
+            .ok_or_else(|| anyhow!("invalid day {}", day))
+    }
+
+    fn parse_time(time: &str) -> Result<u32> {
+        let (h, m) = time
+            .split_once(':')
+            .ok_or_else(|| anyhow!("invalid time {}", time))?;
+        let h = h.parse::<u32>()?;
+        let m = m.parse::<u32>()?;
+        if m >= 60 || h > 24 || (h == 24 && m > 0) {
+            return Err(anyhow!("invalid time {}", time));
+        }
+        Ok(h * 60 + m)
+    }
+
+    // `weekday` is 0 for Monday.
+    fn contains(&self, weekday: u32, minute: u32) -> bool {
+        let on = |day: u32| self.days & (1 << day) != 0;
+        if self.start < self.end {
+            on(weekday) && minute >= self.start && minute < self.end
+        } else {
+            (on(weekday) && minute >= self.start) || (on((weekday + 6) % 7) && minute < self.end)
+        }
+    }
+}
+
+struct ScheduleMatcher {
+    schedules: Vec<Schedule>,
+}
+
+impl ScheduleMatcher {
+    fn new(schedules: &protobuf::RepeatedField<String>) -> Self {
+        let mut values = Vec::new();
+        for schedule in schedules.iter() {
+            match Schedule::new(schedule) {
+                Ok(s) => values.push(s),
+                Err(e) => warn!("invalid schedule [{}]: {}", schedule, e),
+            }
+        }
+        ScheduleMatcher { schedules: values }
+    }
+
+    fn contains(&self, weekday: u32, minute: u32) -> bool {
+        self.schedules.iter().any(|s| s.contains(weekday, minute))
+    }
+}
+
+impl Condition for ScheduleMatcher {
+    // The schedule is checked when the session is routed, sessions lasting
+    // beyond it are kept.
+    fn apply(&self, sess: &Session) -> bool {
+        let now = chrono::Local::now();
+        if self.contains(
+            now.weekday().num_days_from_monday(),
+            now.hour() * 60 + now.minute(),
+        ) {
+            debug!("[{}] matches schedule", &sess.source);
+            true
+        } else {
+            false
+        }
+    }
+}
+
 struct DomainKeywordMatcher {
     value: String,
 }
@@ -253,42 +683,64 @@ impl Condition for DomainKeywordMatcher {
     }
 }
 
-struct DomainSuffixMatcher {
-    value: String,
+// Domains of suffix and full rules in a trie of their labels from the
+// top-level one, so a lookup takes as many steps as the labels of the
+// destination, however many domains the rules have.
+#[derive(Default)]
+struct DomainTrie {
+    children: HashMap<Box<str>, DomainTrie>,
+    // Matches the domain and its subdomains.
+    suffix: bool,
+    // Matches the domain only.
+    full: bool,
 }
 
-impl DomainSuffixMatcher {
-    fn new(value: String) -> Self {
-        DomainSuffixMatcher { value }
+impl DomainTrie {
+    fn insert(&mut self, domain: &str, suffix: bool) {
+        let mut node = self;
+        for label in domain.rsplit('.') {
+            node = node.children.entry(label.into()).or_default();
+        }
+        if suffix {
+            node.suffix = true;
+        } else {
+            node.full = true;
+        }
     }
-}
 
-// test if domain1 is a subdomain of domain2
-// examples:
-//   video.google.com vs google.com -> true
-//   video.google.com vs gle.com -> false
-//   google.com vs video.google.com -> false
-fn is_sub_domain(d1: &str, d2: &str) -> bool {
-    let d1_parts: Vec<&str> = d1.split('.').rev().collect();
-    let d2_parts: Vec<&str> = d2.split('.').rev().collect();
-    if d1_parts.len() < d2_parts.len() {
-        return false;
+    fn is_empty(&self) -> bool {
+        self.children.is_empty()
     }
-    let d2_enum = d2_parts.iter().enumerate();
-    for (i, v) in d2_enum {
-        if &d1_parts[i] != v {
-            return false;
+
+    // examples with a suffix google.com:
+    //   video.google.com -> true
+    //   google.com -> true
+    //   gle.com -> false
+    fn matches(&self, domain: &str) -> bool {
+        let mut node = self;
+        for label in domain.rsplit('.') {
+            node = match node.children.get(label) {
+                Some(child) => child,
+                None => return false,
+            };
+            if node.suffix {
+                return true;
+            }
         }
+        node.full
     }
-    true
 }
 
-impl Condition for DomainSuffixMatcher {
+struct DomainTrieMatcher {
+    trie: DomainTrie,
+}
+
+impl Condition for DomainTrieMatcher {
     fn apply(&self, sess: &Session) -> bool {
         if sess.destination.is_domain() {
             if let Some(domain) = sess.destination.domain() {
-                if is_sub_domain(domain, &self.value) {
-                    debug!("[{}] matches domain suffix [{}]", domain, &self.value);
+                if self.trie.matches(domain) {
+                    debug!("[{}] matches domain suffix or full rules", domain);
                     return true;
                 }
             }
@@ -297,22 +749,27 @@ impl Condition for DomainSuffixMatcher {
     }
 }
 
-struct DomainFullMatcher {
-    value: String,
+#[cfg(feature = "regex")]
+struct DomainRegexMatcher {
+    value: regex::Regex,
 }
 
-impl DomainFullMatcher {
-    fn new(value: String) -> Self {
-        DomainFullMatcher { value }
+#[cfg(feature = "regex")]
+impl DomainRegexMatcher {
+    fn new(value: String) -> Result<Self> {
+        let value = regex::Regex::new(&value)
+            .map_err(|e| anyhow!("invalid domain regex [{}]: {}", value, e))?;
+        Ok(DomainRegexMatcher { value })
     }
 }
 
-impl Condition for DomainFullMatcher {
+#[cfg(feature = "regex")]
+impl Condition for DomainRegexMatcher {
     fn apply(&self, sess: &Session) -> bool {
         if sess.destination.is_domain() {
             if let Some(domain) = sess.destination.domain() {
-                if domain == &self.value {
-                    debug!("{} matches domain [{}]", domain, &self.value);
+                if self.value.is_match(domain) {
+                    debug!("{} matches domain regex [{}]", domain, self.value);
                     return true;
                 }
             }
@@ -328,6 +785,7 @@ struct DomainMatcher {
 impl DomainMatcher {
     fn new(domains: &mut protobuf::RepeatedField<config::Router_Rule_Domain>) -> Self {
         let mut cond_or = ConditionOr::new();
+        let mut trie = DomainTrie::default();
         for rr_domain in domains.iter_mut() {
             let filter = std::mem::take(&mut rr_domain.value);
             match rr_domain.field_type {
@@ -335,13 +793,26 @@ impl DomainMatcher {
                     cond_or.add(Box::new(DomainKeywordMatcher::new(filter)));
                 }
                 config::Router_Rule_Domain_Type::DOMAIN => {
-                    cond_or.add(Box::new(DomainSuffixMatcher::new(filter)));
+                    trie.insert(&filter, true);
                 }
                 config::Router_Rule_Domain_Type::FULL => {
-                    cond_or.add(Box::new(DomainFullMatcher::new(filter)));
+                    trie.insert(&filter, false);
+                }
+                // Compiled once here instead of on each match.
+                #[cfg(feature = "regex")]
+                config::Router_Rule_Domain_Type::REGEX => match DomainRegexMatcher::new(filter) {
+                    Ok(m) => cond_or.add(Box::new(m)),
+                    Err(e) => warn!("{}", e),
+                },
+                #[cfg(not(feature = "regex"))]
+                config::Router_Rule_Domain_Type::REGEX => {
+                    warn!("domain regex [{}] is not supported in this build", filter);
                 }
             }
         }
+        if !trie.is_empty() {
+            cond_or.add(Box::new(DomainTrieMatcher { trie }));
+        }
         DomainMatcher {
             condition: Box::new(cond_or),
         }
@@ -354,6 +825,28 @@ impl Condition for DomainMatcher {
     }
 }
 
+/// Matches domains by the domain conditions of rules the same way as the
+/// router, e.g. the domains of DNS rules.
+pub struct DomainSet {
+    matcher: DomainMatcher,
+}
+
+impl DomainSet {
+    pub fn new(domains: &mut protobuf::RepeatedField<config::Router_Rule_Domain>) -> Self {
+        DomainSet {
+            matcher: DomainMatcher::new(domains),
+        }
+    }
+
+    pub fn matches(&self, domain: &str) -> bool {
+        let sess = Session {
+            destination: SocksAddr::Domain(domain.to_string(), 0),
+            ..Default::default()
+        };
+        self.matcher.apply(&sess)
+    }
+}
+
 struct ConditionAnd {
     conditions: Vec<Box<dyn Condition>>,
 }
@@ -412,60 +905,197 @@ impl Condition for ConditionOr {
     }
 }
 
+struct ConditionNot {
+    condition: Box<dyn Condition>,
+}
+
+impl ConditionNot {
+    fn new(condition: Box<dyn Condition>) -> Self {
+        ConditionNot { condition }
+    }
+}
+
+impl Condition for ConditionNot {
+    fn apply(&self, sess: &Session) -> bool {
+        !self.condition.apply(sess)
+    }
+}
+
 pub struct Router {
     rules: Vec<Rule>,
     domain_resolve: bool,
+    // Whether the rules match the UIDs or the paths of processes.
+    match_uid: bool,
+    match_path: bool,
     dns_client: SyncDnsClient,
+    #[cfg(feature = "router-script")]
+    script: Option<RouteScript>,
+    script_hits: AtomicU64,
+    misses: AtomicU64,
+    outbound_hits: Mutex<HashMap<String, u64>>,
 }
 
 impl Router {
-    fn load_rules(rules: &mut Vec<Rule>, routing_rules: &mut protobuf::RepeatedField<Router_Rule>) {
-        let mut mmdb_readers: HashMap<String, Arc<maxminddb::Reader<Mmap>>> = HashMap::new();
-        for rr in routing_rules.iter_mut() {
-            let mut cond_and = ConditionAnd::new();
+    // Builds the condition of a rule, the conditions of the fields and the
+    // sub-rules are ANDed.
+    fn load_condition(
+        rr: &mut Router_Rule,
+        mmdbs: &mut HashMap<String, Arc<Mmdb>>,
+    ) -> ConditionAnd {
+        let mut cond_and = ConditionAnd::new();
+
+        if rr.domains.len() > 0 {
+            cond_and.add(Box::new(DomainMatcher::new(&mut rr.domains)));
+        }
 
-            if rr.domains.len() > 0 {
-                cond_and.add(Box::new(DomainMatcher::new(&mut rr.domains)));
-            }
+        if rr.ip_cidrs.len() > 0 {
+            cond_and.add(Box::new(IpCidrMatcher::new(&mut rr.ip_cidrs)));
+        }
 
-            if rr.ip_cidrs.len() > 0 {
-                cond_and.add(Box::new(IpCidrMatcher::new(&mut rr.ip_cidrs)));
+        if rr.mmdbs.len() > 0 {
+            for mmdb in rr.mmdbs.iter() {
+                let reader = mmdbs
+                    .entry(mmdb.file.clone())
+                    .or_insert_with(|| Arc::new(Mmdb::new(mmdb.file.clone())))
+                    .clone();
+                if mmdb.asn != 0 {
+                    cond_and.add(Box::new(AsnMatcher::new(reader, mmdb.asn)));
+                    continue;
+                }
+                cond_and.add(Box::new(MmdbMatcher::new(
+                    reader,
+                    mmdb.country_code.clone(),
+                )));
             }
+        }
 
-            if rr.mmdbs.len() > 0 {
-                for mmdb in rr.mmdbs.iter() {
-                    let reader = match mmdb_readers.get(&mmdb.file) {
-                        Some(r) => r.clone(),
-                        None => match maxminddb::Reader::open_mmap(&mmdb.file) {
-                            Ok(r) => {
-                                let r = Arc::new(r);
-                                mmdb_readers.insert((&mmdb.file).to_owned(), r.clone());
-                                r
-                            }
-                            Err(e) => {
-                                warn!("open mmdb file {} failed: {:?}", mmdb.file, e);
-                                continue;
-                            }
-                        },
-                    };
-                    cond_and.add(Box::new(MmdbMatcher::new(
-                        reader,
-                        mmdb.country_code.clone(),
-                    )));
+        if rr.port_ranges.len() > 0 {
+            cond_and.add(Box::new(PortMatcher::new(&rr.port_ranges)));
+        }
+
+        if rr.source_ips.len() > 0 {
+            cond_and.add(Box::new(SourceIpMatcher::new(&mut rr.source_ips)));
+        }
+
+        if rr.source_ports.len() > 0 {
+            cond_and.add(Box::new(SourcePortMatcher::new(&rr.source_ports)));
+        }
+
+        if rr.networks.len() > 0 {
+            cond_and.add(Box::new(NetworkMatcher::new(&mut rr.networks)));
+        }
+
+        if rr.inbound_tags.len() > 0 {
+            cond_and.add(Box::new(InboundTagMatcher::new(&mut rr.inbound_tags)));
+        }
+
+        if rr.apps.len() > 0 {
+            cond_and.add(Box::new(AppMatcher::new(&mut rr.apps)));
+        }
+
+        if rr.process_names.len() > 0 || rr.process_paths.len() > 0 {
+            cond_and.add(Box::new(ProcessMatcher::new(
+                &mut rr.process_names,
+                &mut rr.process_paths,
+            )));
+        }
+
+        if rr.schedules.len() > 0 {
+            cond_and.add(Box::new(ScheduleMatcher::new(&rr.schedules)));
+        }
+
+        if rr.and_rules.len() > 0 {
+            for sub_rule in rr.and_rules.iter_mut() {
+                let cond = Self::load_condition(sub_rule, mmdbs);
+                if !cond.is_empty() {
+                    cond_and.add(Box::new(cond));
                 }
             }
+        }
 
-            if rr.port_ranges.len() > 0 {
-                cond_and.add(Box::new(PortMatcher::new(&rr.port_ranges)));
+        if rr.or_rules.len() > 0 {
+            let mut cond_or = ConditionOr::new();
+            for sub_rule in rr.or_rules.iter_mut() {
+                let cond = Self::load_condition(sub_rule, mmdbs);
+                if !cond.is_empty() {
+                    cond_or.add(Box::new(cond));
+                }
             }
+            cond_and.add(Box::new(cond_or));
+        }
 
-            if rr.networks.len() > 0 {
-                cond_and.add(Box::new(NetworkMatcher::new(&mut rr.networks)));
+        if rr.not_rules.len() > 0 {
+            let mut cond_or = ConditionOr::new();
+            for sub_rule in rr.not_rules.iter_mut() {
+                let cond = Self::load_condition(sub_rule, mmdbs);
+                if !cond.is_empty() {
+                    cond_or.add(Box::new(cond));
+                }
             }
+            cond_and.add(Box::new(ConditionNot::new(Box::new(cond_or))));
+        }
+
+        cond_and
+    }
 
-            if rr.inbound_tags.len() > 0 {
-                cond_and.add(Box::new(InboundTagMatcher::new(&mut rr.inbound_tags)));
+    fn has_ip_condition(rr: &Router_Rule) -> bool {
+        !rr.ip_cidrs.is_empty()
+            || !rr.mmdbs.is_empty()
+            || rr
+                .and_rules
+                .iter()
+                .chain(rr.or_rules.iter())
+                .chain(rr.not_rules.iter())
+                .any(Self::has_ip_condition)
+    }
+
+    // Describes the conditions by their types and first values, e.g.
+    // `domain google.com (+2), network tcp`.
+    fn describe(rr: &Router_Rule) -> String {
+        fn add(desc: &mut Vec<String>, kind: &str, values: &[String]) {
+            match values {
+                [] => (),
+                [v] => desc.push(format!("{} {}", kind, v)),
+                [v, ..] => desc.push(format!("{} {} (+{})", kind, v, values.len() - 1)),
+            }
+        }
+        let mut desc = Vec::new();
+        let domains: Vec<String> = rr.domains.iter().map(|d| d.value.clone()).collect();
+        add(&mut desc, "domain", &domains);
+        add(&mut desc, "ip-cidr", &rr.ip_cidrs);
+        let (asns, mmdbs): (Vec<_>, Vec<_>) = rr.mmdbs.iter().partition(|m| m.asn != 0);
+        let mmdbs: Vec<String> = mmdbs.iter().map(|m| m.country_code.clone()).collect();
+        add(&mut desc, "geoip", &mmdbs);
+        let asns: Vec<String> = asns.iter().map(|m| m.asn.to_string()).collect();
+        add(&mut desc, "asn", &asns);
+        add(&mut desc, "port", &rr.port_ranges);
+        add(&mut desc, "source-ip", &rr.source_ips);
+        add(&mut desc, "source-port", &rr.source_ports);
+        add(&mut desc, "network", &rr.networks);
+        add(&mut desc, "inbound-tag", &rr.inbound_tags);
+        add(&mut desc, "app", &rr.apps);
+        add(&mut desc, "process-name", &rr.process_names);
+        add(&mut desc, "process-path", &rr.process_paths);
+        add(&mut desc, "schedule", &rr.schedules);
+        for (kind, sub_rules) in [
+            ("and", &rr.and_rules),
+            ("or", &rr.or_rules),
+            ("not", &rr.not_rules),
+        ] {
+            if !sub_rules.is_empty() {
+                let subs: Vec<String> = sub_rules.iter().map(Self::describe).collect();
+                desc.push(format!("{}(({}))", kind, subs.join("), (")));
             }
+        }
+        desc.join(", ")
+    }
+
+    fn load_rules(rules: &mut Vec<Rule>, routing_rules: &mut protobuf::RepeatedField<Router_Rule>) {
+        let mut mmdbs: HashMap<String, Arc<Mmdb>> = HashMap::new();
+        for rr in routing_rules.iter_mut() {
+            let resolve = !rr.no_resolve && Self::has_ip_condition(rr);
+            let desc = Self::describe(rr);
+            let cond_and = Self::load_condition(rr, &mut mmdbs);
 
             if cond_and.is_empty() {
                 warn!("empty rule at target {}", rr.target_tag);
@@ -473,7 +1103,24 @@ impl Router {
             }
 
             let tag = std::mem::take(&mut rr.target_tag);
-            rules.push(Rule::new(tag, Box::new(cond_and)));
+            let mut rule = Rule::new(tag, Box::new(cond_and));
+            rule.resolve = resolve;
+            rule.desc = desc;
+            rules.push(rule);
+        }
+    }
+
+    #[cfg(feature = "router-script")]
+    fn load_script(router: &config::Router) -> Option<RouteScript> {
+        if router.script.is_empty() {
+            return None;
+        }
+        match RouteScript::load(&router.script) {
+            Ok(script) => Some(script),
+            Err(e) => {
+                warn!("load route script failed: {}", e);
+                None
+            }
         }
     }
 
@@ -483,65 +1130,163 @@ impl Router {
     ) -> Self {
         let mut rules: Vec<Rule> = Vec::new();
         let mut domain_resolve = false;
+        let mut match_uid = false;
+        let mut match_path = false;
+        #[cfg(feature = "router-script")]
+        let mut script = None;
         if let Some(router) = router.as_mut() {
+            (match_uid, match_path) = Self::process_conditions(&router.rules);
             Self::load_rules(&mut rules, &mut router.rules);
             domain_resolve = router.domain_resolve;
+            #[cfg(feature = "router-script")]
+            {
+                script = Self::load_script(router);
+            }
+            #[cfg(not(feature = "router-script"))]
+            if !router.script.is_empty() {
+                warn!("route script is not supported in this build");
+            }
         }
         Router {
             rules,
             domain_resolve,
+            match_uid,
+            match_path,
             dns_client,
+            #[cfg(feature = "router-script")]
+            script,
+            script_hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            outbound_hits: Mutex::new(HashMap::new()),
         }
     }
 
-    pub fn reload(
-        &mut self,
-        router: &mut protobuf::SingularPtrField<config::Router>,
-    ) -> Result<()> {
-        self.rules.clear();
-        if let Some(router) = router.as_mut() {
-            Self::load_rules(&mut self.rules, &mut router.rules);
-            self.domain_resolve = router.domain_resolve;
+    // Returns whether any rule matches the UIDs or the paths of processes.
+    fn process_conditions(rules: &[Router_Rule]) -> (bool, bool) {
+        let (mut uid, mut path) = (false, false);
+        for rr in rules {
+            uid |= !rr.apps.is_empty();
+            path |= !rr.process_names.is_empty() || !rr.process_paths.is_empty();
+            for sub_rules in [&rr.and_rules, &rr.or_rules, &rr.not_rules] {
+                let (sub_uid, sub_path) = Self::process_conditions(sub_rules);
+                uid |= sub_uid;
+                path |= sub_path;
+            }
         }
-        Ok(())
+        (uid, path)
     }
 
-    pub async fn pick_route(&self, sess: &Session) -> Result<&String> {
+    /// Finds the process of the session if the rules match processes, which
+    /// is done before `pick_route` once per session.
+    pub async fn resolve_process(&self, sess: &mut Session) {
+        if sess.process.is_some() || !(self.match_uid || self.match_path) {
+            return;
+        }
+        // The local address of the inbound is the remote address of the
+        // socket in the process.
+        let process = process::resolve(
+            sess.network,
+            sess.source,
+            sess.local_addr,
+            self.match_uid,
+            self.match_path,
+        )
+        .await;
+        sess.process = Some(Arc::new(process));
+    }
+
+    pub async fn pick_route(&self, sess: &Session) -> Result<String> {
+        // The script goes first, and leaves the sessions it doesn't pick
+        // outbounds for to the rules.
+        #[cfg(feature = "router-script")]
+        if let Some(tag) = self.script.as_ref().and_then(|s| s.route(sess)) {
+            debug!("route script picks [{}]", tag);
+            self.script_hits.fetch_add(1, Ordering::Relaxed);
+            self.hit_outbound(&tag);
+            return Ok(tag);
+        }
+        // A domain destination is resolved on the first rule with IP
+        // conditions it doesn't match, so the rules are still matched in
+        // order and the sessions matching domain rules are never resolved.
+        let mut resolved: Option<Option<Session>> = None;
         for rule in &self.rules {
             if rule.apply(sess) {
-                return Ok(&rule.target);
-            }
-        }
-        if sess.destination.is_domain() && self.domain_resolve {
-            let ips = {
-                self.dns_client
-                    .read()
-                    .await
-                    .lookup(
-                        sess.destination
-                            .domain()
-                            .ok_or_else(|| anyhow!("illegal domain name"))?,
-                    )
-                    .map_err(|e| anyhow!("lookup {} failed: {}", sess.destination.host(), e))
-                    .await?
-            };
-            if !ips.is_empty() {
-                let mut new_sess = sess.clone();
-                new_sess.destination = SocksAddr::from((ips[0], sess.destination.port()));
-                log::trace!(
-                    "re-matching with resolved ip [{}] for [{}]",
-                    ips[0],
-                    sess.destination.host()
-                );
-                for rule in &self.rules {
-                    if rule.apply(&new_sess) {
-                        return Ok(&rule.target);
-                    }
+                return Ok(self.hit(rule));
+            }
+            if !(rule.resolve && self.domain_resolve && sess.destination.is_domain()) {
+                continue;
+            }
+            if resolved.is_none() {
+                resolved = Some(self.resolve(sess).await);
+            }
+            if let Some(Some(resolved_sess)) = resolved.as_ref() {
+                if rule.apply(resolved_sess) {
+                    return Ok(self.hit(rule));
                 }
             }
         }
+        self.misses.fetch_add(1, Ordering::Relaxed);
         Err(anyhow!("no matching rules"))
     }
+
+    fn hit(&self, rule: &Rule) -> String {
+        rule.hits.fetch_add(1, Ordering::Relaxed);
+        self.hit_outbound(&rule.target);
+        rule.target.clone()
+    }
+
+    /// Counts a session routed to the outbound, which is done by
+    /// `pick_route` for the sessions it routes.
+    pub fn hit_outbound(&self, tag: &str) {
+        let mut outbound_hits = self.outbound_hits.lock().unwrap();
+        if let Some(hits) = outbound_hits.get_mut(tag) {
+            *hits += 1;
+        } else {
+            outbound_hits.insert(tag.to_string(), 1);
+        }
+    }
+
+    pub fn stats(&self) -> RouteStats {
+        let mut outbounds: Vec<(String, u64)> = self
+            .outbound_hits
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(tag, hits)| (tag.clone(), *hits))
+            .collect();
+        outbounds.sort();
+        RouteStats {
+            rules: self
+                .rules
+                .iter()
+                .map(|r| RuleStat {
+                    target: r.target.clone(),
+                    desc: r.desc.clone(),
+                    hits: r.hits.load(Ordering::Relaxed),
+                })
+                .collect(),
+            script_hits: self.script_hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            outbounds,
+        }
+    }
+
+    // Returns the session with the domain destination resolved to an IP.
+    async fn resolve(&self, sess: &Session) -> Option<Session> {
+        let domain = sess.destination.domain()?;
+        let ips = match self.dns_client.read().await.lookup(domain).await {
+            Ok(ips) => ips,
+            Err(e) => {
+                debug!("lookup {} failed: {}", domain, e);
+                return None;
+            }
+        };
+        let ip = *ips.first()?;
+        log::trace!("matching with resolved ip [{}] for [{}]", ip, domain);
+        let mut new_sess = sess.clone();
+        new_sess.destination = SocksAddr::from((ip, sess.destination.port()));
+        Some(new_sess)
+    }
 }
 
 #[cfg(test)]
@@ -551,14 +1296,187 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_is_sub_domain() {
-        let d1 = "video.google.com".to_string();
-        let d2 = "google.com".to_string();
-        assert!(is_sub_domain(&d1, &d2));
+    fn test_ip_cidr_matcher() {
+        let mut ips = protobuf::RepeatedField::from_vec(vec![
+            "10.0.0.0/8".to_string(),
+            "10.1.0.0/16".to_string(),
+            "192.168.1.1/32".to_string(),
+            "2001:db8::/32".to_string(),
+            "invalid".to_string(),
+        ]);
+        let m = IpCidrMatcher::new(&mut ips);
+        for (ip, matched) in [
+            ("10.1.2.3", true),
+            ("10.255.255.255", true),
+            ("11.0.0.0", false),
+            ("192.168.1.1", true),
+            ("192.168.1.2", false),
+            ("2001:db8:1::1", true),
+            ("2001:db9::1", false),
+            // IPv4 networks don't contain IPv4-mapped addresses.
+            ("::ffff:10.0.0.1", false),
+        ] {
+            assert_eq!(m.contains(&ip.parse().unwrap()), matched, "{}", ip);
+        }
+
+        let mut ips = protobuf::RepeatedField::from_vec(vec!["0.0.0.0/0".to_string()]);
+        let m = IpCidrMatcher::new(&mut ips);
+        assert!(m.contains(&"1.2.3.4".parse().unwrap()));
+        assert!(!m.contains(&"::1".parse().unwrap()));
+    }
+
+    // cargo test --release -- --ignored --nocapture bench_ip_cidr_matcher
+    #[test]
+    #[ignore]
+    fn bench_ip_cidr_matcher() {
+        // 16k /24 networks in 10.0.0.0/10 and 16k /48 ones in 2001:db8::/32.
+        let mut ips = protobuf::RepeatedField::new();
+        for i in 0..16384u32 {
+            ips.push(format!("10.{}.{}.0/24", i >> 8, i & 0xff));
+            ips.push(format!("2001:db8:{:x}::/48", i));
+        }
+        let m = IpCidrMatcher::new(&mut ips);
+        let addrs: Vec<IpAddr> = (0..10_000u32)
+            .map(|i| {
+                if i % 2 == 0 {
+                    IpAddr::from([10, (i >> 8) as u8 & 0x7f, i as u8, 1])
+                } else {
+                    format!("2001:db9:{:x}::1", i).parse().unwrap()
+                }
+            })
+            .collect();
+        let start = std::time::Instant::now();
+        let matched = addrs.iter().filter(|ip| m.contains(ip)).count();
+        println!(
+            "matched {} of {} addresses, {:?} per address",
+            matched,
+            addrs.len(),
+            start.elapsed() / addrs.len() as u32
+        );
+        assert_eq!(matched, addrs.len() / 2);
+    }
+
+    #[test]
+    fn test_domain_trie() {
+        let mut trie = DomainTrie::default();
+        trie.insert("google.com", true);
+        trie.insert("www.example.com", false);
+        assert!(trie.matches("video.google.com"));
+        assert!(trie.matches("google.com"));
+        assert!(!trie.matches("gle.com"));
+        assert!(!trie.matches("wwwgoogle.com"));
+        assert!(!trie.matches("com"));
+        assert!(trie.matches("www.example.com"));
+        assert!(!trie.matches("example.com"));
+        assert!(!trie.matches("a.www.example.com"));
+
+        // A suffix covers the full domains under it.
+        trie.insert("example.com", true);
+        assert!(trie.matches("a.www.example.com"));
+    }
+
+    // cargo test --release -- --ignored --nocapture bench_domain_matcher
+    #[test]
+    #[ignore]
+    fn bench_domain_matcher() {
+        let mut domains = protobuf::RepeatedField::new();
+        for i in 0..100_000 {
+            let mut domain = config::Router_Rule_Domain::new();
+            domain.field_type = config::Router_Rule_Domain_Type::DOMAIN;
+            domain.value = format!("ads{}.example{}.com", i, i % 100);
+            domains.push(domain);
+        }
+        let start = std::time::Instant::now();
+        let m = DomainMatcher::new(&mut domains);
+        println!("built 100k domains in {:?}", start.elapsed());
+
+        let sessions: Vec<Session> = (0..10_000)
+            .map(|i| Session {
+                destination: SocksAddr::Domain(
+                    format!("cdn.ads{}.example{}.com", i * 7, i * 7 % 100 + i % 2),
+                    443,
+                ),
+                ..Default::default()
+            })
+            .collect();
+        let start = std::time::Instant::now();
+        let matched = sessions.iter().filter(|s| m.apply(s)).count();
+        println!(
+            "matched {} of {} sessions, {:?} per session",
+            matched,
+            sessions.len(),
+            start.elapsed() / sessions.len() as u32
+        );
+        assert_eq!(matched, sessions.len() / 2);
+    }
 
-        let d1 = "video.google.com".to_string();
-        let d2 = "gle.com".to_string();
-        assert!(!is_sub_domain(&d1, &d2));
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_domain_regex_matcher() {
+        let mut domains = protobuf::RepeatedField::new();
+        for v in [r"^ad[s0-9]*\.", r"(^|\.)example\.(com|net)$", "("] {
+            let mut domain = config::Router_Rule_Domain::new();
+            domain.field_type = config::Router_Rule_Domain_Type::REGEX;
+            domain.value = v.to_string();
+            domains.push(domain);
+        }
+        // The invalid one is skipped.
+        let m = DomainMatcher::new(&mut domains);
+        let mut sess = Session {
+            destination: SocksAddr::Domain("ads1.google.com".to_string(), 443),
+            ..Default::default()
+        };
+        assert!(m.apply(&sess));
+        sess.destination = SocksAddr::Domain("www.example.net".to_string(), 443);
+        assert!(m.apply(&sess));
+        sess.destination = SocksAddr::Domain("example.org".to_string(), 443);
+        assert!(!m.apply(&sess));
+        sess.destination = SocksAddr::Domain("badexample.com".to_string(), 443);
+        assert!(!m.apply(&sess));
+        sess.destination = SocksAddr::Ip("1.1.1.1:443".parse().unwrap());
+        assert!(!m.apply(&sess));
+    }
+
+    #[test]
+    fn test_logical_rules() {
+        let sub_rule = |domain: &str, network: &str| {
+            let mut rule = Router_Rule::new();
+            if !domain.is_empty() {
+                let mut d = config::Router_Rule_Domain::new();
+                d.field_type = config::Router_Rule_Domain_Type::DOMAIN;
+                d.value = domain.to_string();
+                rule.domains.push(d);
+            }
+            if !network.is_empty() {
+                rule.networks.push(network.to_string());
+            }
+            rule
+        };
+        // netflix.com AND udp
+        let mut rr = Router_Rule::new();
+        rr.and_rules.push(sub_rule("netflix.com", ""));
+        rr.and_rules.push(sub_rule("", "udp"));
+        let and = Router::load_condition(&mut rr, &mut HashMap::new());
+        // google.com OR (NOT tcp)
+        let mut not_rule = Router_Rule::new();
+        not_rule.not_rules.push(sub_rule("", "tcp"));
+        let mut rr = Router_Rule::new();
+        rr.or_rules.push(sub_rule("google.com", ""));
+        rr.or_rules.push(not_rule);
+        let or = Router::load_condition(&mut rr, &mut HashMap::new());
+
+        let mut sess = Session {
+            network: Network::Udp,
+            destination: SocksAddr::Domain("www.netflix.com".to_string(), 443),
+            ..Default::default()
+        };
+        assert!(and.apply(&sess));
+        assert!(or.apply(&sess));
+        sess.network = Network::Tcp;
+        assert!(!and.apply(&sess));
+        assert!(!or.apply(&sess));
+        sess.destination = SocksAddr::Domain("www.google.com".to_string(), 443);
+        assert!(or.apply(&sess));
     }
 
     #[test]
@@ -587,10 +1505,21 @@ mod tests {
         sess.destination = SocksAddr::Domain("www.google.com".to_string(), 22);
         assert!(m.apply(&sess));
 
+        // test lists of port ranges and single ports
+        let m = PortMatcher::new(&protobuf::RepeatedField::from_vec(vec![
+            "1000-2000, 8443".to_string()
+        ]));
+        sess.destination = SocksAddr::Domain("www.google.com".to_string(), 1500);
+        assert!(m.apply(&sess));
+        sess.destination = SocksAddr::Domain("www.google.com".to_string(), 8443);
+        assert!(m.apply(&sess));
+        sess.destination = SocksAddr::Domain("www.google.com".to_string(), 8444);
+        assert!(!m.apply(&sess));
+
         // test invalid port ranges
         let m = PortRangeMatcher::new("22-21");
         assert!(m.is_err());
-        let m = PortRangeMatcher::new("22");
+        let m = PortRangeMatcher::new("");
         assert!(m.is_err());
         let m = PortRangeMatcher::new("22-");
         assert!(m.is_err());
@@ -601,4 +1530,243 @@ mod tests {
         let m = PortRangeMatcher::new("22-23-24");
         assert!(m.is_err());
     }
+
+    #[test]
+    fn test_source_matchers() {
+        let m = SourceIpMatcher::new(&mut protobuf::RepeatedField::from_vec(vec![
+            "192.168.1.0/24".to_string(),
+        ]));
+        let mut sess = Session {
+            source: "192.168.1.10:50000".parse().unwrap(),
+            ..Default::default()
+        };
+        assert!(m.apply(&sess));
+        sess.source = "[::ffff:192.168.1.10]:50000".parse().unwrap();
+        assert!(m.apply(&sess));
+        sess.source = "192.168.2.10:50000".parse().unwrap();
+        assert!(!m.apply(&sess));
+
+        let m = SourcePortMatcher::new(&protobuf::RepeatedField::from_vec(vec![
+            "50000-50100".to_string()
+        ]));
+        assert!(m.apply(&sess));
+        sess.source = "192.168.2.10:443".parse().unwrap();
+        assert!(!m.apply(&sess));
+    }
+
+    #[test]
+    fn test_network_matcher() {
+        let m = NetworkMatcher::new(&mut protobuf::RepeatedField::from_vec(vec![
+            "tcp, UDP".to_string()
+        ]));
+        let mut sess = Session {
+            network: Network::Tcp,
+            ..Default::default()
+        };
+        assert!(m.apply(&sess));
+        sess.network = Network::Udp;
+        assert!(m.apply(&sess));
+        let m = NetworkMatcher::new(&mut protobuf::RepeatedField::from_vec(vec![
+            "udp".to_string()
+        ]));
+        sess.network = Network::Tcp;
+        assert!(!m.apply(&sess));
+    }
+
+    #[test]
+    fn test_inbound_tag_matcher() {
+        let m = InboundTagMatcher::new(&mut protobuf::RepeatedField::from_vec(vec![
+            "socks".to_string(),
+            "tun".to_string(),
+        ]));
+        let mut sess = Session {
+            inbound_tag: "tun".to_string(),
+            ..Default::default()
+        };
+        assert!(m.apply(&sess));
+        sess.inbound_tag = "http".to_string();
+        assert!(!m.apply(&sess));
+    }
+
+    #[test]
+    fn test_mmdb_matcher() {
+        let mmdb = Arc::new(Mmdb::new("/nonexistent/geo.mmdb".to_string()));
+        let m = MmdbMatcher::new(mmdb.clone(), "cn".to_string());
+        let mut sess = Session {
+            destination: SocksAddr::Domain("www.google.com".to_string(), 443),
+            ..Default::default()
+        };
+        // Domains never open the file.
+        assert!(!m.apply(&sess));
+        assert!(mmdb.reader.read().unwrap().is_none());
+        sess.destination = SocksAddr::Ip("1.1.1.1:443".parse().unwrap());
+        assert!(!m.apply(&sess));
+        assert!(matches!(*mmdb.reader.read().unwrap(), Some(None)));
+        let m = AsnMatcher::new(mmdb, 13335);
+        assert!(!m.apply(&sess));
+    }
+
+    #[test]
+    fn test_route_stats() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let mut dns = config::Dns::new();
+            dns.servers.push("127.0.0.1".to_string());
+            let dns_client = crate::app::dns_client::DnsClient::new(&Some(dns).into()).unwrap();
+            let mut config = config::Router::new();
+            let mut rr = Router_Rule::new();
+            rr.target_tag = "Direct".to_string();
+            rr.ip_cidrs.push("10.0.0.0/8".to_string());
+            rr.ip_cidrs.push("192.168.0.0/16".to_string());
+            config.rules.push(rr);
+            let mut rr = Router_Rule::new();
+            rr.target_tag = "Reject".to_string();
+            rr.networks.push("udp".to_string());
+            config.rules.push(rr);
+            let router = Router::new(
+                &mut Some(config).into(),
+                Arc::new(tokio::sync::RwLock::new(dns_client)),
+            );
+
+            let mut sess = Session {
+                destination: SocksAddr::Ip("10.0.0.1:443".parse().unwrap()),
+                ..Default::default()
+            };
+            assert_eq!(router.pick_route(&sess).await.unwrap(), "Direct");
+            assert_eq!(router.pick_route(&sess).await.unwrap(), "Direct");
+            sess.destination = SocksAddr::Ip("1.1.1.1:443".parse().unwrap());
+            assert!(router.pick_route(&sess).await.is_err());
+            router.hit_outbound("Proxy");
+
+            let stats = router.stats();
+            assert_eq!(stats.rules.len(), 2);
+            assert_eq!(stats.rules[0].desc, "ip-cidr 10.0.0.0/8 (+1)");
+            assert_eq!(stats.rules[0].hits, 2);
+            assert_eq!(stats.rules[1].desc, "network udp");
+            assert_eq!(stats.rules[1].hits, 0);
+            assert_eq!(stats.misses, 1);
+            assert_eq!(
+                stats.outbounds,
+                vec![("Direct".to_string(), 2), ("Proxy".to_string(), 1)]
+            );
+        });
+    }
+
+    #[test]
+    fn test_process_matcher() {
+        let mut rr = Router_Rule::new();
+        rr.target_tag = "Proxy".to_string();
+        let mut sub_rule = Router_Rule::new();
+        sub_rule.process_names.push("curl".to_string());
+        rr.and_rules.push(sub_rule);
+        assert_eq!(Router::process_conditions(&[rr]), (false, true));
+
+        let m = ProcessMatcher::new(
+            &mut protobuf::RepeatedField::from_vec(vec!["curl".to_string()]),
+            &mut protobuf::RepeatedField::from_vec(vec!["/usr/bin/firefox".to_string()]),
+        );
+        // Sessions not resolved don't match.
+        let mut sess = Session::default();
+        assert!(!m.apply(&sess));
+        for (path, matched) in [
+            ("/usr/local/bin/curl", true),
+            ("/usr/bin/firefox", true),
+            ("/usr/bin/wget", false),
+        ] {
+            sess.process = Some(Arc::new(process::ProcessInfo {
+                uid: None,
+                path: Some(path.into()),
+            }));
+            assert_eq!(m.apply(&sess), matched, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_logical_process_rules() {
+        let process_rule = |name: &str| {
+            let mut rule = Router_Rule::new();
+            rule.process_names.push(name.to_string());
+            rule
+        };
+        // curl OR wget
+        let mut or_rule = Router_Rule::new();
+        or_rule.or_rules.push(process_rule("curl"));
+        or_rule.or_rules.push(process_rule("wget"));
+        // NOT firefox
+        let mut not_rule = Router_Rule::new();
+        not_rule.not_rules.push(process_rule("firefox"));
+        // The processes are resolved for rules nested in any logical rule.
+        assert_eq!(
+            Router::process_conditions(&[or_rule.clone()]),
+            (false, true)
+        );
+        assert_eq!(
+            Router::process_conditions(&[not_rule.clone()]),
+            (false, true)
+        );
+        let mut app_rule = Router_Rule::new();
+        app_rule.apps.push("com.example.app".to_string());
+        let mut rr = Router_Rule::new();
+        rr.not_rules.push(app_rule);
+        assert_eq!(Router::process_conditions(&[rr]), (true, false));
+
+        let or = Router::load_condition(&mut or_rule, &mut HashMap::new());
+        let not = Router::load_condition(&mut not_rule, &mut HashMap::new());
+        let mut sess = Session::default();
+        for (path, or_matched, not_matched) in [
+            ("/usr/bin/curl", true, true),
+            ("/usr/bin/wget", true, true),
+            ("/usr/bin/firefox", false, false),
+        ] {
+            sess.process = Some(Arc::new(process::ProcessInfo {
+                uid: None,
+                path: Some(path.into()),
+            }));
+            assert_eq!(or.apply(&sess), or_matched, "{}", path);
+            assert_eq!(not.apply(&sess), not_matched, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_schedule_matcher() {
+        let m = ScheduleMatcher::new(&protobuf::RepeatedField::from_vec(vec![
+            "Mon-Fri 22:00-07:00".to_string(),
+            "Sat,Sun".to_string(),
+            "Fri-Mon 25:00-26:00".to_string(),
+        ]));
+        assert_eq!(m.schedules.len(), 2);
+        let hm = |h: u32, m: u32| h * 60 + m;
+        for (weekday, minute, matched) in [
+            (0, hm(22, 0), true),
+            (0, hm(21, 59), false),
+            (0, hm(12, 0), false),
+            // Past midnight of Monday.
+            (1, hm(6, 59), true),
+            (1, hm(7, 0), false),
+            // Past midnight of Sunday, which is not in the first schedule.
+            (0, hm(6, 0), false),
+            (4, hm(23, 0), true),
+            (5, hm(12, 0), true),
+            (6, hm(0, 0), true),
+        ] {
+            assert_eq!(
+                m.contains(weekday, minute),
+                matched,
+                "{} {}",
+                weekday,
+                minute
+            );
+        }
+
+        let s = Schedule::new("Fri-Mon").unwrap();
+        assert_eq!(s.days, 0b111_0001);
+        let s = Schedule::new("Wednesday 08:30-24:00").unwrap();
+        assert!(s.contains(2, hm(23, 59)));
+        assert!(!s.contains(2, hm(8, 29)));
+        assert!(Schedule::new("Mon 10:00-10:00").is_err());
+        assert!(Schedule::new("Someday").is_err());
+    }
 }
@@ -132,6 +132,20 @@ pub fn add_interface_ipv4_address(
     Ok(())
 }
 
+pub fn set_interface_up(name: &str, mtu: i32) -> Result<()> {
+    Command::new("ip")
+        .arg("link")
+        .arg("set")
+        .arg("dev")
+        .arg(name.to_string())
+        .arg("mtu")
+        .arg(mtu.to_string())
+        .arg("up")
+        .status()
+        .expect("failed to execute command");
+    Ok(())
+}
+
 pub fn add_interface_ipv6_address(name: &str, addr: Ipv6Addr, prefixlen: i32) -> Result<()> {
     Command::new("ip")
         .arg("-6")
@@ -207,6 +221,64 @@ pub fn add_default_ipv6_route(gateway: Ipv6Addr, interface: String, primary: boo
     Ok(())
 }
 
+pub fn add_ipv4_host_route(addr: Ipv4Addr, gateway: Ipv4Addr, interface: String) -> Result<()> {
+    Command::new("ip")
+        .arg("route")
+        .arg("add")
+        .arg(addr.to_string())
+        .arg("via")
+        .arg(gateway.to_string())
+        .arg("dev")
+        .arg(interface)
+        .arg("table")
+        .arg("main")
+        .status()
+        .expect("failed to execute command");
+    Ok(())
+}
+
+pub fn add_ipv6_host_route(addr: Ipv6Addr, gateway: Ipv6Addr, interface: String) -> Result<()> {
+    Command::new("ip")
+        .arg("-6")
+        .arg("route")
+        .arg("add")
+        .arg(addr.to_string())
+        .arg("via")
+        .arg(gateway.to_string())
+        .arg("dev")
+        .arg(interface)
+        .arg("table")
+        .arg("main")
+        .status()
+        .expect("failed to execute command");
+    Ok(())
+}
+
+pub fn delete_ipv4_host_route(addr: Ipv4Addr) -> Result<()> {
+    Command::new("ip")
+        .arg("route")
+        .arg("del")
+        .arg(addr.to_string())
+        .arg("table")
+        .arg("main")
+        .status()
+        .expect("failed to execute command");
+    Ok(())
+}
+
+pub fn delete_ipv6_host_route(addr: Ipv6Addr) -> Result<()> {
+    Command::new("ip")
+        .arg("-6")
+        .arg("route")
+        .arg("del")
+        .arg(addr.to_string())
+        .arg("table")
+        .arg("main")
+        .status()
+        .expect("failed to execute command");
+    Ok(())
+}
+
 pub fn delete_default_ipv4_route(ifscope: Option<String>) -> Result<()> {
     if let Some(ifscope) = ifscope {
         Command::new("ip")
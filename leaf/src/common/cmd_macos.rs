@@ -151,6 +151,74 @@ pub fn add_default_ipv6_route(gateway: Ipv6Addr, interface: String, primary: boo
     Ok(())
 }
 
+pub fn add_ipv4_host_route(addr: Ipv4Addr, gateway: Ipv4Addr, _interface: String) -> Result<()> {
+    Command::new("route")
+        .arg("add")
+        .arg("-inet")
+        .arg("-host")
+        .arg(addr.to_string())
+        .arg(gateway.to_string())
+        .status()
+        .expect("failed to execute command");
+    Ok(())
+}
+
+pub fn add_ipv6_host_route(addr: Ipv6Addr, gateway: Ipv6Addr, interface: String) -> Result<()> {
+    // FIXME https://doc.rust-lang.org/std/net/struct.Ipv6Addr.html#method.is_global
+    let gw = if (gateway.segments()[0] & 0xffc0) == 0xfe80 {
+        format!("{}%{}", gateway.to_string(), interface)
+    } else {
+        gateway.to_string()
+    };
+    Command::new("route")
+        .arg("add")
+        .arg("-inet6")
+        .arg("-host")
+        .arg(addr.to_string())
+        .arg(gw)
+        .status()
+        .expect("failed to execute command");
+    Ok(())
+}
+
+/// Routes the address to the interface directly, it keeps the original
+/// gateway reachable on the physical interface once the default route is
+/// taken over by the TUN device.
+pub fn add_ipv4_interface_route(addr: Ipv4Addr, interface: String) -> Result<()> {
+    Command::new("route")
+        .arg("add")
+        .arg("-inet")
+        .arg("-host")
+        .arg(addr.to_string())
+        .arg("-interface")
+        .arg(interface)
+        .status()
+        .expect("failed to execute command");
+    Ok(())
+}
+
+pub fn delete_ipv4_host_route(addr: Ipv4Addr) -> Result<()> {
+    Command::new("route")
+        .arg("delete")
+        .arg("-inet")
+        .arg("-host")
+        .arg(addr.to_string())
+        .status()
+        .expect("failed to execute command");
+    Ok(())
+}
+
+pub fn delete_ipv6_host_route(addr: Ipv6Addr) -> Result<()> {
+    Command::new("route")
+        .arg("delete")
+        .arg("-inet6")
+        .arg("-host")
+        .arg(addr.to_string())
+        .status()
+        .expect("failed to execute command");
+    Ok(())
+}
+
 pub fn delete_default_ipv4_route(ifscope: Option<String>) -> Result<()> {
     if let Some(ifscope) = ifscope {
         Command::new("route")
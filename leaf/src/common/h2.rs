@@ -0,0 +1,62 @@
+//! Helpers of the HTTP/2 clients, which multiplex their requests over a
+//! single connection.
+
+use std::future::Future;
+use std::io;
+
+use bytes::Bytes;
+use h2::client::{Connection, SendRequest};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::Mutex;
+
+pub fn h2_err(e: h2::Error) -> io::Error {
+    if e.is_io() {
+        e.into_io().unwrap()
+    } else {
+        io::Error::new(io::ErrorKind::Other, e)
+    }
+}
+
+/// Drives the connection in a task until it's closed, `name` and `peer` are
+/// for logging.
+pub fn spawn_connection<T>(connection: Connection<T, Bytes>, name: &'static str, peer: String)
+where
+    T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            log::debug!("{} connection to {} closed: {}", name, peer, e);
+        }
+    });
+}
+
+/// A connection shared by the requests, a new one is made when it's closed.
+#[derive(Default)]
+pub struct SharedConnection(Mutex<Option<SendRequest<Bytes>>>);
+
+impl SharedConnection {
+    /// Returns the connection once it's ready for a new request, `connect` is
+    /// called if there's none or it's closed.
+    pub async fn get<F, Fut, E>(&self, connect: F) -> Result<SendRequest<Bytes>, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<SendRequest<Bytes>, E>>,
+        E: From<io::Error>,
+    {
+        let mut connection = self.0.lock().await;
+        if let Some(c) = connection.clone() {
+            if let Ok(c) = c.ready().await {
+                return Ok(c);
+            }
+        }
+        let c = connect().await?.ready().await.map_err(h2_err)?;
+        *connection = Some(c.clone());
+        Ok(c)
+    }
+
+    /// Drops the connection so that the next request makes a new one, e.g.
+    /// after the server has sent a GOAWAY.
+    pub async fn reset(&self) {
+        self.0.lock().await.take();
+    }
+}
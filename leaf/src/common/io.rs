@@ -4,6 +4,7 @@ use std::pin::Pin;
 use std::task::{Context, Poll};
 use std::time::Duration;
 
+use bytes::{Buf, Bytes};
 use futures::ready;
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
@@ -132,10 +133,15 @@ struct CopyBidirectional<'a, A: ?Sized, B: ?Sized> {
     b_to_a: TransferState,
     a_to_b_count: u64,
     b_to_a_count: u64,
+    // The direction is ended if no data is transferred within the timeout
+    // after the other direction is done, the connection is half-closed in
+    // the meantime.
     a_to_b_delay: Option<Pin<Box<tokio::time::Sleep>>>,
     b_to_a_delay: Option<Pin<Box<tokio::time::Sleep>>>,
     a_to_b_timeout_duration: Duration,
     b_to_a_timeout_duration: Duration,
+    a_to_b_last_amt: u64,
+    b_to_a_last_amt: u64,
 }
 
 impl<'a, A, B> Future for CopyBidirectional<'a, A, B>
@@ -158,6 +164,8 @@ where
             b_to_a_delay,
             a_to_b_timeout_duration,
             b_to_a_timeout_duration,
+            a_to_b_last_amt,
+            b_to_a_last_amt,
         } = &mut *self;
 
         let mut a = Pin::new(a);
@@ -175,6 +183,12 @@ where
                         Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
                         Poll::Pending => {
                             if let Some(delay) = a_to_b_delay {
+                                if buf.amount_transfered() > *a_to_b_last_amt {
+                                    *a_to_b_last_amt = buf.amount_transfered();
+                                    delay.as_mut().reset(
+                                        tokio::time::Instant::now() + *a_to_b_timeout_duration,
+                                    );
+                                }
                                 match delay.as_mut().poll(cx) {
                                     Poll::Ready(()) => {
                                         *a_to_b =
@@ -215,6 +229,12 @@ where
                         Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
                         Poll::Pending => {
                             if let Some(delay) = b_to_a_delay {
+                                if buf.amount_transfered() > *b_to_a_last_amt {
+                                    *b_to_a_last_amt = buf.amount_transfered();
+                                    delay.as_mut().reset(
+                                        tokio::time::Instant::now() + *b_to_a_timeout_duration,
+                                    );
+                                }
                                 match delay.as_mut().poll(cx) {
                                     Poll::Ready(()) => {
                                         *b_to_a =
@@ -276,6 +296,119 @@ where
         b_to_a_delay: None,
         a_to_b_timeout_duration,
         b_to_a_timeout_duration,
+        a_to_b_last_amt: 0,
+        b_to_a_last_amt: 0,
     }
     .await
 }
+
+/// A stream with some bytes already read from it, e.g. when detecting the
+/// protocol, put back in front.
+pub struct PrefixedStream<T> {
+    inner: T,
+    prefix: Bytes,
+}
+
+impl<T> PrefixedStream<T> {
+    pub fn new(inner: T, prefix: Bytes) -> Self {
+        PrefixedStream { inner, prefix }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for PrefixedStream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if !self.prefix.is_empty() {
+            let n = std::cmp::min(buf.remaining(), self.prefix.len());
+            buf.put_slice(&self.prefix[..n]);
+            self.prefix.advance(n);
+            Poll::Ready(Ok(()))
+        } else {
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for PrefixedStream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[test]
+    fn test_half_close() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .start_paused(true)
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let (mut client, mut a) = tokio::io::duplex(1024);
+            let (mut b, mut server) = tokio::io::duplex(1024);
+            let timeout = Duration::from_millis(200);
+            let relay = tokio::spawn(async move {
+                copy_buf_bidirectional_with_timeout(&mut a, &mut b, 1024, timeout, timeout).await
+            });
+
+            client.write_all(b"request").await.unwrap();
+            client.shutdown().await.unwrap();
+            let mut buf = Vec::new();
+            server.read_to_end(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"request");
+
+            // The response keeps flowing after the uplink is closed, longer
+            // than the timeout in total. Each part is read before the clock
+            // moves on, so the relay has seen it.
+            for _ in 0..4 {
+                tokio::time::advance(Duration::from_millis(100)).await;
+                server.write_all(b"response").await.unwrap();
+                let mut buf = [0u8; 8];
+                client.read_exact(&mut buf).await.unwrap();
+                assert_eq!(&buf, b"response");
+            }
+            drop(server);
+            let mut buf = Vec::new();
+            client.read_to_end(&mut buf).await.unwrap();
+            assert!(buf.is_empty());
+            assert_eq!(relay.await.unwrap().unwrap(), (7, 32));
+        });
+    }
+
+    #[test]
+    fn test_prefixed_stream() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let (mut client, server) = tokio::io::duplex(1024);
+            let mut stream = PrefixedStream::new(server, Bytes::from_static(b"GET"));
+            client.write_all(b" / HTTP/1.1").await.unwrap();
+            drop(client);
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"GET / HTTP/1.1");
+        });
+    }
+}
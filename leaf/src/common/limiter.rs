@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::Inbound;
+
+struct State {
+    total: usize,
+    per_source: HashMap<IpAddr, usize>,
+    window_start: Instant,
+    window_accepted: u32,
+}
+
+/// Limits the connections accepted by an inbound, protecting devices with
+/// little memory from clients opening sockets without bound. A limit of 0
+/// means unlimited.
+pub struct ConnectionLimiter {
+    max_connections: usize,
+    max_connections_per_source: usize,
+    accept_rate: u32,
+    state: Mutex<State>,
+}
+
+impl ConnectionLimiter {
+    /// `accept_rate` is the number of connections accepted per second.
+    pub fn new(max_connections: u32, max_connections_per_source: u32, accept_rate: u32) -> Self {
+        ConnectionLimiter {
+            max_connections: max_connections as usize,
+            max_connections_per_source: max_connections_per_source as usize,
+            accept_rate,
+            state: Mutex::new(State {
+                total: 0,
+                per_source: HashMap::new(),
+                window_start: Instant::now(),
+                window_accepted: 0,
+            }),
+        }
+    }
+
+    pub fn from_inbound(inbound: &Inbound) -> Self {
+        Self::new(
+            inbound.max_connections,
+            inbound.max_connections_per_source,
+            inbound.accept_rate,
+        )
+    }
+
+    fn is_unlimited(&self) -> bool {
+        self.max_connections == 0 && self.max_connections_per_source == 0 && self.accept_rate == 0
+    }
+
+    /// Admits a new connection from `source`, the returned permit must be
+    /// held for the lifetime of the connection. Returns None if any of the
+    /// limits is exceeded.
+    pub fn try_acquire(self: &Arc<Self>, source: IpAddr) -> Option<ConnectionPermit> {
+        if self.is_unlimited() {
+            return Some(ConnectionPermit {
+                limiter: None,
+                source,
+            });
+        }
+        let mut state = self.state.lock().unwrap();
+        if self.max_connections > 0 && state.total >= self.max_connections {
+            return None;
+        }
+        if self.max_connections_per_source > 0
+            && state.per_source.get(&source).copied().unwrap_or(0)
+                >= self.max_connections_per_source
+        {
+            return None;
+        }
+        if self.accept_rate > 0 {
+            let now = Instant::now();
+            if now.duration_since(state.window_start) >= Duration::from_secs(1) {
+                state.window_start = now;
+                state.window_accepted = 0;
+            }
+            if state.window_accepted >= self.accept_rate {
+                return None;
+            }
+            state.window_accepted += 1;
+        }
+        state.total += 1;
+        *state.per_source.entry(source).or_insert(0) += 1;
+        Some(ConnectionPermit {
+            limiter: Some(self.clone()),
+            source,
+        })
+    }
+}
+
+/// Releases the connection slot when dropped.
+pub struct ConnectionPermit {
+    limiter: Option<Arc<ConnectionLimiter>>,
+    source: IpAddr,
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        let limiter = match &self.limiter {
+            Some(l) => l,
+            None => return,
+        };
+        let mut state = limiter.state.lock().unwrap();
+        state.total -= 1;
+        if let Some(n) = state.per_source.get_mut(&self.source) {
+            *n -= 1;
+            if *n == 0 {
+                state.per_source.remove(&self.source);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_limits() {
+        let a: IpAddr = "10.0.0.1".parse().unwrap();
+        let b: IpAddr = "10.0.0.2".parse().unwrap();
+        let c: IpAddr = "10.0.0.3".parse().unwrap();
+        let limiter = Arc::new(ConnectionLimiter::new(3, 2, 0));
+        let p1 = limiter.try_acquire(a).unwrap();
+        let _p2 = limiter.try_acquire(a).unwrap();
+        assert!(limiter.try_acquire(a).is_none());
+        let _p3 = limiter.try_acquire(b).unwrap();
+        assert!(limiter.try_acquire(c).is_none());
+        drop(p1);
+        assert!(limiter.try_acquire(c).is_some());
+        assert!(limiter.try_acquire(a).is_some());
+    }
+
+    #[test]
+    fn test_accept_rate() {
+        let a: IpAddr = "10.0.0.1".parse().unwrap();
+        let limiter = Arc::new(ConnectionLimiter::new(0, 0, 2));
+        assert!(limiter.try_acquire(a).is_some());
+        assert!(limiter.try_acquire(a).is_some());
+        // Closed connections still count against the rate.
+        assert!(limiter.try_acquire(a).is_none());
+        limiter.state.lock().unwrap().window_start -= Duration::from_secs(1);
+        assert!(limiter.try_acquire(a).is_some());
+    }
+}
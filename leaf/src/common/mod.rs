@@ -1,8 +1,18 @@
 pub mod crypto;
 pub mod io;
+pub mod limiter;
 pub mod net;
+pub mod process;
+pub mod proxy_protocol;
 pub mod resolver;
 pub mod sniff;
+pub mod uuid;
+
+#[cfg(feature = "h2")]
+pub mod h2;
+
+#[cfg(any(feature = "outbound-reality", feature = "outbound-ech"))]
+pub mod tls13;
 
 #[cfg(target_os = "macos")]
 pub mod cmd_macos;
@@ -1,6 +1,15 @@
+use std::io;
 use std::net::{SocketAddr, SocketAddrV6};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
+use socket2::SockRef;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
 
 pub fn parse_bind_addr(bind: &str) -> Result<SocketAddr> {
     let mut split = bind.split('%');
@@ -21,3 +30,103 @@ pub fn parse_bind_addr(bind: &str) -> Result<SocketAddr> {
         None => Ok(SocketAddr::new(ip_addr.parse()?, 0)),
     }
 }
+
+/// A TCP stream which is reset rather than closed when it's dropped, once the
+/// flag returned by `new` is set.
+pub struct ResettableTcpStream {
+    inner: TcpStream,
+    reset: Arc<AtomicBool>,
+}
+
+impl ResettableTcpStream {
+    pub fn new(inner: TcpStream) -> (Self, Arc<AtomicBool>) {
+        let reset = Arc::new(AtomicBool::new(false));
+        (
+            ResettableTcpStream {
+                inner,
+                reset: reset.clone(),
+            },
+            reset,
+        )
+    }
+}
+
+impl AsyncRead for ResettableTcpStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for ResettableTcpStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+impl Drop for ResettableTcpStream {
+    fn drop(&mut self) {
+        // Closing a socket lingering for 0 seconds sends a RST.
+        if self.reset.load(Ordering::Relaxed) {
+            let _ = SockRef::from(&self.inner).set_linger(Some(Duration::ZERO));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn test_reset() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            for reset in [false, true] {
+                let mut client = TcpStream::connect(addr).await.unwrap();
+                let (stream, flag) = ResettableTcpStream::new(listener.accept().await.unwrap().0);
+                flag.store(reset, Ordering::Relaxed);
+                drop(stream);
+                let mut buf = [0u8; 16];
+                match client.read(&mut buf).await {
+                    Ok(n) => assert!(!reset && n == 0),
+                    Err(e) => assert!(reset && e.kind() == io::ErrorKind::ConnectionReset),
+                }
+            }
+        });
+    }
+}
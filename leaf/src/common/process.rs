@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+
+use crate::session::Network;
+
+/// Finds the UID of the process owning the socket of the given local (source)
+/// and remote (destination) addresses.
+pub type UidResolver = Box<dyn Fn(Network, &SocketAddr, &SocketAddr) -> Option<u32> + Send + Sync>;
+
+/// Finds the executable path of the process owning the socket of the given
+/// local (source) and remote (destination) addresses.
+pub type ProcessResolver =
+    Box<dyn Fn(Network, &SocketAddr, &SocketAddr) -> Option<PathBuf> + Send + Sync>;
+
+lazy_static! {
+    static ref UID_RESOLVER: RwLock<Option<UidResolver>> = RwLock::new(None);
+    static ref PROCESS_RESOLVER: RwLock<Option<ProcessResolver>> = RwLock::new(None);
+}
+
+/// The process owning the socket of a session, the fields not asked for or
+/// not found are None.
+#[derive(Debug, Default)]
+pub struct ProcessInfo {
+    pub uid: Option<u32>,
+    pub path: Option<PathBuf>,
+}
+
+/// Finds the UID and the executable path of the process owning the socket in
+/// a blocking task, as procfs is walked.
+pub async fn resolve(
+    network: Network,
+    source: SocketAddr,
+    destination: SocketAddr,
+    uid: bool,
+    path: bool,
+) -> ProcessInfo {
+    tokio::task::spawn_blocking(move || ProcessInfo {
+        uid: if uid {
+            find_uid(network, &source, &destination)
+        } else {
+            None
+        },
+        path: if path {
+            find_process(network, &source, &destination)
+        } else {
+            None
+        },
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Sets a resolver to find socket owners, e.g. the connectivity API on
+/// Android, where /proc/net is not accessible since Android 10.
+pub fn set_uid_resolver(resolver: Option<UidResolver>) {
+    *UID_RESOLVER.write().unwrap() = resolver;
+}
+
+/// Sets a resolver to find the processes owning sockets, for platforms
+/// without procfs, e.g. with libproc on macOS or GetExtendedTcpTable on
+/// Windows.
+pub fn set_process_resolver(resolver: Option<ProcessResolver>) {
+    *PROCESS_RESOLVER.write().unwrap() = resolver;
+}
+
+/// Returns the UID of the process owning the socket, the resolver is tried
+/// first and /proc/net is the fallback.
+pub fn find_uid(network: Network, source: &SocketAddr, destination: &SocketAddr) -> Option<u32> {
+    if let Some(resolver) = UID_RESOLVER.read().unwrap().as_ref() {
+        if let Some(uid) = resolver(network, source, destination) {
+            return Some(uid);
+        }
+    }
+    find_socket_procfs(network, source, destination).map(|(uid, _)| uid)
+}
+
+/// Returns the executable path of the process owning the socket, the
+/// resolver is tried first and procfs is the fallback.
+pub fn find_process(
+    network: Network,
+    source: &SocketAddr,
+    destination: &SocketAddr,
+) -> Option<PathBuf> {
+    if let Some(resolver) = PROCESS_RESOLVER.read().unwrap().as_ref() {
+        if let Some(path) = resolver(network, source, destination) {
+            return Some(path);
+        }
+    }
+    let (_, inode) = find_socket_procfs(network, source, destination)?;
+    find_process_procfs(inode)
+}
+
+// Finds the process with a file descriptor of the socket inode, which
+// requires permission to read the fds of other users' processes.
+fn find_process_procfs(inode: u64) -> Option<PathBuf> {
+    let link = PathBuf::from(format!("socket:[{}]", inode));
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        let pid = entry.file_name();
+        if !pid.to_string_lossy().bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        let fds = match std::fs::read_dir(entry.path().join("fd")) {
+            Ok(fds) => fds,
+            Err(_) => continue,
+        };
+        for fd in fds.flatten() {
+            if std::fs::read_link(fd.path()).ok().as_ref() == Some(&link) {
+                return std::fs::read_link(entry.path().join("exe")).ok();
+            }
+        }
+    }
+    None
+}
+
+// Returns the UID and the inode of the socket.
+fn find_socket_procfs(
+    network: Network,
+    source: &SocketAddr,
+    destination: &SocketAddr,
+) -> Option<(u32, u64)> {
+    let files = match network {
+        Network::Tcp => ["/proc/net/tcp", "/proc/net/tcp6"],
+        Network::Udp => ["/proc/net/udp", "/proc/net/udp6"],
+    };
+    for file in files {
+        if let Ok(content) = std::fs::read_to_string(file) {
+            let udp = network == Network::Udp;
+            if let Some(socket) = parse_proc_net(&content, source, destination, udp) {
+                return Some(socket);
+            }
+        }
+    }
+    None
+}
+
+// Finds the socket of the exact local and remote addresses, so another
+// socket on the same port, e.g. a listener, isn't taken. Unconnected UDP
+// sockets are the exception, which have no remote address and are usually
+// bound to the unspecified address.
+fn parse_proc_net(
+    content: &str,
+    source: &SocketAddr,
+    destination: &SocketAddr,
+    udp: bool,
+) -> Option<(u32, u64)> {
+    let source_ip = canonical_ip(source.ip());
+    let destination_ip = canonical_ip(destination.ip());
+    let mut unconnected = None;
+    for line in content.lines().skip(1) {
+        let cols: Vec<&str> = line.split_whitespace().collect();
+        if cols.len() < 10 {
+            continue;
+        }
+        let (local, remote) = match (parse_hex_addr(cols[1]), parse_hex_addr(cols[2])) {
+            (Some(local), Some(remote)) => (local, remote),
+            _ => continue,
+        };
+        if local.1 != source.port() {
+            continue;
+        }
+        if canonical_ip(local.0) == source_ip
+            && canonical_ip(remote.0) == destination_ip
+            && remote.1 == destination.port()
+        {
+            return Some((cols[7].parse().ok()?, cols[9].parse().ok()?));
+        }
+        if udp
+            && unconnected.is_none()
+            && remote.0.is_unspecified()
+            && remote.1 == 0
+            && (local.0.is_unspecified() || canonical_ip(local.0) == source_ip)
+        {
+            unconnected = Some((cols[7], cols[9]));
+        }
+    }
+    let (uid, inode) = unconnected?;
+    Some((uid.parse().ok()?, inode.parse().ok()?))
+}
+
+// Addresses in /proc/net are hex strings of 32-bit words in host byte order.
+fn parse_hex_addr(s: &str) -> Option<(IpAddr, u16)> {
+    let (ip, port) = s.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let ip = match ip.len() {
+        8 => {
+            let word = u32::from_str_radix(ip, 16).ok()?;
+            IpAddr::V4(Ipv4Addr::from(word.to_ne_bytes()))
+        }
+        32 => {
+            let mut octets = [0u8; 16];
+            for (i, chunk) in octets.chunks_exact_mut(4).enumerate() {
+                let word = u32::from_str_radix(&ip[i * 8..i * 8 + 8], 16).ok()?;
+                chunk.copy_from_slice(&word.to_ne_bytes());
+            }
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return None,
+    };
+    Some((ip, port))
+}
+
+// Dual-stack sockets have IPv4-mapped addresses.
+fn canonical_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        _ => ip,
+    }
+}
+
+/// Returns the UIDs of installed Android packages, it requires permission to
+/// read /data/system/packages.list.
+pub fn load_packages() -> HashMap<String, u32> {
+    match std::fs::read_to_string("/data/system/packages.list") {
+        Ok(content) => parse_packages(&content),
+        Err(_) => HashMap::new(),
+    }
+}
+
+fn parse_packages(content: &str) -> HashMap<String, u32> {
+    let mut packages = HashMap::new();
+    for line in content.lines() {
+        let mut cols = line.split_whitespace();
+        if let (Some(name), Some(uid)) = (cols.next(), cols.next()) {
+            if let Ok(uid) = uid.parse::<u32>() {
+                packages.insert(name.to_string(), uid);
+            }
+        }
+    }
+    packages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn test_parse_proc_net() {
+        let content = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 1 1 0000000000000000 100 0 0 10 0
+   1: 00000000:0035 00000000:0000 07 00000000:00000000 00:00000000 00000000 10123        0 2 2 0000000000000000 0
+   2: 0000000000000000FFFF00000A01A8C0:C350 0000000000000000FFFF0000E0E2D8AC:01BB 01 00000000:00000000 00:00000000 00000000 10456        0 3 1 0000000000000000 20 4 30 10 -1";
+        let remote = "172.216.226.224:443".parse().unwrap();
+        // Listeners are not taken for connections of the same port.
+        assert_eq!(
+            parse_proc_net(content, &"127.0.0.1:8080".parse().unwrap(), &remote, false),
+            None
+        );
+        assert_eq!(
+            parse_proc_net(content, &"10.0.0.2:53".parse().unwrap(), &remote, true),
+            Some((10123, 2))
+        );
+        assert_eq!(
+            parse_proc_net(content, &"10.0.0.2:53".parse().unwrap(), &remote, false),
+            None
+        );
+        assert_eq!(
+            parse_proc_net(
+                content,
+                &"192.168.1.10:50000".parse().unwrap(),
+                &remote,
+                false
+            ),
+            Some((10456, 3))
+        );
+        assert_eq!(
+            parse_proc_net(
+                content,
+                &"192.168.1.10:50000".parse().unwrap(),
+                &"172.216.226.224:80".parse().unwrap(),
+                false
+            ),
+            None
+        );
+        assert_eq!(
+            parse_proc_net(content, &"127.0.0.1:8081".parse().unwrap(), &remote, false),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_find_process() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let stream = std::net::TcpStream::connect(addr).unwrap();
+        let (_, inode) =
+            find_socket_procfs(Network::Tcp, &stream.local_addr().unwrap(), &addr).unwrap();
+        assert_eq!(
+            find_process_procfs(inode),
+            Some(std::env::current_exe().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_parse_packages() {
+        let content = "com.android.chrome 10123 0 /data/user/0/com.android.chrome default:targetSdkVersion=30 3003
+com.example.app 10456 1 /data/user/0/com.example.app default:targetSdkVersion=31 none";
+        let packages = parse_packages(content);
+        assert_eq!(packages.get("com.android.chrome"), Some(&10123));
+        assert_eq!(packages.get("com.example.app"), Some(&10456));
+    }
+}
@@ -0,0 +1,159 @@
+//! Parses headers of the PROXY protocol, versions 1 and 2, which carry the
+//! original client addresses of connections forwarded by load balancers
+//! such as HAProxy and nginx.
+//!
+//! https://www.haproxy.org/download/2.4/doc/proxy-protocol.txt
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+const V1_MAX_LEN: usize = 107;
+const V2_SIGNATURE: [u8; 12] = [
+    0x0d, 0x0a, 0x0d, 0x0a, 0x00, 0x0d, 0x0a, 0x51, 0x55, 0x49, 0x54, 0x0a,
+];
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("proxy protocol: {}", msg),
+    )
+}
+
+/// Reads a PROXY protocol header from the stream, exactly the header is
+/// consumed. Returns the source address conveyed, or None if the header
+/// doesn't carry one, e.g. health checks from the proxy itself.
+pub async fn read_header<T>(stream: &mut T) -> io::Result<Option<SocketAddr>>
+where
+    T: AsyncRead + Unpin,
+{
+    let mut buf = [0u8; 16];
+    stream.read_exact(&mut buf[..5]).await?;
+    if &buf[..5] == b"PROXY" {
+        return read_v1(stream).await;
+    }
+    stream.read_exact(&mut buf[5..]).await?;
+    if buf[..12] != V2_SIGNATURE {
+        return Err(invalid("no header"));
+    }
+    if buf[12] >> 4 != 0x2 {
+        return Err(invalid("unknown version"));
+    }
+    let len = u16::from_be_bytes([buf[14], buf[15]]) as usize;
+    let mut addrs = vec![0u8; len];
+    stream.read_exact(&mut addrs).await?;
+    // LOCAL command
+    if buf[12] & 0x0f == 0x0 {
+        return Ok(None);
+    }
+    match buf[13] >> 4 {
+        // AF_INET
+        0x1 if len >= 12 => {
+            let ip = Ipv4Addr::new(addrs[0], addrs[1], addrs[2], addrs[3]);
+            let port = u16::from_be_bytes([addrs[8], addrs[9]]);
+            Ok(Some(SocketAddr::new(IpAddr::V4(ip), port)))
+        }
+        // AF_INET6
+        0x2 if len >= 36 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&addrs[..16]);
+            let port = u16::from_be_bytes([addrs[32], addrs[33]]);
+            Ok(Some(SocketAddr::new(
+                IpAddr::V6(Ipv6Addr::from(octets)),
+                port,
+            )))
+        }
+        // AF_UNSPEC, AF_UNIX
+        0x0 | 0x3 => Ok(None),
+        _ => Err(invalid("invalid address block")),
+    }
+}
+
+// Reads the rest of a version 1 header after "PROXY", byte by byte to not
+// consume the payload.
+async fn read_v1<T>(stream: &mut T) -> io::Result<Option<SocketAddr>>
+where
+    T: AsyncRead + Unpin,
+{
+    let mut line = Vec::new();
+    loop {
+        let b = stream.read_u8().await?;
+        line.push(b);
+        if line.ends_with(b"\r\n") {
+            break;
+        }
+        if line.len() + 5 > V1_MAX_LEN {
+            return Err(invalid("header too long"));
+        }
+    }
+    let line = std::str::from_utf8(&line[..line.len() - 2]).map_err(|_| invalid("not text"))?;
+    let parts: Vec<&str> = line.split(' ').collect();
+    // The line starts with a space after "PROXY".
+    match parts.get(1) {
+        Some(&"TCP4") | Some(&"TCP6") if parts.len() == 6 => {
+            let ip = parts[2]
+                .parse::<IpAddr>()
+                .map_err(|_| invalid("invalid source address"))?;
+            let port = parts[4]
+                .parse::<u16>()
+                .map_err(|_| invalid("invalid source port"))?;
+            Ok(Some(SocketAddr::new(ip, port)))
+        }
+        Some(&"UNKNOWN") => Ok(None),
+        _ => Err(invalid("invalid header")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(data: &[u8]) -> (io::Result<Option<SocketAddr>>, Vec<u8>) {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let mut stream = data;
+            let res = read_header(&mut stream).await;
+            (res, stream.to_vec())
+        })
+    }
+
+    #[test]
+    fn test_v1() {
+        let (res, rest) = read(b"PROXY TCP4 1.2.3.4 5.6.7.8 1234 80\r\nGET /");
+        assert_eq!(res.unwrap(), Some("1.2.3.4:1234".parse().unwrap()));
+        assert_eq!(&rest, b"GET /");
+
+        let (res, _) = read(b"PROXY TCP6 ::1 ::2 1234 80\r\n");
+        assert_eq!(res.unwrap(), Some("[::1]:1234".parse().unwrap()));
+
+        let (res, rest) = read(b"PROXY UNKNOWN\r\ndata");
+        assert_eq!(res.unwrap(), None);
+        assert_eq!(&rest, b"data");
+
+        assert!(read(b"PROXY TCP4 1.2.3.4\r\n").0.is_err());
+        assert!(read(b"GET / HTTP/1.1\r\n\r\n").0.is_err());
+    }
+
+    #[test]
+    fn test_v2() {
+        let mut data = V2_SIGNATURE.to_vec();
+        // PROXY command, TCP over IPv4
+        data.extend_from_slice(&[0x21, 0x11, 0x00, 12]);
+        data.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 0x04, 0xd2, 0x00, 0x50]);
+        data.extend_from_slice(b"payload");
+        let (res, rest) = read(&data);
+        assert_eq!(res.unwrap(), Some("1.2.3.4:1234".parse().unwrap()));
+        assert_eq!(&rest, b"payload");
+
+        let mut data = V2_SIGNATURE.to_vec();
+        // LOCAL command
+        data.extend_from_slice(&[0x20, 0x00, 0x00, 0x00]);
+        data.extend_from_slice(b"payload");
+        let (res, rest) = read(&data);
+        assert_eq!(res.unwrap(), None);
+        assert_eq!(&rest, b"payload");
+    }
+}
@@ -3,7 +3,7 @@ use std::net::{IpAddr, SocketAddr};
 use anyhow::{anyhow, Result};
 use futures::TryFutureExt;
 
-use crate::app::SyncDnsClient;
+use crate::app::{dns_client::DomainStrategy, SyncDnsClient};
 
 pub struct Resolver {
     ips: Vec<IpAddr>,
@@ -15,15 +15,17 @@ impl Resolver {
         dns_client: SyncDnsClient,
         address: &'a String,
         port: &'a u16,
+        strategy: DomainStrategy,
     ) -> Result<Self> {
-        let mut ips = {
+        let ips = {
             dns_client
                 .read()
                 .await
-                .lookup(address)
+                .lookup_with_strategy(address, strategy)
                 .map_err(|e| anyhow!("lookup {} failed: {}", address, e))
                 .await?
         };
+        let mut ips = interleave(ips);
         ips.reverse();
         Ok(Resolver {
             ips,
@@ -32,6 +34,35 @@ impl Resolver {
     }
 }
 
+// Interleaves the IPv6 and IPv4 addresses, starting with the family of the
+// first one, so a dial falls back to the other family early if one is broken,
+// as RFC 8305 section 4 suggests.
+fn interleave(ips: Vec<IpAddr>) -> Vec<IpAddr> {
+    let first_v6 = match ips.first() {
+        Some(ip) => ip.is_ipv6(),
+        None => return ips,
+    };
+    let (mut preferred, mut other): (Vec<IpAddr>, Vec<IpAddr>) =
+        ips.into_iter().partition(|ip| ip.is_ipv6() == first_v6);
+    preferred.reverse();
+    other.reverse();
+    let mut ips = Vec::with_capacity(preferred.len() + other.len());
+    loop {
+        match (preferred.pop(), other.pop()) {
+            (None, None) => break,
+            (a, b) => ips.extend(a.into_iter().chain(b)),
+        }
+    }
+    ips
+}
+
+impl Resolver {
+    /// Returns the number of addresses not yet taken.
+    pub fn remaining(&self) -> usize {
+        self.ips.len()
+    }
+}
+
 impl Iterator for Resolver {
     type Item = SocketAddr;
 
@@ -39,3 +70,29 @@ impl Iterator for Resolver {
         self.ips.pop().map(|ip| SocketAddr::new(ip, self.port))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interleave() {
+        let ips: Vec<IpAddr> = vec![
+            "2001:db8::1".parse().unwrap(),
+            "2001:db8::2".parse().unwrap(),
+            "2001:db8::3".parse().unwrap(),
+            "192.0.2.1".parse().unwrap(),
+        ];
+        let expected: Vec<IpAddr> = vec![
+            "2001:db8::1".parse().unwrap(),
+            "192.0.2.1".parse().unwrap(),
+            "2001:db8::2".parse().unwrap(),
+            "2001:db8::3".parse().unwrap(),
+        ];
+        assert_eq!(interleave(ips), expected);
+
+        let ips: Vec<IpAddr> = vec!["192.0.2.1".parse().unwrap(), "192.0.2.2".parse().unwrap()];
+        assert_eq!(interleave(ips.clone()), ips);
+        assert!(interleave(Vec::new()).is_empty());
+    }
+}
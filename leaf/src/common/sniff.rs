@@ -1,6 +1,9 @@
 use std::cmp::min;
+use std::fmt;
 use std::io;
+use std::net::IpAddr;
 use std::pin::Pin;
+use std::str::FromStr;
 use std::task::{Context, Poll};
 use std::time::Duration;
 
@@ -12,6 +15,7 @@ use tokio::time::timeout;
 pub struct SniffingStream<T> {
     inner: T,
     buf: BytesMut,
+    protocol: Option<Protocol>,
 }
 
 impl<T> SniffingStream<T>
@@ -22,125 +26,44 @@ where
         SniffingStream {
             inner,
             buf: BytesMut::new(),
+            protocol: None,
         }
     }
 
-    pub async fn sniff(&mut self) -> io::Result<Option<String>> {
+    /// Returns the protocol of the sniffed domain.
+    pub fn protocol(&self) -> Option<Protocol> {
+        self.protocol
+    }
+
+    /// Reads the first bytes of the stream and tries to find the domain
+    /// requested with the protocols, the bytes read are kept and returned
+    /// by subsequent reads.
+    pub async fn sniff(&mut self, protocols: &[Protocol]) -> io::Result<Option<String>> {
         let mut buf = vec![0u8; 2 * 1024];
-        'outer: for _ in 0..2 {
+        for _ in 0..2 {
             match timeout(Duration::from_millis(100), self.inner.read(&mut buf)).await {
                 Ok(res) => match res {
                     Ok(n) => {
                         self.buf.extend_from_slice(&buf[..n]);
-
-                        // https://tls.ulfheim.net/
-
-                        let sbuf = &self.buf[..];
-                        if sbuf.len() < 5 {
-                            continue;
-                        }
-                        // handshake record type
-                        if sbuf[0] != 0x16 {
-                            return Ok(None);
-                        }
-                        // protocol version
-                        if sbuf[1] != 0x3 {
-                            return Ok(None);
-                        }
-                        let header_len = BigEndian::read_u16(&sbuf[3..5]) as usize;
-                        if sbuf.len() < 5 + header_len {
-                            continue;
-                        }
-                        let sbuf = &sbuf[5..5 + header_len];
-                        // ?
-                        if sbuf.len() < 42 {
-                            continue;
-                        }
-                        let session_id_len = sbuf[38] as usize;
-                        if session_id_len > 32 || sbuf.len() < 39 + session_id_len {
-                            continue;
-                        }
-                        let sbuf = &sbuf[39 + session_id_len..];
-                        if sbuf.len() < 2 {
-                            continue;
-                        }
-                        let cipher_suite_bytes = BigEndian::read_u16(&sbuf[..2]) as usize;
-                        if sbuf.len() < 2 + cipher_suite_bytes {
-                            continue;
-                        }
-                        let sbuf = &sbuf[2 + cipher_suite_bytes..];
-                        if sbuf.is_empty() {
-                            continue;
-                        }
-                        let compression_method_bytes = sbuf[0] as usize;
-                        if sbuf.len() < 1 + compression_method_bytes {
-                            continue;
-                        }
-                        let sbuf = &sbuf[1 + compression_method_bytes..];
-                        if sbuf.len() < 2 {
-                            continue;
-                        }
-                        let extensions_bytes = BigEndian::read_u16(&sbuf[..2]) as usize;
-                        if sbuf.len() < 2 + extensions_bytes {
-                            continue;
-                        }
-                        let mut sbuf = &sbuf[2..2 + extensions_bytes];
-                        while !sbuf.is_empty() {
-                            // extension + extension-specific-len
-                            if sbuf.len() < 4 {
-                                continue 'outer;
-                            }
-                            let extension = BigEndian::read_u16(&sbuf[..2]);
-                            let extension_len = BigEndian::read_u16(&sbuf[2..4]) as usize;
-                            sbuf = &sbuf[4..];
-                            if sbuf.len() < extension_len {
-                                continue 'outer;
-                            }
-                            // extension "server name"
-                            if extension == 0x0 {
-                                let mut ebuf = &sbuf[..extension_len];
-                                if ebuf.len() < 2 {
-                                    continue 'outer;
-                                }
-                                let entry_len = BigEndian::read_u16(&ebuf[..2]) as usize;
-                                ebuf = &ebuf[2..];
-                                if ebuf.len() < entry_len {
-                                    continue 'outer;
-                                }
-                                // just make sure no oob
-                                if ebuf.is_empty() {
-                                    continue 'outer;
+                        let mut incomplete = false;
+                        for protocol in protocols {
+                            let res = match protocol {
+                                Protocol::Tls => tls_sni(&self.buf),
+                                Protocol::Http => http_host(&self.buf),
+                                Protocol::Quic => continue,
+                            };
+                            match res {
+                                Sniffed::Domain(domain) => {
+                                    self.protocol = Some(*protocol);
+                                    return Ok(Some(domain));
                                 }
-                                let entry_type = ebuf[0];
-                                // type "DNS hostname"
-                                if entry_type == 0x0 {
-                                    ebuf = &ebuf[1..];
-                                    // just make sure no oob
-                                    if ebuf.len() < 2 {
-                                        continue 'outer;
-                                    }
-                                    let hostname_len = BigEndian::read_u16(&ebuf[..2]) as usize;
-                                    ebuf = &ebuf[2..];
-                                    if ebuf.len() < hostname_len {
-                                        continue 'outer;
-                                    }
-                                    return Ok(Some(
-                                        String::from_utf8_lossy(&ebuf[..hostname_len]).into(),
-                                    ));
-                                } else {
-                                    // TODO
-                                    // I assume there's only "DNS hostname" type
-                                    // in the the "server name" extension, should
-                                    // check if this is true later.
-                                    //
-                                    // I also assume there's only one entry in the
-                                    // "server name" extension list.
-                                    return Ok(None);
-                                }
-                            } else {
-                                sbuf = &sbuf[extension_len..];
+                                Sniffed::Incomplete => incomplete = true,
+                                Sniffed::Unknown => (),
                             }
                         }
+                        if !incomplete || n == 0 {
+                            return Ok(None);
+                        }
                     }
                     Err(e) => {
                         return Err(e);
@@ -155,6 +78,403 @@ where
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Protocol {
+    Tls,
+    Http,
+    Quic,
+}
+
+impl FromStr for Protocol {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tls" => Ok(Protocol::Tls),
+            "http" => Ok(Protocol::Http),
+            "quic" => Ok(Protocol::Quic),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown sniffing protocol {}", s),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Protocol::Tls => write!(f, "tls"),
+            Protocol::Http => write!(f, "http"),
+            Protocol::Quic => write!(f, "quic"),
+        }
+    }
+}
+
+enum Sniffed {
+    Domain(String),
+    // More data is needed.
+    Incomplete,
+    Unknown,
+}
+
+// https://tls.ulfheim.net/
+fn tls_sni(sbuf: &[u8]) -> Sniffed {
+    if sbuf.len() < 5 {
+        return Sniffed::Incomplete;
+    }
+    // handshake record type
+    if sbuf[0] != 0x16 {
+        return Sniffed::Unknown;
+    }
+    // protocol version
+    if sbuf[1] != 0x3 {
+        return Sniffed::Unknown;
+    }
+    let header_len = BigEndian::read_u16(&sbuf[3..5]) as usize;
+    if sbuf.len() < 5 + header_len {
+        return Sniffed::Incomplete;
+    }
+    client_hello_sni(&sbuf[5..5 + header_len])
+}
+
+// Finds the server name in a ClientHello handshake message.
+fn client_hello_sni(sbuf: &[u8]) -> Sniffed {
+    // handshake type "client hello"
+    if !sbuf.is_empty() && sbuf[0] != 0x1 {
+        return Sniffed::Unknown;
+    }
+    if sbuf.len() < 42 {
+        return Sniffed::Incomplete;
+    }
+    let session_id_len = sbuf[38] as usize;
+    if session_id_len > 32 {
+        return Sniffed::Unknown;
+    }
+    if sbuf.len() < 39 + session_id_len {
+        return Sniffed::Incomplete;
+    }
+    let sbuf = &sbuf[39 + session_id_len..];
+    if sbuf.len() < 2 {
+        return Sniffed::Incomplete;
+    }
+    let cipher_suite_bytes = BigEndian::read_u16(&sbuf[..2]) as usize;
+    if sbuf.len() < 2 + cipher_suite_bytes {
+        return Sniffed::Incomplete;
+    }
+    let sbuf = &sbuf[2 + cipher_suite_bytes..];
+    if sbuf.is_empty() {
+        return Sniffed::Incomplete;
+    }
+    let compression_method_bytes = sbuf[0] as usize;
+    if sbuf.len() < 1 + compression_method_bytes {
+        return Sniffed::Incomplete;
+    }
+    let sbuf = &sbuf[1 + compression_method_bytes..];
+    if sbuf.len() < 2 {
+        return Sniffed::Incomplete;
+    }
+    let extensions_bytes = BigEndian::read_u16(&sbuf[..2]) as usize;
+    let mut sbuf = &sbuf[2..];
+    let mut complete = sbuf.len() >= extensions_bytes;
+    if complete {
+        sbuf = &sbuf[..extensions_bytes];
+    }
+    while !sbuf.is_empty() {
+        // extension + extension-specific-len
+        if sbuf.len() < 4 {
+            complete = false;
+            break;
+        }
+        let extension = BigEndian::read_u16(&sbuf[..2]);
+        let extension_len = BigEndian::read_u16(&sbuf[2..4]) as usize;
+        sbuf = &sbuf[4..];
+        if sbuf.len() < extension_len {
+            complete = false;
+            break;
+        }
+        // extension "server name"
+        if extension == 0x0 {
+            let mut ebuf = &sbuf[..extension_len];
+            if ebuf.len() < 2 {
+                return Sniffed::Unknown;
+            }
+            let entry_len = BigEndian::read_u16(&ebuf[..2]) as usize;
+            ebuf = &ebuf[2..];
+            // just make sure no oob
+            if ebuf.len() < entry_len || ebuf.is_empty() {
+                return Sniffed::Unknown;
+            }
+            let entry_type = ebuf[0];
+            // type "DNS hostname"
+            if entry_type == 0x0 {
+                ebuf = &ebuf[1..];
+                // just make sure no oob
+                if ebuf.len() < 2 {
+                    return Sniffed::Unknown;
+                }
+                let hostname_len = BigEndian::read_u16(&ebuf[..2]) as usize;
+                ebuf = &ebuf[2..];
+                if ebuf.len() < hostname_len {
+                    return Sniffed::Unknown;
+                }
+                return Sniffed::Domain(String::from_utf8_lossy(&ebuf[..hostname_len]).into());
+            } else {
+                // TODO
+                // I assume there's only "DNS hostname" type
+                // in the the "server name" extension, should
+                // check if this is true later.
+                //
+                // I also assume there's only one entry in the
+                // "server name" extension list.
+                return Sniffed::Unknown;
+            }
+        } else {
+            sbuf = &sbuf[extension_len..];
+        }
+    }
+    if complete {
+        Sniffed::Unknown
+    } else {
+        Sniffed::Incomplete
+    }
+}
+
+const HTTP_METHODS: [&str; 9] = [
+    "GET", "POST", "HEAD", "PUT", "DELETE", "OPTIONS", "PATCH", "CONNECT", "TRACE",
+];
+
+// Finds the Host header of a plain HTTP/1.x request.
+fn http_host(sbuf: &[u8]) -> Sniffed {
+    let method_len = match sbuf.iter().position(|b| *b == b' ') {
+        Some(n) => n,
+        None if sbuf.len() < 8 && sbuf.iter().all(|b| b.is_ascii_uppercase()) => {
+            return Sniffed::Incomplete;
+        }
+        None => return Sniffed::Unknown,
+    };
+    if !HTTP_METHODS
+        .iter()
+        .any(|m| m.as_bytes() == &sbuf[..method_len])
+    {
+        return Sniffed::Unknown;
+    }
+    let mut lines = sbuf.split(|b| *b == b'\n').skip(1).peekable();
+    while let Some(line) = lines.next() {
+        // The last line is not terminated yet.
+        if lines.peek().is_none() {
+            return Sniffed::Incomplete;
+        }
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            break;
+        }
+        if line.len() > 5 && line[..5].eq_ignore_ascii_case(b"host:") {
+            let host = String::from_utf8_lossy(&line[5..]);
+            let host = host.trim();
+            // Strips the port.
+            let host = if let Some(h) = host.strip_prefix('[') {
+                h.split(']').next().unwrap_or_default()
+            } else {
+                host.split(':').next().unwrap_or_default()
+            };
+            if host.is_empty() || host.parse::<IpAddr>().is_ok() {
+                return Sniffed::Unknown;
+            }
+            return Sniffed::Domain(host.to_string());
+        }
+    }
+    Sniffed::Unknown
+}
+
+/// Whether the datagram looks like an Initial packet of QUIC v1, other
+/// datagrams can't start a QUIC connection.
+pub fn is_quic_initial(packet: &[u8]) -> bool {
+    // Long header with the fixed bit, type Initial.
+    packet.len() >= 7 && packet[0] & 0xf0 == 0xc0 && packet[1..5] == [0, 0, 0, 1]
+}
+
+// Max number of Initial packets a ClientHello could span.
+#[cfg(feature = "ring")]
+const QUIC_MAX_INITIAL_PACKETS: usize = 4;
+
+/// Finds the server name in the ClientHello carried by the Initial packets
+/// of a QUIC v1 connection, large ClientHellos span more than one packet.
+#[derive(Default)]
+pub struct QuicSniffer {
+    #[cfg(feature = "ring")]
+    dcid: Vec<u8>,
+    // CRYPTO frames by offsets, clients may send them out of order.
+    #[cfg(feature = "ring")]
+    frames: Vec<(usize, Vec<u8>)>,
+    #[cfg(feature = "ring")]
+    packets: usize,
+}
+
+impl QuicSniffer {
+    /// Feeds a datagram sent by the client, returns Pending if the next
+    /// datagram is needed.
+    #[cfg(feature = "ring")]
+    pub fn feed(&mut self, packet: &[u8]) -> Poll<Option<String>> {
+        let (dcid, frames) = match quic::decrypt_initial(packet) {
+            Some(v) => v,
+            None => return Poll::Ready(None),
+        };
+        // Initial packets of a connection have the same DCID before the
+        // server responds.
+        if self.packets > 0 && dcid != self.dcid {
+            return Poll::Ready(None);
+        }
+        self.dcid = dcid;
+        self.packets += 1;
+        self.frames.extend(frames);
+        self.frames.sort_by_key(|(offset, _)| *offset);
+
+        let mut crypto = Vec::new();
+        for (offset, data) in self.frames.iter() {
+            let offset = *offset;
+            if offset > crypto.len() {
+                break;
+            }
+            if offset + data.len() > crypto.len() {
+                crypto.extend_from_slice(&data[crypto.len() - offset..]);
+            }
+        }
+        match client_hello_sni(&crypto) {
+            Sniffed::Domain(domain) => Poll::Ready(Some(domain)),
+            Sniffed::Incomplete if self.packets < QUIC_MAX_INITIAL_PACKETS => Poll::Pending,
+            _ => Poll::Ready(None),
+        }
+    }
+
+    #[cfg(not(feature = "ring"))]
+    pub fn feed(&mut self, _packet: &[u8]) -> Poll<Option<String>> {
+        Poll::Ready(None)
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc9001.html#name-initial-secrets
+#[cfg(feature = "ring")]
+mod quic {
+    use ring::aead::{self, quic::HeaderProtectionKey, Aad, LessSafeKey, Nonce, UnboundKey};
+    use ring::hkdf;
+
+    const INITIAL_SALT: [u8; 20] = [
+        0x38, 0x76, 0x2c, 0xf7, 0xf5, 0x59, 0x34, 0xb3, 0x4d, 0x17, 0x9a, 0xe6, 0xa4, 0xc8, 0x0c,
+        0xad, 0xcc, 0xbb, 0x7f, 0x0a,
+    ];
+
+    struct Len(usize);
+
+    impl hkdf::KeyType for Len {
+        fn len(&self) -> usize {
+            self.0
+        }
+    }
+
+    fn expand_label(prk: &hkdf::Prk, label: &[u8], out: &mut [u8]) -> Option<()> {
+        let out_len = (out.len() as u16).to_be_bytes();
+        let label_len = [6 + label.len() as u8];
+        let info = [&out_len[..], &label_len[..], b"tls13 ", label, &[0u8][..]];
+        prk.expand(&info, Len(out.len())).ok()?.fill(out).ok()
+    }
+
+    // Returns the key, IV and header protection key of client Initial packets.
+    pub(super) fn client_keys(dcid: &[u8]) -> Option<([u8; 16], [u8; 12], [u8; 16])> {
+        let initial = hkdf::Salt::new(hkdf::HKDF_SHA256, &INITIAL_SALT).extract(dcid);
+        let mut secret = [0u8; 32];
+        expand_label(&initial, b"client in", &mut secret)?;
+        let client = hkdf::Prk::new_less_safe(hkdf::HKDF_SHA256, &secret);
+        let mut key = [0u8; 16];
+        let mut iv = [0u8; 12];
+        let mut hp = [0u8; 16];
+        expand_label(&client, b"quic key", &mut key)?;
+        expand_label(&client, b"quic iv", &mut iv)?;
+        expand_label(&client, b"quic hp", &mut hp)?;
+        Some((key, iv, hp))
+    }
+
+    fn read_varint(buf: &[u8]) -> Option<(u64, usize)> {
+        let first = *buf.first()?;
+        let len = 1 << (first >> 6);
+        if buf.len() < len {
+            return None;
+        }
+        let mut v = (first & 0x3f) as u64;
+        for b in &buf[1..len] {
+            v = (v << 8) | *b as u64;
+        }
+        Some((v, len))
+    }
+
+    // Decrypts a client Initial packet and returns the DCID and the CRYPTO
+    // frames.
+    #[allow(clippy::type_complexity)]
+    pub(super) fn decrypt_initial(packet: &[u8]) -> Option<(Vec<u8>, Vec<(usize, Vec<u8>)>)> {
+        if !super::is_quic_initial(packet) {
+            return None;
+        }
+        let mut pos = 5;
+        let dcid_len = packet[pos] as usize;
+        let dcid = packet.get(pos + 1..pos + 1 + dcid_len)?;
+        pos += 1 + dcid_len;
+        let scid_len = *packet.get(pos)? as usize;
+        pos += 1 + scid_len;
+        let (token_len, n) = read_varint(packet.get(pos..)?)?;
+        pos += n + token_len as usize;
+        let (length, n) = read_varint(packet.get(pos..)?)?;
+        pos += n;
+        let pn_offset = pos;
+        let end = pn_offset.checked_add(length as usize)?;
+        if end > packet.len() || length < 20 {
+            return None;
+        }
+
+        let (key, iv, hp) = client_keys(dcid)?;
+        let hp = HeaderProtectionKey::new(&aead::quic::AES_128, &hp).ok()?;
+        let mask = hp.new_mask(&packet[pn_offset + 4..pn_offset + 20]).ok()?;
+        let mut header = packet[..pn_offset + 4].to_vec();
+        header[0] ^= mask[0] & 0x0f;
+        let pn_len = (header[0] & 0x03) as usize + 1;
+        header.truncate(pn_offset + pn_len);
+        let mut nonce = iv;
+        for i in 0..pn_len {
+            header[pn_offset + i] ^= mask[1 + i];
+            nonce[12 - pn_len + i] ^= header[pn_offset + i];
+        }
+
+        let key = LessSafeKey::new(UnboundKey::new(&aead::AES_128_GCM, &key).ok()?);
+        let mut payload = packet[pn_offset + pn_len..end].to_vec();
+        let plain = key
+            .open_in_place(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(&header),
+                &mut payload,
+            )
+            .ok()?;
+
+        let mut frames = Vec::new();
+        let mut buf = &plain[..];
+        while let Some(&frame_type) = buf.first() {
+            match frame_type {
+                // PADDING, PING
+                0x00 | 0x01 => buf = &buf[1..],
+                // CRYPTO
+                0x06 => {
+                    let (offset, n1) = read_varint(&buf[1..])?;
+                    let (len, n2) = read_varint(&buf[1 + n1..])?;
+                    let start = 1 + n1 + n2;
+                    let data = buf.get(start..start + len as usize)?;
+                    frames.push((offset as usize, data.to_vec()));
+                    buf = &buf[start + len as usize..];
+                }
+                _ => break,
+            }
+        }
+        Some((dcid.to_vec(), frames))
+    }
+}
+
 impl<T: AsyncRead + Unpin> AsyncRead for SniffingStream<T> {
     fn poll_read(
         mut self: Pin<&mut Self>,
@@ -189,3 +509,197 @@ impl<T: AsyncWrite + Unpin> AsyncWrite for SniffingStream<T> {
         AsyncWrite::poll_shutdown(Pin::new(&mut self.inner), cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+
+    fn client_hello(name: &str) -> Vec<u8> {
+        let mut sni = vec![0x00, 0x00];
+        let list_len = 3 + name.len();
+        sni.extend_from_slice(&((list_len + 2) as u16).to_be_bytes());
+        sni.extend_from_slice(&(list_len as u16).to_be_bytes());
+        sni.push(0x00);
+        sni.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        sni.extend_from_slice(name.as_bytes());
+
+        let mut body = vec![0x03, 0x03];
+        body.extend_from_slice(&[0u8; 32]);
+        // session id, cipher suites, compression methods
+        body.extend_from_slice(&[0x00, 0x00, 0x02, 0x13, 0x01, 0x01, 0x00]);
+        body.extend_from_slice(&(sni.len() as u16).to_be_bytes());
+        body.extend_from_slice(&sni);
+
+        let mut hs = vec![0x01, 0x00];
+        hs.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        hs.extend_from_slice(&body);
+        hs
+    }
+
+    fn domain(res: Sniffed) -> Option<String> {
+        match res {
+            Sniffed::Domain(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_tls_sni() {
+        let hs = client_hello("example.com");
+        let mut record = vec![0x16, 0x03, 0x01];
+        record.extend_from_slice(&(hs.len() as u16).to_be_bytes());
+        record.extend_from_slice(&hs);
+        assert_eq!(domain(tls_sni(&record)), Some("example.com".to_string()));
+        assert!(matches!(tls_sni(&record[..20]), Sniffed::Incomplete));
+        assert!(matches!(tls_sni(b"GET / HTTP/1.1\r\n"), Sniffed::Unknown));
+    }
+
+    #[test]
+    fn test_http_host() {
+        let req = b"GET / HTTP/1.1\r\nUser-Agent: curl\r\nHost: example.com:8080\r\n\r\n";
+        assert_eq!(domain(http_host(req)), Some("example.com".to_string()));
+        assert!(matches!(http_host(&req[..20]), Sniffed::Incomplete));
+        assert!(matches!(http_host(b"GE"), Sniffed::Incomplete));
+        assert!(matches!(
+            http_host(b"GET / HTTP/1.1\r\nHost: 1.2.3.4\r\n\r\n"),
+            Sniffed::Unknown
+        ));
+        assert!(matches!(
+            http_host(b"GET / HTTP/1.1\r\n\r\nHost: example.com\r\n"),
+            Sniffed::Unknown
+        ));
+        assert!(matches!(http_host(&[0x16, 0x03, 0x01]), Sniffed::Unknown));
+    }
+
+    #[test]
+    fn test_sniffing_stream() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let (client, server) = tokio::io::duplex(1024);
+            let (_, mut writer) = tokio::io::split(client);
+            let req = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n";
+            writer.write_all(&req[..20]).await.unwrap();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                writer.write_all(&req[20..]).await.unwrap();
+            });
+            let mut stream = SniffingStream::new(server);
+            let domain = stream
+                .sniff(&[Protocol::Tls, Protocol::Http])
+                .await
+                .unwrap();
+            assert_eq!(domain, Some("example.com".to_string()));
+            let mut buf = vec![0u8; req.len()];
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, req);
+        });
+    }
+
+    // Builds a client Initial packet with a 2-byte packet number.
+    #[cfg(feature = "ring")]
+    fn initial_packet(dcid: &[u8], pn: u8, mut payload: Vec<u8>) -> Vec<u8> {
+        use ring::aead::{self, quic::HeaderProtectionKey, Aad, LessSafeKey, Nonce, UnboundKey};
+
+        let (key, iv, hp) = quic::client_keys(dcid).unwrap();
+        payload.resize(200, 0);
+        let mut header = vec![0xc1, 0x00, 0x00, 0x00, 0x01, dcid.len() as u8];
+        header.extend_from_slice(dcid);
+        // SCID, token
+        header.extend_from_slice(&[0x00, 0x00]);
+        header.extend_from_slice(&((payload.len() + 2 + 16) as u16 | 0x4000).to_be_bytes());
+        let pn_offset = header.len();
+        header.extend_from_slice(&[0x00, pn]);
+
+        let mut nonce = iv;
+        nonce[11] ^= pn;
+        let sealing = LessSafeKey::new(UnboundKey::new(&aead::AES_128_GCM, &key).unwrap());
+        sealing
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(&header),
+                &mut payload,
+            )
+            .unwrap();
+        let mut packet = header;
+        packet.extend_from_slice(&payload);
+        let mask = HeaderProtectionKey::new(&aead::quic::AES_128, &hp)
+            .unwrap()
+            .new_mask(&packet[pn_offset + 4..pn_offset + 20])
+            .unwrap();
+        packet[0] ^= mask[0] & 0x0f;
+        packet[pn_offset] ^= mask[1];
+        packet[pn_offset + 1] ^= mask[2];
+        packet
+    }
+
+    #[cfg(feature = "ring")]
+    fn crypto_frame(offset: usize, data: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0x06];
+        frame.extend_from_slice(&(offset as u16 | 0x4000).to_be_bytes());
+        frame.extend_from_slice(&(data.len() as u16 | 0x4000).to_be_bytes());
+        frame.extend_from_slice(data);
+        frame
+    }
+
+    #[cfg(feature = "ring")]
+    #[test]
+    fn test_quic_sni() {
+        // https://www.rfc-editor.org/rfc/rfc9001.html#name-keys
+        let dcid = [0x83, 0x94, 0xc8, 0xf0, 0x3e, 0x51, 0x57, 0x08];
+        let (key, iv, hp) = quic::client_keys(&dcid).unwrap();
+        assert_eq!(
+            key,
+            [
+                0x1f, 0x36, 0x96, 0x13, 0xdd, 0x76, 0xd5, 0x46, 0x77, 0x30, 0xef, 0xcb, 0xe3, 0xb1,
+                0xa2, 0x2d
+            ]
+        );
+        assert_eq!(
+            iv,
+            [0xfa, 0x04, 0x4b, 0x2f, 0x42, 0xa3, 0xfd, 0x3b, 0x46, 0xfb, 0x25, 0x5c]
+        );
+        assert_eq!(
+            hp,
+            [
+                0x9f, 0x50, 0x44, 0x9e, 0x04, 0xa0, 0xe8, 0x10, 0x28, 0x3a, 0x1e, 0x99, 0x33, 0xad,
+                0xed, 0xd2
+            ]
+        );
+
+        let hs = client_hello("example.com");
+
+        // A CRYPTO frame split in two, sent in reverse order.
+        let mut payload = crypto_frame(10, &hs[10..]);
+        payload.extend(crypto_frame(0, &hs[..10]));
+        let mut packet = initial_packet(&dcid, 2, payload);
+        assert!(is_quic_initial(&packet));
+        let mut sniffer = QuicSniffer::default();
+        assert_eq!(
+            sniffer.feed(&packet),
+            Poll::Ready(Some("example.com".to_string()))
+        );
+
+        packet[40] ^= 0xff;
+        let mut sniffer = QuicSniffer::default();
+        assert_eq!(sniffer.feed(&packet), Poll::Ready(None));
+
+        // The ClientHello spans two packets.
+        let mut sniffer = QuicSniffer::default();
+        let packet = initial_packet(&dcid, 0, crypto_frame(0, &hs[..40]));
+        assert_eq!(sniffer.feed(&packet), Poll::Pending);
+        let packet = initial_packet(&dcid, 1, crypto_frame(40, &hs[40..]));
+        assert_eq!(
+            sniffer.feed(&packet),
+            Poll::Ready(Some("example.com".to_string()))
+        );
+
+        assert!(!is_quic_initial(b"not quic"));
+        let mut sniffer = QuicSniffer::default();
+        assert_eq!(sniffer.feed(b"not quic"), Poll::Ready(None));
+    }
+}
@@ -0,0 +1,249 @@
+use std::io;
+
+use bytes::{BufMut, BytesMut};
+use ring::{aead, digest, hkdf, hmac};
+
+use super::wire::*;
+
+/// A TLS 1.3 cipher suite.
+#[derive(Clone, Copy)]
+pub struct Suite {
+    aead: &'static aead::Algorithm,
+    hkdf: hkdf::Algorithm,
+    hmac: hmac::Algorithm,
+    pub hash: &'static digest::Algorithm,
+}
+
+// In the order of preference of Chrome.
+pub const CIPHER_SUITES: &[u16] = &[0x1301, 0x1302, 0x1303];
+
+impl Suite {
+    pub fn from_id(id: u16) -> Option<Self> {
+        match id {
+            // TLS_AES_128_GCM_SHA256
+            0x1301 => Some(Suite {
+                aead: &aead::AES_128_GCM,
+                hkdf: hkdf::HKDF_SHA256,
+                hmac: hmac::HMAC_SHA256,
+                hash: &digest::SHA256,
+            }),
+            // TLS_AES_256_GCM_SHA384
+            0x1302 => Some(Suite {
+                aead: &aead::AES_256_GCM,
+                hkdf: hkdf::HKDF_SHA384,
+                hmac: hmac::HMAC_SHA384,
+                hash: &digest::SHA384,
+            }),
+            // TLS_CHACHA20_POLY1305_SHA256
+            0x1303 => Some(Suite {
+                aead: &aead::CHACHA20_POLY1305,
+                hkdf: hkdf::HKDF_SHA256,
+                hmac: hmac::HMAC_SHA256,
+                hash: &digest::SHA256,
+            }),
+            _ => None,
+        }
+    }
+
+    fn hash_len(&self) -> usize {
+        self.hash.output_len
+    }
+
+    fn empty_hash(&self) -> digest::Digest {
+        digest::digest(self.hash, &[])
+    }
+
+    /// The handshake secret from the shared secret of the key exchange,
+    /// there's no PSK.
+    pub fn handshake_secret(&self, shared: &[u8]) -> hkdf::Prk {
+        let zeros = [0u8; 48];
+        let zeros = &zeros[..self.hash_len()];
+        let early = hkdf::Salt::new(self.hkdf, zeros).extract(zeros);
+        let derived = self.derive_secret(&early, b"derived", self.empty_hash().as_ref());
+        hkdf::Salt::new(self.hkdf, &derived).extract(shared)
+    }
+
+    pub fn master_secret(&self, handshake_secret: &hkdf::Prk) -> hkdf::Prk {
+        let zeros = [0u8; 48];
+        let derived = self.derive_secret(handshake_secret, b"derived", self.empty_hash().as_ref());
+        hkdf::Salt::new(self.hkdf, &derived).extract(&zeros[..self.hash_len()])
+    }
+
+    /// HKDF-Extract with a zero salt.
+    pub fn extract(&self, ikm: &[u8]) -> hkdf::Prk {
+        let zeros = [0u8; 48];
+        hkdf::Salt::new(self.hkdf, &zeros[..self.hash_len()]).extract(ikm)
+    }
+
+    pub fn derive_secret(&self, secret: &hkdf::Prk, label: &[u8], hash: &[u8]) -> Vec<u8> {
+        expand_label(secret, label, hash, self.hash_len())
+    }
+
+    /// The verify data of a Finished message.
+    pub fn finished(&self, traffic_secret: &[u8], hash: &[u8]) -> hmac::Tag {
+        let prk = hkdf::Prk::new_less_safe(self.hkdf, traffic_secret);
+        let key = expand_label(&prk, b"finished", &[], self.hash_len());
+        hmac::sign(&hmac::Key::new(self.hmac, &key), hash)
+    }
+}
+
+/// An output length of HKDF-Expand.
+pub struct Len(pub usize);
+
+impl hkdf::KeyType for Len {
+    fn len(&self) -> usize {
+        self.0
+    }
+}
+
+/// HKDF-Expand-Label of RFC 8446.
+pub fn expand_label(secret: &hkdf::Prk, label: &[u8], context: &[u8], len: usize) -> Vec<u8> {
+    let out_len = (len as u16).to_be_bytes();
+    let label_len = [6 + label.len() as u8];
+    let context_len = [context.len() as u8];
+    let info = [
+        &out_len[..],
+        &label_len,
+        b"tls13 ",
+        label,
+        &context_len,
+        context,
+    ];
+    let mut out = vec![0u8; len];
+    // Expanding fails only if the length is too large for the hash.
+    secret
+        .expand(&info, Len(len))
+        .and_then(|okm| okm.fill(&mut out))
+        .unwrap();
+    out
+}
+
+/// The keys of a traffic secret protecting records in one direction.
+pub struct RecordKey {
+    suite: Suite,
+    secret: Vec<u8>,
+    key: aead::LessSafeKey,
+    iv: [u8; aead::NONCE_LEN],
+    seq: u64,
+}
+
+impl RecordKey {
+    pub fn new(suite: Suite, secret: Vec<u8>) -> Self {
+        let prk = hkdf::Prk::new_less_safe(suite.hkdf, &secret);
+        let key = expand_label(&prk, b"key", &[], suite.aead.key_len());
+        let mut iv = [0u8; aead::NONCE_LEN];
+        iv.copy_from_slice(&expand_label(&prk, b"iv", &[], aead::NONCE_LEN));
+        RecordKey {
+            suite,
+            secret,
+            // The key has the length of the algorithm.
+            key: aead::LessSafeKey::new(aead::UnboundKey::new(suite.aead, &key).unwrap()),
+            iv,
+            seq: 0,
+        }
+    }
+
+    /// The keys of the next traffic secret, after a KeyUpdate.
+    pub fn update(&self) -> Self {
+        let prk = hkdf::Prk::new_less_safe(self.suite.hkdf, &self.secret);
+        let secret = expand_label(&prk, b"traffic upd", &[], self.suite.hash_len());
+        RecordKey::new(self.suite, secret)
+    }
+
+    fn nonce(&mut self) -> aead::Nonce {
+        let mut nonce = self.iv;
+        for (n, s) in nonce[4..].iter_mut().zip(self.seq.to_be_bytes()) {
+            *n ^= s;
+        }
+        self.seq += 1;
+        aead::Nonce::assume_unique_for_key(nonce)
+    }
+
+    /// Encrypts `data` of `content_type` into a record appended to `out`.
+    pub fn seal(&mut self, content_type: u8, data: &[u8], out: &mut BytesMut) {
+        let len = data.len() + 1 + self.key.algorithm().tag_len();
+        let header = [CONTENT_APPLICATION_DATA, 3, 3, (len >> 8) as u8, len as u8];
+        let mut payload = Vec::with_capacity(len);
+        payload.extend_from_slice(data);
+        payload.push(content_type);
+        let nonce = self.nonce();
+        // Sealing fails only if the data is too long for the cipher.
+        self.key
+            .seal_in_place_append_tag(nonce, aead::Aad::from(header), &mut payload)
+            .unwrap();
+        out.put_slice(&header);
+        out.put_slice(&payload);
+    }
+
+    /// Decrypts the payload of a record in place, returns the content type
+    /// and the data.
+    pub fn open<'a>(&mut self, header: &[u8], payload: &'a mut [u8]) -> io::Result<(u8, &'a [u8])> {
+        let nonce = self.nonce();
+        let plain = self
+            .key
+            .open_in_place(nonce, aead::Aad::from(header), payload)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "decryption failed"))?;
+        // The content type is the last non-zero byte, followed by padding.
+        let end = plain.iter().rposition(|b| *b != 0).ok_or_else(malformed)?;
+        Ok((plain[end], &plain[..end]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_schedule() {
+        // The simple 1-RTT handshake of RFC 8448.
+        let suite = Suite::from_id(0x1301).unwrap();
+        let shared =
+            hex::decode("8bd4054fb55b9d63fdfbacf9f04b9f0d35e6d63f537563efd46272900f89492d")
+                .unwrap();
+        let hash = hex::decode("860c06edc07858ee8e78f0e7428c58edd6b43f2ca3e6e95f02ed063cf0e1cad8")
+            .unwrap();
+        let handshake_secret = suite.handshake_secret(&shared);
+        assert_eq!(
+            hex::encode(suite.derive_secret(&handshake_secret, b"c hs traffic", &hash)),
+            "b3eddb126e067f35a780b3abf45e2d8f3b1a950738f52e9600746a0e27a55a21"
+        );
+        let server_secret = suite.derive_secret(&handshake_secret, b"s hs traffic", &hash);
+        assert_eq!(
+            hex::encode(&server_secret),
+            "b67b7d690cc16c4e75e54213cb2d37b4e9c912bcded9105d42befd59d391ad38"
+        );
+        let key = RecordKey::new(suite, server_secret);
+        assert_eq!(hex::encode(key.iv), "5d313eb2671276ee13000b30");
+    }
+
+    #[test]
+    fn test_record() {
+        for id in CIPHER_SUITES {
+            let suite = Suite::from_id(*id).unwrap();
+            let mut sealer = RecordKey::new(suite, vec![1u8; suite.hash_len()]);
+            let mut opener = RecordKey::new(suite, vec![1u8; suite.hash_len()]);
+            let mut buf = BytesMut::new();
+            sealer.seal(CONTENT_APPLICATION_DATA, b"hello", &mut buf);
+            sealer.seal(CONTENT_HANDSHAKE, b"", &mut buf);
+            for (content_type, data) in [(CONTENT_APPLICATION_DATA, &b"hello"[..]), (22, b"")] {
+                let mut record = buf.split_to(RECORD_HEADER_LEN + data.len() + 17);
+                let (header, payload) = record.split_at_mut(RECORD_HEADER_LEN);
+                assert_eq!(header[0], CONTENT_APPLICATION_DATA);
+                assert_eq!(opener.open(header, payload).unwrap(), (content_type, data));
+            }
+
+            // The keys of both sides are updated the same.
+            let mut sealer = sealer.update();
+            let mut opener = opener.update();
+            sealer.seal(CONTENT_APPLICATION_DATA, b"world", &mut buf);
+            let (header, payload) = buf.split_at_mut(RECORD_HEADER_LEN);
+            assert!(RecordKey::new(suite, vec![1u8; suite.hash_len()])
+                .open(header, &mut payload.to_vec())
+                .is_err());
+            assert_eq!(
+                opener.open(header, payload).unwrap(),
+                (CONTENT_APPLICATION_DATA, &b"world"[..])
+            );
+        }
+    }
+}
@@ -0,0 +1,419 @@
+use std::io;
+use std::net::IpAddr;
+
+use bytes::{BufMut, BytesMut};
+use ring::{agreement, constant_time, digest};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::crypto::{RecordKey, Suite, CIPHER_SUITES};
+use super::wire::*;
+
+// The TLS 1.2 cipher suites Chrome offers after the TLS 1.3 ones.
+const TLS12_CIPHER_SUITES: &[u16] = &[
+    0xc02b, 0xc02f, 0xc02c, 0xc030, 0xcca9, 0xcca8, 0xc013, 0xc014, 0x009c, 0x009d, 0x002f, 0x0035,
+];
+
+// The signature algorithms of Chrome.
+const SIGNATURE_ALGORITHMS: &[u16] = &[
+    0x0403, 0x0804, 0x0401, 0x0503, 0x0805, 0x0501, 0x0806, 0x0601,
+];
+
+// The random of a ServerHello which is a HelloRetryRequest.
+const HELLO_RETRY_REQUEST: [u8; 32] = [
+    0xcf, 0x21, 0xad, 0x74, 0xe5, 0x9a, 0x61, 0x11, 0xbe, 0x1d, 0x8c, 0x02, 0x1e, 0x65, 0xb8, 0x91,
+    0xc2, 0xa2, 0x11, 0x16, 0x7a, 0xbb, 0x8c, 0x5e, 0x07, 0x9e, 0x09, 0xe2, 0xc8, 0xa8, 0x33, 0x9c,
+];
+
+const MAX_HANDSHAKE_LEN: usize = 64 * 1024;
+
+pub fn handshake_err(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, msg)
+}
+
+/// The fields of a ClientHello which differ between connections, the rest
+/// are Chrome's.
+pub struct ClientHello<'a> {
+    pub random: &'a [u8; 32],
+    pub session_id: &'a [u8],
+    /// The X25519 public key.
+    pub key_share: &'a [u8],
+    pub server_name: &'a str,
+    pub alpns: &'a [String],
+    /// Offers TLS 1.2 besides TLS 1.3.
+    pub tls12: bool,
+    /// Extensions put after the others.
+    pub extensions: &'a [(u16, &'a [u8])],
+}
+
+impl<'a> ClientHello<'a> {
+    /// Encodes the handshake message.
+    pub fn encode(&self) -> Vec<u8> {
+        let tls12_cipher_suites = if self.tls12 { TLS12_CIPHER_SUITES } else { &[] };
+        let mut buf = BytesMut::new();
+        buf.put_u8(HANDSHAKE_CLIENT_HELLO);
+        put_prefixed(&mut buf, 3, |buf| {
+            buf.put_u16(TLS12);
+            buf.put_slice(self.random);
+            put_prefixed(buf, 1, |buf| buf.put_slice(self.session_id));
+            put_prefixed(buf, 2, |buf| {
+                for suite in CIPHER_SUITES.iter().chain(tls12_cipher_suites) {
+                    buf.put_u16(*suite);
+                }
+            });
+            // null compression
+            put_prefixed(buf, 1, |buf| buf.put_u8(0));
+            put_prefixed(buf, 2, |buf| {
+                if self.server_name.parse::<IpAddr>().is_err() {
+                    put_extension(buf, EXT_SERVER_NAME, |buf| {
+                        put_prefixed(buf, 2, |buf| {
+                            // host_name
+                            buf.put_u8(0);
+                            put_prefixed(buf, 2, |buf| buf.put_slice(self.server_name.as_bytes()));
+                        });
+                    });
+                }
+                put_extension(buf, EXT_EXTENDED_MASTER_SECRET, |_| ());
+                put_extension(buf, EXT_RENEGOTIATION_INFO, |buf| buf.put_u8(0));
+                put_extension(buf, EXT_SUPPORTED_GROUPS, |buf| {
+                    put_prefixed(buf, 2, |buf| {
+                        for group in [GROUP_X25519, GROUP_SECP256R1, GROUP_SECP384R1] {
+                            buf.put_u16(group);
+                        }
+                    });
+                });
+                // uncompressed
+                put_extension(buf, EXT_EC_POINT_FORMATS, |buf| {
+                    put_prefixed(buf, 1, |buf| buf.put_u8(0));
+                });
+                put_extension(buf, EXT_SESSION_TICKET, |_| ());
+                if !self.alpns.is_empty() {
+                    put_extension(buf, EXT_ALPN, |buf| {
+                        put_prefixed(buf, 2, |buf| {
+                            for alpn in self.alpns {
+                                put_prefixed(buf, 1, |buf| buf.put_slice(alpn.as_bytes()));
+                            }
+                        });
+                    });
+                }
+                // OCSP without responder IDs and extensions
+                put_extension(buf, EXT_STATUS_REQUEST, |buf| {
+                    buf.put_u8(1);
+                    buf.put_u16(0);
+                    buf.put_u16(0);
+                });
+                put_extension(buf, EXT_SIGNATURE_ALGORITHMS, |buf| {
+                    put_prefixed(buf, 2, |buf| {
+                        for alg in SIGNATURE_ALGORITHMS {
+                            buf.put_u16(*alg);
+                        }
+                    });
+                });
+                put_extension(buf, EXT_SCT, |_| ());
+                put_extension(buf, EXT_KEY_SHARE, |buf| {
+                    put_prefixed(buf, 2, |buf| {
+                        buf.put_u16(GROUP_X25519);
+                        put_prefixed(buf, 2, |buf| buf.put_slice(self.key_share));
+                    });
+                });
+                // psk_dhe_ke
+                put_extension(buf, EXT_PSK_KEY_EXCHANGE_MODES, |buf| {
+                    put_prefixed(buf, 1, |buf| buf.put_u8(1));
+                });
+                put_extension(buf, EXT_SUPPORTED_VERSIONS, |buf| {
+                    put_prefixed(buf, 1, |buf| {
+                        buf.put_u16(TLS13);
+                        if self.tls12 {
+                            buf.put_u16(TLS12);
+                        }
+                    });
+                });
+                for (ext_type, data) in self.extensions {
+                    put_extension(buf, *ext_type, |buf| buf.put_slice(data));
+                }
+            });
+        });
+        buf.to_vec()
+    }
+}
+
+/// Sends a ClientHello message in a record.
+pub async fn send_client_hello<S>(stream: &mut S, hello: &[u8]) -> io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let mut record = BytesMut::new();
+    record.put_u8(CONTENT_HANDSHAKE);
+    record.put_u16(0x0301);
+    put_prefixed(&mut record, 2, |buf| buf.put_slice(hello));
+    stream.write_all(&record).await
+}
+
+/// Returns the cipher suite and the key share of the server.
+pub fn parse_server_hello(msg: &[u8], session_id: &[u8]) -> io::Result<(Suite, Vec<u8>)> {
+    let mut r = Reader::new(&msg[4..]);
+    // legacy_version
+    r.u16()?;
+    if r.bytes(32)? == HELLO_RETRY_REQUEST {
+        return Err(handshake_err(
+            "tls hello retry request isn't supported".to_string(),
+        ));
+    }
+    if r.prefixed(1)? != session_id {
+        return Err(malformed());
+    }
+    let suite = r.u16()?;
+    // legacy_compression_method
+    r.u8()?;
+    let mut extensions = Reader::new(r.prefixed(2)?);
+    let mut version = None;
+    let mut key_share = None;
+    while !extensions.is_empty() {
+        let ext_type = extensions.u16()?;
+        let mut data = Reader::new(extensions.prefixed(2)?);
+        match ext_type {
+            EXT_SUPPORTED_VERSIONS => version = Some(data.u16()?),
+            EXT_KEY_SHARE => {
+                if data.u16()? != GROUP_X25519 {
+                    return Err(malformed());
+                }
+                key_share = Some(data.prefixed(2)?.to_vec());
+            }
+            _ => (),
+        }
+    }
+    if version != Some(TLS13) {
+        return Err(handshake_err("tls 1.3 isn't negotiated".to_string()));
+    }
+    let suite = Suite::from_id(suite)
+        .ok_or_else(|| handshake_err(format!("unsupported tls cipher suite {:#06x}", suite)))?;
+    Ok((suite, key_share.ok_or_else(malformed)?))
+}
+
+// Returns the certificate chain of a Certificate message.
+fn parse_certificates(msg: &[u8]) -> io::Result<Vec<&[u8]>> {
+    let mut r = Reader::new(&msg[4..]);
+    // certificate_request_context
+    r.prefixed(1)?;
+    let mut entries = Reader::new(r.prefixed(3)?);
+    let mut certs = Vec::new();
+    while !entries.is_empty() {
+        certs.push(entries.prefixed(3)?);
+        // extensions
+        entries.prefixed(2)?;
+    }
+    if certs.is_empty() {
+        return Err(malformed());
+    }
+    Ok(certs)
+}
+
+/// What the server authenticates itself with.
+pub struct ServerAuth<'a> {
+    /// The end-entity certificate first.
+    pub certificates: Vec<&'a [u8]>,
+    /// The signature scheme of the CertificateVerify.
+    pub scheme: u16,
+    pub signature: &'a [u8],
+    /// The content signed.
+    pub content: Vec<u8>,
+}
+
+impl<'a> ServerAuth<'a> {
+    fn new(certificate: &'a [u8], certificate_verify: &'a [u8], hash: &[u8]) -> io::Result<Self> {
+        let mut r = Reader::new(&certificate_verify[4..]);
+        let scheme = r.u16()?;
+        let signature = r.prefixed(2)?;
+        let mut content = vec![0x20u8; 64];
+        content.extend_from_slice(b"TLS 1.3, server CertificateVerify\0");
+        content.extend_from_slice(hash);
+        Ok(ServerAuth {
+            certificates: parse_certificates(certificate)?,
+            scheme,
+            signature,
+            content,
+        })
+    }
+}
+
+/// Reassembles handshake messages from records.
+#[derive(Default)]
+pub struct HandshakeReader {
+    buf: Vec<u8>,
+}
+
+impl HandshakeReader {
+    /// Reads a message of `msg_type`, records are decrypted with `key` if
+    /// given.
+    pub async fn read<S>(
+        &mut self,
+        stream: &mut S,
+        mut key: Option<&mut RecordKey>,
+        msg_type: u8,
+    ) -> io::Result<Vec<u8>>
+    where
+        S: AsyncRead + Unpin,
+    {
+        loop {
+            if self.buf.len() >= 4 {
+                let len = Reader::new(&self.buf[1..4]).u24()?;
+                if len > MAX_HANDSHAKE_LEN {
+                    return Err(malformed());
+                }
+                if self.buf.len() >= 4 + len {
+                    let msg: Vec<u8> = self.buf.drain(..4 + len).collect();
+                    if msg[0] != msg_type {
+                        return Err(handshake_err(format!(
+                            "unexpected tls message {}, expecting {}",
+                            msg[0], msg_type
+                        )));
+                    }
+                    return Ok(msg);
+                }
+            }
+            let mut header = [0u8; RECORD_HEADER_LEN];
+            stream.read_exact(&mut header).await?;
+            let len = u16::from_be_bytes([header[3], header[4]]) as usize;
+            if len > MAX_CIPHERTEXT_LEN {
+                return Err(malformed());
+            }
+            let mut payload = vec![0u8; len];
+            stream.read_exact(&mut payload).await?;
+            match (header[0], key.as_mut()) {
+                // Sent for middlebox compatibility.
+                (CONTENT_CHANGE_CIPHER_SPEC, _) => (),
+                (CONTENT_ALERT, _) => {
+                    return Err(alert_err(payload.get(1).copied().unwrap_or(0)));
+                }
+                (CONTENT_HANDSHAKE, None) => self.buf.extend_from_slice(&payload),
+                (CONTENT_APPLICATION_DATA, Some(key)) => match key.open(&header, &mut payload)? {
+                    (CONTENT_HANDSHAKE, data) => self.buf.extend_from_slice(data),
+                    (CONTENT_ALERT, data) => {
+                        return Err(alert_err(data.get(1).copied().unwrap_or(0)));
+                    }
+                    _ => return Err(malformed()),
+                },
+                _ => return Err(malformed()),
+            }
+        }
+    }
+}
+
+pub fn x25519(
+    private_key: agreement::EphemeralPrivateKey,
+    public_key: &[u8],
+) -> io::Result<Vec<u8>> {
+    agreement::agree_ephemeral(
+        private_key,
+        &agreement::UnparsedPublicKey::new(&agreement::X25519, public_key),
+        handshake_err("tls key exchange failed".to_string()),
+        |k| Ok(k.to_vec()),
+    )
+}
+
+/// Runs the rest of the handshake once the ServerHello is in the
+/// `transcript`, the server is authenticated by `verify`. Returns the keys
+/// of the records from and to the server.
+pub async fn finish<S, F>(
+    stream: &mut S,
+    reader: &mut HandshakeReader,
+    suite: Suite,
+    shared: &[u8],
+    mut transcript: digest::Context,
+    verify: F,
+) -> io::Result<(RecordKey, RecordKey)>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    F: FnOnce(&ServerAuth) -> io::Result<()>,
+{
+    let hash = transcript.clone().finish();
+    let handshake_secret = suite.handshake_secret(shared);
+    let client_secret = suite.derive_secret(&handshake_secret, b"c hs traffic", hash.as_ref());
+    let server_secret = suite.derive_secret(&handshake_secret, b"s hs traffic", hash.as_ref());
+    let mut opener = RecordKey::new(suite, server_secret.clone());
+
+    let msg = reader
+        .read(stream, Some(&mut opener), HANDSHAKE_ENCRYPTED_EXTENSIONS)
+        .await?;
+    transcript.update(&msg);
+    let certificate = reader
+        .read(stream, Some(&mut opener), HANDSHAKE_CERTIFICATE)
+        .await?;
+    transcript.update(&certificate);
+    let certificate_verify = reader
+        .read(stream, Some(&mut opener), HANDSHAKE_CERTIFICATE_VERIFY)
+        .await?;
+    verify(&ServerAuth::new(
+        &certificate,
+        &certificate_verify,
+        transcript.clone().finish().as_ref(),
+    )?)?;
+    transcript.update(&certificate_verify);
+    let msg = reader
+        .read(stream, Some(&mut opener), HANDSHAKE_FINISHED)
+        .await?;
+    let verify_data = suite.finished(&server_secret, transcript.clone().finish().as_ref());
+    constant_time::verify_slices_are_equal(&msg[4..], verify_data.as_ref())
+        .map_err(|_| handshake_err("invalid tls server finished".to_string()))?;
+    transcript.update(&msg);
+    if !reader.buf.is_empty() {
+        return Err(malformed());
+    }
+
+    let hash = transcript.finish();
+    let master_secret = suite.master_secret(&handshake_secret);
+    let client_secret_app = suite.derive_secret(&master_secret, b"c ap traffic", hash.as_ref());
+    let server_secret_app = suite.derive_secret(&master_secret, b"s ap traffic", hash.as_ref());
+
+    let verify_data = suite.finished(&client_secret, hash.as_ref());
+    let mut finished = BytesMut::new();
+    finished.put_u8(HANDSHAKE_FINISHED);
+    put_prefixed(&mut finished, 3, |buf| buf.put_slice(verify_data.as_ref()));
+    // A ChangeCipherSpec comes first as the session ID isn't empty.
+    let mut buf = BytesMut::from(&[CONTENT_CHANGE_CIPHER_SPEC, 3, 3, 0, 1, 1][..]);
+    RecordKey::new(suite, client_secret).seal(CONTENT_HANDSHAKE, &finished, &mut buf);
+    stream.write_all(&buf).await?;
+
+    Ok((
+        RecordKey::new(suite, server_secret_app),
+        RecordKey::new(suite, client_secret_app),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_hello() {
+        let alpns = ["h2".to_string()];
+        let extensions: &[(u16, &[u8])] = &[(0xfe0d, &[1])];
+        let hello = ClientHello {
+            random: &[1u8; 32],
+            session_id: &[],
+            key_share: &[2u8; 32],
+            server_name: "example.com",
+            alpns: &alpns,
+            tls12: false,
+            extensions,
+        }
+        .encode();
+        assert_eq!(Reader::new(&hello[1..4]).u24().unwrap(), hello.len() - 4);
+        let mut r = Reader::new(&hello[4..]);
+        r.bytes(2 + 32).unwrap();
+        assert!(r.prefixed(1).unwrap().is_empty());
+        assert_eq!(r.prefixed(2).unwrap(), [0x13, 0x01, 0x13, 0x02, 0x13, 0x03]);
+        r.prefixed(1).unwrap();
+        let mut extensions = Reader::new(r.prefixed(2).unwrap());
+        assert!(r.is_empty());
+        let mut last = None;
+        while !extensions.is_empty() {
+            let ext_type = extensions.u16().unwrap();
+            let data = extensions.prefixed(2).unwrap();
+            if ext_type == EXT_SUPPORTED_VERSIONS {
+                assert_eq!(data, [2, 3, 4]);
+            }
+            last = Some((ext_type, data));
+        }
+        assert_eq!(last, Some((0xfe0d, &[1u8][..])));
+    }
+}
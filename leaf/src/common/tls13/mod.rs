@@ -0,0 +1,10 @@
+//! A minimal TLS 1.3 client for the handshakes rustls and OpenSSL can't make,
+//! those of REALITY and ECH. Only the X25519 key exchange is supported, the
+//! ClientHello resembles Chrome's without GREASE.
+
+pub mod crypto;
+pub mod handshake;
+pub mod stream;
+pub mod wire;
+
+pub use stream::Stream;
@@ -0,0 +1,252 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{Buf, BufMut, BytesMut};
+use futures::ready;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use super::crypto::RecordKey;
+use super::wire::*;
+
+/// The application data of a connection after the handshake.
+pub struct Stream<S> {
+    inner: S,
+    opener: RecordKey,
+    sealer: RecordKey,
+    read_buf: BytesMut,
+    data: BytesMut,
+    eof: bool,
+    write_buf: BytesMut,
+    written: usize,
+    // The server asked for our keys to be updated.
+    key_update: bool,
+    closing: bool,
+}
+
+impl<S> Stream<S> {
+    pub fn new(inner: S, opener: RecordKey, sealer: RecordKey) -> Self {
+        Stream {
+            inner,
+            opener,
+            sealer,
+            read_buf: BytesMut::new(),
+            data: BytesMut::new(),
+            eof: false,
+            write_buf: BytesMut::new(),
+            written: 0,
+            key_update: false,
+            closing: false,
+        }
+    }
+
+    // Decrypts the records received completely.
+    fn process_records(&mut self) -> io::Result<()> {
+        while self.read_buf.len() >= RECORD_HEADER_LEN && !self.eof {
+            let len = u16::from_be_bytes([self.read_buf[3], self.read_buf[4]]) as usize;
+            if len > MAX_CIPHERTEXT_LEN {
+                return Err(malformed());
+            }
+            if self.read_buf.len() < RECORD_HEADER_LEN + len {
+                break;
+            }
+            let mut record = self.read_buf.split_to(RECORD_HEADER_LEN + len);
+            let (header, payload) = record.split_at_mut(RECORD_HEADER_LEN);
+            match header[0] {
+                CONTENT_APPLICATION_DATA => (),
+                CONTENT_CHANGE_CIPHER_SPEC => continue,
+                CONTENT_ALERT => return Err(alert_err(payload.get(1).copied().unwrap_or(0))),
+                _ => return Err(malformed()),
+            }
+            let (content_type, data) = self.opener.open(header, payload)?;
+            match content_type {
+                CONTENT_APPLICATION_DATA => self.data.put_slice(data),
+                CONTENT_HANDSHAKE => self.process_handshake(data)?,
+                CONTENT_ALERT => match data.get(1).copied() {
+                    Some(ALERT_CLOSE_NOTIFY) => self.eof = true,
+                    description => return Err(alert_err(description.unwrap_or(0))),
+                },
+                _ => return Err(malformed()),
+            }
+        }
+        Ok(())
+    }
+
+    // Handles post-handshake messages, each is expected in a single record.
+    fn process_handshake(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut r = Reader::new(data);
+        while !r.is_empty() {
+            let msg_type = r.u8()?;
+            let body = r.prefixed(3)?;
+            match msg_type {
+                // Sessions aren't resumed.
+                HANDSHAKE_NEW_SESSION_TICKET => (),
+                HANDSHAKE_KEY_UPDATE => {
+                    self.opener = self.opener.update();
+                    if body.first() == Some(&1) {
+                        self.key_update = true;
+                    }
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unexpected tls message {}", msg_type),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S: AsyncWrite + Unpin> Stream<S> {
+    fn poll_write_buf(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.write_buf.is_empty() {
+            let n = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.write_buf))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.write_buf.advance(n);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Stream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let me = &mut *self;
+        loop {
+            if !me.data.is_empty() {
+                let n = me.data.len().min(buf.remaining());
+                buf.put_slice(&me.data[..n]);
+                me.data.advance(n);
+                return Poll::Ready(Ok(()));
+            }
+            if me.eof {
+                return Poll::Ready(Ok(()));
+            }
+            let start = me.read_buf.len();
+            me.read_buf
+                .resize(start + RECORD_HEADER_LEN + MAX_CIPHERTEXT_LEN, 0);
+            let mut read_buf = ReadBuf::new(&mut me.read_buf[start..]);
+            let res = Pin::new(&mut me.inner).poll_read(cx, &mut read_buf);
+            let n = read_buf.filled().len();
+            me.read_buf.truncate(start + n);
+            ready!(res)?;
+            if n == 0 {
+                if !me.read_buf.is_empty() {
+                    return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+                }
+                me.eof = true;
+                continue;
+            }
+            me.process_records()?;
+        }
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Stream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        if me.write_buf.is_empty() {
+            if buf.is_empty() {
+                return Poll::Ready(Ok(0));
+            }
+            if me.key_update {
+                // update_not_requested
+                me.sealer.seal(
+                    CONTENT_HANDSHAKE,
+                    &[HANDSHAKE_KEY_UPDATE, 0, 0, 1, 0],
+                    &mut me.write_buf,
+                );
+                me.sealer = me.sealer.update();
+                me.key_update = false;
+            }
+            let n = buf.len().min(MAX_FRAGMENT_LEN);
+            me.sealer
+                .seal(CONTENT_APPLICATION_DATA, &buf[..n], &mut me.write_buf);
+            me.written = n;
+        }
+        ready!(me.poll_write_buf(cx))?;
+        Poll::Ready(Ok(me.written))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_buf(cx))?;
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let me = &mut *self;
+        if !me.closing {
+            me.sealer
+                .seal(CONTENT_ALERT, &[1, ALERT_CLOSE_NOTIFY], &mut me.write_buf);
+            me.closing = true;
+        }
+        ready!(me.poll_write_buf(cx))?;
+        Pin::new(&mut me.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::super::crypto::Suite;
+    use super::*;
+
+    #[test]
+    fn test_stream() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let suite = Suite::from_id(0x1301).unwrap();
+            let key = |b| RecordKey::new(suite, vec![b; 32]);
+            let (client_io, server_io) = tokio::io::duplex(1024);
+            let mut client = Stream::new(client_io, key(1), key(2));
+            let mut server = Stream::new(server_io, key(2), key(1));
+
+            let data = vec![7u8; 100000];
+            let expected = data.clone();
+            let reader = tokio::spawn(async move {
+                let mut buf = vec![0u8; expected.len()];
+                server.read_exact(&mut buf).await.unwrap();
+                assert_eq!(buf, expected);
+                server
+            });
+            client.write_all(&data).await.unwrap();
+            let mut server = reader.await.unwrap();
+
+            // The server asks for a key update, the client answers before
+            // its next data.
+            server
+                .sealer
+                .seal(CONTENT_HANDSHAKE, &[24, 0, 0, 1, 1], &mut server.write_buf);
+            server.sealer = server.sealer.update();
+            server.flush().await.unwrap();
+            server.write_all(b"hello").await.unwrap();
+            let mut buf = [0u8; 5];
+            client.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"hello");
+            assert!(client.key_update);
+            client.write_all(b"world").await.unwrap();
+            server.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"world");
+            assert!(!server.key_update);
+
+            server.shutdown().await.unwrap();
+            let mut buf = Vec::new();
+            client.read_to_end(&mut buf).await.unwrap();
+            assert!(buf.is_empty());
+        });
+    }
+}
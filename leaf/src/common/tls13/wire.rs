@@ -0,0 +1,157 @@
+use std::io;
+
+use bytes::{BufMut, BytesMut};
+
+pub const CONTENT_CHANGE_CIPHER_SPEC: u8 = 20;
+pub const CONTENT_ALERT: u8 = 21;
+pub const CONTENT_HANDSHAKE: u8 = 22;
+pub const CONTENT_APPLICATION_DATA: u8 = 23;
+
+pub const HANDSHAKE_CLIENT_HELLO: u8 = 1;
+pub const HANDSHAKE_SERVER_HELLO: u8 = 2;
+pub const HANDSHAKE_NEW_SESSION_TICKET: u8 = 4;
+pub const HANDSHAKE_ENCRYPTED_EXTENSIONS: u8 = 8;
+pub const HANDSHAKE_CERTIFICATE: u8 = 11;
+pub const HANDSHAKE_CERTIFICATE_VERIFY: u8 = 15;
+pub const HANDSHAKE_FINISHED: u8 = 20;
+pub const HANDSHAKE_KEY_UPDATE: u8 = 24;
+
+pub const EXT_SERVER_NAME: u16 = 0;
+pub const EXT_STATUS_REQUEST: u16 = 5;
+pub const EXT_SUPPORTED_GROUPS: u16 = 10;
+pub const EXT_EC_POINT_FORMATS: u16 = 11;
+pub const EXT_SIGNATURE_ALGORITHMS: u16 = 13;
+pub const EXT_ALPN: u16 = 16;
+pub const EXT_SCT: u16 = 18;
+pub const EXT_EXTENDED_MASTER_SECRET: u16 = 23;
+pub const EXT_SESSION_TICKET: u16 = 35;
+pub const EXT_SUPPORTED_VERSIONS: u16 = 43;
+pub const EXT_PSK_KEY_EXCHANGE_MODES: u16 = 45;
+pub const EXT_KEY_SHARE: u16 = 51;
+pub const EXT_RENEGOTIATION_INFO: u16 = 0xff01;
+
+pub const GROUP_X25519: u16 = 0x001d;
+pub const GROUP_SECP256R1: u16 = 0x0017;
+pub const GROUP_SECP384R1: u16 = 0x0018;
+
+pub const SIG_ED25519: u16 = 0x0807;
+
+pub const TLS12: u16 = 0x0303;
+pub const TLS13: u16 = 0x0304;
+
+pub const ALERT_CLOSE_NOTIFY: u8 = 0;
+
+pub const RECORD_HEADER_LEN: usize = 5;
+pub const MAX_FRAGMENT_LEN: usize = 16384;
+// A ciphertext may be 256 bytes larger than the plaintext.
+pub const MAX_CIPHERTEXT_LEN: usize = MAX_FRAGMENT_LEN + 256;
+
+pub fn malformed() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "malformed tls message")
+}
+
+pub fn alert_err(description: u8) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::ConnectionAborted,
+        format!("received tls alert {}", description),
+    )
+}
+
+/// Writes the data put by `f` prefixed by its length in `len_size` bytes.
+pub fn put_prefixed<F>(buf: &mut BytesMut, len_size: usize, f: F)
+where
+    F: FnOnce(&mut BytesMut),
+{
+    let start = buf.len();
+    buf.put_slice(&[0u8; 3][..len_size]);
+    f(buf);
+    let len = buf.len() - start - len_size;
+    for i in 0..len_size {
+        buf[start + i] = (len >> (8 * (len_size - 1 - i))) as u8;
+    }
+}
+
+pub fn put_extension<F>(buf: &mut BytesMut, ext_type: u16, f: F)
+where
+    F: FnOnce(&mut BytesMut),
+{
+    buf.put_u16(ext_type);
+    put_prefixed(buf, 2, f);
+}
+
+/// Reads fields of a message.
+pub struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Reader { buf }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// The data not read yet.
+    pub fn rest(&self) -> &'a [u8] {
+        self.buf
+    }
+
+    pub fn bytes(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.buf.len() < n {
+            return Err(malformed());
+        }
+        let (b, rest) = self.buf.split_at(n);
+        self.buf = rest;
+        Ok(b)
+    }
+
+    pub fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> io::Result<u16> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    pub fn u24(&mut self) -> io::Result<usize> {
+        let b = self.bytes(3)?;
+        Ok((b[0] as usize) << 16 | (b[1] as usize) << 8 | b[2] as usize)
+    }
+
+    /// Reads data prefixed by its length in `len_size` bytes.
+    pub fn prefixed(&mut self, len_size: usize) -> io::Result<&'a [u8]> {
+        let len = match len_size {
+            1 => self.u8()? as usize,
+            2 => self.u16()? as usize,
+            _ => self.u24()?,
+        };
+        self.bytes(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefixed() {
+        let mut buf = BytesMut::new();
+        put_extension(&mut buf, EXT_ALPN, |buf| {
+            put_prefixed(buf, 2, |buf| {
+                put_prefixed(buf, 1, |buf| buf.put_slice(b"h2"));
+            });
+        });
+        assert_eq!(&buf[..], &[0, 16, 0, 5, 0, 3, 2, b'h', b'2']);
+
+        let mut r = Reader::new(&buf);
+        assert_eq!(r.u16().unwrap(), EXT_ALPN);
+        let mut ext = Reader::new(r.prefixed(2).unwrap());
+        assert!(r.is_empty());
+        let mut protocols = Reader::new(ext.prefixed(2).unwrap());
+        assert_eq!(protocols.prefixed(1).unwrap(), b"h2");
+        assert!(protocols.u24().is_err());
+    }
+}
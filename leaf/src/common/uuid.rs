@@ -0,0 +1,26 @@
+/// Parses a UUID in the hyphenated form.
+pub fn parse_uuid(s: &str) -> Option<[u8; 16]> {
+    let s: String = s.chars().filter(|c| *c != '-').collect();
+    if s.len() != 32 || !s.is_ascii() {
+        return None;
+    }
+    let mut id = [0u8; 16];
+    for (i, b) in id.iter_mut().enumerate() {
+        *b = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_uuid() {
+        let id = parse_uuid("b831381d-6324-4d53-ad4f-8cda48b30811").unwrap();
+        assert_eq!(id[0], 0xb8);
+        assert_eq!(id[15], 0x11);
+        assert!(parse_uuid("b831381d-6324-4d53-ad4f-8cda48b3081").is_none());
+        assert!(parse_uuid("x831381d-6324-4d53-ad4f-8cda48b30811").is_none());
+    }
+}
@@ -4,7 +4,7 @@ use std::fs::File;
 use std::io::{self, BufRead};
 use std::path::Path;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use protobuf::Message;
 use regex::Regex;
 
@@ -17,6 +17,8 @@ pub struct Tun {
     pub netmask: Option<String>,
     pub gateway: Option<String>,
     pub mtu: Option<i32>,
+    pub ipv6_address: Option<String>,
+    pub ipv6_prefixlen: Option<i32>,
 }
 
 #[derive(Debug, Default)]
@@ -24,26 +26,70 @@ pub struct General {
     pub tun: Option<Tun>,
     pub tun_fd: Option<i32>,
     pub tun_auto: Option<bool>,
+    pub tun_icmp_reply: Option<bool>,
+    pub tun_queues: Option<i32>,
+    pub bypass_lan: Option<String>,
+    pub tun_post_up: Option<String>,
+    pub tun_pre_down: Option<String>,
+    pub fake_ipv6: Option<bool>,
+    pub dns_hijack: Option<Vec<String>>,
     pub loglevel: Option<String>,
     pub logoutput: Option<String>,
     pub dns_server: Option<Vec<String>>,
     pub dns_interface: Option<String>,
+    pub dns_client_subnet: Option<String>,
     pub always_real_ip: Option<Vec<String>>,
     pub always_fake_ip: Option<Vec<String>>,
+    pub fake_ip_cidr: Option<String>,
+    pub fake_ipv6_cidr: Option<String>,
+    pub fake_ip_pool_size: Option<i32>,
+    pub fake_ip_eviction: Option<String>,
+    pub fake_ip_ttl: Option<i32>,
     pub http_interface: Option<String>,
     pub http_port: Option<u16>,
     pub socks_interface: Option<String>,
     pub socks_port: Option<u16>,
+    pub socks_users: Option<Vec<String>>,
+    pub mixed_interface: Option<String>,
+    pub mixed_port: Option<u16>,
+    pub dns_inbound_interface: Option<String>,
+    pub dns_inbound_port: Option<u16>,
+    pub dns_inbound_fake_dns: Option<bool>,
+    pub redirect_interface: Option<String>,
+    pub redirect_port: Option<u16>,
+    pub tproxy_interface: Option<String>,
+    pub tproxy_port: Option<u16>,
+    pub ss_interface: Option<String>,
+    pub ss_port: Option<u16>,
+    pub ss_encrypt_method: Option<String>,
+    pub ss_password: Option<String>,
+    pub max_connections: Option<u32>,
+    pub max_connections_per_source: Option<u32>,
+    pub accept_rate: Option<u32>,
+    pub sniffing: Option<bool>,
+    pub sniffing_protocols: Option<Vec<String>>,
+    pub sniffing_ports: Option<Vec<String>>,
+    pub sniffing_route_only: Option<bool>,
     pub api_interface: Option<String>,
     pub api_port: Option<u16>,
     pub routing_domain_resolve: Option<bool>,
+    pub routing_script: Option<String>,
+    pub geoip_file: Option<String>,
+    pub geosite_file: Option<String>,
+    pub asn_file: Option<String>,
 }
 
 #[derive(Debug)]
 pub struct Proxy {
     pub tag: String,
     pub protocol: String,
-    pub interface: String,
+    pub interface: Option<String>,
+    pub bind_address: Option<Vec<String>>,
+    pub domain_strategy: Option<String>,
+    pub tfo: Option<bool>,
+    pub connect_timeout: Option<u32>,
+    pub handshake_timeout: Option<u32>,
+    pub tls_handshake_timeout: Option<u32>,
 
     // common
     pub address: Option<String>,
@@ -51,15 +97,33 @@ pub struct Proxy {
 
     // shadowsocks
     pub encrypt_method: Option<String>,
+    pub plugin: Option<String>,
+    pub plugin_opts: Option<String>,
+    pub udp_over_tcp: Option<bool>,
 
     // shadowsocks, trojan
+    pub obfs: Option<String>,
+    pub obfs_host: Option<String>,
+    pub obfs_uri: Option<String>,
+
+    // shadowsocks, trojan, ssh, naive
     pub password: Option<String>,
 
     pub ws: Option<bool>,
     pub tls: Option<bool>,
     pub tls_cert: Option<String>,
+    pub tls_client_cert: Option<String>,
+    pub tls_client_key: Option<String>,
+    pub tls_cert_sha256: Option<Vec<String>>,
+    pub ech: Option<bool>,
+    pub ech_config: Option<String>,
+    pub reality_public_key: Option<String>,
+    pub reality_short_id: Option<String>,
     pub ws_path: Option<String>,
     pub ws_host: Option<String>,
+    pub ws_headers: Option<HashMap<String, String>>,
+    pub ws_max_early_data: Option<u32>,
+    pub ws_early_data_header_name: Option<String>,
 
     // trojan
     pub sni: Option<String>,
@@ -67,8 +131,35 @@ pub struct Proxy {
     pub amux: Option<bool>,
     pub amux_max: Option<i32>,
     pub amux_con: Option<i32>,
+    pub amux_keep_alive: Option<i32>,
+    pub amux_idle_timeout: Option<i32>,
+    pub mux: Option<bool>,
+    pub mux_max_streams: Option<i32>,
+    pub mux_min_connections: Option<i32>,
+    pub mux_max_connections: Option<i32>,
+    pub mux_padding: Option<bool>,
 
     pub quic: Option<bool>,
+
+    // trojan, vless, vmess
+    pub grpc: Option<bool>,
+    pub grpc_service_name: Option<String>,
+    pub h2: Option<bool>,
+    pub h2_host: Option<String>,
+    pub h2_path: Option<String>,
+
+    // vless, vmess
+    pub uuid: Option<String>,
+
+    // ssh, naive
+    pub username: Option<String>,
+    pub private_key: Option<String>,
+    pub host_key: Option<String>,
+    pub insecure_skip_host_key: Option<bool>,
+
+    // drop
+    pub reject_tcp: Option<String>,
+    pub reject_udp: Option<String>,
 }
 
 impl Default for Proxy {
@@ -76,21 +167,62 @@ impl Default for Proxy {
         Proxy {
             tag: "".to_string(),
             protocol: "".to_string(),
-            interface: (&*crate::option::UNSPECIFIED_BIND_ADDR).ip().to_string(),
+            interface: None,
+            bind_address: None,
+            domain_strategy: None,
+            tfo: None,
+            connect_timeout: None,
+            handshake_timeout: None,
+            tls_handshake_timeout: None,
             address: None,
             port: None,
             encrypt_method: Some("chacha20-ietf-poly1305".to_string()),
+            plugin: None,
+            plugin_opts: None,
+            udp_over_tcp: Some(false),
+            obfs: None,
+            obfs_host: None,
+            obfs_uri: None,
             password: None,
             ws: Some(false),
             tls: Some(false),
             tls_cert: None,
+            tls_client_cert: None,
+            tls_client_key: None,
+            tls_cert_sha256: None,
+            ech: Some(false),
+            ech_config: None,
+            reality_public_key: None,
+            reality_short_id: None,
             ws_path: None,
             ws_host: None,
+            ws_headers: None,
+            ws_max_early_data: None,
+            ws_early_data_header_name: None,
             sni: None,
             amux: Some(false),
             amux_max: Some(8),
             amux_con: Some(2),
+            amux_keep_alive: Some(0),
+            amux_idle_timeout: Some(0),
+            mux: Some(false),
+            mux_max_streams: Some(8),
+            mux_min_connections: Some(1),
+            mux_max_connections: Some(4),
+            mux_padding: Some(false),
             quic: Some(false),
+            grpc: Some(false),
+            grpc_service_name: None,
+            h2: Some(false),
+            h2_host: None,
+            h2_path: None,
+            uuid: None,
+            username: None,
+            private_key: None,
+            host_key: None,
+            insecure_skip_host_key: None,
+            reject_tcp: None,
+            reject_udp: None,
         }
     }
 }
@@ -100,6 +232,9 @@ pub struct ProxyGroup {
     pub protocol: String,
     pub actors: Option<Vec<String>>,
 
+    // select, failover, url-test
+    pub filter: Option<String>,
+
     // failover
     pub health_check: Option<bool>,
     pub check_interval: Option<i32>,
@@ -110,12 +245,35 @@ pub struct ProxyGroup {
     pub cache_timeout: Option<i32>,
     pub last_resort: Option<String>,
     pub health_check_timeout: Option<i32>,
+    pub eject_threshold: Option<i32>,
+    pub eject_duration: Option<i32>,
+    pub max_eject_duration: Option<i32>,
+    pub health_check_url: Option<String>,
+    pub health_check_status: Option<i32>,
+    pub health_check_concurrency: Option<i32>,
+    pub udp_health_check: Option<bool>,
+    pub udp_health_check_server: Option<String>,
 
     // tryall
     pub delay_base: Option<i32>,
+    pub attempt_timeout: Option<i32>,
+
+    // retry
+    pub attempts: Option<i32>,
+    pub backoff: Option<i32>,
+    pub max_backoff: Option<i32>,
+    pub jitter: Option<i32>,
+    pub rotate: Option<bool>,
 
     // static
     pub method: Option<String>,
+    pub weights: Option<Vec<u32>>,
+
+    // url-test
+    pub url: Option<String>,
+    pub interval: Option<i32>,
+    pub tolerance: Option<i32>,
+    pub timeout: Option<i32>,
 }
 
 impl Default for ProxyGroup {
@@ -124,6 +282,7 @@ impl Default for ProxyGroup {
             tag: "".to_string(),
             protocol: "".to_string(),
             actors: None,
+            filter: None,
             health_check: Some(true),
             check_interval: Some(300),
             fail_timeout: Some(4),
@@ -133,17 +292,65 @@ impl Default for ProxyGroup {
             cache_timeout: Some(60),
             last_resort: None,
             health_check_timeout: Some(5),
+            eject_threshold: Some(3),
+            eject_duration: Some(30),
+            max_eject_duration: Some(600),
+            health_check_url: None,
+            health_check_status: Some(0),
+            health_check_concurrency: Some(0),
+            udp_health_check: Some(true),
+            udp_health_check_server: None,
             delay_base: Some(0),
+            attempt_timeout: Some(0),
+            attempts: Some(3),
+            backoff: Some(200),
+            max_backoff: Some(5000),
+            jitter: Some(50),
+            rotate: Some(false),
             method: Some("random".to_string()),
+            weights: None,
+            url: None,
+            interval: Some(300),
+            tolerance: Some(50),
+            timeout: Some(5),
         }
     }
 }
 
+#[derive(Debug, Default)]
+pub struct ProxyProvider {
+    pub tag: String,
+    pub url: String,
+    pub path: Option<String>,
+    pub interval: Option<u32>,
+}
+
+#[derive(Debug, Default)]
+pub struct RuleProvider {
+    pub tag: String,
+    pub url: String,
+    pub path: Option<String>,
+    pub interval: Option<u32>,
+    pub format: Option<String>,
+}
+
 #[derive(Debug, Default)]
 pub struct Rule {
     pub type_field: String,
     pub filter: Option<String>,
     pub target: String,
+    // The conditions of AND, OR and NOT rules.
+    pub sub_rules: Vec<Rule>,
+    pub no_resolve: bool,
+}
+
+// The servers of the domains matching a domain rule, e.g.
+// `GEOSITE, cn, 223.5.5.5, 119.29.29.29`.
+#[derive(Debug, Default)]
+pub struct DnsRule {
+    pub type_field: String,
+    pub filter: String,
+    pub servers: Vec<String>,
 }
 
 #[derive(Debug, Default)]
@@ -151,8 +358,11 @@ pub struct Config {
     pub general: Option<General>,
     pub proxy: Option<Vec<Proxy>>,
     pub proxy_group: Option<Vec<ProxyGroup>>,
+    pub proxy_provider: Option<Vec<ProxyProvider>>,
+    pub rule_provider: Option<Vec<RuleProvider>>,
     pub rule: Option<Vec<Rule>>,
     pub host: Option<HashMap<String, Vec<String>>>,
+    pub dns_rule: Option<Vec<DnsRule>>,
 }
 
 fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
@@ -210,6 +420,64 @@ where
     }
 }
 
+// Splits the text at the commas outside of parentheses.
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(text[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(text[start..].trim());
+    items
+}
+
+// Parses the conditions of a logical rule, e.g. the
+// `((DOMAIN-SUFFIX,netflix.com),(NETWORK,udp))` of
+// `AND,((DOMAIN-SUFFIX,netflix.com),(NETWORK,udp)),Reject`, the conditions can
+// be logical rules too.
+fn parse_logical_rule(type_field: &str, conditions: &str) -> Result<Rule> {
+    let conditions = conditions
+        .strip_prefix('(')
+        .and_then(|x| x.strip_suffix(')'))
+        .ok_or_else(|| anyhow!("conditions must be in parentheses"))?;
+    let mut rule = Rule {
+        type_field: type_field.to_string(),
+        ..Default::default()
+    };
+    for cond in split_top_level(conditions) {
+        let cond = cond
+            .strip_prefix('(')
+            .and_then(|x| x.strip_suffix(')'))
+            .ok_or_else(|| anyhow!("condition [{}] must be in parentheses", cond))?;
+        let params = split_top_level(cond);
+        if params.len() < 2 {
+            return Err(anyhow!("invalid condition [{}]", cond));
+        }
+        let sub_rule = match params[0] {
+            "AND" | "OR" | "NOT" => parse_logical_rule(params[0], params[1])?,
+            _ => Rule {
+                type_field: params[0].to_string(),
+                filter: Some(params[1..].join(",")),
+                ..Default::default()
+            },
+        };
+        rule.sub_rules.push(sub_rule);
+    }
+    if rule.sub_rules.is_empty() {
+        return Err(anyhow!("empty conditions"));
+    }
+    Ok(rule)
+}
+
 fn get_string(text: &str) -> Option<String> {
     if !text.is_empty() {
         Some(text.to_string())
@@ -243,7 +511,8 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
     let mut general = General::default();
     let general_lines = get_lines_by_section("General", lines.iter());
     for line in general_lines {
-        let parts: Vec<&str> = line.split('=').map(str::trim).collect();
+        // Values may have `=`, e.g. the options of DNS servers.
+        let parts: Vec<&str> = line.splitn(2, '=').map(str::trim).collect();
         if parts.len() != 2 {
             continue;
         }
@@ -257,19 +526,69 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
                         general.tun_auto = Some(items[0] == "auto");
                         continue;
                     }
-                    if items.len() != 5 {
+                    if items.len() != 5 && items.len() != 6 {
                         continue;
                     }
-                    let tun = Tun {
+                    let mut tun = Tun {
                         name: Some(items[0].clone()),
                         address: Some(items[1].clone()),
                         netmask: Some(items[2].clone()),
                         gateway: Some(items[3].clone()),
                         mtu: get_value::<i32>(&items[4]),
+                        ..Default::default()
                     };
+                    // The optional 6th item is an IPv6 address with prefix
+                    // length, e.g. fd00::2/64.
+                    if let Some(ipv6) = items.get(5) {
+                        let mut ipv6 = ipv6.split('/');
+                        tun.ipv6_address = ipv6.next().map(str::to_string);
+                        tun.ipv6_prefixlen = ipv6.next().and_then(get_value::<i32>);
+                    }
                     general.tun = Some(tun);
                 }
             }
+            "tun-icmp-reply" => {
+                general.tun_icmp_reply = Some(parts[1] == "true");
+            }
+            "tun-queues" => {
+                general.tun_queues = get_value::<i32>(parts[1]);
+            }
+            "max-connections" => {
+                general.max_connections = get_value::<u32>(parts[1]);
+            }
+            "max-connections-per-source" => {
+                general.max_connections_per_source = get_value::<u32>(parts[1]);
+            }
+            "accept-rate" => {
+                general.accept_rate = get_value::<u32>(parts[1]);
+            }
+            "sniffing" => {
+                general.sniffing = Some(parts[1] == "true");
+            }
+            "sniffing-protocols" => {
+                general.sniffing_protocols = get_char_sep_slice(parts[1], ',');
+            }
+            "sniffing-ports" => {
+                general.sniffing_ports = get_char_sep_slice(parts[1], ',');
+            }
+            "sniffing-route-only" => {
+                general.sniffing_route_only = Some(parts[1] == "true");
+            }
+            "tun-post-up" => {
+                general.tun_post_up = Some(parts[1].to_string());
+            }
+            "tun-pre-down" => {
+                general.tun_pre_down = Some(parts[1].to_string());
+            }
+            "fake-ipv6" => {
+                general.fake_ipv6 = Some(parts[1] == "true");
+            }
+            "bypass-lan" => {
+                general.bypass_lan = Some(parts[1].to_string());
+            }
+            "dns-hijack" => {
+                general.dns_hijack = get_char_sep_slice(parts[1], ',');
+            }
             "loglevel" => {
                 general.loglevel = Some(parts[1].to_string());
             }
@@ -282,12 +601,30 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
             "dns-interface" => {
                 general.dns_interface = get_string(parts[1]);
             }
+            "dns-client-subnet" => {
+                general.dns_client_subnet = get_string(parts[1]);
+            }
             "always-real-ip" => {
                 general.always_real_ip = get_char_sep_slice(parts[1], ',');
             }
             "always-fake-ip" => {
                 general.always_fake_ip = get_char_sep_slice(parts[1], ',');
             }
+            "fake-ip-cidr" => {
+                general.fake_ip_cidr = Some(parts[1].to_string());
+            }
+            "fake-ipv6-cidr" => {
+                general.fake_ipv6_cidr = Some(parts[1].to_string());
+            }
+            "fake-ip-pool-size" => {
+                general.fake_ip_pool_size = get_value::<i32>(parts[1]);
+            }
+            "fake-ip-eviction" => {
+                general.fake_ip_eviction = Some(parts[1].to_string());
+            }
+            "fake-ip-ttl" => {
+                general.fake_ip_ttl = get_value::<i32>(parts[1]);
+            }
             "routing-domain-resolve" => {
                 general.routing_domain_resolve = if parts[1] == "true" {
                     Some(true)
@@ -295,6 +632,18 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
                     Some(false)
                 };
             }
+            "routing-script" => {
+                general.routing_script = get_string(parts[1]);
+            }
+            "geoip-file" => {
+                general.geoip_file = get_string(parts[1]);
+            }
+            "geosite-file" => {
+                general.geosite_file = get_string(parts[1]);
+            }
+            "asn-file" => {
+                general.asn_file = get_string(parts[1]);
+            }
             "http-interface" | "interface" => {
                 general.http_interface = get_string(parts[1]);
             }
@@ -307,6 +656,48 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
             "socks-port" => {
                 general.socks_port = get_value::<u16>(parts[1]);
             }
+            "socks-users" => {
+                general.socks_users = get_char_sep_slice(parts[1], ',');
+            }
+            "mixed-interface" => {
+                general.mixed_interface = get_string(parts[1]);
+            }
+            "mixed-port" => {
+                general.mixed_port = get_value::<u16>(parts[1]);
+            }
+            "dns-inbound-interface" => {
+                general.dns_inbound_interface = get_string(parts[1]);
+            }
+            "dns-inbound-port" => {
+                general.dns_inbound_port = get_value::<u16>(parts[1]);
+            }
+            "dns-inbound-fake-dns" => {
+                general.dns_inbound_fake_dns = Some(parts[1] == "true");
+            }
+            "redirect-interface" => {
+                general.redirect_interface = get_string(parts[1]);
+            }
+            "redirect-port" => {
+                general.redirect_port = get_value::<u16>(parts[1]);
+            }
+            "tproxy-interface" => {
+                general.tproxy_interface = get_string(parts[1]);
+            }
+            "tproxy-port" => {
+                general.tproxy_port = get_value::<u16>(parts[1]);
+            }
+            "ss-interface" => {
+                general.ss_interface = get_string(parts[1]);
+            }
+            "ss-port" => {
+                general.ss_port = get_value::<u16>(parts[1]);
+            }
+            "ss-encrypt-method" => {
+                general.ss_encrypt_method = get_string(parts[1]);
+            }
+            "ss-password" => {
+                general.ss_password = get_string(parts[1]);
+            }
             "api-interface" => {
                 general.api_interface = get_string(parts[1]);
             }
@@ -345,7 +736,7 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
         // extract key-value params
         // let params = &params[2..];
         for param in &params {
-            let parts: Vec<&str> = param.split('=').map(str::trim).collect();
+            let parts: Vec<&str> = param.splitn(2, '=').map(str::trim).collect();
             if parts.len() != 2 {
                 continue;
             }
@@ -358,20 +749,90 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
                 "encrypt-method" => {
                     proxy.encrypt_method = Some(v.to_string());
                 }
+                "plugin" => {
+                    proxy.plugin = Some(v.to_string());
+                }
+                "plugin-opts" => {
+                    proxy.plugin_opts = Some(v.to_string());
+                }
+                "udp-over-tcp" => {
+                    proxy.udp_over_tcp = if v == "true" { Some(true) } else { Some(false) };
+                }
+                "obfs" => {
+                    proxy.obfs = Some(v.to_string());
+                }
+                "obfs-host" => {
+                    proxy.obfs_host = Some(v.to_string());
+                }
+                "obfs-uri" => {
+                    proxy.obfs_uri = Some(v.to_string());
+                }
                 "password" => {
                     proxy.password = Some(v.to_string());
                 }
+                "uuid" => {
+                    proxy.uuid = Some(v.to_string());
+                }
+                "username" => {
+                    proxy.username = Some(v.to_string());
+                }
+                "private-key" => {
+                    proxy.private_key = Some(v.to_string());
+                }
+                "host-key" => {
+                    proxy.host_key = Some(v.to_string());
+                }
+                "insecure-skip-host-key" => {
+                    proxy.insecure_skip_host_key = Some(v == "true");
+                }
                 "ws" => proxy.ws = if v == "true" { Some(true) } else { Some(false) },
                 "tls" => proxy.tls = if v == "true" { Some(true) } else { Some(false) },
                 "tls-cert" => {
                     proxy.tls_cert = Some(v.to_string());
                 }
+                "tls-client-cert" => {
+                    proxy.tls_client_cert = Some(v.to_string());
+                }
+                "tls-client-key" => {
+                    proxy.tls_client_key = Some(v.to_string());
+                }
+                "tls-cert-sha256" => {
+                    // e.g. tls-cert-sha256=hash1|hash2
+                    proxy.tls_cert_sha256 =
+                        Some(v.split('|').map(|x| x.trim().to_string()).collect());
+                }
+                "ech" => proxy.ech = if v == "true" { Some(true) } else { Some(false) },
+                "ech-config" => {
+                    proxy.ech_config = Some(v.to_string());
+                }
+                "reality-public-key" => {
+                    proxy.reality_public_key = Some(v.to_string());
+                }
+                "reality-short-id" => {
+                    proxy.reality_short_id = Some(v.to_string());
+                }
                 "ws-path" => {
                     proxy.ws_path = Some(v.to_string());
                 }
                 "ws-host" => {
                     proxy.ws_host = Some(v.to_string());
                 }
+                "ws-headers" => {
+                    // e.g. ws-headers=Host:example.com|User-Agent:Mozilla/5.0
+                    let mut headers = HashMap::new();
+                    for header in v.split('|') {
+                        if let Some((name, value)) = header.split_once(':') {
+                            headers.insert(name.trim().to_string(), value.trim().to_string());
+                        }
+                    }
+                    proxy.ws_headers = Some(headers);
+                }
+                "ws-max-early-data" => {
+                    proxy.ws_max_early_data = v.parse::<u32>().ok();
+                }
+                "ws-early-data-header-name" => {
+                    proxy.ws_early_data_header_name = Some(v.to_string());
+                }
                 "sni" => {
                     proxy.sni = Some(v.to_string());
                 }
@@ -392,9 +853,63 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
                     };
                     proxy.amux_con = i;
                 }
+                "amux-keep-alive" => {
+                    proxy.amux_keep_alive = v.parse::<i32>().ok();
+                }
+                "amux-idle-timeout" => {
+                    proxy.amux_idle_timeout = v.parse::<i32>().ok();
+                }
+                "mux" => proxy.mux = if v == "true" { Some(true) } else { Some(false) },
+                "mux-max-streams" => {
+                    proxy.mux_max_streams = v.parse::<i32>().ok();
+                }
+                "mux-min-connections" => {
+                    proxy.mux_min_connections = v.parse::<i32>().ok();
+                }
+                "mux-max-connections" => {
+                    proxy.mux_max_connections = v.parse::<i32>().ok();
+                }
+                "mux-padding" => {
+                    proxy.mux_padding = if v == "true" { Some(true) } else { Some(false) }
+                }
                 "quic" => proxy.quic = if v == "true" { Some(true) } else { Some(false) },
+                "grpc" => proxy.grpc = if v == "true" { Some(true) } else { Some(false) },
+                "grpc-service-name" => {
+                    proxy.grpc_service_name = Some(v.to_string());
+                }
+                "h2" => proxy.h2 = if v == "true" { Some(true) } else { Some(false) },
+                "h2-host" => {
+                    proxy.h2_host = Some(v.to_string());
+                }
+                "h2-path" => {
+                    proxy.h2_path = Some(v.to_string());
+                }
                 "interface" => {
-                    proxy.interface = v.to_string();
+                    proxy.interface = Some(v.to_string());
+                }
+                "bind-address" => {
+                    proxy.bind_address = Some(v.split('|').map(|x| x.trim().to_string()).collect());
+                }
+                "domain-strategy" => {
+                    proxy.domain_strategy = Some(v.to_string());
+                }
+                "tfo" => {
+                    proxy.tfo = if v == "true" { Some(true) } else { Some(false) };
+                }
+                "connect-timeout" => {
+                    proxy.connect_timeout = v.parse::<u32>().ok();
+                }
+                "handshake-timeout" => {
+                    proxy.handshake_timeout = v.parse::<u32>().ok();
+                }
+                "tls-handshake-timeout" => {
+                    proxy.tls_handshake_timeout = v.parse::<u32>().ok();
+                }
+                "tcp" => {
+                    proxy.reject_tcp = Some(v.to_string());
+                }
+                "udp" => {
+                    proxy.reject_udp = Some(v.to_string());
                 }
                 _ => {}
             }
@@ -478,8 +993,11 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
                 actors.push(param.to_string());
             }
         }
-        if actors.is_empty() {
-            // require at least one actor
+        let has_filter = params
+            .iter()
+            .any(|x| x.split('=').next().map(str::trim) == Some("filter"));
+        if actors.is_empty() && !has_filter {
+            // require at least one actor or a filter
             continue;
         }
         group.actors = Some(actors);
@@ -496,6 +1014,9 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
                     continue;
                 }
                 match k {
+                    "filter" => {
+                        group.filter = Some(v.to_string());
+                    }
                     "health-check" => {
                         group.health_check = if v == "true" { Some(true) } else { Some(false) };
                     }
@@ -553,6 +1074,55 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
                         };
                         group.health_check_timeout = i;
                     }
+                    "eject-threshold" => {
+                        let i = if let Ok(i) = v.parse::<i32>() {
+                            Some(i)
+                        } else {
+                            None
+                        };
+                        group.eject_threshold = i;
+                    }
+                    "eject-duration" => {
+                        let i = if let Ok(i) = v.parse::<i32>() {
+                            Some(i)
+                        } else {
+                            None
+                        };
+                        group.eject_duration = i;
+                    }
+                    "max-eject-duration" => {
+                        let i = if let Ok(i) = v.parse::<i32>() {
+                            Some(i)
+                        } else {
+                            None
+                        };
+                        group.max_eject_duration = i;
+                    }
+                    "health-check-url" => {
+                        group.health_check_url = Some(v.to_string());
+                    }
+                    "health-check-status" => {
+                        let i = if let Ok(i) = v.parse::<i32>() {
+                            Some(i)
+                        } else {
+                            None
+                        };
+                        group.health_check_status = i;
+                    }
+                    "health-check-concurrency" => {
+                        let i = if let Ok(i) = v.parse::<i32>() {
+                            Some(i)
+                        } else {
+                            None
+                        };
+                        group.health_check_concurrency = i;
+                    }
+                    "udp-health-check" => {
+                        group.udp_health_check = if v == "true" { Some(true) } else { Some(false) };
+                    }
+                    "udp-health-check-server" => {
+                        group.udp_health_check_server = Some(v.to_string());
+                    }
                     "delay-base" => {
                         let i = if let Ok(i) = v.parse::<i32>() {
                             Some(i)
@@ -561,6 +1131,49 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
                         };
                         group.delay_base = i;
                     }
+                    "attempt-timeout" => {
+                        let i = if let Ok(i) = v.parse::<i32>() {
+                            Some(i)
+                        } else {
+                            None
+                        };
+                        group.attempt_timeout = i;
+                    }
+                    "attempts" => {
+                        let i = if let Ok(i) = v.parse::<i32>() {
+                            Some(i)
+                        } else {
+                            None
+                        };
+                        group.attempts = i;
+                    }
+                    "backoff" => {
+                        let i = if let Ok(i) = v.parse::<i32>() {
+                            Some(i)
+                        } else {
+                            None
+                        };
+                        group.backoff = i;
+                    }
+                    "max-backoff" => {
+                        let i = if let Ok(i) = v.parse::<i32>() {
+                            Some(i)
+                        } else {
+                            None
+                        };
+                        group.max_backoff = i;
+                    }
+                    "jitter" => {
+                        let i = if let Ok(i) = v.parse::<i32>() {
+                            Some(i)
+                        } else {
+                            None
+                        };
+                        group.jitter = i;
+                    }
+                    "rotate" => {
+                        group.rotate = if v == "true" { Some(true) } else { Some(false) };
+                    }
                     "method" => {
                         let i = if let Ok(i) = v.parse::<String>() {
                             Some(i)
@@ -569,6 +1182,42 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
                         };
                         group.method = i;
                     }
+                    "weights" => {
+                        let mut weights = Vec::new();
+                        for w in v.split('|').map(str::trim) {
+                            if let Ok(w) = w.parse::<u32>() {
+                                weights.push(w);
+                            }
+                        }
+                        group.weights = Some(weights);
+                    }
+                    "url" => {
+                        group.url = Some(v.to_string());
+                    }
+                    "interval" => {
+                        let i = if let Ok(i) = v.parse::<i32>() {
+                            Some(i)
+                        } else {
+                            None
+                        };
+                        group.interval = i;
+                    }
+                    "tolerance" => {
+                        let i = if let Ok(i) = v.parse::<i32>() {
+                            Some(i)
+                        } else {
+                            None
+                        };
+                        group.tolerance = i;
+                    }
+                    "timeout" => {
+                        let i = if let Ok(i) = v.parse::<i32>() {
+                            Some(i)
+                        } else {
+                            None
+                        };
+                        group.timeout = i;
+                    }
                     _ => {}
                 }
             }
@@ -576,10 +1225,8 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
 
         // compat
         match group.protocol.as_str() {
-            // url-test group is just failover without failover
             "url-test" => {
-                group.protocol = "failover".to_string();
-                group.failover = Some(false);
+                group.protocol = "urltest".to_string();
             }
             // fallback group is just failover
             "fallback" => {
@@ -591,72 +1238,342 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
         proxy_groups.push(group);
     }
 
-    let mut rules = Vec::new();
-    let rule_lines = get_lines_by_section("Rule", lines.iter());
-    for line in rule_lines {
-        let params = if let Some(p) = get_char_sep_slice(&line, ',') {
+    let mut proxy_providers = Vec::new();
+    let proxy_provider_lines = get_lines_by_section("Proxy Provider", lines.iter());
+    for line in proxy_provider_lines {
+        let parts: Vec<&str> = line.splitn(2, '=').map(str::trim).collect();
+        if parts.len() != 2 {
+            continue;
+        }
+        let tag = parts[0];
+        if tag.is_empty() {
+            // empty tag is not allowed
+            continue;
+        }
+        let params = if let Some(p) = get_char_sep_slice(parts[1], ',') {
             p
         } else {
             continue;
         };
-        if params.len() < 2 {
-            continue; // at lease 2 params
-        }
-        let mut rule = Rule {
-            type_field: params[0].to_string(),
+        // the 1st must be the url
+        let mut provider = ProxyProvider {
+            tag: tag.to_string(),
+            url: params[0].clone(),
             ..Default::default()
         };
-
-        // handle the FINAL rule first
-        if rule.type_field == "FINAL" {
-            rule.target = params[1].to_string();
-            rules.push(rule);
-            continue; // maybe break? to enforce FINAL as the final rule
-        }
-
-        if params.len() < 3 {
-            continue; // at lease 3 params except the FINAL rule
-        }
-
-        // the 3th must be the target
-        rule.target = params[2].to_string();
-
-        match rule.type_field.as_str() {
-            "IP-CIDR" | "DOMAIN" | "DOMAIN-SUFFIX" | "DOMAIN-KEYWORD" | "GEOIP" | "EXTERNAL"
-            | "PORT-RANGE" | "NETWORK" | "INBOUND-TAG" => {
-                rule.filter = Some(params[1].to_string());
+        for param in &params[1..] {
+            let parts: Vec<&str> = param.splitn(2, '=').map(str::trim).collect();
+            if parts.len() != 2 {
+                continue;
+            }
+            match parts[0] {
+                "path" => {
+                    provider.path = get_string(parts[1]);
+                }
+                "interval" => {
+                    provider.interval = get_value::<u32>(parts[1]);
+                }
+                _ => {}
             }
-            _ => {}
         }
-
-        rules.push(rule);
+        proxy_providers.push(provider);
     }
 
-    let mut hosts = HashMap::new();
-    let host_lines = get_lines_by_section("Host", lines.iter());
-    for line in host_lines {
-        let parts: Vec<&str> = line.split('=').map(str::trim).collect();
+    let mut rule_providers = Vec::new();
+    let rule_provider_lines = get_lines_by_section("Rule Provider", lines.iter());
+    for line in rule_provider_lines {
+        let parts: Vec<&str> = line.splitn(2, '=').map(str::trim).collect();
         if parts.len() != 2 {
             continue;
         }
-        let name = parts[0];
-        let ips: Vec<String> = parts[1]
-            .split(',')
-            .map(str::trim)
-            .map(|x| x.to_owned())
-            .collect();
-        hosts.insert(name.to_owned(), ips);
+        let tag = parts[0];
+        if tag.is_empty() {
+            // empty tag is not allowed
+            continue;
+        }
+        let params = if let Some(p) = get_char_sep_slice(parts[1], ',') {
+            p
+        } else {
+            continue;
+        };
+        // the 1st must be the url, or the path of a local file
+        let mut provider = RuleProvider {
+            tag: tag.to_string(),
+            ..Default::default()
+        };
+        if params[0].starts_with("http://") || params[0].starts_with("https://") {
+            provider.url = params[0].clone();
+        } else {
+            provider.path = Some(params[0].clone());
+        }
+        for param in &params[1..] {
+            let parts: Vec<&str> = param.splitn(2, '=').map(str::trim).collect();
+            if parts.len() != 2 {
+                continue;
+            }
+            match parts[0] {
+                "path" => {
+                    provider.path = get_string(parts[1]);
+                }
+                "interval" => {
+                    provider.interval = get_value::<u32>(parts[1]);
+                }
+                "format" => {
+                    provider.format = get_string(parts[1]);
+                }
+                _ => {}
+            }
+        }
+        rule_providers.push(provider);
+    }
+
+    let mut rules = Vec::new();
+    let rule_lines = get_lines_by_section("Rule", lines.iter());
+    for line in rule_lines {
+        // logical rules, e.g. AND,((DOMAIN-SUFFIX,netflix.com),(NETWORK,udp)),Reject
+        let logical_params = split_top_level(&line);
+        if matches!(logical_params[0], "AND" | "OR" | "NOT") {
+            if logical_params.len() < 3 {
+                continue;
+            }
+            match parse_logical_rule(logical_params[0], logical_params[1]) {
+                Ok(mut rule) => {
+                    rule.target = logical_params[2].to_string();
+                    rule.no_resolve = logical_params[3..].contains(&"no-resolve");
+                    rules.push(rule);
+                }
+                Err(e) => println!("invalid rule [{}]: {}", line, e),
+            }
+            continue;
+        }
+
+        let params = if let Some(p) = get_char_sep_slice(&line, ',') {
+            p
+        } else {
+            continue;
+        };
+        if params.len() < 2 {
+            continue; // at lease 2 params
+        }
+        let mut rule = Rule {
+            type_field: params[0].to_string(),
+            ..Default::default()
+        };
+
+        // handle the FINAL rule first
+        if rule.type_field == "FINAL" {
+            rule.target = params[1].to_string();
+            // the default of some inbounds only, e.g. FINAL, Direct, inbound=socks|http
+            rule.filter = params[2..]
+                .iter()
+                .find_map(|x| x.strip_prefix("inbound="))
+                .map(|x| x.trim().to_string());
+            rules.push(rule);
+            continue; // maybe break? to enforce FINAL as the final rule
+        }
+
+        if params.len() < 3 {
+            continue; // at lease 3 params except the FINAL rule
+        }
+
+        // the 3th must be the target
+        rule.target = params[2].to_string();
+        // the options following the target, e.g. IP-CIDR, 1.0.0.0/8, Proxy, no-resolve
+        rule.no_resolve = params[3..].iter().any(|x| x == "no-resolve");
+
+        // lists of ports and networks, e.g. PORT-RANGE, 1000-2000, 8443, Proxy,
+        // and regexes and schedules which may contain commas, end with the
+        // target
+        if rule.type_field == "PORT-RANGE"
+            || rule.type_field == "SRC-PORT"
+            || rule.type_field == "NETWORK"
+            || rule.type_field == "DOMAIN-REGEX"
+            || rule.type_field == "SCHEDULE"
+        {
+            rule.target = params[params.len() - 1].to_string();
+            rule.filter = Some(params[1..params.len() - 1].join(","));
+            rules.push(rule);
+            continue;
+        }
+
+        match rule.type_field.as_str() {
+            "IP-CIDR" | "DOMAIN" | "DOMAIN-SUFFIX" | "DOMAIN-KEYWORD" | "GEOIP" | "GEOSITE"
+            | "EXTERNAL" | "RULE-SET" | "PORT-RANGE" | "NETWORK" | "INBOUND-TAG" | "APP"
+            | "PROCESS-NAME" | "PROCESS-PATH" | "SRC-IP-CIDR" | "IP-ASN" | "GEOIP-ASN" => {
+                rule.filter = Some(params[1].to_string());
+            }
+            _ => {}
+        }
+
+        rules.push(rule);
+    }
+
+    let mut hosts = HashMap::new();
+    let host_lines = get_lines_by_section("Host", lines.iter());
+    for line in host_lines {
+        let parts: Vec<&str> = line.split('=').map(str::trim).collect();
+        if parts.len() != 2 {
+            continue;
+        }
+        let name = parts[0];
+        let ips: Vec<String> = parts[1]
+            .split(',')
+            .map(str::trim)
+            .map(|x| x.to_owned())
+            .collect();
+        hosts.insert(name.to_owned(), ips);
+    }
+
+    let mut dns_rules = Vec::new();
+    let dns_rule_lines = get_lines_by_section("DNS Rule", lines.iter());
+    for line in dns_rule_lines {
+        let params = if let Some(p) = get_char_sep_slice(&line, ',') {
+            p
+        } else {
+            continue;
+        };
+        if params.len() < 3 {
+            continue; // the type, the filter and at least 1 server
+        }
+        dns_rules.push(DnsRule {
+            type_field: params[0].to_string(),
+            filter: params[1].to_string(),
+            servers: params[2..].to_vec(),
+        });
     }
 
     Ok(Config {
         general: Some(general),
         proxy: Some(proxies),
         proxy_group: Some(proxy_groups),
+        proxy_provider: Some(proxy_providers),
+        rule_provider: Some(rule_providers),
         rule: Some(rules),
         host: Some(hosts),
+        dns_rule: Some(dns_rules),
     })
 }
 
+fn to_internal_rule(
+    ext_rule: &mut Rule,
+    rule: &mut internal::Router_Rule,
+    geoip_file: &str,
+    geosite_file: &str,
+    asn_file: &str,
+) {
+    if matches!(ext_rule.type_field.as_str(), "AND" | "OR" | "NOT") {
+        for ext_sub_rule in ext_rule.sub_rules.iter_mut() {
+            let mut sub_rule = internal::Router_Rule::new();
+            to_internal_rule(
+                ext_sub_rule,
+                &mut sub_rule,
+                geoip_file,
+                geosite_file,
+                asn_file,
+            );
+            match ext_rule.type_field.as_str() {
+                "AND" => rule.and_rules.push(sub_rule),
+                "OR" => rule.or_rules.push(sub_rule),
+                _ => rule.not_rules.push(sub_rule),
+            }
+        }
+        return;
+    }
+
+    // the remaining rules must have a filter
+    let ext_filter = if let Some(f) = ext_rule.filter.as_mut() {
+        std::mem::take(f)
+    } else {
+        return;
+    };
+    match ext_rule.type_field.as_str() {
+        "IP-CIDR" => {
+            rule.ip_cidrs.push(ext_filter);
+        }
+        "DOMAIN" => {
+            let mut domain = internal::Router_Rule_Domain::new();
+            domain.field_type = internal::Router_Rule_Domain_Type::FULL;
+            domain.value = ext_filter;
+            rule.domains.push(domain);
+        }
+        "DOMAIN-KEYWORD" => {
+            let mut domain = internal::Router_Rule_Domain::new();
+            domain.field_type = internal::Router_Rule_Domain_Type::PLAIN;
+            domain.value = ext_filter;
+            rule.domains.push(domain);
+        }
+        "DOMAIN-SUFFIX" => {
+            let mut domain = internal::Router_Rule_Domain::new();
+            domain.field_type = internal::Router_Rule_Domain_Type::DOMAIN;
+            domain.value = ext_filter;
+            rule.domains.push(domain);
+        }
+        "DOMAIN-REGEX" => {
+            let mut domain = internal::Router_Rule_Domain::new();
+            domain.field_type = internal::Router_Rule_Domain_Type::REGEX;
+            domain.value = ext_filter;
+            rule.domains.push(domain);
+        }
+        "GEOIP" => {
+            let mut mmdb = internal::Router_Rule_Mmdb::new();
+            mmdb.file = geoip_file.to_string();
+            mmdb.country_code = ext_filter;
+            rule.mmdbs.push(mmdb)
+        }
+        "IP-ASN" | "GEOIP-ASN" => match ext_filter.trim_start_matches("AS").parse::<u32>() {
+            Ok(asn) => {
+                let mut mmdb = internal::Router_Rule_Mmdb::new();
+                mmdb.file = asn_file.to_string();
+                mmdb.asn = asn;
+                rule.mmdbs.push(mmdb)
+            }
+            Err(_) => println!("invalid asn {}", ext_filter),
+        },
+        "GEOSITE" => {
+            if let Err(e) = external_rule::add_site_rule(rule, geosite_file, &ext_filter) {
+                println!("load geosite rule failed: {}", e);
+            }
+        }
+        "RULE-SET" => {
+            rule.rule_sets.push(ext_filter);
+        }
+        "EXTERNAL" => match external_rule::add_external_rule(rule, &ext_filter) {
+            Ok(_) => (),
+            Err(e) => {
+                println!("load external rule failed: {}", e);
+            }
+        },
+        "PORT-RANGE" => {
+            rule.port_ranges.push(ext_filter);
+        }
+        "NETWORK" => {
+            rule.networks.push(ext_filter);
+        }
+        "SRC-IP-CIDR" => {
+            rule.source_ips.push(ext_filter);
+        }
+        "SRC-PORT" => {
+            rule.source_ports.push(ext_filter);
+        }
+        "INBOUND-TAG" => {
+            rule.inbound_tags.push(ext_filter);
+        }
+        "APP" => {
+            rule.apps.push(ext_filter);
+        }
+        "PROCESS-NAME" => {
+            rule.process_names.push(ext_filter);
+        }
+        "PROCESS-PATH" => {
+            rule.process_paths.push(ext_filter);
+        }
+        "SCHEDULE" => {
+            rule.schedules.push(ext_filter);
+        }
+        _ => {}
+    }
+}
+
 pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
     let mut log = internal::Log::new();
     if let Some(ext_general) = &conf.general {
@@ -697,6 +1614,100 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
             inbound.tag = "socks".to_string();
             inbound.address = ext_general.socks_interface.as_ref().unwrap().to_string();
             inbound.port = ext_general.socks_port.unwrap() as u32;
+            if let Some(ext_users) = &ext_general.socks_users {
+                let mut settings = internal::SocksInboundSettings::new();
+                for ext_user in ext_users {
+                    // username:password
+                    let mut parts = ext_user.splitn(2, ':');
+                    let mut user = internal::SocksInboundSettings_User::new();
+                    user.username = parts.next().unwrap_or_default().to_string();
+                    user.password = parts.next().unwrap_or_default().to_string();
+                    settings.users.push(user);
+                }
+                let settings = settings.write_to_bytes().unwrap();
+                inbound.settings = settings;
+            }
+            inbounds.push(inbound);
+        }
+        if ext_general.mixed_interface.is_some() && ext_general.mixed_port.is_some() {
+            let mut inbound = internal::Inbound::new();
+            inbound.protocol = "mixed".to_string();
+            inbound.tag = "mixed".to_string();
+            inbound.address = ext_general.mixed_interface.as_ref().unwrap().to_string();
+            inbound.port = ext_general.mixed_port.unwrap() as u32;
+            inbounds.push(inbound);
+        }
+        if ext_general.dns_inbound_interface.is_some() && ext_general.dns_inbound_port.is_some() {
+            let mut inbound = internal::Inbound::new();
+            inbound.protocol = "dns".to_string();
+            inbound.tag = "dns".to_string();
+            inbound.address = ext_general
+                .dns_inbound_interface
+                .as_ref()
+                .unwrap()
+                .to_string();
+            inbound.port = ext_general.dns_inbound_port.unwrap() as u32;
+            // Queries are forwarded to the DNS servers leaf uses.
+            let mut settings = internal::DnsInboundSettings::new();
+            if let Some(ext_dns_servers) = &ext_general.dns_server {
+                for ext_dns_server in ext_dns_servers {
+                    settings.servers.push(ext_dns_server.clone());
+                }
+            }
+            // Fake DNS shares the filters and the range with TUN.
+            if ext_general.dns_inbound_fake_dns == Some(true) {
+                settings.fake_dns = true;
+                if let Some(ext_always_real_ip) = &ext_general.always_real_ip {
+                    for item in ext_always_real_ip {
+                        settings.fake_dns_exclude.push(item.clone());
+                    }
+                }
+                if let Some(ext_always_fake_ip) = &ext_general.always_fake_ip {
+                    for item in ext_always_fake_ip {
+                        settings.fake_dns_include.push(item.clone());
+                    }
+                }
+                if let Some(ext_fake_ip_cidr) = &ext_general.fake_ip_cidr {
+                    settings.fake_ip_cidr = ext_fake_ip_cidr.clone();
+                }
+            }
+            let settings = settings.write_to_bytes().unwrap();
+            inbound.settings = settings;
+            inbounds.push(inbound);
+        }
+        if ext_general.redirect_interface.is_some() && ext_general.redirect_port.is_some() {
+            let mut inbound = internal::Inbound::new();
+            inbound.protocol = "redirect".to_string();
+            inbound.tag = "redirect".to_string();
+            inbound.address = ext_general.redirect_interface.as_ref().unwrap().to_string();
+            inbound.port = ext_general.redirect_port.unwrap() as u32;
+            inbounds.push(inbound);
+        }
+        if ext_general.tproxy_interface.is_some() && ext_general.tproxy_port.is_some() {
+            let mut inbound = internal::Inbound::new();
+            inbound.protocol = "tproxy".to_string();
+            inbound.tag = "tproxy".to_string();
+            inbound.address = ext_general.tproxy_interface.as_ref().unwrap().to_string();
+            inbound.port = ext_general.tproxy_port.unwrap() as u32;
+            inbounds.push(inbound);
+        }
+        if ext_general.ss_interface.is_some()
+            && ext_general.ss_port.is_some()
+            && ext_general.ss_password.is_some()
+        {
+            let mut inbound = internal::Inbound::new();
+            inbound.protocol = "shadowsocks".to_string();
+            inbound.tag = "shadowsocks".to_string();
+            inbound.address = ext_general.ss_interface.as_ref().unwrap().to_string();
+            inbound.port = ext_general.ss_port.unwrap() as u32;
+            let mut settings = internal::ShadowsocksInboundSettings::new();
+            settings.method = ext_general
+                .ss_encrypt_method
+                .clone()
+                .unwrap_or_else(|| "chacha20-ietf-poly1305".to_string());
+            settings.password = ext_general.ss_password.as_ref().unwrap().to_string();
+            let settings = settings.write_to_bytes().unwrap();
+            inbound.settings = settings;
             inbounds.push(inbound);
         }
 
@@ -729,6 +1740,47 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
                 }
             }
 
+            if let Some(ext_fake_ip_cidr) = &ext_general.fake_ip_cidr {
+                settings.fake_ip_cidr = ext_fake_ip_cidr.clone();
+            }
+            if let Some(ext_fake_ipv6_cidr) = &ext_general.fake_ipv6_cidr {
+                settings.fake_ipv6_cidr = ext_fake_ipv6_cidr.clone();
+            }
+            if let Some(ext_fake_ip_pool_size) = ext_general.fake_ip_pool_size {
+                settings.fake_ip_pool_size = ext_fake_ip_pool_size;
+            }
+            if let Some(ext_fake_ip_eviction) = &ext_general.fake_ip_eviction {
+                settings.fake_ip_eviction = ext_fake_ip_eviction.clone();
+            }
+
+            if let Some(ext_fake_ip_ttl) = ext_general.fake_ip_ttl {
+                settings.fake_ip_ttl = ext_fake_ip_ttl;
+            }
+
+            if let Some(ext_post_up) = &ext_general.tun_post_up {
+                settings.post_up = ext_post_up.clone();
+            }
+
+            if let Some(ext_pre_down) = &ext_general.tun_pre_down {
+                settings.pre_down = ext_pre_down.clone();
+            }
+
+            if let Some(ext_bypass_lan) = &ext_general.bypass_lan {
+                settings.bypass_lan_outbound = ext_bypass_lan.clone();
+            }
+
+            if let Some(ext_queues) = ext_general.tun_queues {
+                settings.queues = ext_queues;
+            }
+
+            if let Some(ext_dns_hijack) = &ext_general.dns_hijack {
+                let mut dns_hijack = protobuf::RepeatedField::new();
+                for item in ext_dns_hijack {
+                    dns_hijack.push(item.clone());
+                }
+                settings.dns_hijack = dns_hijack;
+            }
+
             if ext_general.tun_fd.is_some() {
                 settings.fd = ext_general.tun_fd.unwrap();
             } else if ext_general.tun_auto.is_some() && ext_general.tun_auto.unwrap() {
@@ -755,6 +1807,20 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
                 } else {
                     settings.mtu = 1500;
                 }
+                if let Some(ext_ipv6_address) = &ext_tun.ipv6_address {
+                    settings.ipv6_address = ext_ipv6_address.clone();
+                }
+                if let Some(ext_ipv6_prefixlen) = ext_tun.ipv6_prefixlen {
+                    settings.ipv6_prefixlen = ext_ipv6_prefixlen;
+                }
+            }
+
+            if let Some(ext_fake_ipv6) = ext_general.fake_ipv6 {
+                settings.fake_ipv6 = ext_fake_ipv6;
+            }
+
+            if let Some(ext_icmp_reply) = ext_general.tun_icmp_reply {
+                settings.icmp_reply = ext_icmp_reply;
             }
 
             // TODO tun opts
@@ -762,6 +1828,40 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
             inbound.settings = settings;
             inbounds.push(inbound);
         }
+
+        let mut sniffing = None;
+        if let Some(ext_sniffing) = ext_general.sniffing {
+            let mut s = internal::Inbound_Sniffing::new();
+            s.enabled = ext_sniffing;
+            if let Some(ext_protocols) = &ext_general.sniffing_protocols {
+                s.protocols = protobuf::RepeatedField::from_vec(ext_protocols.clone());
+            }
+            if let Some(ext_ports) = &ext_general.sniffing_ports {
+                for ext_port in ext_ports {
+                    if let Some(port) = get_value::<u16>(ext_port) {
+                        s.ports.push(port as u32);
+                    }
+                }
+            }
+            s.route_only = ext_general.sniffing_route_only.unwrap_or(false);
+            sniffing = Some(s);
+        }
+
+        // Limits and sniffing apply to each of the inbounds.
+        for inbound in inbounds.iter_mut() {
+            if let Some(s) = &sniffing {
+                inbound.sniffing = protobuf::SingularPtrField::some(s.clone());
+            }
+            if let Some(ext_max_connections) = ext_general.max_connections {
+                inbound.max_connections = ext_max_connections;
+            }
+            if let Some(ext_max_connections_per_source) = ext_general.max_connections_per_source {
+                inbound.max_connections_per_source = ext_max_connections_per_source;
+            }
+            if let Some(ext_accept_rate) = ext_general.accept_rate {
+                inbound.accept_rate = ext_accept_rate;
+            }
+        }
     }
 
     let mut outbounds = protobuf::RepeatedField::new();
@@ -775,7 +1875,21 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
             outbound.protocol = ext_protocol.to_string();
             outbound.tag = ext_proxy.tag.clone();
             match outbound.protocol.as_str() {
-                "direct" | "drop" => {
+                "direct" => {
+                    outbounds.push(outbound);
+                }
+                "drop" => {
+                    if ext_proxy.reject_tcp.is_some() || ext_proxy.reject_udp.is_some() {
+                        let mut settings = internal::DropOutboundSettings::new();
+                        if let Some(ext_tcp) = &ext_proxy.reject_tcp {
+                            settings.tcp = ext_tcp.clone();
+                        }
+                        if let Some(ext_udp) = &ext_proxy.reject_udp {
+                            settings.udp = ext_udp.clone();
+                        }
+                        let settings = settings.write_to_bytes().unwrap();
+                        outbound.settings = settings;
+                    }
                     outbounds.push(outbound);
                 }
                 "redirect" => {
@@ -818,11 +1932,447 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
                     if let Some(ext_password) = &ext_proxy.password {
                         settings.password = ext_password.clone();
                     }
+                    if let Some(ext_plugin) = &ext_proxy.plugin {
+                        settings.plugin = ext_plugin.clone();
+                    }
+                    if let Some(ext_plugin_opts) = &ext_proxy.plugin_opts {
+                        settings.plugin_opts = ext_plugin_opts.clone();
+                    }
+                    if let Some(ext_udp_over_tcp) = ext_proxy.udp_over_tcp {
+                        settings.udp_over_tcp = ext_udp_over_tcp;
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
-                    outbounds.push(outbound);
-                }
-                "trojan" => {
+
+                    let ext_obfs = if let Some(ext_obfs) = &ext_proxy.obfs {
+                        ext_obfs.clone()
+                    } else {
+                        outbounds.push(outbound);
+                        continue;
+                    };
+                    outbound.tag = format!("{}_ss_xxx", ext_proxy.tag.clone());
+
+                    // obfs
+                    let mut obfs_outbound = internal::Outbound::new();
+                    obfs_outbound.protocol = "obfs".to_string();
+                    let mut obfs_settings = internal::ObfsOutboundSettings::new();
+                    obfs_settings.method = ext_obfs;
+                    if let Some(ext_obfs_host) = &ext_proxy.obfs_host {
+                        obfs_settings.host = ext_obfs_host.clone();
+                    } else if let Some(ext_address) = &ext_proxy.address {
+                        obfs_settings.host = ext_address.clone();
+                    }
+                    if let Some(ext_obfs_uri) = &ext_proxy.obfs_uri {
+                        obfs_settings.path = ext_obfs_uri.clone();
+                    } else {
+                        obfs_settings.path = "/".to_string();
+                    }
+                    let obfs_settings = obfs_settings.write_to_bytes().unwrap();
+                    obfs_outbound.settings = obfs_settings;
+                    obfs_outbound.tag = format!("{}_obfs_xxx", ext_proxy.tag.clone());
+
+                    // chain
+                    let mut chain_outbound = internal::Outbound::new();
+                    chain_outbound.tag = ext_proxy.tag.clone();
+                    let mut chain_settings = internal::ChainOutboundSettings::new();
+                    chain_settings.actors.push(obfs_outbound.tag.clone());
+                    chain_settings.actors.push(outbound.tag.clone());
+                    let chain_settings = chain_settings.write_to_bytes().unwrap();
+                    chain_outbound.settings = chain_settings;
+                    chain_outbound.protocol = "chain".to_string();
+
+                    // always push chain first, in case there isn't final rule,
+                    // the chain outbound will be the default one to use
+                    outbounds.push(chain_outbound);
+                    outbounds.push(obfs_outbound);
+                    outbounds.push(outbound);
+                }
+                "trojan" => {
+                    // tls
+                    let mut tls_outbound = internal::Outbound::new();
+                    tls_outbound.protocol = "tls".to_string();
+                    let mut tls_settings = internal::TlsOutboundSettings::new();
+                    if let Some(ext_sni) = &ext_proxy.sni {
+                        tls_settings.server_name = ext_sni.clone();
+                    }
+                    if ext_proxy.grpc.unwrap() || ext_proxy.h2.unwrap() {
+                        tls_settings.alpn.push("h2".to_string());
+                    }
+                    if let Some(ext_tls_cert) = &ext_proxy.tls_cert {
+                        let cert = Path::new(ext_tls_cert);
+                        if cert.is_absolute() {
+                            tls_settings.certificate = cert.to_string_lossy().to_string();
+                        } else {
+                            let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
+                            let path = asset_loc.join(cert).to_string_lossy().to_string();
+                            tls_settings.certificate = path;
+                        }
+                    }
+                    tls_settings.ech = ext_proxy.ech.unwrap();
+                    if let Some(ext_ech_config) = &ext_proxy.ech_config {
+                        tls_settings.ech_config = ext_ech_config.clone();
+                    }
+                    if let Some(ext_tls_client_cert) = &ext_proxy.tls_client_cert {
+                        let cert = Path::new(ext_tls_client_cert);
+                        if cert.is_absolute() {
+                            tls_settings.client_certificate = cert.to_string_lossy().to_string();
+                        } else {
+                            let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
+                            let path = asset_loc.join(cert).to_string_lossy().to_string();
+                            tls_settings.client_certificate = path;
+                        }
+                    }
+                    if let Some(ext_tls_client_key) = &ext_proxy.tls_client_key {
+                        let key = Path::new(ext_tls_client_key);
+                        if key.is_absolute() {
+                            tls_settings.client_certificate_key = key.to_string_lossy().to_string();
+                        } else {
+                            let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
+                            let path = asset_loc.join(key).to_string_lossy().to_string();
+                            tls_settings.client_certificate_key = path;
+                        }
+                    }
+                    if let Some(ext_tls_cert_sha256) = &ext_proxy.tls_cert_sha256 {
+                        tls_settings.pinned_peer_certificate_chain_sha256 =
+                            protobuf::RepeatedField::from_vec(ext_tls_cert_sha256.clone());
+                    }
+                    let tls_settings = tls_settings.write_to_bytes().unwrap();
+                    tls_outbound.settings = tls_settings;
+                    if let Some(ext_public_key) = &ext_proxy.reality_public_key {
+                        // REALITY takes the place of TLS
+                        tls_outbound.protocol = "reality".to_string();
+                        let mut reality_settings = internal::RealityOutboundSettings::new();
+                        if let Some(ext_sni) = &ext_proxy.sni {
+                            reality_settings.server_name = ext_sni.clone();
+                        }
+                        reality_settings.public_key = ext_public_key.clone();
+                        if let Some(ext_short_id) = &ext_proxy.reality_short_id {
+                            reality_settings.short_id = ext_short_id.clone();
+                        }
+                        if ext_proxy.grpc.unwrap() || ext_proxy.h2.unwrap() {
+                            reality_settings.alpn.push("h2".to_string());
+                        }
+                        let reality_settings = reality_settings.write_to_bytes().unwrap();
+                        tls_outbound.settings = reality_settings;
+                    }
+                    tls_outbound.tag = format!("{}_tls_xxx", ext_proxy.tag.clone());
+
+                    // ws
+                    let mut ws_outbound = internal::Outbound::new();
+                    ws_outbound.protocol = "ws".to_string();
+                    let mut ws_settings = internal::WebSocketOutboundSettings::new();
+                    if let Some(ext_ws_path) = &ext_proxy.ws_path {
+                        ws_settings.path = ext_ws_path.clone();
+                    } else {
+                        ws_settings.path = "/".to_string();
+                    }
+                    if let Some(ext_ws_headers) = &ext_proxy.ws_headers {
+                        ws_settings.headers = ext_ws_headers.clone();
+                    }
+                    if let Some(ext_ws_host) = &ext_proxy.ws_host {
+                        ws_settings
+                            .headers
+                            .retain(|k, _| !k.eq_ignore_ascii_case("Host"));
+                        ws_settings
+                            .headers
+                            .insert("Host".to_string(), ext_ws_host.clone());
+                    }
+                    if let Some(ext_max_early_data) = &ext_proxy.ws_max_early_data {
+                        ws_settings.max_early_data = *ext_max_early_data;
+                    }
+                    if let Some(ext_header_name) = &ext_proxy.ws_early_data_header_name {
+                        ws_settings.early_data_header_name = ext_header_name.clone();
+                    }
+                    let ws_settings = ws_settings.write_to_bytes().unwrap();
+                    ws_outbound.settings = ws_settings;
+                    ws_outbound.tag = format!("{}_ws_xxx", ext_proxy.tag.clone());
+
+                    // obfs
+                    let mut obfs_outbound = internal::Outbound::new();
+                    obfs_outbound.protocol = "obfs".to_string();
+                    let mut obfs_settings = internal::ObfsOutboundSettings::new();
+                    if let Some(ext_obfs) = &ext_proxy.obfs {
+                        obfs_settings.method = ext_obfs.clone();
+                    }
+                    if let Some(ext_obfs_host) = &ext_proxy.obfs_host {
+                        obfs_settings.host = ext_obfs_host.clone();
+                    } else if let Some(ext_address) = &ext_proxy.address {
+                        obfs_settings.host = ext_address.clone();
+                    }
+                    if let Some(ext_obfs_uri) = &ext_proxy.obfs_uri {
+                        obfs_settings.path = ext_obfs_uri.clone();
+                    } else {
+                        obfs_settings.path = "/".to_string();
+                    }
+                    let obfs_settings = obfs_settings.write_to_bytes().unwrap();
+                    obfs_outbound.settings = obfs_settings;
+                    obfs_outbound.tag = format!("{}_obfs_xxx", ext_proxy.tag.clone());
+
+                    // amux
+                    let mut amux_outbound = internal::Outbound::new();
+                    amux_outbound.tag = ext_proxy.tag.clone();
+                    let mut amux_settings = internal::AMuxOutboundSettings::new();
+                    if ext_proxy.obfs.is_some() {
+                        amux_settings.actors.push(obfs_outbound.tag.clone());
+                    }
+                    // always enable tls for trojan
+                    amux_settings.actors.push(tls_outbound.tag.clone());
+                    if ext_proxy.ws.unwrap() {
+                        amux_settings.actors.push(ws_outbound.tag.clone());
+                    }
+                    if let Some(ext_address) = &ext_proxy.address {
+                        amux_settings.address = ext_address.clone();
+                    }
+                    if let Some(ext_port) = &ext_proxy.port {
+                        amux_settings.port = *ext_port as u32;
+                    }
+                    if let Some(ext_max_accepts) = &ext_proxy.amux_max {
+                        amux_settings.max_accepts = *ext_max_accepts as u32;
+                    }
+                    if let Some(ext_concurrency) = &ext_proxy.amux_con {
+                        amux_settings.concurrency = *ext_concurrency as u32;
+                    }
+                    if let Some(ext_keep_alive) = &ext_proxy.amux_keep_alive {
+                        amux_settings.keep_alive = *ext_keep_alive as u32;
+                    }
+                    if let Some(ext_idle_timeout) = &ext_proxy.amux_idle_timeout {
+                        amux_settings.idle_timeout = *ext_idle_timeout as u32;
+                    }
+                    let amux_settings = amux_settings.write_to_bytes().unwrap();
+                    amux_outbound.settings = amux_settings;
+                    amux_outbound.protocol = "amux".to_string();
+                    amux_outbound.tag = format!("{}_amux_xxx", ext_proxy.tag.clone());
+
+                    // mux
+                    let mut mux_outbound = internal::Outbound::new();
+                    let mut mux_settings = internal::MuxOutboundSettings::new();
+                    if ext_proxy.obfs.is_some() {
+                        mux_settings.actors.push(obfs_outbound.tag.clone());
+                    }
+                    mux_settings.actors.push(tls_outbound.tag.clone());
+                    if ext_proxy.ws.unwrap() {
+                        mux_settings.actors.push(ws_outbound.tag.clone());
+                    }
+                    if let Some(ext_address) = &ext_proxy.address {
+                        mux_settings.address = ext_address.clone();
+                    }
+                    if let Some(ext_port) = &ext_proxy.port {
+                        mux_settings.port = *ext_port as u32;
+                    }
+                    if let Some(ext_max_streams) = &ext_proxy.mux_max_streams {
+                        mux_settings.max_streams = *ext_max_streams as u32;
+                    }
+                    if let Some(ext_min_connections) = &ext_proxy.mux_min_connections {
+                        mux_settings.min_connections = *ext_min_connections as u32;
+                    }
+                    if let Some(ext_max_connections) = &ext_proxy.mux_max_connections {
+                        mux_settings.max_connections = *ext_max_connections as u32;
+                    }
+                    mux_settings.padding = ext_proxy.mux_padding.unwrap();
+                    let mux_settings = mux_settings.write_to_bytes().unwrap();
+                    mux_outbound.settings = mux_settings;
+                    mux_outbound.protocol = "mux".to_string();
+                    mux_outbound.tag = format!("{}_mux_xxx", ext_proxy.tag.clone());
+                    // grpc
+                    let mut grpc_outbound = internal::Outbound::new();
+                    grpc_outbound.protocol = "grpc".to_string();
+                    let mut grpc_settings = internal::GrpcOutboundSettings::new();
+                    if let Some(ext_address) = &ext_proxy.address {
+                        grpc_settings.address = ext_address.clone();
+                    }
+                    if let Some(ext_port) = &ext_proxy.port {
+                        grpc_settings.port = *ext_port as u32;
+                    }
+                    if let Some(ext_sni) = &ext_proxy.sni {
+                        grpc_settings.host = ext_sni.clone();
+                    }
+                    if let Some(ext_service_name) = &ext_proxy.grpc_service_name {
+                        grpc_settings.service_name = ext_service_name.clone();
+                    }
+                    if ext_proxy.obfs.is_some() {
+                        grpc_settings.actors.push(obfs_outbound.tag.clone());
+                    }
+                    grpc_settings.actors.push(tls_outbound.tag.clone());
+                    let grpc_settings = grpc_settings.write_to_bytes().unwrap();
+                    grpc_outbound.settings = grpc_settings;
+                    grpc_outbound.tag = format!("{}_grpc_xxx", ext_proxy.tag.clone());
+
+                    // h2
+                    let mut h2_outbound = internal::Outbound::new();
+                    h2_outbound.protocol = "h2".to_string();
+                    let mut h2_settings = internal::Http2OutboundSettings::new();
+                    if let Some(ext_address) = &ext_proxy.address {
+                        h2_settings.address = ext_address.clone();
+                    }
+                    if let Some(ext_port) = &ext_proxy.port {
+                        h2_settings.port = *ext_port as u32;
+                    }
+                    if let Some(ext_h2_host) = &ext_proxy.h2_host {
+                        h2_settings.host = ext_h2_host.clone();
+                    } else if let Some(ext_sni) = &ext_proxy.sni {
+                        h2_settings.host = ext_sni.clone();
+                    }
+                    if let Some(ext_h2_path) = &ext_proxy.h2_path {
+                        h2_settings.path = ext_h2_path.clone();
+                    } else {
+                        h2_settings.path = "/".to_string();
+                    }
+                    if ext_proxy.obfs.is_some() {
+                        h2_settings.actors.push(obfs_outbound.tag.clone());
+                    }
+                    h2_settings.actors.push(tls_outbound.tag.clone());
+                    let h2_settings = h2_settings.write_to_bytes().unwrap();
+                    h2_outbound.settings = h2_settings;
+                    h2_outbound.tag = format!("{}_h2_xxx", ext_proxy.tag.clone());
+
+                    // quic
+                    let mut quic_outbound = internal::Outbound::new();
+                    quic_outbound.tag = ext_proxy.tag.clone();
+                    let mut quic_settings = internal::QuicOutboundSettings::new();
+                    if let Some(ext_address) = &ext_proxy.address {
+                        quic_settings.address = ext_address.clone();
+                    }
+                    if let Some(ext_port) = &ext_proxy.port {
+                        quic_settings.port = *ext_port as u32;
+                    }
+                    if let Some(ext_sni) = &ext_proxy.sni {
+                        quic_settings.server_name = ext_sni.clone();
+                    }
+                    if let Some(ext_tls_cert) = &ext_proxy.tls_cert {
+                        let cert = Path::new(ext_tls_cert);
+                        if cert.is_absolute() {
+                            quic_settings.certificate = cert.to_string_lossy().to_string();
+                        } else {
+                            let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
+                            let path = asset_loc.join(cert).to_string_lossy().to_string();
+                            quic_settings.certificate = path;
+                        }
+                    }
+                    let quic_settings = quic_settings.write_to_bytes().unwrap();
+                    quic_outbound.settings = quic_settings;
+                    quic_outbound.protocol = "quic".to_string();
+                    quic_outbound.tag = format!("{}_quic_xxx", ext_proxy.tag.clone());
+
+                    // plain trojan
+                    let mut settings = internal::TrojanOutboundSettings::new();
+                    if !ext_proxy.amux.unwrap()
+                        && !ext_proxy.mux.unwrap()
+                        && !ext_proxy.grpc.unwrap()
+                        && !ext_proxy.h2.unwrap()
+                    {
+                        if let Some(ext_address) = &ext_proxy.address {
+                            settings.address = ext_address.clone();
+                        }
+                        if let Some(ext_port) = &ext_proxy.port {
+                            settings.port = *ext_port as u32;
+                        }
+                    }
+                    if let Some(ext_password) = &ext_proxy.password {
+                        settings.password = ext_password.clone();
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbound.tag = format!("{}_trojan_xxx", ext_proxy.tag.clone());
+
+                    // chain
+                    let mut chain_outbound = internal::Outbound::new();
+                    chain_outbound.tag = ext_proxy.tag.clone();
+                    let mut chain_settings = internal::ChainOutboundSettings::new();
+                    if ext_proxy.amux.unwrap() {
+                        chain_settings.actors.push(amux_outbound.tag.clone());
+                    } else if ext_proxy.mux.unwrap() {
+                        chain_settings.actors.push(mux_outbound.tag.clone());
+                    } else if ext_proxy.grpc.unwrap() {
+                        chain_settings.actors.push(grpc_outbound.tag.clone());
+                    } else if ext_proxy.h2.unwrap() {
+                        chain_settings.actors.push(h2_outbound.tag.clone());
+                    } else if ext_proxy.quic.unwrap() {
+                        chain_settings.actors.push(quic_outbound.tag.clone());
+                    } else {
+                        if ext_proxy.obfs.is_some() {
+                            chain_settings.actors.push(obfs_outbound.tag.clone());
+                        }
+                        chain_settings.actors.push(tls_outbound.tag.clone());
+                        if ext_proxy.ws.unwrap() {
+                            chain_settings.actors.push(ws_outbound.tag.clone());
+                        }
+                    }
+                    chain_settings.actors.push(outbound.tag.clone());
+                    let chain_settings = chain_settings.write_to_bytes().unwrap();
+                    chain_outbound.settings = chain_settings;
+                    chain_outbound.protocol = "chain".to_string();
+
+                    // always push chain first, in case there isn't final rule,
+                    // the chain outbound will be the default one to use
+                    outbounds.push(chain_outbound);
+                    if ext_proxy.amux.unwrap() {
+                        outbounds.push(amux_outbound);
+                    } else if ext_proxy.mux.unwrap() {
+                        outbounds.push(mux_outbound);
+                    } else if ext_proxy.grpc.unwrap() {
+                        outbounds.push(grpc_outbound);
+                    } else if ext_proxy.h2.unwrap() {
+                        outbounds.push(h2_outbound);
+                    }
+                    if ext_proxy.quic.unwrap() {
+                        outbounds.push(quic_outbound);
+                    } else {
+                        if ext_proxy.obfs.is_some() {
+                            outbounds.push(obfs_outbound);
+                        }
+                        outbounds.push(tls_outbound);
+                    }
+                    if ext_proxy.ws.unwrap() {
+                        outbounds.push(ws_outbound);
+                    }
+                    outbounds.push(outbound);
+                }
+                "vless" | "vmess" => {
+                    let settings = if ext_proxy.protocol == "vless" {
+                        let mut settings = internal::VLessOutboundSettings::new();
+                        if let Some(ext_address) = &ext_proxy.address {
+                            settings.address = ext_address.clone();
+                        }
+                        if let Some(ext_port) = &ext_proxy.port {
+                            settings.port = *ext_port as u32;
+                        }
+                        if let Some(ext_uuid) = &ext_proxy.uuid {
+                            settings.uuid = ext_uuid.clone();
+                        }
+                        settings.write_to_bytes().unwrap()
+                    } else {
+                        let mut settings = internal::VMessOutboundSettings::new();
+                        if let Some(ext_address) = &ext_proxy.address {
+                            settings.address = ext_address.clone();
+                        }
+                        if let Some(ext_port) = &ext_proxy.port {
+                            settings.port = *ext_port as u32;
+                        }
+                        if let Some(ext_uuid) = &ext_proxy.uuid {
+                            settings.uuid = ext_uuid.clone();
+                        }
+                        if let Some(ext_encrypt_method) = &ext_proxy.encrypt_method {
+                            settings.security = ext_encrypt_method.clone();
+                        }
+                        settings.write_to_bytes().unwrap()
+                    };
+                    outbound.settings = settings;
+
+                    if !ext_proxy.tls.unwrap()
+                        && !ext_proxy.ws.unwrap()
+                        && !ext_proxy.grpc.unwrap()
+                        && !ext_proxy.h2.unwrap()
+                        && !ext_proxy.quic.unwrap()
+                    {
+                        outbounds.push(outbound);
+                        continue;
+                    }
+                    outbound.tag = format!("{}_{}_xxx", ext_proxy.tag.clone(), ext_proxy.protocol);
+
+                    let mut chain_outbound = internal::Outbound::new();
+                    chain_outbound.tag = ext_proxy.tag.clone();
+                    let mut chain_settings = internal::ChainOutboundSettings::new();
+
                     // tls
                     let mut tls_outbound = internal::Outbound::new();
                     tls_outbound.protocol = "tls".to_string();
@@ -830,6 +2380,9 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
                     if let Some(ext_sni) = &ext_proxy.sni {
                         tls_settings.server_name = ext_sni.clone();
                     }
+                    if ext_proxy.grpc.unwrap() || ext_proxy.h2.unwrap() {
+                        tls_settings.alpn.push("h2".to_string());
+                    }
                     if let Some(ext_tls_cert) = &ext_proxy.tls_cert {
                         let cert = Path::new(ext_tls_cert);
                         if cert.is_absolute() {
@@ -840,8 +2393,53 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
                             tls_settings.certificate = path;
                         }
                     }
+                    tls_settings.ech = ext_proxy.ech.unwrap();
+                    if let Some(ext_ech_config) = &ext_proxy.ech_config {
+                        tls_settings.ech_config = ext_ech_config.clone();
+                    }
+                    if let Some(ext_tls_client_cert) = &ext_proxy.tls_client_cert {
+                        let cert = Path::new(ext_tls_client_cert);
+                        if cert.is_absolute() {
+                            tls_settings.client_certificate = cert.to_string_lossy().to_string();
+                        } else {
+                            let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
+                            let path = asset_loc.join(cert).to_string_lossy().to_string();
+                            tls_settings.client_certificate = path;
+                        }
+                    }
+                    if let Some(ext_tls_client_key) = &ext_proxy.tls_client_key {
+                        let key = Path::new(ext_tls_client_key);
+                        if key.is_absolute() {
+                            tls_settings.client_certificate_key = key.to_string_lossy().to_string();
+                        } else {
+                            let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
+                            let path = asset_loc.join(key).to_string_lossy().to_string();
+                            tls_settings.client_certificate_key = path;
+                        }
+                    }
+                    if let Some(ext_tls_cert_sha256) = &ext_proxy.tls_cert_sha256 {
+                        tls_settings.pinned_peer_certificate_chain_sha256 =
+                            protobuf::RepeatedField::from_vec(ext_tls_cert_sha256.clone());
+                    }
                     let tls_settings = tls_settings.write_to_bytes().unwrap();
                     tls_outbound.settings = tls_settings;
+                    if let Some(ext_public_key) = &ext_proxy.reality_public_key {
+                        // REALITY takes the place of TLS
+                        tls_outbound.protocol = "reality".to_string();
+                        let mut reality_settings = internal::RealityOutboundSettings::new();
+                        if let Some(ext_sni) = &ext_proxy.sni {
+                            reality_settings.server_name = ext_sni.clone();
+                        }
+                        reality_settings.public_key = ext_public_key.clone();
+                        if let Some(ext_short_id) = &ext_proxy.reality_short_id {
+                            reality_settings.short_id = ext_short_id.clone();
+                        }
+                        if ext_proxy.grpc.unwrap() || ext_proxy.h2.unwrap() {
+                            reality_settings.alpn.push("h2".to_string());
+                        }
+                        let reality_settings = reality_settings.write_to_bytes().unwrap();
+                        tls_outbound.settings = reality_settings;
+                    }
                     tls_outbound.tag = format!("{}_tls_xxx", ext_proxy.tag.clone());
 
                     // ws
@@ -853,40 +2451,73 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
                     } else {
                         ws_settings.path = "/".to_string();
                     }
+                    if let Some(ext_ws_headers) = &ext_proxy.ws_headers {
+                        ws_settings.headers = ext_ws_headers.clone();
+                    }
                     if let Some(ext_ws_host) = &ext_proxy.ws_host {
-                        let mut headers = HashMap::new();
-                        headers.insert("Host".to_string(), ext_ws_host.clone());
-                        ws_settings.headers = headers;
+                        ws_settings
+                            .headers
+                            .retain(|k, _| !k.eq_ignore_ascii_case("Host"));
+                        ws_settings
+                            .headers
+                            .insert("Host".to_string(), ext_ws_host.clone());
+                    }
+                    if let Some(ext_max_early_data) = &ext_proxy.ws_max_early_data {
+                        ws_settings.max_early_data = *ext_max_early_data;
+                    }
+                    if let Some(ext_header_name) = &ext_proxy.ws_early_data_header_name {
+                        ws_settings.early_data_header_name = ext_header_name.clone();
                     }
                     let ws_settings = ws_settings.write_to_bytes().unwrap();
                     ws_outbound.settings = ws_settings;
                     ws_outbound.tag = format!("{}_ws_xxx", ext_proxy.tag.clone());
 
-                    // amux
-                    let mut amux_outbound = internal::Outbound::new();
-                    amux_outbound.tag = ext_proxy.tag.clone();
-                    let mut amux_settings = internal::AMuxOutboundSettings::new();
-                    // always enable tls for trojan
-                    amux_settings.actors.push(tls_outbound.tag.clone());
-                    if ext_proxy.ws.unwrap() {
-                        amux_settings.actors.push(ws_outbound.tag.clone());
+                    // grpc
+                    let mut grpc_outbound = internal::Outbound::new();
+                    grpc_outbound.protocol = "grpc".to_string();
+                    let mut grpc_settings = internal::GrpcOutboundSettings::new();
+                    if let Some(ext_address) = &ext_proxy.address {
+                        grpc_settings.address = ext_address.clone();
+                    }
+                    if let Some(ext_port) = &ext_proxy.port {
+                        grpc_settings.port = *ext_port as u32;
                     }
+                    if let Some(ext_sni) = &ext_proxy.sni {
+                        grpc_settings.host = ext_sni.clone();
+                    }
+                    if let Some(ext_service_name) = &ext_proxy.grpc_service_name {
+                        grpc_settings.service_name = ext_service_name.clone();
+                    }
+                    grpc_settings.actors.push(tls_outbound.tag.clone());
+                    let grpc_settings = grpc_settings.write_to_bytes().unwrap();
+                    grpc_outbound.settings = grpc_settings;
+                    grpc_outbound.tag = format!("{}_grpc_xxx", ext_proxy.tag.clone());
+
+                    // h2
+                    let mut h2_outbound = internal::Outbound::new();
+                    h2_outbound.protocol = "h2".to_string();
+                    let mut h2_settings = internal::Http2OutboundSettings::new();
                     if let Some(ext_address) = &ext_proxy.address {
-                        amux_settings.address = ext_address.clone();
+                        h2_settings.address = ext_address.clone();
                     }
                     if let Some(ext_port) = &ext_proxy.port {
-                        amux_settings.port = *ext_port as u32;
+                        h2_settings.port = *ext_port as u32;
                     }
-                    if let Some(ext_max_accepts) = &ext_proxy.amux_max {
-                        amux_settings.max_accepts = *ext_max_accepts as u32;
+                    if let Some(ext_h2_host) = &ext_proxy.h2_host {
+                        h2_settings.host = ext_h2_host.clone();
+                    } else if let Some(ext_sni) = &ext_proxy.sni {
+                        h2_settings.host = ext_sni.clone();
                     }
-                    if let Some(ext_concurrency) = &ext_proxy.amux_con {
-                        amux_settings.concurrency = *ext_concurrency as u32;
+                    if let Some(ext_h2_path) = &ext_proxy.h2_path {
+                        h2_settings.path = ext_h2_path.clone();
+                    } else {
+                        h2_settings.path = "/".to_string();
                     }
-                    let amux_settings = amux_settings.write_to_bytes().unwrap();
-                    amux_outbound.settings = amux_settings;
-                    amux_outbound.protocol = "amux".to_string();
-                    amux_outbound.tag = format!("{}_amux_xxx", ext_proxy.tag.clone());
+                    h2_settings.actors.push(tls_outbound.tag.clone());
+                    let h2_settings = h2_settings.write_to_bytes().unwrap();
+                    h2_outbound.settings = h2_settings;
+                    h2_outbound.tag = format!("{}_h2_xxx", ext_proxy.tag.clone());
+
                     // quic
                     let mut quic_outbound = internal::Outbound::new();
                     quic_outbound.tag = ext_proxy.tag.clone();
@@ -915,33 +2546,17 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
                     quic_outbound.protocol = "quic".to_string();
                     quic_outbound.tag = format!("{}_quic_xxx", ext_proxy.tag.clone());
 
-                    // plain trojan
-                    let mut settings = internal::TrojanOutboundSettings::new();
-                    if !ext_proxy.amux.unwrap() {
-                        if let Some(ext_address) = &ext_proxy.address {
-                            settings.address = ext_address.clone();
-                        }
-                        if let Some(ext_port) = &ext_proxy.port {
-                            settings.port = *ext_port as u32;
-                        }
-                    }
-                    if let Some(ext_password) = &ext_proxy.password {
-                        settings.password = ext_password.clone();
-                    }
-                    let settings = settings.write_to_bytes().unwrap();
-                    outbound.settings = settings;
-                    outbound.tag = format!("{}_trojan_xxx", ext_proxy.tag.clone());
-
-                    // chain
-                    let mut chain_outbound = internal::Outbound::new();
-                    chain_outbound.tag = ext_proxy.tag.clone();
-                    let mut chain_settings = internal::ChainOutboundSettings::new();
-                    if ext_proxy.amux.unwrap() {
-                        chain_settings.actors.push(amux_outbound.tag.clone());
-                    } else if ext_proxy.quic.unwrap() {
+                    // grpc and h2 are always over tls
+                    if ext_proxy.quic.unwrap() {
                         chain_settings.actors.push(quic_outbound.tag.clone());
+                    } else if ext_proxy.grpc.unwrap() {
+                        chain_settings.actors.push(grpc_outbound.tag.clone());
+                    } else if ext_proxy.h2.unwrap() {
+                        chain_settings.actors.push(h2_outbound.tag.clone());
                     } else {
-                        chain_settings.actors.push(tls_outbound.tag.clone());
+                        if ext_proxy.tls.unwrap() {
+                            chain_settings.actors.push(tls_outbound.tag.clone());
+                        }
                         if ext_proxy.ws.unwrap() {
                             chain_settings.actors.push(ws_outbound.tag.clone());
                         }
@@ -954,22 +2569,116 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
                     // always push chain first, in case there isn't final rule,
                     // the chain outbound will be the default one to use
                     outbounds.push(chain_outbound);
-                    if ext_proxy.amux.unwrap() {
-                        outbounds.push(amux_outbound);
-                    }
                     if ext_proxy.quic.unwrap() {
                         outbounds.push(quic_outbound);
-                    } else {
+                    } else if ext_proxy.grpc.unwrap() {
+                        outbounds.push(grpc_outbound);
+                        outbounds.push(tls_outbound);
+                    } else if ext_proxy.h2.unwrap() {
+                        outbounds.push(h2_outbound);
                         outbounds.push(tls_outbound);
+                    } else {
+                        if ext_proxy.tls.unwrap() {
+                            outbounds.push(tls_outbound);
+                        }
+                        if ext_proxy.ws.unwrap() {
+                            outbounds.push(ws_outbound);
+                        }
                     }
-                    if ext_proxy.ws.unwrap() {
-                        outbounds.push(ws_outbound);
+                    outbounds.push(outbound);
+                }
+                "naive" => {
+                    let mut settings = internal::NaiveOutboundSettings::new();
+                    if let Some(ext_address) = &ext_proxy.address {
+                        settings.address = ext_address.clone();
+                    }
+                    if let Some(ext_port) = &ext_proxy.port {
+                        settings.port = *ext_port as u32;
+                    }
+                    if let Some(ext_username) = &ext_proxy.username {
+                        settings.username = ext_username.clone();
+                    }
+                    if let Some(ext_password) = &ext_proxy.password {
+                        settings.password = ext_password.clone();
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "ssh" => {
+                    let mut settings = internal::SshOutboundSettings::new();
+                    if let Some(ext_address) = &ext_proxy.address {
+                        settings.address = ext_address.clone();
+                    }
+                    if let Some(ext_port) = &ext_proxy.port {
+                        settings.port = *ext_port as u32;
+                    }
+                    if let Some(ext_username) = &ext_proxy.username {
+                        settings.username = ext_username.clone();
+                    }
+                    if let Some(ext_password) = &ext_proxy.password {
+                        settings.password = ext_password.clone();
+                    }
+                    if let Some(ext_private_key) = &ext_proxy.private_key {
+                        let key = Path::new(ext_private_key);
+                        if key.is_absolute() {
+                            settings.private_key = key.to_string_lossy().to_string();
+                        } else {
+                            let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
+                            let path = asset_loc.join(key).to_string_lossy().to_string();
+                            settings.private_key = path;
+                        }
+                    }
+                    if let Some(ext_host_key) = &ext_proxy.host_key {
+                        settings.host_key = ext_host_key.clone();
                     }
+                    if let Some(ext_skip) = ext_proxy.insecure_skip_host_key {
+                        settings.insecure_skip_host_key = ext_skip;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
                     outbounds.push(outbound);
                 }
                 _ => {}
             }
         }
+        // The outbounds tagged as the proxies are the ones dialed, i.e. the
+        // outermost ones of chains.
+        for ext_proxy in ext_proxies {
+            for outbound in outbounds.iter_mut().filter(|o| o.tag == ext_proxy.tag) {
+                if let Some(ext_interface) = &ext_proxy.interface {
+                    outbound.bind_interface = ext_interface.clone();
+                }
+                if let Some(ext_bind_address) = &ext_proxy.bind_address {
+                    outbound.bind_addresses =
+                        protobuf::RepeatedField::from_vec(ext_bind_address.clone());
+                }
+                if let Some(ext_domain_strategy) = &ext_proxy.domain_strategy {
+                    outbound.domain_strategy = match ext_domain_strategy.as_str() {
+                        "prefer_ipv4" => internal::Outbound_DomainStrategy::PREFER_IPV4,
+                        "prefer_ipv6" => internal::Outbound_DomainStrategy::PREFER_IPV6,
+                        "ipv4_only" => internal::Outbound_DomainStrategy::IPV4_ONLY,
+                        "ipv6_only" => internal::Outbound_DomainStrategy::IPV6_ONLY,
+                        _ => internal::Outbound_DomainStrategy::AS_IS,
+                    };
+                }
+                if let Some(ext_tfo) = ext_proxy.tfo {
+                    outbound.tcp_fast_open = ext_tfo;
+                }
+                if let Some(ext_connect_timeout) = ext_proxy.connect_timeout {
+                    outbound.connect_timeout = ext_connect_timeout;
+                }
+                if let Some(ext_handshake_timeout) = ext_proxy.handshake_timeout {
+                    outbound.handshake_timeout = ext_handshake_timeout;
+                }
+            }
+            if let Some(ext_tls_handshake_timeout) = ext_proxy.tls_handshake_timeout {
+                let tls_tag = format!("{}_tls_xxx", ext_proxy.tag);
+                for outbound in outbounds.iter_mut().filter(|o| o.tag == tls_tag) {
+                    outbound.handshake_timeout = ext_tls_handshake_timeout;
+                }
+            }
+        }
     }
 
     if let Some(ext_proxy_groups) = &conf.proxy_group {
@@ -1001,6 +2710,35 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
                     } else {
                         settings.delay_base = 0;
                     }
+                    if let Some(ext_attempt_timeout) = ext_proxy_group.attempt_timeout {
+                        settings.attempt_timeout = ext_attempt_timeout as u32;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "retry" => {
+                    let mut settings = internal::RetryOutboundSettings::new();
+                    if let Some(ext_actors) = &ext_proxy_group.actors {
+                        for ext_actor in ext_actors {
+                            settings.actors.push(ext_actor.to_string());
+                        }
+                    }
+                    if let Some(ext_attempts) = ext_proxy_group.attempts {
+                        settings.attempts = ext_attempts as u32;
+                    }
+                    if let Some(ext_backoff) = ext_proxy_group.backoff {
+                        settings.backoff_base = ext_backoff as u32;
+                    }
+                    if let Some(ext_max_backoff) = ext_proxy_group.max_backoff {
+                        settings.max_backoff = ext_max_backoff as u32;
+                    }
+                    if let Some(ext_jitter) = ext_proxy_group.jitter {
+                        settings.jitter = ext_jitter as u32;
+                    }
+                    if let Some(ext_rotate) = ext_proxy_group.rotate {
+                        settings.rotate = ext_rotate;
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -1017,6 +2755,22 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
                     } else {
                         settings.method = "random".to_string();
                     }
+                    if let Some(ext_weights) = &ext_proxy_group.weights {
+                        settings.weights = ext_weights.clone();
+                    }
+                    if let Some(ext_url) = &ext_proxy_group.url {
+                        settings.url = ext_url.clone();
+                    }
+                    if let Some(ext_interval) = ext_proxy_group.interval {
+                        settings.interval = ext_interval as u32;
+                    } else {
+                        settings.interval = 300;
+                    }
+                    if let Some(ext_timeout) = ext_proxy_group.timeout {
+                        settings.timeout = ext_timeout as u32;
+                    } else {
+                        settings.timeout = 5;
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -1028,6 +2782,9 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
                             settings.actors.push(ext_actor.to_string());
                         }
                     }
+                    if let Some(ext_filter) = &ext_proxy_group.filter {
+                        settings.filter = ext_filter.clone();
+                    }
                     if let Some(ext_fail_timeout) = ext_proxy_group.fail_timeout {
                         settings.fail_timeout = ext_fail_timeout as u32;
                     } else {
@@ -1073,6 +2830,74 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
                     } else {
                         settings.health_check_timeout = 4;
                     }
+                    if let Some(ext_eject_threshold) = ext_proxy_group.eject_threshold {
+                        settings.eject_threshold = ext_eject_threshold as u32;
+                    } else {
+                        settings.eject_threshold = 3;
+                    }
+                    if let Some(ext_eject_duration) = ext_proxy_group.eject_duration {
+                        settings.eject_duration = ext_eject_duration as u32;
+                    } else {
+                        settings.eject_duration = 30;
+                    }
+                    if let Some(ext_max_eject_duration) = ext_proxy_group.max_eject_duration {
+                        settings.max_eject_duration = ext_max_eject_duration as u32;
+                    } else {
+                        settings.max_eject_duration = 600;
+                    }
+                    if let Some(ext_health_check_url) = &ext_proxy_group.health_check_url {
+                        settings.health_check_url = ext_health_check_url.clone();
+                    }
+                    if let Some(ext_health_check_status) = ext_proxy_group.health_check_status {
+                        settings.health_check_status = ext_health_check_status as u32;
+                    }
+                    if let Some(ext_health_check_concurrency) =
+                        ext_proxy_group.health_check_concurrency
+                    {
+                        settings.health_check_concurrency = ext_health_check_concurrency as u32;
+                    }
+                    if let Some(ext_udp_health_check) = ext_proxy_group.udp_health_check {
+                        settings.udp_health_check = ext_udp_health_check;
+                    } else {
+                        settings.udp_health_check = true;
+                    }
+                    if let Some(ext_udp_health_check_server) =
+                        &ext_proxy_group.udp_health_check_server
+                    {
+                        settings.udp_health_check_server = ext_udp_health_check_server.clone();
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "urltest" => {
+                    let mut settings = internal::UrlTestOutboundSettings::new();
+                    if let Some(ext_actors) = &ext_proxy_group.actors {
+                        for ext_actor in ext_actors {
+                            settings.actors.push(ext_actor.to_string());
+                        }
+                    }
+                    if let Some(ext_filter) = &ext_proxy_group.filter {
+                        settings.filter = ext_filter.clone();
+                    }
+                    if let Some(ext_url) = &ext_proxy_group.url {
+                        settings.url = ext_url.clone();
+                    }
+                    if let Some(ext_interval) = ext_proxy_group.interval {
+                        settings.interval = ext_interval as u32;
+                    } else {
+                        settings.interval = 300;
+                    }
+                    if let Some(ext_tolerance) = ext_proxy_group.tolerance {
+                        settings.tolerance = ext_tolerance as u32;
+                    } else {
+                        settings.tolerance = 50;
+                    }
+                    if let Some(ext_timeout) = ext_proxy_group.timeout {
+                        settings.timeout = ext_timeout as u32;
+                    } else {
+                        settings.timeout = 5;
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -1084,6 +2909,9 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
                             settings.actors.push(ext_actor.to_string());
                         }
                     }
+                    if let Some(ext_filter) = &ext_proxy_group.filter {
+                        settings.filter = ext_filter.clone();
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -1093,14 +2921,78 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
         }
     }
 
+    let mut providers = protobuf::RepeatedField::new();
+    if let Some(ext_proxy_providers) = &conf.proxy_provider {
+        for ext_proxy_provider in ext_proxy_providers {
+            let mut provider = internal::Provider::new();
+            provider.tag = ext_proxy_provider.tag.clone();
+            provider.url = ext_proxy_provider.url.clone();
+            if let Some(ext_path) = &ext_proxy_provider.path {
+                let path = Path::new(ext_path);
+                if path.is_absolute() {
+                    provider.path = path.to_string_lossy().to_string();
+                } else {
+                    let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
+                    provider.path = asset_loc.join(path).to_string_lossy().to_string();
+                }
+            }
+            if let Some(ext_interval) = ext_proxy_provider.interval {
+                provider.interval = ext_interval;
+            }
+            providers.push(provider);
+        }
+    }
+
+    let mut rule_providers = protobuf::RepeatedField::new();
+    if let Some(ext_rule_providers) = &conf.rule_provider {
+        for ext_rule_provider in ext_rule_providers {
+            let mut provider = internal::Provider::new();
+            provider.tag = ext_rule_provider.tag.clone();
+            provider.url = ext_rule_provider.url.clone();
+            if let Some(ext_path) = &ext_rule_provider.path {
+                provider.path = external_rule::asset_path(ext_path);
+            }
+            if let Some(ext_interval) = ext_rule_provider.interval {
+                provider.interval = ext_interval;
+            }
+            provider.format = ext_rule_provider
+                .format
+                .clone()
+                .unwrap_or_else(|| "text".to_string());
+            rule_providers.push(provider);
+        }
+    }
+
     let mut int_router = internal::Router::new();
     let mut rules = protobuf::RepeatedField::new();
+    let geoip_file =
+        external_rule::geoip_file(conf.general.as_ref().and_then(|x| x.geoip_file.as_deref()));
+    let geosite_file = external_rule::geosite_file(
+        conf.general
+            .as_ref()
+            .and_then(|x| x.geosite_file.as_deref()),
+    );
+    let asn_file =
+        external_rule::asn_file(conf.general.as_ref().and_then(|x| x.asn_file.as_deref()));
+    // the defaults of inbounds follow all the other rules
+    let mut inbound_finals = Vec::new();
     if let Some(ext_rules) = conf.rule.as_mut() {
         for ext_rule in ext_rules.iter_mut() {
             let mut rule = internal::Router_Rule::new();
 
             let target_tag = std::mem::take(&mut ext_rule.target);
             rule.target_tag = target_tag;
+            rule.no_resolve = ext_rule.no_resolve;
+
+            if let ("FINAL", Some(ext_inbounds)) =
+                (ext_rule.type_field.as_str(), ext_rule.filter.as_ref())
+            {
+                for ext_inbound in ext_inbounds.split('|') {
+                    rule.inbound_tags.push(ext_inbound.trim().to_string());
+                }
+                inbound_finals.push(rule);
+                continue;
+            }
 
             // handle FINAL rule first
             if ext_rule.type_field == "FINAL" {
@@ -1118,67 +3010,19 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
                 continue;
             }
 
-            // the remaining rules must have a filter
-            let ext_filter = if let Some(f) = ext_rule.filter.as_mut() {
-                std::mem::take(f)
-            } else {
-                continue;
-            };
-            match ext_rule.type_field.as_str() {
-                "IP-CIDR" => {
-                    rule.ip_cidrs.push(ext_filter);
-                }
-                "DOMAIN" => {
-                    let mut domain = internal::Router_Rule_Domain::new();
-                    domain.field_type = internal::Router_Rule_Domain_Type::FULL;
-                    domain.value = ext_filter;
-                    rule.domains.push(domain);
-                }
-                "DOMAIN-KEYWORD" => {
-                    let mut domain = internal::Router_Rule_Domain::new();
-                    domain.field_type = internal::Router_Rule_Domain_Type::PLAIN;
-                    domain.value = ext_filter;
-                    rule.domains.push(domain);
-                }
-                "DOMAIN-SUFFIX" => {
-                    let mut domain = internal::Router_Rule_Domain::new();
-                    domain.field_type = internal::Router_Rule_Domain_Type::DOMAIN;
-                    domain.value = ext_filter;
-                    rule.domains.push(domain);
-                }
-                "GEOIP" => {
-                    let mut mmdb = internal::Router_Rule_Mmdb::new();
-
-                    let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
-                    mmdb.file = asset_loc.join("geo.mmdb").to_string_lossy().to_string();
-                    mmdb.country_code = ext_filter;
-                    rule.mmdbs.push(mmdb)
-                }
-                "EXTERNAL" => match external_rule::add_external_rule(&mut rule, &ext_filter) {
-                    Ok(_) => (),
-                    Err(e) => {
-                        println!("load external rule failed: {}", e);
-                    }
-                },
-                "PORT-RANGE" => {
-                    rule.port_ranges.push(ext_filter);
-                }
-                "NETWORK" => {
-                    rule.networks.push(ext_filter);
-                }
-                "INBOUND-TAG" => {
-                    rule.inbound_tags.push(ext_filter);
-                }
-                _ => {}
-            }
+            to_internal_rule(ext_rule, &mut rule, &geoip_file, &geosite_file, &asn_file);
             rules.push(rule);
         }
     }
+    rules.extend(inbound_finals);
     int_router.rules = rules;
     if let Some(ext_general) = &conf.general {
         if let Some(ext_domain_resolve) = ext_general.routing_domain_resolve {
             int_router.domain_resolve = ext_domain_resolve;
         }
+        if let Some(ext_script) = ext_general.routing_script.as_ref() {
+            int_router.script = external_rule::asset_path(ext_script);
+        }
     }
     let router = protobuf::SingularPtrField::some(int_router);
 
@@ -1194,6 +3038,9 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
                 dns.servers = servers;
             }
         }
+        if let Some(ext_dns_client_subnet) = &ext_general.dns_client_subnet {
+            dns.client_subnet = ext_dns_client_subnet.clone();
+        }
     }
     if let Some(ext_hosts) = &conf.host {
         for (name, static_ips) in ext_hosts.iter() {
@@ -1209,6 +3056,37 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
     if !hosts.is_empty() {
         dns.hosts = hosts;
     }
+    if let Some(ext_dns_rules) = conf.dns_rule.as_mut() {
+        for ext_dns_rule in ext_dns_rules.iter_mut() {
+            // Only the domain conditions apply to DNS rules.
+            match ext_dns_rule.type_field.as_str() {
+                "DOMAIN" | "DOMAIN-SUFFIX" | "DOMAIN-KEYWORD" | "DOMAIN-REGEX" | "GEOSITE"
+                | "EXTERNAL" => (),
+                _ => {
+                    println!("unsupported dns rule type {}", &ext_dns_rule.type_field);
+                    continue;
+                }
+            }
+            let mut ext_rule = Rule {
+                type_field: std::mem::take(&mut ext_dns_rule.type_field),
+                filter: Some(std::mem::take(&mut ext_dns_rule.filter)),
+                ..Default::default()
+            };
+            let mut rule = internal::Router_Rule::new();
+            to_internal_rule(
+                &mut ext_rule,
+                &mut rule,
+                &geoip_file,
+                &geosite_file,
+                &asn_file,
+            );
+            let mut dns_rule = internal::Dns_Rule::new();
+            dns_rule.domains = rule.domains;
+            dns_rule.servers =
+                protobuf::RepeatedField::from_vec(std::mem::take(&mut ext_dns_rule.servers));
+            dns.rules.push(dns_rule);
+        }
+    }
 
     let mut config = internal::Config::new();
     config.log = protobuf::SingularPtrField::some(log);
@@ -1216,6 +3094,8 @@ pub fn to_internal(conf: &mut Config) -> Result<internal::Config> {
     config.outbounds = outbounds;
     config.router = router;
     config.dns = protobuf::SingularPtrField::some(dns);
+    config.providers = providers;
+    config.rule_providers = rule_providers;
 
     Ok(config)
 }
@@ -1234,3 +3114,127 @@ where
     let mut config = from_lines(lines)?;
     to_internal(&mut config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logical_rules() {
+        let conf = "[Rule]\n\
+            AND, ((DOMAIN-SUFFIX, netflix.com), (NETWORK, udp)), Reject\n\
+            OR, ((PORT-RANGE, 80, 443), (NOT, ((DOMAIN-REGEX, ^a{1,2}\\.)))), Direct\n\
+            NOT, (DOMAIN, example.com), Direct\n\
+            FINAL, Direct\n";
+        let config = from_string(conf).unwrap();
+        let rules = &config.router.as_ref().unwrap().rules;
+        // The NOT rule is skipped as its condition isn't in parentheses.
+        assert_eq!(rules.len(), 2);
+
+        assert_eq!(rules[0].target_tag, "Reject");
+        assert_eq!(rules[0].and_rules.len(), 2);
+        assert_eq!(rules[0].and_rules[0].domains[0].value, "netflix.com");
+        assert_eq!(rules[0].and_rules[1].networks[0], "udp");
+
+        assert_eq!(rules[1].or_rules.len(), 2);
+        assert_eq!(rules[1].or_rules[0].port_ranges[0], "80,443");
+        let not_rule = &rules[1].or_rules[1].not_rules[0];
+        assert_eq!(not_rule.domains[0].value, "^a{1,2}\\.");
+    }
+
+    #[test]
+    fn test_no_resolve() {
+        let conf = "[Rule]\n\
+            IP-CIDR, 1.0.0.0/8, Proxy, no-resolve\n\
+            GEOIP, cn, Direct\n\
+            AND, ((GEOIP, us), (NETWORK, tcp)), Proxy, no-resolve\n";
+        let config = from_string(conf).unwrap();
+        let rules = &config.router.as_ref().unwrap().rules;
+        assert!(rules[0].no_resolve);
+        assert!(!rules[1].no_resolve);
+        assert!(rules[2].no_resolve);
+    }
+
+    #[test]
+    fn test_schedule() {
+        let conf = "[Rule]\n\
+            SCHEDULE, Sat, Sun 22:00-07:00, Reject\n\
+            AND, ((DOMAIN-SUFFIX, game.com), (SCHEDULE, Mon-Fri 22:00-07:00)), Reject\n";
+        let config = from_string(conf).unwrap();
+        let rules = &config.router.as_ref().unwrap().rules;
+        assert_eq!(rules[0].schedules[0], "Sat,Sun 22:00-07:00");
+        assert_eq!(rules[0].target_tag, "Reject");
+        assert_eq!(rules[1].and_rules[1].schedules[0], "Mon-Fri 22:00-07:00");
+    }
+
+    #[test]
+    fn test_ip_asn() {
+        let conf = "[General]\n\
+            asn-file = /tmp/asn.mmdb\n\
+            [Rule]\n\
+            IP-ASN, 13335, Proxy\n\
+            IP-ASN, AS15169, Proxy, no-resolve\n";
+        let config = from_string(conf).unwrap();
+        let rules = &config.router.as_ref().unwrap().rules;
+        assert_eq!(rules[0].mmdbs[0].file, "/tmp/asn.mmdb");
+        assert_eq!(rules[0].mmdbs[0].asn, 13335);
+        assert_eq!(rules[1].mmdbs[0].asn, 15169);
+        assert!(rules[1].no_resolve);
+    }
+
+    #[test]
+    fn test_dns_servers() {
+        let conf = "[General]\n\
+            dns-server = 223.5.5.5, tls://8.8.8.8?sni=dns.google&tls-cert-sha256=YWJj=\n\
+            dns-client-subnet = 203.0.113.0/24\n";
+        let config = from_string(conf).unwrap();
+        let dns = config.dns.as_ref().unwrap();
+        assert_eq!(dns.servers[0], "223.5.5.5");
+        assert_eq!(
+            dns.servers[1],
+            "tls://8.8.8.8?sni=dns.google&tls-cert-sha256=YWJj="
+        );
+        assert_eq!(dns.client_subnet, "203.0.113.0/24");
+    }
+
+    #[test]
+    fn test_dns_rules() {
+        let conf = "[General]\n\
+            dns-server = 223.5.5.5\n\
+            [DNS Rule]\n\
+            DOMAIN-SUFFIX, corp.example.com, 10.0.0.53, 10.0.0.54\n\
+            DOMAIN, example.org, https://dns.google/dns-query\n\
+            IP-CIDR, 10.0.0.0/8, 10.0.0.53\n";
+        let config = from_string(conf).unwrap();
+        let rules = &config.dns.as_ref().unwrap().rules;
+        assert_eq!(rules.len(), 2);
+        assert_eq!(
+            rules[0].domains[0].field_type,
+            internal::Router_Rule_Domain_Type::DOMAIN
+        );
+        assert_eq!(rules[0].domains[0].value, "corp.example.com");
+        assert_eq!(rules[0].servers.to_vec(), vec!["10.0.0.53", "10.0.0.54"]);
+        assert_eq!(
+            rules[1].domains[0].field_type,
+            internal::Router_Rule_Domain_Type::FULL
+        );
+        assert_eq!(rules[1].servers[0], "https://dns.google/dns-query");
+    }
+
+    #[test]
+    fn test_inbound_final() {
+        let conf = "[Proxy]\n\
+            Direct = direct\n\
+            Proxy = socks, 127.0.0.1, 1080\n\
+            [Rule]\n\
+            FINAL, Direct, inbound=socks|http\n\
+            DOMAIN, example.com, Direct\n\
+            FINAL, Proxy\n";
+        let config = from_string(conf).unwrap();
+        let rules = &config.router.as_ref().unwrap().rules;
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[1].target_tag, "Direct");
+        assert_eq!(rules[1].inbound_tags.to_vec(), vec!["socks", "http"]);
+        assert_eq!(config.outbounds[0].tag, "Proxy");
+    }
+}
@@ -7,20 +7,37 @@ use anyhow::Result;
 
 use super::{geosite, internal};
 
+// Returns the path of an asset file, relative paths are under ASSET_LOCATION.
+pub fn asset_path(file: &str) -> String {
+    if Path::new(file).is_absolute() {
+        file.to_string()
+    } else {
+        let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
+        asset_loc.join(file).to_string_lossy().to_string()
+    }
+}
+
+// Returns the path of the mmdb file of GEOIP rules, `geo.mmdb` by default.
+pub fn geoip_file(file: Option<&str>) -> String {
+    asset_path(file.unwrap_or("geo.mmdb"))
+}
+
+// Returns the path of the geosite file of GEOSITE rules, `site.dat` by default.
+pub fn geosite_file(file: Option<&str>) -> String {
+    asset_path(file.unwrap_or("site.dat"))
+}
+
+// Returns the path of the mmdb file of IP-ASN rules, `asn.mmdb` by default.
+pub fn asn_file(file: Option<&str>) -> String {
+    asset_path(file.unwrap_or("asn.mmdb"))
+}
+
 pub fn load_file_or_default(filter: &str, default: &str) -> Result<(String, String)> {
     let parts: Vec<&str> = filter.split(':').collect();
     let (file, code) = if parts.len() == 3 {
-        let path = if Path::new(parts[1]).is_absolute() {
-            parts[1].to_string()
-        } else {
-            let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
-            asset_loc.join(parts[1]).to_string_lossy().to_string()
-        };
-        (path, parts[2].to_string())
+        (asset_path(parts[1]), parts[2].to_string())
     } else if parts.len() == 2 {
-        let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
-        let path = asset_loc.join(default).to_string_lossy().to_string();
-        (path, parts[1].to_string())
+        (asset_path(default), parts[1].to_string())
     } else {
         return Err(anyhow!("invalid external rule: {}", filter));
     };
@@ -56,48 +73,98 @@ pub fn add_external_rule(rule: &mut internal::Router_Rule, ext_external: &str) -
                 return Err(anyhow!("load site rule failed: {}", e));
             }
         };
+        add_site_rule(rule, &file, &code)?;
+    }
+    Ok(())
+}
 
-        // Loads SiteGroup objects one by one instead of loading the whole list.
-        let mut reader = BufReader::with_capacity(2048, File::open(&file)?);
-        let mut input = protobuf::CodedInputStream::new(&mut reader);
-        while !input.eof()? {
-            let _ = input.read_raw_byte()?; // skip
-            let mut site_group = input.read_message::<geosite::SiteGroup>()?;
-            if site_group.tag == code.to_uppercase() {
-                for domain in site_group.domain.iter_mut() {
-                    let mut domain_rule = match domain.field_type {
-                        geosite::Domain_Type::Plain => {
-                            let mut d = internal::Router_Rule_Domain::new();
-                            d.field_type = internal::Router_Rule_Domain_Type::PLAIN;
-                            d
-                        }
-                        geosite::Domain_Type::Domain => {
-                            let mut d = internal::Router_Rule_Domain::new();
-                            d.field_type = internal::Router_Rule_Domain_Type::DOMAIN;
-                            d
-                        }
-                        geosite::Domain_Type::Full => {
-                            let mut d = internal::Router_Rule_Domain::new();
-                            d.field_type = internal::Router_Rule_Domain_Type::FULL;
-                            d
-                        }
-                        _ => {
-                            continue;
-                        }
-                    };
-                    let value = std::mem::take(&mut domain.value);
-                    domain_rule.value = value;
-                    rule.domains.push(domain_rule);
+// Adds the domains of a geosite category to the rule, `CODE@ATTR` takes only
+// the domains with the attribute, e.g. `google@cn`.
+pub fn add_site_rule(rule: &mut internal::Router_Rule, file: &str, code: &str) -> Result<()> {
+    let (code, attr) = match code.split_once('@') {
+        Some((code, attr)) => (code, Some(attr)),
+        None => (code, None),
+    };
+
+    // Loads SiteGroup objects one by one instead of loading the whole list.
+    let mut reader = BufReader::with_capacity(2048, File::open(file)?);
+    let mut input = protobuf::CodedInputStream::new(&mut reader);
+    while !input.eof()? {
+        let _ = input.read_raw_byte()?; // skip
+        let mut site_group = input.read_message::<geosite::SiteGroup>()?;
+        if site_group.tag.to_uppercase() != code.to_uppercase() {
+            continue;
+        }
+        let mut n = 0;
+        for domain in site_group.domain.iter_mut() {
+            if let Some(attr) = attr {
+                if !domain.attribute.iter().any(|a| a.key == attr) {
+                    continue;
                 }
-                println!(
-                    "loaded {} domain rules from [{}] for tag [{}]",
-                    rule.domains.len(),
-                    file,
-                    code
-                );
-                break; // assume at most 1 matched tag
             }
+            let mut domain_rule = internal::Router_Rule_Domain::new();
+            domain_rule.field_type = match domain.field_type {
+                geosite::Domain_Type::Plain => internal::Router_Rule_Domain_Type::PLAIN,
+                geosite::Domain_Type::Domain => internal::Router_Rule_Domain_Type::DOMAIN,
+                geosite::Domain_Type::Full => internal::Router_Rule_Domain_Type::FULL,
+                geosite::Domain_Type::Regex => internal::Router_Rule_Domain_Type::REGEX,
+            };
+            domain_rule.value = std::mem::take(&mut domain.value);
+            rule.domains.push(domain_rule);
+            n += 1;
         }
+        println!(
+            "loaded {} domain rules from [{}] for tag [{}]",
+            n, file, site_group.tag
+        );
+        return Ok(()); // assume at most 1 matched tag
+    }
+    Err(anyhow!("tag [{}] not found in [{}]", code, file))
+}
+
+#[cfg(test)]
+mod tests {
+    use protobuf::Message;
+
+    use super::*;
+
+    #[test]
+    fn test_add_site_rule() {
+        let mut list = geosite::SiteGroupList::new();
+        for (tag, values) in [
+            ("CN", vec!["baidu.com", "qq.com"]),
+            ("GOOGLE", vec!["google.com", "google.cn"]),
+        ] {
+            let mut group = geosite::SiteGroup::new();
+            group.tag = tag.to_string();
+            for v in values {
+                let mut domain = geosite::Domain::new();
+                domain.field_type = geosite::Domain_Type::Domain;
+                domain.value = v.to_string();
+                if v.ends_with(".cn") {
+                    let mut attr = geosite::Domain_Attribute::new();
+                    attr.key = "cn".to_string();
+                    domain.attribute.push(attr);
+                }
+                group.domain.push(domain);
+            }
+            list.site_group.push(group);
+        }
+        let file = std::env::temp_dir().join("leaf_test_site.dat");
+        std::fs::write(&file, list.write_to_bytes().unwrap()).unwrap();
+        let file = file.to_string_lossy().to_string();
+
+        let mut rule = internal::Router_Rule::new();
+        add_site_rule(&mut rule, &file, "google").unwrap();
+        assert_eq!(rule.domains.len(), 2);
+
+        let mut rule = internal::Router_Rule::new();
+        add_site_rule(&mut rule, &file, "google@cn").unwrap();
+        assert_eq!(rule.domains.len(), 1);
+        assert_eq!(rule.domains[0].value, "google.cn");
+
+        let mut rule = internal::Router_Rule::new();
+        assert!(add_site_rule(&mut rule, &file, "category-ads").is_err());
+        std::fs::remove_file(&file).unwrap();
     }
-    Ok(())
 }
@@ -28,6 +28,8 @@ pub struct Dns {
     // message fields
     pub servers: ::protobuf::RepeatedField<::std::string::String>,
     pub hosts: ::std::collections::HashMap<::std::string::String, Dns_Ips>,
+    pub rules: ::protobuf::RepeatedField<Dns_Rule>,
+    pub client_subnet: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -57,10 +59,29 @@ impl Dns {
     pub fn get_hosts(&self) -> &::std::collections::HashMap<::std::string::String, Dns_Ips> {
         &self.hosts
     }
+
+    // repeated .Dns.Rule rules = 4;
+
+
+    pub fn get_rules(&self) -> &[Dns_Rule] {
+        &self.rules
+    }
+
+    // string client_subnet = 5;
+
+
+    pub fn get_client_subnet(&self) -> &str {
+        &self.client_subnet
+    }
 }
 
 impl ::protobuf::Message for Dns {
     fn is_initialized(&self) -> bool {
+        for v in &self.rules {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
         true
     }
 
@@ -74,6 +95,12 @@ impl ::protobuf::Message for Dns {
                 3 => {
                     ::protobuf::rt::read_map_into::<::protobuf::types::ProtobufTypeString, ::protobuf::types::ProtobufTypeMessage<Dns_Ips>>(wire_type, is, &mut self.hosts)?;
                 },
+                4 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.rules)?;
+                },
+                5 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.client_subnet)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -90,6 +117,13 @@ impl ::protobuf::Message for Dns {
             my_size += ::protobuf::rt::string_size(1, &value);
         };
         my_size += ::protobuf::rt::compute_map_size::<::protobuf::types::ProtobufTypeString, ::protobuf::types::ProtobufTypeMessage<Dns_Ips>>(3, &self.hosts);
+        for value in &self.rules {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        if !self.client_subnet.is_empty() {
+            my_size += ::protobuf::rt::string_size(5, &self.client_subnet);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -100,6 +134,14 @@ impl ::protobuf::Message for Dns {
             os.write_string(1, &v)?;
         };
         ::protobuf::rt::write_map_with_cached_sizes::<::protobuf::types::ProtobufTypeString, ::protobuf::types::ProtobufTypeMessage<Dns_Ips>>(3, &self.hosts, os)?;
+        for v in &self.rules {
+            os.write_tag(4, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        if !self.client_subnet.is_empty() {
+            os.write_string(5, &self.client_subnet)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -144,6 +186,8 @@ impl ::protobuf::Clear for Dns {
     fn clear(&mut self) {
         self.servers.clear();
         self.hosts.clear();
+        self.rules.clear();
+        self.client_subnet.clear();
         self.unknown_fields.clear();
     }
 }
@@ -271,6 +315,149 @@ impl ::protobuf::reflect::ProtobufValue for Dns_Ips {
     }
 }
 
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct Dns_Rule {
+    // message fields
+    pub domains: ::protobuf::RepeatedField<Router_Rule_Domain>,
+    pub servers: ::protobuf::RepeatedField<::std::string::String>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a Dns_Rule {
+    fn default() -> &'a Dns_Rule {
+        <Dns_Rule as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl Dns_Rule {
+    pub fn new() -> Dns_Rule {
+        ::std::default::Default::default()
+    }
+
+    // repeated .Router.Rule.Domain domains = 1;
+
+
+    pub fn get_domains(&self) -> &[Router_Rule_Domain] {
+        &self.domains
+    }
+
+    // repeated string servers = 2;
+
+
+    pub fn get_servers(&self) -> &[::std::string::String] {
+        &self.servers
+    }
+}
+
+impl ::protobuf::Message for Dns_Rule {
+    fn is_initialized(&self) -> bool {
+        for v in &self.domains {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.domains)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.servers)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        for value in &self.domains {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        for value in &self.servers {
+            my_size += ::protobuf::rt::string_size(2, &value);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        for v in &self.domains {
+            os.write_tag(1, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        for v in &self.servers {
+            os.write_string(2, &v)?;
+        };
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> Dns_Rule {
+        Dns_Rule::new()
+    }
+
+    fn default_instance() -> &'static Dns_Rule {
+        static instance: ::protobuf::rt::LazyV2<Dns_Rule> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(Dns_Rule::new)
+    }
+}
+
+impl ::protobuf::Clear for Dns_Rule {
+    fn clear(&mut self) {
+        self.domains.clear();
+        self.servers.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for Dns_Rule {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
 #[derive(PartialEq,Clone,Default,Debug)]
 pub struct Log {
     // message fields
@@ -531,6 +718,20 @@ pub struct TunInboundSettings {
     pub mtu: i32,
     pub fake_dns_exclude: ::protobuf::RepeatedField<::std::string::String>,
     pub fake_dns_include: ::protobuf::RepeatedField<::std::string::String>,
+    pub ipv6_address: ::std::string::String,
+    pub ipv6_prefixlen: i32,
+    pub icmp_reply: bool,
+    pub dns_hijack: ::protobuf::RepeatedField<::std::string::String>,
+    pub queues: i32,
+    pub fake_ip_cidr: ::std::string::String,
+    pub fake_ipv6_cidr: ::std::string::String,
+    pub fake_ip_pool_size: i32,
+    pub fake_ip_eviction: ::std::string::String,
+    pub bypass_lan_outbound: ::std::string::String,
+    pub post_up: ::std::string::String,
+    pub pre_down: ::std::string::String,
+    pub fake_ipv6: bool,
+    pub fake_ip_ttl: i32,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -609,6 +810,104 @@ impl TunInboundSettings {
     pub fn get_fake_dns_include(&self) -> &[::std::string::String] {
         &self.fake_dns_include
     }
+
+    // string ipv6_address = 10;
+
+
+    pub fn get_ipv6_address(&self) -> &str {
+        &self.ipv6_address
+    }
+
+    // int32 ipv6_prefixlen = 11;
+
+
+    pub fn get_ipv6_prefixlen(&self) -> i32 {
+        self.ipv6_prefixlen
+    }
+
+    // bool icmp_reply = 12;
+
+
+    pub fn get_icmp_reply(&self) -> bool {
+        self.icmp_reply
+    }
+
+    // repeated string dns_hijack = 13;
+
+
+    pub fn get_dns_hijack(&self) -> &[::std::string::String] {
+        &self.dns_hijack
+    }
+
+    // int32 queues = 14;
+
+
+    pub fn get_queues(&self) -> i32 {
+        self.queues
+    }
+
+    // string fake_ip_cidr = 15;
+
+
+    pub fn get_fake_ip_cidr(&self) -> &str {
+        &self.fake_ip_cidr
+    }
+
+    // string fake_ipv6_cidr = 16;
+
+
+    pub fn get_fake_ipv6_cidr(&self) -> &str {
+        &self.fake_ipv6_cidr
+    }
+
+    // int32 fake_ip_pool_size = 17;
+
+
+    pub fn get_fake_ip_pool_size(&self) -> i32 {
+        self.fake_ip_pool_size
+    }
+
+    // string fake_ip_eviction = 18;
+
+
+    pub fn get_fake_ip_eviction(&self) -> &str {
+        &self.fake_ip_eviction
+    }
+
+    // string bypass_lan_outbound = 19;
+
+
+    pub fn get_bypass_lan_outbound(&self) -> &str {
+        &self.bypass_lan_outbound
+    }
+
+    // string post_up = 20;
+
+
+    pub fn get_post_up(&self) -> &str {
+        &self.post_up
+    }
+
+    // string pre_down = 21;
+
+
+    pub fn get_pre_down(&self) -> &str {
+        &self.pre_down
+    }
+
+    // bool fake_ipv6 = 22;
+
+
+    pub fn get_fake_ipv6(&self) -> bool {
+        self.fake_ipv6
+    }
+
+    // int32 fake_ip_ttl = 23;
+
+
+    pub fn get_fake_ip_ttl(&self) -> i32 {
+        self.fake_ip_ttl
+    }
 }
 
 impl ::protobuf::Message for TunInboundSettings {
@@ -659,6 +958,72 @@ impl ::protobuf::Message for TunInboundSettings {
                 8 => {
                     ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.fake_dns_include)?;
                 },
+                10 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.ipv6_address)?;
+                },
+                11 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int32()?;
+                    self.ipv6_prefixlen = tmp;
+                },
+                12 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.icmp_reply = tmp;
+                },
+                13 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.dns_hijack)?;
+                },
+                14 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int32()?;
+                    self.queues = tmp;
+                },
+                15 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.fake_ip_cidr)?;
+                },
+                16 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.fake_ipv6_cidr)?;
+                },
+                17 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int32()?;
+                    self.fake_ip_pool_size = tmp;
+                },
+                18 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.fake_ip_eviction)?;
+                },
+                19 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.bypass_lan_outbound)?;
+                },
+                20 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.post_up)?;
+                },
+                21 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.pre_down)?;
+                },
+                22 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.fake_ipv6 = tmp;
+                },
+                23 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int32()?;
+                    self.fake_ip_ttl = tmp;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -698,9 +1063,51 @@ impl ::protobuf::Message for TunInboundSettings {
         for value in &self.fake_dns_include {
             my_size += ::protobuf::rt::string_size(8, &value);
         };
-        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
-        self.cached_size.set(my_size);
-        my_size
+        if !self.ipv6_address.is_empty() {
+            my_size += ::protobuf::rt::string_size(10, &self.ipv6_address);
+        }
+        if self.ipv6_prefixlen != 0 {
+            my_size += ::protobuf::rt::value_size(11, self.ipv6_prefixlen, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.icmp_reply != false {
+            my_size += 2;
+        }
+        for value in &self.dns_hijack {
+            my_size += ::protobuf::rt::string_size(13, &value);
+        };
+        if self.queues != 0 {
+            my_size += ::protobuf::rt::value_size(14, self.queues, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.fake_ip_cidr.is_empty() {
+            my_size += ::protobuf::rt::string_size(15, &self.fake_ip_cidr);
+        }
+        if !self.fake_ipv6_cidr.is_empty() {
+            my_size += ::protobuf::rt::string_size(16, &self.fake_ipv6_cidr);
+        }
+        if self.fake_ip_pool_size != 0 {
+            my_size += ::protobuf::rt::value_size(17, self.fake_ip_pool_size, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.fake_ip_eviction.is_empty() {
+            my_size += ::protobuf::rt::string_size(18, &self.fake_ip_eviction);
+        }
+        if !self.bypass_lan_outbound.is_empty() {
+            my_size += ::protobuf::rt::string_size(19, &self.bypass_lan_outbound);
+        }
+        if !self.post_up.is_empty() {
+            my_size += ::protobuf::rt::string_size(20, &self.post_up);
+        }
+        if !self.pre_down.is_empty() {
+            my_size += ::protobuf::rt::string_size(21, &self.pre_down);
+        }
+        if self.fake_ipv6 != false {
+            my_size += 3;
+        }
+        if self.fake_ip_ttl != 0 {
+            my_size += ::protobuf::rt::value_size(23, self.fake_ip_ttl, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
@@ -731,6 +1138,48 @@ impl ::protobuf::Message for TunInboundSettings {
         for v in &self.fake_dns_include {
             os.write_string(8, &v)?;
         };
+        if !self.ipv6_address.is_empty() {
+            os.write_string(10, &self.ipv6_address)?;
+        }
+        if self.ipv6_prefixlen != 0 {
+            os.write_int32(11, self.ipv6_prefixlen)?;
+        }
+        if self.icmp_reply != false {
+            os.write_bool(12, self.icmp_reply)?;
+        }
+        for v in &self.dns_hijack {
+            os.write_string(13, &v)?;
+        };
+        if self.queues != 0 {
+            os.write_int32(14, self.queues)?;
+        }
+        if !self.fake_ip_cidr.is_empty() {
+            os.write_string(15, &self.fake_ip_cidr)?;
+        }
+        if !self.fake_ipv6_cidr.is_empty() {
+            os.write_string(16, &self.fake_ipv6_cidr)?;
+        }
+        if self.fake_ip_pool_size != 0 {
+            os.write_int32(17, self.fake_ip_pool_size)?;
+        }
+        if !self.fake_ip_eviction.is_empty() {
+            os.write_string(18, &self.fake_ip_eviction)?;
+        }
+        if !self.bypass_lan_outbound.is_empty() {
+            os.write_string(19, &self.bypass_lan_outbound)?;
+        }
+        if !self.post_up.is_empty() {
+            os.write_string(20, &self.post_up)?;
+        }
+        if !self.pre_down.is_empty() {
+            os.write_string(21, &self.pre_down)?;
+        }
+        if self.fake_ipv6 != false {
+            os.write_bool(22, self.fake_ipv6)?;
+        }
+        if self.fake_ip_ttl != 0 {
+            os.write_int32(23, self.fake_ip_ttl)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -782,6 +1231,20 @@ impl ::protobuf::Clear for TunInboundSettings {
         self.mtu = 0;
         self.fake_dns_exclude.clear();
         self.fake_dns_include.clear();
+        self.ipv6_address.clear();
+        self.ipv6_prefixlen = 0;
+        self.icmp_reply = false;
+        self.dns_hijack.clear();
+        self.queues = 0;
+        self.fake_ip_cidr.clear();
+        self.fake_ipv6_cidr.clear();
+        self.fake_ip_pool_size = 0;
+        self.fake_ip_eviction.clear();
+        self.bypass_lan_outbound.clear();
+        self.post_up.clear();
+        self.pre_down.clear();
+        self.fake_ipv6 = false;
+        self.fake_ip_ttl = 0;
         self.unknown_fields.clear();
     }
 }
@@ -793,42 +1256,42 @@ impl ::protobuf::reflect::ProtobufValue for TunInboundSettings {
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct ShadowsocksInboundSettings {
+pub struct TunnelInboundSettings {
     // message fields
-    pub method: ::std::string::String,
-    pub password: ::std::string::String,
+    pub address: ::std::string::String,
+    pub port: u32,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a ShadowsocksInboundSettings {
-    fn default() -> &'a ShadowsocksInboundSettings {
-        <ShadowsocksInboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a TunnelInboundSettings {
+    fn default() -> &'a TunnelInboundSettings {
+        <TunnelInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl ShadowsocksInboundSettings {
-    pub fn new() -> ShadowsocksInboundSettings {
+impl TunnelInboundSettings {
+    pub fn new() -> TunnelInboundSettings {
         ::std::default::Default::default()
     }
 
-    // string method = 1;
+    // string address = 1;
 
 
-    pub fn get_method(&self) -> &str {
-        &self.method
+    pub fn get_address(&self) -> &str {
+        &self.address
     }
 
-    // string password = 2;
+    // uint32 port = 2;
 
 
-    pub fn get_password(&self) -> &str {
-        &self.password
+    pub fn get_port(&self) -> u32 {
+        self.port
     }
 }
 
-impl ::protobuf::Message for ShadowsocksInboundSettings {
+impl ::protobuf::Message for TunnelInboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -838,10 +1301,14 @@ impl ::protobuf::Message for ShadowsocksInboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.method)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
                 },
                 2 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.password)?;
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -855,11 +1322,11 @@ impl ::protobuf::Message for ShadowsocksInboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.method.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.method);
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
         }
-        if !self.password.is_empty() {
-            my_size += ::protobuf::rt::string_size(2, &self.password);
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -867,11 +1334,11 @@ impl ::protobuf::Message for ShadowsocksInboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.method.is_empty() {
-            os.write_string(1, &self.method)?;
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
         }
-        if !self.password.is_empty() {
-            os.write_string(2, &self.password)?;
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -903,59 +1370,99 @@ impl ::protobuf::Message for ShadowsocksInboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> ShadowsocksInboundSettings {
-        ShadowsocksInboundSettings::new()
+    fn new() -> TunnelInboundSettings {
+        TunnelInboundSettings::new()
     }
 
-    fn default_instance() -> &'static ShadowsocksInboundSettings {
-        static instance: ::protobuf::rt::LazyV2<ShadowsocksInboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(ShadowsocksInboundSettings::new)
+    fn default_instance() -> &'static TunnelInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<TunnelInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(TunnelInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for ShadowsocksInboundSettings {
+impl ::protobuf::Clear for TunnelInboundSettings {
     fn clear(&mut self) {
-        self.method.clear();
-        self.password.clear();
+        self.address.clear();
+        self.port = 0;
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for ShadowsocksInboundSettings {
+impl ::protobuf::reflect::ProtobufValue for TunnelInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct TrojanInboundSettings {
+pub struct DnsInboundSettings {
     // message fields
-    pub passwords: ::protobuf::RepeatedField<::std::string::String>,
+    pub servers: ::protobuf::RepeatedField<::std::string::String>,
+    pub domain_servers: ::std::collections::HashMap<::std::string::String, ::std::string::String>,
+    pub fake_dns: bool,
+    pub fake_dns_exclude: ::protobuf::RepeatedField<::std::string::String>,
+    pub fake_dns_include: ::protobuf::RepeatedField<::std::string::String>,
+    pub fake_ip_cidr: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a TrojanInboundSettings {
-    fn default() -> &'a TrojanInboundSettings {
-        <TrojanInboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a DnsInboundSettings {
+    fn default() -> &'a DnsInboundSettings {
+        <DnsInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl TrojanInboundSettings {
-    pub fn new() -> TrojanInboundSettings {
+impl DnsInboundSettings {
+    pub fn new() -> DnsInboundSettings {
         ::std::default::Default::default()
     }
 
-    // repeated string passwords = 1;
+    // repeated string servers = 1;
 
 
-    pub fn get_passwords(&self) -> &[::std::string::String] {
-        &self.passwords
+    pub fn get_servers(&self) -> &[::std::string::String] {
+        &self.servers
+    }
+
+    // repeated .DnsInboundSettings.DomainServersEntry domain_servers = 2;
+
+
+    pub fn get_domain_servers(&self) -> &::std::collections::HashMap<::std::string::String, ::std::string::String> {
+        &self.domain_servers
+    }
+
+    // bool fake_dns = 3;
+
+
+    pub fn get_fake_dns(&self) -> bool {
+        self.fake_dns
+    }
+
+    // repeated string fake_dns_exclude = 4;
+
+
+    pub fn get_fake_dns_exclude(&self) -> &[::std::string::String] {
+        &self.fake_dns_exclude
+    }
+
+    // repeated string fake_dns_include = 5;
+
+
+    pub fn get_fake_dns_include(&self) -> &[::std::string::String] {
+        &self.fake_dns_include
+    }
+
+    // string fake_ip_cidr = 6;
+
+
+    pub fn get_fake_ip_cidr(&self) -> &str {
+        &self.fake_ip_cidr
     }
 }
 
-impl ::protobuf::Message for TrojanInboundSettings {
+impl ::protobuf::Message for DnsInboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -965,7 +1472,26 @@ impl ::protobuf::Message for TrojanInboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.passwords)?;
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.servers)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_map_into::<::protobuf::types::ProtobufTypeString, ::protobuf::types::ProtobufTypeString>(wire_type, is, &mut self.domain_servers)?;
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.fake_dns = tmp;
+                },
+                4 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.fake_dns_exclude)?;
+                },
+                5 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.fake_dns_include)?;
+                },
+                6 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.fake_ip_cidr)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -979,18 +1505,44 @@ impl ::protobuf::Message for TrojanInboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        for value in &self.passwords {
+        for value in &self.servers {
             my_size += ::protobuf::rt::string_size(1, &value);
         };
+        my_size += ::protobuf::rt::compute_map_size::<::protobuf::types::ProtobufTypeString, ::protobuf::types::ProtobufTypeString>(2, &self.domain_servers);
+        if self.fake_dns != false {
+            my_size += 2;
+        }
+        for value in &self.fake_dns_exclude {
+            my_size += ::protobuf::rt::string_size(4, &value);
+        };
+        for value in &self.fake_dns_include {
+            my_size += ::protobuf::rt::string_size(5, &value);
+        };
+        if !self.fake_ip_cidr.is_empty() {
+            my_size += ::protobuf::rt::string_size(6, &self.fake_ip_cidr);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        for v in &self.passwords {
+        for v in &self.servers {
             os.write_string(1, &v)?;
         };
+        ::protobuf::rt::write_map_with_cached_sizes::<::protobuf::types::ProtobufTypeString, ::protobuf::types::ProtobufTypeString>(2, &self.domain_servers, os)?;
+        if self.fake_dns != false {
+            os.write_bool(3, self.fake_dns)?;
+        }
+        for v in &self.fake_dns_exclude {
+            os.write_string(4, &v)?;
+        };
+        for v in &self.fake_dns_include {
+            os.write_string(5, &v)?;
+        };
+        if !self.fake_ip_cidr.is_empty() {
+            os.write_string(6, &self.fake_ip_cidr)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -1021,59 +1573,69 @@ impl ::protobuf::Message for TrojanInboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> TrojanInboundSettings {
-        TrojanInboundSettings::new()
+    fn new() -> DnsInboundSettings {
+        DnsInboundSettings::new()
     }
 
-    fn default_instance() -> &'static TrojanInboundSettings {
-        static instance: ::protobuf::rt::LazyV2<TrojanInboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(TrojanInboundSettings::new)
+    fn default_instance() -> &'static DnsInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<DnsInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(DnsInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for TrojanInboundSettings {
+impl ::protobuf::Clear for DnsInboundSettings {
     fn clear(&mut self) {
-        self.passwords.clear();
+        self.servers.clear();
+        self.domain_servers.clear();
+        self.fake_dns = false;
+        self.fake_dns_exclude.clear();
+        self.fake_dns_include.clear();
+        self.fake_ip_cidr.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for TrojanInboundSettings {
+impl ::protobuf::reflect::ProtobufValue for DnsInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct WebSocketInboundSettings {
+pub struct SocksInboundSettings {
     // message fields
-    pub path: ::std::string::String,
+    pub users: ::protobuf::RepeatedField<SocksInboundSettings_User>,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a WebSocketInboundSettings {
-    fn default() -> &'a WebSocketInboundSettings {
-        <WebSocketInboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a SocksInboundSettings {
+    fn default() -> &'a SocksInboundSettings {
+        <SocksInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl WebSocketInboundSettings {
-    pub fn new() -> WebSocketInboundSettings {
+impl SocksInboundSettings {
+    pub fn new() -> SocksInboundSettings {
         ::std::default::Default::default()
     }
 
-    // string path = 1;
+    // repeated .SocksInboundSettings.User users = 1;
 
 
-    pub fn get_path(&self) -> &str {
-        &self.path
+    pub fn get_users(&self) -> &[SocksInboundSettings_User] {
+        &self.users
     }
 }
 
-impl ::protobuf::Message for WebSocketInboundSettings {
+impl ::protobuf::Message for SocksInboundSettings {
     fn is_initialized(&self) -> bool {
+        for v in &self.users {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
         true
     }
 
@@ -1082,7 +1644,7 @@ impl ::protobuf::Message for WebSocketInboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.path)?;
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.users)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -1096,18 +1658,21 @@ impl ::protobuf::Message for WebSocketInboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.path.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.path);
-        }
+        for value in &self.users {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.path.is_empty() {
-            os.write_string(1, &self.path)?;
-        }
+        for v in &self.users {
+            os.write_tag(1, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -1138,58 +1703,66 @@ impl ::protobuf::Message for WebSocketInboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> WebSocketInboundSettings {
-        WebSocketInboundSettings::new()
+    fn new() -> SocksInboundSettings {
+        SocksInboundSettings::new()
     }
 
-    fn default_instance() -> &'static WebSocketInboundSettings {
-        static instance: ::protobuf::rt::LazyV2<WebSocketInboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(WebSocketInboundSettings::new)
+    fn default_instance() -> &'static SocksInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<SocksInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(SocksInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for WebSocketInboundSettings {
+impl ::protobuf::Clear for SocksInboundSettings {
     fn clear(&mut self) {
-        self.path.clear();
+        self.users.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for WebSocketInboundSettings {
+impl ::protobuf::reflect::ProtobufValue for SocksInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct AMuxInboundSettings {
+pub struct SocksInboundSettings_User {
     // message fields
-    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    pub username: ::std::string::String,
+    pub password: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a AMuxInboundSettings {
-    fn default() -> &'a AMuxInboundSettings {
-        <AMuxInboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a SocksInboundSettings_User {
+    fn default() -> &'a SocksInboundSettings_User {
+        <SocksInboundSettings_User as ::protobuf::Message>::default_instance()
     }
 }
 
-impl AMuxInboundSettings {
-    pub fn new() -> AMuxInboundSettings {
+impl SocksInboundSettings_User {
+    pub fn new() -> SocksInboundSettings_User {
         ::std::default::Default::default()
     }
 
-    // repeated string actors = 1;
+    // string username = 1;
 
 
-    pub fn get_actors(&self) -> &[::std::string::String] {
-        &self.actors
+    pub fn get_username(&self) -> &str {
+        &self.username
+    }
+
+    // string password = 2;
+
+
+    pub fn get_password(&self) -> &str {
+        &self.password
     }
 }
 
-impl ::protobuf::Message for AMuxInboundSettings {
+impl ::protobuf::Message for SocksInboundSettings_User {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -1199,7 +1772,10 @@ impl ::protobuf::Message for AMuxInboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.username)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.password)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -1213,18 +1789,24 @@ impl ::protobuf::Message for AMuxInboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        for value in &self.actors {
-            my_size += ::protobuf::rt::string_size(1, &value);
-        };
+        if !self.username.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.username);
+        }
+        if !self.password.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.password);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        for v in &self.actors {
-            os.write_string(1, &v)?;
-        };
+        if !self.username.is_empty() {
+            os.write_string(1, &self.username)?;
+        }
+        if !self.password.is_empty() {
+            os.write_string(2, &self.password)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -1255,66 +1837,67 @@ impl ::protobuf::Message for AMuxInboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> AMuxInboundSettings {
-        AMuxInboundSettings::new()
+    fn new() -> SocksInboundSettings_User {
+        SocksInboundSettings_User::new()
     }
 
-    fn default_instance() -> &'static AMuxInboundSettings {
-        static instance: ::protobuf::rt::LazyV2<AMuxInboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(AMuxInboundSettings::new)
+    fn default_instance() -> &'static SocksInboundSettings_User {
+        static instance: ::protobuf::rt::LazyV2<SocksInboundSettings_User> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(SocksInboundSettings_User::new)
     }
 }
 
-impl ::protobuf::Clear for AMuxInboundSettings {
+impl ::protobuf::Clear for SocksInboundSettings_User {
     fn clear(&mut self) {
-        self.actors.clear();
+        self.username.clear();
+        self.password.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for AMuxInboundSettings {
+impl ::protobuf::reflect::ProtobufValue for SocksInboundSettings_User {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct QuicInboundSettings {
+pub struct ShadowsocksInboundSettings {
     // message fields
-    pub certificate: ::std::string::String,
-    pub certificate_key: ::std::string::String,
+    pub method: ::std::string::String,
+    pub password: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a QuicInboundSettings {
-    fn default() -> &'a QuicInboundSettings {
-        <QuicInboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a ShadowsocksInboundSettings {
+    fn default() -> &'a ShadowsocksInboundSettings {
+        <ShadowsocksInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl QuicInboundSettings {
-    pub fn new() -> QuicInboundSettings {
+impl ShadowsocksInboundSettings {
+    pub fn new() -> ShadowsocksInboundSettings {
         ::std::default::Default::default()
     }
 
-    // string certificate = 1;
+    // string method = 1;
 
 
-    pub fn get_certificate(&self) -> &str {
-        &self.certificate
+    pub fn get_method(&self) -> &str {
+        &self.method
     }
 
-    // string certificate_key = 2;
+    // string password = 2;
 
 
-    pub fn get_certificate_key(&self) -> &str {
-        &self.certificate_key
+    pub fn get_password(&self) -> &str {
+        &self.password
     }
 }
 
-impl ::protobuf::Message for QuicInboundSettings {
+impl ::protobuf::Message for ShadowsocksInboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -1324,10 +1907,10 @@ impl ::protobuf::Message for QuicInboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.certificate)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.method)?;
                 },
                 2 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.certificate_key)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.password)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -1341,11 +1924,11 @@ impl ::protobuf::Message for QuicInboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.certificate.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.certificate);
+        if !self.method.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.method);
         }
-        if !self.certificate_key.is_empty() {
-            my_size += ::protobuf::rt::string_size(2, &self.certificate_key);
+        if !self.password.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.password);
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -1353,11 +1936,11 @@ impl ::protobuf::Message for QuicInboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.certificate.is_empty() {
-            os.write_string(1, &self.certificate)?;
+        if !self.method.is_empty() {
+            os.write_string(1, &self.method)?;
         }
-        if !self.certificate_key.is_empty() {
-            os.write_string(2, &self.certificate_key)?;
+        if !self.password.is_empty() {
+            os.write_string(2, &self.password)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -1389,67 +1972,67 @@ impl ::protobuf::Message for QuicInboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> QuicInboundSettings {
-        QuicInboundSettings::new()
+    fn new() -> ShadowsocksInboundSettings {
+        ShadowsocksInboundSettings::new()
     }
 
-    fn default_instance() -> &'static QuicInboundSettings {
-        static instance: ::protobuf::rt::LazyV2<QuicInboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(QuicInboundSettings::new)
+    fn default_instance() -> &'static ShadowsocksInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<ShadowsocksInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(ShadowsocksInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for QuicInboundSettings {
+impl ::protobuf::Clear for ShadowsocksInboundSettings {
     fn clear(&mut self) {
-        self.certificate.clear();
-        self.certificate_key.clear();
+        self.method.clear();
+        self.password.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for QuicInboundSettings {
+impl ::protobuf::reflect::ProtobufValue for ShadowsocksInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct TlsInboundSettings {
+pub struct TrojanInboundSettings {
     // message fields
-    pub certificate: ::std::string::String,
-    pub certificate_key: ::std::string::String,
+    pub passwords: ::protobuf::RepeatedField<::std::string::String>,
+    pub fallback: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a TlsInboundSettings {
-    fn default() -> &'a TlsInboundSettings {
-        <TlsInboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a TrojanInboundSettings {
+    fn default() -> &'a TrojanInboundSettings {
+        <TrojanInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl TlsInboundSettings {
-    pub fn new() -> TlsInboundSettings {
+impl TrojanInboundSettings {
+    pub fn new() -> TrojanInboundSettings {
         ::std::default::Default::default()
     }
 
-    // string certificate = 1;
+    // repeated string passwords = 1;
 
 
-    pub fn get_certificate(&self) -> &str {
-        &self.certificate
+    pub fn get_passwords(&self) -> &[::std::string::String] {
+        &self.passwords
     }
 
-    // string certificate_key = 2;
+    // string fallback = 2;
 
 
-    pub fn get_certificate_key(&self) -> &str {
-        &self.certificate_key
+    pub fn get_fallback(&self) -> &str {
+        &self.fallback
     }
 }
 
-impl ::protobuf::Message for TlsInboundSettings {
+impl ::protobuf::Message for TrojanInboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -1459,10 +2042,10 @@ impl ::protobuf::Message for TlsInboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.certificate)?;
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.passwords)?;
                 },
                 2 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.certificate_key)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.fallback)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -1476,11 +2059,11 @@ impl ::protobuf::Message for TlsInboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.certificate.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.certificate);
-        }
-        if !self.certificate_key.is_empty() {
-            my_size += ::protobuf::rt::string_size(2, &self.certificate_key);
+        for value in &self.passwords {
+            my_size += ::protobuf::rt::string_size(1, &value);
+        };
+        if !self.fallback.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.fallback);
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -1488,11 +2071,11 @@ impl ::protobuf::Message for TlsInboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.certificate.is_empty() {
-            os.write_string(1, &self.certificate)?;
-        }
-        if !self.certificate_key.is_empty() {
-            os.write_string(2, &self.certificate_key)?;
+        for v in &self.passwords {
+            os.write_string(1, &v)?;
+        };
+        if !self.fallback.is_empty() {
+            os.write_string(2, &self.fallback)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -1524,59 +2107,59 @@ impl ::protobuf::Message for TlsInboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> TlsInboundSettings {
-        TlsInboundSettings::new()
+    fn new() -> TrojanInboundSettings {
+        TrojanInboundSettings::new()
     }
 
-    fn default_instance() -> &'static TlsInboundSettings {
-        static instance: ::protobuf::rt::LazyV2<TlsInboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(TlsInboundSettings::new)
+    fn default_instance() -> &'static TrojanInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<TrojanInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(TrojanInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for TlsInboundSettings {
+impl ::protobuf::Clear for TrojanInboundSettings {
     fn clear(&mut self) {
-        self.certificate.clear();
-        self.certificate_key.clear();
+        self.passwords.clear();
+        self.fallback.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for TlsInboundSettings {
+impl ::protobuf::reflect::ProtobufValue for TrojanInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct ChainInboundSettings {
+pub struct VLessInboundSettings {
     // message fields
-    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    pub uuids: ::protobuf::RepeatedField<::std::string::String>,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a ChainInboundSettings {
-    fn default() -> &'a ChainInboundSettings {
-        <ChainInboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a VLessInboundSettings {
+    fn default() -> &'a VLessInboundSettings {
+        <VLessInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl ChainInboundSettings {
-    pub fn new() -> ChainInboundSettings {
+impl VLessInboundSettings {
+    pub fn new() -> VLessInboundSettings {
         ::std::default::Default::default()
     }
 
-    // repeated string actors = 1;
+    // repeated string uuids = 1;
 
 
-    pub fn get_actors(&self) -> &[::std::string::String] {
-        &self.actors
+    pub fn get_uuids(&self) -> &[::std::string::String] {
+        &self.uuids
     }
 }
 
-impl ::protobuf::Message for ChainInboundSettings {
+impl ::protobuf::Message for VLessInboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -1586,7 +2169,7 @@ impl ::protobuf::Message for ChainInboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.uuids)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -1600,7 +2183,7 @@ impl ::protobuf::Message for ChainInboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        for value in &self.actors {
+        for value in &self.uuids {
             my_size += ::protobuf::rt::string_size(1, &value);
         };
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
@@ -1609,7 +2192,7 @@ impl ::protobuf::Message for ChainInboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        for v in &self.actors {
+        for v in &self.uuids {
             os.write_string(1, &v)?;
         };
         os.write_unknown_fields(self.get_unknown_fields())?;
@@ -1642,90 +2225,58 @@ impl ::protobuf::Message for ChainInboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> ChainInboundSettings {
-        ChainInboundSettings::new()
+    fn new() -> VLessInboundSettings {
+        VLessInboundSettings::new()
     }
 
-    fn default_instance() -> &'static ChainInboundSettings {
-        static instance: ::protobuf::rt::LazyV2<ChainInboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(ChainInboundSettings::new)
+    fn default_instance() -> &'static VLessInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<VLessInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(VLessInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for ChainInboundSettings {
+impl ::protobuf::Clear for VLessInboundSettings {
     fn clear(&mut self) {
-        self.actors.clear();
+        self.uuids.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for ChainInboundSettings {
+impl ::protobuf::reflect::ProtobufValue for VLessInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct Inbound {
+pub struct VMessInboundSettings {
     // message fields
-    pub tag: ::std::string::String,
-    pub protocol: ::std::string::String,
-    pub address: ::std::string::String,
-    pub port: u32,
-    pub settings: ::std::vec::Vec<u8>,
+    pub uuids: ::protobuf::RepeatedField<::std::string::String>,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a Inbound {
-    fn default() -> &'a Inbound {
-        <Inbound as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a VMessInboundSettings {
+    fn default() -> &'a VMessInboundSettings {
+        <VMessInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl Inbound {
-    pub fn new() -> Inbound {
+impl VMessInboundSettings {
+    pub fn new() -> VMessInboundSettings {
         ::std::default::Default::default()
     }
 
-    // string tag = 1;
-
-
-    pub fn get_tag(&self) -> &str {
-        &self.tag
-    }
-
-    // string protocol = 2;
-
-
-    pub fn get_protocol(&self) -> &str {
-        &self.protocol
-    }
-
-    // string address = 3;
-
-
-    pub fn get_address(&self) -> &str {
-        &self.address
-    }
-
-    // uint32 port = 4;
-
-
-    pub fn get_port(&self) -> u32 {
-        self.port
-    }
-
-    // bytes settings = 5;
+    // repeated string uuids = 1;
 
 
-    pub fn get_settings(&self) -> &[u8] {
-        &self.settings
+    pub fn get_uuids(&self) -> &[::std::string::String] {
+        &self.uuids
     }
 }
 
-impl ::protobuf::Message for Inbound {
+impl ::protobuf::Message for VMessInboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -1735,23 +2286,7 @@ impl ::protobuf::Message for Inbound {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.tag)?;
-                },
-                2 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.protocol)?;
-                },
-                3 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
-                },
-                4 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_uint32()?;
-                    self.port = tmp;
-                },
-                5 => {
-                    ::protobuf::rt::read_singular_proto3_bytes_into(wire_type, is, &mut self.settings)?;
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.uuids)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -1765,42 +2300,18 @@ impl ::protobuf::Message for Inbound {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.tag.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.tag);
-        }
-        if !self.protocol.is_empty() {
-            my_size += ::protobuf::rt::string_size(2, &self.protocol);
-        }
-        if !self.address.is_empty() {
-            my_size += ::protobuf::rt::string_size(3, &self.address);
-        }
-        if self.port != 0 {
-            my_size += ::protobuf::rt::value_size(4, self.port, ::protobuf::wire_format::WireTypeVarint);
-        }
-        if !self.settings.is_empty() {
-            my_size += ::protobuf::rt::bytes_size(5, &self.settings);
-        }
+        for value in &self.uuids {
+            my_size += ::protobuf::rt::string_size(1, &value);
+        };
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.tag.is_empty() {
-            os.write_string(1, &self.tag)?;
-        }
-        if !self.protocol.is_empty() {
-            os.write_string(2, &self.protocol)?;
-        }
-        if !self.address.is_empty() {
-            os.write_string(3, &self.address)?;
-        }
-        if self.port != 0 {
-            os.write_uint32(4, self.port)?;
-        }
-        if !self.settings.is_empty() {
-            os.write_bytes(5, &self.settings)?;
-        }
+        for v in &self.uuids {
+            os.write_string(1, &v)?;
+        };
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -1831,70 +2342,66 @@ impl ::protobuf::Message for Inbound {
         Self::descriptor_static()
     }
 
-    fn new() -> Inbound {
-        Inbound::new()
+    fn new() -> VMessInboundSettings {
+        VMessInboundSettings::new()
     }
 
-    fn default_instance() -> &'static Inbound {
-        static instance: ::protobuf::rt::LazyV2<Inbound> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(Inbound::new)
+    fn default_instance() -> &'static VMessInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<VMessInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(VMessInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for Inbound {
+impl ::protobuf::Clear for VMessInboundSettings {
     fn clear(&mut self) {
-        self.tag.clear();
-        self.protocol.clear();
-        self.address.clear();
-        self.port = 0;
-        self.settings.clear();
+        self.uuids.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for Inbound {
+impl ::protobuf::reflect::ProtobufValue for VMessInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct RedirectOutboundSettings {
+pub struct WebSocketInboundSettings {
     // message fields
-    pub address: ::std::string::String,
-    pub port: u32,
+    pub path: ::std::string::String,
+    pub early_data_header_name: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a RedirectOutboundSettings {
-    fn default() -> &'a RedirectOutboundSettings {
-        <RedirectOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a WebSocketInboundSettings {
+    fn default() -> &'a WebSocketInboundSettings {
+        <WebSocketInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl RedirectOutboundSettings {
-    pub fn new() -> RedirectOutboundSettings {
+impl WebSocketInboundSettings {
+    pub fn new() -> WebSocketInboundSettings {
         ::std::default::Default::default()
     }
 
-    // string address = 1;
+    // string path = 1;
 
 
-    pub fn get_address(&self) -> &str {
-        &self.address
+    pub fn get_path(&self) -> &str {
+        &self.path
     }
 
-    // uint32 port = 2;
+    // string early_data_header_name = 2;
 
 
-    pub fn get_port(&self) -> u32 {
-        self.port
+    pub fn get_early_data_header_name(&self) -> &str {
+        &self.early_data_header_name
     }
 }
 
-impl ::protobuf::Message for RedirectOutboundSettings {
+impl ::protobuf::Message for WebSocketInboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -1904,14 +2411,10 @@ impl ::protobuf::Message for RedirectOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.path)?;
                 },
                 2 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_uint32()?;
-                    self.port = tmp;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.early_data_header_name)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -1925,11 +2428,11 @@ impl ::protobuf::Message for RedirectOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.address.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.address);
+        if !self.path.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.path);
         }
-        if self.port != 0 {
-            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        if !self.early_data_header_name.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.early_data_header_name);
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -1937,11 +2440,11 @@ impl ::protobuf::Message for RedirectOutboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.address.is_empty() {
-            os.write_string(1, &self.address)?;
+        if !self.path.is_empty() {
+            os.write_string(1, &self.path)?;
         }
-        if self.port != 0 {
-            os.write_uint32(2, self.port)?;
+        if !self.early_data_header_name.is_empty() {
+            os.write_string(2, &self.early_data_header_name)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -1973,67 +2476,59 @@ impl ::protobuf::Message for RedirectOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> RedirectOutboundSettings {
-        RedirectOutboundSettings::new()
+    fn new() -> WebSocketInboundSettings {
+        WebSocketInboundSettings::new()
     }
 
-    fn default_instance() -> &'static RedirectOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<RedirectOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(RedirectOutboundSettings::new)
+    fn default_instance() -> &'static WebSocketInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<WebSocketInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(WebSocketInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for RedirectOutboundSettings {
+impl ::protobuf::Clear for WebSocketInboundSettings {
     fn clear(&mut self) {
-        self.address.clear();
-        self.port = 0;
+        self.path.clear();
+        self.early_data_header_name.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for RedirectOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for WebSocketInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct SocksOutboundSettings {
+pub struct AMuxInboundSettings {
     // message fields
-    pub address: ::std::string::String,
-    pub port: u32,
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a SocksOutboundSettings {
-    fn default() -> &'a SocksOutboundSettings {
-        <SocksOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a AMuxInboundSettings {
+    fn default() -> &'a AMuxInboundSettings {
+        <AMuxInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl SocksOutboundSettings {
-    pub fn new() -> SocksOutboundSettings {
+impl AMuxInboundSettings {
+    pub fn new() -> AMuxInboundSettings {
         ::std::default::Default::default()
     }
 
-    // string address = 1;
-
-
-    pub fn get_address(&self) -> &str {
-        &self.address
-    }
-
-    // uint32 port = 2;
+    // repeated string actors = 1;
 
 
-    pub fn get_port(&self) -> u32 {
-        self.port
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
     }
 }
 
-impl ::protobuf::Message for SocksOutboundSettings {
+impl ::protobuf::Message for AMuxInboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -2043,14 +2538,7 @@ impl ::protobuf::Message for SocksOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
-                },
-                2 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_uint32()?;
-                    self.port = tmp;
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -2064,24 +2552,18 @@ impl ::protobuf::Message for SocksOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.address.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.address);
-        }
-        if self.port != 0 {
-            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
-        }
+        for value in &self.actors {
+            my_size += ::protobuf::rt::string_size(1, &value);
+        };
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.address.is_empty() {
-            os.write_string(1, &self.address)?;
-        }
-        if self.port != 0 {
-            os.write_uint32(2, self.port)?;
-        }
+        for v in &self.actors {
+            os.write_string(1, &v)?;
+        };
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -2112,83 +2594,58 @@ impl ::protobuf::Message for SocksOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> SocksOutboundSettings {
-        SocksOutboundSettings::new()
+    fn new() -> AMuxInboundSettings {
+        AMuxInboundSettings::new()
     }
 
-    fn default_instance() -> &'static SocksOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<SocksOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(SocksOutboundSettings::new)
+    fn default_instance() -> &'static AMuxInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<AMuxInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(AMuxInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for SocksOutboundSettings {
+impl ::protobuf::Clear for AMuxInboundSettings {
     fn clear(&mut self) {
-        self.address.clear();
-        self.port = 0;
+        self.actors.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for SocksOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for AMuxInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct ShadowsocksOutboundSettings {
+pub struct MuxInboundSettings {
     // message fields
-    pub address: ::std::string::String,
-    pub port: u32,
-    pub method: ::std::string::String,
-    pub password: ::std::string::String,
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a ShadowsocksOutboundSettings {
-    fn default() -> &'a ShadowsocksOutboundSettings {
-        <ShadowsocksOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a MuxInboundSettings {
+    fn default() -> &'a MuxInboundSettings {
+        <MuxInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl ShadowsocksOutboundSettings {
-    pub fn new() -> ShadowsocksOutboundSettings {
+impl MuxInboundSettings {
+    pub fn new() -> MuxInboundSettings {
         ::std::default::Default::default()
     }
 
-    // string address = 1;
-
-
-    pub fn get_address(&self) -> &str {
-        &self.address
-    }
-
-    // uint32 port = 2;
-
-
-    pub fn get_port(&self) -> u32 {
-        self.port
-    }
-
-    // string method = 3;
-
-
-    pub fn get_method(&self) -> &str {
-        &self.method
-    }
-
-    // string password = 4;
+    // repeated string actors = 1;
 
 
-    pub fn get_password(&self) -> &str {
-        &self.password
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
     }
 }
 
-impl ::protobuf::Message for ShadowsocksOutboundSettings {
+impl ::protobuf::Message for MuxInboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -2198,20 +2655,7 @@ impl ::protobuf::Message for ShadowsocksOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
-                },
-                2 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_uint32()?;
-                    self.port = tmp;
-                },
-                3 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.method)?;
-                },
-                4 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.password)?;
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -2225,36 +2669,18 @@ impl ::protobuf::Message for ShadowsocksOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.address.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.address);
-        }
-        if self.port != 0 {
-            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
-        }
-        if !self.method.is_empty() {
-            my_size += ::protobuf::rt::string_size(3, &self.method);
-        }
-        if !self.password.is_empty() {
-            my_size += ::protobuf::rt::string_size(4, &self.password);
-        }
+        for value in &self.actors {
+            my_size += ::protobuf::rt::string_size(1, &value);
+        };
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.address.is_empty() {
-            os.write_string(1, &self.address)?;
-        }
-        if self.port != 0 {
-            os.write_uint32(2, self.port)?;
-        }
-        if !self.method.is_empty() {
-            os.write_string(3, &self.method)?;
-        }
-        if !self.password.is_empty() {
-            os.write_string(4, &self.password)?;
-        }
+        for v in &self.actors {
+            os.write_string(1, &v)?;
+        };
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -2285,77 +2711,74 @@ impl ::protobuf::Message for ShadowsocksOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> ShadowsocksOutboundSettings {
-        ShadowsocksOutboundSettings::new()
+    fn new() -> MuxInboundSettings {
+        MuxInboundSettings::new()
     }
 
-    fn default_instance() -> &'static ShadowsocksOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<ShadowsocksOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(ShadowsocksOutboundSettings::new)
+    fn default_instance() -> &'static MuxInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<MuxInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(MuxInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for ShadowsocksOutboundSettings {
+impl ::protobuf::Clear for MuxInboundSettings {
     fn clear(&mut self) {
-        self.address.clear();
-        self.port = 0;
-        self.method.clear();
-        self.password.clear();
+        self.actors.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for ShadowsocksOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for MuxInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct TrojanOutboundSettings {
+pub struct QuicInboundSettings {
     // message fields
-    pub address: ::std::string::String,
-    pub port: u32,
-    pub password: ::std::string::String,
+    pub certificate: ::std::string::String,
+    pub certificate_key: ::std::string::String,
+    pub zero_rtt: bool,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a TrojanOutboundSettings {
-    fn default() -> &'a TrojanOutboundSettings {
-        <TrojanOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a QuicInboundSettings {
+    fn default() -> &'a QuicInboundSettings {
+        <QuicInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl TrojanOutboundSettings {
-    pub fn new() -> TrojanOutboundSettings {
-        ::std::default::Default::default()
+impl QuicInboundSettings {
+    pub fn new() -> QuicInboundSettings {
+        ::std::default::Default::default()
     }
 
-    // string address = 1;
+    // string certificate = 1;
 
 
-    pub fn get_address(&self) -> &str {
-        &self.address
+    pub fn get_certificate(&self) -> &str {
+        &self.certificate
     }
 
-    // uint32 port = 2;
+    // string certificate_key = 2;
 
 
-    pub fn get_port(&self) -> u32 {
-        self.port
+    pub fn get_certificate_key(&self) -> &str {
+        &self.certificate_key
     }
 
-    // string password = 3;
+    // bool zero_rtt = 3;
 
 
-    pub fn get_password(&self) -> &str {
-        &self.password
+    pub fn get_zero_rtt(&self) -> bool {
+        self.zero_rtt
     }
 }
 
-impl ::protobuf::Message for TrojanOutboundSettings {
+impl ::protobuf::Message for QuicInboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -2365,17 +2788,17 @@ impl ::protobuf::Message for TrojanOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.certificate)?;
                 },
                 2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.certificate_key)?;
+                },
+                3 => {
                     if wire_type != ::protobuf::wire_format::WireTypeVarint {
                         return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
                     }
-                    let tmp = is.read_uint32()?;
-                    self.port = tmp;
-                },
-                3 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.password)?;
+                    let tmp = is.read_bool()?;
+                    self.zero_rtt = tmp;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -2389,14 +2812,14 @@ impl ::protobuf::Message for TrojanOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.address.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.address);
+        if !self.certificate.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.certificate);
         }
-        if self.port != 0 {
-            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        if !self.certificate_key.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.certificate_key);
         }
-        if !self.password.is_empty() {
-            my_size += ::protobuf::rt::string_size(3, &self.password);
+        if self.zero_rtt != false {
+            my_size += 2;
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -2404,14 +2827,14 @@ impl ::protobuf::Message for TrojanOutboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.address.is_empty() {
-            os.write_string(1, &self.address)?;
+        if !self.certificate.is_empty() {
+            os.write_string(1, &self.certificate)?;
         }
-        if self.port != 0 {
-            os.write_uint32(2, self.port)?;
+        if !self.certificate_key.is_empty() {
+            os.write_string(2, &self.certificate_key)?;
         }
-        if !self.password.is_empty() {
-            os.write_string(3, &self.password)?;
+        if self.zero_rtt != false {
+            os.write_bool(3, self.zero_rtt)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -2443,76 +2866,68 @@ impl ::protobuf::Message for TrojanOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> TrojanOutboundSettings {
-        TrojanOutboundSettings::new()
+    fn new() -> QuicInboundSettings {
+        QuicInboundSettings::new()
     }
 
-    fn default_instance() -> &'static TrojanOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<TrojanOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(TrojanOutboundSettings::new)
+    fn default_instance() -> &'static QuicInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<QuicInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(QuicInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for TrojanOutboundSettings {
+impl ::protobuf::Clear for QuicInboundSettings {
     fn clear(&mut self) {
-        self.address.clear();
-        self.port = 0;
-        self.password.clear();
+        self.certificate.clear();
+        self.certificate_key.clear();
+        self.zero_rtt = false;
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for TrojanOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for QuicInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct TlsOutboundSettings {
+pub struct TlsInboundSettings {
     // message fields
-    pub server_name: ::std::string::String,
-    pub alpn: ::protobuf::RepeatedField<::std::string::String>,
     pub certificate: ::std::string::String,
+    pub certificate_key: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a TlsOutboundSettings {
-    fn default() -> &'a TlsOutboundSettings {
-        <TlsOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a TlsInboundSettings {
+    fn default() -> &'a TlsInboundSettings {
+        <TlsInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl TlsOutboundSettings {
-    pub fn new() -> TlsOutboundSettings {
+impl TlsInboundSettings {
+    pub fn new() -> TlsInboundSettings {
         ::std::default::Default::default()
     }
 
-    // string server_name = 1;
-
-
-    pub fn get_server_name(&self) -> &str {
-        &self.server_name
-    }
-
-    // repeated string alpn = 2;
+    // string certificate = 1;
 
 
-    pub fn get_alpn(&self) -> &[::std::string::String] {
-        &self.alpn
+    pub fn get_certificate(&self) -> &str {
+        &self.certificate
     }
 
-    // string certificate = 3;
+    // string certificate_key = 2;
 
 
-    pub fn get_certificate(&self) -> &str {
-        &self.certificate
+    pub fn get_certificate_key(&self) -> &str {
+        &self.certificate_key
     }
 }
 
-impl ::protobuf::Message for TlsOutboundSettings {
+impl ::protobuf::Message for TlsInboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -2522,13 +2937,10 @@ impl ::protobuf::Message for TlsOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.server_name)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.certificate)?;
                 },
                 2 => {
-                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.alpn)?;
-                },
-                3 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.certificate)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.certificate_key)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -2542,14 +2954,11 @@ impl ::protobuf::Message for TlsOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.server_name.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.server_name);
-        }
-        for value in &self.alpn {
-            my_size += ::protobuf::rt::string_size(2, &value);
-        };
         if !self.certificate.is_empty() {
-            my_size += ::protobuf::rt::string_size(3, &self.certificate);
+            my_size += ::protobuf::rt::string_size(1, &self.certificate);
+        }
+        if !self.certificate_key.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.certificate_key);
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -2557,14 +2966,11 @@ impl ::protobuf::Message for TlsOutboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.server_name.is_empty() {
-            os.write_string(1, &self.server_name)?;
-        }
-        for v in &self.alpn {
-            os.write_string(2, &v)?;
-        };
         if !self.certificate.is_empty() {
-            os.write_string(3, &self.certificate)?;
+            os.write_string(1, &self.certificate)?;
+        }
+        if !self.certificate_key.is_empty() {
+            os.write_string(2, &self.certificate_key)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -2596,68 +3002,59 @@ impl ::protobuf::Message for TlsOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> TlsOutboundSettings {
-        TlsOutboundSettings::new()
+    fn new() -> TlsInboundSettings {
+        TlsInboundSettings::new()
     }
 
-    fn default_instance() -> &'static TlsOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<TlsOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(TlsOutboundSettings::new)
+    fn default_instance() -> &'static TlsInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<TlsInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(TlsInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for TlsOutboundSettings {
+impl ::protobuf::Clear for TlsInboundSettings {
     fn clear(&mut self) {
-        self.server_name.clear();
-        self.alpn.clear();
         self.certificate.clear();
+        self.certificate_key.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for TlsOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for TlsInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct WebSocketOutboundSettings {
+pub struct ChainInboundSettings {
     // message fields
-    pub path: ::std::string::String,
-    pub headers: ::std::collections::HashMap<::std::string::String, ::std::string::String>,
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a WebSocketOutboundSettings {
-    fn default() -> &'a WebSocketOutboundSettings {
-        <WebSocketOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a ChainInboundSettings {
+    fn default() -> &'a ChainInboundSettings {
+        <ChainInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl WebSocketOutboundSettings {
-    pub fn new() -> WebSocketOutboundSettings {
+impl ChainInboundSettings {
+    pub fn new() -> ChainInboundSettings {
         ::std::default::Default::default()
     }
 
-    // string path = 1;
-
-
-    pub fn get_path(&self) -> &str {
-        &self.path
-    }
-
-    // repeated .WebSocketOutboundSettings.HeadersEntry headers = 2;
+    // repeated string actors = 1;
 
 
-    pub fn get_headers(&self) -> &::std::collections::HashMap<::std::string::String, ::std::string::String> {
-        &self.headers
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
     }
 }
 
-impl ::protobuf::Message for WebSocketOutboundSettings {
+impl ::protobuf::Message for ChainInboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -2667,10 +3064,7 @@ impl ::protobuf::Message for WebSocketOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.path)?;
-                },
-                2 => {
-                    ::protobuf::rt::read_map_into::<::protobuf::types::ProtobufTypeString, ::protobuf::types::ProtobufTypeString>(wire_type, is, &mut self.headers)?;
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -2684,20 +3078,18 @@ impl ::protobuf::Message for WebSocketOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.path.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.path);
-        }
-        my_size += ::protobuf::rt::compute_map_size::<::protobuf::types::ProtobufTypeString, ::protobuf::types::ProtobufTypeString>(2, &self.headers);
+        for value in &self.actors {
+            my_size += ::protobuf::rt::string_size(1, &value);
+        };
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.path.is_empty() {
-            os.write_string(1, &self.path)?;
-        }
-        ::protobuf::rt::write_map_with_cached_sizes::<::protobuf::types::ProtobufTypeString, ::protobuf::types::ProtobufTypeString>(2, &self.headers, os)?;
+        for v in &self.actors {
+            os.write_string(1, &v)?;
+        };
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -2728,84 +3120,207 @@ impl ::protobuf::Message for WebSocketOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> WebSocketOutboundSettings {
-        WebSocketOutboundSettings::new()
+    fn new() -> ChainInboundSettings {
+        ChainInboundSettings::new()
     }
 
-    fn default_instance() -> &'static WebSocketOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<WebSocketOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(WebSocketOutboundSettings::new)
+    fn default_instance() -> &'static ChainInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<ChainInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(ChainInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for WebSocketOutboundSettings {
+impl ::protobuf::Clear for ChainInboundSettings {
     fn clear(&mut self) {
-        self.path.clear();
-        self.headers.clear();
+        self.actors.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for WebSocketOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for ChainInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct TryAllOutboundSettings {
+pub struct Inbound {
     // message fields
-    pub actors: ::protobuf::RepeatedField<::std::string::String>,
-    pub delay_base: u32,
+    pub tag: ::std::string::String,
+    pub protocol: ::std::string::String,
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub settings: ::std::vec::Vec<u8>,
+    pub proxy_protocol: bool,
+    pub max_connections: u32,
+    pub max_connections_per_source: u32,
+    pub accept_rate: u32,
+    pub sniffing: ::protobuf::SingularPtrField<Inbound_Sniffing>,
+    pub tcp_fast_open: bool,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a TryAllOutboundSettings {
-    fn default() -> &'a TryAllOutboundSettings {
-        <TryAllOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a Inbound {
+    fn default() -> &'a Inbound {
+        <Inbound as ::protobuf::Message>::default_instance()
     }
 }
 
-impl TryAllOutboundSettings {
-    pub fn new() -> TryAllOutboundSettings {
+impl Inbound {
+    pub fn new() -> Inbound {
         ::std::default::Default::default()
     }
 
-    // repeated string actors = 1;
+    // string tag = 1;
 
 
-    pub fn get_actors(&self) -> &[::std::string::String] {
-        &self.actors
+    pub fn get_tag(&self) -> &str {
+        &self.tag
     }
 
-    // uint32 delay_base = 2;
+    // string protocol = 2;
 
 
-    pub fn get_delay_base(&self) -> u32 {
-        self.delay_base
+    pub fn get_protocol(&self) -> &str {
+        &self.protocol
     }
-}
 
-impl ::protobuf::Message for TryAllOutboundSettings {
-    fn is_initialized(&self) -> bool {
-        true
-    }
+    // string address = 3;
+
+
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+
+    // uint32 port = 4;
+
+
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+
+    // bytes settings = 5;
+
+
+    pub fn get_settings(&self) -> &[u8] {
+        &self.settings
+    }
+
+    // bool proxy_protocol = 6;
+
+
+    pub fn get_proxy_protocol(&self) -> bool {
+        self.proxy_protocol
+    }
+
+    // uint32 max_connections = 7;
+
+
+    pub fn get_max_connections(&self) -> u32 {
+        self.max_connections
+    }
+
+    // uint32 max_connections_per_source = 8;
+
+
+    pub fn get_max_connections_per_source(&self) -> u32 {
+        self.max_connections_per_source
+    }
+
+    // uint32 accept_rate = 9;
+
+
+    pub fn get_accept_rate(&self) -> u32 {
+        self.accept_rate
+    }
+
+    // .Inbound.Sniffing sniffing = 10;
+
+
+    pub fn get_sniffing(&self) -> &Inbound_Sniffing {
+        self.sniffing.as_ref().unwrap_or_else(|| <Inbound_Sniffing as ::protobuf::Message>::default_instance())
+    }
+
+    // bool tcp_fast_open = 11;
+
+
+    pub fn get_tcp_fast_open(&self) -> bool {
+        self.tcp_fast_open
+    }
+}
+
+impl ::protobuf::Message for Inbound {
+    fn is_initialized(&self) -> bool {
+        for v in &self.sniffing {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
 
     fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
         while !is.eof()? {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.tag)?;
                 },
                 2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.protocol)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                4 => {
                     if wire_type != ::protobuf::wire_format::WireTypeVarint {
                         return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
                     }
                     let tmp = is.read_uint32()?;
-                    self.delay_base = tmp;
+                    self.port = tmp;
+                },
+                5 => {
+                    ::protobuf::rt::read_singular_proto3_bytes_into(wire_type, is, &mut self.settings)?;
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.proxy_protocol = tmp;
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.max_connections = tmp;
+                },
+                8 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.max_connections_per_source = tmp;
+                },
+                9 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.accept_rate = tmp;
+                },
+                10 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.sniffing)?;
+                },
+                11 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.tcp_fast_open = tmp;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -2819,11 +3334,39 @@ impl ::protobuf::Message for TryAllOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        for value in &self.actors {
-            my_size += ::protobuf::rt::string_size(1, &value);
-        };
-        if self.delay_base != 0 {
-            my_size += ::protobuf::rt::value_size(2, self.delay_base, ::protobuf::wire_format::WireTypeVarint);
+        if !self.tag.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.tag);
+        }
+        if !self.protocol.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.protocol);
+        }
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(4, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.settings.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(5, &self.settings);
+        }
+        if self.proxy_protocol != false {
+            my_size += 2;
+        }
+        if self.max_connections != 0 {
+            my_size += ::protobuf::rt::value_size(7, self.max_connections, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.max_connections_per_source != 0 {
+            my_size += ::protobuf::rt::value_size(8, self.max_connections_per_source, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.accept_rate != 0 {
+            my_size += ::protobuf::rt::value_size(9, self.accept_rate, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(ref v) = self.sniffing.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
+        if self.tcp_fast_open != false {
+            my_size += 2;
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -2831,11 +3374,40 @@ impl ::protobuf::Message for TryAllOutboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        for v in &self.actors {
-            os.write_string(1, &v)?;
-        };
-        if self.delay_base != 0 {
-            os.write_uint32(2, self.delay_base)?;
+        if !self.tag.is_empty() {
+            os.write_string(1, &self.tag)?;
+        }
+        if !self.protocol.is_empty() {
+            os.write_string(2, &self.protocol)?;
+        }
+        if !self.address.is_empty() {
+            os.write_string(3, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(4, self.port)?;
+        }
+        if !self.settings.is_empty() {
+            os.write_bytes(5, &self.settings)?;
+        }
+        if self.proxy_protocol != false {
+            os.write_bool(6, self.proxy_protocol)?;
+        }
+        if self.max_connections != 0 {
+            os.write_uint32(7, self.max_connections)?;
+        }
+        if self.max_connections_per_source != 0 {
+            os.write_uint32(8, self.max_connections_per_source)?;
+        }
+        if self.accept_rate != 0 {
+            os.write_uint32(9, self.accept_rate)?;
+        }
+        if let Some(ref v) = self.sniffing.as_ref() {
+            os.write_tag(10, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
+        if self.tcp_fast_open != false {
+            os.write_bool(11, self.tcp_fast_open)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -2867,67 +3439,92 @@ impl ::protobuf::Message for TryAllOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> TryAllOutboundSettings {
-        TryAllOutboundSettings::new()
+    fn new() -> Inbound {
+        Inbound::new()
     }
 
-    fn default_instance() -> &'static TryAllOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<TryAllOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(TryAllOutboundSettings::new)
+    fn default_instance() -> &'static Inbound {
+        static instance: ::protobuf::rt::LazyV2<Inbound> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(Inbound::new)
     }
 }
 
-impl ::protobuf::Clear for TryAllOutboundSettings {
+impl ::protobuf::Clear for Inbound {
     fn clear(&mut self) {
-        self.actors.clear();
-        self.delay_base = 0;
+        self.tag.clear();
+        self.protocol.clear();
+        self.address.clear();
+        self.port = 0;
+        self.settings.clear();
+        self.proxy_protocol = false;
+        self.max_connections = 0;
+        self.max_connections_per_source = 0;
+        self.accept_rate = 0;
+        self.sniffing.clear();
+        self.tcp_fast_open = false;
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for TryAllOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for Inbound {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct StaticOutboundSettings {
+pub struct Inbound_Sniffing {
     // message fields
-    pub actors: ::protobuf::RepeatedField<::std::string::String>,
-    pub method: ::std::string::String,
+    pub enabled: bool,
+    pub protocols: ::protobuf::RepeatedField<::std::string::String>,
+    pub ports: ::std::vec::Vec<u32>,
+    pub route_only: bool,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a StaticOutboundSettings {
-    fn default() -> &'a StaticOutboundSettings {
-        <StaticOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a Inbound_Sniffing {
+    fn default() -> &'a Inbound_Sniffing {
+        <Inbound_Sniffing as ::protobuf::Message>::default_instance()
     }
 }
 
-impl StaticOutboundSettings {
-    pub fn new() -> StaticOutboundSettings {
+impl Inbound_Sniffing {
+    pub fn new() -> Inbound_Sniffing {
         ::std::default::Default::default()
     }
 
-    // repeated string actors = 1;
+    // bool enabled = 1;
 
 
-    pub fn get_actors(&self) -> &[::std::string::String] {
-        &self.actors
+    pub fn get_enabled(&self) -> bool {
+        self.enabled
     }
 
-    // string method = 2;
+    // repeated string protocols = 2;
 
 
-    pub fn get_method(&self) -> &str {
-        &self.method
+    pub fn get_protocols(&self) -> &[::std::string::String] {
+        &self.protocols
+    }
+
+    // repeated uint32 ports = 3;
+
+
+    pub fn get_ports(&self) -> &[u32] {
+        &self.ports
+    }
+
+    // bool route_only = 4;
+
+
+    pub fn get_route_only(&self) -> bool {
+        self.route_only
     }
 }
 
-impl ::protobuf::Message for StaticOutboundSettings {
+impl ::protobuf::Message for Inbound_Sniffing {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -2937,10 +3534,24 @@ impl ::protobuf::Message for StaticOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.enabled = tmp;
                 },
                 2 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.method)?;
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.protocols)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_repeated_uint32_into(wire_type, is, &mut self.ports)?;
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.route_only = tmp;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -2954,11 +3565,17 @@ impl ::protobuf::Message for StaticOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        for value in &self.actors {
-            my_size += ::protobuf::rt::string_size(1, &value);
+        if self.enabled != false {
+            my_size += 2;
+        }
+        for value in &self.protocols {
+            my_size += ::protobuf::rt::string_size(2, &value);
         };
-        if !self.method.is_empty() {
-            my_size += ::protobuf::rt::string_size(2, &self.method);
+        for value in &self.ports {
+            my_size += ::protobuf::rt::value_size(3, *value, ::protobuf::wire_format::WireTypeVarint);
+        };
+        if self.route_only != false {
+            my_size += 2;
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -2966,11 +3583,17 @@ impl ::protobuf::Message for StaticOutboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        for v in &self.actors {
-            os.write_string(1, &v)?;
+        if self.enabled != false {
+            os.write_bool(1, self.enabled)?;
+        }
+        for v in &self.protocols {
+            os.write_string(2, &v)?;
         };
-        if !self.method.is_empty() {
-            os.write_string(2, &self.method)?;
+        for v in &self.ports {
+            os.write_uint32(3, *v)?;
+        };
+        if self.route_only != false {
+            os.write_bool(4, self.route_only)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -3002,91 +3625,69 @@ impl ::protobuf::Message for StaticOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> StaticOutboundSettings {
-        StaticOutboundSettings::new()
+    fn new() -> Inbound_Sniffing {
+        Inbound_Sniffing::new()
     }
 
-    fn default_instance() -> &'static StaticOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<StaticOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(StaticOutboundSettings::new)
+    fn default_instance() -> &'static Inbound_Sniffing {
+        static instance: ::protobuf::rt::LazyV2<Inbound_Sniffing> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(Inbound_Sniffing::new)
     }
 }
 
-impl ::protobuf::Clear for StaticOutboundSettings {
+impl ::protobuf::Clear for Inbound_Sniffing {
     fn clear(&mut self) {
-        self.actors.clear();
-        self.method.clear();
+        self.enabled = false;
+        self.protocols.clear();
+        self.ports.clear();
+        self.route_only = false;
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for StaticOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for Inbound_Sniffing {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct AMuxOutboundSettings {
+pub struct DropOutboundSettings {
     // message fields
-    pub address: ::std::string::String,
-    pub port: u32,
-    pub actors: ::protobuf::RepeatedField<::std::string::String>,
-    pub max_accepts: u32,
-    pub concurrency: u32,
+    pub tcp: ::std::string::String,
+    pub udp: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a AMuxOutboundSettings {
-    fn default() -> &'a AMuxOutboundSettings {
-        <AMuxOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a DropOutboundSettings {
+    fn default() -> &'a DropOutboundSettings {
+        <DropOutboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl AMuxOutboundSettings {
-    pub fn new() -> AMuxOutboundSettings {
+impl DropOutboundSettings {
+    pub fn new() -> DropOutboundSettings {
         ::std::default::Default::default()
     }
 
-    // string address = 1;
+    // string tcp = 1;
 
 
-    pub fn get_address(&self) -> &str {
-        &self.address
+    pub fn get_tcp(&self) -> &str {
+        &self.tcp
     }
 
-    // uint32 port = 2;
+    // string udp = 2;
 
 
-    pub fn get_port(&self) -> u32 {
-        self.port
+    pub fn get_country_code(&self) -> &str {
+        &self.udp
     }
+}
 
-    // repeated string actors = 3;
-
-
-    pub fn get_actors(&self) -> &[::std::string::String] {
-        &self.actors
-    }
-
-    // uint32 max_accepts = 4;
-
-
-    pub fn get_max_accepts(&self) -> u32 {
-        self.max_accepts
-    }
-
-    // uint32 concurrency = 5;
-
-
-    pub fn get_concurrency(&self) -> u32 {
-        self.concurrency
-    }
-}
-
-impl ::protobuf::Message for AMuxOutboundSettings {
+impl ::protobuf::Message for DropOutboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -3096,31 +3697,10 @@ impl ::protobuf::Message for AMuxOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.tcp)?;
                 },
                 2 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_uint32()?;
-                    self.port = tmp;
-                },
-                3 => {
-                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
-                },
-                4 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_uint32()?;
-                    self.max_accepts = tmp;
-                },
-                5 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_uint32()?;
-                    self.concurrency = tmp;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.udp)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -3134,20 +3714,11 @@ impl ::protobuf::Message for AMuxOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.address.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.address);
-        }
-        if self.port != 0 {
-            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
-        }
-        for value in &self.actors {
-            my_size += ::protobuf::rt::string_size(3, &value);
-        };
-        if self.max_accepts != 0 {
-            my_size += ::protobuf::rt::value_size(4, self.max_accepts, ::protobuf::wire_format::WireTypeVarint);
+        if !self.tcp.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.tcp);
         }
-        if self.concurrency != 0 {
-            my_size += ::protobuf::rt::value_size(5, self.concurrency, ::protobuf::wire_format::WireTypeVarint);
+        if !self.udp.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.udp);
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -3155,20 +3726,11 @@ impl ::protobuf::Message for AMuxOutboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.address.is_empty() {
-            os.write_string(1, &self.address)?;
-        }
-        if self.port != 0 {
-            os.write_uint32(2, self.port)?;
-        }
-        for v in &self.actors {
-            os.write_string(3, &v)?;
-        };
-        if self.max_accepts != 0 {
-            os.write_uint32(4, self.max_accepts)?;
+        if !self.tcp.is_empty() {
+            os.write_string(1, &self.tcp)?;
         }
-        if self.concurrency != 0 {
-            os.write_uint32(5, self.concurrency)?;
+        if !self.udp.is_empty() {
+            os.write_string(2, &self.udp)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -3200,53 +3762,48 @@ impl ::protobuf::Message for AMuxOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> AMuxOutboundSettings {
-        AMuxOutboundSettings::new()
+    fn new() -> DropOutboundSettings {
+        DropOutboundSettings::new()
     }
 
-    fn default_instance() -> &'static AMuxOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<AMuxOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(AMuxOutboundSettings::new)
+    fn default_instance() -> &'static DropOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<DropOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(DropOutboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for AMuxOutboundSettings {
+impl ::protobuf::Clear for DropOutboundSettings {
     fn clear(&mut self) {
-        self.address.clear();
-        self.port = 0;
-        self.actors.clear();
-        self.max_accepts = 0;
-        self.concurrency = 0;
+        self.tcp.clear();
+        self.udp.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for AMuxOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for DropOutboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct QuicOutboundSettings {
+pub struct RedirectOutboundSettings {
     // message fields
     pub address: ::std::string::String,
     pub port: u32,
-    pub server_name: ::std::string::String,
-    pub certificate: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a QuicOutboundSettings {
-    fn default() -> &'a QuicOutboundSettings {
-        <QuicOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a RedirectOutboundSettings {
+    fn default() -> &'a RedirectOutboundSettings {
+        <RedirectOutboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl QuicOutboundSettings {
-    pub fn new() -> QuicOutboundSettings {
+impl RedirectOutboundSettings {
+    pub fn new() -> RedirectOutboundSettings {
         ::std::default::Default::default()
     }
 
@@ -3263,23 +3820,9 @@ impl QuicOutboundSettings {
     pub fn get_port(&self) -> u32 {
         self.port
     }
-
-    // string server_name = 3;
-
-
-    pub fn get_server_name(&self) -> &str {
-        &self.server_name
-    }
-
-    // string certificate = 4;
-
-
-    pub fn get_certificate(&self) -> &str {
-        &self.certificate
-    }
 }
 
-impl ::protobuf::Message for QuicOutboundSettings {
+impl ::protobuf::Message for RedirectOutboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -3298,12 +3841,6 @@ impl ::protobuf::Message for QuicOutboundSettings {
                     let tmp = is.read_uint32()?;
                     self.port = tmp;
                 },
-                3 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.server_name)?;
-                },
-                4 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.certificate)?;
-                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -3322,12 +3859,6 @@ impl ::protobuf::Message for QuicOutboundSettings {
         if self.port != 0 {
             my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
         }
-        if !self.server_name.is_empty() {
-            my_size += ::protobuf::rt::string_size(3, &self.server_name);
-        }
-        if !self.certificate.is_empty() {
-            my_size += ::protobuf::rt::string_size(4, &self.certificate);
-        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -3340,12 +3871,6 @@ impl ::protobuf::Message for QuicOutboundSettings {
         if self.port != 0 {
             os.write_uint32(2, self.port)?;
         }
-        if !self.server_name.is_empty() {
-            os.write_string(3, &self.server_name)?;
-        }
-        if !self.certificate.is_empty() {
-            os.write_string(4, &self.certificate)?;
-        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -3376,61 +3901,67 @@ impl ::protobuf::Message for QuicOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> QuicOutboundSettings {
-        QuicOutboundSettings::new()
+    fn new() -> RedirectOutboundSettings {
+        RedirectOutboundSettings::new()
     }
 
-    fn default_instance() -> &'static QuicOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<QuicOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(QuicOutboundSettings::new)
+    fn default_instance() -> &'static RedirectOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<RedirectOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(RedirectOutboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for QuicOutboundSettings {
+impl ::protobuf::Clear for RedirectOutboundSettings {
     fn clear(&mut self) {
         self.address.clear();
         self.port = 0;
-        self.server_name.clear();
-        self.certificate.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for QuicOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for RedirectOutboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct ChainOutboundSettings {
+pub struct SocksOutboundSettings {
     // message fields
-    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    pub address: ::std::string::String,
+    pub port: u32,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a ChainOutboundSettings {
-    fn default() -> &'a ChainOutboundSettings {
-        <ChainOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a SocksOutboundSettings {
+    fn default() -> &'a SocksOutboundSettings {
+        <SocksOutboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl ChainOutboundSettings {
-    pub fn new() -> ChainOutboundSettings {
+impl SocksOutboundSettings {
+    pub fn new() -> SocksOutboundSettings {
         ::std::default::Default::default()
     }
 
-    // repeated string actors = 1;
+    // string address = 1;
 
 
-    pub fn get_actors(&self) -> &[::std::string::String] {
-        &self.actors
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+
+    // uint32 port = 2;
+
+
+    pub fn get_port(&self) -> u32 {
+        self.port
     }
 }
 
-impl ::protobuf::Message for ChainOutboundSettings {
+impl ::protobuf::Message for SocksOutboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -3440,7 +3971,14 @@ impl ::protobuf::Message for ChainOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -3454,18 +3992,24 @@ impl ::protobuf::Message for ChainOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        for value in &self.actors {
-            my_size += ::protobuf::rt::string_size(1, &value);
-        };
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        for v in &self.actors {
-            os.write_string(1, &v)?;
-        };
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -3496,130 +4040,4239 @@ impl ::protobuf::Message for ChainOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> ChainOutboundSettings {
-        ChainOutboundSettings::new()
+    fn new() -> SocksOutboundSettings {
+        SocksOutboundSettings::new()
     }
 
-    fn default_instance() -> &'static ChainOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<ChainOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(ChainOutboundSettings::new)
+    fn default_instance() -> &'static SocksOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<SocksOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(SocksOutboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for ChainOutboundSettings {
+impl ::protobuf::Clear for SocksOutboundSettings {
     fn clear(&mut self) {
-        self.actors.clear();
+        self.address.clear();
+        self.port = 0;
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for ChainOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for SocksOutboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default,Debug)]
-pub struct FailOverOutboundSettings {
+pub struct ShadowsocksOutboundSettings {
     // message fields
-    pub actors: ::protobuf::RepeatedField<::std::string::String>,
-    pub fail_timeout: u32,
-    pub health_check: bool,
-    pub check_interval: u32,
-    pub failover: bool,
-    pub fallback_cache: bool,
-    pub cache_size: u32,
-    pub cache_timeout: u32,
-    pub last_resort: ::std::string::String,
-    pub health_check_timeout: u32,
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub method: ::std::string::String,
+    pub password: ::std::string::String,
+    pub plugin: ::std::string::String,
+    pub plugin_opts: ::std::string::String,
+    pub udp_over_tcp: bool,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a FailOverOutboundSettings {
-    fn default() -> &'a FailOverOutboundSettings {
-        <FailOverOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a ShadowsocksOutboundSettings {
+    fn default() -> &'a ShadowsocksOutboundSettings {
+        <ShadowsocksOutboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl FailOverOutboundSettings {
-    pub fn new() -> FailOverOutboundSettings {
+impl ShadowsocksOutboundSettings {
+    pub fn new() -> ShadowsocksOutboundSettings {
         ::std::default::Default::default()
     }
 
-    // repeated string actors = 1;
+    // string address = 1;
 
 
-    pub fn get_actors(&self) -> &[::std::string::String] {
-        &self.actors
+    pub fn get_address(&self) -> &str {
+        &self.address
     }
 
-    // uint32 fail_timeout = 2;
+    // uint32 port = 2;
 
 
-    pub fn get_fail_timeout(&self) -> u32 {
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+
+    // string method = 3;
+
+
+    pub fn get_method(&self) -> &str {
+        &self.method
+    }
+
+    // string password = 4;
+
+
+    pub fn get_password(&self) -> &str {
+        &self.password
+    }
+
+    // string plugin = 5;
+
+
+    pub fn get_plugin(&self) -> &str {
+        &self.plugin
+    }
+
+    // string plugin_opts = 6;
+
+
+    pub fn get_plugin_opts(&self) -> &str {
+        &self.plugin_opts
+    }
+
+    // bool udp_over_tcp = 7;
+
+
+    pub fn get_udp_over_tcp(&self) -> bool {
+        self.udp_over_tcp
+    }
+}
+
+impl ::protobuf::Message for ShadowsocksOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.method)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.password)?;
+                },
+                5 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.plugin)?;
+                },
+                6 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.plugin_opts)?;
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.udp_over_tcp = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.method.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.method);
+        }
+        if !self.password.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.password);
+        }
+        if !self.plugin.is_empty() {
+            my_size += ::protobuf::rt::string_size(5, &self.plugin);
+        }
+        if !self.plugin_opts.is_empty() {
+            my_size += ::protobuf::rt::string_size(6, &self.plugin_opts);
+        }
+        if self.udp_over_tcp != false {
+            my_size += 2;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if !self.method.is_empty() {
+            os.write_string(3, &self.method)?;
+        }
+        if !self.password.is_empty() {
+            os.write_string(4, &self.password)?;
+        }
+        if !self.plugin.is_empty() {
+            os.write_string(5, &self.plugin)?;
+        }
+        if !self.plugin_opts.is_empty() {
+            os.write_string(6, &self.plugin_opts)?;
+        }
+        if self.udp_over_tcp != false {
+            os.write_bool(7, self.udp_over_tcp)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> ShadowsocksOutboundSettings {
+        ShadowsocksOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static ShadowsocksOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<ShadowsocksOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(ShadowsocksOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for ShadowsocksOutboundSettings {
+    fn clear(&mut self) {
+        self.address.clear();
+        self.port = 0;
+        self.method.clear();
+        self.password.clear();
+        self.plugin.clear();
+        self.plugin_opts.clear();
+        self.udp_over_tcp = false;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ShadowsocksOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct TrojanOutboundSettings {
+    // message fields
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub password: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a TrojanOutboundSettings {
+    fn default() -> &'a TrojanOutboundSettings {
+        <TrojanOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl TrojanOutboundSettings {
+    pub fn new() -> TrojanOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string address = 1;
+
+
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+
+    // uint32 port = 2;
+
+
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+
+    // string password = 3;
+
+
+    pub fn get_password(&self) -> &str {
+        &self.password
+    }
+}
+
+impl ::protobuf::Message for TrojanOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.password)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.password.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.password);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if !self.password.is_empty() {
+            os.write_string(3, &self.password)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> TrojanOutboundSettings {
+        TrojanOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static TrojanOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<TrojanOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(TrojanOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for TrojanOutboundSettings {
+    fn clear(&mut self) {
+        self.address.clear();
+        self.port = 0;
+        self.password.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for TrojanOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct VLessOutboundSettings {
+    // message fields
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub uuid: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a VLessOutboundSettings {
+    fn default() -> &'a VLessOutboundSettings {
+        <VLessOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl VLessOutboundSettings {
+    pub fn new() -> VLessOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string address = 1;
+
+
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+
+    // uint32 port = 2;
+
+
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+
+    // string uuid = 3;
+
+
+    pub fn get_uuid(&self) -> &str {
+        &self.uuid
+    }
+}
+
+impl ::protobuf::Message for VLessOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.uuid)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.uuid.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.uuid);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if !self.uuid.is_empty() {
+            os.write_string(3, &self.uuid)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> VLessOutboundSettings {
+        VLessOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static VLessOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<VLessOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(VLessOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for VLessOutboundSettings {
+    fn clear(&mut self) {
+        self.address.clear();
+        self.port = 0;
+        self.uuid.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for VLessOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct VMessOutboundSettings {
+    // message fields
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub uuid: ::std::string::String,
+    pub security: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a VMessOutboundSettings {
+    fn default() -> &'a VMessOutboundSettings {
+        <VMessOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl VMessOutboundSettings {
+    pub fn new() -> VMessOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string address = 1;
+
+
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+
+    // uint32 port = 2;
+
+
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+
+    // string uuid = 3;
+
+
+    pub fn get_uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    // string security = 4;
+
+
+    pub fn get_security(&self) -> &str {
+        &self.security
+    }
+}
+
+impl ::protobuf::Message for VMessOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.uuid)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.security)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.uuid.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.uuid);
+        }
+        if !self.security.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.security);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if !self.uuid.is_empty() {
+            os.write_string(3, &self.uuid)?;
+        }
+        if !self.security.is_empty() {
+            os.write_string(4, &self.security)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> VMessOutboundSettings {
+        VMessOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static VMessOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<VMessOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(VMessOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for VMessOutboundSettings {
+    fn clear(&mut self) {
+        self.address.clear();
+        self.port = 0;
+        self.uuid.clear();
+        self.security.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for VMessOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct SshOutboundSettings {
+    // message fields
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub username: ::std::string::String,
+    pub password: ::std::string::String,
+    pub private_key: ::std::string::String,
+    pub host_key: ::std::string::String,
+    pub insecure_skip_host_key: bool,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a SshOutboundSettings {
+    fn default() -> &'a SshOutboundSettings {
+        <SshOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl SshOutboundSettings {
+    pub fn new() -> SshOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string address = 1;
+
+
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+
+    // uint32 port = 2;
+
+
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+
+    // string username = 3;
+
+
+    pub fn get_username(&self) -> &str {
+        &self.username
+    }
+
+    // string password = 4;
+
+
+    pub fn get_password(&self) -> &str {
+        &self.password
+    }
+
+    // string private_key = 5;
+
+
+    pub fn get_private_key(&self) -> &str {
+        &self.private_key
+    }
+
+    // string host_key = 6;
+
+
+    pub fn get_host_key(&self) -> &str {
+        &self.host_key
+    }
+
+    // bool insecure_skip_host_key = 7;
+
+
+    pub fn get_insecure_skip_host_key(&self) -> bool {
+        self.insecure_skip_host_key
+    }
+}
+
+impl ::protobuf::Message for SshOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.username)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.password)?;
+                },
+                5 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.private_key)?;
+                },
+                6 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.host_key)?;
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.insecure_skip_host_key = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.username.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.username);
+        }
+        if !self.password.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.password);
+        }
+        if !self.private_key.is_empty() {
+            my_size += ::protobuf::rt::string_size(5, &self.private_key);
+        }
+        if !self.host_key.is_empty() {
+            my_size += ::protobuf::rt::string_size(6, &self.host_key);
+        }
+        if self.insecure_skip_host_key != false {
+            my_size += 2;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if !self.username.is_empty() {
+            os.write_string(3, &self.username)?;
+        }
+        if !self.password.is_empty() {
+            os.write_string(4, &self.password)?;
+        }
+        if !self.private_key.is_empty() {
+            os.write_string(5, &self.private_key)?;
+        }
+        if !self.host_key.is_empty() {
+            os.write_string(6, &self.host_key)?;
+        }
+        if self.insecure_skip_host_key != false {
+            os.write_bool(7, self.insecure_skip_host_key)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> SshOutboundSettings {
+        SshOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static SshOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<SshOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(SshOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for SshOutboundSettings {
+    fn clear(&mut self) {
+        self.address.clear();
+        self.port = 0;
+        self.username.clear();
+        self.password.clear();
+        self.private_key.clear();
+        self.host_key.clear();
+        self.insecure_skip_host_key = false;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SshOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct TlsOutboundSettings {
+    // message fields
+    pub server_name: ::std::string::String,
+    pub alpn: ::protobuf::RepeatedField<::std::string::String>,
+    pub certificate: ::std::string::String,
+    pub ech: bool,
+    pub ech_config: ::std::string::String,
+    pub client_certificate: ::std::string::String,
+    pub client_certificate_key: ::std::string::String,
+    pub pinned_peer_certificate_chain_sha256: ::protobuf::RepeatedField<::std::string::String>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a TlsOutboundSettings {
+    fn default() -> &'a TlsOutboundSettings {
+        <TlsOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl TlsOutboundSettings {
+    pub fn new() -> TlsOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string server_name = 1;
+
+
+    pub fn get_server_name(&self) -> &str {
+        &self.server_name
+    }
+
+    // repeated string alpn = 2;
+
+
+    pub fn get_alpn(&self) -> &[::std::string::String] {
+        &self.alpn
+    }
+
+    // string certificate = 3;
+
+
+    pub fn get_certificate(&self) -> &str {
+        &self.certificate
+    }
+
+    // bool ech = 5;
+
+
+    pub fn get_ech(&self) -> bool {
+        self.ech
+    }
+
+    // string ech_config = 6;
+
+
+    pub fn get_ech_config(&self) -> &str {
+        &self.ech_config
+    }
+
+    // string client_certificate = 7;
+
+
+    pub fn get_client_certificate(&self) -> &str {
+        &self.client_certificate
+    }
+
+    // string client_certificate_key = 8;
+
+
+    pub fn get_client_certificate_key(&self) -> &str {
+        &self.client_certificate_key
+    }
+
+    // repeated string pinned_peer_certificate_chain_sha256 = 9;
+
+
+    pub fn get_pinned_peer_certificate_chain_sha256(&self) -> &[::std::string::String] {
+        &self.pinned_peer_certificate_chain_sha256
+    }
+}
+
+impl ::protobuf::Message for TlsOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.server_name)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.alpn)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.certificate)?;
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.ech = tmp;
+                },
+                6 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.ech_config)?;
+                },
+                7 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.client_certificate)?;
+                },
+                8 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.client_certificate_key)?;
+                },
+                9 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.pinned_peer_certificate_chain_sha256)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.server_name.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.server_name);
+        }
+        for value in &self.alpn {
+            my_size += ::protobuf::rt::string_size(2, &value);
+        };
+        if !self.certificate.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.certificate);
+        }
+        if self.ech != false {
+            my_size += 2;
+        }
+        if !self.ech_config.is_empty() {
+            my_size += ::protobuf::rt::string_size(6, &self.ech_config);
+        }
+        if !self.client_certificate.is_empty() {
+            my_size += ::protobuf::rt::string_size(7, &self.client_certificate);
+        }
+        if !self.client_certificate_key.is_empty() {
+            my_size += ::protobuf::rt::string_size(8, &self.client_certificate_key);
+        }
+        for value in &self.pinned_peer_certificate_chain_sha256 {
+            my_size += ::protobuf::rt::string_size(9, &value);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.server_name.is_empty() {
+            os.write_string(1, &self.server_name)?;
+        }
+        for v in &self.alpn {
+            os.write_string(2, &v)?;
+        };
+        if !self.certificate.is_empty() {
+            os.write_string(3, &self.certificate)?;
+        }
+        if self.ech != false {
+            os.write_bool(5, self.ech)?;
+        }
+        if !self.ech_config.is_empty() {
+            os.write_string(6, &self.ech_config)?;
+        }
+        if !self.client_certificate.is_empty() {
+            os.write_string(7, &self.client_certificate)?;
+        }
+        if !self.client_certificate_key.is_empty() {
+            os.write_string(8, &self.client_certificate_key)?;
+        }
+        for v in &self.pinned_peer_certificate_chain_sha256 {
+            os.write_string(9, &v)?;
+        };
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> TlsOutboundSettings {
+        TlsOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static TlsOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<TlsOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(TlsOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for TlsOutboundSettings {
+    fn clear(&mut self) {
+        self.server_name.clear();
+        self.alpn.clear();
+        self.certificate.clear();
+        self.ech = false;
+        self.ech_config.clear();
+        self.client_certificate.clear();
+        self.client_certificate_key.clear();
+        self.pinned_peer_certificate_chain_sha256.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for TlsOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct RealityOutboundSettings {
+    // message fields
+    pub server_name: ::std::string::String,
+    pub public_key: ::std::string::String,
+    pub short_id: ::std::string::String,
+    pub alpn: ::protobuf::RepeatedField<::std::string::String>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a RealityOutboundSettings {
+    fn default() -> &'a RealityOutboundSettings {
+        <RealityOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl RealityOutboundSettings {
+    pub fn new() -> RealityOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string server_name = 1;
+
+
+    pub fn get_server_name(&self) -> &str {
+        &self.server_name
+    }
+
+    // string public_key = 2;
+
+
+    pub fn get_public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    // string short_id = 3;
+
+
+    pub fn get_short_id(&self) -> &str {
+        &self.short_id
+    }
+
+    // repeated string alpn = 4;
+
+
+    pub fn get_alpn(&self) -> &[::std::string::String] {
+        &self.alpn
+    }
+}
+
+impl ::protobuf::Message for RealityOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.server_name)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.public_key)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.short_id)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.alpn)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.server_name.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.server_name);
+        }
+        if !self.public_key.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.public_key);
+        }
+        if !self.short_id.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.short_id);
+        }
+        for value in &self.alpn {
+            my_size += ::protobuf::rt::string_size(4, &value);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.server_name.is_empty() {
+            os.write_string(1, &self.server_name)?;
+        }
+        if !self.public_key.is_empty() {
+            os.write_string(2, &self.public_key)?;
+        }
+        if !self.short_id.is_empty() {
+            os.write_string(3, &self.short_id)?;
+        }
+        for v in &self.alpn {
+            os.write_string(4, &v)?;
+        };
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> RealityOutboundSettings {
+        RealityOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static RealityOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<RealityOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(RealityOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for RealityOutboundSettings {
+    fn clear(&mut self) {
+        self.server_name.clear();
+        self.public_key.clear();
+        self.short_id.clear();
+        self.alpn.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for RealityOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct WebSocketOutboundSettings {
+    // message fields
+    pub path: ::std::string::String,
+    pub headers: ::std::collections::HashMap<::std::string::String, ::std::string::String>,
+    pub max_early_data: u32,
+    pub early_data_header_name: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a WebSocketOutboundSettings {
+    fn default() -> &'a WebSocketOutboundSettings {
+        <WebSocketOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl WebSocketOutboundSettings {
+    pub fn new() -> WebSocketOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string path = 1;
+
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    // repeated .WebSocketOutboundSettings.HeadersEntry headers = 2;
+
+
+    pub fn get_headers(&self) -> &::std::collections::HashMap<::std::string::String, ::std::string::String> {
+        &self.headers
+    }
+
+    // uint32 max_early_data = 3;
+
+
+    pub fn get_max_early_data(&self) -> u32 {
+        self.max_early_data
+    }
+
+    // string early_data_header_name = 4;
+
+
+    pub fn get_early_data_header_name(&self) -> &str {
+        &self.early_data_header_name
+    }
+}
+
+impl ::protobuf::Message for WebSocketOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.path)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_map_into::<::protobuf::types::ProtobufTypeString, ::protobuf::types::ProtobufTypeString>(wire_type, is, &mut self.headers)?;
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.max_early_data = tmp;
+                },
+                4 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.early_data_header_name)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.path.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.path);
+        }
+        my_size += ::protobuf::rt::compute_map_size::<::protobuf::types::ProtobufTypeString, ::protobuf::types::ProtobufTypeString>(2, &self.headers);
+        if self.max_early_data != 0 {
+            my_size += ::protobuf::rt::value_size(3, self.max_early_data, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.early_data_header_name.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.early_data_header_name);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.path.is_empty() {
+            os.write_string(1, &self.path)?;
+        }
+        ::protobuf::rt::write_map_with_cached_sizes::<::protobuf::types::ProtobufTypeString, ::protobuf::types::ProtobufTypeString>(2, &self.headers, os)?;
+        if self.max_early_data != 0 {
+            os.write_uint32(3, self.max_early_data)?;
+        }
+        if !self.early_data_header_name.is_empty() {
+            os.write_string(4, &self.early_data_header_name)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> WebSocketOutboundSettings {
+        WebSocketOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static WebSocketOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<WebSocketOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(WebSocketOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for WebSocketOutboundSettings {
+    fn clear(&mut self) {
+        self.path.clear();
+        self.headers.clear();
+        self.max_early_data = 0;
+        self.early_data_header_name.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for WebSocketOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct NaiveOutboundSettings {
+    // message fields
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub username: ::std::string::String,
+    pub password: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a NaiveOutboundSettings {
+    fn default() -> &'a NaiveOutboundSettings {
+        <NaiveOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl NaiveOutboundSettings {
+    pub fn new() -> NaiveOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string address = 1;
+
+
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+
+    // uint32 port = 2;
+
+
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+
+    // string username = 3;
+
+
+    pub fn get_username(&self) -> &str {
+        &self.username
+    }
+
+    // string password = 4;
+
+
+    pub fn get_password(&self) -> &str {
+        &self.password
+    }
+}
+
+impl ::protobuf::Message for NaiveOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.username)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.password)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.username.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.username);
+        }
+        if !self.password.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.password);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if !self.username.is_empty() {
+            os.write_string(3, &self.username)?;
+        }
+        if !self.password.is_empty() {
+            os.write_string(4, &self.password)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> NaiveOutboundSettings {
+        NaiveOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static NaiveOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<NaiveOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(NaiveOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for NaiveOutboundSettings {
+    fn clear(&mut self) {
+        self.address.clear();
+        self.port = 0;
+        self.username.clear();
+        self.password.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for NaiveOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct ObfsOutboundSettings {
+    // message fields
+    pub method: ::std::string::String,
+    pub host: ::std::string::String,
+    pub path: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a ObfsOutboundSettings {
+    fn default() -> &'a ObfsOutboundSettings {
+        <ObfsOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ObfsOutboundSettings {
+    pub fn new() -> ObfsOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string method = 1;
+
+
+    pub fn get_method(&self) -> &str {
+        &self.method
+    }
+
+    // string host = 2;
+
+
+    pub fn get_host(&self) -> &str {
+        &self.host
+    }
+
+    // string path = 3;
+
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl ::protobuf::Message for ObfsOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.method)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.host)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.path)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.method.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.method);
+        }
+        if !self.host.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.host);
+        }
+        if !self.path.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.path);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.method.is_empty() {
+            os.write_string(1, &self.method)?;
+        }
+        if !self.host.is_empty() {
+            os.write_string(2, &self.host)?;
+        }
+        if !self.path.is_empty() {
+            os.write_string(3, &self.path)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> ObfsOutboundSettings {
+        ObfsOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static ObfsOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<ObfsOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(ObfsOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for ObfsOutboundSettings {
+    fn clear(&mut self) {
+        self.method.clear();
+        self.host.clear();
+        self.path.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ObfsOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct TryAllOutboundSettings {
+    // message fields
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    pub delay_base: u32,
+    pub attempt_timeout: u32,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a TryAllOutboundSettings {
+    fn default() -> &'a TryAllOutboundSettings {
+        <TryAllOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl TryAllOutboundSettings {
+    pub fn new() -> TryAllOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // repeated string actors = 1;
+
+
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
+    }
+
+    // uint32 delay_base = 2;
+
+
+    pub fn get_delay_base(&self) -> u32 {
+        self.delay_base
+    }
+
+    // uint32 attempt_timeout = 3;
+
+
+    pub fn get_attempt_timeout(&self) -> u32 {
+        self.attempt_timeout
+    }
+}
+
+impl ::protobuf::Message for TryAllOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.delay_base = tmp;
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.attempt_timeout = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        for value in &self.actors {
+            my_size += ::protobuf::rt::string_size(1, &value);
+        };
+        if self.delay_base != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.delay_base, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.attempt_timeout != 0 {
+            my_size += ::protobuf::rt::value_size(3, self.attempt_timeout, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        for v in &self.actors {
+            os.write_string(1, &v)?;
+        };
+        if self.delay_base != 0 {
+            os.write_uint32(2, self.delay_base)?;
+        }
+        if self.attempt_timeout != 0 {
+            os.write_uint32(3, self.attempt_timeout)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> TryAllOutboundSettings {
+        TryAllOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static TryAllOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<TryAllOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(TryAllOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for TryAllOutboundSettings {
+    fn clear(&mut self) {
+        self.actors.clear();
+        self.delay_base = 0;
+        self.attempt_timeout = 0;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for TryAllOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct RetryOutboundSettings {
+    // message fields
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    pub attempts: u32,
+    pub backoff_base: u32,
+    pub max_backoff: u32,
+    pub jitter: u32,
+    pub rotate: bool,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a RetryOutboundSettings {
+    fn default() -> &'a RetryOutboundSettings {
+        <RetryOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl RetryOutboundSettings {
+    pub fn new() -> RetryOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // repeated string actors = 1;
+
+
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
+    }
+
+    // uint32 attempts = 2;
+
+
+    pub fn get_attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    // uint32 backoff_base = 3;
+
+
+    pub fn get_backoff_base(&self) -> u32 {
+        self.backoff_base
+    }
+
+    // uint32 max_backoff = 4;
+
+
+    pub fn get_max_backoff(&self) -> u32 {
+        self.max_backoff
+    }
+
+    // uint32 jitter = 5;
+
+
+    pub fn get_jitter(&self) -> u32 {
+        self.jitter
+    }
+
+    // bool rotate = 6;
+
+
+    pub fn get_rotate(&self) -> bool {
+        self.rotate
+    }
+}
+
+impl ::protobuf::Message for RetryOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.attempts = tmp;
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.backoff_base = tmp;
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.max_backoff = tmp;
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.jitter = tmp;
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.rotate = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        for value in &self.actors {
+            my_size += ::protobuf::rt::string_size(1, &value);
+        };
+        if self.attempts != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.attempts, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.backoff_base != 0 {
+            my_size += ::protobuf::rt::value_size(3, self.backoff_base, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.max_backoff != 0 {
+            my_size += ::protobuf::rt::value_size(4, self.max_backoff, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.jitter != 0 {
+            my_size += ::protobuf::rt::value_size(5, self.jitter, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.rotate != false {
+            my_size += 2;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        for v in &self.actors {
+            os.write_string(1, &v)?;
+        };
+        if self.attempts != 0 {
+            os.write_uint32(2, self.attempts)?;
+        }
+        if self.backoff_base != 0 {
+            os.write_uint32(3, self.backoff_base)?;
+        }
+        if self.max_backoff != 0 {
+            os.write_uint32(4, self.max_backoff)?;
+        }
+        if self.jitter != 0 {
+            os.write_uint32(5, self.jitter)?;
+        }
+        if self.rotate != false {
+            os.write_bool(6, self.rotate)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> RetryOutboundSettings {
+        RetryOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static RetryOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<RetryOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(RetryOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for RetryOutboundSettings {
+    fn clear(&mut self) {
+        self.actors.clear();
+        self.attempts = 0;
+        self.backoff_base = 0;
+        self.max_backoff = 0;
+        self.jitter = 0;
+        self.rotate = false;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for RetryOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct StaticOutboundSettings {
+    // message fields
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    pub method: ::std::string::String,
+    pub weights: ::std::vec::Vec<u32>,
+    pub url: ::std::string::String,
+    pub interval: u32,
+    pub timeout: u32,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a StaticOutboundSettings {
+    fn default() -> &'a StaticOutboundSettings {
+        <StaticOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl StaticOutboundSettings {
+    pub fn new() -> StaticOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // repeated string actors = 1;
+
+
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
+    }
+
+    // string method = 2;
+
+
+    pub fn get_method(&self) -> &str {
+        &self.method
+    }
+
+    // repeated uint32 weights = 3;
+
+
+    pub fn get_weights(&self) -> &[u32] {
+        &self.weights
+    }
+
+    // string url = 4;
+
+
+    pub fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    // uint32 interval = 5;
+
+
+    pub fn get_interval(&self) -> u32 {
+        self.interval
+    }
+
+    // uint32 timeout = 6;
+
+
+    pub fn get_timeout(&self) -> u32 {
+        self.timeout
+    }
+}
+
+impl ::protobuf::Message for StaticOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.method)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_repeated_uint32_into(wire_type, is, &mut self.weights)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.url)?;
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.interval = tmp;
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.timeout = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        for value in &self.actors {
+            my_size += ::protobuf::rt::string_size(1, &value);
+        };
+        if !self.method.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.method);
+        }
+        for value in &self.weights {
+            my_size += ::protobuf::rt::value_size(3, *value, ::protobuf::wire_format::WireTypeVarint);
+        };
+        if !self.url.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.url);
+        }
+        if self.interval != 0 {
+            my_size += ::protobuf::rt::value_size(5, self.interval, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.timeout != 0 {
+            my_size += ::protobuf::rt::value_size(6, self.timeout, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        for v in &self.actors {
+            os.write_string(1, &v)?;
+        };
+        if !self.method.is_empty() {
+            os.write_string(2, &self.method)?;
+        }
+        for v in &self.weights {
+            os.write_uint32(3, *v)?;
+        };
+        if !self.url.is_empty() {
+            os.write_string(4, &self.url)?;
+        }
+        if self.interval != 0 {
+            os.write_uint32(5, self.interval)?;
+        }
+        if self.timeout != 0 {
+            os.write_uint32(6, self.timeout)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> StaticOutboundSettings {
+        StaticOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static StaticOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<StaticOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(StaticOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for StaticOutboundSettings {
+    fn clear(&mut self) {
+        self.actors.clear();
+        self.method.clear();
+        self.weights.clear();
+        self.url.clear();
+        self.interval = 0;
+        self.timeout = 0;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for StaticOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct AMuxOutboundSettings {
+    // message fields
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    pub max_accepts: u32,
+    pub concurrency: u32,
+    pub keep_alive: u32,
+    pub idle_timeout: u32,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a AMuxOutboundSettings {
+    fn default() -> &'a AMuxOutboundSettings {
+        <AMuxOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl AMuxOutboundSettings {
+    pub fn new() -> AMuxOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string address = 1;
+
+
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+
+    // uint32 port = 2;
+
+
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+
+    // repeated string actors = 3;
+
+
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
+    }
+
+    // uint32 max_accepts = 4;
+
+
+    pub fn get_max_accepts(&self) -> u32 {
+        self.max_accepts
+    }
+
+    // uint32 concurrency = 5;
+
+
+    pub fn get_concurrency(&self) -> u32 {
+        self.concurrency
+    }
+
+    // uint32 keep_alive = 6;
+
+
+    pub fn get_keep_alive(&self) -> u32 {
+        self.keep_alive
+    }
+
+    // uint32 idle_timeout = 7;
+
+
+    pub fn get_idle_timeout(&self) -> u32 {
+        self.idle_timeout
+    }
+}
+
+impl ::protobuf::Message for AMuxOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.max_accepts = tmp;
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.concurrency = tmp;
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.keep_alive = tmp;
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.idle_timeout = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        for value in &self.actors {
+            my_size += ::protobuf::rt::string_size(3, &value);
+        };
+        if self.max_accepts != 0 {
+            my_size += ::protobuf::rt::value_size(4, self.max_accepts, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.concurrency != 0 {
+            my_size += ::protobuf::rt::value_size(5, self.concurrency, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.keep_alive != 0 {
+            my_size += ::protobuf::rt::value_size(6, self.keep_alive, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.idle_timeout != 0 {
+            my_size += ::protobuf::rt::value_size(7, self.idle_timeout, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        for v in &self.actors {
+            os.write_string(3, &v)?;
+        };
+        if self.max_accepts != 0 {
+            os.write_uint32(4, self.max_accepts)?;
+        }
+        if self.concurrency != 0 {
+            os.write_uint32(5, self.concurrency)?;
+        }
+        if self.keep_alive != 0 {
+            os.write_uint32(6, self.keep_alive)?;
+        }
+        if self.idle_timeout != 0 {
+            os.write_uint32(7, self.idle_timeout)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> AMuxOutboundSettings {
+        AMuxOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static AMuxOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<AMuxOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(AMuxOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for AMuxOutboundSettings {
+    fn clear(&mut self) {
+        self.address.clear();
+        self.port = 0;
+        self.actors.clear();
+        self.max_accepts = 0;
+        self.concurrency = 0;
+        self.keep_alive = 0;
+        self.idle_timeout = 0;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for AMuxOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct MuxOutboundSettings {
+    // message fields
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    pub max_streams: u32,
+    pub min_connections: u32,
+    pub max_connections: u32,
+    pub padding: bool,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a MuxOutboundSettings {
+    fn default() -> &'a MuxOutboundSettings {
+        <MuxOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl MuxOutboundSettings {
+    pub fn new() -> MuxOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string address = 1;
+
+
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+
+    // uint32 port = 2;
+
+
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+
+    // repeated string actors = 3;
+
+
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
+    }
+
+    // uint32 max_streams = 4;
+
+
+    pub fn get_max_streams(&self) -> u32 {
+        self.max_streams
+    }
+
+    // uint32 min_connections = 5;
+
+
+    pub fn get_min_connections(&self) -> u32 {
+        self.min_connections
+    }
+
+    // uint32 max_connections = 6;
+
+
+    pub fn get_max_connections(&self) -> u32 {
+        self.max_connections
+    }
+
+    // bool padding = 7;
+
+
+    pub fn get_padding(&self) -> bool {
+        self.padding
+    }
+}
+
+impl ::protobuf::Message for MuxOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.max_streams = tmp;
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.min_connections = tmp;
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.max_connections = tmp;
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.padding = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        for value in &self.actors {
+            my_size += ::protobuf::rt::string_size(3, &value);
+        };
+        if self.max_streams != 0 {
+            my_size += ::protobuf::rt::value_size(4, self.max_streams, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.min_connections != 0 {
+            my_size += ::protobuf::rt::value_size(5, self.min_connections, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.max_connections != 0 {
+            my_size += ::protobuf::rt::value_size(6, self.max_connections, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.padding != false {
+            my_size += 2;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        for v in &self.actors {
+            os.write_string(3, &v)?;
+        };
+        if self.max_streams != 0 {
+            os.write_uint32(4, self.max_streams)?;
+        }
+        if self.min_connections != 0 {
+            os.write_uint32(5, self.min_connections)?;
+        }
+        if self.max_connections != 0 {
+            os.write_uint32(6, self.max_connections)?;
+        }
+        if self.padding != false {
+            os.write_bool(7, self.padding)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> MuxOutboundSettings {
+        MuxOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static MuxOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<MuxOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(MuxOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for MuxOutboundSettings {
+    fn clear(&mut self) {
+        self.address.clear();
+        self.port = 0;
+        self.actors.clear();
+        self.max_streams = 0;
+        self.min_connections = 0;
+        self.max_connections = 0;
+        self.padding = false;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for MuxOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct GrpcOutboundSettings {
+    // message fields
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub host: ::std::string::String,
+    pub service_name: ::std::string::String,
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a GrpcOutboundSettings {
+    fn default() -> &'a GrpcOutboundSettings {
+        <GrpcOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl GrpcOutboundSettings {
+    pub fn new() -> GrpcOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string address = 1;
+
+
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+
+    // uint32 port = 2;
+
+
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+
+    // string host = 3;
+
+
+    pub fn get_host(&self) -> &str {
+        &self.host
+    }
+
+    // string service_name = 4;
+
+
+    pub fn get_service_name(&self) -> &str {
+        &self.service_name
+    }
+
+    // repeated string actors = 5;
+
+
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
+    }
+}
+
+impl ::protobuf::Message for GrpcOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.host)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.service_name)?;
+                },
+                5 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.host.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.host);
+        }
+        if !self.service_name.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.service_name);
+        }
+        for value in &self.actors {
+            my_size += ::protobuf::rt::string_size(5, &value);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if !self.host.is_empty() {
+            os.write_string(3, &self.host)?;
+        }
+        if !self.service_name.is_empty() {
+            os.write_string(4, &self.service_name)?;
+        }
+        for v in &self.actors {
+            os.write_string(5, &v)?;
+        };
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> GrpcOutboundSettings {
+        GrpcOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static GrpcOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<GrpcOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(GrpcOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for GrpcOutboundSettings {
+    fn clear(&mut self) {
+        self.address.clear();
+        self.port = 0;
+        self.host.clear();
+        self.service_name.clear();
+        self.actors.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for GrpcOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct Http2OutboundSettings {
+    // message fields
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub host: ::std::string::String,
+    pub path: ::std::string::String,
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a Http2OutboundSettings {
+    fn default() -> &'a Http2OutboundSettings {
+        <Http2OutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl Http2OutboundSettings {
+    pub fn new() -> Http2OutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string address = 1;
+
+
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+
+    // uint32 port = 2;
+
+
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+
+    // string host = 3;
+
+
+    pub fn get_host(&self) -> &str {
+        &self.host
+    }
+
+    // string path = 4;
+
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    // repeated string actors = 5;
+
+
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
+    }
+}
+
+impl ::protobuf::Message for Http2OutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.host)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.path)?;
+                },
+                5 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.host.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.host);
+        }
+        if !self.path.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.path);
+        }
+        for value in &self.actors {
+            my_size += ::protobuf::rt::string_size(5, &value);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if !self.host.is_empty() {
+            os.write_string(3, &self.host)?;
+        }
+        if !self.path.is_empty() {
+            os.write_string(4, &self.path)?;
+        }
+        for v in &self.actors {
+            os.write_string(5, &v)?;
+        };
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> Http2OutboundSettings {
+        Http2OutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static Http2OutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<Http2OutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(Http2OutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for Http2OutboundSettings {
+    fn clear(&mut self) {
+        self.address.clear();
+        self.port = 0;
+        self.host.clear();
+        self.path.clear();
+        self.actors.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for Http2OutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct QuicOutboundSettings {
+    // message fields
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub server_name: ::std::string::String,
+    pub certificate: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a QuicOutboundSettings {
+    fn default() -> &'a QuicOutboundSettings {
+        <QuicOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl QuicOutboundSettings {
+    pub fn new() -> QuicOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string address = 1;
+
+
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+
+    // uint32 port = 2;
+
+
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+
+    // string server_name = 3;
+
+
+    pub fn get_server_name(&self) -> &str {
+        &self.server_name
+    }
+
+    // string certificate = 4;
+
+
+    pub fn get_certificate(&self) -> &str {
+        &self.certificate
+    }
+}
+
+impl ::protobuf::Message for QuicOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.server_name)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.certificate)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.server_name.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.server_name);
+        }
+        if !self.certificate.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.certificate);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if !self.server_name.is_empty() {
+            os.write_string(3, &self.server_name)?;
+        }
+        if !self.certificate.is_empty() {
+            os.write_string(4, &self.certificate)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> QuicOutboundSettings {
+        QuicOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static QuicOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<QuicOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(QuicOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for QuicOutboundSettings {
+    fn clear(&mut self) {
+        self.address.clear();
+        self.port = 0;
+        self.server_name.clear();
+        self.certificate.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for QuicOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct ChainOutboundSettings {
+    // message fields
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a ChainOutboundSettings {
+    fn default() -> &'a ChainOutboundSettings {
+        <ChainOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ChainOutboundSettings {
+    pub fn new() -> ChainOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // repeated string actors = 1;
+
+
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
+    }
+}
+
+impl ::protobuf::Message for ChainOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        for value in &self.actors {
+            my_size += ::protobuf::rt::string_size(1, &value);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        for v in &self.actors {
+            os.write_string(1, &v)?;
+        };
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> ChainOutboundSettings {
+        ChainOutboundSettings::new()
+    }
+
+    fn default_instance() -> &'static ChainOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<ChainOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(ChainOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for ChainOutboundSettings {
+    fn clear(&mut self) {
+        self.actors.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ChainOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct FailOverOutboundSettings {
+    // message fields
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    pub fail_timeout: u32,
+    pub health_check: bool,
+    pub check_interval: u32,
+    pub failover: bool,
+    pub fallback_cache: bool,
+    pub cache_size: u32,
+    pub cache_timeout: u32,
+    pub last_resort: ::std::string::String,
+    pub health_check_timeout: u32,
+    pub eject_threshold: u32,
+    pub eject_duration: u32,
+    pub max_eject_duration: u32,
+    pub health_check_url: ::std::string::String,
+    pub health_check_status: u32,
+    pub health_check_concurrency: u32,
+    pub udp_health_check: bool,
+    pub udp_health_check_server: ::std::string::String,
+    pub filter: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a FailOverOutboundSettings {
+    fn default() -> &'a FailOverOutboundSettings {
+        <FailOverOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl FailOverOutboundSettings {
+    pub fn new() -> FailOverOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // repeated string actors = 1;
+
+
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
+    }
+
+    // uint32 fail_timeout = 2;
+
+
+    pub fn get_fail_timeout(&self) -> u32 {
         self.fail_timeout
     }
 
-    // bool health_check = 3;
+    // bool health_check = 3;
+
+
+    pub fn get_health_check(&self) -> bool {
+        self.health_check
+    }
+
+    // uint32 check_interval = 4;
+
+
+    pub fn get_check_interval(&self) -> u32 {
+        self.check_interval
+    }
+
+    // bool failover = 5;
+
+
+    pub fn get_failover(&self) -> bool {
+        self.failover
+    }
+
+    // bool fallback_cache = 6;
+
+
+    pub fn get_fallback_cache(&self) -> bool {
+        self.fallback_cache
+    }
+
+    // uint32 cache_size = 7;
+
+
+    pub fn get_cache_size(&self) -> u32 {
+        self.cache_size
+    }
+
+    // uint32 cache_timeout = 8;
+
+
+    pub fn get_cache_timeout(&self) -> u32 {
+        self.cache_timeout
+    }
+
+    // string last_resort = 9;
+
+
+    pub fn get_last_resort(&self) -> &str {
+        &self.last_resort
+    }
+
+    // uint32 health_check_timeout = 10;
+
+
+    pub fn get_health_check_timeout(&self) -> u32 {
+        self.health_check_timeout
+    }
+
+    // uint32 eject_threshold = 11;
+
+
+    pub fn get_eject_threshold(&self) -> u32 {
+        self.eject_threshold
+    }
+
+    // uint32 eject_duration = 12;
+
+
+    pub fn get_eject_duration(&self) -> u32 {
+        self.eject_duration
+    }
+
+    // uint32 max_eject_duration = 13;
+
+
+    pub fn get_max_eject_duration(&self) -> u32 {
+        self.max_eject_duration
+    }
+
+    // string health_check_url = 14;
+
+
+    pub fn get_health_check_url(&self) -> &str {
+        &self.health_check_url
+    }
+
+    // uint32 health_check_status = 15;
+
+
+    pub fn get_health_check_status(&self) -> u32 {
+        self.health_check_status
+    }
+
+    // uint32 health_check_concurrency = 16;
+
+
+    pub fn get_health_check_concurrency(&self) -> u32 {
+        self.health_check_concurrency
+    }
+
+    // bool udp_health_check = 17;
+
+
+    pub fn get_udp_health_check(&self) -> bool {
+        self.udp_health_check
+    }
+
+    // string udp_health_check_server = 18;
+
+
+    pub fn get_udp_health_check_server(&self) -> &str {
+        &self.udp_health_check_server
+    }
+
+    // string filter = 19;
+
+
+    pub fn get_filter(&self) -> &str {
+        &self.filter
+    }
+}
+
+impl ::protobuf::Message for FailOverOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.fail_timeout = tmp;
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.health_check = tmp;
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.check_interval = tmp;
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.failover = tmp;
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.fallback_cache = tmp;
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.cache_size = tmp;
+                },
+                8 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.cache_timeout = tmp;
+                },
+                9 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.last_resort)?;
+                },
+                10 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.health_check_timeout = tmp;
+                },
+                11 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.eject_threshold = tmp;
+                },
+                12 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.eject_duration = tmp;
+                },
+                13 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.max_eject_duration = tmp;
+                },
+                14 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.health_check_url)?;
+                },
+                15 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.health_check_status = tmp;
+                },
+                16 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.health_check_concurrency = tmp;
+                },
+                17 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.udp_health_check = tmp;
+                },
+                18 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.udp_health_check_server)?;
+                },
+                19 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.filter)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        for value in &self.actors {
+            my_size += ::protobuf::rt::string_size(1, &value);
+        };
+        if self.fail_timeout != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.fail_timeout, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.health_check != false {
+            my_size += 2;
+        }
+        if self.check_interval != 0 {
+            my_size += ::protobuf::rt::value_size(4, self.check_interval, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.failover != false {
+            my_size += 2;
+        }
+        if self.fallback_cache != false {
+            my_size += 2;
+        }
+        if self.cache_size != 0 {
+            my_size += ::protobuf::rt::value_size(7, self.cache_size, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.cache_timeout != 0 {
+            my_size += ::protobuf::rt::value_size(8, self.cache_timeout, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.last_resort.is_empty() {
+            my_size += ::protobuf::rt::string_size(9, &self.last_resort);
+        }
+        if self.health_check_timeout != 0 {
+            my_size += ::protobuf::rt::value_size(10, self.health_check_timeout, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.eject_threshold != 0 {
+            my_size += ::protobuf::rt::value_size(11, self.eject_threshold, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.eject_duration != 0 {
+            my_size += ::protobuf::rt::value_size(12, self.eject_duration, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.max_eject_duration != 0 {
+            my_size += ::protobuf::rt::value_size(13, self.max_eject_duration, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.health_check_url.is_empty() {
+            my_size += ::protobuf::rt::string_size(14, &self.health_check_url);
+        }
+        if self.health_check_status != 0 {
+            my_size += ::protobuf::rt::value_size(15, self.health_check_status, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.health_check_concurrency != 0 {
+            my_size += ::protobuf::rt::value_size(16, self.health_check_concurrency, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.udp_health_check != false {
+            my_size += 3;
+        }
+        if !self.udp_health_check_server.is_empty() {
+            my_size += ::protobuf::rt::string_size(18, &self.udp_health_check_server);
+        }
+        if !self.filter.is_empty() {
+            my_size += ::protobuf::rt::string_size(19, &self.filter);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        for v in &self.actors {
+            os.write_string(1, &v)?;
+        };
+        if self.fail_timeout != 0 {
+            os.write_uint32(2, self.fail_timeout)?;
+        }
+        if self.health_check != false {
+            os.write_bool(3, self.health_check)?;
+        }
+        if self.check_interval != 0 {
+            os.write_uint32(4, self.check_interval)?;
+        }
+        if self.failover != false {
+            os.write_bool(5, self.failover)?;
+        }
+        if self.fallback_cache != false {
+            os.write_bool(6, self.fallback_cache)?;
+        }
+        if self.cache_size != 0 {
+            os.write_uint32(7, self.cache_size)?;
+        }
+        if self.cache_timeout != 0 {
+            os.write_uint32(8, self.cache_timeout)?;
+        }
+        if !self.last_resort.is_empty() {
+            os.write_string(9, &self.last_resort)?;
+        }
+        if self.health_check_timeout != 0 {
+            os.write_uint32(10, self.health_check_timeout)?;
+        }
+        if self.eject_threshold != 0 {
+            os.write_uint32(11, self.eject_threshold)?;
+        }
+        if self.eject_duration != 0 {
+            os.write_uint32(12, self.eject_duration)?;
+        }
+        if self.max_eject_duration != 0 {
+            os.write_uint32(13, self.max_eject_duration)?;
+        }
+        if !self.health_check_url.is_empty() {
+            os.write_string(14, &self.health_check_url)?;
+        }
+        if self.health_check_status != 0 {
+            os.write_uint32(15, self.health_check_status)?;
+        }
+        if self.health_check_concurrency != 0 {
+            os.write_uint32(16, self.health_check_concurrency)?;
+        }
+        if self.udp_health_check != false {
+            os.write_bool(17, self.udp_health_check)?;
+        }
+        if !self.udp_health_check_server.is_empty() {
+            os.write_string(18, &self.udp_health_check_server)?;
+        }
+        if !self.filter.is_empty() {
+            os.write_string(19, &self.filter)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
 
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
 
-    pub fn get_health_check(&self) -> bool {
-        self.health_check
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
     }
 
-    // uint32 check_interval = 4;
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
 
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
 
-    pub fn get_check_interval(&self) -> u32 {
-        self.check_interval
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
     }
 
-    // bool failover = 5;
+    fn new() -> FailOverOutboundSettings {
+        FailOverOutboundSettings::new()
+    }
 
+    fn default_instance() -> &'static FailOverOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<FailOverOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(FailOverOutboundSettings::new)
+    }
+}
 
-    pub fn get_failover(&self) -> bool {
-        self.failover
+impl ::protobuf::Clear for FailOverOutboundSettings {
+    fn clear(&mut self) {
+        self.actors.clear();
+        self.fail_timeout = 0;
+        self.health_check = false;
+        self.check_interval = 0;
+        self.failover = false;
+        self.fallback_cache = false;
+        self.cache_size = 0;
+        self.cache_timeout = 0;
+        self.last_resort.clear();
+        self.health_check_timeout = 0;
+        self.eject_threshold = 0;
+        self.eject_duration = 0;
+        self.max_eject_duration = 0;
+        self.health_check_url.clear();
+        self.health_check_status = 0;
+        self.health_check_concurrency = 0;
+        self.udp_health_check = false;
+        self.udp_health_check_server.clear();
+        self.filter.clear();
+        self.unknown_fields.clear();
     }
+}
 
-    // bool fallback_cache = 6;
+impl ::protobuf::reflect::ProtobufValue for FailOverOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct UrlTestOutboundSettings {
+    // message fields
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    pub url: ::std::string::String,
+    pub interval: u32,
+    pub tolerance: u32,
+    pub timeout: u32,
+    pub filter: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
 
+impl<'a> ::std::default::Default for &'a UrlTestOutboundSettings {
+    fn default() -> &'a UrlTestOutboundSettings {
+        <UrlTestOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
 
-    pub fn get_fallback_cache(&self) -> bool {
-        self.fallback_cache
+impl UrlTestOutboundSettings {
+    pub fn new() -> UrlTestOutboundSettings {
+        ::std::default::Default::default()
     }
 
-    // uint32 cache_size = 7;
+    // repeated string actors = 1;
 
 
-    pub fn get_cache_size(&self) -> u32 {
-        self.cache_size
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
     }
 
-    // uint32 cache_timeout = 8;
+    // string url = 2;
 
 
-    pub fn get_cache_timeout(&self) -> u32 {
-        self.cache_timeout
+    pub fn get_url(&self) -> &str {
+        &self.url
     }
 
-    // string last_resort = 9;
+    // uint32 interval = 3;
 
 
-    pub fn get_last_resort(&self) -> &str {
-        &self.last_resort
+    pub fn get_interval(&self) -> u32 {
+        self.interval
+    }
+
+    // uint32 tolerance = 4;
+
+
+    pub fn get_tolerance(&self) -> u32 {
+        self.tolerance
+    }
+
+    // uint32 timeout = 5;
+
+
+    pub fn get_timeout(&self) -> u32 {
+        self.timeout
     }
 
-    // uint32 health_check_timeout = 10;
+    // string filter = 6;
 
 
-    pub fn get_health_check_timeout(&self) -> u32 {
-        self.health_check_timeout
+    pub fn get_filter(&self) -> &str {
+        &self.filter
     }
 }
 
-impl ::protobuf::Message for FailOverOutboundSettings {
+impl ::protobuf::Message for UrlTestOutboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -3632,63 +8285,31 @@ impl ::protobuf::Message for FailOverOutboundSettings {
                     ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
                 },
                 2 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_uint32()?;
-                    self.fail_timeout = tmp;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.url)?;
                 },
                 3 => {
                     if wire_type != ::protobuf::wire_format::WireTypeVarint {
                         return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
                     }
-                    let tmp = is.read_bool()?;
-                    self.health_check = tmp;
+                    let tmp = is.read_uint32()?;
+                    self.interval = tmp;
                 },
                 4 => {
                     if wire_type != ::protobuf::wire_format::WireTypeVarint {
                         return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
                     }
                     let tmp = is.read_uint32()?;
-                    self.check_interval = tmp;
+                    self.tolerance = tmp;
                 },
                 5 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_bool()?;
-                    self.failover = tmp;
-                },
-                6 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_bool()?;
-                    self.fallback_cache = tmp;
-                },
-                7 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_uint32()?;
-                    self.cache_size = tmp;
-                },
-                8 => {
                     if wire_type != ::protobuf::wire_format::WireTypeVarint {
                         return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
                     }
                     let tmp = is.read_uint32()?;
-                    self.cache_timeout = tmp;
-                },
-                9 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.last_resort)?;
+                    self.timeout = tmp;
                 },
-                10 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_uint32()?;
-                    self.health_check_timeout = tmp;
+                6 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.filter)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -3705,32 +8326,20 @@ impl ::protobuf::Message for FailOverOutboundSettings {
         for value in &self.actors {
             my_size += ::protobuf::rt::string_size(1, &value);
         };
-        if self.fail_timeout != 0 {
-            my_size += ::protobuf::rt::value_size(2, self.fail_timeout, ::protobuf::wire_format::WireTypeVarint);
-        }
-        if self.health_check != false {
-            my_size += 2;
-        }
-        if self.check_interval != 0 {
-            my_size += ::protobuf::rt::value_size(4, self.check_interval, ::protobuf::wire_format::WireTypeVarint);
-        }
-        if self.failover != false {
-            my_size += 2;
+        if !self.url.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.url);
         }
-        if self.fallback_cache != false {
-            my_size += 2;
-        }
-        if self.cache_size != 0 {
-            my_size += ::protobuf::rt::value_size(7, self.cache_size, ::protobuf::wire_format::WireTypeVarint);
+        if self.interval != 0 {
+            my_size += ::protobuf::rt::value_size(3, self.interval, ::protobuf::wire_format::WireTypeVarint);
         }
-        if self.cache_timeout != 0 {
-            my_size += ::protobuf::rt::value_size(8, self.cache_timeout, ::protobuf::wire_format::WireTypeVarint);
+        if self.tolerance != 0 {
+            my_size += ::protobuf::rt::value_size(4, self.tolerance, ::protobuf::wire_format::WireTypeVarint);
         }
-        if !self.last_resort.is_empty() {
-            my_size += ::protobuf::rt::string_size(9, &self.last_resort);
+        if self.timeout != 0 {
+            my_size += ::protobuf::rt::value_size(5, self.timeout, ::protobuf::wire_format::WireTypeVarint);
         }
-        if self.health_check_timeout != 0 {
-            my_size += ::protobuf::rt::value_size(10, self.health_check_timeout, ::protobuf::wire_format::WireTypeVarint);
+        if !self.filter.is_empty() {
+            my_size += ::protobuf::rt::string_size(6, &self.filter);
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -3741,32 +8350,20 @@ impl ::protobuf::Message for FailOverOutboundSettings {
         for v in &self.actors {
             os.write_string(1, &v)?;
         };
-        if self.fail_timeout != 0 {
-            os.write_uint32(2, self.fail_timeout)?;
-        }
-        if self.health_check != false {
-            os.write_bool(3, self.health_check)?;
-        }
-        if self.check_interval != 0 {
-            os.write_uint32(4, self.check_interval)?;
-        }
-        if self.failover != false {
-            os.write_bool(5, self.failover)?;
-        }
-        if self.fallback_cache != false {
-            os.write_bool(6, self.fallback_cache)?;
+        if !self.url.is_empty() {
+            os.write_string(2, &self.url)?;
         }
-        if self.cache_size != 0 {
-            os.write_uint32(7, self.cache_size)?;
+        if self.interval != 0 {
+            os.write_uint32(3, self.interval)?;
         }
-        if self.cache_timeout != 0 {
-            os.write_uint32(8, self.cache_timeout)?;
+        if self.tolerance != 0 {
+            os.write_uint32(4, self.tolerance)?;
         }
-        if !self.last_resort.is_empty() {
-            os.write_string(9, &self.last_resort)?;
+        if self.timeout != 0 {
+            os.write_uint32(5, self.timeout)?;
         }
-        if self.health_check_timeout != 0 {
-            os.write_uint32(10, self.health_check_timeout)?;
+        if !self.filter.is_empty() {
+            os.write_string(6, &self.filter)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -3798,33 +8395,29 @@ impl ::protobuf::Message for FailOverOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> FailOverOutboundSettings {
-        FailOverOutboundSettings::new()
+    fn new() -> UrlTestOutboundSettings {
+        UrlTestOutboundSettings::new()
     }
 
-    fn default_instance() -> &'static FailOverOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<FailOverOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(FailOverOutboundSettings::new)
+    fn default_instance() -> &'static UrlTestOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<UrlTestOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(UrlTestOutboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for FailOverOutboundSettings {
+impl ::protobuf::Clear for UrlTestOutboundSettings {
     fn clear(&mut self) {
         self.actors.clear();
-        self.fail_timeout = 0;
-        self.health_check = false;
-        self.check_interval = 0;
-        self.failover = false;
-        self.fallback_cache = false;
-        self.cache_size = 0;
-        self.cache_timeout = 0;
-        self.last_resort.clear();
-        self.health_check_timeout = 0;
+        self.url.clear();
+        self.interval = 0;
+        self.tolerance = 0;
+        self.timeout = 0;
+        self.filter.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for FailOverOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for UrlTestOutboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
@@ -3834,6 +8427,7 @@ impl ::protobuf::reflect::ProtobufValue for FailOverOutboundSettings {
 pub struct SelectOutboundSettings {
     // message fields
     pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    pub filter: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -3856,6 +8450,13 @@ impl SelectOutboundSettings {
     pub fn get_actors(&self) -> &[::std::string::String] {
         &self.actors
     }
+
+    // string filter = 2;
+
+
+    pub fn get_filter(&self) -> &str {
+        &self.filter
+    }
 }
 
 impl ::protobuf::Message for SelectOutboundSettings {
@@ -3870,6 +8471,9 @@ impl ::protobuf::Message for SelectOutboundSettings {
                 1 => {
                     ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
                 },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.filter)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -3885,6 +8489,9 @@ impl ::protobuf::Message for SelectOutboundSettings {
         for value in &self.actors {
             my_size += ::protobuf::rt::string_size(1, &value);
         };
+        if !self.filter.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.filter);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -3894,6 +8501,9 @@ impl ::protobuf::Message for SelectOutboundSettings {
         for v in &self.actors {
             os.write_string(1, &v)?;
         };
+        if !self.filter.is_empty() {
+            os.write_string(2, &self.filter)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -3937,6 +8547,7 @@ impl ::protobuf::Message for SelectOutboundSettings {
 impl ::protobuf::Clear for SelectOutboundSettings {
     fn clear(&mut self) {
         self.actors.clear();
+        self.filter.clear();
         self.unknown_fields.clear();
     }
 }
@@ -4088,6 +8699,12 @@ pub struct Outbound {
     pub tag: ::std::string::String,
     pub protocol: ::std::string::String,
     pub settings: ::std::vec::Vec<u8>,
+    pub bind_interface: ::std::string::String,
+    pub bind_addresses: ::protobuf::RepeatedField<::std::string::String>,
+    pub domain_strategy: Outbound_DomainStrategy,
+    pub tcp_fast_open: bool,
+    pub connect_timeout: u32,
+    pub handshake_timeout: u32,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -4124,6 +8741,48 @@ impl Outbound {
     pub fn get_settings(&self) -> &[u8] {
         &self.settings
     }
+
+    // string bind_interface = 5;
+
+
+    pub fn get_bind_interface(&self) -> &str {
+        &self.bind_interface
+    }
+
+    // repeated string bind_addresses = 6;
+
+
+    pub fn get_bind_addresses(&self) -> &[::std::string::String] {
+        &self.bind_addresses
+    }
+
+    // .Outbound.DomainStrategy domain_strategy = 7;
+
+
+    pub fn get_domain_strategy(&self) -> Outbound_DomainStrategy {
+        self.domain_strategy
+    }
+
+    // bool tcp_fast_open = 8;
+
+
+    pub fn get_tcp_fast_open(&self) -> bool {
+        self.tcp_fast_open
+    }
+
+    // uint32 connect_timeout = 9;
+
+
+    pub fn get_connect_timeout(&self) -> u32 {
+        self.connect_timeout
+    }
+
+    // uint32 handshake_timeout = 10;
+
+
+    pub fn get_handshake_timeout(&self) -> u32 {
+        self.handshake_timeout
+    }
 }
 
 impl ::protobuf::Message for Outbound {
@@ -4144,6 +8803,36 @@ impl ::protobuf::Message for Outbound {
                 4 => {
                     ::protobuf::rt::read_singular_proto3_bytes_into(wire_type, is, &mut self.settings)?;
                 },
+                5 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.bind_interface)?;
+                },
+                6 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.bind_addresses)?;
+                },
+                7 => {
+                    ::protobuf::rt::read_proto3_enum_with_unknown_fields_into(wire_type, is, &mut self.domain_strategy, 7, &mut self.unknown_fields)?
+                },
+                8 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.tcp_fast_open = tmp;
+                },
+                9 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.connect_timeout = tmp;
+                },
+                10 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.handshake_timeout = tmp;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -4165,6 +8854,24 @@ impl ::protobuf::Message for Outbound {
         if !self.settings.is_empty() {
             my_size += ::protobuf::rt::bytes_size(4, &self.settings);
         }
+        if !self.bind_interface.is_empty() {
+            my_size += ::protobuf::rt::string_size(5, &self.bind_interface);
+        }
+        for value in &self.bind_addresses {
+            my_size += ::protobuf::rt::string_size(6, &value);
+        };
+        if self.domain_strategy != Outbound_DomainStrategy::AS_IS {
+            my_size += ::protobuf::rt::enum_size(7, self.domain_strategy);
+        }
+        if self.tcp_fast_open != false {
+            my_size += 2;
+        }
+        if self.connect_timeout != 0 {
+            my_size += ::protobuf::rt::value_size(9, self.connect_timeout, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.handshake_timeout != 0 {
+            my_size += ::protobuf::rt::value_size(10, self.handshake_timeout, ::protobuf::wire_format::WireTypeVarint);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -4180,6 +8887,24 @@ impl ::protobuf::Message for Outbound {
         if !self.settings.is_empty() {
             os.write_bytes(4, &self.settings)?;
         }
+        if !self.bind_interface.is_empty() {
+            os.write_string(5, &self.bind_interface)?;
+        }
+        for v in &self.bind_addresses {
+            os.write_string(6, &v)?;
+        };
+        if self.domain_strategy != Outbound_DomainStrategy::AS_IS {
+            os.write_enum(7, ::protobuf::ProtobufEnum::value(&self.domain_strategy))?;
+        }
+        if self.tcp_fast_open != false {
+            os.write_bool(8, self.tcp_fast_open)?;
+        }
+        if self.connect_timeout != 0 {
+            os.write_uint32(9, self.connect_timeout)?;
+        }
+        if self.handshake_timeout != 0 {
+            os.write_uint32(10, self.handshake_timeout)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -4225,6 +8950,12 @@ impl ::protobuf::Clear for Outbound {
         self.tag.clear();
         self.protocol.clear();
         self.settings.clear();
+        self.bind_interface.clear();
+        self.bind_addresses.clear();
+        self.domain_strategy = Outbound_DomainStrategy::AS_IS;
+        self.tcp_fast_open = false;
+        self.connect_timeout = 0;
+        self.handshake_timeout = 0;
         self.unknown_fields.clear();
     }
 }
@@ -4235,11 +8966,64 @@ impl ::protobuf::reflect::ProtobufValue for Outbound {
     }
 }
 
+#[derive(Clone,PartialEq,Eq,Debug,Hash)]
+pub enum Outbound_DomainStrategy {
+    AS_IS = 0,
+    PREFER_IPV4 = 1,
+    PREFER_IPV6 = 2,
+    IPV4_ONLY = 3,
+    IPV6_ONLY = 4,
+}
+
+impl ::protobuf::ProtobufEnum for Outbound_DomainStrategy {
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<Outbound_DomainStrategy> {
+        match value {
+            0 => ::std::option::Option::Some(Outbound_DomainStrategy::AS_IS),
+            1 => ::std::option::Option::Some(Outbound_DomainStrategy::PREFER_IPV4),
+            2 => ::std::option::Option::Some(Outbound_DomainStrategy::PREFER_IPV6),
+            3 => ::std::option::Option::Some(Outbound_DomainStrategy::IPV4_ONLY),
+            4 => ::std::option::Option::Some(Outbound_DomainStrategy::IPV6_ONLY),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    fn values() -> &'static [Self] {
+        static values: &'static [Outbound_DomainStrategy] = &[
+            Outbound_DomainStrategy::AS_IS,
+            Outbound_DomainStrategy::PREFER_IPV4,
+            Outbound_DomainStrategy::PREFER_IPV6,
+            Outbound_DomainStrategy::IPV4_ONLY,
+            Outbound_DomainStrategy::IPV6_ONLY,
+        ];
+        values
+    }
+}
+
+impl ::std::marker::Copy for Outbound_DomainStrategy {
+}
+
+impl ::std::default::Default for Outbound_DomainStrategy {
+    fn default() -> Self {
+        Outbound_DomainStrategy::AS_IS
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for Outbound_DomainStrategy {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Enum(::protobuf::ProtobufEnum::descriptor(self))
+    }
+}
+
 #[derive(PartialEq,Clone,Default,Debug)]
 pub struct Router {
     // message fields
     pub rules: ::protobuf::RepeatedField<Router_Rule>,
     pub domain_resolve: bool,
+    pub script: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -4269,6 +9053,13 @@ impl Router {
     pub fn get_domain_resolve(&self) -> bool {
         self.domain_resolve
     }
+
+    // string script = 3;
+
+
+    pub fn get_script(&self) -> &str {
+        &self.script
+    }
 }
 
 impl ::protobuf::Message for Router {
@@ -4295,6 +9086,9 @@ impl ::protobuf::Message for Router {
                     let tmp = is.read_bool()?;
                     self.domain_resolve = tmp;
                 },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.script)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -4314,6 +9108,9 @@ impl ::protobuf::Message for Router {
         if self.domain_resolve != false {
             my_size += 2;
         }
+        if !self.script.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.script);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -4328,6 +9125,9 @@ impl ::protobuf::Message for Router {
         if self.domain_resolve != false {
             os.write_bool(2, self.domain_resolve)?;
         }
+        if !self.script.is_empty() {
+            os.write_string(3, &self.script)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -4372,6 +9172,7 @@ impl ::protobuf::Clear for Router {
     fn clear(&mut self) {
         self.rules.clear();
         self.domain_resolve = false;
+        self.script.clear();
         self.unknown_fields.clear();
     }
 }
@@ -4392,6 +9193,17 @@ pub struct Router_Rule {
     pub port_ranges: ::protobuf::RepeatedField<::std::string::String>,
     pub networks: ::protobuf::RepeatedField<::std::string::String>,
     pub inbound_tags: ::protobuf::RepeatedField<::std::string::String>,
+    pub apps: ::protobuf::RepeatedField<::std::string::String>,
+    pub rule_sets: ::protobuf::RepeatedField<::std::string::String>,
+    pub process_names: ::protobuf::RepeatedField<::std::string::String>,
+    pub process_paths: ::protobuf::RepeatedField<::std::string::String>,
+    pub source_ips: ::protobuf::RepeatedField<::std::string::String>,
+    pub source_ports: ::protobuf::RepeatedField<::std::string::String>,
+    pub and_rules: ::protobuf::RepeatedField<Router_Rule>,
+    pub or_rules: ::protobuf::RepeatedField<Router_Rule>,
+    pub not_rules: ::protobuf::RepeatedField<Router_Rule>,
+    pub no_resolve: bool,
+    pub schedules: ::protobuf::RepeatedField<::std::string::String>,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -4456,6 +9268,83 @@ impl Router_Rule {
     pub fn get_inbound_tags(&self) -> &[::std::string::String] {
         &self.inbound_tags
     }
+
+    // repeated string apps = 8;
+
+
+    pub fn get_apps(&self) -> &[::std::string::String] {
+        &self.apps
+    }
+
+    // repeated string rule_sets = 9;
+
+
+    pub fn get_rule_sets(&self) -> &[::std::string::String] {
+        &self.rule_sets
+    }
+
+    // repeated string process_names = 10;
+
+
+    pub fn get_process_names(&self) -> &[::std::string::String] {
+        &self.process_names
+    }
+
+    // repeated string process_paths = 11;
+
+
+    pub fn get_process_paths(&self) -> &[::std::string::String] {
+        &self.process_paths
+    }
+
+    // repeated string source_ips = 12;
+
+
+    pub fn get_source_ips(&self) -> &[::std::string::String] {
+        &self.source_ips
+    }
+
+    // repeated string source_ports = 13;
+
+
+    pub fn get_source_ports(&self) -> &[::std::string::String] {
+        &self.source_ports
+    }
+
+    // repeated .Router.Rule and_rules = 14;
+
+
+    pub fn get_and_rules(&self) -> &[Router_Rule] {
+        &self.and_rules
+    }
+
+    // repeated .Router.Rule or_rules = 15;
+
+
+    pub fn get_or_rules(&self) -> &[Router_Rule] {
+        &self.or_rules
+    }
+
+    // repeated .Router.Rule not_rules = 16;
+
+
+    pub fn get_not_rules(&self) -> &[Router_Rule] {
+        &self.not_rules
+    }
+
+    // bool no_resolve = 17;
+
+
+    pub fn get_no_resolve(&self) -> bool {
+        self.no_resolve
+    }
+
+    // repeated string schedules = 18;
+
+
+    pub fn get_schedules(&self) -> &[::std::string::String] {
+        &self.schedules
+    }
 }
 
 impl ::protobuf::Message for Router_Rule {
@@ -4470,6 +9359,21 @@ impl ::protobuf::Message for Router_Rule {
                 return false;
             }
         };
+        for v in &self.and_rules {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        for v in &self.or_rules {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        for v in &self.not_rules {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
         true
     }
 
@@ -4498,6 +9402,43 @@ impl ::protobuf::Message for Router_Rule {
                 7 => {
                     ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.inbound_tags)?;
                 },
+                8 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.apps)?;
+                },
+                9 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.rule_sets)?;
+                },
+                10 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.process_names)?;
+                },
+                11 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.process_paths)?;
+                },
+                12 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.source_ips)?;
+                },
+                13 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.source_ports)?;
+                },
+                14 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.and_rules)?;
+                },
+                15 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.or_rules)?;
+                },
+                16 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.not_rules)?;
+                },
+                17 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.no_resolve = tmp;
+                },
+                18 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.schedules)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -4533,6 +9474,42 @@ impl ::protobuf::Message for Router_Rule {
         for value in &self.inbound_tags {
             my_size += ::protobuf::rt::string_size(7, &value);
         };
+        for value in &self.apps {
+            my_size += ::protobuf::rt::string_size(8, &value);
+        };
+        for value in &self.rule_sets {
+            my_size += ::protobuf::rt::string_size(9, &value);
+        };
+        for value in &self.process_names {
+            my_size += ::protobuf::rt::string_size(10, &value);
+        };
+        for value in &self.process_paths {
+            my_size += ::protobuf::rt::string_size(11, &value);
+        };
+        for value in &self.source_ips {
+            my_size += ::protobuf::rt::string_size(12, &value);
+        };
+        for value in &self.source_ports {
+            my_size += ::protobuf::rt::string_size(13, &value);
+        };
+        for value in &self.and_rules {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        for value in &self.or_rules {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        for value in &self.not_rules {
+            let len = value.compute_size();
+            my_size += 2 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        if self.no_resolve != false {
+            my_size += 3;
+        }
+        for value in &self.schedules {
+            my_size += ::protobuf::rt::string_size(18, &value);
+        };
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -4564,6 +9541,45 @@ impl ::protobuf::Message for Router_Rule {
         for v in &self.inbound_tags {
             os.write_string(7, &v)?;
         };
+        for v in &self.apps {
+            os.write_string(8, &v)?;
+        };
+        for v in &self.rule_sets {
+            os.write_string(9, &v)?;
+        };
+        for v in &self.process_names {
+            os.write_string(10, &v)?;
+        };
+        for v in &self.process_paths {
+            os.write_string(11, &v)?;
+        };
+        for v in &self.source_ips {
+            os.write_string(12, &v)?;
+        };
+        for v in &self.source_ports {
+            os.write_string(13, &v)?;
+        };
+        for v in &self.and_rules {
+            os.write_tag(14, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        for v in &self.or_rules {
+            os.write_tag(15, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        for v in &self.not_rules {
+            os.write_tag(16, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        if self.no_resolve != false {
+            os.write_bool(17, self.no_resolve)?;
+        }
+        for v in &self.schedules {
+            os.write_string(18, &v)?;
+        };
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -4613,6 +9629,17 @@ impl ::protobuf::Clear for Router_Rule {
         self.port_ranges.clear();
         self.networks.clear();
         self.inbound_tags.clear();
+        self.apps.clear();
+        self.rule_sets.clear();
+        self.process_names.clear();
+        self.process_paths.clear();
+        self.source_ips.clear();
+        self.source_ports.clear();
+        self.and_rules.clear();
+        self.or_rules.clear();
+        self.not_rules.clear();
+        self.no_resolve = false;
+        self.schedules.clear();
         self.unknown_fields.clear();
     }
 }
@@ -4763,6 +9790,7 @@ pub enum Router_Rule_Domain_Type {
     PLAIN = 0,
     DOMAIN = 1,
     FULL = 2,
+    REGEX = 3,
 }
 
 impl ::protobuf::ProtobufEnum for Router_Rule_Domain_Type {
@@ -4775,6 +9803,7 @@ impl ::protobuf::ProtobufEnum for Router_Rule_Domain_Type {
             0 => ::std::option::Option::Some(Router_Rule_Domain_Type::PLAIN),
             1 => ::std::option::Option::Some(Router_Rule_Domain_Type::DOMAIN),
             2 => ::std::option::Option::Some(Router_Rule_Domain_Type::FULL),
+            3 => ::std::option::Option::Some(Router_Rule_Domain_Type::REGEX),
             _ => ::std::option::Option::None
         }
     }
@@ -4784,6 +9813,7 @@ impl ::protobuf::ProtobufEnum for Router_Rule_Domain_Type {
             Router_Rule_Domain_Type::PLAIN,
             Router_Rule_Domain_Type::DOMAIN,
             Router_Rule_Domain_Type::FULL,
+            Router_Rule_Domain_Type::REGEX,
         ];
         values
     }
@@ -4809,6 +9839,7 @@ pub struct Router_Rule_Mmdb {
     // message fields
     pub file: ::std::string::String,
     pub country_code: ::std::string::String,
+    pub asn: u32,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -4838,6 +9869,13 @@ impl Router_Rule_Mmdb {
     pub fn get_country_code(&self) -> &str {
         &self.country_code
     }
+
+    // uint32 asn = 3;
+
+
+    pub fn get_asn(&self) -> u32 {
+        self.asn
+    }
 }
 
 impl ::protobuf::Message for Router_Rule_Mmdb {
@@ -4855,6 +9893,13 @@ impl ::protobuf::Message for Router_Rule_Mmdb {
                 2 => {
                     ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.country_code)?;
                 },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.asn = tmp;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -4873,6 +9918,9 @@ impl ::protobuf::Message for Router_Rule_Mmdb {
         if !self.country_code.is_empty() {
             my_size += ::protobuf::rt::string_size(2, &self.country_code);
         }
+        if self.asn != 0 {
+            my_size += ::protobuf::rt::value_size(3, self.asn, ::protobuf::wire_format::WireTypeVarint);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -4885,6 +9933,9 @@ impl ::protobuf::Message for Router_Rule_Mmdb {
         if !self.country_code.is_empty() {
             os.write_string(2, &self.country_code)?;
         }
+        if self.asn != 0 {
+            os.write_uint32(3, self.asn)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -4929,6 +9980,7 @@ impl ::protobuf::Clear for Router_Rule_Mmdb {
     fn clear(&mut self) {
         self.file.clear();
         self.country_code.clear();
+        self.asn = 0;
         self.unknown_fields.clear();
     }
 }
@@ -4939,6 +9991,199 @@ impl ::protobuf::reflect::ProtobufValue for Router_Rule_Mmdb {
     }
 }
 
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct Provider {
+    // message fields
+    pub tag: ::std::string::String,
+    pub url: ::std::string::String,
+    pub path: ::std::string::String,
+    pub interval: u32,
+    pub format: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a Provider {
+    fn default() -> &'a Provider {
+        <Provider as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl Provider {
+    pub fn new() -> Provider {
+        ::std::default::Default::default()
+    }
+
+    // string tag = 1;
+
+
+    pub fn get_tag(&self) -> &str {
+        &self.tag
+    }
+
+    // string url = 2;
+
+
+    pub fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    // string path = 3;
+
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    // uint32 interval = 4;
+
+
+    pub fn get_interval(&self) -> u32 {
+        self.interval
+    }
+
+    // string format = 5;
+
+
+    pub fn get_format(&self) -> &str {
+        &self.format
+    }
+}
+
+impl ::protobuf::Message for Provider {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.tag)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.url)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.path)?;
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.interval = tmp;
+                },
+                5 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.format)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.tag.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.tag);
+        }
+        if !self.url.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.url);
+        }
+        if !self.path.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.path);
+        }
+        if self.interval != 0 {
+            my_size += ::protobuf::rt::value_size(4, self.interval, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.format.is_empty() {
+            my_size += ::protobuf::rt::string_size(5, &self.format);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.tag.is_empty() {
+            os.write_string(1, &self.tag)?;
+        }
+        if !self.url.is_empty() {
+            os.write_string(2, &self.url)?;
+        }
+        if !self.path.is_empty() {
+            os.write_string(3, &self.path)?;
+        }
+        if self.interval != 0 {
+            os.write_uint32(4, self.interval)?;
+        }
+        if !self.format.is_empty() {
+            os.write_string(5, &self.format)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> Provider {
+        Provider::new()
+    }
+
+    fn default_instance() -> &'static Provider {
+        static instance: ::protobuf::rt::LazyV2<Provider> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(Provider::new)
+    }
+}
+
+impl ::protobuf::Clear for Provider {
+    fn clear(&mut self) {
+        self.tag.clear();
+        self.url.clear();
+        self.path.clear();
+        self.interval = 0;
+        self.format.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for Provider {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
 #[derive(PartialEq,Clone,Default,Debug)]
 pub struct Config {
     // message fields
@@ -4947,6 +10192,8 @@ pub struct Config {
     pub outbounds: ::protobuf::RepeatedField<Outbound>,
     pub router: ::protobuf::SingularPtrField<Router>,
     pub dns: ::protobuf::SingularPtrField<Dns>,
+    pub providers: ::protobuf::RepeatedField<Provider>,
+    pub rule_providers: ::protobuf::RepeatedField<Provider>,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -4997,6 +10244,20 @@ impl Config {
     pub fn get_dns(&self) -> &Dns {
         self.dns.as_ref().unwrap_or_else(|| <Dns as ::protobuf::Message>::default_instance())
     }
+
+    // repeated .Provider providers = 6;
+
+
+    pub fn get_providers(&self) -> &[Provider] {
+        &self.providers
+    }
+
+    // repeated .Provider rule_providers = 7;
+
+
+    pub fn get_rule_providers(&self) -> &[Provider] {
+        &self.rule_providers
+    }
 }
 
 impl ::protobuf::Message for Config {
@@ -5026,6 +10287,16 @@ impl ::protobuf::Message for Config {
                 return false;
             }
         };
+        for v in &self.providers {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        for v in &self.rule_providers {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
         true
     }
 
@@ -5048,6 +10319,12 @@ impl ::protobuf::Message for Config {
                 5 => {
                     ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.dns)?;
                 },
+                6 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.providers)?;
+                },
+                7 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.rule_providers)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -5080,6 +10357,14 @@ impl ::protobuf::Message for Config {
             let len = v.compute_size();
             my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
         }
+        for value in &self.providers {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        for value in &self.rule_providers {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -5111,6 +10396,16 @@ impl ::protobuf::Message for Config {
             os.write_raw_varint32(v.get_cached_size())?;
             v.write_to_with_cached_sizes(os)?;
         }
+        for v in &self.providers {
+            os.write_tag(6, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        for v in &self.rule_providers {
+            os.write_tag(7, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -5158,6 +10453,8 @@ impl ::protobuf::Clear for Config {
         self.outbounds.clear();
         self.router.clear();
         self.dns.clear();
+        self.providers.clear();
+        self.rule_providers.clear();
         self.unknown_fields.clear();
     }
 }
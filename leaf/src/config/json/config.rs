@@ -13,6 +13,18 @@ use crate::config::{external_rule, internal};
 pub struct Dns {
     pub servers: Option<Vec<String>>,
     pub hosts: Option<HashMap<String, Vec<String>>>,
+    pub rules: Option<Vec<DnsRule>>,
+    #[serde(rename = "clientSubnet")]
+    pub client_subnet: Option<String>,
+}
+
+// The servers of the domains matching the domain conditions of a rule, the
+// other conditions don't apply.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DnsRule {
+    #[serde(flatten)]
+    pub rule: Rule,
+    pub servers: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -21,6 +33,38 @@ pub struct Log {
     pub output: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TunnelInboundSettings {
+    pub address: Option<String>,
+    pub port: Option<u16>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DnsInboundSettings {
+    pub servers: Option<Vec<String>>,
+    #[serde(rename = "domainServers")]
+    pub domain_servers: Option<HashMap<String, String>>,
+    #[serde(rename = "fakeDns")]
+    pub fake_dns: Option<bool>,
+    #[serde(rename = "fakeDnsExclude")]
+    pub fake_dns_exclude: Option<Vec<String>>,
+    #[serde(rename = "fakeDnsInclude")]
+    pub fake_dns_include: Option<Vec<String>>,
+    #[serde(rename = "fakeIpCidr")]
+    pub fake_ip_cidr: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SocksUser {
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SocksInboundSettings {
+    pub users: Option<Vec<SocksUser>>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ShadowsocksInboundSettings {
     pub method: Option<String>,
@@ -30,11 +74,24 @@ pub struct ShadowsocksInboundSettings {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TrojanInboundSettings {
     pub passwords: Option<Vec<String>>,
+    pub fallback: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VLessInboundSettings {
+    pub uuids: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VMessInboundSettings {
+    pub uuids: Option<Vec<String>>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct WebSocketInboundSettings {
     pub path: Option<String>,
+    #[serde(rename = "earlyDataHeaderName")]
+    pub early_data_header_name: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -42,11 +99,18 @@ pub struct AMuxInboundSettings {
     pub actors: Option<Vec<String>>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MuxInboundSettings {
+    pub actors: Option<Vec<String>>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct QuicInboundSettings {
     pub certificate: Option<String>,
     #[serde(rename = "certificateKey")]
     pub certificate_key: Option<String>,
+    #[serde(rename = "zeroRtt")]
+    pub zero_rtt: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -69,10 +133,46 @@ pub struct TunInboundSettings {
     pub gateway: Option<String>,
     pub netmask: Option<String>,
     pub mtu: Option<i32>,
+    #[serde(rename = "ipv6Address")]
+    pub ipv6_address: Option<String>,
+    #[serde(rename = "ipv6Prefixlen")]
+    pub ipv6_prefixlen: Option<i32>,
+    #[serde(rename = "fakeIpv6")]
+    pub fake_ipv6: Option<bool>,
+    #[serde(rename = "icmpReply")]
+    pub icmp_reply: Option<bool>,
+    #[serde(rename = "dnsHijack")]
+    pub dns_hijack: Option<Vec<String>>,
+    pub queues: Option<i32>,
+    #[serde(rename = "bypassLanOutbound")]
+    pub bypass_lan_outbound: Option<String>,
+    #[serde(rename = "postUp")]
+    pub post_up: Option<String>,
+    #[serde(rename = "preDown")]
+    pub pre_down: Option<String>,
     #[serde(rename = "fakeDnsExclude")]
     pub fake_dns_exclude: Option<Vec<String>>,
     #[serde(rename = "fakeDnsInclude")]
     pub fake_dns_include: Option<Vec<String>>,
+    #[serde(rename = "fakeIpCidr")]
+    pub fake_ip_cidr: Option<String>,
+    #[serde(rename = "fakeIpv6Cidr")]
+    pub fake_ipv6_cidr: Option<String>,
+    #[serde(rename = "fakeIpPoolSize")]
+    pub fake_ip_pool_size: Option<i32>,
+    #[serde(rename = "fakeIpEviction")]
+    pub fake_ip_eviction: Option<String>,
+    #[serde(rename = "fakeIpTtl")]
+    pub fake_ip_ttl: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Sniffing {
+    pub enabled: Option<bool>,
+    pub protocols: Option<Vec<String>>,
+    pub ports: Option<Vec<u16>>,
+    #[serde(rename = "routeOnly")]
+    pub route_only: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -82,6 +182,27 @@ pub struct Inbound {
     pub address: Option<String>,
     pub port: Option<u16>,
     pub settings: Option<Box<RawValue>>,
+    #[serde(rename = "proxyProtocol")]
+    pub proxy_protocol: Option<bool>,
+    #[serde(rename = "maxConnections")]
+    pub max_connections: Option<u32>,
+    #[serde(rename = "maxConnectionsPerSource")]
+    pub max_connections_per_source: Option<u32>,
+    #[serde(rename = "acceptRate")]
+    pub accept_rate: Option<u32>,
+    pub sniffing: Option<Sniffing>,
+    #[serde(rename = "tcpFastOpen")]
+    pub tcp_fast_open: Option<bool>,
+    // Used instead of the first outbound for the sessions of the inbound
+    // matching no rules.
+    #[serde(rename = "defaultOutbound")]
+    pub default_outbound: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DropOutboundSettings {
+    pub tcp: Option<String>,
+    pub udp: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -102,6 +223,11 @@ pub struct ShadowsocksOutboundSettings {
     pub port: Option<u16>,
     pub method: Option<String>,
     pub password: Option<String>,
+    pub plugin: Option<String>,
+    #[serde(rename = "pluginOpts")]
+    pub plugin_opts: Option<String>,
+    #[serde(rename = "udpOverTcp")]
+    pub udp_over_tcp: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -111,17 +237,52 @@ pub struct TrojanOutboundSettings {
     pub password: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VLessOutboundSettings {
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    pub uuid: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VMessOutboundSettings {
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    pub uuid: Option<String>,
+    pub security: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SshOutboundSettings {
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    #[serde(rename = "privateKey")]
+    pub private_key: Option<String>,
+    #[serde(rename = "hostKey")]
+    pub host_key: Option<String>,
+    #[serde(rename = "insecureSkipHostKey")]
+    pub insecure_skip_host_key: Option<bool>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TryAllOutboundSettings {
     pub actors: Option<Vec<String>>,
     #[serde(rename = "delayBase")]
     pub delay_base: Option<u32>,
+    #[serde(rename = "attemptTimeout")]
+    pub attempt_timeout: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct StaticOutboundSettings {
     pub actors: Option<Vec<String>>,
     pub method: Option<String>,
+    pub weights: Option<Vec<u32>>,
+    pub url: Option<String>,
+    pub interval: Option<u32>,
+    pub timeout: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -130,12 +291,51 @@ pub struct TlsOutboundSettings {
     pub server_name: Option<String>,
     pub alpn: Option<Vec<String>>,
     pub certificate: Option<String>,
+    pub ech: Option<bool>,
+    #[serde(rename = "echConfig")]
+    pub ech_config: Option<String>,
+    #[serde(rename = "clientCertificate")]
+    pub client_certificate: Option<String>,
+    #[serde(rename = "clientCertificateKey")]
+    pub client_certificate_key: Option<String>,
+    #[serde(rename = "pinnedPeerCertificateChainSha256")]
+    pub pinned_peer_certificate_chain_sha256: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RealityOutboundSettings {
+    #[serde(rename = "serverName")]
+    pub server_name: Option<String>,
+    #[serde(rename = "publicKey")]
+    pub public_key: Option<String>,
+    #[serde(rename = "shortId")]
+    pub short_id: Option<String>,
+    pub alpn: Option<Vec<String>>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct WebSocketOutboundSettings {
     pub path: Option<String>,
     pub headers: Option<HashMap<String, String>>,
+    #[serde(rename = "maxEarlyData")]
+    pub max_early_data: Option<u32>,
+    #[serde(rename = "earlyDataHeaderName")]
+    pub early_data_header_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NaiveOutboundSettings {
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ObfsOutboundSettings {
+    pub method: Option<String>,
+    pub host: Option<String>,
+    pub path: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -146,6 +346,43 @@ pub struct AMuxOutboundSettings {
     #[serde(rename = "maxAccepts")]
     pub max_accepts: Option<u32>,
     pub concurrency: Option<u32>,
+    #[serde(rename = "keepAlive")]
+    pub keep_alive: Option<u32>,
+    #[serde(rename = "idleTimeout")]
+    pub idle_timeout: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MuxOutboundSettings {
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    pub actors: Option<Vec<String>>,
+    #[serde(rename = "maxStreams")]
+    pub max_streams: Option<u32>,
+    #[serde(rename = "minConnections")]
+    pub min_connections: Option<u32>,
+    #[serde(rename = "maxConnections")]
+    pub max_connections: Option<u32>,
+    pub padding: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GrpcOutboundSettings {
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    pub host: Option<String>,
+    #[serde(rename = "serviceName")]
+    pub service_name: Option<String>,
+    pub actors: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Http2OutboundSettings {
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    pub host: Option<String>,
+    pub path: Option<String>,
+    pub actors: Option<Vec<String>>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -166,11 +403,18 @@ pub struct ChainOutboundSettings {
 pub struct RetryOutboundSettings {
     pub actors: Option<Vec<String>>,
     pub attempts: Option<u32>,
+    #[serde(rename = "backoffBase")]
+    pub backoff_base: Option<u32>,
+    #[serde(rename = "maxBackoff")]
+    pub max_backoff: Option<u32>,
+    pub jitter: Option<u32>,
+    pub rotate: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FailOverOutboundSettings {
     pub actors: Option<Vec<String>>,
+    pub filter: Option<String>,
     #[serde(rename = "failTimeout")]
     pub fail_timeout: Option<u32>,
     #[serde(rename = "healthCheck")]
@@ -184,11 +428,40 @@ pub struct FailOverOutboundSettings {
     pub cache_size: Option<u32>,
     #[serde(rename = "cacheTimeout")]
     pub cache_timeout: Option<u32>,
+    #[serde(rename = "ejectThreshold")]
+    pub eject_threshold: Option<u32>,
+    #[serde(rename = "ejectDuration")]
+    pub eject_duration: Option<u32>,
+    #[serde(rename = "maxEjectDuration")]
+    pub max_eject_duration: Option<u32>,
+    #[serde(rename = "healthCheckTimeout")]
+    pub health_check_timeout: Option<u32>,
+    #[serde(rename = "healthCheckUrl")]
+    pub health_check_url: Option<String>,
+    #[serde(rename = "healthCheckStatus")]
+    pub health_check_status: Option<u32>,
+    #[serde(rename = "healthCheckConcurrency")]
+    pub health_check_concurrency: Option<u32>,
+    #[serde(rename = "udpHealthCheck")]
+    pub udp_health_check: Option<bool>,
+    #[serde(rename = "udpHealthCheckServer")]
+    pub udp_health_check_server: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UrlTestOutboundSettings {
+    pub actors: Option<Vec<String>>,
+    pub filter: Option<String>,
+    pub url: Option<String>,
+    pub interval: Option<u32>,
+    pub tolerance: Option<u32>,
+    pub timeout: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SelectOutboundSettings {
     pub actors: Option<Vec<String>>,
+    pub filter: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -201,6 +474,18 @@ pub struct PluginOutboundSettings {
 pub struct Outbound {
     pub protocol: String,
     pub tag: Option<String>,
+    #[serde(rename = "bindInterface")]
+    pub bind_interface: Option<String>,
+    #[serde(rename = "bindAddresses")]
+    pub bind_addresses: Option<Vec<String>>,
+    #[serde(rename = "domainStrategy")]
+    pub domain_strategy: Option<String>,
+    #[serde(rename = "tcpFastOpen")]
+    pub tcp_fast_open: Option<bool>,
+    #[serde(rename = "connectTimeout")]
+    pub connect_timeout: Option<u32>,
+    #[serde(rename = "handshakeTimeout")]
+    pub handshake_timeout: Option<u32>,
     pub settings: Option<Box<RawValue>>,
 }
 
@@ -212,12 +497,37 @@ pub struct Rule {
     pub domain_keyword: Option<Vec<String>>,
     #[serde(rename = "domainSuffix")]
     pub domain_suffix: Option<Vec<String>>,
+    #[serde(rename = "domainRegex")]
+    pub domain_regex: Option<Vec<String>>,
     pub geoip: Option<Vec<String>>,
+    pub geosite: Option<Vec<String>>,
+    pub asn: Option<Vec<u32>>,
     pub external: Option<Vec<String>>,
     #[serde(rename = "portRange")]
     pub port_range: Option<Vec<String>>,
+    pub network: Option<Vec<String>>,
+    #[serde(rename = "sourceIp")]
+    pub source_ip: Option<Vec<String>>,
+    #[serde(rename = "sourcePort")]
+    pub source_port: Option<Vec<String>>,
     #[serde(rename = "inboundTag")]
     pub inbound_tag: Option<Vec<String>>,
+    pub app: Option<Vec<String>>,
+    #[serde(rename = "processName")]
+    pub process_name: Option<Vec<String>>,
+    #[serde(rename = "processPath")]
+    pub process_path: Option<Vec<String>>,
+    #[serde(rename = "ruleSet")]
+    pub rule_set: Option<Vec<String>>,
+    pub schedule: Option<Vec<String>>,
+    // Sub-rules, matched if all of `and`, any of `or` and none of `not` match.
+    pub and: Option<Vec<Rule>>,
+    pub or: Option<Vec<Rule>>,
+    pub not: Option<Vec<Rule>>,
+    #[serde(rename = "noResolve")]
+    pub no_resolve: Option<bool>,
+    // Empty in sub-rules.
+    #[serde(default)]
     pub target: String,
 }
 
@@ -226,6 +536,25 @@ pub struct Router {
     pub rules: Option<Vec<Rule>>,
     #[serde(rename = "domainResolve")]
     pub domain_resolve: Option<bool>,
+    pub script: Option<String>,
+    #[serde(rename = "geoipFile")]
+    pub geoip_file: Option<String>,
+    #[serde(rename = "geositeFile")]
+    pub geosite_file: Option<String>,
+    #[serde(rename = "asnFile")]
+    pub asn_file: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Provider {
+    pub tag: String,
+    // Rule providers may have only a local path.
+    #[serde(default)]
+    pub url: String,
+    pub path: Option<String>,
+    pub interval: Option<u32>,
+    // Rule providers only, `text` or `dat`.
+    pub format: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -235,6 +564,170 @@ pub struct Config {
     pub outbounds: Option<Vec<Outbound>>,
     pub router: Option<Router>,
     pub dns: Option<Dns>,
+    pub providers: Option<Vec<Provider>>,
+    #[serde(rename = "ruleProviders")]
+    pub rule_providers: Option<Vec<Provider>>,
+}
+
+fn to_internal_rule(
+    ext_rule: &mut Rule,
+    geoip_file: &str,
+    geosite_file: &str,
+    asn_file: &str,
+) -> internal::Router_Rule {
+    let mut rule = internal::Router_Rule::new();
+    if let Some(ext_ips) = ext_rule.ip.as_mut() {
+        for ext_ip in ext_ips.drain(0..) {
+            rule.ip_cidrs.push(ext_ip);
+        }
+    }
+    if let Some(ext_domains) = ext_rule.domain.as_mut() {
+        for ext_domain in ext_domains.drain(0..) {
+            let mut domain = internal::Router_Rule_Domain::new();
+            domain.field_type = internal::Router_Rule_Domain_Type::FULL;
+            domain.value = ext_domain;
+            rule.domains.push(domain);
+        }
+    }
+    if let Some(ext_domain_keywords) = ext_rule.domain_keyword.as_mut() {
+        for ext_domain_keyword in ext_domain_keywords.drain(0..) {
+            let mut domain = internal::Router_Rule_Domain::new();
+            domain.field_type = internal::Router_Rule_Domain_Type::PLAIN;
+            domain.value = ext_domain_keyword;
+            rule.domains.push(domain);
+        }
+    }
+    if let Some(ext_domain_suffixes) = ext_rule.domain_suffix.as_mut() {
+        for ext_domain_suffix in ext_domain_suffixes.drain(0..) {
+            let mut domain = internal::Router_Rule_Domain::new();
+            domain.field_type = internal::Router_Rule_Domain_Type::DOMAIN;
+            domain.value = ext_domain_suffix;
+            rule.domains.push(domain);
+        }
+    }
+    if let Some(ext_domain_regexes) = ext_rule.domain_regex.as_mut() {
+        for ext_domain_regex in ext_domain_regexes.drain(0..) {
+            let mut domain = internal::Router_Rule_Domain::new();
+            domain.field_type = internal::Router_Rule_Domain_Type::REGEX;
+            domain.value = ext_domain_regex;
+            rule.domains.push(domain);
+        }
+    }
+    if let Some(ext_geoips) = ext_rule.geoip.as_mut() {
+        for ext_geoip in ext_geoips.drain(0..) {
+            let mut mmdb = internal::Router_Rule_Mmdb::new();
+            mmdb.file = geoip_file.to_string();
+            mmdb.country_code = ext_geoip;
+            rule.mmdbs.push(mmdb)
+        }
+    }
+    if let Some(ext_asns) = ext_rule.asn.as_mut() {
+        for ext_asn in ext_asns.drain(0..) {
+            let mut mmdb = internal::Router_Rule_Mmdb::new();
+            mmdb.file = asn_file.to_string();
+            mmdb.asn = ext_asn;
+            rule.mmdbs.push(mmdb)
+        }
+    }
+    if let Some(ext_geosites) = ext_rule.geosite.as_mut() {
+        for ext_geosite in ext_geosites.drain(0..) {
+            if let Err(e) = external_rule::add_site_rule(&mut rule, geosite_file, &ext_geosite) {
+                println!("load geosite rule failed: {}", e);
+            }
+        }
+    }
+    if let Some(ext_externals) = ext_rule.external.as_mut() {
+        for ext_external in ext_externals.drain(0..) {
+            match external_rule::add_external_rule(&mut rule, &ext_external) {
+                Ok(_) => (),
+                Err(e) => {
+                    println!("load external rule failed: {}", e);
+                }
+            }
+        }
+    }
+    if let Some(ext_port_ranges) = ext_rule.port_range.as_mut() {
+        for ext_port_range in ext_port_ranges.drain(0..) {
+            // FIXME validate
+            rule.port_ranges.push(ext_port_range);
+        }
+    }
+    if let Some(ext_networks) = ext_rule.network.as_mut() {
+        for ext_network in ext_networks.drain(0..) {
+            rule.networks.push(ext_network);
+        }
+    }
+    if let Some(ext_source_ips) = ext_rule.source_ip.as_mut() {
+        for ext_source_ip in ext_source_ips.drain(0..) {
+            rule.source_ips.push(ext_source_ip);
+        }
+    }
+    if let Some(ext_source_ports) = ext_rule.source_port.as_mut() {
+        for ext_source_port in ext_source_ports.drain(0..) {
+            rule.source_ports.push(ext_source_port);
+        }
+    }
+    if let Some(ext_its) = ext_rule.inbound_tag.as_mut() {
+        for it in ext_its.drain(0..) {
+            rule.inbound_tags.push(it);
+        }
+    }
+    if let Some(ext_apps) = ext_rule.app.as_mut() {
+        for app in ext_apps.drain(0..) {
+            rule.apps.push(app);
+        }
+    }
+    if let Some(ext_names) = ext_rule.process_name.as_mut() {
+        for name in ext_names.drain(0..) {
+            rule.process_names.push(name);
+        }
+    }
+    if let Some(ext_paths) = ext_rule.process_path.as_mut() {
+        for path in ext_paths.drain(0..) {
+            rule.process_paths.push(path);
+        }
+    }
+    if let Some(ext_rule_sets) = ext_rule.rule_set.as_mut() {
+        for rule_set in ext_rule_sets.drain(0..) {
+            rule.rule_sets.push(rule_set);
+        }
+    }
+    if let Some(ext_schedules) = ext_rule.schedule.as_mut() {
+        for schedule in ext_schedules.drain(0..) {
+            rule.schedules.push(schedule);
+        }
+    }
+    if let Some(ext_and_rules) = ext_rule.and.as_mut() {
+        for ext_sub_rule in ext_and_rules.iter_mut() {
+            rule.and_rules.push(to_internal_rule(
+                ext_sub_rule,
+                geoip_file,
+                geosite_file,
+                asn_file,
+            ));
+        }
+    }
+    if let Some(ext_or_rules) = ext_rule.or.as_mut() {
+        for ext_sub_rule in ext_or_rules.iter_mut() {
+            rule.or_rules.push(to_internal_rule(
+                ext_sub_rule,
+                geoip_file,
+                geosite_file,
+                asn_file,
+            ));
+        }
+    }
+    if let Some(ext_not_rules) = ext_rule.not.as_mut() {
+        for ext_sub_rule in ext_not_rules.iter_mut() {
+            rule.not_rules.push(to_internal_rule(
+                ext_sub_rule,
+                geoip_file,
+                geosite_file,
+                asn_file,
+            ));
+        }
+    }
+    rule
 }
 
 pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
@@ -278,6 +771,40 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
             if let Some(ext_port) = ext_inbound.port {
                 inbound.port = ext_port as u32;
             }
+            if let Some(ext_proxy_protocol) = ext_inbound.proxy_protocol {
+                inbound.proxy_protocol = ext_proxy_protocol;
+            }
+            if let Some(ext_tcp_fast_open) = ext_inbound.tcp_fast_open {
+                inbound.tcp_fast_open = ext_tcp_fast_open;
+            }
+            if let Some(ext_max_connections) = ext_inbound.max_connections {
+                inbound.max_connections = ext_max_connections;
+            }
+            if let Some(ext_max_connections_per_source) = ext_inbound.max_connections_per_source {
+                inbound.max_connections_per_source = ext_max_connections_per_source;
+            }
+            if let Some(ext_accept_rate) = ext_inbound.accept_rate {
+                inbound.accept_rate = ext_accept_rate;
+            }
+            if let Some(ext_sniffing) = &ext_inbound.sniffing {
+                let mut sniffing = internal::Inbound_Sniffing::new();
+                sniffing.enabled = ext_sniffing.enabled.unwrap_or(true);
+                if let Some(ext_protocols) = &ext_sniffing.protocols {
+                    for ext_protocol in ext_protocols {
+                        match ext_protocol.as_str() {
+                            "tls" | "http" | "quic" => {
+                                sniffing.protocols.push(ext_protocol.clone());
+                            }
+                            _ => return Err(anyhow!("unknown sniffing protocol {}", ext_protocol)),
+                        }
+                    }
+                }
+                if let Some(ext_ports) = &ext_sniffing.ports {
+                    sniffing.ports = ext_ports.iter().map(|p| *p as u32).collect();
+                }
+                sniffing.route_only = ext_sniffing.route_only.unwrap_or(false);
+                inbound.sniffing = protobuf::SingularPtrField::some(sniffing);
+            }
             match inbound.protocol.as_str() {
                 #[cfg(any(
                     target_os = "ios",
@@ -334,6 +861,52 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                         } else {
                             settings.mtu = 1500;
                         }
+                        if let Some(ext_ipv6_address) = ext_settings.ipv6_address {
+                            settings.ipv6_address = ext_ipv6_address;
+                        }
+                        if let Some(ext_ipv6_prefixlen) = ext_settings.ipv6_prefixlen {
+                            settings.ipv6_prefixlen = ext_ipv6_prefixlen;
+                        }
+                    }
+                    if let Some(ext_fake_ipv6) = ext_settings.fake_ipv6 {
+                        settings.fake_ipv6 = ext_fake_ipv6;
+                    }
+                    if let Some(ext_icmp_reply) = ext_settings.icmp_reply {
+                        settings.icmp_reply = ext_icmp_reply;
+                    }
+                    if let Some(ext_fake_ip_cidr) = ext_settings.fake_ip_cidr {
+                        settings.fake_ip_cidr = ext_fake_ip_cidr;
+                    }
+                    if let Some(ext_fake_ipv6_cidr) = ext_settings.fake_ipv6_cidr {
+                        settings.fake_ipv6_cidr = ext_fake_ipv6_cidr;
+                    }
+                    if let Some(ext_fake_ip_pool_size) = ext_settings.fake_ip_pool_size {
+                        settings.fake_ip_pool_size = ext_fake_ip_pool_size;
+                    }
+                    if let Some(ext_fake_ip_eviction) = ext_settings.fake_ip_eviction {
+                        settings.fake_ip_eviction = ext_fake_ip_eviction;
+                    }
+                    if let Some(ext_fake_ip_ttl) = ext_settings.fake_ip_ttl {
+                        settings.fake_ip_ttl = ext_fake_ip_ttl;
+                    }
+                    if let Some(ext_bypass_lan_outbound) = ext_settings.bypass_lan_outbound {
+                        settings.bypass_lan_outbound = ext_bypass_lan_outbound;
+                    }
+                    if let Some(ext_post_up) = ext_settings.post_up {
+                        settings.post_up = ext_post_up;
+                    }
+                    if let Some(ext_pre_down) = ext_settings.pre_down {
+                        settings.pre_down = ext_pre_down;
+                    }
+                    if let Some(ext_queues) = ext_settings.queues {
+                        settings.queues = ext_queues;
+                    }
+                    if let Some(ext_dns_hijack) = ext_settings.dns_hijack {
+                        let mut dns_hijack = protobuf::RepeatedField::new();
+                        for item in ext_dns_hijack {
+                            dns_hijack.push(item);
+                        }
+                        settings.dns_hijack = dns_hijack;
                     }
                     let settings = settings.write_to_bytes().unwrap();
                     inbound.settings = settings;
@@ -342,7 +915,77 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                 "http" => {
                     inbounds.push(inbound);
                 }
-                "socks" => {
+                "socks" | "mixed" => {
+                    if let Some(ext_settings) = ext_inbound.settings.as_ref() {
+                        let mut settings = internal::SocksInboundSettings::new();
+                        let ext_settings: SocksInboundSettings =
+                            serde_json::from_str(ext_settings.get()).unwrap();
+                        if let Some(ext_users) = ext_settings.users {
+                            for ext_user in ext_users {
+                                let mut user = internal::SocksInboundSettings_User::new();
+                                user.username = ext_user.username.unwrap_or_default();
+                                user.password = ext_user.password.unwrap_or_default();
+                                settings.users.push(user);
+                            }
+                        }
+                        let settings = settings.write_to_bytes().unwrap();
+                        inbound.settings = settings;
+                    }
+                    inbounds.push(inbound);
+                }
+                "redirect" => {
+                    inbounds.push(inbound);
+                }
+                "tunnel" => {
+                    if ext_inbound.settings.is_none() {
+                        return Err(anyhow!("invalid tunnel inbound settings"));
+                    }
+                    let mut settings = internal::TunnelInboundSettings::new();
+                    let ext_settings: TunnelInboundSettings =
+                        serde_json::from_str(ext_inbound.settings.as_ref().unwrap().get()).unwrap();
+                    if let Some(ext_address) = ext_settings.address {
+                        settings.address = ext_address;
+                    }
+                    if let Some(ext_port) = ext_settings.port {
+                        settings.port = ext_port as u32;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    inbound.settings = settings;
+                    inbounds.push(inbound);
+                }
+                "dns" => {
+                    let mut settings = internal::DnsInboundSettings::new();
+                    let ext_settings: DnsInboundSettings =
+                        serde_json::from_str(ext_inbound.settings.as_ref().unwrap().get()).unwrap();
+                    if let Some(ext_servers) = ext_settings.servers {
+                        for ext_server in ext_servers {
+                            settings.servers.push(ext_server);
+                        }
+                    }
+                    if let Some(ext_domain_servers) = ext_settings.domain_servers {
+                        for (domain, server) in ext_domain_servers {
+                            settings.domain_servers.insert(domain, server);
+                        }
+                    }
+                    settings.fake_dns = ext_settings.fake_dns.unwrap_or(false);
+                    if let Some(ext_excludes) = ext_settings.fake_dns_exclude {
+                        for ext_exclude in ext_excludes {
+                            settings.fake_dns_exclude.push(ext_exclude);
+                        }
+                    }
+                    if let Some(ext_includes) = ext_settings.fake_dns_include {
+                        for ext_include in ext_includes {
+                            settings.fake_dns_include.push(ext_include);
+                        }
+                    }
+                    if let Some(ext_fake_ip_cidr) = ext_settings.fake_ip_cidr {
+                        settings.fake_ip_cidr = ext_fake_ip_cidr;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    inbound.settings = settings;
+                    inbounds.push(inbound);
+                }
+                "tproxy" => {
                     inbounds.push(inbound);
                 }
                 "shadowsocks" => {
@@ -370,6 +1013,35 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                             settings.passwords.push(ext_pass);
                         }
                     }
+                    if let Some(ext_fallback) = ext_settings.fallback {
+                        settings.fallback = ext_fallback;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    inbound.settings = settings;
+                    inbounds.push(inbound);
+                }
+                "vless" => {
+                    let mut settings = internal::VLessInboundSettings::new();
+                    let ext_settings: VLessInboundSettings =
+                        serde_json::from_str(ext_inbound.settings.as_ref().unwrap().get()).unwrap();
+                    if let Some(ext_uuids) = ext_settings.uuids {
+                        for ext_uuid in ext_uuids {
+                            settings.uuids.push(ext_uuid);
+                        }
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    inbound.settings = settings;
+                    inbounds.push(inbound);
+                }
+                "vmess" => {
+                    let mut settings = internal::VMessInboundSettings::new();
+                    let ext_settings: VMessInboundSettings =
+                        serde_json::from_str(ext_inbound.settings.as_ref().unwrap().get()).unwrap();
+                    if let Some(ext_uuids) = ext_settings.uuids {
+                        for ext_uuid in ext_uuids {
+                            settings.uuids.push(ext_uuid);
+                        }
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     inbound.settings = settings;
                     inbounds.push(inbound);
@@ -386,6 +1058,9 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                             settings.path = "/".to_string();
                         }
                     };
+                    if let Some(ext_early_data_header_name) = ext_settings.early_data_header_name {
+                        settings.early_data_header_name = ext_early_data_header_name;
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     inbound.settings = settings;
                     inbounds.push(inbound);
@@ -407,6 +1082,23 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                     inbound.settings = settings;
                     inbounds.push(inbound);
                 }
+                "mux" => {
+                    let mut settings = internal::MuxInboundSettings::new();
+                    if let Some(ext_settings) = &ext_inbound.settings {
+                        if let Ok(ext_settings) =
+                            serde_json::from_str::<MuxInboundSettings>(ext_settings.get())
+                        {
+                            if let Some(ext_actors) = ext_settings.actors {
+                                for ext_actor in ext_actors {
+                                    settings.actors.push(ext_actor);
+                                }
+                            }
+                        }
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    inbound.settings = settings;
+                    inbounds.push(inbound);
+                }
                 "quic" => {
                     let mut settings = internal::QuicInboundSettings::new();
                     let ext_settings: QuicInboundSettings =
@@ -431,6 +1123,9 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                             settings.certificate_key = path;
                         }
                     }
+                    if let Some(ext_zero_rtt) = ext_settings.zero_rtt {
+                        settings.zero_rtt = ext_zero_rtt;
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     inbound.settings = settings;
                     inbounds.push(inbound);
@@ -494,8 +1189,51 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
             if let Some(ext_tag) = &ext_outbound.tag {
                 outbound.tag = ext_tag.to_owned();
             }
+            if let Some(ext_bind_interface) = &ext_outbound.bind_interface {
+                outbound.bind_interface = ext_bind_interface.to_owned();
+            }
+            if let Some(ext_bind_addresses) = &ext_outbound.bind_addresses {
+                outbound.bind_addresses =
+                    protobuf::RepeatedField::from_vec(ext_bind_addresses.clone());
+            }
+            if let Some(ext_domain_strategy) = &ext_outbound.domain_strategy {
+                outbound.domain_strategy = match ext_domain_strategy.as_str() {
+                    "as_is" => internal::Outbound_DomainStrategy::AS_IS,
+                    "prefer_ipv4" => internal::Outbound_DomainStrategy::PREFER_IPV4,
+                    "prefer_ipv6" => internal::Outbound_DomainStrategy::PREFER_IPV6,
+                    "ipv4_only" => internal::Outbound_DomainStrategy::IPV4_ONLY,
+                    "ipv6_only" => internal::Outbound_DomainStrategy::IPV6_ONLY,
+                    _ => return Err(anyhow!("unknown domain strategy {}", ext_domain_strategy)),
+                };
+            }
+            if let Some(ext_tcp_fast_open) = ext_outbound.tcp_fast_open {
+                outbound.tcp_fast_open = ext_tcp_fast_open;
+            }
+            if let Some(ext_connect_timeout) = ext_outbound.connect_timeout {
+                outbound.connect_timeout = ext_connect_timeout;
+            }
+            if let Some(ext_handshake_timeout) = ext_outbound.handshake_timeout {
+                outbound.handshake_timeout = ext_handshake_timeout;
+            }
             match outbound.protocol.as_str() {
-                "direct" | "drop" => {
+                "direct" => {
+                    outbounds.push(outbound);
+                }
+                "drop" => {
+                    if let Some(ext_settings) = &ext_outbound.settings {
+                        let mut settings = internal::DropOutboundSettings::new();
+                        let ext_settings: DropOutboundSettings =
+                            serde_json::from_str(ext_settings.get())
+                                .map_err(|e| anyhow!("invalid drop outbound settings: {}", e))?;
+                        if let Some(ext_tcp) = ext_settings.tcp {
+                            settings.tcp = ext_tcp;
+                        }
+                        if let Some(ext_udp) = ext_settings.udp {
+                            settings.udp = ext_udp;
+                        }
+                        let settings = settings.write_to_bytes().unwrap();
+                        outbound.settings = settings;
+                    }
                     outbounds.push(outbound);
                 }
                 "redirect" => {
@@ -556,6 +1294,15 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                     if let Some(ext_password) = ext_settings.password {
                         settings.password = ext_password;
                     }
+                    if let Some(ext_plugin) = ext_settings.plugin {
+                        settings.plugin = ext_plugin;
+                    }
+                    if let Some(ext_plugin_opts) = ext_settings.plugin_opts {
+                        settings.plugin_opts = ext_plugin_opts;
+                    }
+                    if let Some(ext_udp_over_tcp) = ext_settings.udp_over_tcp {
+                        settings.udp_over_tcp = ext_udp_over_tcp;
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -581,6 +1328,91 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                     outbound.settings = settings;
                     outbounds.push(outbound);
                 }
+                "vless" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid vless outbound settings"));
+                    }
+                    let mut settings = internal::VLessOutboundSettings::new();
+                    let ext_settings: VLessOutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.as_ref().unwrap().get())
+                            .unwrap();
+                    if let Some(ext_address) = ext_settings.address {
+                        settings.address = ext_address; // TODO checks
+                    }
+                    if let Some(ext_port) = ext_settings.port {
+                        settings.port = ext_port as u32; // TODO checks
+                    }
+                    if let Some(ext_uuid) = ext_settings.uuid {
+                        settings.uuid = ext_uuid;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "vmess" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid vmess outbound settings"));
+                    }
+                    let mut settings = internal::VMessOutboundSettings::new();
+                    let ext_settings: VMessOutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.as_ref().unwrap().get())
+                            .unwrap();
+                    if let Some(ext_address) = ext_settings.address {
+                        settings.address = ext_address; // TODO checks
+                    }
+                    if let Some(ext_port) = ext_settings.port {
+                        settings.port = ext_port as u32; // TODO checks
+                    }
+                    if let Some(ext_uuid) = ext_settings.uuid {
+                        settings.uuid = ext_uuid;
+                    }
+                    if let Some(ext_security) = ext_settings.security {
+                        settings.security = ext_security;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "ssh" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid ssh outbound settings"));
+                    }
+                    let mut settings = internal::SshOutboundSettings::new();
+                    let ext_settings: SshOutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.as_ref().unwrap().get())
+                            .unwrap();
+                    if let Some(ext_address) = ext_settings.address {
+                        settings.address = ext_address; // TODO checks
+                    }
+                    if let Some(ext_port) = ext_settings.port {
+                        settings.port = ext_port as u32; // TODO checks
+                    }
+                    if let Some(ext_username) = ext_settings.username {
+                        settings.username = ext_username;
+                    }
+                    if let Some(ext_password) = ext_settings.password {
+                        settings.password = ext_password;
+                    }
+                    if let Some(ext_private_key) = ext_settings.private_key {
+                        let key = Path::new(&ext_private_key);
+                        if key.is_absolute() {
+                            settings.private_key = key.to_string_lossy().to_string();
+                        } else {
+                            let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
+                            let path = asset_loc.join(key).to_string_lossy().to_string();
+                            settings.private_key = path;
+                        }
+                    }
+                    if let Some(ext_host_key) = ext_settings.host_key {
+                        settings.host_key = ext_host_key;
+                    }
+                    if let Some(ext_skip) = ext_settings.insecure_skip_host_key {
+                        settings.insecure_skip_host_key = ext_skip;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
                 "tls" => {
                     let mut settings = internal::TlsOutboundSettings::new();
                     if ext_outbound.settings.is_some() {
@@ -609,6 +1441,64 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                                 settings.certificate = path;
                             }
                         }
+                        if let Some(ext_ech) = ext_settings.ech {
+                            settings.ech = ext_ech;
+                        }
+                        if let Some(ext_ech_config) = ext_settings.ech_config {
+                            settings.ech_config = ext_ech_config;
+                        }
+                        if let Some(ext_client_certificate) = ext_settings.client_certificate {
+                            let cert = Path::new(&ext_client_certificate);
+                            if cert.is_absolute() {
+                                settings.client_certificate = cert.to_string_lossy().to_string();
+                            } else {
+                                let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
+                                let path = asset_loc.join(cert).to_string_lossy().to_string();
+                                settings.client_certificate = path;
+                            }
+                        }
+                        if let Some(ext_client_certificate_key) =
+                            ext_settings.client_certificate_key
+                        {
+                            let key = Path::new(&ext_client_certificate_key);
+                            if key.is_absolute() {
+                                settings.client_certificate_key = key.to_string_lossy().to_string();
+                            } else {
+                                let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
+                                let path = asset_loc.join(key).to_string_lossy().to_string();
+                                settings.client_certificate_key = path;
+                            }
+                        }
+                        if let Some(ext_pins) = ext_settings.pinned_peer_certificate_chain_sha256 {
+                            settings.pinned_peer_certificate_chain_sha256 =
+                                protobuf::RepeatedField::from_vec(ext_pins);
+                        }
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "reality" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid reality outbound settings"));
+                    }
+                    let mut settings = internal::RealityOutboundSettings::new();
+                    let ext_settings: RealityOutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.as_ref().unwrap().get())
+                            .unwrap();
+                    if let Some(ext_server_name) = ext_settings.server_name {
+                        settings.server_name = ext_server_name;
+                    }
+                    if let Some(ext_public_key) = ext_settings.public_key {
+                        settings.public_key = ext_public_key;
+                    }
+                    if let Some(ext_short_id) = ext_settings.short_id {
+                        settings.short_id = ext_short_id;
+                    }
+                    if let Some(ext_alpns) = ext_settings.alpn {
+                        for ext_alpn in ext_alpns {
+                            settings.alpn.push(ext_alpn);
+                        }
                     }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
@@ -629,6 +1519,63 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                     if let Some(ext_headers) = ext_settings.headers {
                         settings.headers = ext_headers;
                     }
+                    if let Some(ext_max_early_data) = ext_settings.max_early_data {
+                        settings.max_early_data = ext_max_early_data;
+                    }
+                    if let Some(ext_early_data_header_name) = ext_settings.early_data_header_name {
+                        settings.early_data_header_name = ext_early_data_header_name;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "naive" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid naive outbound settings"));
+                    }
+                    let mut settings = internal::NaiveOutboundSettings::new();
+                    let ext_settings: NaiveOutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.as_ref().unwrap().get())
+                            .unwrap();
+                    if let Some(ext_address) = ext_settings.address {
+                        settings.address = ext_address;
+                    }
+                    if let Some(ext_port) = ext_settings.port {
+                        settings.port = ext_port as u32;
+                    } else {
+                        settings.port = 443;
+                    }
+                    if let Some(ext_username) = ext_settings.username {
+                        settings.username = ext_username;
+                    }
+                    if let Some(ext_password) = ext_settings.password {
+                        settings.password = ext_password;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "obfs" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid obfs outbound settings"));
+                    }
+                    let mut settings = internal::ObfsOutboundSettings::new();
+                    let ext_settings: ObfsOutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.as_ref().unwrap().get())
+                            .unwrap();
+                    if let Some(ext_method) = ext_settings.method {
+                        settings.method = ext_method;
+                    } else {
+                        settings.method = "http".to_string();
+                    }
+                    if let Some(ext_host) = ext_settings.host {
+                        settings.host = ext_host;
+                    }
+                    if let Some(ext_path) = ext_settings.path {
+                        settings.path = ext_path;
+                    } else {
+                        settings.path = "/".to_string();
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -651,6 +1598,31 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                     } else {
                         settings.delay_base = 0;
                     }
+                    if let Some(ext_attempt_timeout) = ext_settings.attempt_timeout {
+                        settings.attempt_timeout = ext_attempt_timeout;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "retry" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid retry outbound settings"));
+                    }
+                    let mut settings = internal::RetryOutboundSettings::new();
+                    let ext_settings: RetryOutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.as_ref().unwrap().get())
+                            .unwrap();
+                    if let Some(ext_actors) = ext_settings.actors {
+                        for ext_actor in ext_actors {
+                            settings.actors.push(ext_actor);
+                        }
+                    }
+                    settings.attempts = ext_settings.attempts.unwrap_or(3);
+                    settings.backoff_base = ext_settings.backoff_base.unwrap_or(200);
+                    settings.max_backoff = ext_settings.max_backoff.unwrap_or(5000);
+                    settings.jitter = ext_settings.jitter.unwrap_or(50);
+                    settings.rotate = ext_settings.rotate.unwrap_or(false);
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -673,6 +1645,22 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                     } else {
                         settings.method = "random".to_string();
                     }
+                    if let Some(ext_weights) = ext_settings.weights {
+                        settings.weights = ext_weights;
+                    }
+                    if let Some(ext_url) = ext_settings.url {
+                        settings.url = ext_url;
+                    }
+                    if let Some(ext_interval) = ext_settings.interval {
+                        settings.interval = ext_interval;
+                    } else {
+                        settings.interval = 300;
+                    }
+                    if let Some(ext_timeout) = ext_settings.timeout {
+                        settings.timeout = ext_timeout;
+                    } else {
+                        settings.timeout = 5;
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -690,6 +1678,9 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                             settings.actors.push(ext_actor);
                         }
                     }
+                    if let Some(ext_filter) = ext_settings.filter {
+                        settings.filter = ext_filter;
+                    }
                     if let Some(ext_fail_timeout) = ext_settings.fail_timeout {
                         settings.fail_timeout = ext_fail_timeout;
                     } else {
@@ -725,6 +1716,84 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                     } else {
                         settings.cache_timeout = 60; // in minutes
                     }
+                    if let Some(ext_eject_threshold) = ext_settings.eject_threshold {
+                        settings.eject_threshold = ext_eject_threshold;
+                    } else {
+                        settings.eject_threshold = 3;
+                    }
+                    if let Some(ext_eject_duration) = ext_settings.eject_duration {
+                        settings.eject_duration = ext_eject_duration;
+                    } else {
+                        settings.eject_duration = 30;
+                    }
+                    if let Some(ext_max_eject_duration) = ext_settings.max_eject_duration {
+                        settings.max_eject_duration = ext_max_eject_duration;
+                    } else {
+                        settings.max_eject_duration = 600;
+                    }
+                    if let Some(ext_health_check_timeout) = ext_settings.health_check_timeout {
+                        settings.health_check_timeout = ext_health_check_timeout;
+                    } else {
+                        settings.health_check_timeout = 4;
+                    }
+                    if let Some(ext_health_check_url) = ext_settings.health_check_url {
+                        settings.health_check_url = ext_health_check_url;
+                    }
+                    if let Some(ext_health_check_status) = ext_settings.health_check_status {
+                        settings.health_check_status = ext_health_check_status;
+                    }
+                    if let Some(ext_health_check_concurrency) =
+                        ext_settings.health_check_concurrency
+                    {
+                        settings.health_check_concurrency = ext_health_check_concurrency;
+                    }
+                    if let Some(ext_udp_health_check) = ext_settings.udp_health_check {
+                        settings.udp_health_check = ext_udp_health_check;
+                    } else {
+                        settings.udp_health_check = true;
+                    }
+                    if let Some(ext_udp_health_check_server) = ext_settings.udp_health_check_server
+                    {
+                        settings.udp_health_check_server = ext_udp_health_check_server;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "urltest" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid urltest outbound settings"));
+                    }
+                    let mut settings = internal::UrlTestOutboundSettings::new();
+                    let ext_settings: UrlTestOutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.as_ref().unwrap().get())
+                            .unwrap();
+                    if let Some(ext_actors) = ext_settings.actors {
+                        for ext_actor in ext_actors {
+                            settings.actors.push(ext_actor);
+                        }
+                    }
+                    if let Some(ext_filter) = ext_settings.filter {
+                        settings.filter = ext_filter;
+                    }
+                    if let Some(ext_url) = ext_settings.url {
+                        settings.url = ext_url;
+                    }
+                    if let Some(ext_interval) = ext_settings.interval {
+                        settings.interval = ext_interval;
+                    } else {
+                        settings.interval = 300;
+                    }
+                    if let Some(ext_tolerance) = ext_settings.tolerance {
+                        settings.tolerance = ext_tolerance;
+                    } else {
+                        settings.tolerance = 50;
+                    }
+                    if let Some(ext_timeout) = ext_settings.timeout {
+                        settings.timeout = ext_timeout;
+                    } else {
+                        settings.timeout = 5;
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -758,6 +1827,97 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                     } else {
                         settings.concurrency = 2;
                     }
+                    if let Some(ext_keep_alive) = ext_settings.keep_alive {
+                        settings.keep_alive = ext_keep_alive;
+                    }
+                    if let Some(ext_idle_timeout) = ext_settings.idle_timeout {
+                        settings.idle_timeout = ext_idle_timeout;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "mux" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid mux outbound settings"));
+                    }
+                    let mut settings = internal::MuxOutboundSettings::new();
+                    let ext_settings: MuxOutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.as_ref().unwrap().get())
+                            .unwrap();
+                    if let Some(ext_address) = ext_settings.address {
+                        settings.address = ext_address;
+                    }
+                    if let Some(ext_port) = ext_settings.port {
+                        settings.port = ext_port as u32;
+                    }
+                    if let Some(ext_actors) = ext_settings.actors {
+                        for ext_actor in ext_actors {
+                            settings.actors.push(ext_actor);
+                        }
+                    }
+                    settings.max_streams = ext_settings.max_streams.unwrap_or(8);
+                    settings.min_connections = ext_settings.min_connections.unwrap_or(1);
+                    settings.max_connections = ext_settings.max_connections.unwrap_or(4);
+                    settings.padding = ext_settings.padding.unwrap_or(false);
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "grpc" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid grpc outbound settings"));
+                    }
+                    let mut settings = internal::GrpcOutboundSettings::new();
+                    let ext_settings: GrpcOutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.as_ref().unwrap().get())
+                            .unwrap();
+                    if let Some(ext_address) = ext_settings.address {
+                        settings.address = ext_address;
+                    }
+                    if let Some(ext_port) = ext_settings.port {
+                        settings.port = ext_port as u32;
+                    }
+                    if let Some(ext_host) = ext_settings.host {
+                        settings.host = ext_host;
+                    }
+                    if let Some(ext_service_name) = ext_settings.service_name {
+                        settings.service_name = ext_service_name;
+                    }
+                    if let Some(ext_actors) = ext_settings.actors {
+                        for ext_actor in ext_actors {
+                            settings.actors.push(ext_actor);
+                        }
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "h2" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid h2 outbound settings"));
+                    }
+                    let mut settings = internal::Http2OutboundSettings::new();
+                    let ext_settings: Http2OutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.as_ref().unwrap().get())
+                            .unwrap();
+                    if let Some(ext_address) = ext_settings.address {
+                        settings.address = ext_address;
+                    }
+                    if let Some(ext_port) = ext_settings.port {
+                        settings.port = ext_port as u32;
+                    }
+                    if let Some(ext_host) = ext_settings.host {
+                        settings.host = ext_host;
+                    }
+                    if let Some(ext_path) = ext_settings.path {
+                        settings.path = ext_path;
+                    }
+                    if let Some(ext_actors) = ext_settings.actors {
+                        for ext_actor in ext_actors {
+                            settings.actors.push(ext_actor);
+                        }
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -822,6 +1982,9 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                             settings.actors.push(ext_actor);
                         }
                     }
+                    if let Some(ext_filter) = ext_settings.filter {
+                        settings.filter = ext_filter;
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -851,75 +2014,20 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
         }
     }
 
+    let geosite_file =
+        external_rule::geosite_file(json.router.as_ref().and_then(|x| x.geosite_file.as_deref()));
     let mut router = protobuf::SingularPtrField::none();
     if let Some(ext_router) = json.router.as_mut() {
         let mut int_router = internal::Router::new();
         let mut rules = protobuf::RepeatedField::new();
+        let geoip_file = external_rule::geoip_file(ext_router.geoip_file.as_deref());
+        let asn_file = external_rule::asn_file(ext_router.asn_file.as_deref());
         if let Some(ext_rules) = ext_router.rules.as_mut() {
             // a map for caching external site so we need not load a same file multiple times
             for ext_rule in ext_rules.iter_mut() {
-                let mut rule = internal::Router_Rule::new();
-                let target_tag = std::mem::take(&mut ext_rule.target);
-                rule.target_tag = target_tag;
-                if let Some(ext_ips) = ext_rule.ip.as_mut() {
-                    for ext_ip in ext_ips.drain(0..) {
-                        rule.ip_cidrs.push(ext_ip);
-                    }
-                }
-                if let Some(ext_domains) = ext_rule.domain.as_mut() {
-                    for ext_domain in ext_domains.drain(0..) {
-                        let mut domain = internal::Router_Rule_Domain::new();
-                        domain.field_type = internal::Router_Rule_Domain_Type::FULL;
-                        domain.value = ext_domain;
-                        rule.domains.push(domain);
-                    }
-                }
-                if let Some(ext_domain_keywords) = ext_rule.domain_keyword.as_mut() {
-                    for ext_domain_keyword in ext_domain_keywords.drain(0..) {
-                        let mut domain = internal::Router_Rule_Domain::new();
-                        domain.field_type = internal::Router_Rule_Domain_Type::PLAIN;
-                        domain.value = ext_domain_keyword;
-                        rule.domains.push(domain);
-                    }
-                }
-                if let Some(ext_domain_suffixes) = ext_rule.domain_suffix.as_mut() {
-                    for ext_domain_suffix in ext_domain_suffixes.drain(0..) {
-                        let mut domain = internal::Router_Rule_Domain::new();
-                        domain.field_type = internal::Router_Rule_Domain_Type::DOMAIN;
-                        domain.value = ext_domain_suffix;
-                        rule.domains.push(domain);
-                    }
-                }
-                if let Some(ext_geoips) = ext_rule.geoip.as_mut() {
-                    for ext_geoip in ext_geoips.drain(0..) {
-                        let mut mmdb = internal::Router_Rule_Mmdb::new();
-                        let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
-                        mmdb.file = asset_loc.join("geo.mmdb").to_string_lossy().to_string();
-                        mmdb.country_code = ext_geoip;
-                        rule.mmdbs.push(mmdb)
-                    }
-                }
-                if let Some(ext_externals) = ext_rule.external.as_mut() {
-                    for ext_external in ext_externals.drain(0..) {
-                        match external_rule::add_external_rule(&mut rule, &ext_external) {
-                            Ok(_) => (),
-                            Err(e) => {
-                                println!("load external rule failed: {}", e);
-                            }
-                        }
-                    }
-                }
-                if let Some(ext_port_ranges) = ext_rule.port_range.as_mut() {
-                    for ext_port_range in ext_port_ranges.drain(0..) {
-                        // FIXME validate
-                        rule.port_ranges.push(ext_port_range);
-                    }
-                }
-                if let Some(ext_its) = ext_rule.inbound_tag.as_mut() {
-                    for it in ext_its.drain(0..) {
-                        rule.inbound_tags.push(it);
-                    }
-                }
+                let mut rule = to_internal_rule(ext_rule, &geoip_file, &geosite_file, &asn_file);
+                rule.target_tag = std::mem::take(&mut ext_rule.target);
+                rule.no_resolve = ext_rule.no_resolve.unwrap_or(false);
                 rules.push(rule);
             }
         }
@@ -927,13 +2035,31 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
         if let Some(ext_domain_resolve) = ext_router.domain_resolve {
             int_router.domain_resolve = ext_domain_resolve;
         }
+        if let Some(ext_script) = ext_router.script.as_ref() {
+            int_router.script = external_rule::asset_path(ext_script);
+        }
         router = protobuf::SingularPtrField::some(int_router);
     }
 
+    // The default outbounds of the inbounds follow all the other rules.
+    for ext_inbound in json.inbounds.iter().flatten() {
+        if let (Some(ext_tag), Some(ext_default_outbound)) =
+            (&ext_inbound.tag, &ext_inbound.default_outbound)
+        {
+            let mut rule = internal::Router_Rule::new();
+            rule.inbound_tags.push(ext_tag.clone());
+            rule.target_tag = ext_default_outbound.clone();
+            if router.is_none() {
+                router = protobuf::SingularPtrField::some(internal::Router::new());
+            }
+            router.as_mut().unwrap().rules.push(rule);
+        }
+    }
+
     let mut dns = internal::Dns::new();
     let mut servers = protobuf::RepeatedField::new();
     let mut hosts = HashMap::new();
-    if let Some(ext_dns) = &json.dns {
+    if let Some(ext_dns) = json.dns.as_mut() {
         if let Some(ext_servers) = ext_dns.servers.as_ref() {
             for ext_server in ext_servers {
                 servers.push(ext_server.to_owned());
@@ -950,6 +2076,19 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
                 hosts.insert(name.to_owned(), ips);
             }
         }
+        if let Some(ext_rules) = ext_dns.rules.as_mut() {
+            for ext_rule in ext_rules.iter_mut() {
+                let rule = to_internal_rule(&mut ext_rule.rule, "", &geosite_file, "");
+                let mut dns_rule = internal::Dns_Rule::new();
+                dns_rule.domains = rule.domains;
+                dns_rule.servers =
+                    protobuf::RepeatedField::from_vec(std::mem::take(&mut ext_rule.servers));
+                dns.rules.push(dns_rule);
+            }
+        }
+        if let Some(ext_client_subnet) = ext_dns.client_subnet.as_ref() {
+            dns.client_subnet = ext_client_subnet.to_owned();
+        }
     }
     if servers.len() == 0 {
         servers.push("114.114.114.114".to_string());
@@ -960,12 +2099,56 @@ pub fn to_internal(json: &mut Config) -> Result<internal::Config> {
         dns.hosts = hosts;
     }
 
+    let mut providers = protobuf::RepeatedField::new();
+    if let Some(ext_providers) = &json.providers {
+        for ext_provider in ext_providers {
+            let mut provider = internal::Provider::new();
+            provider.tag = ext_provider.tag.clone();
+            provider.url = ext_provider.url.clone();
+            if let Some(ext_path) = &ext_provider.path {
+                let path = Path::new(ext_path);
+                if path.is_absolute() {
+                    provider.path = path.to_string_lossy().to_string();
+                } else {
+                    let asset_loc = Path::new(&*crate::option::ASSET_LOCATION);
+                    provider.path = asset_loc.join(path).to_string_lossy().to_string();
+                }
+            }
+            if let Some(ext_interval) = ext_provider.interval {
+                provider.interval = ext_interval;
+            }
+            providers.push(provider);
+        }
+    }
+
+    let mut rule_providers = protobuf::RepeatedField::new();
+    if let Some(ext_rule_providers) = &json.rule_providers {
+        for ext_rule_provider in ext_rule_providers {
+            let mut provider = internal::Provider::new();
+            provider.tag = ext_rule_provider.tag.clone();
+            provider.url = ext_rule_provider.url.clone();
+            if let Some(ext_path) = &ext_rule_provider.path {
+                provider.path = external_rule::asset_path(ext_path);
+            }
+            if let Some(ext_interval) = ext_rule_provider.interval {
+                provider.interval = ext_interval;
+            }
+            provider.format = ext_rule_provider
+                .format
+                .clone()
+                .unwrap_or_else(|| "text".to_string());
+            rule_providers.push(provider);
+        }
+    }
+
     let mut config = internal::Config::new();
     config.log = protobuf::SingularPtrField::some(log);
     config.inbounds = inbounds;
     config.outbounds = outbounds;
     config.router = router;
     config.dns = protobuf::SingularPtrField::some(dns);
+    config.providers = providers;
+    config.rule_providers = rule_providers;
     Ok(config)
 }
 
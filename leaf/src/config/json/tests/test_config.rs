@@ -90,3 +90,74 @@ fn test_config() {
 
     assert!(crate::config::json::json_from_string(json_str).is_ok());
 }
+
+#[test]
+fn test_socks_users() {
+    use protobuf::Message;
+
+    let json_str = r#"
+    {
+        "inbounds": [
+            {
+                "address": "127.0.0.1",
+                "port": 1086,
+                "protocol": "socks",
+                "settings": {
+                    "users": [
+                        {
+                            "username": "alice",
+                            "password": "pass"
+                        }
+                    ]
+                }
+            }
+        ]
+    }
+    "#;
+
+    let config = crate::config::json::from_string(json_str).unwrap();
+    let settings =
+        crate::config::SocksInboundSettings::parse_from_bytes(&config.inbounds[0].settings)
+            .unwrap();
+    assert_eq!(settings.users.len(), 1);
+    assert_eq!(settings.users[0].username, "alice");
+    assert_eq!(settings.users[0].password, "pass");
+}
+
+#[test]
+fn test_sniffing() {
+    let json_str = r#"
+    {
+        "inbounds": [
+            {
+                "address": "127.0.0.1",
+                "port": 1086,
+                "protocol": "socks",
+                "sniffing": {
+                    "protocols": ["tls", "http"],
+                    "ports": [80, 443],
+                    "routeOnly": true
+                }
+            },
+            {
+                "address": "127.0.0.1",
+                "port": 1087,
+                "protocol": "http",
+                "sniffing": {
+                    "protocols": ["ftp"]
+                }
+            }
+        ]
+    }
+    "#;
+
+    assert!(crate::config::json::from_string(json_str).is_err());
+
+    let json_str = json_str.replace("\"ftp\"", "\"quic\"");
+    let config = crate::config::json::from_string(&json_str).unwrap();
+    let sniffing = config.inbounds[0].sniffing.as_ref().unwrap();
+    assert!(sniffing.enabled);
+    assert_eq!(sniffing.protocols.to_vec(), vec!["tls", "http"]);
+    assert_eq!(sniffing.ports, vec![80, 443]);
+    assert!(sniffing.route_only);
+}
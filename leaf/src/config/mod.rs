@@ -7,6 +7,11 @@ pub mod external_rule;
 pub mod geosite;
 pub mod internal;
 
+#[cfg(feature = "provider")]
+pub mod provider;
+#[cfg(feature = "provider")]
+pub mod rule_provider;
+
 #[cfg(feature = "config-json")]
 pub mod json;
 
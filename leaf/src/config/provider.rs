@@ -0,0 +1,512 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use anyhow::Result;
+use log::*;
+use percent_encoding::percent_decode_str;
+use protobuf::Message;
+
+use super::{conf, internal};
+
+/// Returns the path of the local copy of the provider.
+pub fn local_path(provider: &internal::Provider) -> Result<PathBuf> {
+    if !provider.path.is_empty() {
+        return Ok(Path::new(&provider.path).to_owned());
+    }
+    // Rule providers have a format.
+    if !provider.format.is_empty() {
+        return crate::app::get_cache_file_path(&format!("rule_provider_{}", &provider.tag));
+    }
+    crate::app::get_cache_file_path(&format!("provider_{}", &provider.tag))
+}
+
+/// Checks whether fetched content is usable by the provider.
+pub fn check(_provider: &internal::Provider, content: &[u8]) -> Result<()> {
+    parse(std::str::from_utf8(content)?)?;
+    Ok(())
+}
+
+fn decode_base64(s: &str) -> Option<String> {
+    let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+    let s = s.trim_end_matches('=');
+    let bytes = base64::decode_config(s, base64::STANDARD_NO_PAD)
+        .or_else(|_| base64::decode_config(s, base64::URL_SAFE_NO_PAD))
+        .ok()?;
+    String::from_utf8(bytes).ok()
+}
+
+fn decode_percent(s: &str) -> String {
+    percent_decode_str(s).decode_utf8_lossy().to_string()
+}
+
+fn new_proxy(tag: &str, protocol: &str, address: &str, port: u16) -> Result<conf::Proxy> {
+    // Commas separate the actors of groups in the conf format.
+    let tag = tag.trim().replace(',', " ");
+    if tag.is_empty() {
+        return Err(anyhow!("missing name"));
+    }
+    if address.is_empty() || port == 0 {
+        return Err(anyhow!("invalid server address of {}", tag));
+    }
+    Ok(conf::Proxy {
+        tag,
+        protocol: protocol.to_string(),
+        address: Some(
+            address
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string(),
+        ),
+        port: Some(port),
+        ..Default::default()
+    })
+}
+
+// Transport of the trojan and vless URIs, e.g. type=ws&path=/p&host=h.
+fn set_transport(proxy: &mut conf::Proxy, query: &HashMap<String, String>) -> Result<()> {
+    match query.get("type").map(String::as_str) {
+        None | Some("tcp") => (),
+        Some("ws") => {
+            proxy.ws = Some(true);
+            proxy.ws_path = query.get("path").cloned();
+            proxy.ws_host = query.get("host").cloned();
+        }
+        Some("grpc") => {
+            proxy.grpc = Some(true);
+            proxy.grpc_service_name = query.get("serviceName").cloned();
+        }
+        Some("h2") | Some("http") => {
+            proxy.h2 = Some(true);
+            proxy.h2_path = query.get("path").cloned();
+            proxy.h2_host = query.get("host").cloned();
+        }
+        Some(t) => return Err(anyhow!("unsupported transport {}", t)),
+    }
+    proxy.sni = query.get("sni").or_else(|| query.get("peer")).cloned();
+    Ok(())
+}
+
+// ss://base64(method:password)@host:port/?plugin=...#name (SIP002), or
+// ss://base64(method:password@host:port)#name
+fn parse_ss_uri(uri: &str) -> Result<conf::Proxy> {
+    let rest = &uri["ss://".len()..];
+    let (rest, name) = rest.split_once('#').unwrap_or((rest, ""));
+    let url = if rest.contains('@') {
+        url::Url::parse(uri)?
+    } else {
+        let decoded = decode_base64(rest).ok_or_else(|| anyhow!("invalid base64"))?;
+        url::Url::parse(&format!("ss://{}", decoded))?
+    };
+    let (method, password) = match url.password() {
+        Some(password) => (decode_percent(url.username()), decode_percent(password)),
+        None => {
+            let userinfo = decode_base64(&decode_percent(url.username()))
+                .ok_or_else(|| anyhow!("invalid base64"))?;
+            let (method, password) = userinfo
+                .split_once(':')
+                .ok_or_else(|| anyhow!("missing password"))?;
+            (method.to_string(), password.to_string())
+        }
+    };
+    let mut proxy = new_proxy(
+        &decode_percent(name),
+        "ss",
+        url.host_str().unwrap_or_default(),
+        url.port().unwrap_or_default(),
+    )?;
+    proxy.encrypt_method = Some(method);
+    proxy.password = Some(password);
+    for (k, v) in url.query_pairs() {
+        if k != "plugin" {
+            continue;
+        }
+        // e.g. obfs-local;obfs=http;obfs-host=example.com
+        let mut opts = v.split(';');
+        match opts.next() {
+            Some("obfs-local") | Some("simple-obfs") => (),
+            Some(p) => return Err(anyhow!("unsupported plugin {}", p)),
+            None => continue,
+        }
+        for opt in opts {
+            match opt.split_once('=') {
+                Some(("obfs", v)) => proxy.obfs = Some(v.to_string()),
+                Some(("obfs-host", v)) => proxy.obfs_host = Some(v.to_string()),
+                Some(("obfs-uri", v)) => proxy.obfs_uri = Some(v.to_string()),
+                _ => (),
+            }
+        }
+    }
+    Ok(proxy)
+}
+
+// trojan://password@host:port?sni=...&type=ws&path=...#name
+fn parse_trojan_uri(uri: &str) -> Result<conf::Proxy> {
+    let url = url::Url::parse(uri)?;
+    let mut proxy = new_proxy(
+        &decode_percent(url.fragment().unwrap_or_default()),
+        "trojan",
+        url.host_str().unwrap_or_default(),
+        url.port().unwrap_or(443),
+    )?;
+    proxy.password = Some(decode_percent(url.username()));
+    let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    set_transport(&mut proxy, &query)?;
+    Ok(proxy)
+}
+
+// vless://uuid@host:port?security=tls&sni=...&type=ws&path=...#name
+fn parse_vless_uri(uri: &str) -> Result<conf::Proxy> {
+    let url = url::Url::parse(uri)?;
+    let mut proxy = new_proxy(
+        &decode_percent(url.fragment().unwrap_or_default()),
+        "vless",
+        url.host_str().unwrap_or_default(),
+        url.port().unwrap_or(443),
+    )?;
+    proxy.uuid = Some(decode_percent(url.username()));
+    let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    if let Some(flow) = query.get("flow").filter(|v| !v.is_empty()) {
+        return Err(anyhow!("unsupported flow {}", flow));
+    }
+    match query.get("security").map(String::as_str) {
+        None | Some("none") | Some("") => (),
+        Some("tls") => proxy.tls = Some(true),
+        Some("reality") => {
+            proxy.tls = Some(true);
+            proxy.reality_public_key = query.get("pbk").cloned();
+            proxy.reality_short_id = query.get("sid").cloned();
+        }
+        Some(s) => return Err(anyhow!("unsupported security {}", s)),
+    }
+    set_transport(&mut proxy, &query)?;
+    Ok(proxy)
+}
+
+fn parse_uri(uri: &str) -> Result<conf::Proxy> {
+    match uri.split_once("://").map(|(scheme, _)| scheme) {
+        Some("ss") => parse_ss_uri(uri),
+        Some("trojan") => parse_trojan_uri(uri),
+        Some("vless") => parse_vless_uri(uri),
+        Some(scheme) => Err(anyhow!("unsupported scheme {}", scheme)),
+        None => Err(anyhow!("invalid uri")),
+    }
+}
+
+fn yaml_str<'a>(v: &'a serde_yaml::Value, k: &str) -> Option<&'a str> {
+    v.get(k)?.as_str()
+}
+
+fn yaml_string(v: &serde_yaml::Value, k: &str) -> Option<String> {
+    match v.get(k)? {
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn yaml_bool(v: &serde_yaml::Value, k: &str) -> bool {
+    v.get(k)
+        .and_then(serde_yaml::Value::as_bool)
+        .unwrap_or(false)
+}
+
+// An item of the proxies of clash.
+fn parse_clash_proxy(v: &serde_yaml::Value) -> Result<conf::Proxy> {
+    let protocol = yaml_str(v, "type").unwrap_or_default();
+    let port = v
+        .get("port")
+        .and_then(|p| match p {
+            serde_yaml::Value::Number(n) => n.as_u64(),
+            serde_yaml::Value::String(s) => s.parse().ok(),
+            _ => None,
+        })
+        .unwrap_or_default();
+    let mut proxy = new_proxy(
+        yaml_str(v, "name").unwrap_or_default(),
+        protocol,
+        yaml_str(v, "server").unwrap_or_default(),
+        port as u16,
+    )?;
+    match protocol {
+        "ss" => {
+            proxy.encrypt_method = yaml_string(v, "cipher");
+            proxy.password = yaml_string(v, "password");
+            proxy.udp_over_tcp = Some(yaml_bool(v, "udp-over-tcp"));
+            match yaml_str(v, "plugin") {
+                None => (),
+                Some("obfs") => {
+                    let opts = v.get("plugin-opts");
+                    proxy.obfs = opts.and_then(|o| yaml_string(o, "mode"));
+                    proxy.obfs_host = opts.and_then(|o| yaml_string(o, "host"));
+                }
+                Some(p) => return Err(anyhow!("unsupported plugin {}", p)),
+            }
+        }
+        "trojan" => {
+            proxy.password = yaml_string(v, "password");
+        }
+        "vless" => {
+            proxy.uuid = yaml_string(v, "uuid");
+            proxy.tls = Some(yaml_bool(v, "tls"));
+            if let Some(flow) = yaml_str(v, "flow").filter(|f| !f.is_empty()) {
+                return Err(anyhow!("unsupported flow {}", flow));
+            }
+            if let Some(opts) = v.get("reality-opts") {
+                proxy.reality_public_key = yaml_string(opts, "public-key");
+                proxy.reality_short_id = yaml_string(opts, "short-id");
+            }
+        }
+        _ => return Err(anyhow!("unsupported type {}", protocol)),
+    }
+    if protocol != "ss" {
+        proxy.sni = yaml_string(v, "sni").or_else(|| yaml_string(v, "servername"));
+        match yaml_str(v, "network") {
+            None | Some("tcp") => (),
+            Some("ws") => {
+                proxy.ws = Some(true);
+                let opts = v.get("ws-opts");
+                proxy.ws_path = opts.and_then(|o| yaml_string(o, "path"));
+                proxy.ws_host = opts
+                    .and_then(|o| o.get("headers"))
+                    .and_then(|h| yaml_string(h, "Host"));
+            }
+            Some("grpc") => {
+                proxy.grpc = Some(true);
+                proxy.grpc_service_name = v
+                    .get("grpc-opts")
+                    .and_then(|o| yaml_string(o, "grpc-service-name"));
+            }
+            Some(n) => return Err(anyhow!("unsupported network {}", n)),
+        }
+    }
+    Ok(proxy)
+}
+
+fn parse_clash(content: &str) -> Result<Vec<Result<conf::Proxy>>> {
+    let doc: serde_yaml::Value = serde_yaml::from_str(content)?;
+    let proxies = doc
+        .get("proxies")
+        .and_then(serde_yaml::Value::as_sequence)
+        .ok_or_else(|| anyhow!("missing proxies"))?;
+    Ok(proxies.iter().map(parse_clash_proxy).collect())
+}
+
+/// Parses the content of a provider, which is either a list of URIs, maybe
+/// base64 encoded, or a clash config. Unsupported entries are skipped.
+pub fn parse(content: &str) -> Result<Vec<conf::Proxy>> {
+    let items = if content.lines().any(|l| l.starts_with("proxies:")) {
+        parse_clash(content)?
+    } else {
+        let decoded;
+        let content = if content.contains("://") {
+            content
+        } else {
+            decoded = decode_base64(content).ok_or_else(|| anyhow!("unknown format"))?;
+            decoded.as_str()
+        };
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(parse_uri)
+            .collect()
+    };
+    let mut proxies = Vec::new();
+    for item in items {
+        match item {
+            Ok(p) => proxies.push(p),
+            Err(e) => debug!("skip provider entry: {}", e),
+        }
+    }
+    if proxies.is_empty() {
+        return Err(anyhow!("no supported proxies"));
+    }
+    Ok(proxies)
+}
+
+// Replaces the tags of providers in the actors with the tags of their
+// outbounds.
+fn expand_actors<M: Message>(
+    settings: &[u8],
+    actors: fn(&mut M) -> &mut protobuf::RepeatedField<String>,
+    members: &HashMap<String, Vec<String>>,
+) -> Result<Vec<u8>> {
+    let mut settings = M::parse_from_bytes(settings)?;
+    let mut expanded = protobuf::RepeatedField::new();
+    for actor in actors(&mut settings).iter() {
+        if let Some(tags) = members.get(actor) {
+            expanded.extend(tags.iter().cloned());
+        } else {
+            expanded.push(actor.clone());
+        }
+    }
+    *actors(&mut settings) = expanded;
+    Ok(settings.write_to_bytes()?)
+}
+
+/// Appends the outbounds of the providers from their local copies, and
+/// replaces the tags of the providers in the actors of groups with the tags
+/// of these outbounds.
+pub fn apply(config: &mut internal::Config) -> Result<()> {
+    let mut tags: HashSet<String> = config.outbounds.iter().map(|o| o.tag.clone()).collect();
+    let mut members = HashMap::new();
+    for provider in config.providers.iter() {
+        let path = local_path(provider)?;
+        let proxies = match std::fs::read_to_string(&path) {
+            Ok(content) => parse(&content)
+                .map_err(|e| anyhow!("invalid [{}] provider content: {}", &provider.tag, e))?,
+            Err(e) => {
+                warn!(
+                    "load [{}] provider from {} failed: {}",
+                    &provider.tag,
+                    path.display(),
+                    e
+                );
+                Vec::new()
+            }
+        };
+        let mut proxy_tags = Vec::new();
+        let mut ext_proxies = Vec::new();
+        for proxy in proxies {
+            if !tags.insert(proxy.tag.clone()) {
+                warn!(
+                    "skip duplicated outbound [{}] of [{}] provider",
+                    &proxy.tag, &provider.tag
+                );
+                continue;
+            }
+            proxy_tags.push(proxy.tag.clone());
+            ext_proxies.push(proxy);
+        }
+        let mut ext_config = conf::Config {
+            proxy: Some(ext_proxies),
+            ..Default::default()
+        };
+        let outbounds = conf::to_internal(&mut ext_config)?.outbounds;
+        debug!(
+            "loaded {} outbounds from [{}] provider",
+            proxy_tags.len(),
+            &provider.tag
+        );
+        config.outbounds.extend(outbounds);
+        members.insert(provider.tag.clone(), proxy_tags);
+    }
+    if members.is_empty() {
+        return Ok(());
+    }
+    for outbound in config.outbounds.iter_mut() {
+        let settings = match outbound.protocol.as_str() {
+            "select" => expand_actors::<internal::SelectOutboundSettings>(
+                &outbound.settings,
+                |s| &mut s.actors,
+                &members,
+            ),
+            "failover" => expand_actors::<internal::FailOverOutboundSettings>(
+                &outbound.settings,
+                |s| &mut s.actors,
+                &members,
+            ),
+            "urltest" => expand_actors::<internal::UrlTestOutboundSettings>(
+                &outbound.settings,
+                |s| &mut s.actors,
+                &members,
+            ),
+            "static" => expand_actors::<internal::StaticOutboundSettings>(
+                &outbound.settings,
+                |s| &mut s.actors,
+                &members,
+            ),
+            "tryall" => expand_actors::<internal::TryAllOutboundSettings>(
+                &outbound.settings,
+                |s| &mut s.actors,
+                &members,
+            ),
+            _ => continue,
+        };
+        outbound.settings = settings
+            .map_err(|e| anyhow!("invalid [{}] outbound settings: {}", &outbound.tag, e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_uris() {
+        let content = base64::encode(
+            "ss://YWVzLTEyOC1nY206dGVzdA@192.168.100.1:8888#Example%201\n\
+             ss://YWVzLTEyOC1nY206dGVzdEAxOTIuMTY4LjEwMC4xOjg4ODg#Example2\n\
+             trojan://pass@example.com:443?sni=sni.example.com&type=ws&path=%2Fws#HK%2001\n\
+             vless://b831381d-6324-4d53-ad4f-8cda48b30811@[::1]:8443?security=tls#JP\n\
+             vmess://eyJhZGQiOiIxLjEuMS4xIn0=\n",
+        );
+        let proxies = parse(&content).unwrap();
+        assert_eq!(proxies.len(), 4);
+        assert_eq!(proxies[0].tag, "Example 1");
+        assert_eq!(proxies[0].protocol, "ss");
+        assert_eq!(proxies[0].address.as_deref(), Some("192.168.100.1"));
+        assert_eq!(proxies[0].port, Some(8888));
+        assert_eq!(proxies[0].encrypt_method.as_deref(), Some("aes-128-gcm"));
+        assert_eq!(proxies[0].password.as_deref(), Some("test"));
+        assert_eq!(proxies[1].tag, "Example2");
+        assert_eq!(proxies[1].password.as_deref(), Some("test"));
+        assert_eq!(proxies[2].tag, "HK 01");
+        assert_eq!(proxies[2].sni.as_deref(), Some("sni.example.com"));
+        assert_eq!(proxies[2].ws, Some(true));
+        assert_eq!(proxies[2].ws_path.as_deref(), Some("/ws"));
+        assert_eq!(proxies[3].address.as_deref(), Some("::1"));
+        assert_eq!(proxies[3].tls, Some(true));
+    }
+
+    #[test]
+    fn test_parse_clash() {
+        let content = r#"
+port: 7890
+proxies:
+  - name: "SG 01"
+    type: ss
+    server: sg.example.com
+    port: 8388
+    cipher: chacha20-ietf-poly1305
+    password: "pass"
+  - name: US
+    type: trojan
+    server: us.example.com
+    port: 443
+    password: pass
+    network: grpc
+    grpc-opts:
+      grpc-service-name: svc
+  - name: Unsupported
+    type: vmess
+    server: vmess.example.com
+    port: 443
+"#;
+        let proxies = parse(content).unwrap();
+        assert_eq!(proxies.len(), 2);
+        assert_eq!(proxies[0].tag, "SG 01");
+        assert_eq!(proxies[0].port, Some(8388));
+        assert_eq!(proxies[1].grpc, Some(true));
+        assert_eq!(proxies[1].grpc_service_name.as_deref(), Some("svc"));
+    }
+
+    #[test]
+    fn test_expand_actors() {
+        let mut settings = internal::SelectOutboundSettings::new();
+        settings.actors.push("Direct".to_string());
+        settings.actors.push("sub".to_string());
+        let mut members = HashMap::new();
+        members.insert("sub".to_string(), vec!["HK".to_string(), "JP".to_string()]);
+        let settings = expand_actors::<internal::SelectOutboundSettings>(
+            &settings.write_to_bytes().unwrap(),
+            |s| &mut s.actors,
+            &members,
+        )
+        .unwrap();
+        let settings = internal::SelectOutboundSettings::parse_from_bytes(&settings).unwrap();
+        assert_eq!(settings.actors.to_vec(), vec!["Direct", "HK", "JP"]);
+    }
+}
@@ -0,0 +1,208 @@
+use std::net::IpAddr;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use log::*;
+use protobuf::Message;
+
+use super::{external_rule, geosite, internal, provider::local_path};
+
+fn add_domain(rule: &mut internal::Router_Rule, t: internal::Router_Rule_Domain_Type, v: &str) {
+    let mut domain = internal::Router_Rule_Domain::new();
+    domain.field_type = t;
+    domain.value = v.to_string();
+    rule.domains.push(domain);
+}
+
+fn add_ip_cidr(rule: &mut internal::Router_Rule, v: &str) -> Result<()> {
+    let (ip, prefix) = v.split_once('/').unwrap_or((v, ""));
+    let ip = ip.parse::<IpAddr>()?;
+    if prefix.is_empty() {
+        let prefix = if ip.is_ipv4() { 32 } else { 128 };
+        rule.ip_cidrs.push(format!("{}/{}", ip, prefix));
+    } else {
+        prefix.parse::<u8>()?;
+        rule.ip_cidrs.push(v.to_string());
+    }
+    Ok(())
+}
+
+// An entry of a text rule provider, which is one of
+// - `DOMAIN,example.com`, `DOMAIN-SUFFIX,...`, `DOMAIN-KEYWORD,...`,
+//   `DOMAIN-REGEX,...`, `IP-CIDR,...` and `IP-CIDR6,...`, as the rules of
+//   clash,
+// - `full:example.com`, `domain:...`, `keyword:...` and `regexp:...`, as the
+//   domain lists of V2Ray,
+// - `+.example.com` or `.example.com` for the domain and its subdomains,
+// - an IP or a CIDR,
+// - or a domain to match fully.
+fn parse_entry(rule: &mut internal::Router_Rule, entry: &str) -> Result<()> {
+    use internal::Router_Rule_Domain_Type::*;
+    if let Some((t, v)) = entry.split_once(',') {
+        // Ignores the trailing options, e.g. no-resolve.
+        let v = v.split(',').next().unwrap_or_default().trim();
+        match t.trim() {
+            "DOMAIN" => add_domain(rule, FULL, v),
+            "DOMAIN-SUFFIX" => add_domain(rule, DOMAIN, v),
+            "DOMAIN-KEYWORD" => add_domain(rule, PLAIN, v),
+            "DOMAIN-REGEX" => add_domain(rule, REGEX, v),
+            "IP-CIDR" | "IP-CIDR6" => add_ip_cidr(rule, v)?,
+            t => return Err(anyhow!("unsupported rule type {}", t)),
+        }
+    } else if let Some(v) = entry.strip_prefix("full:") {
+        add_domain(rule, FULL, v);
+    } else if let Some(v) = entry.strip_prefix("domain:") {
+        add_domain(rule, DOMAIN, v);
+    } else if let Some(v) = entry.strip_prefix("keyword:") {
+        add_domain(rule, PLAIN, v);
+    } else if let Some(v) = entry.strip_prefix("regexp:") {
+        add_domain(rule, REGEX, v);
+    } else if let Some(v) = entry.strip_prefix("+.").or_else(|| entry.strip_prefix('.')) {
+        add_domain(rule, DOMAIN, v);
+    } else if add_ip_cidr(rule, entry).is_err() {
+        if entry.contains(char::is_whitespace) || entry.contains('/') {
+            return Err(anyhow!("invalid entry {}", entry));
+        }
+        add_domain(rule, FULL, entry);
+    }
+    Ok(())
+}
+
+/// Adds the entries of a text rule provider to the rule. The content has an
+/// entry per line, or is a YAML `payload` list of the rule providers of clash.
+/// Returns the number of entries added, unsupported entries are skipped.
+pub fn parse(rule: &mut internal::Router_Rule, content: &str) -> Result<usize> {
+    let mut n = 0;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+        if line == "payload:" {
+            continue;
+        }
+        let entry = line
+            .strip_prefix("- ")
+            .unwrap_or(line)
+            .trim()
+            .trim_matches(|c| c == '\'' || c == '"');
+        match parse_entry(rule, entry) {
+            Ok(_) => n += 1,
+            Err(e) => debug!("skip rule provider entry: {}", e),
+        }
+    }
+    if n == 0 {
+        return Err(anyhow!("no supported entries"));
+    }
+    Ok(n)
+}
+
+/// Checks whether fetched content is usable by the rule provider.
+pub fn check(provider: &internal::Provider, content: &[u8]) -> Result<()> {
+    if provider.format == "dat" {
+        geosite::SiteGroupList::parse_from_bytes(content)?;
+    } else {
+        parse(
+            &mut internal::Router_Rule::new(),
+            std::str::from_utf8(content)?,
+        )?;
+    }
+    Ok(())
+}
+
+fn add_rule_set(
+    rule: &mut internal::Router_Rule,
+    providers: &[internal::Provider],
+    rule_set: &str,
+) -> Result<()> {
+    let (tag, category) = rule_set.split_once(':').unwrap_or((rule_set, ""));
+    let provider = providers
+        .iter()
+        .find(|p| p.tag == tag)
+        .ok_or_else(|| anyhow!("rule provider [{}] not found", tag))?;
+    let path = local_path(provider)?;
+    if provider.format == "dat" {
+        if category.is_empty() {
+            return Err(anyhow!("missing category of [{}] rule provider", tag));
+        }
+        external_rule::add_site_rule(rule, &path.to_string_lossy(), category)?;
+    } else {
+        let n = parse(rule, &std::fs::read_to_string(&path)?)?;
+        debug!("loaded {} entries from [{}] rule provider", n, tag);
+    }
+    Ok(())
+}
+
+/// Adds the entries of the rule providers from their local copies to the
+/// rules referring to them.
+pub fn apply(config: &mut internal::Config) -> Result<()> {
+    let providers = &config.rule_providers;
+    let router = if let Some(router) = config.router.as_mut() {
+        router
+    } else {
+        return Ok(());
+    };
+    for rule in router.rules.iter_mut() {
+        apply_rule(rule, providers);
+    }
+    Ok(())
+}
+
+fn apply_rule(rule: &mut internal::Router_Rule, providers: &[internal::Provider]) {
+    for rule_set in std::mem::take(&mut rule.rule_sets).iter() {
+        if let Err(e) = add_rule_set(rule, providers, rule_set) {
+            warn!("load rule set [{}] failed: {}", rule_set, e);
+        }
+    }
+    for sub_rule in rule
+        .and_rules
+        .iter_mut()
+        .chain(rule.or_rules.iter_mut())
+        .chain(rule.not_rules.iter_mut())
+    {
+        apply_rule(sub_rule, providers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let mut rule = internal::Router_Rule::new();
+        let content = "# comment\n\
+            example.com\n\
+            +.google.com\n\
+            keyword:ads\n\
+            1.2.3.4\n\
+            10.0.0.0/8\n\
+            DOMAIN-SUFFIX,apple.com\n\
+            IP-CIDR6,2001:db8::/32,no-resolve\n\
+            PROCESS-NAME,curl\n";
+        assert_eq!(parse(&mut rule, content).unwrap(), 7);
+        assert_eq!(rule.domains.len(), 4);
+        assert_eq!(rule.domains[0].value, "example.com");
+        assert_eq!(
+            rule.domains[0].field_type,
+            internal::Router_Rule_Domain_Type::FULL
+        );
+        assert_eq!(rule.domains[1].value, "google.com");
+        assert_eq!(
+            rule.domains[1].field_type,
+            internal::Router_Rule_Domain_Type::DOMAIN
+        );
+        assert_eq!(
+            rule.ip_cidrs.to_vec(),
+            vec!["1.2.3.4/32", "10.0.0.0/8", "2001:db8::/32"]
+        );
+
+        let mut rule = internal::Router_Rule::new();
+        let content = "payload:\n  - '+.example.com'\n  - \"192.168.0.0/16\"\n";
+        assert_eq!(parse(&mut rule, content).unwrap(), 2);
+        assert_eq!(rule.domains[0].value, "example.com");
+        assert_eq!(rule.ip_cidrs[0], "192.168.0.0/16");
+
+        assert!(parse(&mut internal::Router_Rule::new(), "# empty\n").is_err());
+    }
+}
@@ -38,7 +38,10 @@ pub mod util;
 #[cfg(any(target_os = "ios", target_os = "macos", target_os = "android"))]
 pub mod mobile;
 
-#[cfg(all(feature = "inbound-tun", any(target_os = "macos", target_os = "linux")))]
+#[cfg(all(
+    any(feature = "inbound-tun", feature = "inbound-tun-smoltcp"),
+    any(target_os = "macos", target_os = "linux")
+))]
 mod sys;
 
 #[derive(Error, Debug)]
@@ -71,8 +74,9 @@ pub struct RuntimeManager {
     #[cfg(feature = "auto-reload")]
     auto_reload: bool,
     reload_tx: mpsc::Sender<std::sync::mpsc::SyncSender<Result<(), Error>>>,
+    reload_router_tx: mpsc::Sender<std::sync::mpsc::SyncSender<Result<(), Error>>>,
     shutdown_tx: mpsc::Sender<()>,
-    router: Arc<RwLock<Router>>,
+    router: Arc<RwLock<Arc<Router>>>,
     dns_client: Arc<RwLock<DnsClient>>,
     outbound_manager: Arc<RwLock<OutboundManager>>,
     #[cfg(feature = "stat")]
@@ -88,8 +92,9 @@ impl RuntimeManager {
         config_path: Option<String>,
         #[cfg(feature = "auto-reload")] auto_reload: bool,
         reload_tx: mpsc::Sender<std::sync::mpsc::SyncSender<Result<(), Error>>>,
+        reload_router_tx: mpsc::Sender<std::sync::mpsc::SyncSender<Result<(), Error>>>,
         shutdown_tx: mpsc::Sender<()>,
-        router: Arc<RwLock<Router>>,
+        router: Arc<RwLock<Arc<Router>>>,
         dns_client: Arc<RwLock<DnsClient>>,
         outbound_manager: Arc<RwLock<OutboundManager>>,
         #[cfg(feature = "stat")] stat_manager: SyncStatManager,
@@ -101,6 +106,7 @@ impl RuntimeManager {
             #[cfg(feature = "auto-reload")]
             auto_reload,
             reload_tx,
+            reload_router_tx,
             shutdown_tx,
             router,
             dns_client,
@@ -138,6 +144,35 @@ impl RuntimeManager {
         Err(Error::Config(anyhow!("not found")))
     }
 
+    /// Returns the selected actors and the latencies of the actors in millis
+    /// of the outbounds with health checks.
+    #[allow(clippy::type_complexity)]
+    pub async fn get_outbound_latencies(
+        &self,
+    ) -> Vec<(String, Option<String>, Vec<(String, Option<u64>)>)> {
+        let health_reports = self.outbound_manager.read().await.health_reports();
+        let mut latencies = Vec::new();
+        for (tag, report) in health_reports.iter() {
+            latencies.push((
+                tag.to_owned(),
+                report.selected().await,
+                report.latencies().await,
+            ));
+        }
+        latencies
+    }
+
+    /// Returns the hits of the rules and the outbounds since the rules were
+    /// loaded.
+    pub async fn get_route_stats(&self) -> app::router::RouteStats {
+        self.router.read().await.stats()
+    }
+
+    /// Returns the hits and misses of the DNS cache since the start.
+    pub async fn get_dns_cache_stats(&self) -> app::dns_client::CacheStats {
+        self.dns_client.read().await.cache_stats().await
+    }
+
     // This function could block by an in-progress connection dialing.
     //
     // TODO Reload FakeDns. And perhaps the inbounds as long as the listening
@@ -150,17 +185,51 @@ impl RuntimeManager {
         };
         log::info!("reloading from config file: {}", config_path);
         let mut config = config::from_file(config_path).map_err(Error::Config)?;
-        self.router.write().await.reload(&mut config.router)?;
-        self.dns_client.write().await.reload(&config.dns)?;
+        #[cfg(feature = "provider")]
+        {
+            config::provider::apply(&mut config).map_err(Error::Config)?;
+            config::rule_provider::apply(&mut config).map_err(Error::Config)?;
+        }
+        // Validates everything before swapping anything, the DNS config is
+        // checked on a new client and the outbounds are only swapped once
+        // they're all loaded.
+        DnsClient::new(&config.dns)?;
         self.outbound_manager
             .write()
             .await
             .reload(&config.outbounds, self.dns_client.clone())
             .await?;
+        self.dns_client.write().await.reload(&config.dns)?;
+        self.swap_router(&mut config).await;
         log::info!("reloaded from config file: {}", config_path);
         Ok(())
     }
 
+    /// Reloads the routing rules, along with the geo databases and the rule
+    /// providers they use, from the config file. Unlike `reload`, the DNS
+    /// servers and the outbounds are kept, so are the connections.
+    pub async fn reload_router(&self) -> Result<(), Error> {
+        let config_path = if let Some(p) = self.config_path.as_ref() {
+            p
+        } else {
+            return Err(Error::NoConfigFile);
+        };
+        log::info!("reloading routing rules from config file: {}", config_path);
+        let mut config = config::from_file(config_path).map_err(Error::Config)?;
+        #[cfg(feature = "provider")]
+        config::rule_provider::apply(&mut config).map_err(Error::Config)?;
+        self.swap_router(&mut config).await;
+        log::info!("reloaded routing rules from config file: {}", config_path);
+        Ok(())
+    }
+
+    // The rules are loaded before taking the lock, so sessions are routed by
+    // the old rules until the new ones are ready.
+    async fn swap_router(&self, config: &mut config::Config) {
+        let router = Router::new(&mut config.router, self.dns_client.clone());
+        *self.router.write().await = Arc::new(router);
+    }
+
     pub fn blocking_reload(&self) -> Result<(), Error> {
         let tx = self.reload_tx.clone();
         let (res_tx, res_rx) = sync_channel(0);
@@ -173,6 +242,19 @@ impl RuntimeManager {
         }
     }
 
+    /// Reloads the routing rules on the runtime, see `reload_router`.
+    pub fn blocking_reload_router(&self) -> Result<(), Error> {
+        let tx = self.reload_router_tx.clone();
+        let (res_tx, res_rx) = sync_channel(0);
+        if let Err(e) = tx.blocking_send(res_tx) {
+            return Err(Error::AsyncChannelSend(e));
+        }
+        match res_rx.recv() {
+            Ok(res) => res,
+            Err(e) => Err(Error::SyncChannelRecv(e)),
+        }
+    }
+
     pub async fn shutdown(&self) -> bool {
         let tx = self.shutdown_tx.clone();
         if let Err(e) = tx.send(()).await {
@@ -286,6 +368,11 @@ pub fn reload(key: RuntimeId) -> Result<(), Error> {
     Err(Error::RuntimeManager)
 }
 
+/// Reloads the routing rules only, see `RuntimeManager::reload_router`.
+pub fn reload_router(key: RuntimeId) -> Result<(), Error> {
+    get_runtime_manager(key)?.blocking_reload_router()
+}
+
 pub fn shutdown(key: RuntimeId) -> bool {
     if let Ok(g) = RUNTIME_MANAGER.lock() {
         if let Some(m) = g.get(&key) {
@@ -295,6 +382,28 @@ pub fn shutdown(key: RuntimeId) -> bool {
     false
 }
 
+fn get_runtime_manager(key: RuntimeId) -> Result<Arc<RuntimeManager>, Error> {
+    if let Ok(g) = RUNTIME_MANAGER.lock() {
+        if let Some(m) = g.get(&key) {
+            return Ok(m.clone());
+        }
+    }
+    Err(Error::RuntimeManager)
+}
+
+/// Selects the `select` outbound of the `outbound` selector, the selection is
+/// persisted and restored on restarts.
+pub fn set_outbound_selected(key: RuntimeId, outbound: &str, select: &str) -> Result<(), Error> {
+    let m = get_runtime_manager(key)?;
+    futures::executor::block_on(m.set_outbound_selected(outbound, select))
+}
+
+/// Returns the tag of the outbound the `outbound` selector currently uses.
+pub fn get_outbound_selected(key: RuntimeId, outbound: &str) -> Result<String, Error> {
+    let m = get_runtime_manager(key)?;
+    futures::executor::block_on(m.get_outbound_selected(outbound))
+}
+
 pub fn is_running(key: RuntimeId) -> bool {
     RUNTIME_MANAGER.lock().unwrap().contains_key(&key)
 }
@@ -359,6 +468,7 @@ pub fn start(rt_id: RuntimeId, opts: StartOptions) -> Result<(), Error> {
     println!("start with options:\n{:#?}", opts);
 
     let (reload_tx, mut reload_rx) = mpsc::channel(1);
+    let (reload_router_tx, mut reload_router_rx) = mpsc::channel(1);
     let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
 
     let config_path = match opts.config {
@@ -392,13 +502,39 @@ pub fn start(rt_id: RuntimeId, opts: StartOptions) -> Result<(), Error> {
     let dns_client = Arc::new(RwLock::new(
         DnsClient::new(&config.dns).map_err(Error::Config)?,
     ));
+    // Providers without local copies are fetched before loading outbounds
+    // and rules.
+    #[cfg(feature = "provider")]
+    {
+        rt.block_on(app::outbound::provider::update(
+            &config.providers,
+            dns_client.clone(),
+            true,
+            config::provider::check,
+        ));
+        config::provider::apply(&mut config).map_err(Error::Config)?;
+        rt.block_on(app::outbound::provider::update(
+            &config.rule_providers,
+            dns_client.clone(),
+            true,
+            config::rule_provider::check,
+        ));
+        config::rule_provider::apply(&mut config).map_err(Error::Config)?;
+    }
     let outbound_manager = Arc::new(RwLock::new(
         OutboundManager::new(&config.outbounds, dns_client.clone()).map_err(Error::Config)?,
     ));
-    let router = Arc::new(RwLock::new(Router::new(
+    // DNS servers may be queried through the outbounds.
+    rt.block_on(async {
+        dns_client
+            .write()
+            .await
+            .set_outbound_manager(Arc::downgrade(&outbound_manager));
+    });
+    let router = Arc::new(RwLock::new(Arc::new(Router::new(
         &mut config.router,
         dns_client.clone(),
-    )));
+    ))));
     #[cfg(feature = "stat")]
     let stat_manager = Arc::new(RwLock::new(StatManager::new()));
     #[cfg(feature = "stat")]
@@ -407,6 +543,7 @@ pub fn start(rt_id: RuntimeId, opts: StartOptions) -> Result<(), Error> {
         outbound_manager.clone(),
         router.clone(),
         dns_client.clone(),
+        &config.inbounds,
         #[cfg(feature = "stat")]
         stat_manager.clone(),
     ));
@@ -418,14 +555,20 @@ pub fn start(rt_id: RuntimeId, opts: StartOptions) -> Result<(), Error> {
         .map_err(Error::Config)?;
     runners.append(&mut inbound_net_runners);
 
-    #[cfg(all(feature = "inbound-tun", any(target_os = "macos", target_os = "linux")))]
+    #[cfg(all(
+        any(feature = "inbound-tun", feature = "inbound-tun-smoltcp"),
+        any(target_os = "macos", target_os = "linux")
+    ))]
     let net_info = if inbound_manager.has_tun_listener() && inbound_manager.tun_auto() {
         sys::get_net_info()
     } else {
         sys::NetInfo::default()
     };
 
-    #[cfg(all(feature = "inbound-tun", any(target_os = "macos", target_os = "linux")))]
+    #[cfg(all(
+        any(feature = "inbound-tun", feature = "inbound-tun-smoltcp"),
+        any(target_os = "macos", target_os = "linux")
+    ))]
     {
         if let sys::NetInfo {
             default_interface: Some(iface),
@@ -442,7 +585,7 @@ pub fn start(rt_id: RuntimeId, opts: StartOptions) -> Result<(), Error> {
     }
 
     #[cfg(all(
-        feature = "inbound-tun",
+        any(feature = "inbound-tun", feature = "inbound-tun-smoltcp"),
         any(
             target_os = "ios",
             target_os = "android",
@@ -454,8 +597,44 @@ pub fn start(rt_id: RuntimeId, opts: StartOptions) -> Result<(), Error> {
         runners.push(r);
     }
 
-    #[cfg(all(feature = "inbound-tun", any(target_os = "macos", target_os = "linux")))]
-    sys::post_tun_creation_setup(&net_info);
+    // Addresses of the proxy servers must not be routed to the TUN device.
+    #[cfg(all(
+        any(feature = "inbound-tun", feature = "inbound-tun-smoltcp"),
+        any(target_os = "macos", target_os = "linux")
+    ))]
+    let excluded_addrs = if net_info.default_interface.is_some() {
+        let servers = rt.block_on(outbound_manager.read()).server_addrs();
+        let mut addrs = Vec::new();
+        for (host, _) in servers {
+            if let Ok(ip) = host.parse::<std::net::IpAddr>() {
+                addrs.push(ip);
+                continue;
+            }
+            match rt.block_on(async { dns_client.read().await.lookup(&host).await }) {
+                Ok(ips) => addrs.extend(ips),
+                Err(e) => log::warn!("resolve proxy server {} failed: {}", &host, e),
+            }
+        }
+        addrs.sort();
+        addrs.dedup();
+        addrs
+    } else {
+        Vec::new()
+    };
+
+    #[cfg(all(
+        any(feature = "inbound-tun", feature = "inbound-tun-smoltcp"),
+        any(target_os = "macos", target_os = "linux")
+    ))]
+    {
+        sys::post_tun_creation_setup(&net_info, &excluded_addrs);
+        if net_info.default_interface.is_some() {
+            sys::restore_on_panic(net_info.clone(), excluded_addrs.clone());
+        }
+    }
+
+    #[cfg(feature = "provider")]
+    let provider_dns_client = dns_client.clone();
 
     let runtime_manager = RuntimeManager::new(
         #[cfg(feature = "auto-reload")]
@@ -464,6 +643,7 @@ pub fn start(rt_id: RuntimeId, opts: StartOptions) -> Result<(), Error> {
         #[cfg(feature = "auto-reload")]
         opts.auto_reload,
         reload_tx,
+        reload_router_tx,
         shutdown_tx,
         router,
         dns_client,
@@ -498,6 +678,43 @@ pub fn start(rt_id: RuntimeId, opts: StartOptions) -> Result<(), Error> {
         }
     }
 
+    // Update providers periodically, the outbounds and rules are reloaded on
+    // changes if started with a config file.
+    #[cfg(feature = "provider")]
+    if !config.providers.is_empty() || !config.rule_providers.is_empty() {
+        let providers = config.providers.to_vec();
+        let rule_providers = config.rule_providers.to_vec();
+        let rm = runtime_manager.clone();
+        runners.push(Box::pin(async move {
+            loop {
+                tokio::time::sleep(app::outbound::provider::CHECK_INTERVAL).await;
+                let changed = app::outbound::provider::update(
+                    &providers,
+                    provider_dns_client.clone(),
+                    false,
+                    config::provider::check,
+                )
+                .await;
+                let rules_changed = app::outbound::provider::update(
+                    &rule_providers,
+                    provider_dns_client.clone(),
+                    false,
+                    config::rule_provider::check,
+                )
+                .await;
+                if changed {
+                    if let Err(e) = rm.reload().await {
+                        log::warn!("reload updated providers failed: {}", e);
+                    }
+                } else if rules_changed {
+                    if let Err(e) = rm.reload_router().await {
+                        log::warn!("reload updated rule providers failed: {}", e);
+                    }
+                }
+            }
+        }));
+    }
+
     drop(config); // explicitly free the memory
 
     // Monitor reload signal.
@@ -515,6 +732,21 @@ pub fn start(rt_id: RuntimeId, opts: StartOptions) -> Result<(), Error> {
         }
     }));
 
+    // Monitor router reload signal.
+    let rm = runtime_manager.clone();
+    tasks.push(Box::pin(async move {
+        loop {
+            if let Some(res_tx) = reload_router_rx.recv().await {
+                let res = rm.reload_router().await;
+                if let Err(e) = res_tx.send(res) {
+                    log::warn!("sending router reload result failed: {}", e);
+                }
+            } else {
+                log::warn!("receiving none router reload signal");
+            }
+        }
+    }));
+
     // The main task joining all runners.
     tasks.push(Box::pin(async move {
         futures::future::join_all(runners).await;
@@ -531,6 +763,41 @@ pub fn start(rt_id: RuntimeId, opts: StartOptions) -> Result<(), Error> {
         let _ = tokio::signal::ctrl_c().await;
     }));
 
+    // Reload the routing rules on SIGHUP.
+    #[cfg(all(feature = "ctrlc", unix))]
+    {
+        let rm = runtime_manager.clone();
+        tasks.push(Box::pin(async move {
+            use tokio::signal::unix::{signal, SignalKind};
+            match signal(SignalKind::hangup()) {
+                Ok(mut s) => {
+                    while s.recv().await.is_some() {
+                        if let Err(e) = rm.reload_router().await {
+                            log::warn!("reload routing rules failed: {}", e);
+                        }
+                    }
+                }
+                Err(e) => log::warn!("register SIGHUP handler failed: {}", e),
+            }
+            futures::future::pending::<()>().await;
+        }));
+    }
+
+    // Monitor SIGTERM so that we have a chance to clean up on termination.
+    #[cfg(all(feature = "ctrlc", unix))]
+    tasks.push(Box::pin(async move {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut s) => {
+                let _ = s.recv().await;
+            }
+            Err(e) => {
+                log::warn!("register SIGTERM handler failed: {}", e);
+                futures::future::pending::<()>().await;
+            }
+        }
+    }));
+
     RUNTIME_MANAGER
         .lock()
         .map_err(|_| Error::RuntimeManager)?
@@ -540,8 +807,11 @@ pub fn start(rt_id: RuntimeId, opts: StartOptions) -> Result<(), Error> {
 
     rt.block_on(futures::future::select_all(tasks));
 
-    #[cfg(all(feature = "inbound-tun", any(target_os = "macos", target_os = "linux")))]
-    sys::post_tun_completion_setup(&net_info);
+    #[cfg(all(
+        any(feature = "inbound-tun", feature = "inbound-tun-smoltcp"),
+        any(target_os = "macos", target_os = "linux")
+    ))]
+    sys::post_tun_completion_setup(&net_info, &excluded_addrs);
 
     rt.shutdown_background();
 
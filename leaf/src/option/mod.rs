@@ -30,10 +30,17 @@ lazy_static! {
         get_env_var_or("DIRECT_TCP_CONCURRENCY", 64)
     };
 
-    /// DNS cache size in the built-in DNS client.
+    /// DNS cache size in the built-in DNS client, in entries of a name and a
+    /// record type.
     pub static ref DNS_CACHE_SIZE: usize = {
         get_env_var_or("DNS_CACHE_SIZE", 64)
     };
+
+    /// Send and receive buffer size of TCP connections in the smoltcp
+    /// netstack, in KB. It's also the max TCP window size.
+    pub static ref NETSTACK_TCP_BUFFER_SIZE: usize = {
+        get_env_var_or("NETSTACK_TCP_BUFFER_SIZE", 16)
+    };
 }
 
 #[cfg(not(target_os = "ios"))]
@@ -48,10 +55,17 @@ lazy_static! {
         get_env_var_or("DIRECT_TCP_CONCURRENCY", 1024)
     };
 
-    /// DNS cache size in the built-in DNS client.
+    /// DNS cache size in the built-in DNS client, in entries of a name and a
+    /// record type.
     pub static ref DNS_CACHE_SIZE: usize = {
         get_env_var_or("DNS_CACHE_SIZE", 512)
     };
+
+    /// Send and receive buffer size of TCP connections in the smoltcp
+    /// netstack, in KB. It's also the max TCP window size.
+    pub static ref NETSTACK_TCP_BUFFER_SIZE: usize = {
+        get_env_var_or("NETSTACK_TCP_BUFFER_SIZE", 64)
+    };
 }
 
 #[cfg(feature = "stat")]
@@ -84,12 +98,12 @@ lazy_static! {
         get_env_var_or("LOG_NO_COLOR", false)
     };
 
-    /// Uplink timeout after downlink EOF.
+    /// Uplink idle timeout after downlink EOF.
     pub static ref TCP_UPLINK_TIMEOUT: u64 = {
         get_env_var_or("TCP_UPLINK_TIMEOUT", 10)
     };
 
-    /// Downlink timeout after uplink EOF.
+    /// Downlink idle timeout after uplink EOF.
     pub static ref TCP_DOWNLINK_TIMEOUT: u64 = {
         get_env_var_or("TCP_DOWNLINK_TIMEOUT", 10)
     };
@@ -108,11 +122,18 @@ lazy_static! {
         get_env_var_or("OUTBOUND_DIAL_TIMEOUT", 4)
     };
 
-    /// Maximum outbound dial concurrency.
+    /// Number of connection attempts a TCP dial starts at once.
     pub static ref OUTBOUND_DIAL_CONCURRENCY: usize = {
         get_env_var_or("OUTBOUND_DIAL_CONCURRENCY", 1)
     };
 
+    /// Delay before a TCP dial starts the next connection attempt while the
+    /// previous ones are in progress, in milliseconds. A failed attempt starts
+    /// the next one immediately.
+    pub static ref OUTBOUND_DIAL_ATTEMPT_DELAY: u64 = {
+        get_env_var_or("OUTBOUND_DIAL_ATTEMPT_DELAY", 250)
+    };
+
     pub static ref ASSET_LOCATION: String = {
         let mut file = std::env::current_exe().unwrap();
         file.pop();
@@ -127,6 +148,12 @@ lazy_static! {
         get_env_var_or("API_LISTEN", "".to_string())
     };
 
+    /// Clamps the MSS of TCP connections through the TUN inbound, 0 disables
+    /// it.
+    pub static ref TUN_TCP_MSS: u16 = {
+        get_env_var_or("TUN_TCP_MSS", 0)
+    };
+
     pub static ref ENABLE_IPV6: bool = {
         get_env_var_or("ENABLE_IPV6", false)
     };
@@ -157,6 +184,18 @@ lazy_static! {
         outbound_binds
     };
 
+    /// Sets the fwmark (`SO_MARK`) of outbound sockets on Linux, 0 disables it.
+    /// Policy routing rules can match the mark to keep leaf's own traffic off
+    /// the TUN default route, e.g. `ip rule add fwmark 0xff table main`. It
+    /// needs the `CAP_NET_ADMIN` capability. Hex values are prefixed with `0x`.
+    pub static ref OUTBOUND_FWMARK: u32 = {
+        let mark = get_env_var_or("OUTBOUND_FWMARK", "0".to_string());
+        match mark.strip_prefix("0x") {
+            Some(hex) => u32::from_str_radix(hex, 16).unwrap_or(0),
+            None => mark.parse().unwrap_or(0),
+        }
+    };
+
     /// Sets the RPC service endpoint for protecting outbound sockets on Android to
     /// avoid infinite loop. The `path` is treated as a Unix domain socket endpoint.
     /// The RPC service simply listens for incoming connections, reads an int32 on
@@ -197,6 +236,24 @@ lazy_static! {
         get_env_var_or("DNS_TIMEOUT", 4)
     };
 
+    /// Minimum seconds a DNS answer is cached for by the built-in DNS client,
+    /// lower TTLs are raised to it.
+    pub static ref DNS_MIN_TTL: u32 = {
+        get_env_var_or("DNS_MIN_TTL", 0)
+    };
+
+    /// Maximum seconds a DNS answer is cached for by the built-in DNS client,
+    /// higher TTLs are lowered to it.
+    pub static ref DNS_MAX_TTL: u32 = {
+        get_env_var_or("DNS_MAX_TTL", 86400)
+    };
+
+    /// Maximum seconds a negative DNS answer, i.e. NXDOMAIN or no records of
+    /// the type, is cached for by the built-in DNS client.
+    pub static ref DNS_MAX_NEGATIVE_TTL: u32 = {
+        get_env_var_or("DNS_MAX_NEGATIVE_TTL", 300)
+    };
+
     pub static ref DEFAULT_TUN_NAME: String = {
         get_env_var_or("DEFAULT_TUN_NAME", "utun233".to_string())
     };
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use std::convert::TryInto;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{io, pin::Pin};
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
@@ -32,6 +32,8 @@ pub mod outbound;
 
 pub const FRAME_STREAM: u8 = 0x01;
 pub const FRAME_STREAM_FIN: u8 = 0x02;
+pub const FRAME_PING: u8 = 0x03;
+pub const FRAME_PONG: u8 = 0x04;
 pub const MAX_STREAM_FRAME_DATA_LEN: u16 = u16::MAX;
 
 pub fn random_u16() -> u16 {
@@ -51,6 +53,11 @@ pub enum MuxFrame {
     Stream(StreamId, Vec<u8>), // |type(1,0x01)|id(2)|len(2)|data|
     /// A frame to close the send half of a stream.
     StreamFin(StreamId), // |type(1,0x02)|id(2)|
+    /// A frame to probe the liveness of a connection, the receiver should
+    /// answer with a Pong frame.
+    Ping, // |type(1,0x03)|
+    /// A frame to answer a Ping frame.
+    Pong, // |type(1,0x04)|
 }
 
 impl MuxFrame {
@@ -68,6 +75,8 @@ impl MuxFrame {
                 buf.put_u8(FRAME_STREAM_FIN);
                 buf.put_u16(*id as u16);
             }
+            MuxFrame::Ping => buf.put_u8(FRAME_PING),
+            MuxFrame::Pong => buf.put_u8(FRAME_PONG),
         }
         buf.freeze()
     }
@@ -82,6 +91,8 @@ impl std::fmt::Display for MuxFrame {
             MuxFrame::StreamFin(stream_id) => {
                 write!(f, "StreamFin({})", stream_id)
             }
+            MuxFrame::Ping => write!(f, "Ping"),
+            MuxFrame::Pong => write!(f, "Pong"),
         }
     }
 }
@@ -307,6 +318,18 @@ impl<S> MuxConnection<S> {
 
                 Ok(Some(frame))
             }
+            FRAME_PING | FRAME_PONG => {
+                let frame = if buf[0] == FRAME_PING {
+                    MuxFrame::Ping
+                } else {
+                    MuxFrame::Pong
+                };
+                let _ = self.read_buf.split_to(1);
+
+                self.read_buf.reserve(3); // minimal frame size
+
+                Ok(Some(frame))
+            }
             _ => Err(unknown_frame(buf[0])),
         }
     }
@@ -392,13 +415,69 @@ struct Accept {
     frame_write_tx: Sender<MuxFrame>,
 }
 
+// Number of keep-alive intervals without receiving anything before a
+// connection is considered dead.
+const KEEP_ALIVE_MISSES: u32 = 3;
+
+// Interval to check the health of a connector, None if neither keep-alive
+// nor idle timeout is enabled.
+fn health_interval(keep_alive: Duration, idle_timeout: Duration) -> Option<Duration> {
+    let idle_interval = idle_timeout / 4;
+    match (keep_alive.is_zero(), idle_interval.is_zero()) {
+        (true, true) => None,
+        (false, true) => Some(keep_alive),
+        (true, false) => Some(idle_interval),
+        (false, false) => Some(min(keep_alive, idle_interval)),
+    }
+}
+
+/// Liveness of a connection, shared by its run loops and connector.
+pub struct Health {
+    // The connection is broken or stops responding.
+    dead: AtomicBool,
+    // The connection has no streams for longer than the idle timeout.
+    idle: AtomicBool,
+    // The receive loop is waiting for a stream to take data rather than
+    // reading the connection.
+    delivering: AtomicBool,
+    // When the receive loop last made progress.
+    last_recv: std::sync::Mutex<Instant>,
+}
+
+impl Health {
+    fn new() -> Self {
+        Health {
+            dead: AtomicBool::new(false),
+            idle: AtomicBool::new(false),
+            delivering: AtomicBool::new(false),
+            last_recv: std::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    fn touch(&self) {
+        *self.last_recv.lock().unwrap() = Instant::now();
+    }
+
+    fn set_delivering(&self, delivering: bool) {
+        self.delivering.store(delivering, Ordering::SeqCst);
+        self.touch();
+    }
+
+    // Whether nothing has been received for `timeout` while the receive loop
+    // is reading the connection.
+    fn is_silent(&self, timeout: Duration) -> bool {
+        !self.delivering.load(Ordering::SeqCst)
+            && self.last_recv.lock().unwrap().elapsed() > timeout
+    }
+}
+
 pub struct MuxSession;
 
 impl MuxSession {
     fn run_frame_receive_loop<S>(
         streams: Streams,
         mut frame_stream: SplitStream<MuxConnection<S>>,
-        recv_end: Option<Arc<Mutex<bool>>>,
+        health: Option<Arc<Health>>,
         mut accept: Option<Accept>,
     ) -> AbortHandle
     where
@@ -408,6 +487,9 @@ impl MuxSession {
             while let Some(frame) = frame_stream.next().await {
                 match frame {
                     Ok(frame) => {
+                        if let Some(health) = health.as_ref() {
+                            health.touch();
+                        }
                         match frame {
                             MuxFrame::Stream(stream_id, data) => {
                                 // In accept mode.
@@ -435,8 +517,16 @@ impl MuxSession {
                                 if let Some(stream_read_tx) =
                                     streams.lock().await.get(&stream_id).cloned()
                                 {
+                                    // A slow stream stalls the receive loop, which
+                                    // shouldn't be taken as a dead connection.
+                                    if let Some(health) = health.as_ref() {
+                                        health.set_delivering(true);
+                                    }
                                     // FIXME error
                                     let _ = stream_read_tx.send(data).await;
+                                    if let Some(health) = health.as_ref() {
+                                        health.set_delivering(false);
+                                    }
                                 }
                             }
                             MuxFrame::StreamFin(stream_id) => {
@@ -453,6 +543,15 @@ impl MuxSession {
                                     streams2.lock().await.remove(&stream_id);
                                 });
                             }
+                            MuxFrame::Ping => {
+                                // Only acceptors answer pings.
+                                if let Some(Accept { frame_write_tx, .. }) = accept.as_ref() {
+                                    if frame_write_tx.send(MuxFrame::Pong).await.is_err() {
+                                        break;
+                                    }
+                                }
+                            }
+                            MuxFrame::Pong => (),
                         }
                     }
                     // Borken pipe.
@@ -463,8 +562,8 @@ impl MuxSession {
                 }
             }
             // Stop receving.
-            if let Some(recv_end) = recv_end {
-                *recv_end.lock().await = true;
+            if let Some(health) = health {
+                health.dead.store(true, Ordering::SeqCst);
             }
             streams.lock().await.clear();
         });
@@ -477,7 +576,7 @@ impl MuxSession {
         streams: Streams,
         mut frame_sink: SplitSink<MuxConnection<S>, MuxFrame>,
         mut frame_write_rx: Receiver<MuxFrame>,
-        send_end: Option<Arc<Mutex<bool>>>,
+        health: Option<Arc<Health>>,
     ) -> AbortHandle
     where
         S: 'static + AsyncRead + AsyncWrite + Unpin + Send,
@@ -498,8 +597,8 @@ impl MuxSession {
                     break;
                 }
             }
-            if let Some(send_end) = send_end {
-                *send_end.lock().await = true;
+            if let Some(health) = health {
+                health.dead.store(true, Ordering::SeqCst);
             }
             streams.lock().await.clear();
         });
@@ -508,25 +607,77 @@ impl MuxSession {
         handle
     }
 
-    pub fn connector<S>(conn: S, max_accepts: usize, concurrency: usize) -> MuxConnector
+    // Pings the peer every `keep_alive` and flags the connection dead when
+    // nothing comes back, and flags it idle after `idle_timeout` without
+    // streams. A zero duration disables either check.
+    fn run_health_loop(
+        streams: Streams,
+        frame_write_tx: Sender<MuxFrame>,
+        health: Arc<Health>,
+        keep_alive: Duration,
+        idle_timeout: Duration,
+    ) -> Option<AbortHandle> {
+        let interval = health_interval(keep_alive, idle_timeout)?;
+        let task = Box::pin(async move {
+            let mut idle_since = Instant::now();
+            loop {
+                sleep(interval).await;
+                if !keep_alive.is_zero() {
+                    if health.is_silent(keep_alive * KEEP_ALIVE_MISSES) {
+                        log::debug!("mux connection stops responding");
+                        health.dead.store(true, Ordering::SeqCst);
+                        streams.lock().await.clear();
+                        break;
+                    }
+                    // Skips the ping if frames are already queued, which
+                    // would be answered just as well.
+                    let _ = frame_write_tx.try_send(MuxFrame::Ping);
+                }
+                if !idle_timeout.is_zero() {
+                    // Holds the lock so that no stream is opened meanwhile.
+                    let streams = streams.lock().await;
+                    if !streams.is_empty() {
+                        idle_since = Instant::now();
+                    } else if idle_since.elapsed() >= idle_timeout {
+                        health.idle.store(true, Ordering::SeqCst);
+                        break;
+                    }
+                }
+            }
+        });
+        let (task, handle) = abortable(task);
+        tokio::spawn(task);
+        Some(handle)
+    }
+
+    pub fn connector<S>(
+        conn: S,
+        max_accepts: usize,
+        concurrency: usize,
+        keep_alive: Duration,
+        idle_timeout: Duration,
+    ) -> MuxConnector
     where
         S: 'static + AsyncRead + AsyncWrite + Unpin + Send,
     {
         let (frame_sink, frame_stream) = MuxConnection::new(conn).split();
         let (frame_write_tx, frame_write_rx) = mpsc::channel::<MuxFrame>(1);
-        let (recv_end, send_end) = (Arc::new(Mutex::new(false)), Arc::new(Mutex::new(false)));
+        let health = Arc::new(Health::new());
         let streams: Streams = Arc::new(Mutex::new(HashMap::new()));
-        let recv_handle = Self::run_frame_receive_loop(
-            streams.clone(),
-            frame_stream,
-            Some(recv_end.clone()),
-            None,
-        );
+        let recv_handle =
+            Self::run_frame_receive_loop(streams.clone(), frame_stream, Some(health.clone()), None);
         let send_handle = Self::run_frame_send_loop(
             streams.clone(),
             frame_sink,
             frame_write_rx,
-            Some(send_end.clone()),
+            Some(health.clone()),
+        );
+        let health_handle = Self::run_health_loop(
+            streams.clone(),
+            frame_write_tx.clone(),
+            health.clone(),
+            keep_alive,
+            idle_timeout,
         );
         let session_id = random_u16();
         MuxConnector::new(
@@ -535,10 +686,10 @@ impl MuxSession {
             session_id,
             streams,
             frame_write_tx,
-            recv_end,
-            send_end,
+            health,
             recv_handle,
             send_handle,
+            health_handle,
         )
     }
 
@@ -579,14 +730,14 @@ pub struct MuxConnector {
     streams: Streams,
     // Sender for sending frames from streams to the send loop.
     frame_write_tx: Sender<MuxFrame>,
-    // Flag the end of the receive loop.
-    recv_end: Arc<Mutex<bool>>,
-    // Flag the end of the send loop.
-    send_end: Arc<Mutex<bool>>,
+    // Liveness of the connection.
+    health: Arc<Health>,
     // Handle to abort the receive loop.
     recv_handle: AbortHandle,
     // Handle to abort the send loop.
     send_handle: AbortHandle,
+    // Handle to abort the health loop, if any.
+    health_handle: Option<AbortHandle>,
     // Indicates the connector has no active streams and is no longer accept
     // new stream request.
     done: AtomicBool,
@@ -600,10 +751,10 @@ impl MuxConnector {
         session_id: SessionId,
         streams: Streams,
         frame_write_tx: Sender<MuxFrame>,
-        recv_end: Arc<Mutex<bool>>,
-        send_end: Arc<Mutex<bool>>,
+        health: Arc<Health>,
         recv_handle: AbortHandle,
         send_handle: AbortHandle,
+        health_handle: Option<AbortHandle>,
     ) -> Self {
         trace!(
             "new mux connector {} (max_accepts: {}, concurrency: {})",
@@ -618,10 +769,10 @@ impl MuxConnector {
             total_accepted: 0,
             streams,
             frame_write_tx,
-            recv_end,
-            send_end,
+            health,
             recv_handle,
             send_handle,
+            health_handle,
             done: AtomicBool::new(false),
         }
     }
@@ -632,34 +783,36 @@ impl MuxConnector {
 
     pub fn is_done(&self) -> bool {
         self.done.load(Ordering::SeqCst)
+            || self.is_dead()
+            || self.health.idle.load(Ordering::SeqCst)
+    }
+
+    /// Whether the underlying connection is broken or stops responding to
+    /// keep-alive pings.
+    pub fn is_dead(&self) -> bool {
+        self.health.dead.load(Ordering::SeqCst)
     }
 
     pub async fn new_stream(&mut self) -> Option<MuxStream> {
+        let mut streams = self.streams.lock().await;
         if self.is_done() {
             return None;
         }
-        if *self.recv_end.lock().await {
-            self.done.store(true, Ordering::Relaxed);
-            return None;
-        }
-        if *self.send_end.lock().await {
-            self.done.store(true, Ordering::Relaxed);
-            return None;
-        }
         if self.total_accepted >= self.max_accepts {
-            if self.streams.lock().await.is_empty() {
+            if streams.is_empty() {
                 self.done.store(true, Ordering::Relaxed);
             }
             return None;
         }
-        if self.streams.lock().await.len() >= self.concurrency {
+        if streams.len() >= self.concurrency {
             return None;
         }
         let frame_write_tx = self.frame_write_tx.clone();
         let stream_id = random_u16();
         let (mux_stream, stream_read_tx) =
             MuxStream::new(self.session_id, stream_id, frame_write_tx);
-        self.streams.lock().await.insert(stream_id, stream_read_tx);
+        streams.insert(stream_id, stream_read_tx);
+        drop(streams);
         self.total_accepted += 1;
         Some(mux_stream)
     }
@@ -669,6 +822,9 @@ impl Drop for MuxConnector {
     fn drop(&mut self) {
         self.recv_handle.abort();
         self.send_handle.abort();
+        if let Some(health_handle) = self.health_handle.as_ref() {
+            health_handle.abort();
+        }
         trace!("drop mux connector {}", self.session_id);
     }
 }
@@ -716,3 +872,45 @@ impl Stream for MuxAcceptor {
         self.stream_accept_rx.poll_recv(cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let keep_alive = Duration::from_millis(50);
+
+            // Pings are answered by acceptors.
+            let (client_io, server_io) = tokio::io::duplex(1024);
+            let _acceptor = MuxSession::acceptor(server_io);
+            let connector = MuxSession::connector(client_io, 8, 2, keep_alive, Duration::ZERO);
+            sleep(Duration::from_millis(400)).await;
+            assert!(!connector.is_done());
+
+            // And a peer not answering is dead.
+            let (client_io, _server_io) = tokio::io::duplex(1024);
+            let connector = MuxSession::connector(client_io, 8, 2, keep_alive, Duration::ZERO);
+            sleep(Duration::from_millis(400)).await;
+            assert!(connector.is_dead());
+
+            // Connectors are done once idle for long enough.
+            let idle_timeout = Duration::from_millis(100);
+            let (client_io, server_io) = tokio::io::duplex(1024);
+            let _acceptor = MuxSession::acceptor(server_io);
+            let mut busy = MuxSession::connector(client_io, 8, 2, Duration::ZERO, idle_timeout);
+            let _stream = busy.new_stream().await.unwrap();
+            let (client_io, server_io) = tokio::io::duplex(1024);
+            let _acceptor2 = MuxSession::acceptor(server_io);
+            let idle = MuxSession::connector(client_io, 8, 2, Duration::ZERO, idle_timeout);
+            sleep(Duration::from_millis(300)).await;
+            assert!(!busy.is_done());
+            assert!(idle.is_done() && !idle.is_dead());
+        });
+    }
+}
@@ -18,13 +18,49 @@ use super::MuxConnector;
 use super::MuxSession;
 use super::MuxStream;
 
+// Establishes connections through the actors and starts connectors on them.
+struct Dialer {
+    address: String,
+    port: u16,
+    actors: Vec<AnyOutboundHandler>,
+    max_accepts: usize,
+    concurrency: usize,
+    keep_alive: Duration,
+    idle_timeout: Duration,
+    dns_client: SyncDnsClient,
+    dial_options: DialOptions,
+}
+
+impl Dialer {
+    async fn dial(&self, sess: &Session) -> io::Result<MuxConnector> {
+        let mut conn = self
+            .new_tcp_stream(sess, self.dns_client.clone(), &self.address, &self.port)
+            .await?;
+        let mut sess = sess.clone();
+        if let Ok(addr) = SocksAddr::try_from((&self.address, self.port)) {
+            sess.destination = addr;
+        }
+        for a in self.actors.iter() {
+            conn = TcpOutboundHandler::handle(a.as_ref(), &sess, Some(conn)).await?;
+        }
+        Ok(MuxSession::connector(
+            conn,
+            self.max_accepts,
+            self.concurrency,
+            self.keep_alive,
+            self.idle_timeout,
+        ))
+    }
+}
+
+impl TcpConnector for Dialer {
+    fn dial_options(&self) -> &DialOptions {
+        &self.dial_options
+    }
+}
+
 pub struct MuxManager {
-    pub address: String,
-    pub port: u16,
-    pub actors: Vec<AnyOutboundHandler>,
-    pub max_accepts: usize,
-    pub concurrency: usize,
-    pub dns_client: SyncDnsClient,
+    dialer: Arc<Dialer>,
     // TODO Verify whether the run loops in connectors are aborted after
     // a config reload.
     pub connectors: Arc<Mutex<Vec<MuxConnector>>>,
@@ -32,24 +68,57 @@ pub struct MuxManager {
 }
 
 impl MuxManager {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         address: String,
         port: u16,
         actors: Vec<AnyOutboundHandler>,
         max_accepts: usize,
         concurrency: usize,
+        keep_alive: Duration,
+        idle_timeout: Duration,
         dns_client: SyncDnsClient,
+        dial_options: DialOptions,
     ) -> (Self, Vec<AbortHandle>) {
         let mut abort_handles = Vec::new();
+        let dialer = Arc::new(Dialer {
+            address,
+            port,
+            actors,
+            max_accepts,
+            concurrency,
+            keep_alive,
+            idle_timeout,
+            dns_client,
+            dial_options,
+        });
         let connectors: Arc<Mutex<Vec<MuxConnector>>> = Arc::new(Mutex::new(Vec::new()));
         let connectors2 = connectors.clone();
-        // A task to monitor and remove completed connectors.
-        // TODO passive detection
+        let dialer2 = dialer.clone();
+        let interval = if keep_alive.is_zero() {
+            Duration::from_secs(120)
+        } else {
+            keep_alive
+        };
+        // A task to remove completed connectors, and to replace dead ones
+        // before new streams run into them.
         let fut = async move {
             loop {
-                connectors2.lock().await.retain(|c| !c.is_done());
-                log::trace!("active connectors {}", connectors2.lock().await.len());
-                tokio::time::sleep(Duration::from_secs(120)).await;
+                let mut connectors = connectors2.lock().await;
+                let dead = connectors.iter().filter(|c| c.is_dead()).count();
+                connectors.retain(|c| !c.is_done());
+                log::trace!("active connectors {}, dead {}", connectors.len(), dead);
+                drop(connectors);
+                for _ in 0..dead {
+                    match dialer2.dial(&Session::default()).await {
+                        Ok(c) => connectors2.lock().await.push(c),
+                        Err(e) => {
+                            log::debug!("rebuilding amux connection failed: {}", e);
+                            break;
+                        }
+                    }
+                }
+                tokio::time::sleep(interval).await;
             }
         };
         let (abortable, abort_handle) = abortable(fut);
@@ -57,12 +126,7 @@ impl MuxManager {
         let monitor_task: BoxFuture<'static, ()> = Box::pin(abortable.map(|_| ()));
         (
             MuxManager {
-                address,
-                port,
-                actors,
-                max_accepts,
-                concurrency,
-                dns_client,
+                dialer,
                 connectors,
                 monitor_task: Mutex::new(Some(monitor_task)),
             },
@@ -77,22 +141,18 @@ impl MuxManager {
             }
         }
 
-        for c in self.connectors.lock().await.iter_mut() {
-            if let Some(s) = c.new_stream().await {
-                return Ok(s);
+        {
+            let mut connectors = self.connectors.lock().await;
+            // Dead connectors are dropped right away so that a new connection
+            // is made instead.
+            connectors.retain(|c| !c.is_dead());
+            for c in connectors.iter_mut() {
+                if let Some(s) = c.new_stream().await {
+                    return Ok(s);
+                }
             }
         }
-        let mut conn = self
-            .new_tcp_stream(self.dns_client.clone(), &self.address, &self.port)
-            .await?;
-        let mut sess = sess.clone();
-        if let Ok(addr) = SocksAddr::try_from((&self.address, self.port)) {
-            sess.destination = addr;
-        }
-        for (_, a) in self.actors.iter().enumerate() {
-            conn = TcpOutboundHandler::handle(a.as_ref(), &sess, Some(conn)).await?;
-        }
-        let mut connector = MuxSession::connector(conn, self.max_accepts, self.concurrency);
+        let mut connector = self.dialer.dial(sess).await?;
         let s = match connector.new_stream().await {
             Some(s) => s,
             None => return Err(io::Error::new(io::ErrorKind::Other, "new stream failed")),
@@ -102,23 +162,34 @@ impl MuxManager {
     }
 }
 
-impl TcpConnector for MuxManager {}
-
 pub struct Handler {
     manager: MuxManager,
 }
 
 impl Handler {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         address: String,
         port: u16,
         actors: Vec<AnyOutboundHandler>,
         max_accepts: usize,
         concurrency: usize,
+        keep_alive: Duration,
+        idle_timeout: Duration,
         dns_client: SyncDnsClient,
+        dial_options: DialOptions,
     ) -> (Self, Vec<AbortHandle>) {
-        let (manager, abort_handles) =
-            MuxManager::new(address, port, actors, max_accepts, concurrency, dns_client);
+        let (manager, abort_handles) = MuxManager::new(
+            address,
+            port,
+            actors,
+            max_accepts,
+            concurrency,
+            keep_alive,
+            idle_timeout,
+            dns_client,
+            dial_options,
+        );
         (Handler { manager }, abort_handles)
     }
 }
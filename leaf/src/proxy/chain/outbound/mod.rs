@@ -1,5 +1,21 @@
+use crate::{proxy::*, session::Session};
+
 pub mod tcp;
 pub mod udp;
 
 pub use tcp::Handler as TcpHandler;
 pub use udp::Handler as UdpHandler;
+
+// Replaces the groups with the actors they relay the session through, which
+// may be groups as well.
+async fn resolve_actors(actors: &[AnyOutboundHandler], sess: &Session) -> Vec<AnyOutboundHandler> {
+    let mut resolved = Vec::with_capacity(actors.len());
+    for a in actors.iter() {
+        let mut a = a.clone();
+        while let Some(actor) = TcpOutboundHandler::group_actor(a.as_ref(), sess).await {
+            a = actor;
+        }
+        resolved.push(a);
+    }
+    resolved
+}
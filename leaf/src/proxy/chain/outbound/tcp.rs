@@ -4,32 +4,32 @@ use std::io;
 use async_trait::async_trait;
 
 use crate::{
+    app::SyncDnsClient,
     proxy::*,
     session::{Session, SocksAddr},
 };
 
 pub struct Handler {
     pub actors: Vec<AnyOutboundHandler>,
+    pub dns_client: SyncDnsClient,
 }
 
-impl Handler {
-    fn next_connect_addr(&self, start: usize) -> Option<OutboundConnect> {
-        for i in start..self.actors.len() {
-            if let Some(addr) = TcpOutboundHandler::connect_addr(self.actors[i].as_ref()) {
-                return Some(addr);
-            }
+fn next_connect(actors: &[AnyOutboundHandler], start: usize) -> Option<(usize, OutboundConnect)> {
+    for (i, a) in actors.iter().enumerate().skip(start) {
+        if let Some(addr) = TcpOutboundHandler::connect_addr(a.as_ref()) {
+            return Some((i, addr));
         }
-        None
     }
+    None
+}
 
-    fn next_session(&self, mut sess: Session, start: usize) -> Session {
-        if let Some(OutboundConnect::Proxy(address, port)) = self.next_connect_addr(start) {
-            if let Ok(addr) = SocksAddr::try_from((address, port)) {
-                sess.destination = addr;
-            }
+fn next_session(actors: &[AnyOutboundHandler], mut sess: Session, start: usize) -> Session {
+    if let Some((_, OutboundConnect::Proxy(address, port))) = next_connect(actors, start) {
+        if let Ok(addr) = SocksAddr::try_from((address, port)) {
+            sess.destination = addr;
         }
-        sess
     }
+    sess
 }
 
 #[async_trait]
@@ -37,12 +37,7 @@ impl TcpOutboundHandler for Handler {
     type Stream = AnyStream;
 
     fn connect_addr(&self) -> Option<OutboundConnect> {
-        for a in self.actors.iter() {
-            if let Some(addr) = TcpOutboundHandler::connect_addr(a.as_ref()) {
-                return Some(addr);
-            }
-        }
-        None
+        next_connect(&self.actors, 0).map(|(_, addr)| addr)
     }
 
     async fn handle<'a>(
@@ -50,16 +45,30 @@ impl TcpOutboundHandler for Handler {
         sess: &'a Session,
         mut stream: Option<Self::Stream>,
     ) -> io::Result<Self::Stream> {
-        match self.connect_addr() {
-            Some(OutboundConnect::NoConnect) => (),
+        let actors = super::resolve_actors(&self.actors, sess).await;
+        // Chains starting with groups connect by themselves, as the first hop
+        // isn't known until the groups are resolved.
+        if stream.is_none() {
+            if let Some((i, _)) = next_connect(&actors, 0) {
+                let first_sess = next_session(&actors, sess.clone(), i + 1);
+                stream = crate::proxy::connect_tcp_outbound(
+                    &first_sess,
+                    self.dns_client.clone(),
+                    &actors[i],
+                )
+                .await?;
+            }
+        }
+        match next_connect(&actors, 0) {
+            Some((_, OutboundConnect::NoConnect)) => (),
             _ => {
                 if stream.is_none() {
                     return Err(io::Error::new(io::ErrorKind::Other, "invalid input"));
                 }
             }
         }
-        for (i, a) in self.actors.iter().enumerate() {
-            let new_sess = self.next_session(sess.clone(), i + 1);
+        for (i, a) in actors.iter().enumerate() {
+            let new_sess = next_session(&actors, sess.clone(), i + 1);
             let s = stream.take();
             stream.replace(TcpOutboundHandler::handle(a.as_ref(), &new_sess, s).await?);
         }
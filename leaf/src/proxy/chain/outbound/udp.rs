@@ -4,6 +4,7 @@ use std::io;
 use async_trait::async_trait;
 
 use crate::{
+    app::SyncDnsClient,
     proxy::*,
     session::{Session, SocksAddr},
 };
@@ -17,48 +18,99 @@ fn invalid_chain(reason: &str) -> io::Error {
 
 pub struct Handler {
     pub actors: Vec<AnyOutboundHandler>,
+    pub dns_client: SyncDnsClient,
 }
 
-impl Handler {
-    fn next_connect_addr(&self, start: usize) -> Option<OutboundConnect> {
-        for i in start..self.actors.len() {
-            if let Some(addr) = UdpOutboundHandler::connect_addr(self.actors[i].as_ref()) {
-                return Some(addr);
-            }
+fn next_connect(actors: &[AnyOutboundHandler], start: usize) -> Option<(usize, OutboundConnect)> {
+    for (i, a) in actors.iter().enumerate().skip(start) {
+        if let Some(addr) = UdpOutboundHandler::connect_addr(a.as_ref()) {
+            return Some((i, addr));
         }
-        None
     }
+    None
+}
 
-    fn next_session(&self, mut sess: Session, start: usize) -> Session {
-        if let Some(OutboundConnect::Proxy(address, port)) = self.next_connect_addr(start) {
-            if let Ok(addr) = SocksAddr::try_from((address, port)) {
-                sess.destination = addr;
-            }
+fn next_session(actors: &[AnyOutboundHandler], mut sess: Session, start: usize) -> Session {
+    if let Some((_, OutboundConnect::Proxy(address, port))) = next_connect(actors, start) {
+        if let Ok(addr) = SocksAddr::try_from((address, port)) {
+            sess.destination = addr;
+        }
+    }
+    sess
+}
+
+fn is_udp_chain(actors: &[AnyOutboundHandler], start: usize) -> bool {
+    for a in actors.iter().skip(start) {
+        if a.transport_type() != DatagramTransportType::Datagram {
+            return false;
         }
-        sess
     }
+    true
+}
 
-    fn is_udp_chain(&self, start: usize) -> bool {
-        for i in start..self.actors.len() {
-            if self.actors[i].transport_type() != DatagramTransportType::Datagram {
-                return false;
+// Whether any hop is a group, of which the transport isn't known until it's
+// resolved.
+fn has_group(actors: &[AnyOutboundHandler]) -> bool {
+    actors
+        .iter()
+        .any(|a| a.transport_type() == DatagramTransportType::Undefined)
+}
+
+fn transport_type(actors: &[AnyOutboundHandler]) -> DatagramTransportType {
+    for a in actors.iter() {
+        if a.transport_type() == DatagramTransportType::Stream {
+            return DatagramTransportType::Stream;
+        }
+    }
+    DatagramTransportType::Datagram
+}
+
+impl Handler {
+    // Connects to the first hop for chains with groups, as the hops and their
+    // transports aren't known until the groups are resolved.
+    async fn connect(
+        &self,
+        actors: &[AnyOutboundHandler],
+        sess: &Session,
+    ) -> io::Result<Option<AnyOutboundTransport>> {
+        let (i, connect) = match next_connect(actors, 0) {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let first_sess = next_session(actors, sess.clone(), i + 1);
+        match connect {
+            // Relays over a stream if any hop needs it.
+            OutboundConnect::Proxy(..)
+                if transport_type(actors) == DatagramTransportType::Stream =>
+            {
+                Ok(crate::proxy::connect_tcp_outbound(
+                    &first_sess,
+                    self.dns_client.clone(),
+                    &actors[i],
+                )
+                .await?
+                .map(OutboundTransport::Stream))
+            }
+            _ => {
+                crate::proxy::connect_udp_outbound(&first_sess, self.dns_client.clone(), &actors[i])
+                    .await
             }
         }
-        true
     }
 
-    async fn handle<'a>(
+    async fn handle_actors<'a>(
         &'a self,
+        actors: &'a [AnyOutboundHandler],
         sess: &'a Session,
         mut stream: Option<Box<dyn ProxyStream>>,
         mut dgram: Option<Box<dyn OutboundDatagram>>,
     ) -> io::Result<Box<dyn OutboundDatagram>> {
-        for (i, a) in self.actors.iter().enumerate() {
-            let new_sess = self.next_session(sess.clone(), i + 1);
+        for (i, a) in actors.iter().enumerate() {
+            let new_sess = next_session(actors, sess.clone(), i + 1);
 
             // Handle the final actor. We're handling UDP traffic, if we're given
             // a stream, this is our last chance to convert it to a datagram.
-            if i == self.actors.len() - 1 {
+            if i == actors.len() - 1 {
                 if let Some(d) = dgram.take() {
                     return UdpOutboundHandler::handle(
                         a.as_ref(),
@@ -80,7 +132,7 @@ impl Handler {
 
             if let Some(s) = stream.take() {
                 // Got a stream, check if we can convert it to a datagram.
-                if self.is_udp_chain(i + 1) {
+                if is_udp_chain(actors, i + 1) {
                     dgram.replace(
                         UdpOutboundHandler::handle(
                             a.as_ref(),
@@ -119,21 +171,19 @@ impl UdpOutboundHandler for Handler {
     type Datagram = AnyOutboundDatagram;
 
     fn connect_addr(&self) -> Option<OutboundConnect> {
-        for a in self.actors.iter() {
-            if let Some(addr) = UdpOutboundHandler::connect_addr(a.as_ref()) {
-                return Some(addr);
-            }
+        // Chains with groups connect by themselves, the transport depends on
+        // the actors the groups resolve to.
+        if has_group(&self.actors) {
+            return Some(OutboundConnect::NoConnect);
         }
-        None
+        next_connect(&self.actors, 0).map(|(_, addr)| addr)
     }
 
     fn transport_type(&self) -> DatagramTransportType {
-        for a in self.actors.iter() {
-            if a.transport_type() == DatagramTransportType::Stream {
-                return DatagramTransportType::Stream;
-            }
+        if has_group(&self.actors) {
+            return DatagramTransportType::Undefined;
         }
-        DatagramTransportType::Datagram
+        transport_type(&self.actors)
     }
 
     async fn handle<'a>(
@@ -141,13 +191,24 @@ impl UdpOutboundHandler for Handler {
         sess: &'a Session,
         transport: Option<OutboundTransport<Self::UStream, Self::Datagram>>,
     ) -> io::Result<Self::Datagram> {
+        let actors = super::resolve_actors(&self.actors, sess).await;
+        let transport = match transport {
+            Some(transport) => Some(transport),
+            None => self.connect(&actors, sess).await?,
+        };
         match transport {
             Some(transport) => match transport {
-                OutboundTransport::Datagram(dgram) => self.handle(sess, None, Some(dgram)).await,
-                OutboundTransport::Stream(stream) => self.handle(sess, Some(stream), None).await,
+                OutboundTransport::Datagram(dgram) => {
+                    self.handle_actors(&actors, sess, None, Some(dgram)).await
+                }
+                OutboundTransport::Stream(stream) => {
+                    self.handle_actors(&actors, sess, Some(stream), None).await
+                }
             },
-            None => match self.next_connect_addr(0) {
-                Some(OutboundConnect::NoConnect) => self.handle(sess, None, None).await,
+            None => match next_connect(&actors, 0) {
+                Some((_, OutboundConnect::NoConnect)) => {
+                    self.handle_actors(&actors, sess, None, None).await
+                }
                 _ => Err(invalid_chain("invalid transport")),
             },
         }
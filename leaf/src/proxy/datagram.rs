@@ -1,7 +1,8 @@
 use std::{
+    collections::HashMap,
     io,
     net::{IpAddr, SocketAddr},
-    sync::Arc,
+    sync::{Arc, Mutex},
 };
 
 use async_trait::async_trait;
@@ -9,29 +10,79 @@ use futures::TryFutureExt;
 use tokio::net::UdpSocket;
 
 use crate::{
-    app::SyncDnsClient,
+    app::{dns_client::DomainStrategy, SyncDnsClient},
     session::{DatagramSource, SocksAddr},
 };
 
 use super::*;
 
+#[derive(Default)]
+struct DomainPeersInner {
+    // Peer addresses of domains.
+    resolved: HashMap<SocketAddr, SocksAddr>,
+    // Domains sent to whose peer addresses aren't known yet.
+    pending: Vec<SocksAddr>,
+}
+
+/// Maps the sources of received packets back to the domains they were sent
+/// to, so the replies of a peer addressed by domain appear to come from that
+/// domain, while packets from any other peer keep their own addresses, as a
+/// full-cone NAT does.
+#[derive(Clone, Default)]
+pub struct DomainPeers(Arc<Mutex<DomainPeersInner>>);
+
+impl DomainPeers {
+    /// Records that `domain` has been resolved to `addr`.
+    pub fn insert(&self, addr: SocketAddr, domain: &SocksAddr) {
+        self.0
+            .lock()
+            .unwrap()
+            .resolved
+            .insert(unmapped_ipv4(addr), domain.clone());
+    }
+
+    /// Records a domain sent to, which is resolved by the remote. The first
+    /// unknown peer replying on the same port is taken as its address.
+    pub fn add_pending(&self, domain: &SocksAddr) {
+        let mut inner = self.0.lock().unwrap();
+        if inner.pending.contains(domain) || inner.resolved.values().any(|d| d == domain) {
+            return;
+        }
+        inner.pending.push(domain.clone());
+    }
+
+    /// Returns the address to report for a packet received from `addr`.
+    pub fn source(&self, addr: SocksAddr) -> SocksAddr {
+        let a = match addr {
+            SocksAddr::Ip(a) => unmapped_ipv4(a),
+            SocksAddr::Domain(..) => return addr,
+        };
+        let mut inner = self.0.lock().unwrap();
+        if let Some(domain) = inner.resolved.get(&a) {
+            return domain.clone();
+        }
+        if let Some(i) = inner.pending.iter().position(|d| d.port() == a.port()) {
+            let domain = inner.pending.remove(i);
+            inner.resolved.insert(a, domain.clone());
+            return domain;
+        }
+        SocksAddr::Ip(a)
+    }
+}
+
 /// An outbound datagram simply wraps a UDP socket.
 pub struct SimpleOutboundDatagram {
     inner: UdpSocket,
-    destination: Option<SocksAddr>,
     dns_client: SyncDnsClient,
+    strategy: DomainStrategy,
 }
 
 impl SimpleOutboundDatagram {
-    pub fn new(
-        inner: UdpSocket,
-        destination: Option<SocksAddr>,
-        dns_client: SyncDnsClient,
-    ) -> Self {
+    pub fn new(inner: UdpSocket, dns_client: SyncDnsClient, strategy: DomainStrategy) -> Self {
         SimpleOutboundDatagram {
             inner,
-            destination,
             dns_client,
+            strategy,
         }
     }
 }
@@ -45,9 +96,15 @@ impl OutboundDatagram for SimpleOutboundDatagram {
     ) {
         let r = Arc::new(self.inner);
         let s = r.clone();
+        let peers = DomainPeers::default();
         (
-            Box::new(SimpleOutboundDatagramRecvHalf(r, self.destination)),
-            Box::new(SimpleOutboundDatagramSendHalf(s, self.dns_client)),
+            Box::new(SimpleOutboundDatagramRecvHalf(r, peers.clone())),
+            Box::new(SimpleOutboundDatagramSendHalf(
+                s,
+                self.dns_client,
+                self.strategy,
+                peers,
+            )),
         )
     }
 }
@@ -64,25 +121,24 @@ fn unmapped_ipv4(addr: SocketAddr) -> SocketAddr {
     addr
 }
 
-pub struct SimpleOutboundDatagramRecvHalf(Arc<UdpSocket>, Option<SocksAddr>);
+pub struct SimpleOutboundDatagramRecvHalf(Arc<UdpSocket>, DomainPeers);
 
 #[async_trait]
 impl OutboundDatagramRecvHalf for SimpleOutboundDatagramRecvHalf {
     async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocksAddr)> {
         match self.0.recv_from(buf).await {
-            Ok((n, a)) => {
-                if self.1.is_some() {
-                    Ok((n, self.1.as_ref().unwrap().clone()))
-                } else {
-                    Ok((n, SocksAddr::Ip(unmapped_ipv4(a))))
-                }
-            }
+            Ok((n, a)) => Ok((n, self.1.source(SocksAddr::Ip(a)))),
             Err(e) => Err(e),
         }
     }
 }
 
-pub struct SimpleOutboundDatagramSendHalf(Arc<UdpSocket>, SyncDnsClient);
+pub struct SimpleOutboundDatagramSendHalf(
+    Arc<UdpSocket>,
+    SyncDnsClient,
+    DomainStrategy,
+    DomainPeers,
+);
 
 #[async_trait]
 impl OutboundDatagramSendHalf for SimpleOutboundDatagramSendHalf {
@@ -93,7 +149,7 @@ impl OutboundDatagramSendHalf for SimpleOutboundDatagramSendHalf {
                     self.1
                         .read()
                         .await
-                        .lookup(domain)
+                        .lookup_with_strategy(domain, self.2)
                         .map_err(|e| {
                             io::Error::new(
                                 io::ErrorKind::Other,
@@ -108,7 +164,9 @@ impl OutboundDatagramSendHalf for SimpleOutboundDatagramSendHalf {
                         "could not resolve to any address",
                     ));
                 }
-                SocketAddr::new(ips[0], port.to_owned())
+                let addr = SocketAddr::new(ips[0], port.to_owned());
+                self.3.insert(addr, target);
+                addr
             }
             SocksAddr::Ip(a) => a.to_owned(),
         };
@@ -177,3 +235,31 @@ impl InboundDatagramSendHalf for SimpleInboundDatagramSendHalf {
         self.0.send_to(buf, dst_addr).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_peers() {
+        let peers = DomainPeers::default();
+        let domain = SocksAddr::Domain("example.com".to_string(), 3478);
+        let ip = |s: &str| SocksAddr::Ip(s.parse().unwrap());
+
+        // Peers resolved locally.
+        peers.insert("1.1.1.1:3478".parse().unwrap(), &domain);
+        assert_eq!(peers.source(ip("1.1.1.1:3478")), domain);
+        assert_eq!(peers.source(ip("[::ffff:1.1.1.1]:3478")), domain);
+        assert_eq!(peers.source(ip("2.2.2.2:3478")), ip("2.2.2.2:3478"));
+
+        // And by the remote.
+        let peers = DomainPeers::default();
+        peers.add_pending(&domain);
+        assert_eq!(peers.source(ip("2.2.2.2:5000")), ip("2.2.2.2:5000"));
+        assert_eq!(peers.source(ip("1.1.1.1:3478")), domain);
+        assert_eq!(peers.source(ip("2.2.2.2:3478")), ip("2.2.2.2:3478"));
+        peers.add_pending(&domain);
+        assert_eq!(peers.source(ip("3.3.3.3:3478")), ip("3.3.3.3:3478"));
+        assert_eq!(peers.source(ip("1.1.1.1:3478")), domain);
+    }
+}
@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use bytes::Bytes;
+use futures::future::{self, Either};
+use log::*;
+use protobuf::Message as _;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::mpsc::channel as tokio_channel;
+use tokio::sync::mpsc::{Receiver as TokioReceiver, Sender as TokioSender};
+use trust_dns_proto::op::Message;
+
+use crate::{
+    app::dispatcher::Dispatcher,
+    app::dns_client::Upstream,
+    app::fake_dns::{FakeDns, FakeDnsMode, FakeIpPool},
+    app::nat_manager::{NatManager, UdpPacket},
+    common::io::PrefixedStream,
+    config::{DnsInboundSettings, Inbound},
+    session::{DatagramSource, Network, Session, SocksAddr},
+    Runner,
+};
+
+/// Chooses the upstream server of queries, servers in `domain_servers` are
+/// used for the domains and their subdomains, then the DNS rules apply, and
+/// `servers` are for others.
+struct Upstreams {
+    servers: Vec<Upstream>,
+    domain_servers: HashMap<String, Upstream>,
+}
+
+impl Upstreams {
+    fn new(settings: &DnsInboundSettings) -> Result<Self> {
+        let mut servers = Vec::new();
+        for server in settings.servers.iter() {
+            servers.push(Upstream::parse(server)?);
+        }
+        if servers.is_empty() {
+            return Err(anyhow!("no dns servers"));
+        }
+        let mut domain_servers = HashMap::new();
+        for (domain, server) in settings.domain_servers.iter() {
+            domain_servers.insert(
+                domain.trim_end_matches('.').to_lowercase(),
+                Upstream::parse(server)?,
+            );
+        }
+        Ok(Upstreams {
+            servers,
+            domain_servers,
+        })
+    }
+
+    // Returns the server in `domain_servers` of the domain or a parent one.
+    fn domain_server(&self, name: &str) -> Option<&Upstream> {
+        let mut domain = name;
+        loop {
+            if let Some(server) = self.domain_servers.get(domain) {
+                return Some(server);
+            }
+            domain = &domain[domain.find('.')? + 1..];
+        }
+    }
+
+    async fn select(&self, query: &[u8], dispatcher: &Dispatcher) -> Upstream {
+        if let Some(name) = query_name(query) {
+            if let Some(server) = self.domain_server(&name) {
+                return server.clone();
+            }
+            if let Some(server) = dispatcher.dns_client().read().await.rule_server(&name) {
+                return server;
+            }
+        }
+        self.servers[0].clone()
+    }
+}
+
+// Returns the name in the question of a query, lowercased and without the
+// trailing dot.
+fn query_name(query: &[u8]) -> Option<String> {
+    let msg = Message::from_vec(query).ok()?;
+    let name = msg.queries().first()?.name().to_ascii().to_lowercase();
+    Some(name.trim_end_matches('.').to_string())
+}
+
+// Reads a query prefixed with its length.
+async fn read_query(stream: &mut TcpStream, buf: &mut Vec<u8>) -> io::Result<()> {
+    buf.resize(2, 0);
+    stream.read_exact(buf).await?;
+    let len = u16::from_be_bytes([buf[0], buf[1]]) as usize;
+    buf.resize(2 + len, 0);
+    stream.read_exact(&mut buf[2..]).await?;
+    Ok(())
+}
+
+// Answers queries of the static hosts, and of the domains fake DNS accepts
+// if it's enabled.
+async fn answer_local(
+    query: &[u8],
+    fakedns: Option<&FakeDns>,
+    dispatcher: &Dispatcher,
+) -> Option<Vec<u8>> {
+    if let Some(resp) = dispatcher.dns_client().read().await.answer_static(query) {
+        return Some(resp);
+    }
+    match fakedns?.generate_fake_response(query).await {
+        Ok(resp) => Some(resp),
+        Err(e) => {
+            trace!("generate fake ip failed: {}", e);
+            None
+        }
+    }
+}
+
+// Queries to encrypted upstreams are sent by leaf directly instead of being
+// dispatched, the upstream is connected to without routing. Queries of the
+// static hosts and fake DNS are answered by leaf.
+async fn exchange(
+    upstream: &Upstream,
+    query: Vec<u8>,
+    fakedns: Option<&FakeDns>,
+    dispatcher: &Dispatcher,
+) -> Option<Vec<u8>> {
+    if let Some(resp) = answer_local(&query, fakedns, dispatcher).await {
+        return Some(resp);
+    }
+    let dns_client = dispatcher.dns_client().read().await;
+    let query = dns_client.with_client_subnet(query);
+    match dns_client.send_query(query, upstream).await {
+        Ok(resp) => Some(resp),
+        Err(e) => {
+            debug!("dns query to {} failed: {}", upstream, e);
+            None
+        }
+    }
+}
+
+async fn answer_stream(
+    mut stream: TcpStream,
+    mut buf: Vec<u8>,
+    upstream: Upstream,
+    fakedns: Option<Arc<FakeDns>>,
+    dispatcher: Arc<Dispatcher>,
+) {
+    loop {
+        let query = buf[2..].to_vec();
+        let resp = match exchange(&upstream, query, fakedns.as_deref(), &dispatcher).await {
+            Some(resp) => resp,
+            None => return,
+        };
+        let mut answer = (resp.len() as u16).to_be_bytes().to_vec();
+        answer.extend_from_slice(&resp);
+        if stream.write_all(&answer).await.is_err() {
+            return;
+        }
+        if read_query(&mut stream, &mut buf).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn handle_inbound_stream(
+    mut stream: TcpStream,
+    inbound_tag: String,
+    upstreams: Arc<Upstreams>,
+    fakedns: Option<Arc<FakeDns>>,
+    dispatcher: Arc<Dispatcher>,
+) {
+    let (source, local_addr) = match (stream.peer_addr(), stream.local_addr()) {
+        (Ok(source), Ok(local_addr)) => (source, local_addr),
+        _ => return,
+    };
+    // Reads the first query to choose the upstream, the connection is then
+    // relayed as is, so later queries go to the same upstream.
+    let mut buf = Vec::new();
+    if read_query(&mut stream, &mut buf).await.is_err() {
+        return;
+    }
+    let upstream = upstreams.select(&buf[2..], &dispatcher).await;
+    let is_local = answer_local(&buf[2..], fakedns.as_deref(), &dispatcher)
+        .await
+        .is_some();
+    let upstream = match upstream.udp_addr() {
+        // A connection starting with a query of the static hosts or fake DNS
+        // is answered by leaf, later queries included.
+        Some(addr) if !is_local => addr,
+        _ => {
+            answer_stream(stream, buf, upstream, fakedns, dispatcher).await;
+            return;
+        }
+    };
+    let sess = Session {
+        network: Network::Tcp,
+        source,
+        local_addr,
+        destination: SocksAddr::Ip(upstream),
+        inbound_tag,
+        ..Default::default()
+    };
+    let stream = PrefixedStream::new(stream, Bytes::from(buf));
+    dispatcher.dispatch_tcp(sess, stream).await;
+}
+
+async fn run_tcp(
+    listen_addr: SocketAddr,
+    inbound_tag: String,
+    upstreams: Arc<Upstreams>,
+    fakedns: Option<Arc<FakeDns>>,
+    dispatcher: Arc<Dispatcher>,
+) {
+    let listener = match TcpListener::bind(&listen_addr).await {
+        Ok(l) => l,
+        Err(e) => {
+            error!("dns inbound listen tcp {} failed: {}", &listen_addr, e);
+            return;
+        }
+    };
+    info!("dns inbound listening tcp {}", &listen_addr);
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(handle_inbound_stream(
+                    stream,
+                    inbound_tag.clone(),
+                    upstreams.clone(),
+                    fakedns.clone(),
+                    dispatcher.clone(),
+                ));
+            }
+            Err(e) => {
+                error!("accept connection failed: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+async fn run_udp(
+    listen_addr: SocketAddr,
+    inbound_tag: String,
+    upstreams: Arc<Upstreams>,
+    fakedns: Option<Arc<FakeDns>>,
+    dispatcher: Arc<Dispatcher>,
+    nat_manager: Arc<NatManager>,
+) {
+    let socket = match UdpSocket::bind(&listen_addr).await {
+        Ok(s) => Arc::new(s),
+        Err(e) => {
+            error!("dns inbound listen udp {} failed: {}", &listen_addr, e);
+            return;
+        }
+    };
+    info!("dns inbound listening udp {}", &listen_addr);
+
+    // Answers from upstreams are sent back to clients as is.
+    let (l_tx, mut l_rx): (TokioSender<UdpPacket>, TokioReceiver<UdpPacket>) = tokio_channel(100);
+    let socket_cloned = socket.clone();
+    tokio::spawn(async move {
+        while let Some(pkt) = l_rx.recv().await {
+            let dst_addr = pkt.dst_addr.must_ip();
+            if let Err(e) = socket_cloned.send_to(&pkt.data, &dst_addr).await {
+                debug!("send dns answer to {} failed: {}", &dst_addr, e);
+            }
+        }
+    });
+
+    let mut buf = vec![0u8; *crate::option::DATAGRAM_BUFFER_SIZE * 1024];
+    loop {
+        let (n, src_addr) = match socket.recv_from(&mut buf).await {
+            Ok(res) => res,
+            Err(e) => {
+                error!("receive dns query failed: {}", e);
+                return;
+            }
+        };
+        let answer = answer_local(&buf[..n], fakedns.as_deref(), &dispatcher).await;
+        if let Some(resp) = answer {
+            if let Err(e) = socket.send_to(&resp, &src_addr).await {
+                debug!("send dns answer to {} failed: {}", &src_addr, e);
+            }
+            continue;
+        }
+        let upstream = upstreams.select(&buf[..n], &dispatcher).await;
+        let upstream = match upstream.udp_addr() {
+            Some(addr) => addr,
+            None => {
+                let query = buf[..n].to_vec();
+                let (dispatcher, socket) = (dispatcher.clone(), socket.clone());
+                tokio::spawn(async move {
+                    if let Some(resp) = exchange(&upstream, query, None, &dispatcher).await {
+                        if let Err(e) = socket.send_to(&resp, &src_addr).await {
+                            debug!("send dns answer to {} failed: {}", &src_addr, e);
+                        }
+                    }
+                });
+                continue;
+            }
+        };
+        let query = dispatcher
+            .dns_client()
+            .read()
+            .await
+            .with_client_subnet(buf[..n].to_vec());
+        let dgram_src = DatagramSource::new(src_addr, None);
+        let pkt = UdpPacket::new(query, SocksAddr::Ip(src_addr), SocksAddr::Ip(upstream));
+        nat_manager
+            .send(None, &dgram_src, &inbound_tag, &l_tx, pkt)
+            .await;
+    }
+}
+
+pub fn new(
+    inbound: Inbound,
+    dispatcher: Arc<Dispatcher>,
+    nat_manager: Arc<NatManager>,
+) -> Result<Runner> {
+    let settings = DnsInboundSettings::parse_from_bytes(&inbound.settings)?;
+    let upstreams = Arc::new(Upstreams::new(&settings)?);
+    let listen_addr = SocketAddr::new(inbound.address.parse::<IpAddr>()?, inbound.port as u16);
+    let fake_dns = if settings.fake_dns {
+        if !settings.fake_dns_exclude.is_empty() && !settings.fake_dns_include.is_empty() {
+            return Err(anyhow!(
+                "fake DNS run in either include mode or exclude mode"
+            ));
+        }
+        let (mode, filters) = if !settings.fake_dns_include.is_empty() {
+            (FakeDnsMode::Include, settings.fake_dns_include.to_vec())
+        } else {
+            (FakeDnsMode::Exclude, settings.fake_dns_exclude.to_vec())
+        };
+        let pool = FakeIpPool::new(&settings.fake_ip_cidr, "", 0, "")?;
+        Some((mode, pool, filters))
+    } else {
+        None
+    };
+    Ok(Box::pin(async move {
+        // Fake IPs given out are connected to through other inbounds, e.g.
+        // redirect or tproxy, the dispatcher maps them back to the domains.
+        let fakedns = match fake_dns {
+            Some((mode, pool, filters)) => {
+                let fakedns = Arc::new(FakeDns::new(mode, pool));
+                fakedns.spawn_flush();
+                for filter in filters.into_iter() {
+                    fakedns.add_filter(filter).await;
+                }
+                dispatcher.add_fake_dns(fakedns.clone());
+                Some(fakedns)
+            }
+            None => None,
+        };
+        let tcp = run_tcp(
+            listen_addr,
+            inbound.tag.clone(),
+            upstreams.clone(),
+            fakedns.clone(),
+            dispatcher.clone(),
+        );
+        let udp = run_udp(
+            listen_addr,
+            inbound.tag.clone(),
+            upstreams,
+            fakedns,
+            dispatcher,
+            nat_manager,
+        );
+        match future::select(Box::pin(tcp), Box::pin(udp)).await {
+            Either::Left(_) => warn!("dns tcp listener stopped"),
+            Either::Right(_) => warn!("dns udp listener stopped"),
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use trust_dns_proto::op::Query;
+    use trust_dns_proto::rr::{Name, RecordType};
+
+    use super::*;
+
+    #[test]
+    fn test_select_upstream() {
+        let mut settings = DnsInboundSettings::new();
+        settings.servers.push("8.8.8.8".to_string());
+        settings
+            .domain_servers
+            .insert("example.com".to_string(), "1.1.1.1:5353".to_string());
+        let upstreams = Upstreams::new(&settings).unwrap();
+
+        let query = |name: &str| {
+            let mut msg = Message::new();
+            msg.add_query(Query::query(Name::from_ascii(name).unwrap(), RecordType::A));
+            msg.to_vec().unwrap()
+        };
+        let select = |query: &[u8]| {
+            query_name(query)
+                .and_then(|name| upstreams.domain_server(&name))
+                .map(|server| server.to_string())
+        };
+        let split = Some("1.1.1.1:5353".to_string());
+        assert_eq!(select(&query("example.com.")), split);
+        assert_eq!(select(&query("www.Example.com.")), split);
+        assert_eq!(select(&query("notexample.com.")), None);
+        assert_eq!(select(&query("example.org.")), None);
+        assert_eq!(select(b"garbage"), None);
+    }
+}
@@ -1,10 +1,89 @@
 use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
 use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
 use crate::{proxy::*, session::Session};
 
-pub struct Handler;
+const HTTP_FORBIDDEN: &[u8] = b"HTTP/1.1 403 Forbidden\r\n\
+    Content-Type: text/plain\r\n\
+    Content-Length: 9\r\n\
+    Connection: close\r\n\
+    \r\n\
+    Forbidden";
+
+/// How the connections are rejected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Resets the connections right away.
+    Close,
+    /// Accepts the connections and never responds.
+    Blackhole,
+    /// Responds a 403 page to the connections to port 80, and resets the
+    /// others.
+    Http,
+}
+
+impl Mode {
+    pub fn new(mode: &str) -> io::Result<Self> {
+        match mode {
+            "" | "close" => Ok(Mode::Close),
+            "blackhole" => Ok(Mode::Blackhole),
+            "http" => Ok(Mode::Http),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown tcp reject mode {}", mode),
+            )),
+        }
+    }
+}
+
+// A stream discarding what's written, which reads the response and then EOF,
+// or never completes a read if it's a blackhole.
+struct RejectStream {
+    response: &'static [u8],
+    blackhole: bool,
+}
+
+impl AsyncRead for RejectStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if self.blackhole {
+            return Poll::Pending;
+        }
+        let n = std::cmp::min(buf.remaining(), self.response.len());
+        buf.put_slice(&self.response[..n]);
+        self.response = &self.response[n..];
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for RejectStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+pub struct Handler {
+    pub mode: Mode,
+}
 
 #[async_trait]
 impl TcpOutboundHandler for Handler {
@@ -16,9 +95,62 @@ impl TcpOutboundHandler for Handler {
 
     async fn handle<'a>(
         &'a self,
-        _sess: &'a Session,
+        sess: &'a Session,
         _stream: Option<Self::Stream>,
     ) -> io::Result<Self::Stream> {
-        Err(io::Error::new(io::ErrorKind::Other, "dropped"))
+        match self.mode {
+            Mode::Blackhole => Ok(Box::new(RejectStream {
+                response: &[],
+                blackhole: true,
+            })),
+            Mode::Http if sess.destination.port() == 80 => Ok(Box::new(RejectStream {
+                response: HTTP_FORBIDDEN,
+                blackhole: false,
+            })),
+            // The inbound connection is reset by the dispatcher.
+            _ => Err(io::Error::new(io::ErrorKind::ConnectionReset, "dropped")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::session::SocksAddr;
+
+    #[test]
+    fn test_reject_stream() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let handler = Handler { mode: Mode::Http };
+            let mut sess = Session {
+                destination: SocksAddr::Domain("example.com".to_string(), 80),
+                ..Default::default()
+            };
+            let mut stream = handler.handle(&sess, None).await.unwrap();
+            stream.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            assert!(buf.starts_with(b"HTTP/1.1 403 Forbidden\r\n"));
+            assert!(buf.ends_with(b"\r\n\r\nForbidden"));
+
+            sess.destination = SocksAddr::Domain("example.com".to_string(), 443);
+            assert!(handler.handle(&sess, None).await.is_err());
+
+            let handler = Handler {
+                mode: Mode::Blackhole,
+            };
+            let mut stream = handler.handle(&sess, None).await.unwrap();
+            let mut buf = [0u8; 16];
+            let res =
+                tokio::time::timeout(std::time::Duration::from_millis(50), stream.read(&mut buf))
+                    .await;
+            assert!(res.is_err());
+        });
     }
 }
@@ -1,10 +1,146 @@
 use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr};
 
 use async_trait::async_trait;
+use tokio::sync::mpsc;
+use trust_dns_proto::op::{
+    header::MessageType, op_code::OpCode, response_code::ResponseCode, Message,
+};
+use trust_dns_proto::rr::{
+    dns_class::DNSClass, record_data::RData, record_type::RecordType, resource::Record,
+};
 
 use crate::{proxy::*, session::Session};
 
-pub struct Handler;
+const ANSWER_TTL: u32 = 60;
+
+/// How the sessions are rejected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Fails the sessions right away.
+    Drop,
+    /// Accepts the datagrams and never responds.
+    Blackhole,
+    /// Answers the DNS queries sent to port 53 with NXDOMAIN, and discards
+    /// other datagrams.
+    Nxdomain,
+    /// Answers the A and AAAA queries sent to port 53 with `0.0.0.0` and `::`,
+    /// and discards other datagrams.
+    Zero,
+}
+
+impl Mode {
+    pub fn new(mode: &str) -> io::Result<Self> {
+        match mode {
+            "" | "drop" => Ok(Mode::Drop),
+            "blackhole" => Ok(Mode::Blackhole),
+            "nxdomain" => Ok(Mode::Nxdomain),
+            "zero" => Ok(Mode::Zero),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown udp reject mode {}", mode),
+            )),
+        }
+    }
+}
+
+// Returns the answer to a DNS query, None if it's not a query.
+fn dns_answer(query: &[u8], mode: Mode) -> Option<Vec<u8>> {
+    let req = Message::from_vec(query).ok()?;
+    if req.message_type() != MessageType::Query {
+        return None;
+    }
+    let mut resp = Message::new();
+    resp.set_id(req.id())
+        .set_message_type(MessageType::Response)
+        .set_op_code(req.op_code());
+    if resp.op_code() == OpCode::Query {
+        resp.set_recursion_desired(req.recursion_desired())
+            .set_recursion_available(true)
+            .set_checking_disabled(req.checking_disabled());
+    }
+    resp.add_queries(req.queries().to_vec());
+    if mode == Mode::Nxdomain {
+        resp.set_response_code(ResponseCode::NXDomain);
+        return resp.to_vec().ok();
+    }
+    resp.set_response_code(ResponseCode::NoError);
+    for query in req.queries() {
+        let rdata = match query.query_type() {
+            RecordType::A => RData::A(Ipv4Addr::UNSPECIFIED),
+            RecordType::AAAA => RData::AAAA(Ipv6Addr::UNSPECIFIED),
+            _ => continue,
+        };
+        let mut ans = Record::new();
+        ans.set_name(query.name().clone())
+            .set_rr_type(query.query_type())
+            .set_ttl(ANSWER_TTL)
+            .set_dns_class(DNSClass::IN)
+            .set_rdata(rdata);
+        resp.add_answer(ans);
+    }
+    resp.to_vec().ok()
+}
+
+pub struct Datagram {
+    mode: Mode,
+}
+
+impl OutboundDatagram for Datagram {
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn OutboundDatagramRecvHalf>,
+        Box<dyn OutboundDatagramSendHalf>,
+    ) {
+        let (tx, rx) = mpsc::channel(16);
+        (
+            Box::new(DatagramRecvHalf(rx)),
+            Box::new(DatagramSendHalf {
+                mode: self.mode,
+                tx,
+            }),
+        )
+    }
+}
+
+pub struct DatagramRecvHalf(mpsc::Receiver<(Vec<u8>, SocksAddr)>);
+
+#[async_trait]
+impl OutboundDatagramRecvHalf for DatagramRecvHalf {
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocksAddr)> {
+        let (pkt, src_addr) = self
+            .0
+            .recv()
+            .await
+            .ok_or_else(|| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        let n = std::cmp::min(buf.len(), pkt.len());
+        buf[..n].copy_from_slice(&pkt[..n]);
+        Ok((n, src_addr))
+    }
+}
+
+pub struct DatagramSendHalf {
+    mode: Mode,
+    tx: mpsc::Sender<(Vec<u8>, SocksAddr)>,
+}
+
+#[async_trait]
+impl OutboundDatagramSendHalf for DatagramSendHalf {
+    async fn send_to(&mut self, buf: &[u8], dst_addr: &SocksAddr) -> io::Result<usize> {
+        if matches!(self.mode, Mode::Nxdomain | Mode::Zero) && dst_addr.port() == 53 {
+            if let Some(resp) = dns_answer(buf, self.mode) {
+                // Answers are dropped if the receiver doesn't keep up.
+                let _ = self.tx.try_send((resp, dst_addr.clone()));
+            }
+        }
+        Ok(buf.len())
+    }
+}
+
+pub struct Handler {
+    pub mode: Mode,
+}
 
 #[async_trait]
 impl UdpOutboundHandler for Handler {
@@ -24,6 +160,81 @@ impl UdpOutboundHandler for Handler {
         _sess: &'a Session,
         _transport: Option<OutboundTransport<Self::UStream, Self::Datagram>>,
     ) -> io::Result<Self::Datagram> {
-        Err(io::Error::new(io::ErrorKind::Other, "dropped"))
+        if self.mode == Mode::Drop {
+            return Err(io::Error::new(io::ErrorKind::Other, "dropped"));
+        }
+        Ok(Box::new(Datagram { mode: self.mode }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use trust_dns_proto::op::Query;
+    use trust_dns_proto::rr::Name;
+
+    use super::*;
+
+    fn query(name: &str, t: RecordType) -> Vec<u8> {
+        let mut msg = Message::new();
+        msg.set_id(7)
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(true);
+        msg.add_query(Query::query(Name::from_ascii(name).unwrap(), t));
+        msg.to_vec().unwrap()
+    }
+
+    #[test]
+    fn test_dns_answer() {
+        let resp = dns_answer(&query("ads.example.com.", RecordType::A), Mode::Nxdomain).unwrap();
+        let resp = Message::from_vec(&resp).unwrap();
+        assert_eq!(resp.id(), 7);
+        assert_eq!(resp.response_code(), ResponseCode::NXDomain);
+        assert!(resp.answers().is_empty());
+
+        let resp = dns_answer(&query("ads.example.com.", RecordType::AAAA), Mode::Zero).unwrap();
+        let resp = Message::from_vec(&resp).unwrap();
+        assert_eq!(resp.response_code(), ResponseCode::NoError);
+        assert_eq!(resp.queries().len(), 1);
+        assert_eq!(
+            resp.answers()[0].rdata(),
+            &RData::AAAA(Ipv6Addr::UNSPECIFIED)
+        );
+
+        assert!(dns_answer(b"not a dns message", Mode::Zero).is_none());
+    }
+
+    #[test]
+    fn test_reject_datagram() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let handler = Handler { mode: Mode::Zero };
+            let sess = Session::default();
+            let (mut recv, mut send) = handler.handle(&sess, None).await.unwrap().split();
+            let dns = SocksAddr::Ip("8.8.8.8:53".parse().unwrap());
+            let q = query("ads.example.com.", RecordType::A);
+            assert_eq!(send.send_to(&q, &dns).await.unwrap(), q.len());
+            let mut buf = [0u8; 512];
+            let (n, addr) = recv.recv_from(&mut buf).await.unwrap();
+            assert_eq!(addr, dns);
+            let resp = Message::from_vec(&buf[..n]).unwrap();
+            assert_eq!(resp.answers()[0].rdata(), &RData::A(Ipv4Addr::UNSPECIFIED));
+
+            // Other datagrams are discarded.
+            let other = SocksAddr::Ip("8.8.8.8:443".parse().unwrap());
+            send.send_to(&q, &other).await.unwrap();
+            let res = tokio::time::timeout(
+                std::time::Duration::from_millis(50),
+                recv.recv_from(&mut buf),
+            )
+            .await;
+            assert!(res.is_err());
+
+            let handler = Handler { mode: Mode::Drop };
+            assert!(handler.handle(&sess, None).await.is_err());
+        });
     }
 }
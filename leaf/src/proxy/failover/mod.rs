@@ -1,5 +1,157 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio::sync::Mutex as TokioMutex;
+
+use crate::app::outbound::HealthReport;
+
 pub mod tcp;
 pub mod udp;
 
 pub use tcp::Handler as TcpHandler;
 pub use udp::Handler as UdpHandler;
+
+/// Results of the TCP health checks of a failover outbound.
+pub struct Report {
+    tags: Vec<String>,
+    schedule: Arc<TokioMutex<Vec<usize>>>,
+    latencies: Arc<TokioMutex<Vec<Option<u64>>>>,
+}
+
+#[async_trait]
+impl HealthReport for Report {
+    async fn selected(&self) -> Option<String> {
+        let schedule = self.schedule.lock().await;
+        schedule.first().map(|i| self.tags[*i].clone())
+    }
+
+    async fn latencies(&self) -> Vec<(String, Option<u64>)> {
+        let latencies = self.latencies.lock().await;
+        self.tags
+            .iter()
+            .cloned()
+            .zip(latencies.iter().copied())
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct Member {
+    failures: u32,
+    ejections: u32,
+    ejected_until: Option<Instant>,
+}
+
+/// Ejector counts consecutive failures of the actors of real connections,
+/// and ejects an actor for a while once its failures reach the threshold.
+/// Connections try it again after the ejection, a failure then ejects it
+/// again for twice as long, up to the max duration, a success recovers it.
+pub struct Ejector {
+    threshold: u32,
+    duration: Duration,
+    max_duration: Duration,
+    members: Mutex<Vec<Member>>,
+}
+
+impl Ejector {
+    /// Never ejects if the threshold is 0.
+    pub fn new(size: usize, threshold: u32, duration: Duration, max_duration: Duration) -> Self {
+        let mut members = Vec::new();
+        members.resize_with(size, Default::default);
+        Self {
+            threshold,
+            duration,
+            max_duration,
+            members: Mutex::new(members),
+        }
+    }
+
+    /// Returns the actors not ejected of the schedule, or the whole schedule
+    /// if they're all ejected.
+    pub fn filter(&self, schedule: Vec<usize>) -> Vec<usize> {
+        self.filter_at(schedule, Instant::now())
+    }
+
+    fn filter_at(&self, schedule: Vec<usize>, now: Instant) -> Vec<usize> {
+        if self.threshold == 0 {
+            return schedule;
+        }
+        let members = self.members.lock().unwrap();
+        let available: Vec<usize> = schedule
+            .iter()
+            .copied()
+            .filter(|i| match members.get(*i).and_then(|m| m.ejected_until) {
+                Some(t) => t <= now,
+                None => true,
+            })
+            .collect();
+        if available.is_empty() {
+            schedule
+        } else {
+            available
+        }
+    }
+
+    pub fn report_success(&self, i: usize) {
+        if let Some(m) = self.members.lock().unwrap().get_mut(i) {
+            *m = Member::default();
+        }
+    }
+
+    /// Returns the ejection duration if the actor is ejected.
+    pub fn report_failure(&self, i: usize) -> Option<Duration> {
+        self.report_failure_at(i, Instant::now())
+    }
+
+    fn report_failure_at(&self, i: usize, now: Instant) -> Option<Duration> {
+        if self.threshold == 0 {
+            return None;
+        }
+        let mut members = self.members.lock().unwrap();
+        let m = members.get_mut(i)?;
+        m.failures = m.failures.saturating_add(1);
+        if m.failures < self.threshold {
+            return None;
+        }
+        let d = self
+            .duration
+            .saturating_mul(1 << m.ejections.min(16))
+            .min(self.max_duration);
+        m.ejections = m.ejections.saturating_add(1);
+        m.ejected_until = Some(now + d);
+        Some(d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ejector() {
+        let secs = Duration::from_secs;
+        let ejector = Ejector::new(2, 2, secs(10), secs(30));
+        let now = Instant::now();
+        assert_eq!(ejector.report_failure_at(0, now), None);
+        assert_eq!(ejector.filter_at(vec![0, 1], now), vec![0, 1]);
+        assert_eq!(ejector.report_failure_at(0, now), Some(secs(10)));
+        assert_eq!(ejector.filter_at(vec![0, 1], now), vec![1]);
+        // Tried again after the ejection, ejected for longer on failures.
+        let now = now + secs(10);
+        assert_eq!(ejector.filter_at(vec![0, 1], now), vec![0, 1]);
+        assert_eq!(ejector.report_failure_at(0, now), Some(secs(20)));
+        assert_eq!(ejector.report_failure_at(0, now), Some(secs(30)));
+        // Never skips all actors.
+        assert_eq!(ejector.report_failure_at(1, now), None);
+        assert_eq!(ejector.report_failure_at(1, now), Some(secs(10)));
+        assert_eq!(ejector.filter_at(vec![1, 0], now), vec![1, 0]);
+        // Recovered on success.
+        ejector.report_success(0);
+        assert_eq!(ejector.filter_at(vec![0, 1], now), vec![0]);
+        assert_eq!(ejector.report_failure_at(0, now), None);
+
+        let ejector = Ejector::new(1, 0, secs(10), secs(30));
+        assert_eq!(ejector.report_failure_at(0, now), None);
+    }
+}
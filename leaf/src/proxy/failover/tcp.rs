@@ -3,20 +3,21 @@ use std::{io, sync::Arc, time};
 use async_trait::async_trait;
 use futures::future::BoxFuture;
 use futures::future::{abortable, AbortHandle};
-use futures::FutureExt;
+use futures::{FutureExt, StreamExt};
 use log::*;
 use lru_time_cache::LruCache;
 use rand::{rngs::StdRng, Rng, SeedableRng};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::sync::Mutex as TokioMutex;
 use tokio::time::timeout;
 
 use crate::{
-    app::SyncDnsClient,
+    app::{outbound::urltest::HttpProbe, SyncDnsClient},
     proxy::*,
-    session::{Session, SocksAddr},
+    session::Session,
 };
 
+use super::{Ejector, Report};
+
 pub struct Handler {
     pub actors: Vec<AnyOutboundHandler>,
     pub fail_timeout: u32,
@@ -24,50 +25,41 @@ pub struct Handler {
     pub health_check_task: TokioMutex<Option<BoxFuture<'static, ()>>>,
     pub cache: Option<Arc<TokioMutex<LruCache<String, usize>>>>,
     pub last_resort: Option<AnyOutboundHandler>,
+    pub ejector: Ejector,
+    pub latencies: Arc<TokioMutex<Vec<Option<u64>>>>,
     pub dns_client: SyncDnsClient,
 }
 
 #[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
 struct Measure(usize, u128); // (index, duration in millis)
 
+#[allow(clippy::too_many_arguments)]
 async fn health_check_task(
     i: usize,
     h: AnyOutboundHandler,
     dns_client: SyncDnsClient,
     mut delay: Option<time::Duration>,
     health_check_timeout: u32,
+    probe: Arc<HttpProbe>,
+    expected_status: u16,
 ) -> Measure {
     if let Some(d) = delay.take() {
         tokio::time::sleep(d).await;
     }
     debug!("health checking tcp for [{}] index [{}]", h.tag(), i);
     let measure = async move {
-        let sess = Session {
-            destination: SocksAddr::Domain("www.google.com".to_string(), 80),
-            ..Default::default()
-        };
         let start = tokio::time::Instant::now();
-        let stream = match crate::proxy::connect_tcp_outbound(&sess, dns_client, &h).await {
-            Ok(s) => s,
-            Err(_) => return Measure(i, u128::MAX),
-        };
-        match TcpOutboundHandler::handle(h.as_ref(), &sess, stream).await {
-            Ok(mut stream) => {
-                if stream.write_all(b"HEAD / HTTP/1.1\r\n\r\n").await.is_err() {
-                    return Measure(i, u128::MAX - 2); // handshake is ok
-                }
-                let mut buf = vec![0u8; 1];
-                match stream.read_exact(&mut buf).await {
-                    // handshake, write and read are ok
-                    Ok(_) => {
-                        let elapsed = tokio::time::Instant::now().duration_since(start);
-                        Measure(i, elapsed.as_millis())
-                    }
-                    // handshake and write are ok
-                    Err(_) => Measure(i, u128::MAX - 3),
-                }
+        match probe.probe(&h, dns_client).await {
+            Ok(status) if expected_status == 0 || status == expected_status => {
+                let elapsed = tokio::time::Instant::now().duration_since(start);
+                Measure(i, elapsed.as_millis())
+            }
+            // handshake, write and read are ok
+            Ok(status) => {
+                debug!("health check for [{}] got status {}", h.tag(), status);
+                Measure(i, u128::MAX - 2)
             }
-            // handshake not ok
+            // handshake, write or read not ok
             Err(_) => Measure(i, u128::MAX),
         }
     };
@@ -96,6 +88,10 @@ impl Handler {
         cache_timeout: u64, // in minutes
         last_resort: Option<AnyOutboundHandler>,
         health_check_timeout: u32,
+        probe: Arc<HttpProbe>,
+        expected_status: u16,
+        concurrency: usize, // 0 for no limit
+        ejector: Ejector,
         dns_client: SyncDnsClient,
     ) -> (Self, Vec<AbortHandle>) {
         let mut abort_handles = Vec::new();
@@ -104,8 +100,10 @@ impl Handler {
             schedule.push(i);
         }
         let schedule = Arc::new(TokioMutex::new(schedule));
+        let latencies = Arc::new(TokioMutex::new(vec![None; actors.len()]));
 
         let schedule2 = schedule.clone();
+        let latencies2 = latencies.clone();
         let actors2 = actors.clone();
         let dns_client2 = dns_client.clone();
         let last_resort2 = last_resort.clone();
@@ -128,13 +126,31 @@ impl Handler {
                             dns_client4,
                             delay,
                             health_check_timeout,
+                            probe.clone(),
+                            expected_status,
                         )));
                     }
-                    let mut measures = futures::future::join_all(checks).await;
+                    let limit = if concurrency == 0 {
+                        checks.len()
+                    } else {
+                        concurrency
+                    };
+                    let mut measures: Vec<Measure> = futures::stream::iter(checks)
+                        .buffer_unordered(limit.max(1))
+                        .collect()
+                        .await;
 
                     measures.sort_by(|a, b| a.1.cmp(&b.1));
                     trace!("sorted tcp health check results:\n{:#?}", measures);
 
+                    let threshold =
+                        time::Duration::from_secs(health_check_timeout.into()).as_millis();
+                    let mut latencies = latencies2.lock().await;
+                    for m in measures.iter() {
+                        latencies[m.0] = Some(m.1).filter(|v| *v < threshold).map(|v| v as u64);
+                    }
+                    drop(latencies);
+
                     let priorities: Vec<String> = measures
                         .iter()
                         .map(|m| {
@@ -147,7 +163,7 @@ impl Handler {
                         })
                         .collect();
 
-                    debug!(
+                    info!(
                         "tcp priority after health check: {}",
                         priorities.join(" > ")
                     );
@@ -211,6 +227,8 @@ impl Handler {
                 health_check_task: TokioMutex::new(task),
                 cache,
                 last_resort,
+                ejector,
+                latencies,
                 dns_client,
             },
             abort_handles,
@@ -218,12 +236,32 @@ impl Handler {
     }
 }
 
+impl Handler {
+    pub fn health_report(&self) -> Report {
+        Report {
+            tags: self.actors.iter().map(|a| a.tag().to_owned()).collect(),
+            schedule: self.schedule.clone(),
+            latencies: self.latencies.clone(),
+        }
+    }
+
+    fn report_failure(&self, i: usize) {
+        if let Some(d) = self.ejector.report_failure(i) {
+            debug!(
+                "failover ejects [{}] from tcp for {}s",
+                self.actors[i].tag(),
+                d.as_secs()
+            );
+        }
+    }
+}
+
 #[async_trait]
 impl TcpOutboundHandler for Handler {
     type Stream = AnyStream;
 
     fn connect_addr(&self) -> Option<OutboundConnect> {
-        None
+        Some(OutboundConnect::NoConnect)
     }
 
     async fn handle<'a>(
@@ -256,12 +294,14 @@ impl TcpOutboundHandler for Handler {
                 };
                 let task = timeout(time::Duration::from_secs(self.fail_timeout as u64), handle);
                 if let Ok(Ok(v)) = task.await {
+                    self.ejector.report_success(*idx);
                     return Ok(v);
                 }
+                self.report_failure(*idx);
             };
         }
 
-        let schedule = self.schedule.lock().await.clone();
+        let schedule = self.ejector.filter(self.schedule.lock().await.clone());
 
         if schedule.is_empty() && self.last_resort.is_some() {
             let handle = async {
@@ -305,6 +345,7 @@ impl TcpOutboundHandler for Handler {
                 // return before timeout
                 Ok(t) => match t {
                     Ok(v) => {
+                        self.ejector.report_success(actor_idx);
                         // Only cache for fallback actors.
                         if let Some(cache) = &self.cache {
                             if sche_idx > 0 {
@@ -326,6 +367,7 @@ impl TcpOutboundHandler for Handler {
                             sess.destination,
                             e,
                         );
+                        self.report_failure(actor_idx);
                         continue;
                     }
                 },
@@ -336,6 +378,7 @@ impl TcpOutboundHandler for Handler {
                         sess.destination,
                         e,
                     );
+                    self.report_failure(actor_idx);
                     continue;
                 }
             }
@@ -345,4 +388,20 @@ impl TcpOutboundHandler for Handler {
             "all outbound attempts failed",
         ))
     }
+
+    // Chains can't fail over with one stream, they use the first actor in
+    // the order of the health checks.
+    async fn group_actor(&self, _sess: &Session) -> Option<AnyOutboundHandler> {
+        if let Some(task) = self.health_check_task.lock().await.take() {
+            tokio::spawn(task);
+        }
+        let schedule = self.ejector.filter(self.schedule.lock().await.clone());
+        match schedule.first() {
+            Some(i) => self.actors.get(*i).cloned(),
+            None => self
+                .last_resort
+                .clone()
+                .or_else(|| self.actors.first().cloned()),
+        }
+    }
 }
@@ -1,15 +1,12 @@
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time;
-use std::{
-    io,
-    net::{IpAddr, Ipv4Addr, SocketAddr},
-};
+use std::{io, net::SocketAddr};
 
 use async_trait::async_trait;
 use futures::future::BoxFuture;
 use futures::future::{abortable, AbortHandle};
-use futures::FutureExt;
+use futures::{FutureExt, StreamExt};
 use log::*;
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use tokio::sync::Mutex as TokioMutex;
@@ -25,12 +22,15 @@ use crate::{
     session::{Session, SocksAddr},
 };
 
+use super::Ejector;
+
 pub struct Handler {
     pub actors: Vec<AnyOutboundHandler>,
     pub fail_timeout: u32,
     pub schedule: Arc<TokioMutex<Vec<usize>>>,
     pub health_check_task: TokioMutex<Option<BoxFuture<'static, ()>>>,
     pub last_resort: Option<AnyOutboundHandler>,
+    pub ejector: Ejector,
     pub dns_client: SyncDnsClient,
 }
 
@@ -43,6 +43,7 @@ async fn health_check_task(
     dns_client: SyncDnsClient,
     mut delay: Option<time::Duration>,
     health_check_timeout: u32,
+    server: SocketAddr,
 ) -> Measure {
     if let Some(d) = delay.take() {
         tokio::time::sleep(d).await;
@@ -50,7 +51,7 @@ async fn health_check_task(
     debug!("health checking udp for [{}] index [{}]", h.tag(), i);
     let measure = async move {
         let sess = Session {
-            destination: SocksAddr::Ip(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)), 53)),
+            destination: SocksAddr::Ip(server),
             ..Default::default()
         };
         let start = tokio::time::Instant::now();
@@ -60,8 +61,7 @@ async fn health_check_task(
         };
         match UdpOutboundHandler::handle(h.as_ref(), &sess, transport).await {
             Ok(socket) => {
-                let addr =
-                    SocksAddr::Ip(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)), 53));
+                let addr = SocksAddr::Ip(server);
                 let mut msg = Message::new();
                 let name = match Name::from_str("www.google.com.") {
                     Ok(n) => n,
@@ -119,6 +119,7 @@ async fn health_check_task(
 }
 
 impl Handler {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         actors: Vec<AnyOutboundHandler>,
         fail_timeout: u32,
@@ -127,6 +128,9 @@ impl Handler {
         failover: bool,
         last_resort: Option<AnyOutboundHandler>,
         health_check_timeout: u32,
+        server: SocketAddr, // a DNS server to check with
+        concurrency: usize, // 0 for no limit
+        ejector: Ejector,
         dns_client: SyncDnsClient,
     ) -> (Self, Vec<AbortHandle>) {
         let mut abort_handles = Vec::new();
@@ -159,9 +163,18 @@ impl Handler {
                             dns_client4,
                             delay,
                             health_check_timeout,
+                            server,
                         )));
                     }
-                    let mut measures = futures::future::join_all(checks).await;
+                    let limit = if concurrency == 0 {
+                        checks.len()
+                    } else {
+                        concurrency
+                    };
+                    let mut measures: Vec<Measure> = futures::stream::iter(checks)
+                        .buffer_unordered(limit.max(1))
+                        .collect()
+                        .await;
 
                     measures.sort_by(|a, b| a.1.cmp(&b.1));
                     trace!("sorted udp health check results:\n{:#?}", measures);
@@ -177,7 +190,7 @@ impl Handler {
                             repr
                         })
                         .collect();
-                    debug!(
+                    info!(
                         "udp priority after health check: {}",
                         priorities.join(" > ")
                     );
@@ -229,6 +242,7 @@ impl Handler {
                 schedule,
                 health_check_task: TokioMutex::new(task),
                 last_resort,
+                ejector,
                 dns_client,
             },
             abort_handles,
@@ -236,13 +250,25 @@ impl Handler {
     }
 }
 
+impl Handler {
+    fn report_failure(&self, i: usize) {
+        if let Some(d) = self.ejector.report_failure(i) {
+            debug!(
+                "failover ejects [{}] from udp for {}s",
+                self.actors[i].tag(),
+                d.as_secs()
+            );
+        }
+    }
+}
+
 #[async_trait]
 impl UdpOutboundHandler for Handler {
     type UStream = AnyStream;
     type Datagram = AnyOutboundDatagram;
 
     fn connect_addr(&self) -> Option<OutboundConnect> {
-        None
+        Some(OutboundConnect::NoConnect)
     }
 
     fn transport_type(&self) -> DatagramTransportType {
@@ -258,7 +284,7 @@ impl UdpOutboundHandler for Handler {
             tokio::spawn(task);
         }
 
-        let schedule = self.schedule.lock().await.clone();
+        let schedule = self.ejector.filter(self.schedule.lock().await.clone());
 
         if schedule.is_empty() && self.last_resort.is_some() {
             let handle = async {
@@ -302,12 +328,21 @@ impl UdpOutboundHandler for Handler {
                 // return before timeout
                 Ok(t) => match t {
                     // return ok
-                    Ok(v) => return Ok(v),
+                    Ok(v) => {
+                        self.ejector.report_success(i);
+                        return Ok(v);
+                    }
                     // return err
-                    Err(_) => continue,
+                    Err(_) => {
+                        self.report_failure(i);
+                        continue;
+                    }
                 },
                 // after timeout
-                Err(_) => continue,
+                Err(_) => {
+                    self.report_failure(i);
+                    continue;
+                }
             }
         }
         Err(io::Error::new(
@@ -0,0 +1,5 @@
+//! gRPC transport compatible with the "gun" transport of V2Ray and Xray.
+//! Streams are carried in bidirectional streaming calls of the `Tun` method,
+//! multiplexed over a single HTTP/2 connection.
+
+pub mod outbound;
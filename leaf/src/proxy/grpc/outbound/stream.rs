@@ -0,0 +1,299 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use ::http::{Response, StatusCode};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures::ready;
+use h2::client::ResponseFuture;
+use h2::{RecvStream, SendStream};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::common::h2::h2_err;
+
+// The default maximum message size of gRPC.
+const MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+// The tag of the `data` field of the Hunk and MultiHunk messages.
+const DATA_TAG: u8 = 0x0a;
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn put_varint(buf: &mut BytesMut, mut v: usize) {
+    while v >= 0x80 {
+        buf.put_u8(v as u8 | 0x80);
+        v >>= 7;
+    }
+    buf.put_u8(v as u8);
+}
+
+fn get_varint(buf: &mut BytesMut) -> io::Result<usize> {
+    let mut v = 0usize;
+    for i in 0..10 {
+        if !buf.has_remaining() {
+            break;
+        }
+        let b = buf.get_u8();
+        v |= ((b & 0x7f) as usize) << (7 * i);
+        if b & 0x80 == 0 {
+            return Ok(v);
+        }
+    }
+    Err(invalid_data("invalid grpc varint"))
+}
+
+/// Encodes data as a length-prefixed gRPC message of a Hunk.
+fn encode_hunk(buf: &mut BytesMut, data: &[u8]) {
+    let mut hunk = BytesMut::with_capacity(data.len() + 6);
+    hunk.put_u8(DATA_TAG);
+    put_varint(&mut hunk, data.len());
+    hunk.put_slice(data);
+    buf.put_u8(0); // uncompressed
+    buf.put_u32(hunk.len() as u32);
+    buf.put_slice(&hunk);
+}
+
+/// Decodes the data of a Hunk or MultiHunk message.
+fn decode_hunk(mut msg: BytesMut, out: &mut BytesMut) -> io::Result<()> {
+    while msg.has_remaining() {
+        if msg.get_u8() != DATA_TAG {
+            return Err(invalid_data("unexpected grpc hunk field"));
+        }
+        let len = get_varint(&mut msg)?;
+        if len > msg.len() {
+            return Err(invalid_data("invalid grpc hunk length"));
+        }
+        out.put_slice(&msg.split_to(len));
+    }
+    Ok(())
+}
+
+enum Recv {
+    Response(ResponseFuture),
+    Body(RecvStream),
+}
+
+/// A `Tun` call, written data is sent in Hunk messages.
+pub struct Stream {
+    send: SendStream<Bytes>,
+    recv: Recv,
+    recv_buf: BytesMut,
+    data: BytesMut,
+    write_buf: BytesMut,
+    written: usize,
+}
+
+impl Stream {
+    pub fn new(send: SendStream<Bytes>, response: ResponseFuture) -> Self {
+        Stream {
+            send,
+            recv: Recv::Response(response),
+            recv_buf: BytesMut::new(),
+            data: BytesMut::new(),
+            write_buf: BytesMut::new(),
+            written: 0,
+        }
+    }
+
+    // Moves the data of complete messages received to the data buffer.
+    fn decode(&mut self) -> io::Result<()> {
+        while self.recv_buf.len() >= 5 {
+            let len = u32::from_be_bytes([
+                self.recv_buf[1],
+                self.recv_buf[2],
+                self.recv_buf[3],
+                self.recv_buf[4],
+            ]) as usize;
+            if self.recv_buf[0] != 0 {
+                return Err(invalid_data("compressed grpc message"));
+            }
+            if len > MAX_MESSAGE_SIZE {
+                return Err(invalid_data("grpc message too large"));
+            }
+            if self.recv_buf.len() < 5 + len {
+                break;
+            }
+            self.recv_buf.advance(5);
+            let msg = self.recv_buf.split_to(len);
+            decode_hunk(msg, &mut self.data)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_response(response: &Response<RecvStream>) -> io::Result<()> {
+    if response.status() != StatusCode::OK {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("grpc call failed: {}", response.status()),
+        ));
+    }
+    if let Some(status) = response.headers().get("grpc-status") {
+        if status != "0" {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("grpc call failed with status {:?}", status),
+            ));
+        }
+    }
+    Ok(())
+}
+
+impl AsyncRead for Stream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let me = &mut *self;
+        loop {
+            if !me.data.is_empty() {
+                let n = me.data.len().min(buf.remaining());
+                buf.put_slice(&me.data[..n]);
+                me.data.advance(n);
+                return Poll::Ready(Ok(()));
+            }
+            let recv = match &mut me.recv {
+                Recv::Response(response) => {
+                    let response = ready!(Pin::new(response).poll(cx)).map_err(h2_err)?;
+                    check_response(&response)?;
+                    me.recv = Recv::Body(response.into_body());
+                    continue;
+                }
+                Recv::Body(recv) => recv,
+            };
+            match ready!(recv.poll_data(cx)) {
+                Some(Ok(data)) => {
+                    let _ = recv.flow_control().release_capacity(data.len());
+                    me.recv_buf.put_slice(&data);
+                    me.decode()?;
+                }
+                Some(Err(e)) => return Poll::Ready(Err(h2_err(e))),
+                None => {
+                    if !me.recv_buf.is_empty() {
+                        return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+                    }
+                    return Poll::Ready(Ok(()));
+                }
+            }
+        }
+    }
+}
+
+impl AsyncWrite for Stream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        if me.write_buf.is_empty() {
+            if buf.is_empty() {
+                return Poll::Ready(Ok(0));
+            }
+            let n = buf.len().min(MAX_MESSAGE_SIZE / 2);
+            encode_hunk(&mut me.write_buf, &buf[..n]);
+            me.written = n;
+        }
+        while !me.write_buf.is_empty() {
+            me.send.reserve_capacity(me.write_buf.len());
+            match ready!(me.send.poll_capacity(cx)) {
+                Some(Ok(n)) => {
+                    let n = n.min(me.write_buf.len());
+                    let data = me.write_buf.split_to(n).freeze();
+                    me.send.send_data(data, false).map_err(h2_err)?;
+                }
+                Some(Err(e)) => return Poll::Ready(Err(h2_err(e))),
+                None => return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+            }
+        }
+        Poll::Ready(Ok(me.written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.send.send_data(Bytes::new(), true).map_err(h2_err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::http::Request;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[test]
+    fn test_hunk() {
+        let data = vec![7u8; 300];
+        let mut buf = BytesMut::new();
+        encode_hunk(&mut buf, &data);
+        // 300 takes 2 bytes as a varint.
+        assert_eq!(&buf[..8], &[0, 0, 0, 1, 47, DATA_TAG, 0xac, 0x02]);
+        let mut out = BytesMut::new();
+        decode_hunk(buf.split_off(5), &mut out).unwrap();
+        assert_eq!(&out[..], &data[..]);
+
+        // A MultiHunk of 2 chunks.
+        let msg = BytesMut::from(&[DATA_TAG, 2, b'h', b'e', DATA_TAG, 3, b'l', b'l', b'o'][..]);
+        let mut out = BytesMut::new();
+        decode_hunk(msg, &mut out).unwrap();
+        assert_eq!(&out[..], b"hello");
+    }
+
+    #[test]
+    fn test_stream() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let (client_io, server_io) = tokio::io::duplex(65536);
+            tokio::spawn(async move {
+                let mut conn = h2::server::handshake(server_io).await.unwrap();
+                let (req, mut respond) = conn.accept().await.unwrap().unwrap();
+                assert_eq!(req.uri().path(), "/name/Tun");
+                tokio::spawn(async move { while conn.accept().await.is_some() {} });
+                let mut body = req.into_body();
+                let resp = Response::builder()
+                    .header("content-type", "application/grpc")
+                    .body(())
+                    .unwrap();
+                let mut send = respond.send_response(resp, false).unwrap();
+                // Echo the messages back as they are.
+                while let Some(data) = body.data().await {
+                    let data = data.unwrap();
+                    let _ = body.flow_control().release_capacity(data.len());
+                    send.send_data(data, false).unwrap();
+                }
+                send.send_data(Bytes::new(), true).unwrap();
+            });
+
+            let (send_request, conn) = h2::client::handshake(client_io).await.unwrap();
+            tokio::spawn(conn);
+            let mut send_request = send_request.ready().await.unwrap();
+            let req = Request::post("https://example.com/name/Tun")
+                .body(())
+                .unwrap();
+            let (response, send) = send_request.send_request(req, false).unwrap();
+            let mut stream = Stream::new(send, response);
+            stream.write_all(b"hello").await.unwrap();
+            let mut buf = [0u8; 5];
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"hello");
+            let data = vec![1u8; 100000];
+            stream.write_all(&data).await.unwrap();
+            let mut buf = vec![0u8; data.len()];
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, data);
+            stream.shutdown().await.unwrap();
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            assert!(buf.is_empty());
+        });
+    }
+}
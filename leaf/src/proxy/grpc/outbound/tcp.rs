@@ -0,0 +1,143 @@
+use std::convert::TryFrom;
+use std::io;
+use std::time::Duration;
+
+use ::http::{Request, Uri};
+use async_trait::async_trait;
+use bytes::Bytes;
+use h2::client::SendRequest;
+
+use crate::{
+    app::SyncDnsClient,
+    common::h2::{h2_err, spawn_connection, SharedConnection},
+    proxy::*,
+    session::{Session, SocksAddr},
+};
+
+use super::stream::Stream;
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// The service name V2Ray and Xray use if it's not configured.
+const DEFAULT_SERVICE_NAME: &str = "GunService";
+
+const INITIAL_WINDOW_SIZE: u32 = 4 * 1024 * 1024;
+const INITIAL_CONNECTION_WINDOW_SIZE: u32 = 8 * 1024 * 1024;
+
+pub struct Handler {
+    address: String,
+    port: u16,
+    uri: Uri,
+    actors: Vec<AnyOutboundHandler>,
+    dns_client: SyncDnsClient,
+    dial_options: DialOptions,
+    connection: SharedConnection,
+}
+
+impl Handler {
+    pub fn new(
+        address: String,
+        port: u16,
+        host: String,
+        service_name: String,
+        actors: Vec<AnyOutboundHandler>,
+        dns_client: SyncDnsClient,
+        dial_options: DialOptions,
+    ) -> io::Result<Self> {
+        let host = if !host.is_empty() {
+            host
+        } else {
+            address.clone()
+        };
+        let service_name = if !service_name.is_empty() {
+            service_name
+        } else {
+            DEFAULT_SERVICE_NAME.to_string()
+        };
+        let uri = format!("https://{}/{}/Tun", host, service_name)
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        Ok(Handler {
+            address,
+            port,
+            uri,
+            actors,
+            dns_client,
+            dial_options,
+            connection: SharedConnection::default(),
+        })
+    }
+
+    async fn connect(&self, sess: &Session) -> io::Result<SendRequest<Bytes>> {
+        let mut stream = self
+            .new_tcp_stream(sess, self.dns_client.clone(), &self.address, &self.port)
+            .await?;
+        let mut sess = sess.clone();
+        if let Ok(addr) = SocksAddr::try_from((&self.address, self.port)) {
+            sess.destination = addr;
+        }
+        let handshake = async {
+            for a in self.actors.iter() {
+                stream = TcpOutboundHandler::handle(a.as_ref(), &sess, Some(stream)).await?;
+            }
+            h2::client::Builder::new()
+                .initial_window_size(INITIAL_WINDOW_SIZE)
+                .initial_connection_window_size(INITIAL_CONNECTION_WINDOW_SIZE)
+                .handshake(stream)
+                .await
+                .map_err(h2_err)
+        };
+        let (send_request, connection) = tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "grpc handshake timed out"))??;
+        log::debug!("grpc connected to {}:{}", &self.address, self.port);
+        spawn_connection(connection, "grpc", self.address.clone());
+        Ok(send_request)
+    }
+
+    async fn send_request(&self, sess: &Session) -> io::Result<SendRequest<Bytes>> {
+        self.connection.get(|| self.connect(sess)).await
+    }
+}
+
+impl TcpConnector for Handler {
+    fn dial_options(&self) -> &DialOptions {
+        &self.dial_options
+    }
+}
+
+#[async_trait]
+impl TcpOutboundHandler for Handler {
+    type Stream = AnyStream;
+
+    fn connect_addr(&self) -> Option<OutboundConnect> {
+        Some(OutboundConnect::NoConnect)
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        _stream: Option<Self::Stream>,
+    ) -> io::Result<Self::Stream> {
+        // All streams are multiplexed over a single connection, a new one is
+        // made when it's closed.
+        let mut send_request = self.send_request(sess).await?;
+        let req = Request::post(self.uri.clone())
+            .header("content-type", "application/grpc")
+            .header("te", "trailers")
+            .header("user-agent", "grpc-go/1.48.0")
+            .body(())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let (response, send) = match send_request.send_request(req, false) {
+            Ok(v) => v,
+            Err(e) => {
+                // E.g. the server has sent a GOAWAY.
+                self.connection.reset().await;
+                return Err(h2_err(e));
+            }
+        };
+        // The response isn't waited for, servers may send the headers only
+        // along with their first message.
+        Ok(Box::new(Stream::new(send, response)))
+    }
+}
@@ -9,6 +9,7 @@ use hyper::{server::conn::Http, service::Service, Body, Request, Response};
 use log::*;
 
 use crate::{
+    common::io::PrefixedStream,
     proxy::*,
     session::{Session, SocksAddr},
 };
@@ -55,11 +56,14 @@ pub struct Handler;
 
 #[async_trait]
 impl TcpInboundHandler for Handler {
+    type TStream = AnyStream;
+    type TDatagram = AnyInboundDatagram;
+
     async fn handle<'a>(
         &'a self,
         mut sess: Session,
-        stream: Box<dyn ProxyStream>,
-    ) -> std::io::Result<InboundTransport> {
+        stream: Self::TStream,
+    ) -> std::io::Result<InboundTransport<Self::TStream, Self::TDatagram>> {
         let http = Http::new();
         let proxy_service = ProxyService::new();
         let conn = http
@@ -99,8 +103,10 @@ impl TcpInboundHandler for Handler {
 
         sess.destination = destination;
 
+        // The client may send data right after the request without waiting
+        // for the response.
         Ok(InboundTransport::Stream(
-            Box::new(SimpleProxyStream(parts.io)),
+            Box::new(PrefixedStream::new(parts.io, parts.read_buf)),
             sess,
         ))
     }
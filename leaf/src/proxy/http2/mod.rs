@@ -0,0 +1,5 @@
+//! HTTP/2 transport compatible with the "h2" transport of V2Ray and Xray.
+//! Streams are the bodies of PUT requests and their responses, multiplexed
+//! over a single HTTP/2 connection.
+
+pub mod outbound;
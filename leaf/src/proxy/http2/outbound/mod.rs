@@ -0,0 +1,4 @@
+mod stream;
+pub mod tcp;
+
+pub use tcp::Handler as TcpHandler;
@@ -0,0 +1,166 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{Buf, Bytes};
+use futures::ready;
+use h2::client::ResponseFuture;
+use h2::{RecvStream, SendStream};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::common::h2::h2_err;
+
+enum Recv {
+    Response(ResponseFuture),
+    Body(RecvStream),
+}
+
+/// The body of a request and the one of its response.
+pub struct Stream {
+    send: SendStream<Bytes>,
+    recv: Recv,
+    recv_buf: Bytes,
+}
+
+impl Stream {
+    pub fn new(send: SendStream<Bytes>, response: ResponseFuture) -> Self {
+        Stream {
+            send,
+            recv: Recv::Response(response),
+            recv_buf: Bytes::new(),
+        }
+    }
+}
+
+impl AsyncRead for Stream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let me = &mut *self;
+        loop {
+            if !me.recv_buf.is_empty() {
+                let n = me.recv_buf.len().min(buf.remaining());
+                buf.put_slice(&me.recv_buf[..n]);
+                me.recv_buf.advance(n);
+                return Poll::Ready(Ok(()));
+            }
+            let recv = match &mut me.recv {
+                Recv::Response(response) => {
+                    let response = ready!(Pin::new(response).poll(cx)).map_err(h2_err)?;
+                    if !response.status().is_success() {
+                        return Poll::Ready(Err(io::Error::new(
+                            io::ErrorKind::Other,
+                            format!("h2 request failed: {}", response.status()),
+                        )));
+                    }
+                    me.recv = Recv::Body(response.into_body());
+                    continue;
+                }
+                Recv::Body(recv) => recv,
+            };
+            match ready!(recv.poll_data(cx)) {
+                Some(Ok(data)) => {
+                    let _ = recv.flow_control().release_capacity(data.len());
+                    me.recv_buf = data;
+                }
+                Some(Err(e)) => return Poll::Ready(Err(h2_err(e))),
+                None => return Poll::Ready(Ok(())),
+            }
+        }
+    }
+}
+
+impl AsyncWrite for Stream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        self.send.reserve_capacity(buf.len());
+        match ready!(self.send.poll_capacity(cx)) {
+            Some(Ok(n)) => {
+                let n = n.min(buf.len());
+                self.send
+                    .send_data(Bytes::copy_from_slice(&buf[..n]), false)
+                    .map_err(h2_err)?;
+                Poll::Ready(Ok(n))
+            }
+            Some(Err(e)) => Poll::Ready(Err(h2_err(e))),
+            None => Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.send.send_data(Bytes::new(), true).map_err(h2_err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::http::{Method, Request, Response};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[test]
+    fn test_stream() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let (client_io, server_io) = tokio::io::duplex(65536);
+            tokio::spawn(async move {
+                let mut conn = h2::server::handshake(server_io).await.unwrap();
+                let (req, mut respond) = conn.accept().await.unwrap().unwrap();
+                assert_eq!(req.method(), Method::PUT);
+                assert_eq!(req.uri().path(), "/path");
+                tokio::spawn(async move { while conn.accept().await.is_some() {} });
+                let mut body = req.into_body();
+                let resp = Response::builder().body(()).unwrap();
+                let mut send = respond.send_response(resp, false).unwrap();
+                while let Some(data) = body.data().await {
+                    let data = data.unwrap();
+                    let _ = body.flow_control().release_capacity(data.len());
+                    send.send_data(data, false).unwrap();
+                }
+                send.send_data(Bytes::new(), true).unwrap();
+            });
+
+            let (send_request, conn) = h2::client::handshake(client_io).await.unwrap();
+            tokio::spawn(conn);
+            let mut send_request = send_request.ready().await.unwrap();
+            let req = Request::put("https://example.com/path").body(()).unwrap();
+            let (response, send) = send_request.send_request(req, false).unwrap();
+            let mut stream = Stream::new(send, response);
+            stream.write_all(b"hello").await.unwrap();
+            let mut buf = [0u8; 5];
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"hello");
+            // More than the initial window size.
+            let data = vec![1u8; 100000];
+            let (mut r, mut w) = tokio::io::split(stream);
+            let expected = data.clone();
+            let reader = tokio::spawn(async move {
+                let mut buf = vec![0u8; expected.len()];
+                r.read_exact(&mut buf).await.unwrap();
+                assert_eq!(buf, expected);
+                let mut buf = Vec::new();
+                r.read_to_end(&mut buf).await.unwrap();
+                assert!(buf.is_empty());
+            });
+            w.write_all(&data).await.unwrap();
+            w.shutdown().await.unwrap();
+            reader.await.unwrap();
+        });
+    }
+}
@@ -0,0 +1,51 @@
+use std::io;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use tokio::io::AsyncReadExt;
+
+use crate::{
+    common::io::PrefixedStream,
+    proxy::{http, socks, *},
+    session::Session,
+};
+
+/// Serves both SOCKS5 and HTTP proxy requests on the same port, the protocol
+/// is told by the first byte, which is the version for SOCKS5.
+pub struct Handler {
+    socks: socks::inbound::TcpHandler,
+    http: http::inbound::TcpHandler,
+}
+
+impl Handler {
+    pub fn new(socks: socks::inbound::TcpHandler) -> Self {
+        Handler {
+            socks,
+            http: http::inbound::TcpHandler,
+        }
+    }
+}
+
+#[async_trait]
+impl TcpInboundHandler for Handler {
+    type TStream = AnyStream;
+    type TDatagram = AnyInboundDatagram;
+
+    async fn handle<'a>(
+        &'a self,
+        sess: Session,
+        mut stream: Self::TStream,
+    ) -> std::io::Result<InboundTransport<Self::TStream, Self::TDatagram>> {
+        let mut buf = [0u8; 1];
+        stream.read_exact(&mut buf).await?;
+        let stream = Box::new(PrefixedStream::new(stream, Bytes::copy_from_slice(&buf)));
+        match buf[0] {
+            0x05 => self.socks.handle(sess, stream).await,
+            b'A'..=b'Z' => self.http.handle(sess, stream).await,
+            _ => Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("unknown protocol of first byte {}", buf[0]),
+            )),
+        }
+    }
+}
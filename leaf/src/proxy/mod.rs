@@ -1,12 +1,11 @@
 use std::ffi::CString;
 use std::io;
-use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
 use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
-use futures::future::select_ok;
-use futures::stream::Stream;
+use futures::stream::{FuturesUnordered, Stream, StreamExt};
 use futures::TryFutureExt;
 use log::*;
 use socket2::SockRef;
@@ -26,7 +25,7 @@ use {
 };
 
 use crate::{
-    app::SyncDnsClient,
+    app::{dns_client::DomainStrategy, SyncDnsClient},
     common::resolver::Resolver,
     option,
     session::{DatagramSource, Session, SocksAddr},
@@ -44,32 +43,54 @@ pub mod amux;
 pub mod chain;
 #[cfg(feature = "outbound-direct")]
 pub mod direct;
+#[cfg(feature = "inbound-dns")]
+pub mod dns;
 #[cfg(feature = "outbound-drop")]
 pub mod drop;
 #[cfg(feature = "outbound-failover")]
 pub mod failover;
+#[cfg(feature = "outbound-grpc")]
+pub mod grpc;
 #[cfg(feature = "inbound-http")]
 pub mod http;
+#[cfg(feature = "outbound-http2")]
+pub mod http2;
+#[cfg(feature = "inbound-mixed")]
+pub mod mixed;
+#[cfg(any(feature = "inbound-mux", feature = "outbound-mux"))]
+pub mod mux;
+#[cfg(feature = "outbound-naive")]
+pub mod naive;
+#[cfg(feature = "outbound-obfs")]
+pub mod obfs;
 #[cfg(any(feature = "inbound-quic", feature = "outbound-quic"))]
 pub mod quic;
-#[cfg(feature = "outbound-redirect")]
+#[cfg(feature = "outbound-reality")]
+pub mod reality;
+#[cfg(any(feature = "inbound-redirect", feature = "outbound-redirect"))]
 pub mod redirect;
+#[cfg(feature = "outbound-retry")]
+pub mod retry;
 #[cfg(feature = "outbound-select")]
 pub mod select;
 #[cfg(any(feature = "inbound-shadowsocks", feature = "outbound-shadowsocks"))]
 pub mod shadowsocks;
 #[cfg(any(feature = "inbound-socks", feature = "outbound-socks"))]
 pub mod socks;
+#[cfg(feature = "outbound-ssh")]
+pub mod ssh;
 #[cfg(feature = "outbound-static")]
 pub mod r#static;
 #[cfg(feature = "outbound-tls")]
 pub mod tls;
+#[cfg(all(feature = "inbound-tproxy", target_os = "linux"))]
+pub mod tproxy;
 #[cfg(any(feature = "inbound-trojan", feature = "outbound-trojan"))]
 pub mod trojan;
 #[cfg(feature = "outbound-tryall")]
 pub mod tryall;
 #[cfg(all(
-    feature = "inbound-tun",
+    any(feature = "inbound-tun", feature = "inbound-tun-smoltcp"),
     any(
         target_os = "ios",
         target_os = "android",
@@ -78,12 +99,23 @@ pub mod tryall;
     )
 ))]
 pub mod tun;
+#[cfg(feature = "inbound-tunnel")]
+pub mod tunnel;
+#[cfg(any(feature = "inbound-shadowsocks", feature = "outbound-shadowsocks"))]
+pub mod uot;
+#[cfg(feature = "outbound-urltest")]
+pub mod urltest;
+#[cfg(any(feature = "inbound-vless", feature = "outbound-vless"))]
+pub mod vless;
+#[cfg(any(feature = "inbound-vmess", feature = "outbound-vmess"))]
+pub mod vmess;
 #[cfg(any(feature = "inbound-ws", feature = "outbound-ws"))]
 pub mod ws;
 
 pub use datagram::{
-    SimpleInboundDatagram, SimpleInboundDatagramRecvHalf, SimpleInboundDatagramSendHalf,
-    SimpleOutboundDatagram, SimpleOutboundDatagramRecvHalf, SimpleOutboundDatagramSendHalf,
+    DomainPeers, SimpleInboundDatagram, SimpleInboundDatagramRecvHalf,
+    SimpleInboundDatagramSendHalf, SimpleOutboundDatagram, SimpleOutboundDatagramRecvHalf,
+    SimpleOutboundDatagramSendHalf,
 };
 
 #[derive(Error, Debug)]
@@ -117,6 +149,17 @@ pub enum OutboundBind {
     Interface(String),
 }
 
+/// Local bindings of the sockets of an outbound, applied instead of the ones
+/// of `OUTBOUND_INTERFACE`.
+#[derive(Debug, Default, Clone)]
+pub struct SocketBind {
+    /// The network interface sockets are bound to.
+    pub interface: Option<String>,
+    /// The local addresses sockets are bound to, the one of the same family
+    /// as the remote address is used.
+    pub addresses: Vec<IpAddr>,
+}
+
 #[cfg(target_os = "android")]
 async fn protect_socket(fd: RawFd) -> io::Result<()> {
     // TODO Warns about empty protect path?
@@ -176,6 +219,26 @@ impl TcpListener {
         })
     }
 
+    /// Binds a listener accepting TCP Fast Open connections if `fast_open` is
+    /// true, on Linux and macOS.
+    pub async fn bind_with_fast_open(addr: &SocketAddr, fast_open: bool) -> io::Result<Self> {
+        if !fast_open {
+            return Self::bind(addr).await;
+        }
+        let socket = match addr {
+            SocketAddr::V4(..) => TcpSocket::new_v4()?,
+            SocketAddr::V6(..) => TcpSocket::new_v6()?,
+        };
+        #[cfg(unix)]
+        socket.set_reuseaddr(true)?;
+        socket.bind(*addr)?;
+        #[cfg(any(target_os = "macos", target_os = "linux"))]
+        set_fast_open(&socket)?;
+        Ok(Self {
+            inner: socket.listen(1024)?,
+        })
+    }
+
     pub async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
         let (stream, addr) = self.inner.accept().await?;
         apply_socket_opts(&stream)?;
@@ -183,7 +246,302 @@ impl TcpListener {
     }
 }
 
-async fn bind_socket<T: BindSocket>(socket: &T, indicator: &SocketAddr) -> io::Result<()> {
+// Enables TCP Fast Open on the listening socket.
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn set_fast_open<S: AsRawFd>(socket: &S) -> io::Result<()> {
+    // The max number of pending TFO requests on Linux, while any positive
+    // value enables it on macOS.
+    #[cfg(target_os = "linux")]
+    let value: libc::c_int = 256;
+    #[cfg(target_os = "macos")]
+    let value: libc::c_int = 1;
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_FASTOPEN,
+            &value as *const _ as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// Dials with TCP Fast Open, the SYN is sent along with the first write, in
+// which connection errors surface.
+#[cfg(target_os = "linux")]
+fn connect_fast_open(socket: TcpSocket, dial_addr: &SocketAddr) -> io::Result<TcpStream> {
+    use std::os::unix::io::{FromRawFd, IntoRawFd};
+
+    let value: libc::c_int = 1;
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_FASTOPEN_CONNECT,
+            &value as *const _ as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    let addr = socket2::SockAddr::from(*dial_addr);
+    let ret = unsafe { libc::connect(socket.as_raw_fd(), addr.as_ptr(), addr.len()) };
+    if ret == -1 {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::EINPROGRESS) {
+            return Err(err);
+        }
+    }
+    let stream = unsafe { std::net::TcpStream::from_raw_fd(socket.into_raw_fd()) };
+    TcpStream::from_std(stream)
+}
+
+// Dials with TCP Fast Open, the SYN is sent along with the first write, in
+// which connection errors surface.
+#[cfg(target_os = "macos")]
+fn connect_fast_open(socket: TcpSocket, dial_addr: &SocketAddr) -> io::Result<TcpStream> {
+    use std::os::unix::io::{FromRawFd, IntoRawFd};
+
+    let addr = socket2::SockAddr::from(*dial_addr);
+    let endpoints = libc::sa_endpoints_t {
+        sae_srcif: 0,
+        sae_srcaddr: std::ptr::null(),
+        sae_srcaddrlen: 0,
+        sae_dstaddr: addr.as_ptr(),
+        sae_dstaddrlen: addr.len(),
+    };
+    let ret = unsafe {
+        libc::connectx(
+            socket.as_raw_fd(),
+            &endpoints,
+            libc::SAE_ASSOCID_ANY,
+            libc::CONNECT_RESUME_ON_READ_WRITE | libc::CONNECT_DATA_IDEMPOTENT,
+            std::ptr::null(),
+            0,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if ret == -1 {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::EINPROGRESS) {
+            return Err(err);
+        }
+    }
+    let stream = unsafe { std::net::TcpStream::from_raw_fd(socket.into_raw_fd()) };
+    TcpStream::from_std(stream)
+}
+
+/// A stream dialed with TCP Fast Open, the handshake is made by the first
+/// write. Without a TFO cookie of the server, the write is sent once the
+/// handshake completes, which fails the write if it doesn't complete in the
+/// connect timeout.
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+struct FastOpenStream {
+    inner: TcpStream,
+    // The deadline of the handshake, until the first write succeeds.
+    connecting: Option<std::pin::Pin<Box<tokio::time::Sleep>>>,
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+impl FastOpenStream {
+    fn new(inner: TcpStream, connect_timeout: Duration) -> Self {
+        FastOpenStream {
+            inner,
+            connecting: Some(Box::pin(tokio::time::sleep(connect_timeout))),
+        }
+    }
+
+    // Writes while connecting, EINPROGRESS means the handshake is going on
+    // without data as there's no cookie, it's written when writable.
+    fn poll_write_connecting(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<io::Result<usize>> {
+        use std::future::Future;
+        use std::task::Poll;
+
+        if let Some(deadline) = self.connecting.as_mut() {
+            if deadline.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "tcp connect timed out",
+                )));
+            }
+        }
+        loop {
+            match self.inner.poll_write_ready(cx) {
+                Poll::Ready(Ok(())) => (),
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+            let inner = &self.inner;
+            let res = inner.try_io(tokio::io::Interest::WRITABLE, || {
+                SockRef::from(inner).send(buf).map_err(|e| {
+                    if e.raw_os_error() == Some(libc::EINPROGRESS) {
+                        io::ErrorKind::WouldBlock.into()
+                    } else {
+                        e
+                    }
+                })
+            });
+            match res {
+                Ok(n) => {
+                    self.connecting = None;
+                    return Poll::Ready(Ok(n));
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+impl AsyncRead for FastOpenStream {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<io::Result<()>> {
+        std::pin::Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+impl AsyncWrite for FastOpenStream {
+    fn poll_write(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<io::Result<usize>> {
+        if self.connecting.is_some() {
+            return self.poll_write_connecting(cx, buf);
+        }
+        std::pin::Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<io::Result<()>> {
+        std::pin::Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<io::Result<()>> {
+        std::pin::Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+// Binds the socket to a network interface.
+fn bind_to_interface<T: BindSocket>(
+    socket: &T,
+    indicator: &SocketAddr,
+    iface: &str,
+) -> io::Result<()> {
+    #[cfg(target_os = "macos")]
+    unsafe {
+        let ifa = CString::new(iface.as_bytes()).unwrap();
+        let ifidx: libc::c_uint = libc::if_nametoindex(ifa.as_ptr());
+        if ifidx == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let ret = match indicator {
+            SocketAddr::V4(..) => {
+                // https://github.com/apple/darwin-xnu/blob/8f02f2a044b9bb1ad951987ef5bab20ec9486310/bsd/netinet/in.h#L484
+                const IP_BOUND_IF: libc::c_int = 25;
+                libc::setsockopt(
+                    socket.as_raw_fd(),
+                    libc::IPPROTO_IP,
+                    IP_BOUND_IF,
+                    &ifidx as *const _ as *const libc::c_void,
+                    std::mem::size_of::<libc::c_uint>() as libc::socklen_t,
+                )
+            }
+            SocketAddr::V6(..) => {
+                // https://github.com/apple/darwin-xnu/blob/8f02f2a044b9bb1ad951987ef5bab20ec9486310/bsd/netinet6/in6.h#L692
+                const IPV6_BOUND_IF: libc::c_int = 125;
+                libc::setsockopt(
+                    socket.as_raw_fd(),
+                    libc::IPPROTO_IPV6,
+                    IPV6_BOUND_IF,
+                    &ifidx as *const _ as *const libc::c_void,
+                    std::mem::size_of::<libc::c_uint>() as libc::socklen_t,
+                )
+            }
+        };
+        if ret == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        trace!("socket bind {}", iface);
+        Ok(())
+    }
+    #[cfg(target_os = "linux")]
+    unsafe {
+        let _ = indicator;
+        let ifa = CString::new(iface.as_bytes()).unwrap();
+        let ret = libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_BINDTODEVICE,
+            ifa.as_ptr() as *const libc::c_void,
+            ifa.as_bytes().len() as libc::socklen_t,
+        );
+        if ret == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        trace!("socket bind {}", iface);
+        Ok(())
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        let _ = (socket, indicator, iface);
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "binding to interface is not supported on this platform",
+        ))
+    }
+}
+
+// Sets the fwmark of the socket to OUTBOUND_FWMARK if it's not 0.
+#[cfg(target_os = "linux")]
+fn mark_socket<S: AsRawFd>(socket: &S) -> io::Result<()> {
+    let mark: u32 = *option::OUTBOUND_FWMARK;
+    if mark == 0 {
+        return Ok(());
+    }
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_MARK,
+            &mark as *const _ as *const libc::c_void,
+            std::mem::size_of::<u32>() as libc::socklen_t,
+        )
+    };
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    trace!("socket mark {}", mark);
+    Ok(())
+}
+
+// Binds the socket as `bind` specifies if set, or to one of OUTBOUND_BINDS.
+async fn bind_socket<T: BindSocket>(
+    socket: &T,
+    indicator: &SocketAddr,
+    bind: Option<&SocketBind>,
+) -> io::Result<()> {
     match indicator.ip() {
         IpAddr::V4(v4) if v4.is_loopback() => {
             socket.bind(&SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0).into())?;
@@ -197,75 +555,33 @@ async fn bind_socket<T: BindSocket>(socket: &T, indicator: &SocketAddr) -> io::R
         }
         _ => {}
     }
+    if let Some(bind) = bind {
+        if let Some(iface) = &bind.interface {
+            bind_to_interface(socket, indicator, iface)?;
+        }
+        if !bind.addresses.is_empty() {
+            let addr = bind
+                .addresses
+                .iter()
+                .find(|a| a.is_ipv4() == indicator.is_ipv4())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("no bind address for {}", indicator),
+                    )
+                })?;
+            socket.bind(&SocketAddr::new(*addr, 0))?;
+            trace!("socket bind {}", addr);
+        }
+        return Ok(());
+    }
     let mut last_err = None;
     for bind in option::OUTBOUND_BINDS.iter() {
         match bind {
-            OutboundBind::Interface(iface) => {
-                #[cfg(target_os = "macos")]
-                unsafe {
-                    let ifa = CString::new(iface.as_bytes()).unwrap();
-                    let ifidx: libc::c_uint = libc::if_nametoindex(ifa.as_ptr());
-                    if ifidx == 0 {
-                        last_err = Some(io::Error::last_os_error());
-                        continue;
-                    }
-
-                    let ret = match indicator {
-                        SocketAddr::V4(..) => {
-                            // https://github.com/apple/darwin-xnu/blob/8f02f2a044b9bb1ad951987ef5bab20ec9486310/bsd/netinet/in.h#L484
-                            const IP_BOUND_IF: libc::c_int = 25;
-                            libc::setsockopt(
-                                socket.as_raw_fd(),
-                                libc::IPPROTO_IP,
-                                IP_BOUND_IF,
-                                &ifidx as *const _ as *const libc::c_void,
-                                std::mem::size_of::<libc::c_uint>() as libc::socklen_t,
-                            )
-                        }
-                        SocketAddr::V6(..) => {
-                            // https://github.com/apple/darwin-xnu/blob/8f02f2a044b9bb1ad951987ef5bab20ec9486310/bsd/netinet6/in6.h#L692
-                            const IPV6_BOUND_IF: libc::c_int = 125;
-                            libc::setsockopt(
-                                socket.as_raw_fd(),
-                                libc::IPPROTO_IPV6,
-                                IPV6_BOUND_IF,
-                                &ifidx as *const _ as *const libc::c_void,
-                                std::mem::size_of::<libc::c_uint>() as libc::socklen_t,
-                            )
-                        }
-                    };
-                    if ret == -1 {
-                        last_err = Some(io::Error::last_os_error());
-                        continue;
-                    }
-                    trace!("socket bind {}", iface);
-                    return Ok(());
-                }
-                #[cfg(target_os = "linux")]
-                unsafe {
-                    let ifa = CString::new(iface.as_bytes()).unwrap();
-                    let ret = libc::setsockopt(
-                        socket.as_raw_fd(),
-                        libc::SOL_SOCKET,
-                        libc::SO_BINDTODEVICE,
-                        ifa.as_ptr() as *const libc::c_void,
-                        ifa.as_bytes().len() as libc::socklen_t,
-                    );
-                    if ret == -1 {
-                        last_err = Some(io::Error::last_os_error());
-                        continue;
-                    }
-                    trace!("socket bind {}", iface);
-                    return Ok(());
-                }
-                #[cfg(not(any(target_os = "macos", target_os = "linux")))]
-                {
-                    return Err(io::Error::new(
-                        io::ErrorKind::Other,
-                        "binding to interface is not supported on this platform",
-                    ));
-                }
-            }
+            OutboundBind::Interface(iface) => match bind_to_interface(socket, indicator, iface) {
+                Ok(()) => return Ok(()),
+                Err(e) => last_err = Some(e),
+            },
             OutboundBind::Ip(addr) => {
                 if (addr.is_ipv4() && indicator.is_ipv4())
                     || (addr.is_ipv6() && indicator.is_ipv6())
@@ -290,6 +606,14 @@ async fn bind_socket<T: BindSocket>(socket: &T, indicator: &SocketAddr) -> io::R
 
 // New UDP socket.
 pub async fn new_udp_socket(indicator: &SocketAddr) -> io::Result<UdpSocket> {
+    new_udp_socket_with_bind(indicator, None).await
+}
+
+// New UDP socket bound as `bind` specifies if set.
+pub async fn new_udp_socket_with_bind(
+    indicator: &SocketAddr,
+    bind: Option<&SocketBind>,
+) -> io::Result<UdpSocket> {
     use socket2::{Domain, Socket, Type};
     let socket = if *option::ENABLE_IPV6 {
         // Dual-stack socket.
@@ -306,11 +630,14 @@ pub async fn new_udp_socket(indicator: &SocketAddr) -> io::Result<UdpSocket> {
     // If the proxy request is coming from an inbound listens on the loopback,
     // the indicator could be a loopback address, we must ignore it.
     if indicator.ip().is_loopback() || *option::ENABLE_IPV6 {
-        bind_socket(&socket, &*option::UNSPECIFIED_BIND_ADDR).await?;
+        bind_socket(&socket, &*option::UNSPECIFIED_BIND_ADDR, bind).await?;
     } else {
-        bind_socket(&socket, indicator).await?;
+        bind_socket(&socket, indicator, bind).await?;
     }
 
+    #[cfg(target_os = "linux")]
+    mark_socket(&socket)?;
+
     #[cfg(target_os = "android")]
     protect_socket(socket.as_raw_fd()).await?;
 
@@ -333,23 +660,37 @@ fn apply_socket_opts<S: AsRawSocket>(socket: &S) -> io::Result<()> {
 }
 
 // A single TCP dial.
-async fn tcp_dial_task(dial_addr: SocketAddr) -> io::Result<(AnyStream, SocketAddr)> {
+async fn tcp_dial_task(
+    dial_addr: SocketAddr,
+    bind: Option<&SocketBind>,
+    fast_open: bool,
+    connect_timeout: Duration,
+) -> io::Result<(AnyStream, SocketAddr)> {
     let socket = match dial_addr {
         SocketAddr::V4(..) => TcpSocket::new_v4()?,
         SocketAddr::V6(..) => TcpSocket::new_v6()?,
     };
 
-    bind_socket(&socket, &dial_addr).await?;
+    bind_socket(&socket, &dial_addr, bind).await?;
+
+    #[cfg(target_os = "linux")]
+    mark_socket(&socket)?;
 
     #[cfg(target_os = "android")]
     protect_socket(socket.as_raw_fd()).await?;
 
     trace!("tcp dialing {}", &dial_addr);
-    let stream = timeout(
-        Duration::from_secs(*option::OUTBOUND_DIAL_TIMEOUT),
-        socket.connect(dial_addr),
-    )
-    .await??;
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    if fast_open {
+        let stream = connect_fast_open(socket, &dial_addr)?;
+        apply_socket_opts(&stream)?;
+        trace!("tcp fast open {} <-> {}", stream.local_addr()?, &dial_addr);
+        let stream = FastOpenStream::new(stream, connect_timeout);
+        return Ok((Box::new(stream), dial_addr));
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    let _ = fast_open;
+    let stream = timeout(connect_timeout, socket.connect(dial_addr)).await??;
 
     apply_socket_opts(&stream)?;
 
@@ -362,15 +703,33 @@ pub async fn connect_tcp_outbound(
     dns_client: SyncDnsClient,
     handler: &AnyOutboundHandler,
 ) -> io::Result<Option<AnyStream>> {
+    let dns_client = sess.dns_client.clone().unwrap_or(dns_client);
+    let bind = handler.bind();
+    let strategy = handler.domain_strategy();
+    let fast_open = handler.tcp_fast_open();
+    let connect_timeout = handler.connect_timeout();
     match TcpOutboundHandler::connect_addr(handler.as_ref()) {
-        Some(OutboundConnect::Proxy(addr, port)) => {
-            Ok(Some(new_tcp_stream(dns_client, &addr, &port).await?))
-        }
+        Some(OutboundConnect::Proxy(addr, port)) => Ok(Some(
+            new_tcp_stream_with_bind(
+                dns_client,
+                &addr,
+                &port,
+                bind,
+                strategy,
+                fast_open,
+                connect_timeout,
+            )
+            .await?,
+        )),
         Some(OutboundConnect::Direct) => Ok(Some(
-            new_tcp_stream(
+            new_tcp_stream_with_bind(
                 dns_client,
                 &sess.destination.host(),
                 &sess.destination.port(),
+                bind,
+                strategy,
+                fast_open,
+                connect_timeout,
             )
             .await?,
         )),
@@ -383,32 +742,45 @@ pub async fn connect_udp_outbound(
     dns_client: SyncDnsClient,
     handler: &AnyOutboundHandler,
 ) -> io::Result<Option<AnyOutboundTransport>> {
+    let dns_client = sess.dns_client.clone().unwrap_or(dns_client);
+    let bind = handler.bind();
+    let strategy = handler.domain_strategy();
+    // Sockets of strategies resolving to IPv6 addresses are IPv6 ones, which
+    // are able to send to IPv4 addresses as well.
+    let indicator = if strategy != DomainStrategy::AsIs && strategy.allows_ipv6() {
+        SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0)
+    } else {
+        sess.source
+    };
     match UdpOutboundHandler::connect_addr(handler.as_ref()) {
         Some(OutboundConnect::Proxy(addr, port)) => {
             match UdpOutboundHandler::transport_type(handler.as_ref()) {
                 DatagramTransportType::Datagram => {
-                    let socket = new_udp_socket(&sess.source).await?;
+                    let socket = new_udp_socket_with_bind(&indicator, bind).await?;
                     Ok(Some(OutboundTransport::Datagram(Box::new(
-                        SimpleOutboundDatagram::new(socket, None, dns_client.clone()),
+                        SimpleOutboundDatagram::new(socket, dns_client.clone(), strategy),
                     ))))
                 }
                 DatagramTransportType::Stream => {
-                    let stream = new_tcp_stream(dns_client.clone(), &addr, &port).await?;
+                    let stream = new_tcp_stream_with_bind(
+                        dns_client.clone(),
+                        &addr,
+                        &port,
+                        bind,
+                        strategy,
+                        handler.tcp_fast_open(),
+                        handler.connect_timeout(),
+                    )
+                    .await?;
                     Ok(Some(OutboundTransport::Stream(stream)))
                 }
                 DatagramTransportType::Undefined => Ok(None),
             }
         }
         Some(OutboundConnect::Direct) => {
-            let socket = new_udp_socket(&sess.source).await?;
-            let dest = match &sess.destination {
-                SocksAddr::Domain(domain, port) => {
-                    Some(SocksAddr::Domain(domain.to_owned(), port.to_owned()))
-                }
-                _ => None,
-            };
+            let socket = new_udp_socket_with_bind(&indicator, bind).await?;
             Ok(Some(OutboundTransport::Datagram(Box::new(
-                SimpleOutboundDatagram::new(socket, dest, dns_client.clone()),
+                SimpleOutboundDatagram::new(socket, dns_client.clone(), strategy),
             ))))
         }
         Some(OutboundConnect::NoConnect) | None => Ok(None),
@@ -421,7 +793,43 @@ pub async fn new_tcp_stream(
     address: &String,
     port: &u16,
 ) -> io::Result<AnyStream> {
-    let mut resolver = Resolver::new(dns_client.clone(), address, port)
+    new_tcp_stream_with_bind(
+        dns_client,
+        address,
+        port,
+        None,
+        DomainStrategy::AsIs,
+        false,
+        None,
+    )
+    .await
+}
+
+// Dials a TCP stream to an address known without a lookup, e.g. an encrypted
+// DNS server.
+pub async fn dial_tcp_stream(addr: &SocketAddr) -> io::Result<AnyStream> {
+    let connect_timeout = Duration::from_secs(*option::OUTBOUND_DIAL_TIMEOUT);
+    let (stream, _) = tcp_dial_task(*addr, None, false, connect_timeout).await?;
+    Ok(stream)
+}
+
+// Dials a TCP stream bound as `bind` specifies if set, the address is resolved
+// with `strategy`. With `fast_open`, the dial completes without waiting for the
+// handshake, which then fails the first read or write if the server is
+// unreachable. Connection attempts time out after `connect_timeout` if set, or
+// OUTBOUND_DIAL_TIMEOUT.
+pub async fn new_tcp_stream_with_bind(
+    dns_client: SyncDnsClient,
+    address: &String,
+    port: &u16,
+    bind: Option<&SocketBind>,
+    strategy: DomainStrategy,
+    fast_open: bool,
+    connect_timeout: Option<Duration>,
+) -> io::Result<AnyStream> {
+    let connect_timeout =
+        connect_timeout.unwrap_or_else(|| Duration::from_secs(*option::OUTBOUND_DIAL_TIMEOUT));
+    let mut resolver = Resolver::new(dns_client.clone(), address, port, strategy)
         .map_err(|e| {
             io::Error::new(
                 io::ErrorKind::Other,
@@ -430,38 +838,72 @@ pub async fn new_tcp_stream(
         })
         .await?;
 
-    let mut last_err = None;
-
-    let mut done = false;
+    // A Fast Open dial completes at once, before the handshake, so it would
+    // always win the race. It's only used for servers of a single address.
+    let fast_open = fast_open && resolver.remaining() == 1;
+
+    // Races the connection attempts as RFC 8305 describes, a new attempt is
+    // started whenever the previous one fails or doesn't complete in time,
+    // the first established connection wins.
+    let attempt_delay = Duration::from_millis(*option::OUTBOUND_DIAL_ATTEMPT_DELAY);
+    let mut attempts = FuturesUnordered::new();
+    for dial_addr in resolver
+        .by_ref()
+        .take(std::cmp::max(*option::OUTBOUND_DIAL_CONCURRENCY, 1))
+    {
+        attempts.push(tcp_dial_task(dial_addr, bind, fast_open, connect_timeout));
+    }
 
-    while !done {
-        let mut tasks = Vec::new();
-        for _ in 0..*option::OUTBOUND_DIAL_CONCURRENCY {
-            let dial_addr = match resolver.next() {
-                Some(a) => a,
-                None => {
-                    done = true; // run out
-                    break; // break and execute tasks if there're any
-                }
-            };
-            let t = tcp_dial_task(dial_addr);
-            tasks.push(Box::pin(t));
-        }
-        if !tasks.is_empty() {
-            match select_ok(tasks.into_iter()).await {
-                Ok(v) => {
-                    #[rustfmt::skip]
-                    dns_client.read().await.optimize_cache(address.to_owned(), v.0.1.ip()).await;
-                    #[rustfmt::skip]
-                    return Ok(v.0.0);
+    let mut last_err = None;
+    let mut exhausted = false;
+
+    while !attempts.is_empty() {
+        let res = if exhausted {
+            attempts.next().await
+        } else {
+            match timeout(attempt_delay, attempts.next()).await {
+                Ok(res) => res,
+                Err(_) => {
+                    match resolver.next() {
+                        Some(dial_addr) => attempts.push(tcp_dial_task(
+                            dial_addr,
+                            bind,
+                            fast_open,
+                            connect_timeout,
+                        )),
+                        None => exhausted = true,
+                    }
+                    continue;
                 }
-                Err(e) => {
-                    last_err = Some(io::Error::new(
-                        io::ErrorKind::Other,
-                        format!("all attempts failed, last error: {}", e),
-                    ));
+            }
+        };
+        match res {
+            Some(Ok((stream, dial_addr))) => {
+                dns_client
+                    .read()
+                    .await
+                    .optimize_cache(address.to_owned(), dial_addr.ip())
+                    .await;
+                return Ok(stream);
+            }
+            Some(Err(e)) => {
+                last_err = Some(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("all attempts failed, last error: {}", e),
+                ));
+                if !exhausted {
+                    match resolver.next() {
+                        Some(dial_addr) => attempts.push(tcp_dial_task(
+                            dial_addr,
+                            bind,
+                            fast_open,
+                            connect_timeout,
+                        )),
+                        None => exhausted = true,
+                    }
                 }
             }
+            None => break,
         }
     }
 
@@ -473,26 +915,54 @@ pub async fn new_tcp_stream(
     }))
 }
 
+/// The options of an outbound applied to the connections it dials itself.
+#[derive(Debug, Default, Clone)]
+pub struct DialOptions {
+    pub bind: Option<SocketBind>,
+    pub domain_strategy: DomainStrategy,
+    pub tcp_fast_open: bool,
+    pub connect_timeout: Option<Duration>,
+}
+
 /// An interface with the ability to dial TCP connections.
 #[async_trait]
 pub trait TcpConnector: Send + Sync + Unpin {
-    /// Dials a TCP connection.
+    /// Returns the options the connections are dialed with.
+    fn dial_options(&self) -> &DialOptions;
+
+    /// Dials a TCP connection for the session, the address is resolved by
+    /// the DNS client of the session if it has one, as the connections of
+    /// `connect_tcp_outbound` are.
     async fn new_tcp_stream(
         &self,
+        sess: &Session,
         dns_client: SyncDnsClient,
         address: &String,
         port: &u16,
     ) -> io::Result<AnyStream> {
-        new_tcp_stream(dns_client, address, port).await
+        let opts = self.dial_options();
+        new_tcp_stream_with_bind(
+            sess.dns_client.clone().unwrap_or(dns_client),
+            address,
+            port,
+            opts.bind.as_ref(),
+            opts.domain_strategy,
+            opts.tcp_fast_open,
+            opts.connect_timeout,
+        )
+        .await
     }
 }
 
 /// An interface with the ability to create UDP sockets.
 #[async_trait]
 pub trait UdpConnector: Send + Sync + Unpin {
+    /// Returns the options the sockets are created with.
+    fn dial_options(&self) -> &DialOptions;
+
     /// Creates a UDP socket.
     async fn new_udp_socket(&self, indicator: &SocketAddr) -> io::Result<UdpSocket> {
-        new_udp_socket(indicator).await
+        new_udp_socket_with_bind(indicator, self.dial_options().bind.as_ref()).await
     }
 }
 
@@ -507,6 +977,28 @@ pub type AnyStream = Box<dyn ProxyStream>;
 pub trait OutboundHandler:
     TcpOutboundHandler + UdpOutboundHandler + Tag + Color + Send + Unpin
 {
+    /// Returns the local bindings of the connections dialed for the
+    /// handler, if not the ones of `OUTBOUND_INTERFACE`.
+    fn bind(&self) -> Option<&SocketBind> {
+        None
+    }
+
+    /// Returns the strategy the server address and the destinations of the
+    /// handler are resolved with.
+    fn domain_strategy(&self) -> DomainStrategy {
+        DomainStrategy::AsIs
+    }
+
+    /// Whether connections dialed for the handler use TCP Fast Open.
+    fn tcp_fast_open(&self) -> bool {
+        false
+    }
+
+    /// Returns the timeout of the connection attempts dialed for the
+    /// handler, if not `OUTBOUND_DIAL_TIMEOUT`.
+    fn connect_timeout(&self) -> Option<Duration> {
+        None
+    }
 }
 
 pub type AnyOutboundHandler = Arc<
@@ -536,6 +1028,13 @@ pub trait TcpOutboundHandler: Send + Sync + Unpin {
         sess: &'a Session,
         stream: Option<Self::Stream>,
     ) -> io::Result<Self::Stream>;
+
+    /// Returns the actor the handler relays the session through if it's a
+    /// group such as select or failover, for chains to resolve groups to
+    /// the actual hops.
+    async fn group_actor(&self, _sess: &Session) -> Option<AnyOutboundHandler> {
+        None
+    }
 }
 
 type AnyTcpOutboundHandler = Box<dyn TcpOutboundHandler<Stream = AnyStream>>;
@@ -0,0 +1,73 @@
+use std::{io, pin::Pin};
+
+use async_trait::async_trait;
+use futures::stream::Stream;
+use futures::{
+    ready,
+    task::{Context, Poll},
+};
+use tokio::sync::mpsc::Receiver;
+
+use crate::{proxy::*, session::Session};
+
+use super::super::{Connection, MuxStream};
+
+pub struct Incoming {
+    sess: Session,
+    streams: Receiver<MuxStream>,
+}
+
+impl Incoming {
+    pub fn new(sess: Session, conn: AnyStream) -> Self {
+        Incoming {
+            sess,
+            streams: Connection::accept(conn),
+        }
+    }
+}
+
+impl Stream for Incoming {
+    type Item = AnyBaseInboundTransport;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(ready!(self.streams.poll_recv(cx)).map(|stream| {
+            let mut sess = self.sess.clone();
+            sess.stream_id = Some(stream.id().into());
+            AnyBaseInboundTransport::Stream(Box::new(stream), sess)
+        }))
+    }
+}
+
+pub struct Handler {
+    pub actors: Vec<AnyInboundHandler>,
+}
+
+#[async_trait]
+impl TcpInboundHandler for Handler {
+    type TStream = AnyStream;
+    type TDatagram = AnyInboundDatagram;
+
+    async fn handle<'a>(
+        &'a self,
+        mut sess: Session,
+        mut stream: Self::TStream,
+    ) -> std::io::Result<InboundTransport<Self::TStream, Self::TDatagram>> {
+        for a in self.actors.iter() {
+            match TcpInboundHandler::handle(a.as_ref(), sess, stream).await? {
+                InboundTransport::Stream(new_stream, new_sess) => {
+                    stream = new_stream;
+                    sess = new_sess;
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        "invalid mux transport",
+                    ));
+                }
+            }
+        }
+        Ok(InboundTransport::Incoming(Box::new(Incoming::new(
+            sess, stream,
+        ))))
+    }
+}
@@ -0,0 +1,627 @@
+//! A stream multiplexing transport with the frames of smux (version 1), many
+//! proxied streams share a few upstream connections. Unlike amux, streams are
+//! opened and closed explicitly and connections are kept alive with NOP
+//! frames, so it works with smux servers as long as padding is off.
+//!
+//! A frame is |version(1)|cmd(1)|length(2)|stream id(4)|data| with the
+//! integers little-endian. With padding, the first data frames of a
+//! connection are followed by NOP frames carrying random data, which is an
+//! extension the receiving side here skips.
+//!
+//! As in smux version 1, there's no flow control of the streams, a
+//! connection stops reading when the data not yet read by its streams fills
+//! the receive buffer, which holds back all of its streams until the slow
+//! ones are read.
+
+use std::cmp::min;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use futures::future::{abortable, select, AbortHandle, Either};
+use futures::{
+    ready,
+    task::{Context, Poll},
+    Future,
+};
+use log::*;
+use rand::{Rng, RngCore};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::sync::mpsc::{
+    self, error::SendError, OwnedPermit, Receiver, Sender, UnboundedReceiver, UnboundedSender,
+};
+use tokio::sync::Semaphore;
+
+#[cfg(feature = "inbound-mux")]
+pub mod inbound;
+#[cfg(feature = "outbound-mux")]
+pub mod outbound;
+
+const VERSION: u8 = 1;
+const CMD_SYN: u8 = 0;
+const CMD_FIN: u8 = 1;
+const CMD_PSH: u8 = 2;
+const CMD_NOP: u8 = 3;
+const HEADER_LEN: usize = 8;
+// The maximum data length of the frames sent, the default of smux.
+const MAX_FRAME_LEN: usize = 32768;
+// The number of data frames padded at the start of a connection.
+const PADDED_FRAMES: usize = 16;
+const MAX_PADDING_LEN: usize = 256;
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(10);
+// The number of keepalive intervals an outgoing connection lives without
+// streams.
+const IDLE_INTERVALS: usize = 6;
+// The number of bytes received and not yet read by the streams of a
+// connection, the default of smux.
+const RECEIVE_BUFFER: usize = 4 * 1024 * 1024;
+// The number of incoming streams waiting to be accepted.
+const ACCEPT_BACKLOG: usize = 64;
+// The number of frames queued for sending on a connection.
+const FRAME_QUEUE: usize = 8;
+
+fn broken_pipe() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "mux connection closed")
+}
+
+struct Frame {
+    cmd: u8,
+    sid: u32,
+    data: Bytes,
+}
+
+impl Frame {
+    fn new(cmd: u8, sid: u32, data: Bytes) -> Self {
+        Frame { cmd, sid, data }
+    }
+
+    fn encode(&self, buf: &mut BytesMut) {
+        buf.put_u8(VERSION);
+        buf.put_u8(self.cmd);
+        buf.put_u16_le(self.data.len() as u16);
+        buf.put_u32_le(self.sid);
+        buf.put_slice(&self.data);
+    }
+}
+
+async fn read_frame<R>(r: &mut R) -> io::Result<Frame>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut header = [0u8; HEADER_LEN];
+    r.read_exact(&mut header).await?;
+    if header[0] != VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported mux version {}", header[0]),
+        ));
+    }
+    let len = u16::from_le_bytes([header[2], header[3]]) as usize;
+    let sid = u32::from_le_bytes(header[4..].try_into().unwrap());
+    let mut data = vec![0u8; len];
+    r.read_exact(&mut data).await?;
+    Ok(Frame::new(header[1], sid, data.into()))
+}
+
+struct State {
+    // The senders of the data received for the open streams, an empty
+    // buffer marks the end of a stream.
+    streams: Mutex<HashMap<u32, UnboundedSender<Bytes>>>,
+    // The room left in the receive buffer, in bytes, given back as the
+    // streams read.
+    buffer: Semaphore,
+    closed: AtomicBool,
+}
+
+impl State {
+    fn new() -> Self {
+        State {
+            streams: Mutex::new(HashMap::new()),
+            buffer: Semaphore::new(RECEIVE_BUFFER),
+            closed: AtomicBool::new(false),
+        }
+    }
+}
+
+type Reserve =
+    Pin<Box<dyn Future<Output = Result<OwnedPermit<Frame>, SendError<()>>> + Send + Sync>>;
+
+pub struct MuxStream {
+    id: u32,
+    state: Arc<State>,
+    data_rx: UnboundedReceiver<Bytes>,
+    buf: Bytes,
+    eof: bool,
+    frame_tx: Sender<Frame>,
+    reserve: Option<Reserve>,
+    fin_sent: bool,
+}
+
+impl MuxStream {
+    fn new(id: u32, state: Arc<State>, frame_tx: Sender<Frame>) -> Self {
+        trace!("new mux stream {}", id);
+        let (data_tx, data_rx) = mpsc::unbounded_channel();
+        state.streams.lock().unwrap().insert(id, data_tx);
+        MuxStream {
+            id,
+            state,
+            data_rx,
+            buf: Bytes::new(),
+            eof: false,
+            frame_tx,
+            reserve: None,
+            fin_sent: false,
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    // Reserves a place for a frame in the queue of the connection.
+    fn poll_permit(&mut self, cx: &mut Context) -> Poll<io::Result<OwnedPermit<Frame>>> {
+        let frame_tx = &self.frame_tx;
+        let reserve = self
+            .reserve
+            .get_or_insert_with(|| Box::pin(frame_tx.clone().reserve_owned()));
+        let res = ready!(reserve.as_mut().poll(cx));
+        self.reserve = None;
+        Poll::Ready(res.map_err(|_| broken_pipe()))
+    }
+}
+
+impl Drop for MuxStream {
+    fn drop(&mut self) {
+        trace!("drop mux stream {}", self.id);
+        self.state.streams.lock().unwrap().remove(&self.id);
+        // Gives back the room of the data not read.
+        self.data_rx.close();
+        let mut n = self.buf.len();
+        while let Ok(data) = self.data_rx.try_recv() {
+            n += data.len();
+        }
+        self.state.buffer.add_permits(n);
+        if !self.fin_sent {
+            send_frame(&self.frame_tx, Frame::new(CMD_FIN, self.id, Bytes::new()));
+        }
+    }
+}
+
+impl AsyncRead for MuxStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<io::Result<()>> {
+        while self.buf.is_empty() {
+            if self.eof {
+                return Poll::Ready(Ok(()));
+            }
+            match ready!(self.data_rx.poll_recv(cx)) {
+                Some(data) if data.is_empty() => self.eof = true,
+                Some(data) => self.buf = data,
+                // The connection is closed before the stream ends.
+                None => return Poll::Ready(Err(broken_pipe())),
+            }
+        }
+        let n = min(buf.remaining(), self.buf.len());
+        buf.put_slice(&self.buf.split_to(n));
+        self.state.buffer.add_permits(n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for MuxStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.fin_sent {
+            return Poll::Ready(Err(broken_pipe()));
+        }
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        let permit = ready!(self.poll_permit(cx))?;
+        let n = min(buf.len(), MAX_FRAME_LEN);
+        permit.send(Frame::new(
+            CMD_PSH,
+            self.id,
+            Bytes::copy_from_slice(&buf[..n]),
+        ));
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        if self.fin_sent {
+            return Poll::Ready(Ok(()));
+        }
+        let permit = ready!(self.poll_permit(cx))?;
+        permit.send(Frame::new(CMD_FIN, self.id, Bytes::new()));
+        self.fin_sent = true;
+        Poll::Ready(Ok(()))
+    }
+}
+
+struct Accept {
+    stream_tx: Sender<MuxStream>,
+}
+
+// Sends a frame without waiting for the queue.
+fn send_frame(frame_tx: &Sender<Frame>, frame: Frame) {
+    if let Err(mpsc::error::TrySendError::Full(frame)) = frame_tx.try_send(frame) {
+        let frame_tx = frame_tx.clone();
+        tokio::spawn(async move {
+            let _ = frame_tx.send(frame).await;
+        });
+    }
+}
+
+// Waits for the streams to read when the receive buffer is full, never
+// closes a stream for falling behind.
+async fn recv_loop<R>(
+    mut r: R,
+    state: Arc<State>,
+    frame_tx: Sender<Frame>,
+    accept: Option<Accept>,
+) -> io::Result<()>
+where
+    R: AsyncRead + Unpin,
+{
+    loop {
+        let frame = read_frame(&mut r).await?;
+        match frame.cmd {
+            CMD_SYN => {
+                // Only incoming connections accept streams.
+                if let Some(accept) = accept.as_ref() {
+                    if state.streams.lock().unwrap().contains_key(&frame.sid) {
+                        continue;
+                    }
+                    let stream = MuxStream::new(frame.sid, state.clone(), frame_tx.clone());
+                    // The stream is closed on drop if it's not accepted.
+                    if accept.stream_tx.try_send(stream).is_err() {
+                        debug!("mux stream {} not accepted", frame.sid);
+                    }
+                }
+            }
+            CMD_PSH => {
+                let data_tx = state.streams.lock().unwrap().get(&frame.sid).cloned();
+                if let (Some(data_tx), false) = (data_tx, frame.data.is_empty()) {
+                    let n = frame.data.len();
+                    state
+                        .buffer
+                        .acquire_many(n as u32)
+                        .await
+                        .map_err(|_| broken_pipe())?
+                        .forget();
+                    if data_tx.send(frame.data).is_err() {
+                        // The stream is dropped meanwhile.
+                        state.buffer.add_permits(n);
+                    }
+                }
+            }
+            CMD_FIN => {
+                let data_tx = state.streams.lock().unwrap().remove(&frame.sid);
+                if let Some(data_tx) = data_tx {
+                    // Ends the stream after the buffered data.
+                    let _ = data_tx.send(Bytes::new());
+                }
+            }
+            CMD_NOP => (),
+            cmd => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown mux command {}", cmd),
+                ));
+            }
+        }
+    }
+}
+
+async fn send_loop<W>(
+    mut w: W,
+    state: Arc<State>,
+    mut frame_rx: Receiver<Frame>,
+    padding: bool,
+    close_idle: bool,
+) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = BytesMut::new();
+    let mut padded_frames = if padding { PADDED_FRAMES } else { 0 };
+    let mut idle_intervals = 0;
+    loop {
+        let mut next = match tokio::time::timeout(KEEPALIVE_INTERVAL, frame_rx.recv()).await {
+            Ok(Some(frame)) => Some(frame),
+            Ok(None) => break,
+            Err(_) => {
+                if close_idle && state.streams.lock().unwrap().is_empty() {
+                    idle_intervals += 1;
+                    if idle_intervals >= IDLE_INTERVALS {
+                        break;
+                    }
+                } else {
+                    idle_intervals = 0;
+                }
+                Some(Frame::new(CMD_NOP, 0, Bytes::new()))
+            }
+        };
+        // Writes the queued frames at once.
+        while let Some(frame) = next.take() {
+            frame.encode(&mut buf);
+            if frame.cmd == CMD_PSH && padded_frames > 0 {
+                padded_frames -= 1;
+                let mut rng = rand::thread_rng();
+                let mut padding = vec![0u8; rng.gen_range(0..MAX_PADDING_LEN)];
+                rng.fill_bytes(&mut padding);
+                Frame::new(CMD_NOP, 0, padding.into()).encode(&mut buf);
+            }
+            if buf.len() < MAX_FRAME_LEN {
+                next = frame_rx.try_recv().ok();
+            }
+        }
+        w.write_all(&buf).await?;
+        w.flush().await?;
+        buf.clear();
+    }
+    w.shutdown().await
+}
+
+/// A multiplexed connection.
+pub struct Connection {
+    state: Arc<State>,
+    frame_tx: Sender<Frame>,
+    next_id: AtomicU32,
+    handle: AbortHandle,
+}
+
+impl Connection {
+    // Runs the connection until either direction ends.
+    fn run<S>(
+        conn: S,
+        state: Arc<State>,
+        frame_tx: Sender<Frame>,
+        frame_rx: Receiver<Frame>,
+        accept: Option<Accept>,
+        padding: bool,
+    ) -> AbortHandle
+    where
+        S: 'static + AsyncRead + AsyncWrite + Send,
+    {
+        let close_idle = accept.is_none();
+        let (r, w) = tokio::io::split(conn);
+        let task = async move {
+            let recv = Box::pin(recv_loop(r, state.clone(), frame_tx, accept));
+            let send = Box::pin(send_loop(w, state.clone(), frame_rx, padding, close_idle));
+            let res = match select(recv, send).await {
+                Either::Left((res, _)) => res,
+                Either::Right((res, _)) => res,
+            };
+            if let Err(e) = res {
+                debug!("mux connection failed: {}", e);
+            }
+            state.closed.store(true, Ordering::Relaxed);
+            state.streams.lock().unwrap().clear();
+        };
+        let (task, handle) = abortable(task);
+        tokio::spawn(task);
+        handle
+    }
+
+    /// Opens streams on an outgoing connection, which is closed when it has
+    /// no streams for a while.
+    pub fn connect<S>(conn: S, padding: bool) -> Self
+    where
+        S: 'static + AsyncRead + AsyncWrite + Send,
+    {
+        let state = Arc::new(State::new());
+        let (frame_tx, frame_rx) = mpsc::channel(FRAME_QUEUE);
+        let handle = Self::run(
+            conn,
+            state.clone(),
+            frame_tx.clone(),
+            frame_rx,
+            None,
+            padding,
+        );
+        Connection {
+            state,
+            frame_tx,
+            // Clients use odd stream IDs as smux does.
+            next_id: AtomicU32::new(1),
+            handle,
+        }
+    }
+
+    /// Accepts streams on an incoming connection until it's closed.
+    pub fn accept<S>(conn: S) -> Receiver<MuxStream>
+    where
+        S: 'static + AsyncRead + AsyncWrite + Send,
+    {
+        let state = Arc::new(State::new());
+        let (frame_tx, frame_rx) = mpsc::channel(FRAME_QUEUE);
+        let (stream_tx, stream_rx) = mpsc::channel(ACCEPT_BACKLOG);
+        let accept = Accept { stream_tx };
+        Self::run(conn, state, frame_tx, frame_rx, Some(accept), false);
+        stream_rx
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state.closed.load(Ordering::Relaxed)
+    }
+
+    pub fn num_streams(&self) -> usize {
+        self.state.streams.lock().unwrap().len()
+    }
+
+    pub async fn open_stream(&self) -> io::Result<MuxStream> {
+        let id = self.next_id.fetch_add(2, Ordering::Relaxed);
+        let stream = MuxStream::new(id, self.state.clone(), self.frame_tx.clone());
+        self.frame_tx
+            .send(Frame::new(CMD_SYN, id, Bytes::new()))
+            .await
+            .map_err(|_| broken_pipe())?;
+        Ok(stream)
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::DuplexStream;
+
+    use super::*;
+
+    fn echo(mut streams: Receiver<MuxStream>) {
+        tokio::spawn(async move {
+            while let Some(stream) = streams.recv().await {
+                tokio::spawn(async move {
+                    let (mut r, mut w) = tokio::io::split(stream);
+                    let _ = tokio::io::copy(&mut r, &mut w).await;
+                    let _ = w.shutdown().await;
+                });
+            }
+        });
+    }
+
+    fn pair(padding: bool) -> (Connection, DuplexStream) {
+        let (client_io, server_io) = tokio::io::duplex(1024);
+        (Connection::connect(client_io, padding), server_io)
+    }
+
+    #[test]
+    fn test_streams() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            for padding in [false, true] {
+                let (conn, server_io) = pair(padding);
+                echo(Connection::accept(server_io));
+                let mut tasks = Vec::new();
+                for i in 0..4u8 {
+                    let mut stream = conn.open_stream().await.unwrap();
+                    assert_eq!(stream.id(), 2 * i as u32 + 1);
+                    tasks.push(tokio::spawn(async move {
+                        let data = vec![i; 100000];
+                        stream.write_all(&data).await.unwrap();
+                        stream.shutdown().await.unwrap();
+                        let mut buf = Vec::new();
+                        stream.read_to_end(&mut buf).await.unwrap();
+                        assert_eq!(buf, data);
+                    }));
+                }
+                for task in tasks {
+                    task.await.unwrap();
+                }
+                assert_eq!(conn.num_streams(), 0);
+                assert!(!conn.is_closed());
+            }
+        });
+    }
+
+    #[test]
+    fn test_frames() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let (conn, mut server_io) = pair(true);
+            let mut stream = conn.open_stream().await.unwrap();
+            stream.write_all(b"hello").await.unwrap();
+            let frame = read_frame(&mut server_io).await.unwrap();
+            assert_eq!((frame.cmd, frame.sid), (CMD_SYN, 1));
+            let frame = read_frame(&mut server_io).await.unwrap();
+            assert_eq!(
+                (frame.cmd, frame.sid, &frame.data[..]),
+                (CMD_PSH, 1, &b"hello"[..])
+            );
+            let frame = read_frame(&mut server_io).await.unwrap();
+            assert!(frame.cmd == CMD_NOP && frame.data.len() < MAX_PADDING_LEN);
+
+            // Frames of unknown streams and padding are skipped.
+            let mut buf = BytesMut::new();
+            Frame::new(CMD_PSH, 3, Bytes::from_static(b"lost")).encode(&mut buf);
+            Frame::new(CMD_NOP, 0, Bytes::from_static(b"padding")).encode(&mut buf);
+            Frame::new(CMD_PSH, 1, Bytes::from_static(b"world")).encode(&mut buf);
+            Frame::new(CMD_FIN, 1, Bytes::new()).encode(&mut buf);
+            server_io.write_all(&buf).await.unwrap();
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            assert_eq!(buf, b"world");
+
+            drop(stream);
+            let frame = read_frame(&mut server_io).await.unwrap();
+            assert_eq!((frame.cmd, frame.sid), (CMD_FIN, 1));
+
+            // The connection fails on an unknown command.
+            let mut stream = conn.open_stream().await.unwrap();
+            let mut buf = BytesMut::new();
+            Frame::new(9, 0, Bytes::new()).encode(&mut buf);
+            server_io.write_all(&buf).await.unwrap();
+            assert!(stream.read(&mut [0u8; 1]).await.is_err());
+            assert!(conn.is_closed());
+        });
+    }
+
+    #[test]
+    fn test_backpressure() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .start_paused(true)
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let (conn, mut server_io) = pair(false);
+            let mut stream1 = conn.open_stream().await.unwrap();
+            let mut stream3 = conn.open_stream().await.unwrap();
+
+            // More data than the receive buffer for a stream not read.
+            let frames = RECEIVE_BUFFER / MAX_FRAME_LEN + 2;
+            let writer = tokio::spawn(async move {
+                let mut buf = BytesMut::new();
+                for _ in 0..frames {
+                    Frame::new(CMD_PSH, 1, vec![1u8; MAX_FRAME_LEN].into()).encode(&mut buf);
+                }
+                Frame::new(CMD_FIN, 1, Bytes::new()).encode(&mut buf);
+                Frame::new(CMD_PSH, 3, Bytes::from_static(b"hello")).encode(&mut buf);
+                Frame::new(CMD_FIN, 3, Bytes::new()).encode(&mut buf);
+                server_io.write_all(&buf).await.unwrap();
+                server_io
+            });
+
+            // The connection waits for the stream to be read, instead of
+            // closing it.
+            let mut buf = Vec::new();
+            let read = tokio::time::timeout(Duration::from_secs(1), stream3.read_to_end(&mut buf));
+            assert!(read.await.is_err());
+            let mut buf = Vec::new();
+            stream1.read_to_end(&mut buf).await.unwrap();
+            assert_eq!(buf.len(), frames * MAX_FRAME_LEN);
+            let mut buf = Vec::new();
+            stream3.read_to_end(&mut buf).await.unwrap();
+            assert_eq!(buf, b"hello");
+            let _server_io = writer.await.unwrap();
+            assert!(!conn.is_closed());
+            assert_eq!(conn.state.buffer.available_permits(), RECEIVE_BUFFER);
+        });
+    }
+}
@@ -0,0 +1,226 @@
+use std::convert::TryFrom;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::{
+    app::SyncDnsClient,
+    proxy::*,
+    session::{Session, SocksAddr},
+};
+
+use super::super::Connection;
+
+#[derive(Default)]
+struct Pool {
+    connections: Vec<Arc<Connection>>,
+    // The number of connections being established.
+    connecting: usize,
+}
+
+// A connection being established, it's no longer counted as connecting when
+// dropped, e.g. on failure or when the stream is cancelled.
+struct Connecting<'a>(&'a Mutex<Pool>);
+
+impl Connecting<'_> {
+    // Adds the established connection to the pool.
+    fn finish(self, conn: Arc<Connection>) {
+        let mut pool = self.0.lock().unwrap();
+        pool.connecting -= 1;
+        pool.connections.push(conn);
+        drop(pool);
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for Connecting<'_> {
+    fn drop(&mut self) {
+        self.0.lock().unwrap().connecting -= 1;
+    }
+}
+
+pub struct Handler {
+    pub address: String,
+    pub port: u16,
+    pub actors: Vec<AnyOutboundHandler>,
+    // The number of streams of a connection before opening another, 0 for
+    // no limit.
+    pub max_streams: usize,
+    // Streams open new connections until there are this many.
+    pub min_connections: usize,
+    // Streams share the least busy connection beyond this many, 0 for no
+    // limit.
+    pub max_connections: usize,
+    pub padding: bool,
+    pub dns_client: SyncDnsClient,
+    pub dial_options: DialOptions,
+    pool: Mutex<Pool>,
+}
+
+impl Handler {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        address: String,
+        port: u16,
+        actors: Vec<AnyOutboundHandler>,
+        max_streams: usize,
+        min_connections: usize,
+        max_connections: usize,
+        padding: bool,
+        dns_client: SyncDnsClient,
+        dial_options: DialOptions,
+    ) -> Self {
+        Handler {
+            address,
+            port,
+            actors,
+            max_streams,
+            min_connections,
+            max_connections,
+            padding,
+            dns_client,
+            dial_options,
+            pool: Mutex::new(Pool::default()),
+        }
+    }
+
+    // Picks a connection for a new stream, or None if a new connection
+    // should be opened, which is counted as connecting.
+    fn pick(&self) -> Option<Arc<Connection>> {
+        let mut pool = self.pool.lock().unwrap();
+        pool.connections.retain(|c| !c.is_closed());
+        let total = pool.connections.len() + pool.connecting;
+        if total >= self.min_connections {
+            if let Some(conn) = pool.connections.iter().min_by_key(|c| c.num_streams()) {
+                if (self.max_streams == 0 || conn.num_streams() < self.max_streams)
+                    || (self.max_connections != 0 && total >= self.max_connections)
+                {
+                    return Some(conn.clone());
+                }
+            }
+        }
+        pool.connecting += 1;
+        None
+    }
+
+    async fn connect(&self, sess: &Session) -> io::Result<AnyStream> {
+        let mut conn = self
+            .new_tcp_stream(sess, self.dns_client.clone(), &self.address, &self.port)
+            .await?;
+        let mut sess = sess.clone();
+        if let Ok(addr) = SocksAddr::try_from((&self.address, self.port)) {
+            sess.destination = addr;
+        }
+        for a in self.actors.iter() {
+            conn = TcpOutboundHandler::handle(a.as_ref(), &sess, Some(conn)).await?;
+        }
+        Ok(conn)
+    }
+}
+
+impl TcpConnector for Handler {
+    fn dial_options(&self) -> &DialOptions {
+        &self.dial_options
+    }
+}
+
+#[async_trait]
+impl TcpOutboundHandler for Handler {
+    type Stream = AnyStream;
+
+    fn connect_addr(&self) -> Option<OutboundConnect> {
+        Some(OutboundConnect::NoConnect)
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        _stream: Option<Self::Stream>,
+    ) -> io::Result<Self::Stream> {
+        let conn = match self.pick() {
+            Some(conn) => conn,
+            None => {
+                let connecting = Connecting(&self.pool);
+                let conn = Arc::new(Connection::connect(self.connect(sess).await?, self.padding));
+                connecting.finish(conn.clone());
+                conn
+            }
+        };
+        Ok(Box::new(conn.open_stream().await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::RwLock;
+
+    use crate::app::dns_client::DnsClient;
+
+    use super::*;
+
+    #[test]
+    fn test_pick() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let mut dns = crate::config::Dns::new();
+            dns.servers.push("127.0.0.1".to_string());
+            let dns_client = Arc::new(RwLock::new(DnsClient::new(&Some(dns).into()).unwrap()));
+            let handler = Handler::new(
+                "127.0.0.1".to_string(),
+                1,
+                Vec::new(),
+                2,
+                2,
+                3,
+                false,
+                dns_client,
+                DialOptions::default(),
+            );
+            let mut peers = Vec::new();
+            let mut add = || {
+                let (client_io, server_io) = tokio::io::duplex(1024);
+                peers.push(Connection::accept(server_io));
+                let conn = Arc::new(Connection::connect(client_io, false));
+                let mut pool = handler.pool.lock().unwrap();
+                pool.connecting -= 1;
+                pool.connections.push(conn.clone());
+                conn
+            };
+
+            // Connections are opened up to the minimum.
+            assert!(handler.pick().is_none());
+            let conn1 = add();
+            assert!(handler.pick().is_none());
+            let conn2 = add();
+            let mut streams = Vec::new();
+            for _ in 0..4 {
+                let conn = handler.pick().unwrap();
+                streams.push(conn.open_stream().await.unwrap());
+            }
+            assert_eq!((conn1.num_streams(), conn2.num_streams()), (2, 2));
+
+            // And up to the maximum when they're busy.
+            assert!(handler.pick().is_none());
+            let conn3 = add();
+            streams.push(handler.pick().unwrap().open_stream().await.unwrap());
+            streams.push(handler.pick().unwrap().open_stream().await.unwrap());
+            assert_eq!(conn3.num_streams(), 2);
+            let conn = handler.pick().unwrap();
+            assert_eq!(conn.num_streams(), 2);
+
+            drop(streams.remove(0));
+            assert!(Arc::ptr_eq(&handler.pick().unwrap(), &conn1));
+
+            // A connection is no longer counted when connecting is given up.
+            let mut handler = handler;
+            handler.min_connections = 4;
+            assert!(handler.pick().is_none());
+            drop(Connecting(&handler.pool));
+            assert_eq!(handler.pool.lock().unwrap().connecting, 0);
+        });
+    }
+}
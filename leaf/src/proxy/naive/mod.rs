@@ -0,0 +1,7 @@
+//! NaiveProxy, connections are carried in HTTP/2 CONNECT streams of a single
+//! TLS connection shared by all sessions. The first frames of each stream
+//! are padded to disguise the lengths of the proxied handshakes.
+//!
+//! The TLS fingerprint is the one of the TLS outbound, not Chrome's.
+
+pub mod outbound;
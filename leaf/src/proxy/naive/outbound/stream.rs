@@ -0,0 +1,243 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures::ready;
+use h2::{RecvStream, SendStream};
+use rand::Rng;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::common::h2::h2_err;
+
+// Only the first frames in each direction are padded.
+const FIRST_PADDINGS: usize = 8;
+const MAX_PADDING_SIZE: usize = 255;
+
+enum ReadState {
+    Header([u8; 3], usize),
+    // The payload and padding sizes left.
+    Payload(usize, usize),
+    Padding(usize),
+}
+
+/// An HTTP/2 CONNECT stream. Padded frames are a 2-byte payload size, a
+/// 1-byte padding size, the payload and the padding of zeros.
+pub struct Stream {
+    send: SendStream<Bytes>,
+    recv: RecvStream,
+    recv_buf: Bytes,
+    read_state: ReadState,
+    read_paddings: usize,
+    write_buf: BytesMut,
+    written: usize,
+    write_paddings: usize,
+}
+
+impl Stream {
+    pub fn new(send: SendStream<Bytes>, recv: RecvStream, padding: bool) -> Self {
+        let paddings = if padding { 0 } else { FIRST_PADDINGS };
+        Stream {
+            send,
+            recv,
+            recv_buf: Bytes::new(),
+            read_state: ReadState::Header([0u8; 3], 0),
+            read_paddings: paddings,
+            write_buf: BytesMut::new(),
+            written: 0,
+            write_paddings: paddings,
+        }
+    }
+
+    fn encode_padded(&mut self, payload: &[u8]) {
+        let padding = rand::thread_rng().gen_range(0..=MAX_PADDING_SIZE);
+        self.write_buf.put_u16(payload.len() as u16);
+        self.write_buf.put_u8(padding as u8);
+        self.write_buf.put_slice(payload);
+        self.write_buf.put_bytes(0, padding);
+        self.written = payload.len();
+    }
+
+    fn poll_send(&mut self, cx: &mut Context<'_>, data: &[u8]) -> Poll<io::Result<usize>> {
+        self.send.reserve_capacity(data.len());
+        match ready!(self.send.poll_capacity(cx)) {
+            Some(Ok(n)) => {
+                let n = n.min(data.len());
+                self.send
+                    .send_data(Bytes::copy_from_slice(&data[..n]), false)
+                    .map_err(h2_err)?;
+                Poll::Ready(Ok(n))
+            }
+            Some(Err(e)) => Poll::Ready(Err(h2_err(e))),
+            None => Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+        }
+    }
+}
+
+impl AsyncRead for Stream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let me = &mut *self;
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        loop {
+            match me.read_state {
+                ReadState::Payload(0, padding) => {
+                    me.read_state = ReadState::Padding(padding);
+                    continue;
+                }
+                ReadState::Padding(0) => {
+                    me.read_paddings += 1;
+                    me.read_state = ReadState::Header([0u8; 3], 0);
+                    continue;
+                }
+                _ => (),
+            }
+            if me.recv_buf.is_empty() {
+                match ready!(me.recv.poll_data(cx)) {
+                    Some(Ok(data)) => {
+                        let _ = me.recv.flow_control().release_capacity(data.len());
+                        me.recv_buf = data;
+                        continue;
+                    }
+                    Some(Err(e)) => return Poll::Ready(Err(h2_err(e))),
+                    None => return Poll::Ready(Ok(())),
+                }
+            }
+            if me.read_paddings >= FIRST_PADDINGS {
+                let n = me.recv_buf.len().min(buf.remaining());
+                buf.put_slice(&me.recv_buf[..n]);
+                me.recv_buf.advance(n);
+                return Poll::Ready(Ok(()));
+            }
+            match &mut me.read_state {
+                ReadState::Header(header, filled) => {
+                    let n = (header.len() - *filled).min(me.recv_buf.len());
+                    header[*filled..*filled + n].copy_from_slice(&me.recv_buf[..n]);
+                    me.recv_buf.advance(n);
+                    *filled += n;
+                    if *filled == header.len() {
+                        let payload = u16::from_be_bytes([header[0], header[1]]) as usize;
+                        me.read_state = ReadState::Payload(payload, header[2] as usize);
+                    }
+                }
+                ReadState::Payload(remaining, _) => {
+                    let n = (*remaining).min(me.recv_buf.len()).min(buf.remaining());
+                    buf.put_slice(&me.recv_buf[..n]);
+                    me.recv_buf.advance(n);
+                    *remaining -= n;
+                    return Poll::Ready(Ok(()));
+                }
+                ReadState::Padding(remaining) => {
+                    let n = (*remaining).min(me.recv_buf.len());
+                    me.recv_buf.advance(n);
+                    *remaining -= n;
+                }
+            }
+        }
+    }
+}
+
+impl AsyncWrite for Stream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        if me.write_buf.is_empty() {
+            if buf.is_empty() {
+                return Poll::Ready(Ok(0));
+            }
+            if me.write_paddings >= FIRST_PADDINGS {
+                return me.poll_send(cx, buf);
+            }
+            me.encode_padded(&buf[..buf.len().min(u16::MAX as usize)]);
+            me.write_paddings += 1;
+        }
+        while !me.write_buf.is_empty() {
+            let data = std::mem::take(&mut me.write_buf);
+            let res = me.poll_send(cx, &data);
+            me.write_buf = data;
+            let n = ready!(res)?;
+            me.write_buf.advance(n);
+        }
+        Poll::Ready(Ok(me.written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.send.send_data(Bytes::new(), true).map_err(h2_err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::http::{Method, Request, Response};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[test]
+    fn test_padded_stream() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let (client_io, server_io) = tokio::io::duplex(65536);
+            tokio::spawn(async move {
+                let mut conn = h2::server::handshake(server_io).await.unwrap();
+                let (req, mut respond) = conn.accept().await.unwrap().unwrap();
+                assert_eq!(req.method(), Method::CONNECT);
+                tokio::spawn(async move { while conn.accept().await.is_some() {} });
+                let resp = Response::builder()
+                    .header("padding", "!!")
+                    .body(())
+                    .unwrap();
+                let send = respond.send_response(resp, false).unwrap();
+                let mut stream = Stream::new(send, req.into_body(), true);
+                let mut buf = [0u8; 1024];
+                loop {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    stream.write_all(&buf[..n]).await.unwrap();
+                }
+                stream.shutdown().await.unwrap();
+            });
+
+            let (send_request, conn) = h2::client::handshake(client_io).await.unwrap();
+            tokio::spawn(conn);
+            let mut send_request = send_request.ready().await.unwrap();
+            let req = Request::builder()
+                .method(Method::CONNECT)
+                .uri("example.com:443")
+                .body(())
+                .unwrap();
+            let (response, send) = send_request.send_request(req, false).unwrap();
+            let response = response.await.unwrap();
+            assert!(response.headers().contains_key("padding"));
+            let mut stream = Stream::new(send, response.into_body(), true);
+            // More messages than the padded frames.
+            for i in 0..FIRST_PADDINGS * 2 {
+                let msg = format!("message {}", i);
+                stream.write_all(msg.as_bytes()).await.unwrap();
+                let mut buf = vec![0u8; msg.len()];
+                stream.read_exact(&mut buf).await.unwrap();
+                assert_eq!(buf, msg.as_bytes());
+            }
+            stream.shutdown().await.unwrap();
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            assert!(buf.is_empty());
+        });
+    }
+}
@@ -0,0 +1,160 @@
+use std::io;
+use std::time::Duration;
+
+use ::http::{Method, Request, StatusCode};
+use anyhow::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+use h2::client::SendRequest;
+use rand::Rng;
+
+use crate::{
+    app::SyncDnsClient,
+    common::h2::{h2_err, spawn_connection, SharedConnection},
+    proxy::*,
+    session::Session,
+};
+
+use super::stream::Stream;
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Characters HPACK can't compress with Huffman coding, so the header is sent
+// with the length it has.
+const PADDING_CHARS: &[u8] = b"!#$()+<>?@[]^`{}";
+
+// The HTTP/2 settings of Chrome.
+const HEADER_TABLE_SIZE: u32 = 65536;
+const MAX_CONCURRENT_STREAMS: u32 = 1000;
+const INITIAL_WINDOW_SIZE: u32 = 6291456;
+const INITIAL_CONNECTION_WINDOW_SIZE: u32 = 15728640;
+const MAX_HEADER_LIST_SIZE: u32 = 262144;
+
+fn padding_header() -> String {
+    let mut rng = rand::thread_rng();
+    let len = rng.gen_range(16..=32);
+    (0..len)
+        .map(|_| PADDING_CHARS[rng.gen_range(0..PADDING_CHARS.len())] as char)
+        .collect()
+}
+
+pub struct Handler {
+    address: String,
+    port: u16,
+    authorization: Option<String>,
+    tls: tls::outbound::TcpHandler,
+    dns_client: SyncDnsClient,
+    dial_options: DialOptions,
+    connection: SharedConnection,
+}
+
+impl Handler {
+    pub fn new(
+        address: String,
+        port: u16,
+        username: String,
+        password: String,
+        dns_client: SyncDnsClient,
+        dial_options: DialOptions,
+    ) -> Result<Self> {
+        let authorization = if !username.is_empty() {
+            Some(format!(
+                "Basic {}",
+                base64::encode(format!("{}:{}", username, password))
+            ))
+        } else {
+            None
+        };
+        let tls =
+            tls::outbound::TcpHandler::new(address.clone(), vec!["h2".to_string()], None, None)?;
+        Ok(Handler {
+            address,
+            port,
+            authorization,
+            tls,
+            dns_client,
+            dial_options,
+            connection: SharedConnection::default(),
+        })
+    }
+
+    async fn connect(&self, sess: &Session) -> io::Result<SendRequest<Bytes>> {
+        let stream = self
+            .new_tcp_stream(sess, self.dns_client.clone(), &self.address, &self.port)
+            .await?;
+        let handshake = async {
+            let stream = TcpOutboundHandler::handle(&self.tls, sess, Some(stream)).await?;
+            h2::client::Builder::new()
+                .header_table_size(HEADER_TABLE_SIZE)
+                .max_concurrent_streams(MAX_CONCURRENT_STREAMS)
+                .initial_window_size(INITIAL_WINDOW_SIZE)
+                .initial_connection_window_size(INITIAL_CONNECTION_WINDOW_SIZE)
+                .max_header_list_size(MAX_HEADER_LIST_SIZE)
+                .handshake(stream)
+                .await
+                .map_err(h2_err)
+        };
+        let (send_request, connection) = tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "naive handshake timed out"))??;
+        log::debug!("naive connected to {}:{}", &self.address, self.port);
+        spawn_connection(connection, "naive", self.address.clone());
+        Ok(send_request)
+    }
+
+    async fn send_request(&self, sess: &Session) -> io::Result<SendRequest<Bytes>> {
+        self.connection.get(|| self.connect(sess)).await
+    }
+}
+
+impl TcpConnector for Handler {
+    fn dial_options(&self) -> &DialOptions {
+        &self.dial_options
+    }
+}
+
+#[async_trait]
+impl TcpOutboundHandler for Handler {
+    type Stream = AnyStream;
+
+    fn connect_addr(&self) -> Option<OutboundConnect> {
+        Some(OutboundConnect::NoConnect)
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        _stream: Option<Self::Stream>,
+    ) -> io::Result<Self::Stream> {
+        // All sessions share a single connection, a new one is made when it's
+        // closed.
+        let mut send_request = self.send_request(sess).await?;
+        let mut req = Request::builder()
+            .method(Method::CONNECT)
+            .uri(sess.destination.to_string())
+            .header("padding", padding_header());
+        if let Some(authorization) = &self.authorization {
+            req = req.header("proxy-authorization", authorization);
+        }
+        let req = req
+            .body(())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let (response, send) = match send_request.send_request(req, false) {
+            Ok(v) => v,
+            Err(e) => {
+                // E.g. the server has sent a GOAWAY.
+                self.connection.reset().await;
+                return Err(h2_err(e));
+            }
+        };
+        let response = response.await.map_err(h2_err)?;
+        if response.status() != StatusCode::OK {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("naive connect failed: {}", response.status()),
+            ));
+        }
+        let padding = response.headers().contains_key("padding");
+        Ok(Box::new(Stream::new(send, response.into_body(), padding)))
+    }
+}
@@ -0,0 +1,186 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{Buf, BufMut, BytesMut};
+use futures::ready;
+use rand::{Rng, RngCore};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+const MAX_RESPONSE_HEADER_SIZE: usize = 8192;
+
+/// Disguises the stream as a WebSocket upgrade the way simple-obfs does, the
+/// first write is sent as the body of the upgrade request, the response
+/// header is stripped before any data.
+pub struct Stream<T> {
+    inner: T,
+    host: String,
+    path: String,
+    request_sent: bool,
+    write_buf: BytesMut,
+    written: usize,
+    // Some until the whole response header has been received.
+    response: Option<BytesMut>,
+    read_buf: BytesMut,
+}
+
+impl<T> Stream<T> {
+    pub fn new(inner: T, host: String, path: String) -> Self {
+        Stream {
+            inner,
+            host,
+            path,
+            request_sent: false,
+            write_buf: BytesMut::new(),
+            written: 0,
+            response: Some(BytesMut::new()),
+            read_buf: BytesMut::new(),
+        }
+    }
+
+    fn encode_request(&mut self, payload: &[u8]) {
+        let mut rng = rand::thread_rng();
+        let mut key = [0u8; 16];
+        rng.fill_bytes(&mut key);
+        let header = format!(
+            "GET {} HTTP/1.1\r\n\
+             Host: {}\r\n\
+             User-Agent: curl/7.{}.{}\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Key: {}\r\n\
+             Content-Length: {}\r\n\r\n",
+            &self.path,
+            &self.host,
+            rng.gen_range(0..52),
+            rng.gen_range(0..3),
+            base64::encode(key),
+            payload.len(),
+        );
+        self.write_buf.put_slice(header.as_bytes());
+        self.write_buf.put_slice(payload);
+        self.written = payload.len();
+    }
+}
+
+fn find_header_end(buf: &[u8]) -> Option<usize> {
+    buf.windows(4).position(|w| w == b"\r\n\r\n").map(|p| p + 4)
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Stream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let me = &mut *self;
+        loop {
+            let response = match me.response.as_mut() {
+                Some(r) => r,
+                None => {
+                    if !me.read_buf.is_empty() {
+                        let n = me.read_buf.len().min(buf.remaining());
+                        buf.put_slice(&me.read_buf[..n]);
+                        me.read_buf.advance(n);
+                        return Poll::Ready(Ok(()));
+                    }
+                    return Pin::new(&mut me.inner).poll_read(cx, buf);
+                }
+            };
+            let mut tmp = [0u8; 1024];
+            let mut tmp_buf = ReadBuf::new(&mut tmp);
+            ready!(Pin::new(&mut me.inner).poll_read(cx, &mut tmp_buf))?;
+            if tmp_buf.filled().is_empty() {
+                return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+            }
+            response.put_slice(tmp_buf.filled());
+            if let Some(end) = find_header_end(response) {
+                if !response.starts_with(b"HTTP/1.1 101") {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "unexpected obfs http response",
+                    )));
+                }
+                me.read_buf = response.split_off(end);
+                me.response = None;
+            } else if response.len() > MAX_RESPONSE_HEADER_SIZE {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "obfs http response header too large",
+                )));
+            }
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Stream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        if !me.request_sent {
+            if buf.is_empty() {
+                return Poll::Ready(Ok(0));
+            }
+            me.encode_request(buf);
+            me.request_sent = true;
+        }
+        if !me.write_buf.is_empty() {
+            while !me.write_buf.is_empty() {
+                let n = ready!(Pin::new(&mut me.inner).poll_write(cx, &me.write_buf))?;
+                if n == 0 {
+                    return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+                }
+                me.write_buf.advance(n);
+            }
+            return Poll::Ready(Ok(me.written));
+        }
+        Pin::new(&mut me.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[test]
+    fn test_http_obfs() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let (client, mut server) = tokio::io::duplex(4096);
+            let mut stream = Stream::new(client, "www.bing.com".to_string(), "/".to_string());
+            stream.write_all(b"hello").await.unwrap();
+            stream.write_all(b" again").await.unwrap();
+
+            let mut req = vec![0u8; 4096];
+            let n = server.read(&mut req).await.unwrap();
+            let req = String::from_utf8_lossy(&req[..n]).to_string();
+            assert!(req.starts_with("GET / HTTP/1.1\r\nHost: www.bing.com\r\n"));
+            assert!(req.contains("Content-Length: 5\r\n\r\nhello again"));
+
+            server
+                .write_all(b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\r\nwor")
+                .await
+                .unwrap();
+            server.write_all(b"ld").await.unwrap();
+            drop(server);
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"world");
+        });
+    }
+}
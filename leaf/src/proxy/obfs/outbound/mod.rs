@@ -0,0 +1,7 @@
+mod http;
+pub mod tcp;
+mod tls;
+pub mod udp;
+
+pub use tcp::Handler as TcpHandler;
+pub use udp::Handler as UdpHandler;
@@ -0,0 +1,43 @@
+use std::io;
+
+use async_trait::async_trait;
+
+use crate::{proxy::*, session::Session};
+
+use super::{http, tls};
+
+pub struct Handler {
+    pub method: String,
+    pub host: String,
+    pub path: String,
+}
+
+#[async_trait]
+impl TcpOutboundHandler for Handler {
+    type Stream = AnyStream;
+
+    fn connect_addr(&self) -> Option<OutboundConnect> {
+        None
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        stream: Option<Self::Stream>,
+    ) -> io::Result<Self::Stream> {
+        let stream = stream.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "invalid input"))?;
+        let host = if !self.host.is_empty() {
+            self.host.clone()
+        } else {
+            sess.destination.host()
+        };
+        match self.method.as_str() {
+            "http" => Ok(Box::new(http::Stream::new(stream, host, self.path.clone()))),
+            "tls" => Ok(Box::new(tls::Stream::new(stream, host))),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("unknown obfs method {}", &self.method),
+            )),
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::{Buf, BufMut, BytesMut};
+use futures::ready;
+use rand::RngCore;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+const CHANGE_CIPHER_SPEC: u8 = 0x14;
+const HANDSHAKE: u8 = 0x16;
+const APPLICATION_DATA: u8 = 0x17;
+
+const MAX_RECORD_SIZE: usize = 16384;
+
+// The cipher suites and extensions of the ClientHello simple-obfs sends.
+const CIPHER_SUITES: &[u8] = &[
+    0xc0, 0x2c, 0xc0, 0x30, 0x00, 0x9f, 0xcc, 0xa9, 0xcc, 0xa8, 0xcc, 0xaa, 0xc0, 0x2b, 0xc0, 0x2f,
+    0x00, 0x9e, 0xc0, 0x24, 0xc0, 0x28, 0x00, 0x6b, 0xc0, 0x23, 0xc0, 0x27, 0x00, 0x67, 0xc0, 0x0a,
+    0xc0, 0x14, 0x00, 0x39, 0xc0, 0x09, 0xc0, 0x13, 0x00, 0x33, 0x00, 0x9d, 0x00, 0x9c, 0x00, 0x3d,
+    0x00, 0x3c, 0x00, 0x35, 0x00, 0x2f, 0x00, 0xff,
+];
+const OTHER_EXTENSIONS: &[u8] = &[
+    // ec_point_formats
+    0x00, 0x0b, 0x00, 0x04, 0x03, 0x00, 0x01, 0x02, //
+    // supported_groups
+    0x00, 0x0a, 0x00, 0x0a, 0x00, 0x08, 0x00, 0x1d, 0x00, 0x17, 0x00, 0x19, 0x00, 0x18, //
+    // signature_algorithms
+    0x00, 0x0d, 0x00, 0x20, 0x00, 0x1e, 0x06, 0x01, 0x06, 0x02, 0x06, 0x03, 0x05, 0x01, 0x05, 0x02,
+    0x05, 0x03, 0x04, 0x01, 0x04, 0x02, 0x04, 0x03, 0x03, 0x01, 0x03, 0x02, 0x03, 0x03, 0x02, 0x01,
+    0x02, 0x02, 0x02, 0x03, //
+    // encrypt_then_mac
+    0x00, 0x16, 0x00, 0x00, //
+    // extended_master_secret
+    0x00, 0x17, 0x00, 0x00,
+];
+
+enum ReadState {
+    Header([u8; 5], usize),
+    Skip(usize),
+    Data(usize),
+}
+
+/// Disguises the stream as a TLS 1.2 session the way simple-obfs does. The
+/// first write is sent in the session ticket of a ClientHello, the rest as
+/// application data records. The server hides its first data in the
+/// handshake record following its ServerHello and ChangeCipherSpec.
+pub struct Stream<T> {
+    inner: T,
+    host: String,
+    hello_sent: bool,
+    hello_received: bool,
+    write_buf: BytesMut,
+    written: usize,
+    read_state: ReadState,
+}
+
+impl<T> Stream<T> {
+    pub fn new(inner: T, host: String) -> Self {
+        Stream {
+            inner,
+            host,
+            hello_sent: false,
+            hello_received: false,
+            write_buf: BytesMut::new(),
+            written: 0,
+            read_state: ReadState::Header([0u8; 5], 0),
+        }
+    }
+
+    fn encode_client_hello(&mut self, payload: &[u8]) {
+        let host = self.host.as_bytes();
+        let ext_len = 4 + payload.len() + 9 + host.len() + OTHER_EXTENSIONS.len();
+        let hello_len = 2 + 32 + 1 + 32 + 2 + CIPHER_SUITES.len() + 2 + 2 + ext_len;
+        let buf = &mut self.write_buf;
+        buf.put_u8(HANDSHAKE);
+        buf.put_u16(0x0301);
+        buf.put_u16((4 + hello_len) as u16);
+        // ClientHello
+        buf.put_u8(0x01);
+        buf.put_u8(0x00);
+        buf.put_u16(hello_len as u16);
+        buf.put_u16(0x0303);
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        buf.put_u32(time as u32);
+        let mut random = [0u8; 28 + 32];
+        rand::thread_rng().fill_bytes(&mut random);
+        buf.put_slice(&random[..28]);
+        // session id
+        buf.put_u8(32);
+        buf.put_slice(&random[28..]);
+        buf.put_u16(CIPHER_SUITES.len() as u16);
+        buf.put_slice(CIPHER_SUITES);
+        // null compression
+        buf.put_u8(0x01);
+        buf.put_u8(0x00);
+        buf.put_u16(ext_len as u16);
+        // session_ticket
+        buf.put_u16(0x0023);
+        buf.put_u16(payload.len() as u16);
+        buf.put_slice(payload);
+        // server_name
+        buf.put_u16(0x0000);
+        buf.put_u16((host.len() + 5) as u16);
+        buf.put_u16((host.len() + 3) as u16);
+        buf.put_u8(0x00);
+        buf.put_u16(host.len() as u16);
+        buf.put_slice(host);
+        buf.put_slice(OTHER_EXTENSIONS);
+        self.written = payload.len();
+    }
+
+    fn encode_application_data(&mut self, payload: &[u8]) {
+        self.write_buf.put_u8(APPLICATION_DATA);
+        self.write_buf.put_u16(0x0303);
+        self.write_buf.put_u16(payload.len() as u16);
+        self.write_buf.put_slice(payload);
+        self.written = payload.len();
+    }
+}
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Stream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let me = &mut *self;
+        loop {
+            match &mut me.read_state {
+                ReadState::Header(header, filled) => {
+                    let mut header_buf = ReadBuf::new(&mut header[*filled..]);
+                    ready!(Pin::new(&mut me.inner).poll_read(cx, &mut header_buf))?;
+                    let n = header_buf.filled().len();
+                    if n == 0 {
+                        if *filled == 0 {
+                            return Poll::Ready(Ok(()));
+                        }
+                        return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+                    }
+                    *filled += n;
+                    if *filled < header.len() {
+                        continue;
+                    }
+                    let len = u16::from_be_bytes([header[3], header[4]]) as usize;
+                    let state = match header[0] {
+                        CHANGE_CIPHER_SPEC => ReadState::Skip(len),
+                        HANDSHAKE if !me.hello_received => {
+                            me.hello_received = true;
+                            ReadState::Skip(len)
+                        }
+                        HANDSHAKE | APPLICATION_DATA => ReadState::Data(len),
+                        _ => return Poll::Ready(Err(invalid_data("unexpected obfs tls record"))),
+                    };
+                    me.read_state = state;
+                }
+                ReadState::Skip(0) | ReadState::Data(0) => {
+                    me.read_state = ReadState::Header([0u8; 5], 0);
+                }
+                ReadState::Skip(remaining) => {
+                    let mut tmp = [0u8; 1024];
+                    let n = (*remaining).min(tmp.len());
+                    let mut tmp_buf = ReadBuf::new(&mut tmp[..n]);
+                    ready!(Pin::new(&mut me.inner).poll_read(cx, &mut tmp_buf))?;
+                    if tmp_buf.filled().is_empty() {
+                        return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+                    }
+                    *remaining -= tmp_buf.filled().len();
+                }
+                ReadState::Data(remaining) => {
+                    if buf.remaining() == 0 {
+                        return Poll::Ready(Ok(()));
+                    }
+                    let n = (*remaining).min(buf.remaining());
+                    let mut data_buf = ReadBuf::new(buf.initialize_unfilled_to(n));
+                    ready!(Pin::new(&mut me.inner).poll_read(cx, &mut data_buf))?;
+                    let n = data_buf.filled().len();
+                    if n == 0 {
+                        return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+                    }
+                    buf.advance(n);
+                    *remaining -= n;
+                    return Poll::Ready(Ok(()));
+                }
+            }
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Stream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        if me.write_buf.is_empty() {
+            if buf.is_empty() {
+                return Poll::Ready(Ok(0));
+            }
+            let payload = &buf[..buf.len().min(MAX_RECORD_SIZE)];
+            if !me.hello_sent {
+                me.encode_client_hello(payload);
+                me.hello_sent = true;
+            } else {
+                me.encode_application_data(payload);
+            }
+        }
+        while !me.write_buf.is_empty() {
+            let n = ready!(Pin::new(&mut me.inner).poll_write(cx, &me.write_buf))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            me.write_buf.advance(n);
+        }
+        Poll::Ready(Ok(me.written))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    fn record(typ: u8, body: &[u8]) -> Vec<u8> {
+        let mut r = vec![typ, 0x03, 0x03];
+        r.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        r.extend_from_slice(body);
+        r
+    }
+
+    #[test]
+    fn test_tls_obfs() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let (client, mut server) = tokio::io::duplex(4096);
+            let mut stream = Stream::new(client, "www.bing.com".to_string());
+            stream.write_all(b"hello").await.unwrap();
+            stream.write_all(b" again").await.unwrap();
+
+            // The record header, handshake header, version, random and
+            // session id, cipher suites, compression methods.
+            let offset = 5 + 4 + 2 + 32 + 33 + 2 + CIPHER_SUITES.len() + 2;
+            let mut hello = vec![0u8; offset + 2 + 4 + 5];
+            server.read_exact(&mut hello).await.unwrap();
+            assert_eq!(&hello[..3], &[HANDSHAKE, 0x03, 0x01]);
+            let record_len = u16::from_be_bytes([hello[3], hello[4]]) as usize;
+            assert_eq!(&hello[offset + 2..offset + 6], &[0x00, 0x23, 0x00, 0x05]);
+            assert_eq!(&hello[offset + 6..], b"hello");
+            let mut rest = vec![0u8; 5 + record_len - hello.len()];
+            server.read_exact(&mut rest).await.unwrap();
+            assert!(rest.ends_with(OTHER_EXTENSIONS));
+            let mut data = [0u8; 11];
+            server.read_exact(&mut data).await.unwrap();
+            assert_eq!(&data, b"\x17\x03\x03\x00\x06 again");
+
+            server
+                .write_all(&record(HANDSHAKE, &[0x02; 70]))
+                .await
+                .unwrap();
+            server
+                .write_all(&record(CHANGE_CIPHER_SPEC, &[0x01]))
+                .await
+                .unwrap();
+            server.write_all(&record(HANDSHAKE, b"wor")).await.unwrap();
+            server
+                .write_all(&record(APPLICATION_DATA, b"ld"))
+                .await
+                .unwrap();
+            drop(server);
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"world");
+        });
+    }
+}
@@ -1,9 +1,11 @@
 use std::io;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
+use tokio::time::timeout;
 
-use crate::session::Session;
+use crate::{app::dns_client::DomainStrategy, session::Session};
 
 use super::*;
 
@@ -12,27 +14,34 @@ use super::*;
 pub struct Handler {
     tag: String,
     color: colored::Color,
+    bind: Option<SocketBind>,
+    domain_strategy: DomainStrategy,
+    tcp_fast_open: bool,
+    connect_timeout: Option<Duration>,
+    // Limits the handshakes of the handlers, which include the ones of the
+    // inner handlers for outbounds such as chain.
+    handshake_timeout: Option<Duration>,
     tcp_handler: AnyTcpOutboundHandler,
     udp_handler: AnyUdpOutboundHandler,
 }
 
-impl Handler {
-    pub(self) fn new(
-        tag: String,
-        color: colored::Color,
-        tcp_handler: AnyTcpOutboundHandler,
-        udp_handler: AnyUdpOutboundHandler,
-    ) -> Arc<Self> {
-        Arc::new(Handler {
-            tag,
-            color,
-            tcp_handler,
-            udp_handler,
-        })
+impl OutboundHandler for Handler {
+    fn bind(&self) -> Option<&SocketBind> {
+        self.bind.as_ref()
+    }
+
+    fn domain_strategy(&self) -> DomainStrategy {
+        self.domain_strategy
+    }
+
+    fn tcp_fast_open(&self) -> bool {
+        self.tcp_fast_open
     }
-}
 
-impl OutboundHandler for Handler {}
+    fn connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout
+    }
+}
 
 impl Tag for Handler {
     fn tag(&self) -> &String {
@@ -59,7 +68,16 @@ impl TcpOutboundHandler for Handler {
         sess: &'a Session,
         stream: Option<Self::Stream>,
     ) -> io::Result<Self::Stream> {
-        self.tcp_handler.handle(sess, stream).await
+        match self.handshake_timeout {
+            Some(t) => timeout(t, self.tcp_handler.handle(sess, stream))
+                .await
+                .map_err(|_| handshake_timed_out())?,
+            None => self.tcp_handler.handle(sess, stream).await,
+        }
+    }
+
+    async fn group_actor(&self, sess: &Session) -> Option<AnyOutboundHandler> {
+        self.tcp_handler.group_actor(sess).await
     }
 }
 
@@ -81,13 +99,27 @@ impl UdpOutboundHandler for Handler {
         sess: &'a Session,
         transport: Option<OutboundTransport<Self::UStream, Self::Datagram>>,
     ) -> io::Result<Self::Datagram> {
-        self.udp_handler.handle(sess, transport).await
+        match self.handshake_timeout {
+            Some(t) => timeout(t, self.udp_handler.handle(sess, transport))
+                .await
+                .map_err(|_| handshake_timed_out())?,
+            None => self.udp_handler.handle(sess, transport).await,
+        }
     }
 }
 
+fn handshake_timed_out() -> io::Error {
+    io::Error::new(io::ErrorKind::TimedOut, "outbound handshake timed out")
+}
+
 pub struct HandlerBuilder {
     tag: String,
     color: colored::Color,
+    bind: Option<SocketBind>,
+    domain_strategy: DomainStrategy,
+    tcp_fast_open: bool,
+    connect_timeout: Option<Duration>,
+    handshake_timeout: Option<Duration>,
     tcp_handler: AnyTcpOutboundHandler,
     udp_handler: AnyUdpOutboundHandler,
 }
@@ -97,6 +129,11 @@ impl HandlerBuilder {
         Self {
             tag: "".to_string(),
             color: colored::Color::Magenta,
+            bind: None,
+            domain_strategy: DomainStrategy::AsIs,
+            tcp_fast_open: false,
+            connect_timeout: None,
+            handshake_timeout: None,
             tcp_handler: Box::new(super::null::outbound::TcpHandler { connect: None }),
             udp_handler: Box::new(super::null::outbound::UdpHandler {
                 connect: None,
@@ -115,6 +152,31 @@ impl HandlerBuilder {
         self
     }
 
+    pub fn bind(mut self, v: Option<SocketBind>) -> Self {
+        self.bind = v;
+        self
+    }
+
+    pub fn domain_strategy(mut self, v: DomainStrategy) -> Self {
+        self.domain_strategy = v;
+        self
+    }
+
+    pub fn tcp_fast_open(mut self, v: bool) -> Self {
+        self.tcp_fast_open = v;
+        self
+    }
+
+    pub fn connect_timeout(mut self, v: Option<Duration>) -> Self {
+        self.connect_timeout = v;
+        self
+    }
+
+    pub fn handshake_timeout(mut self, v: Option<Duration>) -> Self {
+        self.handshake_timeout = v;
+        self
+    }
+
     pub fn tcp_handler(mut self, v: AnyTcpOutboundHandler) -> Self {
         self.tcp_handler = v;
         self
@@ -126,7 +188,17 @@ impl HandlerBuilder {
     }
 
     pub fn build(self) -> Arc<Handler> {
-        Handler::new(self.tag, self.color, self.tcp_handler, self.udp_handler)
+        Arc::new(Handler {
+            tag: self.tag,
+            color: self.color,
+            bind: self.bind,
+            domain_strategy: self.domain_strategy,
+            tcp_fast_open: self.tcp_fast_open,
+            connect_timeout: self.connect_timeout,
+            handshake_timeout: self.handshake_timeout,
+            tcp_handler: self.tcp_handler,
+            udp_handler: self.udp_handler,
+        })
     }
 }
 
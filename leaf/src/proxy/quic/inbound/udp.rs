@@ -19,15 +19,17 @@ struct Incoming {
     connectings: Vec<quinn::Connecting>,
     new_conns: Vec<quinn::NewConnection>,
     incoming_closed: bool,
+    zero_rtt: bool,
 }
 
 impl Incoming {
-    pub fn new(inner: quinn::Incoming) -> Self {
+    pub fn new(inner: quinn::Incoming, zero_rtt: bool) -> Self {
         Incoming {
             inner,
             connectings: Vec::new(),
             new_conns: Vec::new(),
             incoming_closed: false,
+            zero_rtt,
         }
     }
 }
@@ -40,9 +42,13 @@ impl Stream for Incoming {
 
         if !self.incoming_closed {
             match Pin::new(&mut self.inner).poll_next(cx) {
-                Poll::Ready(Some(connecting)) => {
-                    self.connectings.push(connecting);
-                }
+                // Accepts 0-RTT data of resumed sessions if enabled, it always
+                // succeeds for incoming connections.
+                Poll::Ready(Some(connecting)) if self.zero_rtt => match connecting.into_0rtt() {
+                    Ok((new_conn, _)) => self.new_conns.push(new_conn),
+                    Err(connecting) => self.connectings.push(connecting),
+                },
+                Poll::Ready(Some(connecting)) => self.connectings.push(connecting),
                 Poll::Ready(None) => {
                     self.incoming_closed = true;
                 }
@@ -127,13 +133,17 @@ where
 pub struct Handler {
     certificate: String,
     certificate_key: String,
+    // Whether 0-RTT data is accepted. Requests in it can be replayed by
+    // anyone on the path, so it's off by default.
+    zero_rtt: bool,
 }
 
 impl Handler {
-    pub fn new(certificate: String, certificate_key: String) -> Self {
+    pub fn new(certificate: String, certificate_key: String, zero_rtt: bool) -> Self {
         Self {
             certificate,
             certificate_key,
+            zero_rtt,
         }
     }
 }
@@ -189,11 +199,16 @@ impl UdpInboundHandler for Handler {
             }
         };
 
-        let server_crypto = rustls::ServerConfig::builder()
+        let mut server_crypto = rustls::ServerConfig::builder()
             .with_safe_defaults()
             .with_no_client_auth()
             .with_single_cert(cert, key)
             .map_err(quic_err)?;
+        // QUIC requires either 0 or u32::MAX, clients don't send 0-RTT data
+        // with tickets of 0.
+        if self.zero_rtt {
+            server_crypto.max_early_data_size = u32::MAX;
+        }
 
         let mut transport_config = quinn::TransportConfig::default();
         transport_config
@@ -213,6 +228,7 @@ impl UdpInboundHandler for Handler {
         // let (_, incoming) = endpoint.with_socket(socket.into_std()?).map_err(quic_err)?;
         Ok(InboundTransport::Incoming(Box::new(Incoming::new(
             incoming,
+            self.zero_rtt,
         ))))
     }
 }
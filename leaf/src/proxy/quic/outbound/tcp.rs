@@ -3,6 +3,7 @@ use std::io;
 use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use futures::TryFutureExt;
@@ -12,6 +13,8 @@ use crate::{app::SyncDnsClient, proxy::*, session::Session};
 
 use super::QuicProxyStream;
 
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
 fn quic_err<E>(error: E) -> io::Error
 where
     E: Into<Box<dyn std::error::Error + Send + Sync>>,
@@ -30,6 +33,7 @@ struct Manager {
     port: u16,
     server_name: Option<String>,
     dns_client: SyncDnsClient,
+    dial_options: DialOptions,
     client_config: quinn::ClientConfig,
     connections: Mutex<Vec<Connection>>,
 }
@@ -41,6 +45,7 @@ impl Manager {
         server_name: Option<String>,
         certificate: Option<String>,
         dns_client: SyncDnsClient,
+        dial_options: DialOptions,
     ) -> Self {
         let mut roots = rustls::RootCertStore::empty();
         if let Some(cert_path) = certificate.as_ref() {
@@ -69,16 +74,24 @@ impl Manager {
             }
         }
 
-        let client_crypto = rustls::ClientConfig::builder()
+        let mut client_crypto = rustls::ClientConfig::builder()
             .with_safe_defaults()
             .with_root_certificates(roots)
             .with_no_client_auth();
+        // Sessions are resumed with 0-RTT, the session cache is shared by all
+        // the connections made with this config.
+        client_crypto.enable_early_data = true;
 
         let mut client_config = quinn::ClientConfig::new(Arc::new(client_crypto));
         let mut transport_config = quinn::TransportConfig::default();
         transport_config.max_idle_timeout(Some(quinn::IdleTimeout::from(quinn::VarInt::from_u32(
             300_000,
         )))); // ms
+              // Connections migrate to the new path when the client address changes,
+              // e.g. on a Wi-Fi to cellular handover, once the server receives packets
+              // from it. Keep-alives make sure there are such packets while the client
+              // is only receiving data.
+        transport_config.keep_alive_interval(Some(KEEP_ALIVE_INTERVAL));
         client_config.transport = Arc::new(transport_config);
 
         Manager {
@@ -86,6 +99,7 @@ impl Manager {
             port,
             server_name,
             dns_client,
+            dial_options,
             client_config,
             connections: Mutex::new(Vec::new()),
         }
@@ -121,20 +135,11 @@ impl Manager {
             }
         }
 
-        // FIXME A better indicator.
-        let socket = self
-            .new_udp_socket(&*crate::option::UNSPECIFIED_BIND_ADDR)
-            .await?;
-        let (mut endpoint, _) =
-            quinn::Endpoint::new(quinn::EndpointConfig::default(), None, socket.into_std()?)
-                .map_err(quic_err)?;
-        endpoint.set_default_client_config(self.client_config.clone());
-
         let ips = {
             self.dns_client
                 .read()
                 .await
-                .lookup(&self.address)
+                .lookup_with_strategy(&self.address, self.dial_options.domain_strategy)
                 .map_err(|e| {
                     io::Error::new(
                         io::ErrorKind::Other,
@@ -151,17 +156,42 @@ impl Manager {
         }
         let connect_addr = SocketAddr::new(ips[0], self.port);
 
+        let socket = self.new_udp_socket(&connect_addr).await?;
+        let (mut endpoint, _) =
+            quinn::Endpoint::new(quinn::EndpointConfig::default(), None, socket.into_std()?)
+                .map_err(quic_err)?;
+        endpoint.set_default_client_config(self.client_config.clone());
+
         let server_name = if let Some(name) = self.server_name.as_ref() {
             name
         } else {
             &self.address
         };
 
-        let new_conn = endpoint
+        let connecting = endpoint
             .connect(connect_addr, server_name)
-            .map_err(quic_err)?
-            .await
             .map_err(quic_err)?;
+        // The first stream is sent along with the handshake if the session is
+        // resumed. It's reset if the server rejects 0-RTT data, e.g. after a
+        // restart.
+        let new_conn = match connecting.into_0rtt() {
+            Ok((new_conn, zero_rtt_accepted)) => {
+                let address = self.address.clone();
+                tokio::spawn(async move {
+                    if !zero_rtt_accepted.await {
+                        log::debug!("quic 0-rtt rejected by {}", address);
+                    }
+                });
+                new_conn
+            }
+            Err(connecting) => match self.dial_options.connect_timeout {
+                Some(t) => tokio::time::timeout(t, connecting)
+                    .await
+                    .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "quic connect timed out"))?
+                    .map_err(quic_err)?,
+                None => connecting.await.map_err(quic_err)?,
+            },
+        };
 
         let (send, recv) = new_conn.connection.open_bi().await.map_err(quic_err)?;
 
@@ -175,7 +205,11 @@ impl Manager {
     }
 }
 
-impl UdpConnector for Manager {}
+impl UdpConnector for Manager {
+    fn dial_options(&self) -> &DialOptions {
+        &self.dial_options
+    }
+}
 
 pub struct Handler {
     manager: Manager,
@@ -188,9 +222,17 @@ impl Handler {
         server_name: Option<String>,
         certificate: Option<String>,
         dns_client: SyncDnsClient,
+        dial_options: DialOptions,
     ) -> Self {
         Self {
-            manager: Manager::new(address, port, server_name, certificate, dns_client),
+            manager: Manager::new(
+                address,
+                port,
+                server_name,
+                certificate,
+                dns_client,
+                dial_options,
+            ),
         }
     }
 
@@ -201,7 +243,11 @@ impl Handler {
     }
 }
 
-impl UdpConnector for Handler {}
+impl UdpConnector for Handler {
+    fn dial_options(&self) -> &DialOptions {
+        &self.manager.dial_options
+    }
+}
 
 #[async_trait]
 impl TcpOutboundHandler for Handler {
@@ -219,3 +265,98 @@ impl TcpOutboundHandler for Handler {
         Ok(Box::new(self.new_stream().await?))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::sync::RwLock;
+
+    use crate::app::dns_client::DnsClient;
+
+    use super::*;
+
+    #[test]
+    fn test_0rtt() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+            let cert_der = cert.serialize_der().unwrap();
+            let cert_path = std::env::temp_dir().join("leaf-test-quic-0rtt.der");
+            fs::write(&cert_path, &cert_der).unwrap();
+
+            let mut server_crypto = rustls::ServerConfig::builder()
+                .with_safe_defaults()
+                .with_no_client_auth()
+                .with_single_cert(
+                    vec![rustls::Certificate(cert_der)],
+                    rustls::PrivateKey(cert.serialize_private_key_der()),
+                )
+                .unwrap();
+            server_crypto.max_early_data_size = u32::MAX;
+            let server_config = quinn::ServerConfig::with_crypto(Arc::new(server_crypto));
+            let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+            let port = socket.local_addr().unwrap().port();
+            let (_endpoint, mut incoming) = quinn::Endpoint::new(
+                quinn::EndpointConfig::default(),
+                Some(server_config),
+                socket,
+            )
+            .unwrap();
+            tokio::spawn(async move {
+                while let Some(connecting) = incoming.next().await {
+                    let mut new_conn = connecting.into_0rtt().unwrap().0;
+                    tokio::spawn(async move {
+                        while let Some(Ok((mut send, mut recv))) = new_conn.bi_streams.next().await
+                        {
+                            tokio::spawn(async move {
+                                let _ = tokio::io::copy(&mut recv, &mut send).await;
+                                let _ = send.finish().await;
+                            });
+                        }
+                    });
+                }
+            });
+
+            let mut dns = crate::config::Dns::new();
+            dns.servers.push("127.0.0.1".to_string());
+            let dns_client = Arc::new(RwLock::new(DnsClient::new(&Some(dns).into()).unwrap()));
+            let handler = Handler::new(
+                "127.0.0.1".to_string(),
+                port,
+                Some("localhost".to_string()),
+                Some(cert_path.to_string_lossy().to_string()),
+                dns_client,
+                DialOptions::default(),
+            );
+            let mut stream = handler.new_stream().await.unwrap();
+            stream.write_all(b"hello").await.unwrap();
+            let mut buf = [0u8; 5];
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"hello");
+
+            // The session ticket of the connection above is received by now,
+            // a new connection with the same config is resumed with 0-RTT.
+            let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+            let (mut endpoint, _) =
+                quinn::Endpoint::new(quinn::EndpointConfig::default(), None, socket).unwrap();
+            endpoint.set_default_client_config(handler.manager.client_config.clone());
+            let connecting = endpoint
+                .connect(SocketAddr::from(([127, 0, 0, 1], port)), "localhost")
+                .unwrap();
+            let (new_conn, accepted) = match connecting.into_0rtt() {
+                Ok(v) => v,
+                Err(_) => panic!("0-rtt not available"),
+            };
+            let (send, recv) = new_conn.connection.open_bi().await.unwrap();
+            let mut stream = QuicProxyStream { recv, send };
+            stream.write_all(b"hello").await.unwrap();
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"hello");
+            assert!(accepted.await);
+        });
+    }
+}
@@ -0,0 +1,435 @@
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{aead, digest, hkdf, hmac, signature};
+use tokio::io::{AsyncRead, AsyncWrite};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::common::tls13::{crypto::RecordKey, handshake::*, wire::*};
+
+// The Xray version in the session ID, servers may be configured to accept
+// only some versions.
+const VERSION: [u8; 3] = [1, 8, 0];
+
+// The offsets of the random and the session ID in a ClientHello message.
+const RANDOM_OFFSET: usize = 4 + 2;
+const SESSION_ID_OFFSET: usize = RANDOM_OFFSET + 32 + 1;
+
+fn auth_err() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        "reality authentication failed, wrong public key or not a reality server",
+    )
+}
+
+pub struct Config {
+    /// The X25519 public key of the server.
+    pub public_key: [u8; 32],
+    pub short_id: [u8; 8],
+    pub alpns: Vec<String>,
+}
+
+fn client_hello(
+    random: &[u8; 32],
+    key_share: &[u8],
+    server_name: &str,
+    alpns: &[String],
+) -> Vec<u8> {
+    ClientHello {
+        random,
+        // The session ID is sealed once the message is complete.
+        session_id: &[0u8; 32],
+        key_share,
+        server_name,
+        alpns,
+        tls12: true,
+        extensions: &[],
+    }
+    .encode()
+}
+
+/// Seals the version, the time and the short ID in the session ID of `hello`
+/// with the key shared with the server, returns the key.
+fn seal_session_id(hello: &mut [u8], shared: &[u8], short_id: &[u8; 8], now: u32) -> Vec<u8> {
+    let mut random = [0u8; 32];
+    random.copy_from_slice(&hello[RANDOM_OFFSET..RANDOM_OFFSET + 32]);
+    let mut auth_key = vec![0u8; 32];
+    hkdf::Salt::new(hkdf::HKDF_SHA256, &random[..20])
+        .extract(shared)
+        .expand(&[b"REALITY"], hkdf::HKDF_SHA256)
+        .and_then(|okm| okm.fill(&mut auth_key))
+        .unwrap();
+
+    let mut session_id = Vec::with_capacity(32);
+    session_id.extend_from_slice(&VERSION);
+    // reserved
+    session_id.push(0);
+    session_id.extend_from_slice(&now.to_be_bytes());
+    session_id.extend_from_slice(short_id);
+    // The AAD is the ClientHello with an empty session ID.
+    let key = aead::LessSafeKey::new(aead::UnboundKey::new(&aead::AES_256_GCM, &auth_key).unwrap());
+    let nonce = aead::Nonce::try_assume_unique_for_key(&random[20..]).unwrap();
+    key.seal_in_place_append_tag(nonce, aead::Aad::from(&hello[..]), &mut session_id)
+        .unwrap();
+    hello[SESSION_ID_OFFSET..SESSION_ID_OFFSET + 32].copy_from_slice(&session_id);
+    auth_key
+}
+
+// Splits a DER element into its tag, its content and the data following it.
+fn der_element(buf: &[u8]) -> io::Result<(u8, &[u8], &[u8])> {
+    let mut r = Reader::new(buf);
+    let tag = r.u8()?;
+    let len = match r.u8()? {
+        n if n < 0x80 => n as usize,
+        n if (0x81..=0x83).contains(&n) => r
+            .bytes((n & 0x7f) as usize)?
+            .iter()
+            .fold(0, |len, b| len << 8 | *b as usize),
+        _ => return Err(malformed()),
+    };
+    let content = r.bytes(len)?;
+    Ok((tag, content, r.rest()))
+}
+
+/// Checks the certificate is the temporary one of a REALITY server, whose
+/// signature is the HMAC of its Ed25519 public key, returns the public key.
+fn verify_certificate<'a>(auth_key: &[u8], cert: &'a [u8]) -> io::Result<&'a [u8]> {
+    let (_, cert, _) = der_element(cert)?;
+    let (_, tbs, rest) = der_element(cert)?;
+    let (_, _, rest) = der_element(rest)?;
+    let (_, signature, _) = der_element(rest)?;
+    let mut rest = tbs;
+    // version
+    if rest.first() == Some(&0xa0) {
+        rest = der_element(rest)?.2;
+    }
+    // serial number, signature, issuer, validity and subject
+    for _ in 0..5 {
+        rest = der_element(rest)?.2;
+    }
+    let (_, spki, _) = der_element(rest)?;
+    let (_, algorithm, rest) = der_element(spki)?;
+    let (_, public_key, _) = der_element(rest)?;
+    // The OID of Ed25519 and a key without unused bits.
+    if algorithm != [0x06, 0x03, 0x2b, 0x65, 0x70] || public_key.len() != 33 || public_key[0] != 0 {
+        return Err(auth_err());
+    }
+    let public_key = &public_key[1..];
+    let signature = signature.get(1..).ok_or_else(malformed)?;
+    hmac::verify(
+        &hmac::Key::new(hmac::HMAC_SHA512, auth_key),
+        public_key,
+        signature,
+    )
+    .map_err(|_| auth_err())?;
+    Ok(public_key)
+}
+
+// REALITY servers sign with Ed25519 whatever the client offers.
+fn verify_certificate_verify(public_key: &[u8], auth: &ServerAuth) -> io::Result<()> {
+    if auth.scheme != SIG_ED25519 {
+        return Err(auth_err());
+    }
+    signature::UnparsedPublicKey::new(&signature::ED25519, public_key)
+        .verify(&auth.content, auth.signature)
+        .map_err(|_| auth_err())
+}
+
+// Computes the X25519 shared secret, a public key of low order is rejected
+// as ring does.
+fn diffie_hellman(private: &StaticSecret, public_key: &[u8]) -> io::Result<Vec<u8>> {
+    let public_key: [u8; 32] = public_key
+        .try_into()
+        .map_err(|_| handshake_err("tls key exchange failed".to_string()))?;
+    let shared = private.diffie_hellman(&PublicKey::from(public_key));
+    if !shared.was_contributory() {
+        return Err(handshake_err("tls key exchange failed".to_string()));
+    }
+    Ok(shared.as_bytes().to_vec())
+}
+
+/// Runs the handshake on `stream`, returns the keys of the records from and
+/// to the server.
+pub async fn handshake<S>(
+    stream: &mut S,
+    server_name: &str,
+    config: &Config,
+) -> io::Result<(RecordKey, RecordKey)>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let rng = SystemRandom::new();
+    let mut seed = [0u8; 32];
+    let mut random = [0u8; 32];
+    rng.fill(&mut seed)
+        .and_then(|_| rng.fill(&mut random))
+        .map_err(|_| handshake_err("generate random failed".to_string()))?;
+    // The key share is used for both the authentication and the key
+    // exchange, so it's a static key rather than an ephemeral one of ring,
+    // which can be used only once.
+    let private = StaticSecret::from(seed);
+    let public = PublicKey::from(&private);
+    let shared = diffie_hellman(&private, &config.public_key)?;
+
+    let mut hello = client_hello(&random, public.as_bytes(), server_name, &config.alpns);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as u32)
+        .unwrap_or(0);
+    let auth_key = seal_session_id(&mut hello, &shared, &config.short_id, now);
+    send_client_hello(stream, &hello).await?;
+
+    let mut reader = HandshakeReader::default();
+    let server_hello = reader.read(stream, None, HANDSHAKE_SERVER_HELLO).await?;
+    let (suite, server_public) = parse_server_hello(
+        &server_hello,
+        &hello[SESSION_ID_OFFSET..SESSION_ID_OFFSET + 32],
+    )?;
+    let shared = diffie_hellman(&private, &server_public)?;
+
+    let mut transcript = digest::Context::new(suite.hash);
+    transcript.update(&hello);
+    transcript.update(&server_hello);
+    finish(stream, &mut reader, suite, &shared, transcript, |auth| {
+        let public_key = verify_certificate(&auth_key, auth.certificates[0])?;
+        verify_certificate_verify(public_key, auth)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::agreement;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    const SERVER_SEED: [u8; 32] = [7u8; 32];
+
+    fn server_key() -> agreement::EphemeralPrivateKey {
+        agreement::EphemeralPrivateKey::generate(
+            &agreement::X25519,
+            &ring::test::rand::FixedSliceRandom {
+                bytes: &SERVER_SEED,
+            },
+        )
+        .unwrap()
+    }
+
+    fn server_public_key() -> [u8; 32] {
+        let mut public_key = [0u8; 32];
+        public_key.copy_from_slice(server_key().compute_public_key().unwrap().as_ref());
+        public_key
+    }
+
+    // Authenticates a ClientHello as a REALITY server does, returns the
+    // session ID opened and the key.
+    fn open_session_id(hello: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+        let mut r = Reader::new(&hello[SESSION_ID_OFFSET + 32..]);
+        r.prefixed(2).unwrap();
+        r.prefixed(1).unwrap();
+        let mut extensions = Reader::new(r.prefixed(2).unwrap());
+        let mut key_share = None;
+        while !extensions.is_empty() {
+            let ext_type = extensions.u16().unwrap();
+            let data = extensions.prefixed(2).unwrap();
+            if ext_type == EXT_KEY_SHARE {
+                key_share = Some(&data[6..]);
+            }
+        }
+        let shared = x25519(server_key(), key_share.unwrap()).unwrap();
+        let random = &hello[RANDOM_OFFSET..RANDOM_OFFSET + 32];
+        let mut auth_key = vec![0u8; 32];
+        hkdf::Salt::new(hkdf::HKDF_SHA256, &random[..20])
+            .extract(&shared)
+            .expand(&[b"REALITY"], hkdf::HKDF_SHA256)
+            .and_then(|okm| okm.fill(&mut auth_key))
+            .unwrap();
+        let key =
+            aead::LessSafeKey::new(aead::UnboundKey::new(&aead::AES_256_GCM, &auth_key).unwrap());
+        let nonce = aead::Nonce::try_assume_unique_for_key(&random[20..]).unwrap();
+        let mut aad = hello.to_vec();
+        aad[SESSION_ID_OFFSET..SESSION_ID_OFFSET + 32].fill(0);
+        let mut session_id = hello[SESSION_ID_OFFSET..SESSION_ID_OFFSET + 32].to_vec();
+        key.open_in_place(nonce, aead::Aad::from(&aad), &mut session_id)
+            .ok()?;
+        session_id.truncate(16);
+        Some((session_id, auth_key))
+    }
+
+    // Makes a certificate as REALITY servers do.
+    fn reality_certificate(auth_key: &[u8]) -> (rcgen::Certificate, Vec<u8>) {
+        let mut params = rcgen::CertificateParams::new(vec!["example.com".to_string()]);
+        params.alg = &rcgen::PKCS_ED25519;
+        let cert = rcgen::Certificate::from_params(params).unwrap();
+        let mut der = cert.serialize_der().unwrap();
+        let signature = hmac::sign(
+            &hmac::Key::new(hmac::HMAC_SHA512, auth_key),
+            cert.get_key_pair().public_key_raw(),
+        );
+        let len = der.len();
+        der[len - 64..].copy_from_slice(signature.as_ref());
+        (cert, der)
+    }
+
+    #[test]
+    fn test_session_id() {
+        let private =
+            agreement::EphemeralPrivateKey::generate(&agreement::X25519, &SystemRandom::new())
+                .unwrap();
+        let public = private.compute_public_key().unwrap();
+        let shared = x25519(private, &server_public_key()).unwrap();
+        let mut hello = client_hello(
+            &[1u8; 32],
+            public.as_ref(),
+            "example.com",
+            &["h2".to_string()],
+        );
+        assert_eq!(Reader::new(&hello[1..4]).u24().unwrap(), hello.len() - 4);
+        let short_id = [1, 2, 3, 4, 5, 6, 7, 8];
+        let auth_key = seal_session_id(&mut hello, &shared, &short_id, 0x01020304);
+        let (session_id, server_auth_key) = open_session_id(&hello).unwrap();
+        assert_eq!(session_id, [1, 8, 0, 0, 1, 2, 3, 4, 1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(auth_key, server_auth_key);
+
+        // The whole ClientHello is authenticated.
+        let len = hello.len();
+        hello[len - 1] ^= 1;
+        assert!(open_session_id(&hello).is_none());
+    }
+
+    #[test]
+    fn test_certificate() {
+        let (cert, der) = reality_certificate(&[1u8; 32]);
+        assert_eq!(
+            verify_certificate(&[1u8; 32], &der).unwrap(),
+            cert.get_key_pair().public_key_raw()
+        );
+        assert!(verify_certificate(&[2u8; 32], &der).is_err());
+
+        // The certificate of the site the name is borrowed from.
+        let cert = rcgen::generate_simple_self_signed(vec!["example.com".to_string()]).unwrap();
+        let err = verify_certificate(&[1u8; 32], &cert.serialize_der().unwrap()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[cfg(feature = "rustls-tls")]
+    mod server {
+        use std::sync::Arc;
+
+        use bytes::Bytes;
+        use tokio::io::DuplexStream;
+        use tokio_rustls::rustls::{
+            server::{ClientHello, ResolvesServerCert},
+            sign, Certificate, ServerConfig, SignatureAlgorithm, SignatureScheme,
+        };
+
+        use crate::common::io::PrefixedStream;
+
+        use super::*;
+
+        // Signs with Ed25519 whatever the client offers, as REALITY servers
+        // do.
+        #[derive(Clone)]
+        struct Ed25519Key(Arc<signature::Ed25519KeyPair>);
+
+        impl sign::SigningKey for Ed25519Key {
+            fn choose_scheme(&self, _offered: &[SignatureScheme]) -> Option<Box<dyn sign::Signer>> {
+                Some(Box::new(self.clone()))
+            }
+
+            fn algorithm(&self) -> SignatureAlgorithm {
+                SignatureAlgorithm::ED25519
+            }
+        }
+
+        impl sign::Signer for Ed25519Key {
+            fn sign(&self, message: &[u8]) -> Result<Vec<u8>, tokio_rustls::rustls::Error> {
+                Ok(self.0.sign(message).as_ref().to_vec())
+            }
+
+            fn scheme(&self) -> SignatureScheme {
+                SignatureScheme::ED25519
+            }
+        }
+
+        struct Resolver(Arc<sign::CertifiedKey>);
+
+        impl ResolvesServerCert for Resolver {
+            fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<sign::CertifiedKey>> {
+                Some(self.0.clone())
+            }
+        }
+
+        /// Echoes data as a REALITY server, the certificate is signed with a
+        /// wrong key if the client fails the authentication.
+        pub async fn serve(mut stream: DuplexStream) -> io::Result<()> {
+            let mut record = vec![0u8; RECORD_HEADER_LEN];
+            stream.read_exact(&mut record).await?;
+            let len = u16::from_be_bytes([record[3], record[4]]) as usize;
+            record.resize(RECORD_HEADER_LEN + len, 0);
+            stream.read_exact(&mut record[RECORD_HEADER_LEN..]).await?;
+            let auth_key = open_session_id(&record[RECORD_HEADER_LEN..])
+                .map(|(_, auth_key)| auth_key)
+                .unwrap_or_else(|| vec![0u8; 32]);
+            let (cert, der) = reality_certificate(&auth_key);
+            let key_pair =
+                signature::Ed25519KeyPair::from_pkcs8(&cert.serialize_private_key_der()).unwrap();
+            let certified_key = sign::CertifiedKey::new(
+                vec![Certificate(der)],
+                Arc::new(Ed25519Key(Arc::new(key_pair))),
+            );
+            let config = ServerConfig::builder()
+                .with_safe_defaults()
+                .with_no_client_auth()
+                .with_cert_resolver(Arc::new(Resolver(Arc::new(certified_key))));
+            let stream = PrefixedStream::new(stream, Bytes::from(record));
+            let mut stream = tokio_rustls::TlsAcceptor::from(Arc::new(config))
+                .accept(stream)
+                .await?;
+            let mut buf = vec![0u8; 100000];
+            stream.read_exact(&mut buf).await?;
+            stream.write_all(&buf).await?;
+            stream.shutdown().await
+        }
+    }
+
+    #[cfg(feature = "rustls-tls")]
+    #[test]
+    fn test_handshake() {
+        use crate::common::tls13::Stream;
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let mut config = Config {
+                public_key: server_public_key(),
+                short_id: [1u8; 8],
+                alpns: vec!["h2".to_string(), "http/1.1".to_string()],
+            };
+            let (mut client_io, server_io) = tokio::io::duplex(65536);
+            let server = tokio::spawn(server::serve(server_io));
+            let (opener, sealer) = handshake(&mut client_io, "example.com", &config)
+                .await
+                .unwrap();
+            let mut stream = Stream::new(client_io, opener, sealer);
+            let data = vec![7u8; 100000];
+            stream.write_all(&data).await.unwrap();
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            assert_eq!(buf, data);
+            server.await.unwrap().unwrap();
+
+            config.public_key[0] ^= 1;
+            let (mut client_io, server_io) = tokio::io::duplex(65536);
+            tokio::spawn(server::serve(server_io));
+            let err = handshake(&mut client_io, "example.com", &config)
+                .await
+                .err()
+                .unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        });
+    }
+}
@@ -0,0 +1,11 @@
+//! REALITY of Xray, a TLS 1.3 client which authenticates itself to the
+//! server in the session ID of the ClientHello, the server answers with a
+//! temporary certificate signed with the shared key instead of forwarding the
+//! connection to the site it borrows the name of.
+//!
+//! rustls and OpenSSL don't let the session ID depend on the key share, so
+//! the handshake is made with the client of `common::tls13`.
+
+pub mod outbound;
+
+mod handshake;
@@ -0,0 +1,77 @@
+use std::io;
+
+use async_trait::async_trait;
+
+use crate::{common::tls13::Stream, proxy::*, session::Session};
+
+use super::super::handshake::{handshake, Config};
+
+pub struct Handler {
+    server_name: String,
+    config: Config,
+}
+
+impl Handler {
+    pub fn new(
+        server_name: String,
+        public_key: &str,
+        short_id: &str,
+        mut alpns: Vec<String>,
+    ) -> io::Result<Self> {
+        let invalid = |what| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid reality {}", what),
+            )
+        };
+        // Xray prints keys in base64url without padding.
+        let public_key: [u8; 32] =
+            base64::decode_config(public_key.trim_end_matches('='), base64::URL_SAFE_NO_PAD)
+                .ok()
+                .and_then(|key| key.try_into().ok())
+                .ok_or_else(|| invalid("public key"))?;
+        // Short IDs are up to 8 bytes, padded with zeros.
+        let id = hex::decode(short_id).map_err(|_| invalid("short id"))?;
+        if id.len() > 8 {
+            return Err(invalid("short id"));
+        }
+        let mut short_id = [0u8; 8];
+        short_id[..id.len()].copy_from_slice(&id);
+        if alpns.is_empty() {
+            alpns = vec!["h2".to_string(), "http/1.1".to_string()];
+        }
+        Ok(Handler {
+            server_name,
+            config: Config {
+                public_key,
+                short_id,
+                alpns,
+            },
+        })
+    }
+}
+
+#[async_trait]
+impl TcpOutboundHandler for Handler {
+    type Stream = AnyStream;
+
+    fn connect_addr(&self) -> Option<OutboundConnect> {
+        None
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        stream: Option<Self::Stream>,
+    ) -> io::Result<Self::Stream> {
+        let mut stream =
+            stream.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "invalid reality input"))?;
+        let name = if !self.server_name.is_empty() {
+            self.server_name.clone()
+        } else {
+            sess.destination.host()
+        };
+        let (opener, sealer) = handshake(&mut stream, &name, &self.config).await?;
+        Ok(Box::new(Stream::new(stream, opener, sealer)))
+    }
+}
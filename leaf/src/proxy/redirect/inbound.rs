@@ -0,0 +1,149 @@
+use std::io;
+use std::mem;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::os::unix::io::AsRawFd;
+use std::sync::Arc;
+
+use anyhow::Result;
+use log::*;
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::{
+    app::dispatcher::Dispatcher,
+    common::limiter::ConnectionLimiter,
+    common::net::ResettableTcpStream,
+    config::Inbound,
+    session::{Network, Session, SocksAddr},
+    Runner,
+};
+
+/// Returns the destination of a connection before it's redirected by
+/// iptables, the connection must be tracked by netfilter.
+pub fn original_dst(stream: &TcpStream) -> io::Result<SocketAddr> {
+    let fd = stream.as_raw_fd();
+    match stream.local_addr()? {
+        SocketAddr::V4(_) => {
+            let mut addr: libc::sockaddr_in = unsafe { mem::zeroed() };
+            let mut len = mem::size_of::<libc::sockaddr_in>() as libc::socklen_t;
+            let ret = unsafe {
+                libc::getsockopt(
+                    fd,
+                    libc::SOL_IP,
+                    libc::SO_ORIGINAL_DST,
+                    &mut addr as *mut _ as *mut libc::c_void,
+                    &mut len,
+                )
+            };
+            if ret != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let ip = Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr));
+            Ok(SocketAddr::new(IpAddr::V4(ip), u16::from_be(addr.sin_port)))
+        }
+        SocketAddr::V6(_) => {
+            let mut addr: libc::sockaddr_in6 = unsafe { mem::zeroed() };
+            let mut len = mem::size_of::<libc::sockaddr_in6>() as libc::socklen_t;
+            // IP6T_SO_ORIGINAL_DST has the same value as SO_ORIGINAL_DST.
+            let ret = unsafe {
+                libc::getsockopt(
+                    fd,
+                    libc::SOL_IPV6,
+                    libc::SO_ORIGINAL_DST,
+                    &mut addr as *mut _ as *mut libc::c_void,
+                    &mut len,
+                )
+            };
+            if ret != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let ip = Ipv6Addr::from(addr.sin6_addr.s6_addr);
+            Ok(SocketAddr::new(
+                IpAddr::V6(ip),
+                u16::from_be(addr.sin6_port),
+            ))
+        }
+    }
+}
+
+async fn handle_inbound_stream(
+    stream: TcpStream,
+    inbound_tag: String,
+    dispatcher: Arc<Dispatcher>,
+) {
+    let source = match stream.peer_addr() {
+        Ok(a) => a,
+        Err(e) => {
+            debug!("get peer address failed: {}", e);
+            return;
+        }
+    };
+    let local_addr = match stream.local_addr() {
+        Ok(a) => a,
+        Err(e) => {
+            debug!("get local address failed: {}", e);
+            return;
+        }
+    };
+    let destination = match original_dst(&stream) {
+        Ok(a) => a,
+        Err(e) => {
+            debug!("get original destination of {} failed: {}", &source, e);
+            return;
+        }
+    };
+    // Connections made to the inbound directly are not redirected, they
+    // would otherwise loop back.
+    if destination == local_addr {
+        debug!("connection from {} is not redirected", &source);
+        return;
+    }
+    let mut sess = Session {
+        network: Network::Tcp,
+        source,
+        local_addr,
+        destination: SocksAddr::Ip(destination),
+        inbound_tag,
+        ..Default::default()
+    };
+    let (stream, reset) = ResettableTcpStream::new(stream);
+    sess.inbound_reset = Some(reset);
+    dispatcher.dispatch_tcp(sess, stream).await;
+}
+
+pub fn new(inbound: Inbound, dispatcher: Arc<Dispatcher>) -> Result<Runner> {
+    let listen_addr = SocketAddr::new(inbound.address.parse::<IpAddr>()?, inbound.port as u16);
+    let limiter = Arc::new(ConnectionLimiter::from_inbound(&inbound));
+    Ok(Box::pin(async move {
+        let listener = match TcpListener::bind(&listen_addr).await {
+            Ok(l) => l,
+            Err(e) => {
+                error!("redirect inbound bind {} failed: {}", &listen_addr, e);
+                return;
+            }
+        };
+        info!("redirect inbound listening tcp {}", &listen_addr);
+        loop {
+            match listener.accept().await {
+                Ok((stream, source)) => {
+                    let permit = match limiter.try_acquire(source.ip()) {
+                        Some(p) => p,
+                        None => {
+                            debug!("connection from {} rejected by limits", &source);
+                            continue;
+                        }
+                    };
+                    let fut =
+                        handle_inbound_stream(stream, inbound.tag.clone(), dispatcher.clone());
+                    tokio::spawn(async move {
+                        fut.await;
+                        drop(permit);
+                    });
+                }
+                Err(e) => {
+                    error!("accept connection failed: {}", e);
+                    break;
+                }
+            }
+        }
+    }))
+}
@@ -1,5 +1,11 @@
+#[cfg(all(feature = "inbound-redirect", target_os = "linux"))]
+pub mod inbound;
+#[cfg(feature = "outbound-redirect")]
 pub mod tcp;
+#[cfg(feature = "outbound-redirect")]
 pub mod udp;
 
+#[cfg(feature = "outbound-redirect")]
 pub use tcp::Handler as TcpHandler;
+#[cfg(feature = "outbound-redirect")]
 pub use udp::Handler as UdpHandler;
@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+pub mod tcp;
+pub mod udp;
+
+pub use tcp::Handler as TcpHandler;
+pub use udp::Handler as UdpHandler;
+
+/// How a retry group retries the connections failed through its actors.
+pub struct Retry {
+    /// Total attempts of a connection, including the first one.
+    pub attempts: u32,
+    /// The delay before the first retry, doubled on each following one.
+    pub backoff_base: Duration,
+    pub max_backoff: Duration,
+    /// In percent, the part of a delay which is randomly cut off, so the
+    /// retries of concurrent connections spread out.
+    pub jitter: u32,
+    /// Uses the actors in turn instead of retrying the first one.
+    pub rotate: bool,
+}
+
+impl Retry {
+    /// Returns the index of the actor for the attempt.
+    pub fn actor(&self, attempt: u32, actors: usize) -> usize {
+        if self.rotate {
+            attempt as usize % actors
+        } else {
+            0
+        }
+    }
+
+    /// Returns the delay before the attempt.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.backoff_with(attempt, rand::thread_rng().gen())
+    }
+
+    // `random` is in [0, 1).
+    fn backoff_with(&self, attempt: u32, random: f64) -> Duration {
+        if attempt == 0 {
+            return Duration::ZERO;
+        }
+        let delay = self
+            .backoff_base
+            .saturating_mul(1 << (attempt - 1).min(20))
+            .min(self.max_backoff);
+        delay.mul_f64(1.0 - random * self.jitter.min(100) as f64 / 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry() {
+        let ms = Duration::from_millis;
+        let retry = Retry {
+            attempts: 5,
+            backoff_base: ms(100),
+            max_backoff: ms(500),
+            jitter: 50,
+            rotate: false,
+        };
+        assert_eq!(retry.backoff_with(0, 0.0), Duration::ZERO);
+        assert_eq!(retry.backoff_with(1, 0.0), ms(100));
+        assert_eq!(retry.backoff_with(2, 0.0), ms(200));
+        assert_eq!(retry.backoff_with(3, 0.0), ms(400));
+        assert_eq!(retry.backoff_with(4, 0.0), ms(500));
+        assert_eq!(retry.backoff_with(40, 0.0), ms(500));
+        // Cuts off half at most.
+        assert_eq!(retry.backoff_with(2, 0.5), ms(150));
+        assert!(retry.backoff(2) > ms(100) && retry.backoff(2) <= ms(200));
+        assert_eq!(retry.actor(3, 2), 0);
+        let retry = Retry {
+            rotate: true,
+            ..retry
+        };
+        assert_eq!(retry.actor(0, 2), 0);
+        assert_eq!(retry.actor(3, 2), 1);
+    }
+}
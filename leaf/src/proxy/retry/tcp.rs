@@ -0,0 +1,75 @@
+use std::io;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use log::*;
+
+use crate::{app::SyncDnsClient, proxy::*, session::Session};
+
+use super::Retry;
+
+pub struct Handler {
+    pub actors: Vec<AnyOutboundHandler>,
+    pub retry: Arc<Retry>,
+    pub dns_client: SyncDnsClient,
+}
+
+#[async_trait]
+impl TcpOutboundHandler for Handler {
+    type Stream = AnyStream;
+
+    fn connect_addr(&self) -> Option<OutboundConnect> {
+        Some(OutboundConnect::NoConnect)
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        _stream: Option<Self::Stream>,
+    ) -> io::Result<Self::Stream> {
+        let mut last_err = None;
+        for attempt in 0..self.retry.attempts.max(1) {
+            let backoff = self.retry.backoff(attempt);
+            if !backoff.is_zero() {
+                tokio::time::sleep(backoff).await;
+            }
+            let a = &self.actors[self.retry.actor(attempt, self.actors.len())];
+            debug!(
+                "retry handles tcp [{}] to [{}], attempt {}",
+                sess.destination,
+                a.tag(),
+                attempt + 1
+            );
+            let handle = async {
+                let stream =
+                    crate::proxy::connect_tcp_outbound(sess, self.dns_client.clone(), a).await?;
+                TcpOutboundHandler::handle(a.as_ref(), sess, stream).await
+            };
+            match handle.await {
+                Ok(stream) => return Ok(stream),
+                Err(e) => {
+                    trace!(
+                        "[{}] failed to handle [{}]: {}",
+                        a.tag(),
+                        sess.destination,
+                        e
+                    );
+                    last_err = Some(e);
+                }
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "all outbound attempts failed, last error: {}",
+                last_err.map(|e| e.to_string()).unwrap_or_default()
+            ),
+        ))
+    }
+
+    // Chains can't retry a hop on the same connection, they use the actor of
+    // the first attempt.
+    async fn group_actor(&self, _sess: &Session) -> Option<AnyOutboundHandler> {
+        self.actors.first().cloned()
+    }
+}
@@ -0,0 +1,74 @@
+use std::io;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use log::*;
+
+use crate::{app::SyncDnsClient, proxy::*, session::Session};
+
+use super::Retry;
+
+pub struct Handler {
+    pub actors: Vec<AnyOutboundHandler>,
+    pub retry: Arc<Retry>,
+    pub dns_client: SyncDnsClient,
+}
+
+#[async_trait]
+impl UdpOutboundHandler for Handler {
+    type UStream = AnyStream;
+    type Datagram = AnyOutboundDatagram;
+
+    fn connect_addr(&self) -> Option<OutboundConnect> {
+        Some(OutboundConnect::NoConnect)
+    }
+
+    fn transport_type(&self) -> DatagramTransportType {
+        DatagramTransportType::Undefined
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        _transport: Option<OutboundTransport<Self::UStream, Self::Datagram>>,
+    ) -> io::Result<Self::Datagram> {
+        let mut last_err = None;
+        for attempt in 0..self.retry.attempts.max(1) {
+            let backoff = self.retry.backoff(attempt);
+            if !backoff.is_zero() {
+                tokio::time::sleep(backoff).await;
+            }
+            let a = &self.actors[self.retry.actor(attempt, self.actors.len())];
+            debug!(
+                "retry handles udp [{}] to [{}], attempt {}",
+                sess.destination,
+                a.tag(),
+                attempt + 1
+            );
+            let handle = async {
+                let transport =
+                    crate::proxy::connect_udp_outbound(sess, self.dns_client.clone(), a).await?;
+                UdpOutboundHandler::handle(a.as_ref(), sess, transport).await
+            };
+            match handle.await {
+                Ok(datagram) => return Ok(datagram),
+                Err(e) => {
+                    trace!(
+                        "[{}] failed to handle [{}]: {}",
+                        a.tag(),
+                        sess.destination,
+                        e
+                    );
+                    last_err = Some(e);
+                }
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "all outbound attempts failed, last error: {}",
+                last_err.map(|e| e.to_string()).unwrap_or_default()
+            ),
+        ))
+    }
+}
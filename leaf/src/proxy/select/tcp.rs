@@ -6,7 +6,7 @@ use tokio::sync::RwLock;
 
 use crate::{
     app::outbound::selector::OutboundSelector,
-    proxy::{OutboundConnect, ProxyStream, TcpOutboundHandler},
+    proxy::{AnyOutboundHandler, OutboundConnect, ProxyStream, TcpOutboundHandler},
     session::Session,
 };
 
@@ -17,7 +17,7 @@ pub struct Handler {
 #[async_trait]
 impl TcpOutboundHandler for Handler {
     fn connect_addr(&self) -> Option<OutboundConnect> {
-        None
+        Some(OutboundConnect::NoConnect)
     }
 
     async fn handle<'a>(
@@ -32,4 +32,8 @@ impl TcpOutboundHandler for Handler {
             Err(io::Error::new(io::ErrorKind::Other, "no selected outbound"))
         }
     }
+
+    async fn group_actor(&self, _sess: &Session) -> Option<AnyOutboundHandler> {
+        self.selector.read().await.get_selected()
+    }
 }
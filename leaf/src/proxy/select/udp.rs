@@ -8,7 +8,7 @@ use tokio::sync::RwLock;
 use crate::{
     app::outbound::selector::OutboundSelector,
     proxy::{
-        DatagramTransportType, OutboundConnect, OutboundDatagram, OutboundTransport,
+        AnyOutboundDatagram, AnyStream, DatagramTransportType, OutboundConnect, OutboundTransport,
         UdpOutboundHandler,
     },
     session::Session,
@@ -24,7 +24,7 @@ impl UdpOutboundHandler for Handler {
     type Datagram = AnyOutboundDatagram;
 
     fn connect_addr(&self) -> Option<OutboundConnect> {
-        None
+        Some(OutboundConnect::NoConnect)
     }
 
     fn transport_type(&self) -> DatagramTransportType {
@@ -1,3 +1,5 @@
+use aes::cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, NewBlockCipher};
+use aes::{Aes128, Aes256};
 use anyhow::anyhow;
 use anyhow::Result;
 use hkdf::Hkdf;
@@ -30,6 +32,15 @@ impl NonceSequence for ShadowsocksNonceSequence {
     }
 }
 
+/// The nonce of a single message, e.g. a 2022 UDP packet.
+pub struct FixedNonceSequence(pub Vec<u8>);
+
+impl NonceSequence for FixedNonceSequence {
+    fn advance(&mut self) -> Result<Vec<u8>> {
+        Ok(self.0.clone())
+    }
+}
+
 pub fn kdf(pass: &str, size: usize) -> Result<Vec<u8>> {
     let pass = pass.as_bytes();
     let mut key = Vec::new();
@@ -49,3 +60,64 @@ pub fn hkdf_sha1(key: &[u8], salt: &[u8], info: Vec<u8>, size: usize) -> Result<
         .map_err(|_| anyhow!("hkdf expand failed"))?;
     Ok(okm.to_vec())
 }
+
+/// Derives the session subkey of the 2022 ciphers from the pre-shared key
+/// and the salt, or the session ID for UDP.
+pub fn blake3_subkey(key: &[u8], salt: &[u8], size: usize) -> Vec<u8> {
+    let mut hasher = blake3::Hasher::new_derive_key("shadowsocks 2022 session subkey");
+    hasher.update(key);
+    hasher.update(salt);
+    let mut okm = vec![0u8; size];
+    hasher.finalize_xof().fill(&mut okm);
+    okm
+}
+
+/// Decodes the base64 encoded pre-shared key of the 2022 ciphers.
+pub fn decode_psk(password: &str, size: usize) -> Result<Vec<u8>> {
+    let key = base64::decode(password).map_err(|e| anyhow!("invalid base64 key: {}", e))?;
+    if key.len() != size {
+        return Err(anyhow!(
+            "expect a {} bytes key, got {} bytes",
+            size,
+            key.len()
+        ));
+    }
+    Ok(key)
+}
+
+/// Encrypts the separate header of 2022 UDP packets, a single AES block,
+/// with the pre-shared key.
+pub enum HeaderCipher {
+    Aes128(Box<Aes128>),
+    Aes256(Box<Aes256>),
+}
+
+impl HeaderCipher {
+    pub fn new(key: &[u8]) -> Result<Self> {
+        match key.len() {
+            16 => Ok(HeaderCipher::Aes128(Box::new(
+                Aes128::new_from_slice(key).map_err(|_| anyhow!("invalid AES key"))?,
+            ))),
+            32 => Ok(HeaderCipher::Aes256(Box::new(
+                Aes256::new_from_slice(key).map_err(|_| anyhow!("invalid AES key"))?,
+            ))),
+            n => Err(anyhow!("invalid AES key length {}", n)),
+        }
+    }
+
+    pub fn encrypt(&self, block: &mut [u8]) {
+        let block = GenericArray::from_mut_slice(&mut block[..16]);
+        match self {
+            HeaderCipher::Aes128(c) => c.encrypt_block(block),
+            HeaderCipher::Aes256(c) => c.encrypt_block(block),
+        }
+    }
+
+    pub fn decrypt(&self, block: &mut [u8]) {
+        let block = GenericArray::from_mut_slice(&mut block[..16]);
+        match self {
+            HeaderCipher::Aes128(c) => c.decrypt_block(block),
+            HeaderCipher::Aes256(c) => c.decrypt_block(block),
+        }
+    }
+}
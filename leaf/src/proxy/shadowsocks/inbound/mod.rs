@@ -4,4 +4,5 @@ mod udp;
 pub use tcp::Handler as TcpHandler;
 pub use udp::Handler as UdpHandler;
 
+use super::replay;
 use super::shadow;
@@ -1,15 +1,20 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 
 use crate::{
+    proxy::uot,
     proxy::*,
-    session::{Session, SocksAddr, SocksAddrWireType},
+    session::{DatagramSource, Network, Session, SocksAddr, SocksAddrWireType},
 };
 
+use super::replay::ReplayFilter;
 use super::shadow::ShadowedStream;
 
 pub struct Handler {
     pub cipher: String,
     pub password: String,
+    pub replay_filter: Arc<ReplayFilter>,
 }
 
 #[async_trait]
@@ -22,8 +27,20 @@ impl TcpInboundHandler for Handler {
         mut sess: Session,
         stream: Self::TStream,
     ) -> std::io::Result<InboundTransport<Self::TStream, Self::TDatagram>> {
-        let mut stream = ShadowedStream::new(stream, &self.cipher, &self.password)?;
+        let mut stream = ShadowedStream::new(stream, &self.cipher, &self.password)?
+            .server()
+            .with_replay_filter(self.replay_filter.clone());
         let destination = SocksAddr::read_from(&mut stream, SocksAddrWireType::PortLast).await?;
+        if uot::is_magic_address(&destination) {
+            let source = DatagramSource::new(sess.source, sess.stream_id);
+            let (dgram, destination) =
+                uot::ServerDatagram::accept(stream, &destination, source).await?;
+            sess.network = Network::Udp;
+            if let Some(destination) = destination {
+                sess.destination = destination;
+            }
+            return Ok(InboundTransport::Datagram(Box::new(dgram), Some(sess)));
+        }
         sess.destination = destination;
 
         Ok(InboundTransport::Stream(Box::new(stream), sess))
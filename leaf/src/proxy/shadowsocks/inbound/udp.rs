@@ -12,11 +12,13 @@ use crate::{
     session::{SocksAddr, SocksAddrWireType},
 };
 
+use super::replay::ReplayFilter;
 use super::shadow::{self, ShadowedDatagram};
 
 pub struct Handler {
     pub cipher: String,
     pub password: String,
+    pub replay_filter: Arc<ReplayFilter>,
 }
 
 #[async_trait]
@@ -28,7 +30,8 @@ impl UdpInboundHandler for Handler {
         &'a self,
         socket: Self::UDatagram,
     ) -> io::Result<InboundTransport<Self::UStream, Self::UDatagram>> {
-        let dgram = ShadowedDatagram::new(&self.cipher, &self.password)?;
+        let dgram = ShadowedDatagram::new(&self.cipher, &self.password)?
+            .with_replay_filter(self.replay_filter.clone());
         Ok(InboundTransport::Datagram(
             Box::new(Datagram { dgram, socket }),
             None,
@@ -75,7 +78,7 @@ impl InboundDatagramRecvHalf for DatagramRecvHalf {
         recv_buf.resize(n, 0);
         let plaintext = self
             .0
-            .decrypt(recv_buf)
+            .decrypt_from(recv_buf, &src_addr.address)
             .map_err(|e| ProxyError::DatagramWarn(anyhow!("Decrypt payload failed: {}", e)))?;
         let dst_addr = SocksAddr::try_from((&plaintext[..], SocksAddrWireType::PortLast))
             .map_err(|e| ProxyError::DatagramWarn(anyhow!("Parse target address failed: {}", e)))?;
@@ -101,7 +104,10 @@ impl InboundDatagramSendHalf for DatagramSendHalf {
         let mut send_buf = BytesMut::new();
         src_addr.write_buf(&mut send_buf, SocksAddrWireType::PortLast);
         send_buf.put_slice(buf);
-        let ciphertext = self.0.encrypt(send_buf).map_err(|_| shadow::crypto_err())?;
+        let ciphertext = self
+            .0
+            .encrypt_to(send_buf, dst_addr)
+            .map_err(|_| shadow::crypto_err())?;
         self.1.send_to(&ciphertext[..], src_addr, dst_addr).await
     }
 }
@@ -1,4 +1,5 @@
 mod crypto;
+pub mod replay;
 pub mod shadow;
 
 #[cfg(feature = "inbound-shadowsocks")]
@@ -1,3 +1,4 @@
+pub mod plugin;
 pub mod tcp;
 pub mod udp;
 
@@ -0,0 +1,107 @@
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+
+use log::*;
+
+/// A SIP003 plugin, e.g. v2ray-plugin or obfs-local, running as a child
+/// process. The plugin listens on a local port and forwards the shadowsocks
+/// stream to the remote server with its own transport.
+pub struct Plugin {
+    plugin: String,
+    plugin_opts: String,
+    remote_host: String,
+    remote_port: u16,
+    local_addr: SocketAddr,
+    child: Mutex<Child>,
+}
+
+impl Plugin {
+    pub fn start(
+        plugin: &str,
+        plugin_opts: &str,
+        remote_host: &str,
+        remote_port: u16,
+    ) -> io::Result<Self> {
+        // Let the system pick a free port, the plugin binds it right after.
+        let local_addr = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?.local_addr()?;
+        let child = spawn(plugin, plugin_opts, remote_host, remote_port, &local_addr)?;
+        debug!(
+            "started plugin {} (pid {}) on {} for {}:{}",
+            plugin,
+            child.id(),
+            local_addr,
+            remote_host,
+            remote_port
+        );
+        Ok(Plugin {
+            plugin: plugin.to_string(),
+            plugin_opts: plugin_opts.to_string(),
+            remote_host: remote_host.to_string(),
+            remote_port,
+            local_addr,
+            child: Mutex::new(child),
+        })
+    }
+
+    pub fn local_addr(&self) -> &SocketAddr {
+        &self.local_addr
+    }
+
+    /// Restarts the plugin if it has exited.
+    pub fn ensure_running(&self) {
+        let mut child = self.child.lock().unwrap();
+        match child.try_wait() {
+            Ok(None) => (),
+            Ok(Some(status)) => {
+                warn!("plugin {} exited with {}, restarting", &self.plugin, status);
+                match spawn(
+                    &self.plugin,
+                    &self.plugin_opts,
+                    &self.remote_host,
+                    self.remote_port,
+                    &self.local_addr,
+                ) {
+                    Ok(c) => *child = c,
+                    Err(e) => warn!("restart plugin {} failed: {}", &self.plugin, e),
+                }
+            }
+            Err(e) => warn!("wait plugin {} failed: {}", &self.plugin, e),
+        }
+    }
+}
+
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        if let Ok(child) = self.child.get_mut() {
+            let _ = child.kill();
+            let _ = child.wait();
+            debug!("stopped plugin {} (pid {})", &self.plugin, child.id());
+        }
+    }
+}
+
+fn spawn(
+    plugin: &str,
+    plugin_opts: &str,
+    remote_host: &str,
+    remote_port: u16,
+    local_addr: &SocketAddr,
+) -> io::Result<Child> {
+    let mut cmd = Command::new(plugin);
+    cmd.env("SS_REMOTE_HOST", remote_host)
+        .env("SS_REMOTE_PORT", remote_port.to_string())
+        .env("SS_LOCAL_HOST", local_addr.ip().to_string())
+        .env("SS_LOCAL_PORT", local_addr.port().to_string())
+        .stdin(Stdio::null());
+    if !plugin_opts.is_empty() {
+        cmd.env("SS_PLUGIN_OPTIONS", plugin_opts);
+    }
+    cmd.spawn().map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            format!("spawn plugin {} failed: {}", plugin, e),
+        )
+    })
+}
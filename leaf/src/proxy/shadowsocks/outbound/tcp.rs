@@ -1,9 +1,11 @@
 use std::io;
+use std::sync::Arc;
 
 use async_trait::async_trait;
 use bytes::BytesMut;
 use tokio::io::AsyncWriteExt;
 
+use super::plugin::Plugin;
 use super::shadow::ShadowedStream;
 use crate::{
     proxy::*,
@@ -15,6 +17,7 @@ pub struct Handler {
     pub port: u16,
     pub cipher: String,
     pub password: String,
+    pub plugin: Option<Arc<Plugin>>,
 }
 
 #[async_trait]
@@ -22,6 +25,11 @@ impl TcpOutboundHandler for Handler {
     type Stream = AnyStream;
 
     fn connect_addr(&self) -> Option<OutboundConnect> {
+        if let Some(plugin) = &self.plugin {
+            plugin.ensure_running();
+            let addr = plugin.local_addr();
+            return Some(OutboundConnect::Proxy(addr.ip().to_string(), addr.port()));
+        }
         Some(OutboundConnect::Proxy(self.address.clone(), self.port))
     }
 
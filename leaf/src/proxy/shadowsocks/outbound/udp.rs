@@ -2,19 +2,25 @@ use std::{convert::TryFrom, io, sync::Arc};
 
 use async_trait::async_trait;
 use bytes::{BufMut, BytesMut};
+use tokio::io::AsyncWriteExt;
 
 use crate::{
     proxy::*,
     session::{Session, SocksAddr, SocksAddrWireType},
 };
 
-use super::shadow::{self, ShadowedDatagram};
+use super::plugin::Plugin;
+use super::shadow::{self, ShadowedDatagram, ShadowedStream};
 
 pub struct Handler {
     pub address: String,
     pub port: u16,
     pub cipher: String,
     pub password: String,
+    pub plugin: Option<Arc<Plugin>>,
+    // Carries datagrams over a TCP stream, which also goes through the
+    // plugin if any.
+    pub udp_over_tcp: bool,
 }
 
 #[async_trait]
@@ -23,11 +29,20 @@ impl UdpOutboundHandler for Handler {
     type Datagram = AnyOutboundDatagram;
 
     fn connect_addr(&self) -> Option<OutboundConnect> {
+        if let Some(plugin) = self.plugin.as_ref().filter(|_| self.udp_over_tcp) {
+            plugin.ensure_running();
+            let addr = plugin.local_addr();
+            return Some(OutboundConnect::Proxy(addr.ip().to_string(), addr.port()));
+        }
         Some(OutboundConnect::Proxy(self.address.clone(), self.port))
     }
 
     fn transport_type(&self) -> DatagramTransportType {
-        DatagramTransportType::Datagram
+        if self.udp_over_tcp {
+            DatagramTransportType::Stream
+        } else {
+            DatagramTransportType::Datagram
+        }
     }
 
     async fn handle<'a>(
@@ -35,6 +50,23 @@ impl UdpOutboundHandler for Handler {
         sess: &'a Session,
         transport: Option<OutboundTransport<Self::UStream, Self::Datagram>>,
     ) -> io::Result<Self::Datagram> {
+        if self.udp_over_tcp {
+            let stream = if let Some(OutboundTransport::Stream(stream)) = transport {
+                stream
+            } else {
+                return Err(io::Error::new(io::ErrorKind::Other, "invalid input"));
+            };
+            let mut stream = ShadowedStream::new(stream, &self.cipher, &self.password)?;
+            let mut buf = BytesMut::new();
+            uot::magic_address().write_buf(&mut buf, SocksAddrWireType::PortLast);
+            uot::write_request(&sess.destination, &mut buf);
+            stream.write_all(&buf).await?;
+            return Ok(Box::new(uot::ClientDatagram::new(
+                stream,
+                &sess.destination,
+            )));
+        }
+
         let server_addr = SocksAddr::try_from((&self.address, self.port))?;
 
         let socket = if let Some(OutboundTransport::Datagram(socket)) = transport {
@@ -45,17 +77,15 @@ impl UdpOutboundHandler for Handler {
 
         let dgram = ShadowedDatagram::new(&self.cipher, &self.password)?;
 
-        let destination = match &sess.destination {
-            SocksAddr::Domain(domain, port) => {
-                Some(SocksAddr::Domain(domain.to_owned(), port.to_owned()))
-            }
-            _ => None,
-        };
+        let peers = DomainPeers::default();
+        if let SocksAddr::Domain(..) = &sess.destination {
+            peers.add_pending(&sess.destination);
+        }
 
         Ok(Box::new(Datagram {
             dgram,
             socket,
-            destination,
+            peers,
             server_addr,
         }))
     }
@@ -64,7 +94,7 @@ impl UdpOutboundHandler for Handler {
 pub struct Datagram {
     pub dgram: ShadowedDatagram,
     pub socket: Box<dyn OutboundDatagram>,
-    pub destination: Option<SocksAddr>,
+    pub peers: DomainPeers,
     pub server_addr: SocksAddr,
 }
 
@@ -78,11 +108,12 @@ impl OutboundDatagram for Datagram {
         let dgram = Arc::new(self.dgram);
         let (r, s) = self.socket.split();
         (
-            Box::new(DatagramRecvHalf(dgram.clone(), r, self.destination)),
+            Box::new(DatagramRecvHalf(dgram.clone(), r, self.peers.clone())),
             Box::new(DatagramSendHalf {
                 dgram,
                 send_half: s,
                 server_addr: self.server_addr,
+                peers: self.peers,
             }),
         )
     }
@@ -91,7 +122,7 @@ impl OutboundDatagram for Datagram {
 pub struct DatagramRecvHalf(
     Arc<ShadowedDatagram>,
     Box<dyn OutboundDatagramRecvHalf>,
-    Option<SocksAddr>,
+    DomainPeers,
 );
 
 #[async_trait]
@@ -107,10 +138,7 @@ impl OutboundDatagramRecvHalf for DatagramRecvHalf {
         assert!(payload_len <= buf.len());
         buf[..payload_len]
             .copy_from_slice(&plaintext[src_addr.size()..src_addr.size() + payload_len]);
-        Ok((
-            payload_len,
-            self.2.as_ref().map(Clone::clone).unwrap_or(src_addr),
-        ))
+        Ok((payload_len, self.2.source(src_addr)))
     }
 }
 
@@ -118,11 +146,15 @@ pub struct DatagramSendHalf {
     dgram: Arc<ShadowedDatagram>,
     send_half: Box<dyn OutboundDatagramSendHalf>,
     server_addr: SocksAddr,
+    peers: DomainPeers,
 }
 
 #[async_trait]
 impl OutboundDatagramSendHalf for DatagramSendHalf {
     async fn send_to(&mut self, buf: &[u8], target: &SocksAddr) -> io::Result<usize> {
+        if let SocksAddr::Domain(..) = target {
+            self.peers.add_pending(target);
+        }
         let mut send_buf = BytesMut::new();
         target.write_buf(&mut send_buf, SocksAddrWireType::PortLast);
         send_buf.put_slice(buf);
@@ -0,0 +1,120 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+// Number of salts kept in each generation, at most twice the number are
+// remembered.
+const GENERATION_CAPACITY: usize = 100_000;
+
+/// Remembers salts seen recently, requests reusing any of them are replays
+/// and must be rejected. Salts are kept in two generations, the older one is
+/// dropped when the current one is full.
+pub struct ReplayFilter {
+    inner: Mutex<Generations>,
+}
+
+struct Generations {
+    current: HashSet<Vec<u8>>,
+    previous: HashSet<Vec<u8>>,
+}
+
+impl ReplayFilter {
+    pub fn new() -> Self {
+        ReplayFilter {
+            inner: Mutex::new(Generations {
+                current: HashSet::new(),
+                previous: HashSet::new(),
+            }),
+        }
+    }
+
+    /// Records the salt, returns false if it has been seen before.
+    pub fn check_and_insert(&self, salt: &[u8]) -> bool {
+        let mut g = self.inner.lock().unwrap();
+        if g.current.contains(salt) || g.previous.contains(salt) {
+            return false;
+        }
+        if g.current.len() >= GENERATION_CAPACITY {
+            g.previous = std::mem::take(&mut g.current);
+        }
+        g.current.insert(salt.to_vec());
+        true
+    }
+}
+
+impl Default for ReplayFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Number of packet IDs tracked by a packet window.
+const PACKET_WINDOW_SIZE: u64 = 128;
+
+/// Remembers packet IDs of a 2022 UDP session in a sliding window, packets
+/// reusing an ID or older than the window are replays.
+#[derive(Default)]
+pub struct PacketWindow {
+    // The greatest ID seen, bit i of the mask is set if `last - i` is seen.
+    last: u64,
+    mask: u128,
+}
+
+impl PacketWindow {
+    /// Records the packet ID, returns false if it's a replay.
+    pub fn check_and_insert(&mut self, id: u64) -> bool {
+        if self.mask == 0 || id > self.last {
+            let shift = id.wrapping_sub(self.last);
+            if self.mask == 0 || shift >= PACKET_WINDOW_SIZE {
+                self.mask = 1;
+            } else {
+                self.mask = (self.mask << shift) | 1;
+            }
+            self.last = id;
+            return true;
+        }
+        let offset = self.last - id;
+        if offset >= PACKET_WINDOW_SIZE || self.mask & (1 << offset) != 0 {
+            return false;
+        }
+        self.mask |= 1 << offset;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_filter() {
+        let filter = ReplayFilter::new();
+        assert!(filter.check_and_insert(b"salt1"));
+        assert!(filter.check_and_insert(b"salt2"));
+        assert!(!filter.check_and_insert(b"salt1"));
+
+        // Salts survive one generation switch and are forgotten after two.
+        for i in 0..GENERATION_CAPACITY {
+            assert!(filter.check_and_insert(&(i as u64).to_be_bytes()));
+        }
+        assert!(!filter.check_and_insert(b"salt2"));
+        for i in GENERATION_CAPACITY..GENERATION_CAPACITY * 2 {
+            assert!(filter.check_and_insert(&(i as u64).to_be_bytes()));
+        }
+        assert!(filter.check_and_insert(b"salt2"));
+    }
+
+    #[test]
+    fn test_packet_window() {
+        let mut window = PacketWindow::default();
+        assert!(window.check_and_insert(0));
+        assert!(!window.check_and_insert(0));
+        assert!(window.check_and_insert(2));
+        assert!(window.check_and_insert(1));
+        assert!(!window.check_and_insert(1));
+        assert!(window.check_and_insert(200));
+        assert!(!window.check_and_insert(2));
+        assert!(window.check_and_insert(200 - 127));
+        assert!(!window.check_and_insert(200 - 128));
+        assert!(!window.check_and_insert(200));
+    }
+}
@@ -1,4 +1,8 @@
+use std::convert::TryFrom;
 use std::mem::MaybeUninit;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{cmp::min, io, pin::Pin};
 
 use byteorder::{BigEndian, ByteOrder};
@@ -8,6 +12,7 @@ use futures::{
     task::{Context, Poll},
 };
 use log::*;
+use lru::LruCache;
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
@@ -15,11 +20,93 @@ use crate::common::crypto::{
     aead::{AeadCipher, AeadDecryptor, AeadEncryptor},
     Cipher, Decryptor, Encryptor, SizedCipher,
 };
+use crate::session::{SocksAddr, SocksAddrWireType};
 
-use super::crypto::{hkdf_sha1, kdf, ShadowsocksNonceSequence};
+use super::crypto::{
+    blake3_subkey, decode_psk, hkdf_sha1, kdf, FixedNonceSequence, HeaderCipher,
+    ShadowsocksNonceSequence,
+};
+use super::replay::{PacketWindow, ReplayFilter};
+
+// Prefix of the 2022 ciphers of SIP022, e.g. 2022-blake3-aes-128-gcm.
+const PREFIX_2022: &str = "2022-blake3-";
+
+// Types of 2022 stream and packet headers.
+const REQUEST_TYPE: u8 = 0;
+const RESPONSE_TYPE: u8 = 1;
+
+// Maximum difference in seconds of timestamps in 2022 headers from the
+// local time.
+const MAX_TIME_DIFF: u64 = 30;
+
+// Maximum padding of 2022 requests without initial payload.
+const MAX_PADDING_SIZE: usize = 900;
+
+// Maximum number of 2022 UDP sessions an inbound keeps track of.
+const MAX_UDP_SESSIONS: usize = 1024;
+
+/// Creates the cipher and the pre-shared key of a method, returns whether
+/// the method is a 2022 one as well. Passwords of 2022 ciphers are base64
+/// encoded keys.
+fn new_cipher(method: &str, password: &str) -> io::Result<(AeadCipher, Vec<u8>, bool)> {
+    let (name, is_2022) = match method.strip_prefix(PREFIX_2022) {
+        Some(name @ ("aes-128-gcm" | "aes-256-gcm")) => (name, true),
+        Some(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("unsupported cipher: {}", method),
+            ));
+        }
+        None => (method, false),
+    };
+    let cipher = AeadCipher::new(name).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            format!("create AEAD cipher failed: {}", e),
+        )
+    })?;
+    let psk = if is_2022 {
+        decode_psk(password, cipher.key_len())
+    } else {
+        kdf(password, cipher.key_len())
+    }
+    .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("derive key failed: {}", e)))?;
+    Ok((cipher, psk, is_2022))
+}
+
+fn subkey(is_2022: bool, psk: &[u8], salt: &[u8], size: usize) -> io::Result<Vec<u8>> {
+    if is_2022 {
+        return Ok(blake3_subkey(psk, salt, size));
+    }
+    hkdf_sha1(
+        psk,
+        salt,
+        String::from("ss-subkey").as_bytes().to_vec(),
+        size,
+    )
+    .map_err(|_| crypto_err())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn check_timestamp(timestamp: u64) -> io::Result<()> {
+    if timestamp.abs_diff(now()) > MAX_TIME_DIFF {
+        return Err(header_err("invalid timestamp"));
+    }
+    Ok(())
+}
 
 enum ReadState {
     WaitingSalt,
+    // The fixed-length header of 2022 requests and responses.
+    WaitingHeader,
+    // The variable-length header of 2022 requests.
+    WaitingRequest(usize),
     WaitingLength,
     WaitingData(usize),
     PendingData(usize),
@@ -28,6 +115,8 @@ enum ReadState {
 enum WriteState {
     WaitingSalt,
     PendingSalt(usize, usize),
+    // The headers of 2022 requests and responses, the salt is written along.
+    WaitingHeader,
     WaitingChunk,
     PendingChunk(usize, (usize, usize)),
 }
@@ -36,6 +125,10 @@ pub struct ShadowedStream<T> {
     inner: T,
     cipher: AeadCipher,
     psk: Vec<u8>,
+    is_2022: bool,
+    server: bool,
+    // The salt of the request, a 2022 response carries it.
+    request_salt: Option<Vec<u8>>,
     enc: Option<AeadEncryptor<ShadowsocksNonceSequence>>,
     dec: Option<AeadDecryptor<ShadowsocksNonceSequence>>,
     read_buf: BytesMut,
@@ -43,23 +136,24 @@ pub struct ShadowedStream<T> {
     read_state: ReadState,
     write_state: WriteState,
     read_pos: usize,
+    replay_filter: Option<Arc<ReplayFilter>>,
+    // The salt read, checked against the filter once the first chunk is
+    // authenticated.
+    unchecked_salt: Option<Vec<u8>>,
 }
 
 impl<T> ShadowedStream<T> {
+    /// Creates a client stream. With 2022 ciphers, the first write must
+    /// begin with the target address, which goes into the request header.
     pub fn new(s: T, cipher: &str, password: &str) -> io::Result<Self> {
-        let cipher = AeadCipher::new(cipher).map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                format!("create AEAD cipher failed: {}", e),
-            )
-        })?;
-        let psk = kdf(password, cipher.key_len()).map_err(|e| {
-            io::Error::new(io::ErrorKind::Other, format!("derive key failed: {}", e))
-        })?;
+        let (cipher, psk, is_2022) = new_cipher(cipher, password)?;
         Ok(ShadowedStream {
             inner: s,
             cipher,
             psk,
+            is_2022,
+            server: false,
+            request_salt: None,
             enc: None,
             dec: None,
 
@@ -69,8 +163,24 @@ impl<T> ShadowedStream<T> {
             read_state: ReadState::WaitingSalt,
             write_state: WriteState::WaitingSalt,
             read_pos: 0,
+            replay_filter: None,
+            unchecked_salt: None,
         })
     }
+
+    /// Makes it a server stream, which reads requests and writes responses.
+    /// Only 2022 ciphers tell them apart.
+    pub fn server(mut self) -> Self {
+        self.server = true;
+        self
+    }
+
+    /// Rejects streams with salts seen by the filter, salts of both
+    /// directions are recorded.
+    pub fn with_replay_filter(mut self, filter: Arc<ReplayFilter>) -> Self {
+        self.replay_filter = Some(filter);
+        self
+    }
 }
 
 trait ReadExt {
@@ -112,10 +222,44 @@ where
     }
 }
 
+impl<T> ShadowedStream<T> {
+    // Records the salt of the stream after the first chunk decrypts, so
+    // junk from probers never goes into the filter.
+    fn check_salt(&mut self) -> io::Result<()> {
+        if let (Some(salt), Some(filter)) = (self.unchecked_salt.take(), &self.replay_filter) {
+            if !filter.check_and_insert(&salt) {
+                return Err(replay_err());
+            }
+        }
+        Ok(())
+    }
+}
+
 pub fn crypto_err() -> io::Error {
     io::Error::new(io::ErrorKind::Other, "crypto error")
 }
 
+fn replay_err() -> io::Error {
+    io::Error::new(io::ErrorKind::Other, "replayed salt")
+}
+
+fn header_err(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// Seals `data` as a chunk appended to `buf`.
+fn seal_chunk(
+    enc: &mut AeadEncryptor<ShadowsocksNonceSequence>,
+    buf: &mut BytesMut,
+    data: &[u8],
+) -> io::Result<()> {
+    let mut chunk = BytesMut::with_capacity(data.len() + 16);
+    chunk.put_slice(data);
+    enc.encrypt(&mut chunk).map_err(|_| crypto_err())?;
+    buf.put_slice(&chunk);
+    Ok(())
+}
+
 impl<T> AsyncRead for ShadowedStream<T>
 where
     T: AsyncRead + Unpin,
@@ -131,13 +275,15 @@ where
                     // read salt and create decryptor
                     let salt_size = self.cipher.key_len();
                     ready!(self.poll_read_exact(cx, salt_size))?;
-                    let key = hkdf_sha1(
+                    if self.replay_filter.is_some() {
+                        self.unchecked_salt = Some(self.read_buf[..salt_size].to_vec());
+                    }
+                    let key = subkey(
+                        self.is_2022,
                         &self.psk,
                         &self.read_buf[..salt_size],
-                        String::from("ss-subkey").as_bytes().to_vec(),
                         self.cipher.key_len(),
-                    )
-                    .map_err(|_| crypto_err())?;
+                    )?;
                     let nonce =
                         super::crypto::ShadowsocksNonceSequence::new(self.cipher.nonce_len());
                     let dec = self
@@ -145,10 +291,76 @@ where
                         .decryptor(&key, nonce)
                         .map_err(|_| crypto_err())?;
                     self.dec.replace(dec);
+                    if self.is_2022 && self.server {
+                        self.request_salt = Some(self.read_buf[..salt_size].to_vec());
+                    }
                     self.read_buf.clear();
 
-                    // ready to read payload length
-                    self.read_state = ReadState::WaitingLength;
+                    // ready to read the header or payload length
+                    if self.is_2022 {
+                        self.read_state = ReadState::WaitingHeader;
+                    } else {
+                        self.read_state = ReadState::WaitingLength;
+                    }
+                }
+                ReadState::WaitingHeader => {
+                    // type, timestamp, request salt of responses, length
+                    let me = &mut *self;
+                    let salt_size = me.cipher.key_len();
+                    let header_size = if me.server { 11 } else { 11 + salt_size };
+                    ready!(me.poll_read_exact(cx, header_size + me.cipher.tag_len()))?;
+                    let dec = me.dec.as_mut().expect("uninitialized cipher");
+                    dec.decrypt(&mut me.read_buf).map_err(|_| crypto_err())?;
+                    me.check_salt()?;
+                    let header = &me.read_buf[..header_size];
+                    let expected_type = if me.server {
+                        REQUEST_TYPE
+                    } else {
+                        RESPONSE_TYPE
+                    };
+                    if header[0] != expected_type {
+                        return Poll::Ready(Err(header_err("invalid stream type")));
+                    }
+                    check_timestamp(BigEndian::read_u64(&header[1..9]))?;
+                    if !me.server && me.request_salt.as_deref() != Some(&header[9..9 + salt_size]) {
+                        return Poll::Ready(Err(header_err("request salt mismatch")));
+                    }
+                    let len = BigEndian::read_u16(&header[header_size - 2..]) as usize;
+
+                    // requests continue with the variable-length header,
+                    // responses with the initial payload
+                    if me.server {
+                        me.read_state = ReadState::WaitingRequest(len);
+                    } else {
+                        me.read_state = ReadState::WaitingData(len);
+                    }
+                }
+                ReadState::WaitingRequest(n) => {
+                    // address, padding length, padding, initial payload
+                    let me = &mut *self;
+                    ready!(me.poll_read_exact(cx, n + me.cipher.tag_len()))?;
+                    let dec = me.dec.as_mut().expect("uninitialized cipher");
+                    dec.decrypt(&mut me.read_buf).map_err(|_| crypto_err())?;
+                    let header = &me.read_buf[..n];
+                    let addr_size =
+                        SocksAddr::try_from((header, SocksAddrWireType::PortLast))?.size();
+                    if header.len() < addr_size + 2 {
+                        return Poll::Ready(Err(header_err("invalid request header")));
+                    }
+                    let padding_size = BigEndian::read_u16(&header[addr_size..]) as usize;
+                    let payload_start = addr_size + 2 + padding_size;
+                    if header.len() < payload_start {
+                        return Poll::Ready(Err(header_err("invalid request header")));
+                    }
+
+                    // the address and initial payload are read as data, the
+                    // address is read by the inbound
+                    let mut data = BytesMut::with_capacity(n - 2 - padding_size);
+                    data.put_slice(&header[..addr_size]);
+                    data.put_slice(&header[payload_start..]);
+                    let len = data.len();
+                    me.read_buf = data;
+                    me.read_state = ReadState::PendingData(len);
                 }
                 ReadState::WaitingLength => {
                     // read and decipher payload length
@@ -163,6 +375,7 @@ where
                     }
                     let dec = me.dec.as_mut().expect("uninitialized cipher");
                     dec.decrypt(&mut me.read_buf).map_err(|_| crypto_err())?;
+                    me.check_salt()?;
                     let payload_len = BigEndian::read_u16(&me.read_buf) as usize;
 
                     // ready to read payload
@@ -176,8 +389,13 @@ where
                     let dec = me.dec.as_mut().expect("uninitialized cipher");
                     dec.decrypt(&mut me.read_buf).map_err(|_| crypto_err())?;
 
-                    // ready to read plaintext payload into buf
-                    me.read_state = ReadState::PendingData(n);
+                    // ready to read plaintext payload into buf, the initial
+                    // payload of 2022 responses can be empty
+                    if n == 0 {
+                        me.read_state = ReadState::WaitingLength;
+                    } else {
+                        me.read_state = ReadState::PendingData(n);
+                    }
                 }
                 ReadState::PendingData(n) => {
                     let to_read = min(buf.remaining(), n);
@@ -218,14 +436,16 @@ where
                     for i in 0..salt_size {
                         self.write_buf[i] = rng.gen();
                     }
+                    if let Some(filter) = self.replay_filter.as_ref() {
+                        filter.check_and_insert(&self.write_buf[..salt_size]);
+                    }
 
-                    let key = hkdf_sha1(
+                    let key = subkey(
+                        self.is_2022,
                         &self.psk,
                         &self.write_buf[..salt_size],
-                        String::from("ss-subkey").as_bytes().to_vec(),
                         self.cipher.key_len(),
-                    )
-                    .map_err(|_| crypto_err())?;
+                    )?;
                     let nonce =
                         super::crypto::ShadowsocksNonceSequence::new(self.cipher.nonce_len());
                     let enc = self
@@ -235,6 +455,14 @@ where
 
                     self.enc.replace(enc);
 
+                    if self.is_2022 {
+                        if !self.server {
+                            self.request_salt = Some(self.write_buf[..salt_size].to_vec());
+                        }
+                        self.write_state = WriteState::WaitingHeader;
+                        continue;
+                    }
+
                     // ready to write salt
                     self.write_state = WriteState::PendingSalt(salt_size, 0);
                 }
@@ -258,10 +486,58 @@ where
                         self.write_state = WriteState::PendingSalt(total, written + nw);
                     }
                 }
+                WriteState::WaitingHeader => {
+                    let me = &mut *self;
+                    let mut fixed = BytesMut::new();
+                    let mut variable = BytesMut::new();
+                    let consumed;
+                    if me.server {
+                        // type, timestamp, request salt, length of the
+                        // initial payload
+                        let request_salt = me
+                            .request_salt
+                            .as_ref()
+                            .ok_or_else(|| header_err("response before request"))?;
+                        consumed = min(buf.len(), 0xffff);
+                        fixed.put_u8(RESPONSE_TYPE);
+                        fixed.put_u64(now());
+                        fixed.put_slice(request_salt);
+                        fixed.put_u16(consumed as u16);
+                        variable.put_slice(&buf[..consumed]);
+                    } else {
+                        // address, padding length, padding, initial payload
+                        let addr_size =
+                            SocksAddr::try_from((buf, SocksAddrWireType::PortLast))?.size();
+                        let payload_size = min(buf.len() - addr_size, 0xffff - addr_size - 2);
+                        let padding_size = if payload_size == 0 {
+                            StdRng::from_entropy().gen_range(1..=MAX_PADDING_SIZE)
+                        } else {
+                            0
+                        };
+                        consumed = addr_size + payload_size;
+                        variable.put_slice(&buf[..addr_size]);
+                        variable.put_u16(padding_size as u16);
+                        variable.resize(variable.len() + padding_size, 0);
+                        variable.put_slice(&buf[addr_size..consumed]);
+
+                        // type, timestamp, length of the variable-length header
+                        fixed.put_u8(REQUEST_TYPE);
+                        fixed.put_u64(now());
+                        fixed.put_u16(variable.len() as u16);
+                    }
+                    let enc = me.enc.as_mut().expect("uninitialized cipher");
+                    seal_chunk(enc, &mut me.write_buf, &fixed)?;
+                    seal_chunk(enc, &mut me.write_buf, &variable)?;
+
+                    // ready to write salt and headers
+                    me.write_state = WriteState::PendingChunk(consumed, (me.write_buf.len(), 0));
+                }
                 WriteState::WaitingChunk => {
                     let me = &mut *self;
-                    // 0x3fff is the mandatory maximum size in ss spec
-                    let consume_len = min(buf.len(), 0x3fff);
+                    // 0x3fff is the mandatory maximum size in ss spec, 0xffff
+                    // for 2022 ciphers
+                    let max_size = if me.is_2022 { 0xffff } else { 0x3fff };
+                    let consume_len = min(buf.len(), max_size);
                     let enc = me.enc.as_mut().expect("uninitialized cipher");
 
                     // seal payload length
@@ -328,27 +604,90 @@ fn short_packet() -> io::Error {
     io::Error::new(io::ErrorKind::Other, "short packet")
 }
 
+// The 2022 UDP session of a client.
+struct ClientSession {
+    id: u64,
+    packet_id: u64,
+    // The latest session of the server and its packet window.
+    server: Option<(u64, PacketWindow)>,
+}
+
+// A 2022 UDP session of a client on the server.
+struct ServerSession {
+    client_id: u64,
+    client_window: PacketWindow,
+    id: u64,
+    packet_id: u64,
+}
+
 pub struct ShadowedDatagram {
     cipher: AeadCipher,
     psk: Vec<u8>,
+    replay_filter: Option<Arc<ReplayFilter>>,
+    // The separate header cipher of 2022 ciphers.
+    header_cipher: Option<HeaderCipher>,
+    session: Mutex<ClientSession>,
+    client_sessions: Mutex<Option<LruCache<SocketAddr, ServerSession>>>,
 }
 
 impl ShadowedDatagram {
     pub fn new(cipher: &str, password: &str) -> io::Result<Self> {
-        let cipher = AeadCipher::new(cipher).map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                format!("create AEAD cipher failed: {}", e),
-            )
-        })?;
-        let psk = kdf(password, cipher.key_len()).map_err(|e| {
-            io::Error::new(io::ErrorKind::Other, format!("derive key failed: {}", e))
-        })?;
-        Ok(ShadowedDatagram { cipher, psk })
+        let (cipher, psk, is_2022) = new_cipher(cipher, password)?;
+        let header_cipher = if is_2022 {
+            Some(HeaderCipher::new(&psk).map_err(|_| crypto_err())?)
+        } else {
+            None
+        };
+        Ok(ShadowedDatagram {
+            cipher,
+            psk,
+            replay_filter: None,
+            header_cipher,
+            session: Mutex::new(ClientSession {
+                id: rand::random(),
+                packet_id: 0,
+                server: None,
+            }),
+            client_sessions: Mutex::new(None),
+        })
+    }
+
+    /// Rejects packets with salts seen by the filter, salts of both
+    /// directions are recorded. Packets of 2022 ciphers are checked by
+    /// packet IDs instead.
+    pub fn with_replay_filter(mut self, filter: Arc<ReplayFilter>) -> Self {
+        self.replay_filter = Some(filter);
+        self
     }
 
-    /// Decrypts a message. On success, returns the plaintext.
+    /// Decrypts a message from the server. On success, returns the plaintext.
     pub fn decrypt(&self, mut buf: BytesMut) -> io::Result<Bytes> {
+        if self.header_cipher.is_some() {
+            let (session_id, packet_id, mut body) = self.open_packet(buf)?;
+            // type, timestamp, client session ID, padding length
+            if body.len() < 19 || body[0] != RESPONSE_TYPE {
+                return Err(header_err("invalid packet header"));
+            }
+            check_timestamp(BigEndian::read_u64(&body[1..9]))?;
+            let padding_size = BigEndian::read_u16(&body[17..19]) as usize;
+            if body.len() < 19 + padding_size {
+                return Err(short_packet());
+            }
+            let mut session = self.session.lock().unwrap();
+            if BigEndian::read_u64(&body[9..17]) != session.id {
+                return Err(header_err("client session ID mismatch"));
+            }
+            // A new server session, e.g. the server restarts.
+            if !matches!(session.server, Some((id, _)) if id == session_id) {
+                session.server = Some((session_id, PacketWindow::default()));
+            }
+            let (_, window) = session.server.as_mut().unwrap();
+            if !window.check_and_insert(packet_id) {
+                return Err(replay_err());
+            }
+            return Ok(body.split_off(19 + padding_size).freeze());
+        }
+
         let salt_size = self.cipher.key_len();
         let tag_len = self.cipher.tag_len();
         let buf_len = buf.len();
@@ -359,13 +698,7 @@ impl ShadowedDatagram {
 
         let salt = buf.split_to(salt_size);
 
-        let key = hkdf_sha1(
-            &self.psk,
-            &salt,
-            String::from("ss-subkey").as_bytes().to_vec(),
-            self.cipher.key_len(),
-        )
-        .map_err(|_| crypto_err())?;
+        let key = subkey(false, &self.psk, &salt, self.cipher.key_len())?;
         let nonce = ShadowsocksNonceSequence::new(self.cipher.nonce_len());
         let mut dec = self
             .cipher
@@ -379,17 +712,39 @@ impl ShadowedDatagram {
 
         dec.decrypt(&mut buf).map_err(|_| crypto_err())?;
 
+        // Only salts of authenticated packets are recorded.
+        if let Some(filter) = self.replay_filter.as_ref() {
+            if !filter.check_and_insert(&salt) {
+                return Err(replay_err());
+            }
+        }
+
         let _ = buf.split_off(buf_len - salt_size - tag_len);
 
         Ok(buf.freeze())
     }
 
-    /// Encrypts a message. On success, returns the ciphertext.
+    /// Encrypts a message to the server. On success, returns the ciphertext.
     pub fn encrypt(&self, mut buf: BytesMut) -> io::Result<Bytes> {
         if buf.is_empty() {
             return Ok(Bytes::new());
         }
 
+        if self.header_cipher.is_some() {
+            let (session_id, packet_id) = {
+                let mut session = self.session.lock().unwrap();
+                session.packet_id += 1;
+                (session.id, session.packet_id - 1)
+            };
+            // type, timestamp, padding length
+            let mut body = BytesMut::with_capacity(11 + buf.len());
+            body.put_u8(REQUEST_TYPE);
+            body.put_u64(now());
+            body.put_u16(0);
+            body.put_slice(&buf);
+            return self.seal_packet(session_id, packet_id, body);
+        }
+
         let salt_size = self.cipher.key_len();
 
         let mut buffer = BytesMut::new(); // TODO optimize
@@ -400,14 +755,16 @@ impl ShadowedDatagram {
         for i in 0..salt_size {
             buffer[i] = rng.gen();
         }
+        if let Some(filter) = self.replay_filter.as_ref() {
+            filter.check_and_insert(&buffer[..salt_size]);
+        }
 
-        let key = hkdf_sha1(
+        let key = subkey(
+            false,
             &self.psk,
             &buffer[..salt_size],
-            String::from("ss-subkey").as_bytes().to_vec(),
             self.cipher.key_len(),
-        )
-        .map_err(|_| crypto_err())?;
+        )?;
         let nonce = ShadowsocksNonceSequence::new(self.cipher.nonce_len());
         let mut enc = self
             .cipher
@@ -420,4 +777,274 @@ impl ShadowedDatagram {
 
         Ok(buffer.freeze())
     }
+
+    /// Decrypts a message from a client. On success, returns the plaintext.
+    pub fn decrypt_from(&self, buf: BytesMut, client: &SocketAddr) -> io::Result<Bytes> {
+        if self.header_cipher.is_none() {
+            return self.decrypt(buf);
+        }
+        let (session_id, packet_id, mut body) = self.open_packet(buf)?;
+        // type, timestamp, padding length
+        if body.len() < 11 || body[0] != REQUEST_TYPE {
+            return Err(header_err("invalid packet header"));
+        }
+        check_timestamp(BigEndian::read_u64(&body[1..9]))?;
+        let padding_size = BigEndian::read_u16(&body[9..11]) as usize;
+        if body.len() < 11 + padding_size {
+            return Err(short_packet());
+        }
+        let mut sessions = self.client_sessions.lock().unwrap();
+        let sessions = sessions.get_or_insert_with(|| LruCache::new(MAX_UDP_SESSIONS));
+        if !matches!(sessions.peek(client), Some(s) if s.client_id == session_id) {
+            sessions.put(
+                *client,
+                ServerSession {
+                    client_id: session_id,
+                    client_window: PacketWindow::default(),
+                    id: rand::random(),
+                    packet_id: 0,
+                },
+            );
+        }
+        let session = sessions.get_mut(client).unwrap();
+        if !session.client_window.check_and_insert(packet_id) {
+            return Err(replay_err());
+        }
+        Ok(body.split_off(11 + padding_size).freeze())
+    }
+
+    /// Encrypts a message to a client. On success, returns the ciphertext.
+    pub fn encrypt_to(&self, buf: BytesMut, client: &SocketAddr) -> io::Result<Bytes> {
+        if self.header_cipher.is_none() {
+            return self.encrypt(buf);
+        }
+        let (client_id, session_id, packet_id) = {
+            let mut sessions = self.client_sessions.lock().unwrap();
+            let session = sessions
+                .as_mut()
+                .and_then(|s| s.get_mut(client))
+                .ok_or_else(|| header_err("unknown client session"))?;
+            session.packet_id += 1;
+            (session.client_id, session.id, session.packet_id - 1)
+        };
+        // type, timestamp, client session ID, padding length
+        let mut body = BytesMut::with_capacity(19 + buf.len());
+        body.put_u8(RESPONSE_TYPE);
+        body.put_u64(now());
+        body.put_u64(client_id);
+        body.put_u16(0);
+        body.put_slice(&buf);
+        self.seal_packet(session_id, packet_id, body)
+    }
+
+    // Seals a 2022 packet, the session ID and packet ID make the separate
+    // header, which is the nonce of the body as well.
+    fn seal_packet(
+        &self,
+        session_id: u64,
+        packet_id: u64,
+        mut body: BytesMut,
+    ) -> io::Result<Bytes> {
+        let mut header = [0u8; 16];
+        BigEndian::write_u64(&mut header[..8], session_id);
+        BigEndian::write_u64(&mut header[8..], packet_id);
+        let key = blake3_subkey(&self.psk, &header[..8], self.cipher.key_len());
+        let mut enc = self
+            .cipher
+            .encryptor(&key, FixedNonceSequence(header[4..].to_vec()))
+            .map_err(|_| crypto_err())?;
+        enc.encrypt(&mut body).map_err(|_| crypto_err())?;
+        self.header_cipher
+            .as_ref()
+            .expect("uninitialized cipher")
+            .encrypt(&mut header);
+        let mut packet = BytesMut::with_capacity(header.len() + body.len());
+        packet.put_slice(&header);
+        packet.put_slice(&body);
+        Ok(packet.freeze())
+    }
+
+    // Opens a 2022 packet, returns the session ID, packet ID and the body.
+    fn open_packet(&self, mut buf: BytesMut) -> io::Result<(u64, u64, BytesMut)> {
+        let tag_len = self.cipher.tag_len();
+        if buf.len() < 16 + tag_len {
+            return Err(short_packet());
+        }
+        let mut body = buf.split_off(16);
+        self.header_cipher
+            .as_ref()
+            .expect("uninitialized cipher")
+            .decrypt(&mut buf);
+        let session_id = BigEndian::read_u64(&buf[..8]);
+        let packet_id = BigEndian::read_u64(&buf[8..]);
+        let key = blake3_subkey(&self.psk, &buf[..8], self.cipher.key_len());
+        let mut dec = self
+            .cipher
+            .decryptor(&key, FixedNonceSequence(buf[4..].to_vec()))
+            .map_err(|_| crypto_err())?;
+        dec.decrypt(&mut body).map_err(|_| crypto_err())?;
+        body.truncate(body.len() - tag_len);
+        Ok((session_id, packet_id, body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    const KEY_128: &str = "AAECAwQFBgcICQoLDA0ODw==";
+    const KEY_256: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+
+    #[test]
+    fn test_stream() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        for (method, password) in [
+            ("aes-128-gcm", "password"),
+            ("2022-blake3-aes-128-gcm", KEY_128),
+            ("2022-blake3-aes-256-gcm", KEY_256),
+        ] {
+            rt.block_on(async {
+                let (a, b) = tokio::io::duplex(1024);
+                let mut client = ShadowedStream::new(a, method, password).unwrap();
+                let mut server = ShadowedStream::new(b, method, password)
+                    .unwrap()
+                    .server()
+                    .with_replay_filter(Arc::new(ReplayFilter::new()));
+                let target = SocksAddr::try_from(("example.com", 443)).unwrap();
+                let payload = vec![7u8; 100_000];
+
+                let p = payload.clone();
+                let client_task = tokio::spawn(async move {
+                    let mut buf = BytesMut::new();
+                    target.write_buf(&mut buf, SocksAddrWireType::PortLast);
+                    client.write_all(&buf).await.unwrap();
+                    client.write_all(&p).await.unwrap();
+                    let mut resp = vec![0u8; p.len()];
+                    client.read_exact(&mut resp).await.unwrap();
+                    assert_eq!(resp, p);
+                });
+
+                let addr = SocksAddr::read_from(&mut server, SocksAddrWireType::PortLast)
+                    .await
+                    .unwrap();
+                assert_eq!(addr.to_string(), "example.com:443");
+                let mut req = vec![0u8; payload.len()];
+                server.read_exact(&mut req).await.unwrap();
+                assert_eq!(req, payload);
+                server.write_all(&req).await.unwrap();
+                client_task.await.unwrap();
+            });
+        }
+    }
+
+    #[test]
+    fn test_stream_2022_wrong_key() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let (a, b) = tokio::io::duplex(1024);
+            let mut client = ShadowedStream::new(a, "2022-blake3-aes-128-gcm", KEY_128).unwrap();
+            let mut server =
+                ShadowedStream::new(b, "2022-blake3-aes-128-gcm", "AAAAAAAAAAAAAAAAAAAAAA==")
+                    .unwrap()
+                    .server();
+            let mut buf = BytesMut::new();
+            SocksAddr::try_from(("example.com", 443))
+                .unwrap()
+                .write_buf(&mut buf, SocksAddrWireType::PortLast);
+            client.write_all(&buf).await.unwrap();
+            assert!(
+                SocksAddr::read_from(&mut server, SocksAddrWireType::PortLast)
+                    .await
+                    .is_err()
+            );
+        });
+        assert!(ShadowedStream::new((), "2022-blake3-aes-256-gcm", KEY_128).is_err());
+        assert!(ShadowedStream::new((), "2022-blake3-chacha20-poly1305", KEY_256).is_err());
+    }
+
+    #[test]
+    fn test_stream_replay() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        for (method, password) in [
+            ("aes-128-gcm", "password"),
+            ("2022-blake3-aes-128-gcm", KEY_128),
+        ] {
+            rt.block_on(async {
+                let filter = Arc::new(ReplayFilter::new());
+                let mut buf = BytesMut::new();
+                SocksAddr::try_from(("example.com", 443))
+                    .unwrap()
+                    .write_buf(&mut buf, SocksAddrWireType::PortLast);
+                let (a, mut b) = tokio::io::duplex(1024);
+                let mut client = ShadowedStream::new(a, method, password).unwrap();
+                client.write_all(&buf).await.unwrap();
+                drop(client);
+                let mut request = Vec::new();
+                b.read_to_end(&mut request).await.unwrap();
+
+                let read_request = |data: Vec<u8>| {
+                    let filter = filter.clone();
+                    async move {
+                        let (mut a, b) = tokio::io::duplex(1024);
+                        a.write_all(&data).await.unwrap();
+                        drop(a);
+                        let mut server = ShadowedStream::new(b, method, password)
+                            .unwrap()
+                            .server()
+                            .with_replay_filter(filter);
+                        SocksAddr::read_from(&mut server, SocksAddrWireType::PortLast)
+                            .await
+                            .is_ok()
+                    }
+                };
+                // A probe with the salt of the request and a junk chunk
+                // doesn't record the salt.
+                let mut probe = request[..16].to_vec();
+                probe.extend_from_slice(&[0u8; 64]);
+                assert!(!read_request(probe).await);
+                assert!(read_request(request.clone()).await);
+                // replayed
+                assert!(!read_request(request).await);
+            });
+        }
+    }
+
+    #[test]
+    fn test_datagram_2022() {
+        let client = ShadowedDatagram::new("2022-blake3-aes-256-gcm", KEY_256).unwrap();
+        let server = ShadowedDatagram::new("2022-blake3-aes-256-gcm", KEY_256).unwrap();
+        let peer: SocketAddr = "127.0.0.1:1080".parse().unwrap();
+
+        let request = client.encrypt(BytesMut::from(&b"request"[..])).unwrap();
+        let plaintext = server
+            .decrypt_from(BytesMut::from(&request[..]), &peer)
+            .unwrap();
+        assert_eq!(&plaintext[..], b"request");
+        // replayed
+        assert!(server
+            .decrypt_from(BytesMut::from(&request[..]), &peer)
+            .is_err());
+
+        let response = server
+            .encrypt_to(BytesMut::from(&b"response"[..]), &peer)
+            .unwrap();
+        let plaintext = client.decrypt(BytesMut::from(&response[..])).unwrap();
+        assert_eq!(&plaintext[..], b"response");
+        assert!(client.decrypt(BytesMut::from(&response[..])).is_err());
+
+        // responses of other clients
+        let other = ShadowedDatagram::new("2022-blake3-aes-256-gcm", KEY_256).unwrap();
+        assert!(other.decrypt(BytesMut::from(&response[..])).is_err());
+    }
 }
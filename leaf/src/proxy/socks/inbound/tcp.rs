@@ -1,16 +1,63 @@
+use std::collections::HashMap;
 use std::io;
 
 use async_trait::async_trait;
 use bytes::{BufMut, BytesMut};
 use log::*;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UdpSocket;
+use tokio::sync::oneshot;
 
 use crate::{
     proxy::*,
     session::{Session, SocksAddr, SocksAddrWireType},
 };
 
-pub struct Handler;
+use super::udp::{AssociatedDatagram, Datagram};
+
+pub struct Handler {
+    users: HashMap<String, String>,
+}
+
+impl Handler {
+    /// Requires username/password authentication if `users`, mapping
+    /// usernames to passwords, is not empty.
+    pub fn new(users: HashMap<String, String>) -> Self {
+        Handler { users }
+    }
+
+    // Username/password authentication as RFC 1929, returns the username.
+    async fn authenticate(&self, stream: &mut AnyStream) -> io::Result<String> {
+        let mut buf = BytesMut::new();
+        // ver, ulen
+        buf.resize(2, 0);
+        stream.read_exact(&mut buf[..]).await?;
+        if buf[0] != 0x01 {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("unknown socks5 authentication version {}", buf[0]),
+            ));
+        }
+        // uname, plen
+        buf.resize(buf[1] as usize + 1, 0);
+        stream.read_exact(&mut buf[..]).await?;
+        let plen = buf[buf.len() - 1] as usize;
+        let username = String::from_utf8_lossy(&buf[..buf.len() - 1]).to_string();
+        // passwd
+        buf.resize(plen, 0);
+        stream.read_exact(&mut buf[..]).await?;
+        let password = String::from_utf8_lossy(&buf[..]).to_string();
+        if self.users.get(&username) != Some(&password) {
+            stream.write_all(&[0x01, 0x01]).await?;
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("socks5 authentication failed for user {}", username),
+            ));
+        }
+        stream.write_all(&[0x01, 0x00]).await?;
+        Ok(username)
+    }
+}
 
 #[async_trait]
 impl TcpInboundHandler for Handler {
@@ -44,17 +91,9 @@ impl TcpInboundHandler for Handler {
         buf.resize(nmethods, 0);
         // methods
         stream.read_exact(&mut buf[..]).await?;
-        let mut method_accepted = false;
-        let mut method_idx: u8 = 0;
-        let supported_method: u8 = 0x0;
-        for (idx, method) in buf[..].iter().enumerate() {
-            if method == &supported_method {
-                method_accepted = true;
-                method_idx = idx as u8;
-                break;
-            }
-        }
-        if !method_accepted {
+        // no authentication, or username/password if there're users
+        let supported_method: u8 = if self.users.is_empty() { 0x00 } else { 0x02 };
+        if !buf[..].contains(&supported_method) {
             stream.write_all(&[0x05, 0xff]).await?;
             return Err(io::Error::new(
                 io::ErrorKind::Other,
@@ -62,7 +101,10 @@ impl TcpInboundHandler for Handler {
             ));
         }
 
-        stream.write_all(&[0x05, method_idx]).await?;
+        stream.write_all(&[0x05, supported_method]).await?;
+        if supported_method == 0x02 {
+            sess.user = Some(self.authenticate(&mut stream).await?);
+        }
 
         // handle request
         buf.resize(3, 0);
@@ -85,7 +127,10 @@ impl TcpInboundHandler for Handler {
         let cmd = buf[1];
         // connect, udp associate
         if cmd != 0x01 && cmd != 0x03 {
-            // TODO reply?
+            // command not supported
+            stream
+                .write_all(&[0x05, 0x07, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                .await?;
             return Err(io::Error::new(
                 io::ErrorKind::Other,
                 format!("unsupported socks5 cmd {}", cmd),
@@ -108,26 +153,35 @@ impl TcpInboundHandler for Handler {
                 Ok(InboundTransport::Stream(stream, sess))
             }
             0x03 => {
+                // Allocate a relay socket for the association, on the same
+                // address the client reaches us.
+                let socket = UdpSocket::bind((sess.local_addr.ip(), 0)).await?;
                 buf.clear();
                 buf.put_u8(0x05); // version 5
                 buf.put_u8(0x0); // succeeded
                 buf.put_u8(0x0); // rsv
-                let relay_addr = SocksAddr::from(sess.local_addr);
+                let relay_addr = SocksAddr::from(socket.local_addr()?);
                 relay_addr.write_buf(&mut buf, SocksAddrWireType::PortLast);
                 stream.write_all(&buf[..]).await?;
+                debug!("udp association {} <-> {}", &sess.source, &relay_addr);
+                // The association lasts as long as the control connection.
+                let (closed_tx, closed_rx) = oneshot::channel::<()>();
                 tokio::spawn(async move {
                     let mut buf = [0u8; 1];
-                    // TODO explicitly drop resources allocated above before waiting?
-                    // if stream.read_exact(&mut buf).await.is_err() {
-                    //     // perhaps explicitly notifies the NAT manager?
-                    //     debug!("udp association end");
-                    // }
-                    if let Err(e) = stream.read_exact(&mut buf).await {
-                        // perhaps explicitly notifies the NAT manager?
-                        debug!("udp association end: {}", e);
+                    loop {
+                        match stream.read(&mut buf).await {
+                            Ok(0) | Err(_) => break,
+                            Ok(_) => continue,
+                        }
                     }
+                    debug!("udp association end");
+                    drop(closed_tx);
                 });
-                Ok(InboundTransport::Empty)
+                let socket = AssociatedDatagram::new(socket, sess.source.ip(), closed_rx);
+                Ok(InboundTransport::Datagram(
+                    Box::new(Datagram::new(Box::new(socket))),
+                    None,
+                ))
             }
             _ => Err(io::Error::new(io::ErrorKind::Other, "invalid cmd")),
         }
@@ -1,10 +1,15 @@
 use std::convert::TryFrom;
 use std::io;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
 
 use anyhow::anyhow;
 use async_trait::async_trait;
 use bytes::{BufMut, BytesMut};
+use futures::future::{self, Either};
+use futures::pin_mut;
+use tokio::net::UdpSocket;
+use tokio::sync::oneshot;
 
 use crate::{
     proxy::*,
@@ -33,6 +38,12 @@ pub struct Datagram {
     socket: Box<dyn InboundDatagram>,
 }
 
+impl Datagram {
+    pub fn new(socket: Box<dyn InboundDatagram>) -> Self {
+        Datagram { socket }
+    }
+}
+
 impl InboundDatagram for Datagram {
     fn split(
         self: Box<Self>,
@@ -65,9 +76,16 @@ impl InboundDatagramRecvHalf for DatagramRecvHalf {
         if n < 3 {
             return Err(ProxyError::DatagramWarn(anyhow!("Short message")));
         }
-        let dst_addr = SocksAddr::try_from((&recv_buf[3..], SocksAddrWireType::PortLast))
+        // Fragmentation is not supported, drop fragments as the RFC allows.
+        if recv_buf[2] != 0 {
+            return Err(ProxyError::DatagramWarn(anyhow!("Fragmented message")));
+        }
+        let dst_addr = SocksAddr::try_from((&recv_buf[3..n], SocksAddrWireType::PortLast))
             .map_err(|e| ProxyError::DatagramWarn(anyhow!("Parse target address failed: {}", e)))?;
         let header_size = 3 + dst_addr.size();
+        if n < header_size {
+            return Err(ProxyError::DatagramWarn(anyhow!("Short message")));
+        }
         let payload_size = n - header_size;
         assert!(buf.len() >= payload_size);
         (&mut buf[..payload_size])
@@ -94,3 +112,144 @@ impl InboundDatagramSendHalf for DatagramSendHalf {
         self.0.send_to(&send_buf[..], src_addr, dst_addr).await
     }
 }
+
+/// The UDP relay socket allocated for a UDP ASSOCIATE request. Only
+/// datagrams from the client are accepted, and the association ends when
+/// `closed` resolves, i.e. the control connection is closed.
+pub struct AssociatedDatagram {
+    socket: UdpSocket,
+    client_ip: IpAddr,
+    closed: oneshot::Receiver<()>,
+}
+
+impl AssociatedDatagram {
+    pub fn new(socket: UdpSocket, client_ip: IpAddr, closed: oneshot::Receiver<()>) -> Self {
+        AssociatedDatagram {
+            socket,
+            client_ip,
+            closed,
+        }
+    }
+}
+
+impl InboundDatagram for AssociatedDatagram {
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn InboundDatagramRecvHalf>,
+        Box<dyn InboundDatagramSendHalf>,
+    ) {
+        let socket = Arc::new(self.socket);
+        (
+            Box::new(AssociatedDatagramRecvHalf {
+                socket: socket.clone(),
+                client_ip: self.client_ip,
+                closed: self.closed,
+            }),
+            Box::new(AssociatedDatagramSendHalf(socket)),
+        )
+    }
+
+    fn into_std(self: Box<Self>) -> io::Result<std::net::UdpSocket> {
+        self.socket.into_std()
+    }
+}
+
+pub struct AssociatedDatagramRecvHalf {
+    socket: Arc<UdpSocket>,
+    client_ip: IpAddr,
+    closed: oneshot::Receiver<()>,
+}
+
+#[async_trait]
+impl InboundDatagramRecvHalf for AssociatedDatagramRecvHalf {
+    async fn recv_from(
+        &mut self,
+        buf: &mut [u8],
+    ) -> ProxyResult<(usize, DatagramSource, SocksAddr)> {
+        loop {
+            let recv = self.socket.recv_from(buf);
+            pin_mut!(recv);
+            match future::select(recv, &mut self.closed).await {
+                Either::Left((Ok((n, src_addr)), _)) => {
+                    if src_addr.ip() != self.client_ip {
+                        continue;
+                    }
+                    return Ok((
+                        n,
+                        DatagramSource::new(src_addr, None),
+                        SocksAddr::any_ipv4(),
+                    ));
+                }
+                Either::Left((Err(e), _)) => return Err(ProxyError::DatagramFatal(e.into())),
+                Either::Right(_) => {
+                    return Err(ProxyError::DatagramFatal(anyhow!("Association closed")))
+                }
+            }
+        }
+    }
+}
+
+pub struct AssociatedDatagramSendHalf(Arc<UdpSocket>);
+
+#[async_trait]
+impl InboundDatagramSendHalf for AssociatedDatagramSendHalf {
+    async fn send_to(
+        &mut self,
+        buf: &[u8],
+        _src_addr: &SocksAddr,
+        dst_addr: &SocketAddr,
+    ) -> io::Result<usize> {
+        self.0.send_to(buf, dst_addr).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_associated_datagram() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let relay = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let relay_addr = relay.local_addr().unwrap();
+            let (closed_tx, closed_rx) = oneshot::channel();
+            let dgram = Datagram::new(Box::new(AssociatedDatagram::new(
+                relay,
+                "127.0.0.1".parse().unwrap(),
+                closed_rx,
+            )));
+            let (mut r, mut s) = Box::new(dgram).split();
+
+            let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let client_addr = client.local_addr().unwrap();
+            let target = SocksAddr::from(("1.2.3.4".parse::<IpAddr>().unwrap(), 53));
+            let mut msg = BytesMut::new();
+            msg.put_slice(&[0, 0, 0]);
+            target.write_buf(&mut msg, SocksAddrWireType::PortLast);
+            msg.put_slice(b"hello");
+            client.send_to(&msg, relay_addr).await.unwrap();
+
+            let mut buf = [0u8; 64];
+            let (n, src, dst) = r.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..n], b"hello");
+            assert_eq!(src.address, client_addr);
+            assert_eq!(dst, target);
+
+            s.send_to(b"world", &target, &client_addr).await.unwrap();
+            let (n, _) = client.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..3], &[0, 0, 0]);
+            assert_eq!(&buf[3 + target.size()..n], b"world");
+
+            drop(closed_tx);
+            assert!(matches!(
+                r.recv_from(&mut buf).await,
+                Err(ProxyError::DatagramFatal(_))
+            ));
+        });
+    }
+}
@@ -18,10 +18,20 @@ pub struct Handler {
     pub address: String,
     pub port: u16,
     pub dns_client: SyncDnsClient,
+    pub dial_options: DialOptions,
 }
 
-impl TcpConnector for Handler {}
-impl UdpConnector for Handler {}
+impl TcpConnector for Handler {
+    fn dial_options(&self) -> &DialOptions {
+        &self.dial_options
+    }
+}
+
+impl UdpConnector for Handler {
+    fn dial_options(&self) -> &DialOptions {
+        &self.dial_options
+    }
+}
 
 #[async_trait]
 impl UdpOutboundHandler for Handler {
@@ -43,7 +53,7 @@ impl UdpOutboundHandler for Handler {
     ) -> io::Result<Self::Datagram> {
         // TODO support chaining, this requires implementing our own socks5 client
         let stream = self
-            .new_tcp_stream(self.dns_client.clone(), &self.address, &self.port)
+            .new_tcp_stream(sess, self.dns_client.clone(), &self.address, &self.port)
             .await?;
         let socket = self.new_udp_socket(&sess.source).await?;
         let socket = SocksDatagram::associate(stream, socket, None::<Auth>, None::<AddrKind>)
@@ -0,0 +1,516 @@
+use std::cmp::min;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::{io, pin::Pin};
+
+use bytes::{BufMut, BytesMut};
+use futures::future::{abortable, AbortHandle};
+use futures::{
+    ready,
+    task::{Context, Poll},
+    Future,
+};
+use log::{debug, trace};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::{oneshot, Notify};
+
+use super::transport::{disconnected, Opener, Sealer};
+use super::wire::*;
+
+// The window and maximum packet size we advertise, same as OpenSSH.
+const WINDOW_SIZE: u32 = 2 * 1024 * 1024;
+const MAX_PACKET_SIZE: u32 = 32 * 1024;
+
+const OPEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn broken_pipe() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "ssh connection closed")
+}
+
+/// The number of bytes the peer allows us to send.
+struct Window {
+    size: Mutex<u32>,
+    closed: AtomicBool,
+    notify: Notify,
+}
+
+impl Window {
+    fn new() -> Self {
+        Window {
+            size: Mutex::new(0),
+            closed: AtomicBool::new(false),
+            notify: Notify::new(),
+        }
+    }
+
+    // Takes at most `max` bytes, returns 0 if the channel is closed.
+    async fn take(&self, max: u32) -> u32 {
+        loop {
+            let notified = self.notify.notified();
+            if self.closed.load(Ordering::Relaxed) {
+                return 0;
+            }
+            {
+                let mut size = self.size.lock().unwrap();
+                if *size > 0 {
+                    let n = min(*size, max);
+                    *size -= n;
+                    return n;
+                }
+            }
+            notified.await;
+        }
+    }
+
+    fn add(&self, n: u32) {
+        {
+            let mut size = self.size.lock().unwrap();
+            *size = size.saturating_add(n);
+        }
+        self.notify.notify_one();
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+        self.notify.notify_one();
+    }
+}
+
+type OpenResult = io::Result<(u32, u32)>;
+
+struct Channel {
+    remote_id: u32,
+    read_tx: UnboundedSender<Vec<u8>>,
+    window: Arc<Window>,
+    open_tx: Option<oneshot::Sender<OpenResult>>,
+    close_sent: bool,
+}
+
+type Channels = Arc<Mutex<HashMap<u32, Channel>>>;
+
+fn channel_msg(msg: u8, remote_id: u32) -> BytesMut {
+    let mut buf = BytesMut::new();
+    buf.put_u8(msg);
+    buf.put_u32(remote_id);
+    buf
+}
+
+enum TaskState {
+    Idle,
+    Pending(Pin<Box<dyn Future<Output = u32> + Send + Sync>>),
+}
+
+/// A `direct-tcpip` channel.
+pub struct ChannelStream {
+    id: u32,
+    remote_id: u32,
+    max_packet_size: u32,
+    window: Arc<Window>,
+    read_rx: UnboundedReceiver<Vec<u8>>,
+    buf: BytesMut,
+    eof: bool,
+    // Bytes read since the last window adjustment.
+    consumed: u32,
+    msg_tx: UnboundedSender<Vec<u8>>,
+    channels: Channels,
+    write_state: TaskState,
+    shutdown: bool,
+}
+
+impl ChannelStream {
+    // Sends a message of the channel unless it has been closed, peers
+    // disconnect on messages to channels they have freed.
+    fn send(&self, msg: BytesMut) -> io::Result<()> {
+        let channels = self.channels.lock().unwrap();
+        if !matches!(channels.get(&self.id), Some(channel) if !channel.close_sent) {
+            return Err(broken_pipe());
+        }
+        self.msg_tx.send(msg.to_vec()).map_err(|_| broken_pipe())
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.consumed += n as u32;
+        if self.consumed >= WINDOW_SIZE / 2 {
+            let mut msg = channel_msg(MSG_CHANNEL_WINDOW_ADJUST, self.remote_id);
+            msg.put_u32(self.consumed);
+            let _ = self.send(msg);
+            self.consumed = 0;
+        }
+    }
+}
+
+impl Drop for ChannelStream {
+    fn drop(&mut self) {
+        trace!("drop ssh channel {}", self.id);
+        let mut channels = self.channels.lock().unwrap();
+        if let Some(channel) = channels.get_mut(&self.id) {
+            if !channel.close_sent {
+                channel.close_sent = true;
+                let _ = self
+                    .msg_tx
+                    .send(channel_msg(MSG_CHANNEL_CLOSE, self.remote_id).to_vec());
+            }
+        }
+    }
+}
+
+impl AsyncRead for ChannelStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<io::Result<()>> {
+        if !self.buf.is_empty() {
+            let to_read = min(buf.remaining(), self.buf.len());
+            let for_read = self.buf.split_to(to_read);
+            buf.put_slice(&for_read);
+            self.consume(to_read);
+            return Poll::Ready(Ok(()));
+        }
+        if self.eof {
+            return Poll::Ready(Ok(()));
+        }
+        match ready!(self.read_rx.poll_recv(cx)) {
+            Some(data) if data.is_empty() => {
+                self.eof = true;
+                Poll::Ready(Ok(()))
+            }
+            Some(data) => {
+                let to_read = min(buf.remaining(), data.len());
+                buf.put_slice(&data[..to_read]);
+                if data.len() > to_read {
+                    self.buf.extend_from_slice(&data[to_read..]);
+                }
+                self.consume(to_read);
+                Poll::Ready(Ok(()))
+            }
+            None => Poll::Ready(Err(broken_pipe())),
+        }
+    }
+}
+
+impl AsyncWrite for ChannelStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        loop {
+            match self.write_state {
+                TaskState::Idle => {
+                    let window = self.window.clone();
+                    let max = min(buf.len() as u32, self.max_packet_size);
+                    self.write_state =
+                        TaskState::Pending(Box::pin(async move { window.take(max).await }));
+                }
+                TaskState::Pending(ref mut task) => {
+                    let n = ready!(task.as_mut().poll(cx));
+                    self.write_state = TaskState::Idle;
+                    if n == 0 {
+                        return Poll::Ready(Err(broken_pipe()));
+                    }
+                    // The buffer may be shorter than the one we took the window for.
+                    let to_write = min(n as usize, buf.len());
+                    if (to_write as u32) < n {
+                        self.window.add(n - to_write as u32);
+                    }
+                    let mut msg = channel_msg(MSG_CHANNEL_DATA, self.remote_id);
+                    put_string(&mut msg, &buf[..to_write]);
+                    self.send(msg)?;
+                    return Poll::Ready(Ok(to_write));
+                }
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        if !self.shutdown {
+            self.shutdown = true;
+            self.send(channel_msg(MSG_CHANNEL_EOF, self.remote_id))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// An authenticated SSH connection, channels are opened on it for sessions.
+pub struct Connection {
+    channels: Channels,
+    msg_tx: UnboundedSender<Vec<u8>>,
+    next_id: AtomicU32,
+    done: Arc<AtomicBool>,
+    recv_handle: AbortHandle,
+    send_handle: AbortHandle,
+}
+
+impl Connection {
+    pub fn new<S>(stream: S, sealer: Sealer, opener: Opener) -> Self
+    where
+        S: 'static + AsyncRead + AsyncWrite + Unpin + Send,
+    {
+        let (r, w) = tokio::io::split(stream);
+        let channels: Channels = Arc::new(Mutex::new(HashMap::new()));
+        let (msg_tx, msg_rx) = mpsc::unbounded_channel();
+        let done = Arc::new(AtomicBool::new(false));
+
+        let recv_task = Self::run_receive_loop(r, opener, channels.clone(), msg_tx.clone());
+        let channels2 = channels.clone();
+        let done2 = done.clone();
+        let (recv_task, recv_handle) = abortable(async move {
+            if let Err(e) = recv_task.await {
+                debug!("ssh connection receive loop ended: {}", e);
+            }
+            done2.store(true, Ordering::Relaxed);
+            Self::close_channels(&channels2);
+        });
+        tokio::spawn(recv_task);
+
+        let send_task = Self::run_send_loop(w, sealer, msg_rx);
+        let channels2 = channels.clone();
+        let done2 = done.clone();
+        let (send_task, send_handle) = abortable(async move {
+            if let Err(e) = send_task.await {
+                debug!("ssh connection send loop ended: {}", e);
+            }
+            done2.store(true, Ordering::Relaxed);
+            Self::close_channels(&channels2);
+        });
+        tokio::spawn(send_task);
+
+        Connection {
+            channels,
+            msg_tx,
+            next_id: AtomicU32::new(0),
+            done,
+            recv_handle,
+            send_handle,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    fn close_channels(channels: &Channels) {
+        for (_, mut channel) in channels.lock().unwrap().drain() {
+            channel.window.close();
+            if let Some(open_tx) = channel.open_tx.take() {
+                let _ = open_tx.send(Err(broken_pipe()));
+            }
+        }
+    }
+
+    async fn run_send_loop<W>(
+        mut w: W,
+        mut sealer: Sealer,
+        mut msg_rx: UnboundedReceiver<Vec<u8>>,
+    ) -> io::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        while let Some(msg) = msg_rx.recv().await {
+            w.write_all(&sealer.seal(&msg)).await?;
+        }
+        Ok(())
+    }
+
+    async fn run_receive_loop<R>(
+        mut r: R,
+        mut opener: Opener,
+        channels: Channels,
+        msg_tx: UnboundedSender<Vec<u8>>,
+    ) -> io::Result<()>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let send = |msg: BytesMut| msg_tx.send(msg.to_vec()).map_err(|_| broken_pipe());
+        loop {
+            let payload = opener.read(&mut r).await?;
+            let mut rd = Reader::new(&payload);
+            match rd.u8()? {
+                MSG_CHANNEL_DATA => {
+                    let id = rd.u32()?;
+                    let data = rd.string()?;
+                    if let Some(channel) = channels.lock().unwrap().get(&id) {
+                        let _ = channel.read_tx.send(data.to_vec());
+                    }
+                }
+                MSG_CHANNEL_EXTENDED_DATA => {
+                    // Not used by direct-tcpip channels, the window is given back.
+                    let id = rd.u32()?;
+                    rd.u32()?;
+                    let data = rd.string()?;
+                    let remote_id = channels.lock().unwrap().get(&id).map(|c| c.remote_id);
+                    if let Some(remote_id) = remote_id {
+                        let mut msg = channel_msg(MSG_CHANNEL_WINDOW_ADJUST, remote_id);
+                        msg.put_u32(data.len() as u32);
+                        send(msg)?;
+                    }
+                }
+                MSG_CHANNEL_WINDOW_ADJUST => {
+                    let id = rd.u32()?;
+                    let n = rd.u32()?;
+                    if let Some(channel) = channels.lock().unwrap().get(&id) {
+                        channel.window.add(n);
+                    }
+                }
+                MSG_CHANNEL_OPEN_CONFIRMATION => {
+                    let id = rd.u32()?;
+                    let remote_id = rd.u32()?;
+                    let window = rd.u32()?;
+                    let max_packet_size = rd.u32()?;
+                    let mut channels = channels.lock().unwrap();
+                    match channels.get_mut(&id).and_then(|c| {
+                        c.remote_id = remote_id;
+                        c.window.add(window);
+                        c.open_tx.take()
+                    }) {
+                        Some(open_tx) => {
+                            if open_tx.send(Ok((remote_id, max_packet_size))).is_err() {
+                                // Timed out.
+                                channels.get_mut(&id).unwrap().close_sent = true;
+                                send(channel_msg(MSG_CHANNEL_CLOSE, remote_id))?;
+                            }
+                        }
+                        None => {
+                            send(channel_msg(MSG_CHANNEL_CLOSE, remote_id))?;
+                        }
+                    }
+                }
+                MSG_CHANNEL_OPEN_FAILURE => {
+                    let id = rd.u32()?;
+                    let reason = rd.u32()?;
+                    let desc = String::from_utf8_lossy(rd.string()?).to_string();
+                    if let Some(open_tx) =
+                        channels.lock().unwrap().remove(&id).and_then(|c| c.open_tx)
+                    {
+                        let _ = open_tx.send(Err(io::Error::new(
+                            io::ErrorKind::ConnectionRefused,
+                            format!("open ssh channel failed ({}): {}", reason, desc),
+                        )));
+                    }
+                }
+                MSG_CHANNEL_EOF => {
+                    let id = rd.u32()?;
+                    if let Some(channel) = channels.lock().unwrap().get(&id) {
+                        let _ = channel.read_tx.send(Vec::new());
+                    }
+                }
+                MSG_CHANNEL_CLOSE => {
+                    let id = rd.u32()?;
+                    let channel = channels.lock().unwrap().remove(&id);
+                    if let Some(channel) = channel {
+                        let _ = channel.read_tx.send(Vec::new());
+                        channel.window.close();
+                        if !channel.close_sent {
+                            send(channel_msg(MSG_CHANNEL_CLOSE, channel.remote_id))?;
+                        }
+                    }
+                }
+                MSG_CHANNEL_REQUEST => {
+                    let id = rd.u32()?;
+                    rd.string()?;
+                    let want_reply = rd.bool()?;
+                    let remote_id = channels.lock().unwrap().get(&id).map(|c| c.remote_id);
+                    if let (true, Some(remote_id)) = (want_reply, remote_id) {
+                        send(channel_msg(MSG_CHANNEL_FAILURE, remote_id))?;
+                    }
+                }
+                MSG_GLOBAL_REQUEST => {
+                    // e.g. keepalive@openssh.com
+                    rd.string()?;
+                    if rd.bool()? {
+                        let mut msg = BytesMut::new();
+                        msg.put_u8(MSG_REQUEST_FAILURE);
+                        send(msg)?;
+                    }
+                }
+                MSG_IGNORE | MSG_DEBUG | MSG_UNIMPLEMENTED => (),
+                MSG_DISCONNECT => return Err(disconnected(&payload)),
+                MSG_KEXINIT => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        "ssh re-keying is not supported",
+                    ));
+                }
+                msg => {
+                    debug!("ignored ssh message {}", msg);
+                }
+            }
+        }
+    }
+
+    /// Opens a `direct-tcpip` channel to `host:port`.
+    pub async fn open_direct_tcpip(&self, host: &str, port: u16) -> io::Result<ChannelStream> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (open_tx, open_rx) = oneshot::channel();
+        let (read_tx, read_rx) = mpsc::unbounded_channel();
+        let window = Arc::new(Window::new());
+        self.channels.lock().unwrap().insert(
+            id,
+            Channel {
+                remote_id: 0,
+                read_tx,
+                window: window.clone(),
+                open_tx: Some(open_tx),
+                close_sent: false,
+            },
+        );
+        let mut msg = BytesMut::new();
+        msg.put_u8(MSG_CHANNEL_OPEN);
+        put_string(&mut msg, b"direct-tcpip");
+        msg.put_u32(id);
+        msg.put_u32(WINDOW_SIZE);
+        msg.put_u32(MAX_PACKET_SIZE);
+        put_string(&mut msg, host.as_bytes());
+        msg.put_u32(port as u32);
+        // originator address and port
+        put_string(&mut msg, b"127.0.0.1");
+        msg.put_u32(0);
+        self.msg_tx.send(msg.to_vec()).map_err(|_| broken_pipe())?;
+        let (remote_id, max_packet_size) = match tokio::time::timeout(OPEN_TIMEOUT, open_rx).await {
+            Ok(Ok(res)) => res?,
+            Ok(Err(_)) => return Err(broken_pipe()),
+            Err(_) => {
+                self.channels.lock().unwrap().remove(&id);
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "open ssh channel timed out",
+                ));
+            }
+        };
+        trace!("opened ssh channel {} to {}:{}", id, host, port);
+        Ok(ChannelStream {
+            id,
+            remote_id,
+            // Packets are kept small as OpenSSH does.
+            max_packet_size: min(max_packet_size, MAX_PACKET_SIZE),
+            window,
+            read_rx,
+            buf: BytesMut::new(),
+            eof: false,
+            consumed: 0,
+            msg_tx: self.msg_tx.clone(),
+            channels: self.channels.clone(),
+            write_state: TaskState::Idle,
+            shutdown: false,
+        })
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.recv_handle.abort();
+        self.send_handle.abort();
+        Self::close_channels(&self.channels);
+    }
+}
@@ -0,0 +1,15 @@
+//! SSH, connections are carried in `direct-tcpip` channels of a single SSH
+//! connection shared by all sessions.
+//!
+//! Only what OpenSSH 6.5 and later servers support by default is implemented,
+//! the curve25519-sha256 key exchange, ssh-ed25519 host keys and the
+//! aes-gcm@openssh.com ciphers. Users are authenticated with passwords or
+//! unencrypted ed25519 private keys. Re-keying isn't supported, servers
+//! re-key AES-GCM connections only after 2^32 blocks by default.
+
+pub mod outbound;
+
+mod connection;
+mod key;
+mod transport;
+mod wire;
@@ -0,0 +1,167 @@
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+use crate::{app::SyncDnsClient, proxy::*, session::Session};
+
+use super::super::connection::Connection;
+use super::super::key::{parse_public_key, PrivateKey};
+use super::super::transport::{handshake, Auth, Config};
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Handler {
+    address: String,
+    port: u16,
+    config: Config,
+    dns_client: SyncDnsClient,
+    dial_options: DialOptions,
+    connection: Mutex<Option<Arc<Connection>>>,
+}
+
+impl Handler {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        address: String,
+        port: u16,
+        username: String,
+        password: String,
+        private_key: String,
+        host_key: String,
+        insecure_skip_host_key: bool,
+        dns_client: SyncDnsClient,
+        dial_options: DialOptions,
+    ) -> io::Result<Self> {
+        let auth = if !private_key.is_empty() {
+            let data = std::fs::read_to_string(&private_key).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("read private key {} failed: {}", &private_key, e),
+                )
+            })?;
+            Auth::PrivateKey(PrivateKey::from_openssh(&data)?)
+        } else {
+            Auth::Password(password)
+        };
+        // The password or the signature of the user would be sent to whoever
+        // answers the connection if the host key is not checked.
+        let host_key = if !host_key.is_empty() {
+            Some(parse_public_key(&host_key)?)
+        } else if insecure_skip_host_key {
+            log::warn!(
+                "host key of ssh server {}:{} is not verified",
+                &address,
+                port
+            );
+            None
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "missing ssh host key, set host-key or insecure-skip-host-key",
+            ));
+        };
+        Ok(Handler {
+            address,
+            port,
+            config: Config {
+                username,
+                auth,
+                host_key,
+            },
+            dns_client,
+            dial_options,
+            connection: Mutex::new(None),
+        })
+    }
+
+    async fn connect(&self, sess: &Session) -> io::Result<Connection> {
+        let mut stream = self
+            .new_tcp_stream(sess, self.dns_client.clone(), &self.address, &self.port)
+            .await?;
+        let (sealer, opener) =
+            tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake(&mut stream, &self.config))
+                .await
+                .map_err(|_| {
+                    io::Error::new(io::ErrorKind::TimedOut, "ssh handshake timed out")
+                })??;
+        log::debug!("ssh connected to {}:{}", &self.address, self.port);
+        Ok(Connection::new(stream, sealer, opener))
+    }
+}
+
+impl TcpConnector for Handler {
+    fn dial_options(&self) -> &DialOptions {
+        &self.dial_options
+    }
+}
+
+#[async_trait]
+impl TcpOutboundHandler for Handler {
+    type Stream = AnyStream;
+
+    fn connect_addr(&self) -> Option<OutboundConnect> {
+        Some(OutboundConnect::NoConnect)
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        _stream: Option<Self::Stream>,
+    ) -> io::Result<Self::Stream> {
+        // All sessions share a single connection, a new one is made when it's
+        // closed.
+        let connection = {
+            let mut connection = self.connection.lock().await;
+            match connection.as_ref() {
+                Some(c) if !c.is_done() => c.clone(),
+                _ => {
+                    let c = Arc::new(self.connect(sess).await?);
+                    *connection = Some(c.clone());
+                    c
+                }
+            }
+        };
+        let stream = connection
+            .open_direct_tcpip(&sess.destination.host(), sess.destination.port())
+            .await?;
+        Ok(Box::new(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::RwLock;
+
+    use crate::app::dns_client::DnsClient;
+
+    use super::*;
+
+    #[test]
+    fn test_host_key_required() {
+        let mut dns = crate::config::Dns::new();
+        dns.servers.push("1.1.1.1".to_string());
+        let dns = protobuf::SingularPtrField::some(dns);
+        let dns_client = Arc::new(RwLock::new(DnsClient::new(&dns).unwrap()));
+        let new = |host_key: &str, skip| {
+            Handler::new(
+                "example.com".to_string(),
+                22,
+                "leaf".to_string(),
+                "password".to_string(),
+                String::new(),
+                host_key.to_string(),
+                skip,
+                dns_client.clone(),
+                DialOptions::default(),
+            )
+        };
+        assert!(new("", false).is_err());
+        assert!(new("", true).is_ok());
+        let host_key =
+            "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIEo0GuHXFd5+JkStw8+cGTiz10miLlWR/MUFemOTDUxY";
+        assert!(new(host_key, false).is_ok());
+    }
+}
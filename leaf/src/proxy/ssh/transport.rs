@@ -0,0 +1,498 @@
+use std::io;
+
+use bytes::{BufMut, BytesMut};
+use rand::RngCore;
+use ring::{aead, agreement, digest, rand::SystemRandom};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::key::{self, PrivateKey, ED25519};
+use super::wire::*;
+
+const CLIENT_VERSION: &str = "SSH-2.0-leaf";
+
+const KEX_ALGORITHMS: &[&str] = &["curve25519-sha256", "curve25519-sha256@libssh.org"];
+const CIPHERS: &[&str] = &["aes256-gcm@openssh.com", "aes128-gcm@openssh.com"];
+// AEAD ciphers don't use the negotiated MACs, one is listed as the
+// negotiation requires.
+const MACS: &str = "hmac-sha2-256";
+
+const TAG_LEN: usize = 16;
+const MAX_PACKET_LEN: usize = 256 * 1024;
+
+fn handshake_err(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, msg)
+}
+
+/// AES-GCM in the way of RFC 5647, the last 8 bytes of the IV are an
+/// invocation counter incremented for each packet.
+pub struct Cipher {
+    key: aead::LessSafeKey,
+    iv: [u8; 12],
+}
+
+impl Cipher {
+    pub fn new(name: &str, key: &[u8], iv: &[u8]) -> io::Result<Self> {
+        let (algorithm, key_len) = match name {
+            "aes256-gcm@openssh.com" => (&aead::AES_256_GCM, 32),
+            "aes128-gcm@openssh.com" => (&aead::AES_128_GCM, 16),
+            _ => return Err(handshake_err(format!("unsupported cipher {}", name))),
+        };
+        let key = aead::UnboundKey::new(algorithm, &key[..key_len])
+            .map_err(|_| handshake_err("invalid cipher key".to_string()))?;
+        let mut fixed_iv = [0u8; 12];
+        fixed_iv.copy_from_slice(&iv[..12]);
+        Ok(Cipher {
+            key: aead::LessSafeKey::new(key),
+            iv: fixed_iv,
+        })
+    }
+
+    fn nonce(&mut self) -> aead::Nonce {
+        let nonce = aead::Nonce::assume_unique_for_key(self.iv);
+        let mut counter = [0u8; 8];
+        counter.copy_from_slice(&self.iv[4..]);
+        let counter = u64::from_be_bytes(counter).wrapping_add(1);
+        self.iv[4..].copy_from_slice(&counter.to_be_bytes());
+        nonce
+    }
+}
+
+/// Encodes and encrypts outgoing packets.
+#[derive(Default)]
+pub struct Sealer {
+    cipher: Option<Cipher>,
+}
+
+impl Sealer {
+    pub fn new(cipher: Cipher) -> Self {
+        Sealer {
+            cipher: Some(cipher),
+        }
+    }
+
+    pub fn seal(&mut self, payload: &[u8]) -> Vec<u8> {
+        // The packet length isn't encrypted and not counted with AES-GCM.
+        let (block_size, unpadded) = if self.cipher.is_some() {
+            (16, 1 + payload.len())
+        } else {
+            (8, 4 + 1 + payload.len())
+        };
+        let mut padding = block_size - unpadded % block_size;
+        if padding < 4 {
+            padding += block_size;
+        }
+        let len = 1 + payload.len() + padding;
+        let mut packet = Vec::with_capacity(4 + len + TAG_LEN);
+        packet.put_u32(len as u32);
+        packet.put_u8(padding as u8);
+        packet.put_slice(payload);
+        let mut padding_bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut padding_bytes[..padding]);
+        packet.put_slice(&padding_bytes[..padding]);
+        if let Some(cipher) = self.cipher.as_mut() {
+            let nonce = cipher.nonce();
+            let mut body = packet.split_off(4);
+            // Sealing fails only if the data is too long for the cipher.
+            cipher
+                .key
+                .seal_in_place_append_tag(
+                    nonce,
+                    aead::Aad::from((len as u32).to_be_bytes()),
+                    &mut body,
+                )
+                .unwrap();
+            packet.extend_from_slice(&body);
+        }
+        packet
+    }
+}
+
+/// Decrypts and decodes incoming packets.
+#[derive(Default)]
+pub struct Opener {
+    cipher: Option<Cipher>,
+}
+
+impl Opener {
+    pub fn new(cipher: Cipher) -> Self {
+        Opener {
+            cipher: Some(cipher),
+        }
+    }
+
+    /// Reads a packet, returns the payload.
+    pub async fn read<R>(&mut self, r: &mut R) -> io::Result<Vec<u8>>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let len = r.read_u32().await? as usize;
+        if !(5..=MAX_PACKET_LEN).contains(&len) {
+            return Err(malformed());
+        }
+        let tag_len = if self.cipher.is_some() { TAG_LEN } else { 0 };
+        let mut body = vec![0u8; len + tag_len];
+        r.read_exact(&mut body).await?;
+        if let Some(cipher) = self.cipher.as_mut() {
+            let nonce = cipher.nonce();
+            cipher
+                .key
+                .open_in_place(
+                    nonce,
+                    aead::Aad::from((len as u32).to_be_bytes()),
+                    &mut body,
+                )
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "decryption failed"))?;
+            body.truncate(len);
+        }
+        let padding = body[0] as usize;
+        if padding + 1 > len {
+            return Err(malformed());
+        }
+        body.truncate(len - padding);
+        body.remove(0);
+        Ok(body)
+    }
+}
+
+pub enum Auth {
+    Password(String),
+    PrivateKey(PrivateKey),
+}
+
+pub struct Config {
+    pub username: String,
+    pub auth: Auth,
+    /// The expected host key in the SSH wire format, any host key is accepted
+    /// if not set, which is only allowed by `insecure-skip-host-key`.
+    pub host_key: Option<Vec<u8>>,
+}
+
+struct Transport<'a, S> {
+    stream: &'a mut S,
+    sealer: Sealer,
+    opener: Opener,
+}
+
+impl<'a, S> Transport<'a, S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    async fn send(&mut self, payload: &[u8]) -> io::Result<()> {
+        let packet = self.sealer.seal(payload);
+        self.stream.write_all(&packet).await
+    }
+
+    async fn recv(&mut self) -> io::Result<Vec<u8>> {
+        loop {
+            let payload = self.opener.read(&mut self.stream).await?;
+            match payload.first() {
+                Some(&MSG_IGNORE) | Some(&MSG_DEBUG) | Some(&MSG_UNIMPLEMENTED) => continue,
+                Some(&MSG_DISCONNECT) => return Err(disconnected(&payload)),
+                Some(_) => return Ok(payload),
+                None => return Err(malformed()),
+            }
+        }
+    }
+
+    async fn expect(&mut self, msg: u8) -> io::Result<Vec<u8>> {
+        let payload = self.recv().await?;
+        if payload[0] != msg {
+            return Err(handshake_err(format!(
+                "unexpected ssh message {}, expecting {}",
+                payload[0], msg
+            )));
+        }
+        Ok(payload)
+    }
+}
+
+/// Returns the error carried in a disconnect message.
+pub fn disconnected(payload: &[u8]) -> io::Error {
+    let mut r = Reader::new(payload);
+    let reason = r.skip(1).and_then(|_| r.u32()).unwrap_or(0);
+    let desc = r.string().unwrap_or_default();
+    io::Error::new(
+        io::ErrorKind::ConnectionAborted,
+        format!(
+            "ssh disconnected ({}): {}",
+            reason,
+            String::from_utf8_lossy(desc)
+        ),
+    )
+}
+
+// Picks the first algorithm of ours the server supports.
+fn negotiate<'b>(ours: &[&'b str], theirs: &[&str], what: &str) -> io::Result<&'b str> {
+    ours.iter()
+        .find(|a| theirs.contains(a))
+        .copied()
+        .ok_or_else(|| {
+            handshake_err(format!(
+                "no matching {} found, server offers {}",
+                what,
+                theirs.join(",")
+            ))
+        })
+}
+
+fn kexinit() -> Vec<u8> {
+    let mut buf = BytesMut::new();
+    buf.put_u8(MSG_KEXINIT);
+    let mut cookie = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut cookie);
+    buf.put_slice(&cookie);
+    put_string(&mut buf, KEX_ALGORITHMS.join(",").as_bytes());
+    put_string(&mut buf, ED25519.as_bytes());
+    put_string(&mut buf, CIPHERS.join(",").as_bytes());
+    put_string(&mut buf, CIPHERS.join(",").as_bytes());
+    put_string(&mut buf, MACS.as_bytes());
+    put_string(&mut buf, MACS.as_bytes());
+    put_string(&mut buf, b"none");
+    put_string(&mut buf, b"none");
+    put_string(&mut buf, b"");
+    put_string(&mut buf, b"");
+    // first_kex_packet_follows
+    buf.put_u8(0);
+    // reserved
+    buf.put_u32(0);
+    buf.to_vec()
+}
+
+// Reads the version line of the server, lines before it are ignored.
+async fn read_version<S>(stream: &mut S) -> io::Result<Vec<u8>>
+where
+    S: AsyncRead + Unpin,
+{
+    // Reads byte by byte to not consume the packets following.
+    let mut line = Vec::new();
+    loop {
+        let b = stream.read_u8().await?;
+        if b != b'\n' {
+            if line.len() >= 255 {
+                return Err(handshake_err("invalid ssh version".to_string()));
+            }
+            line.push(b);
+            continue;
+        }
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        if line.starts_with(b"SSH-") {
+            if !line.starts_with(b"SSH-2.0-") && !line.starts_with(b"SSH-1.99-") {
+                return Err(handshake_err(format!(
+                    "unsupported ssh version {}",
+                    String::from_utf8_lossy(&line)
+                )));
+            }
+            return Ok(line);
+        }
+        line.clear();
+    }
+}
+
+fn derive_key(k: &[u8], h: &[u8], x: u8, session_id: &[u8]) -> digest::Digest {
+    let mut ctx = digest::Context::new(&digest::SHA256);
+    ctx.update(k);
+    ctx.update(h);
+    ctx.update(&[x]);
+    ctx.update(session_id);
+    ctx.finish()
+}
+
+/// Runs the key exchange and user authentication on `stream`, returns the
+/// sealer and opener for the connection protocol.
+pub async fn handshake<S>(stream: &mut S, config: &Config) -> io::Result<(Sealer, Opener)>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream
+        .write_all(format!("{}\r\n", CLIENT_VERSION).as_bytes())
+        .await?;
+    let server_version = read_version(stream).await?;
+
+    let mut t = Transport {
+        stream,
+        sealer: Sealer::default(),
+        opener: Opener::default(),
+    };
+
+    let client_kexinit = kexinit();
+    t.send(&client_kexinit).await?;
+    let server_kexinit = t.expect(MSG_KEXINIT).await?;
+    let mut r = Reader::new(&server_kexinit);
+    r.skip(1 + 16)?;
+    let kex_algorithms = r.name_list()?;
+    let host_key_algorithms = r.name_list()?;
+    let ciphers_c2s = r.name_list()?;
+    let ciphers_s2c = r.name_list()?;
+    let kex = negotiate(KEX_ALGORITHMS, &kex_algorithms, "key exchange method")?;
+    negotiate(&[ED25519], &host_key_algorithms, "host key algorithm")?;
+    let cipher_c2s = negotiate(CIPHERS, &ciphers_c2s, "cipher")?;
+    let cipher_s2c = negotiate(CIPHERS, &ciphers_s2c, "cipher")?;
+    // MACs, compressions and languages
+    for _ in 0..6 {
+        r.string()?;
+    }
+    // Ignores the packet sent by a wrong guess of the server.
+    if r.bool()? && (kex_algorithms[0] != kex || host_key_algorithms[0] != ED25519) {
+        t.recv().await?;
+    }
+
+    let rng = SystemRandom::new();
+    let private = agreement::EphemeralPrivateKey::generate(&agreement::X25519, &rng)
+        .map_err(|_| handshake_err("generate key failed".to_string()))?;
+    let client_public = private
+        .compute_public_key()
+        .map_err(|_| handshake_err("generate key failed".to_string()))?;
+    let mut buf = BytesMut::new();
+    buf.put_u8(MSG_KEX_ECDH_INIT);
+    put_string(&mut buf, client_public.as_ref());
+    t.send(&buf).await?;
+
+    let reply = t.expect(MSG_KEX_ECDH_REPLY).await?;
+    let mut r = Reader::new(&reply);
+    r.skip(1)?;
+    let host_key = r.string()?;
+    let server_public = r.string()?;
+    let sig = r.string()?;
+    let shared = agreement::agree_ephemeral(
+        private,
+        &agreement::UnparsedPublicKey::new(&agreement::X25519, server_public),
+        handshake_err("key exchange failed".to_string()),
+        |k| Ok(k.to_vec()),
+    )?;
+    let mut k = BytesMut::new();
+    put_mpint(&mut k, &shared);
+
+    let mut buf = BytesMut::new();
+    put_string(&mut buf, CLIENT_VERSION.as_bytes());
+    put_string(&mut buf, &server_version);
+    put_string(&mut buf, &client_kexinit);
+    put_string(&mut buf, &server_kexinit);
+    put_string(&mut buf, host_key);
+    put_string(&mut buf, client_public.as_ref());
+    put_string(&mut buf, server_public);
+    buf.put_slice(&k);
+    let h = digest::digest(&digest::SHA256, &buf);
+
+    key::verify(host_key, h.as_ref(), sig)?;
+    if let Some(expected) = config.host_key.as_ref() {
+        if expected[..] != host_key[..] {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "ssh host key mismatch",
+            ));
+        }
+    }
+
+    t.send(&[MSG_NEWKEYS]).await?;
+    t.expect(MSG_NEWKEYS).await?;
+
+    // The exchange hash of the first key exchange is the session ID.
+    let session_id = h.as_ref();
+    let key = |x| derive_key(&k, h.as_ref(), x, session_id);
+    t.sealer = Sealer::new(Cipher::new(
+        cipher_c2s,
+        key(b'C').as_ref(),
+        key(b'A').as_ref(),
+    )?);
+    t.opener = Opener::new(Cipher::new(
+        cipher_s2c,
+        key(b'D').as_ref(),
+        key(b'B').as_ref(),
+    )?);
+
+    let mut buf = BytesMut::new();
+    buf.put_u8(MSG_SERVICE_REQUEST);
+    put_string(&mut buf, b"ssh-userauth");
+    t.send(&buf).await?;
+    t.expect(MSG_SERVICE_ACCEPT).await?;
+
+    let mut buf = BytesMut::new();
+    buf.put_u8(MSG_USERAUTH_REQUEST);
+    put_string(&mut buf, config.username.as_bytes());
+    put_string(&mut buf, b"ssh-connection");
+    match &config.auth {
+        Auth::Password(password) => {
+            put_string(&mut buf, b"password");
+            buf.put_u8(0);
+            put_string(&mut buf, password.as_bytes());
+        }
+        Auth::PrivateKey(private_key) => {
+            put_string(&mut buf, b"publickey");
+            buf.put_u8(1);
+            put_string(&mut buf, ED25519.as_bytes());
+            put_string(&mut buf, private_key.public_blob());
+            let mut data = BytesMut::new();
+            put_string(&mut data, session_id);
+            data.put_slice(&buf);
+            let sig = private_key.sign(&data);
+            put_string(&mut buf, &sig);
+        }
+    }
+    t.send(&buf).await?;
+    loop {
+        let payload = t.recv().await?;
+        match payload[0] {
+            MSG_USERAUTH_SUCCESS => break,
+            MSG_USERAUTH_BANNER => continue,
+            MSG_USERAUTH_FAILURE => {
+                let mut r = Reader::new(&payload);
+                r.skip(1)?;
+                let methods = r.name_list()?;
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!(
+                        "ssh authentication failed, methods that can continue: {}",
+                        methods.join(",")
+                    ),
+                ));
+            }
+            msg => {
+                return Err(handshake_err(format!(
+                    "unexpected ssh message {} during authentication",
+                    msg
+                )));
+            }
+        }
+    }
+
+    Ok((t.sealer, t.opener))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packet() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let key = [7u8; 32];
+            let iv = [9u8; 12];
+            let mut plain_sealer = Sealer::default();
+            let mut sealer = Sealer::new(Cipher::new(CIPHERS[0], &key, &iv).unwrap());
+            let mut opener = Opener::new(Cipher::new(CIPHERS[0], &key, &iv).unwrap());
+            let packet = plain_sealer.seal(b"hello");
+            assert_eq!(packet.len() % 8, 0);
+            let mut buf = Vec::new();
+            for payload in [&b"hello"[..], b"", &[1u8; 100]] {
+                let packet = sealer.seal(payload);
+                assert_eq!((packet.len() - 4 - TAG_LEN) % 16, 0);
+                buf.extend_from_slice(&packet);
+            }
+            let mut r = &buf[..];
+            assert_eq!(opener.read(&mut r).await.unwrap(), b"hello");
+            assert_eq!(opener.read(&mut r).await.unwrap(), b"");
+            assert_eq!(opener.read(&mut r).await.unwrap(), vec![1u8; 100]);
+            assert!(r.is_empty());
+
+            // Packets are bound to the sequence.
+            let mut opener = Opener::new(Cipher::new(CIPHERS[0], &key, &iv).unwrap());
+            let packet = sealer.seal(b"hello");
+            assert!(opener.read(&mut &packet[..]).await.is_err());
+        });
+    }
+}
@@ -0,0 +1,142 @@
+use std::io;
+
+use bytes::{BufMut, BytesMut};
+
+pub const MSG_DISCONNECT: u8 = 1;
+pub const MSG_IGNORE: u8 = 2;
+pub const MSG_UNIMPLEMENTED: u8 = 3;
+pub const MSG_DEBUG: u8 = 4;
+pub const MSG_SERVICE_REQUEST: u8 = 5;
+pub const MSG_SERVICE_ACCEPT: u8 = 6;
+pub const MSG_KEXINIT: u8 = 20;
+pub const MSG_NEWKEYS: u8 = 21;
+pub const MSG_KEX_ECDH_INIT: u8 = 30;
+pub const MSG_KEX_ECDH_REPLY: u8 = 31;
+pub const MSG_USERAUTH_REQUEST: u8 = 50;
+pub const MSG_USERAUTH_FAILURE: u8 = 51;
+pub const MSG_USERAUTH_SUCCESS: u8 = 52;
+pub const MSG_USERAUTH_BANNER: u8 = 53;
+pub const MSG_GLOBAL_REQUEST: u8 = 80;
+pub const MSG_REQUEST_FAILURE: u8 = 82;
+pub const MSG_CHANNEL_OPEN: u8 = 90;
+pub const MSG_CHANNEL_OPEN_CONFIRMATION: u8 = 91;
+pub const MSG_CHANNEL_OPEN_FAILURE: u8 = 92;
+pub const MSG_CHANNEL_WINDOW_ADJUST: u8 = 93;
+pub const MSG_CHANNEL_DATA: u8 = 94;
+pub const MSG_CHANNEL_EXTENDED_DATA: u8 = 95;
+pub const MSG_CHANNEL_EOF: u8 = 96;
+pub const MSG_CHANNEL_CLOSE: u8 = 97;
+pub const MSG_CHANNEL_REQUEST: u8 = 98;
+pub const MSG_CHANNEL_FAILURE: u8 = 100;
+
+pub fn malformed() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "malformed ssh message")
+}
+
+pub fn put_string(buf: &mut BytesMut, s: &[u8]) {
+    buf.put_u32(s.len() as u32);
+    buf.put_slice(s);
+}
+
+/// Writes an unsigned big-endian integer as an mpint.
+pub fn put_mpint(buf: &mut BytesMut, n: &[u8]) {
+    let n = match n.iter().position(|b| *b != 0) {
+        Some(i) => &n[i..],
+        None => &[],
+    };
+    if matches!(n.first(), Some(b) if b & 0x80 != 0) {
+        buf.put_u32(n.len() as u32 + 1);
+        buf.put_u8(0);
+    } else {
+        buf.put_u32(n.len() as u32);
+    }
+    buf.put_slice(n);
+}
+
+/// Reads fields of a message.
+pub struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Reader { buf }
+    }
+
+    pub fn u8(&mut self) -> io::Result<u8> {
+        let (b, rest) = self.buf.split_first().ok_or_else(malformed)?;
+        self.buf = rest;
+        Ok(*b)
+    }
+
+    pub fn bool(&mut self) -> io::Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub fn u32(&mut self) -> io::Result<u32> {
+        if self.buf.len() < 4 {
+            return Err(malformed());
+        }
+        let (n, rest) = self.buf.split_at(4);
+        self.buf = rest;
+        Ok(u32::from_be_bytes([n[0], n[1], n[2], n[3]]))
+    }
+
+    pub fn string(&mut self) -> io::Result<&'a [u8]> {
+        let len = self.u32()? as usize;
+        if self.buf.len() < len {
+            return Err(malformed());
+        }
+        let (s, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(s)
+    }
+
+    pub fn name_list(&mut self) -> io::Result<Vec<&'a str>> {
+        let s = std::str::from_utf8(self.string()?).map_err(|_| malformed())?;
+        Ok(s.split(',').collect())
+    }
+
+    pub fn skip(&mut self, n: usize) -> io::Result<()> {
+        if self.buf.len() < n {
+            return Err(malformed());
+        }
+        self.buf = &self.buf[n..];
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mpint() {
+        // Examples from RFC 4251 section 5.
+        let mut buf = BytesMut::new();
+        put_mpint(&mut buf, &[0, 0]);
+        assert_eq!(&buf[..], &[0, 0, 0, 0]);
+        let mut buf = BytesMut::new();
+        put_mpint(&mut buf, &[0x09, 0xa3, 0x78, 0xf9, 0xb2, 0xe3, 0x32, 0xa7]);
+        assert_eq!(
+            &buf[..],
+            &[0, 0, 0, 0x08, 0x09, 0xa3, 0x78, 0xf9, 0xb2, 0xe3, 0x32, 0xa7]
+        );
+        let mut buf = BytesMut::new();
+        put_mpint(&mut buf, &[0x00, 0x80]);
+        assert_eq!(&buf[..], &[0, 0, 0, 0x02, 0x00, 0x80]);
+    }
+
+    #[test]
+    fn test_reader() {
+        let mut buf = BytesMut::new();
+        buf.put_u8(MSG_KEXINIT);
+        put_string(&mut buf, b"a,b");
+        buf.put_u8(1);
+        let mut r = Reader::new(&buf);
+        assert_eq!(r.u8().unwrap(), MSG_KEXINIT);
+        assert_eq!(r.name_list().unwrap(), vec!["a", "b"]);
+        assert!(r.bool().unwrap());
+        assert!(r.u32().is_err());
+    }
+}
@@ -1,10 +1,183 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{app::outbound::urltest::UrlTester, proxy::AnyOutboundHandler, session::Session};
+
 pub mod tcp;
 pub mod udp;
 
 pub use tcp::Handler as TcpHandler;
 pub use udp::Handler as UdpHandler;
 
-pub(self) enum Method {
+enum Method {
     Random,
-    RoundRobin,
+    RoundRobin(AtomicUsize),
+    // smooth weighted round-robin, (weight, current weight) of each actor
+    Weighted(Mutex<Vec<(i64, i64)>>),
+    LeastRtt(Arc<UrlTester>),
+    // by the destination host
+    Hash,
+    // by the source IP
+    SourceHash,
+}
+
+/// Balancer picks an actor for each connection of a `static` outbound, it's
+/// shared by the TCP and UDP handlers.
+pub struct Balancer {
+    actors: Vec<AnyOutboundHandler>,
+    method: Method,
+}
+
+impl Balancer {
+    /// Weights are of the actors in order, all 1 if empty. The tester is
+    /// required by the `least-rtt` method.
+    pub fn new(
+        actors: Vec<AnyOutboundHandler>,
+        method: &str,
+        weights: &[u32],
+        tester: Option<Arc<UrlTester>>,
+    ) -> Result<Self> {
+        let method = match method {
+            "random" => Method::Random,
+            "rr" => Method::RoundRobin(AtomicUsize::new(0)),
+            "weighted" => {
+                if !weights.is_empty() && weights.len() != actors.len() {
+                    return Err(anyhow!(
+                        "{} weights for {} actors",
+                        weights.len(),
+                        actors.len()
+                    ));
+                }
+                let weights: Vec<(i64, i64)> = if weights.is_empty() {
+                    vec![(1, 0); actors.len()]
+                } else {
+                    weights.iter().map(|w| (*w as i64, 0)).collect()
+                };
+                if weights.iter().all(|(w, _)| *w == 0) {
+                    return Err(anyhow!("all weights are zero"));
+                }
+                Method::Weighted(Mutex::new(weights))
+            }
+            "least-rtt" => {
+                Method::LeastRtt(tester.ok_or_else(|| anyhow!("health check is required"))?)
+            }
+            "hash" => Method::Hash,
+            "source-hash" => Method::SourceHash,
+            _ => return Err(anyhow!("unknown method")),
+        };
+        Ok(Balancer { actors, method })
+    }
+
+    fn tags(&self) -> impl Iterator<Item = &str> {
+        self.actors.iter().map(|a| a.tag().as_str())
+    }
+
+    pub async fn pick(&self, sess: &Session) -> AnyOutboundHandler {
+        match &self.method {
+            Method::Random => {
+                let mut rng = StdRng::from_entropy();
+                let i: usize = rng.gen_range(0..self.actors.len());
+                self.actors[i].clone()
+            }
+            Method::RoundRobin(next) => {
+                let i = next.fetch_add(1, Ordering::Relaxed) % self.actors.len();
+                self.actors[i].clone()
+            }
+            Method::Weighted(weights) => {
+                let i = pick_weighted(&mut weights.lock().unwrap());
+                self.actors[i].clone()
+            }
+            Method::LeastRtt(tester) => {
+                tester.start();
+                tester.get_selected().await
+            }
+            Method::Hash => {
+                let i = pick_hash(&sess.destination.host(), self.tags());
+                self.actors[i].clone()
+            }
+            Method::SourceHash => {
+                let source = sess.forwarded_source.unwrap_or_else(|| sess.source.ip());
+                let i = pick_hash(&source, self.tags());
+                self.actors[i].clone()
+            }
+        }
+    }
+}
+
+// Rendezvous hashing, keys of an actor stay with it unless it's removed, and
+// only its keys move to others then.
+fn pick_hash<'a, K: Hash + ?Sized>(key: &K, tags: impl Iterator<Item = &'a str>) -> usize {
+    let mut best = (0, 0);
+    for (i, tag) in tags.enumerate() {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        tag.hash(&mut hasher);
+        let score = hasher.finish();
+        if i == 0 || score > best.1 {
+            best = (i, score);
+        }
+    }
+    best.0
+}
+
+fn pick_weighted(weights: &mut [(i64, i64)]) -> usize {
+    let total: i64 = weights.iter().map(|(w, _)| w).sum();
+    let mut best = (0, i64::MIN);
+    for (i, (w, c)) in weights.iter_mut().enumerate() {
+        *c += *w;
+        if *c > best.1 {
+            best = (i, *c);
+        }
+    }
+    weights[best.0].1 -= total;
+    best.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_weighted() {
+        let mut weights = vec![(5, 0), (1, 0), (1, 0)];
+        let picks: Vec<usize> = (0..7).map(|_| pick_weighted(&mut weights)).collect();
+        assert_eq!(picks, vec![0, 0, 1, 0, 2, 0, 0]);
+        // Back to the initial state after a cycle.
+        assert_eq!(weights, vec![(5, 0), (1, 0), (1, 0)]);
+
+        let mut weights = vec![(0, 0), (2, 0)];
+        for _ in 0..4 {
+            assert_eq!(pick_weighted(&mut weights), 1);
+        }
+    }
+
+    #[test]
+    fn test_pick_hash() {
+        let actors = ["a", "b", "c", "d"];
+        let hosts: Vec<String> = (0..100).map(|i| format!("{}.example.com", i)).collect();
+        let picks: Vec<usize> = hosts
+            .iter()
+            .map(|h| pick_hash(h, actors.iter().copied()))
+            .collect();
+        // Stable and spread.
+        for (h, i) in hosts.iter().zip(picks.iter()) {
+            assert_eq!(pick_hash(h, actors.iter().copied()), *i);
+        }
+        for i in 0..actors.len() {
+            assert!(picks.contains(&i));
+        }
+        // Only the keys of the removed actor move.
+        let remaining = ["a", "b", "d"];
+        for (h, i) in hosts.iter().zip(picks.iter()) {
+            let j = pick_hash(h, remaining.iter().copied());
+            if *i != 2 {
+                assert_eq!(actors[*i], remaining[j]);
+            }
+        }
+    }
 }
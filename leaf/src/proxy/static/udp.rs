@@ -1,38 +1,24 @@
 use std::io;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
-use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use rand::{rngs::StdRng, Rng, SeedableRng};
+use log::*;
 
 use crate::{app::SyncDnsClient, proxy::*, session::Session};
 
-use super::Method;
+use super::Balancer;
 
 pub struct Handler {
-    actors: Vec<AnyOutboundHandler>,
-    method: Method,
-    next: Option<AtomicUsize>,
+    balancer: Arc<Balancer>,
     dns_client: SyncDnsClient,
 }
 
 impl Handler {
-    pub fn new(
-        actors: Vec<AnyOutboundHandler>,
-        dns_client: SyncDnsClient,
-        method: &str,
-    ) -> Result<Self> {
-        let (method, next) = match method {
-            "random" => (Method::Random, None),
-            "rr" => (Method::RoundRobin, Some(AtomicUsize::new(0))),
-            _ => return Err(anyhow!("unknown method")),
-        };
-        Ok(Handler {
-            actors,
-            method,
-            next,
+    pub fn new(balancer: Arc<Balancer>, dns_client: SyncDnsClient) -> Self {
+        Handler {
+            balancer,
             dns_client,
-        })
+        }
     }
 }
 
@@ -42,7 +28,7 @@ impl UdpOutboundHandler for Handler {
     type Datagram = AnyOutboundDatagram;
 
     fn connect_addr(&self) -> Option<OutboundConnect> {
-        None
+        Some(OutboundConnect::NoConnect)
     }
 
     fn transport_type(&self) -> DatagramTransportType {
@@ -54,31 +40,9 @@ impl UdpOutboundHandler for Handler {
         sess: &'a Session,
         _transport: Option<OutboundTransport<Self::UStream, Self::Datagram>>,
     ) -> io::Result<Self::Datagram> {
-        match self.method {
-            Method::Random => {
-                let mut rng = StdRng::from_entropy();
-                let i: usize = rng.gen_range(0..self.actors.len());
-                let t = crate::proxy::connect_udp_outbound(
-                    sess,
-                    self.dns_client.clone(),
-                    &self.actors[i],
-                )
-                .await?;
-                UdpOutboundHandler::handle(self.actors[i].as_ref(), sess, t).await
-            }
-            Method::RoundRobin => {
-                let current = self.next.as_ref().unwrap().load(Ordering::Relaxed);
-                let a = &self.actors[current];
-                let next = if current >= self.actors.len() - 1 {
-                    0
-                } else {
-                    current + 1
-                };
-                self.next.as_ref().unwrap().store(next, Ordering::Relaxed);
-                let t =
-                    crate::proxy::connect_udp_outbound(sess, self.dns_client.clone(), a).await?;
-                UdpOutboundHandler::handle(a.as_ref(), sess, t).await
-            }
-        }
+        let a = self.balancer.pick(sess).await;
+        debug!("static handles udp [{}] to [{}]", sess.destination, a.tag());
+        let t = crate::proxy::connect_udp_outbound(sess, self.dns_client.clone(), &a).await?;
+        UdpOutboundHandler::handle(a.as_ref(), sess, t).await
     }
 }
@@ -1,13 +1,12 @@
-use std::fs::File;
-use std::io::{self, BufReader};
+use std::io;
 use std::path::Path;
 
 use anyhow::Result;
 
 #[cfg(feature = "rustls-tls")]
 use {
-    rustls_pemfile::{certs, pkcs8_private_keys, rsa_private_keys},
-    tokio_rustls::rustls::{Certificate, PrivateKey, ServerConfig},
+    crate::proxy::tls::{load_certs, load_keys},
+    tokio_rustls::rustls::ServerConfig,
     tokio_rustls::TlsAcceptor,
 };
 
@@ -18,25 +17,6 @@ pub struct Handler {
     acceptor: TlsAcceptor,
 }
 
-#[cfg(feature = "rustls-tls")]
-fn load_certs(path: &Path) -> io::Result<Vec<Certificate>> {
-    certs(&mut BufReader::new(File::open(path)?))
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid cert"))
-        .map(|mut certs| certs.drain(..).map(Certificate).collect())
-}
-
-#[cfg(feature = "rustls-tls")]
-fn load_keys(path: &Path) -> io::Result<Vec<PrivateKey>> {
-    let mut keys: Vec<PrivateKey> = pkcs8_private_keys(&mut BufReader::new(File::open(path)?))
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid key"))
-        .map(|mut keys| keys.drain(..).map(PrivateKey).collect())?;
-    let mut keys2: Vec<PrivateKey> = rsa_private_keys(&mut BufReader::new(File::open(path)?))
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid key"))
-        .map(|mut keys| keys.drain(..).map(PrivateKey).collect())?;
-    keys.append(&mut keys2);
-    Ok(keys)
-}
-
 impl Handler {
     pub fn new(certificate: String, certificate_key: String) -> Result<Self> {
         #[cfg(feature = "rustls-tls")]
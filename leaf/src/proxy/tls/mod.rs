@@ -1,4 +1,34 @@
+#[cfg(feature = "rustls-tls")]
+use {
+    rustls_pemfile::{certs, pkcs8_private_keys, rsa_private_keys},
+    std::{
+        fs::File,
+        io::{self, BufReader},
+        path::Path,
+    },
+    tokio_rustls::rustls::{Certificate, PrivateKey},
+};
+
 #[cfg(feature = "inbound-tls")]
 pub mod inbound;
 #[cfg(feature = "outbound-tls")]
 pub mod outbound;
+
+#[cfg(feature = "rustls-tls")]
+pub(crate) fn load_certs(path: &Path) -> io::Result<Vec<Certificate>> {
+    certs(&mut BufReader::new(File::open(path)?))
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid cert"))
+        .map(|mut certs| certs.drain(..).map(Certificate).collect())
+}
+
+#[cfg(feature = "rustls-tls")]
+pub(crate) fn load_keys(path: &Path) -> io::Result<Vec<PrivateKey>> {
+    let mut keys: Vec<PrivateKey> = pkcs8_private_keys(&mut BufReader::new(File::open(path)?))
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid key"))
+        .map(|mut keys| keys.drain(..).map(PrivateKey).collect())?;
+    let mut keys2: Vec<PrivateKey> = rsa_private_keys(&mut BufReader::new(File::open(path)?))
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid key"))
+        .map(|mut keys| keys.drain(..).map(PrivateKey).collect())?;
+    keys.append(&mut keys2);
+    Ok(keys)
+}
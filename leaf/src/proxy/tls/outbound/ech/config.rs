@@ -0,0 +1,208 @@
+use std::io;
+
+use crate::common::tls13::wire::Reader;
+
+use super::hpke::{self, KEM_X25519_HKDF_SHA256};
+
+// The version of ECHConfig of the final drafts and RFC 9849.
+const ECH_VERSION: u16 = 0xfe0d;
+
+/// An ECHConfig the client supports.
+pub struct EchConfig {
+    // The whole ECHConfig, which is a part of the HPKE info.
+    raw: Vec<u8>,
+    pub config_id: u8,
+    pub public_key: Vec<u8>,
+    pub suite: hpke::Suite,
+    pub maximum_name_length: u8,
+    pub public_name: String,
+}
+
+fn invalid_config() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid ech config")
+}
+
+impl EchConfig {
+    /// Selects the first config of an ECHConfigList with a version, a KEM and
+    /// an HPKE cipher suite supported.
+    pub fn select(list: &[u8]) -> io::Result<Self> {
+        let mut r = Reader::new(list);
+        let mut configs = Reader::new(r.prefixed(2).map_err(|_| invalid_config())?);
+        while !configs.is_empty() {
+            let start = configs.rest();
+            let version = configs.u16().map_err(|_| invalid_config())?;
+            let contents = configs.prefixed(2).map_err(|_| invalid_config())?;
+            if version != ECH_VERSION {
+                continue;
+            }
+            let raw = &start[..start.len() - configs.rest().len()];
+            if let Some(config) = Self::parse(raw, contents).map_err(|_| invalid_config())? {
+                return Ok(config);
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "no supported ech config",
+        ))
+    }
+
+    // Returns None if the config isn't supported.
+    fn parse(raw: &[u8], contents: &[u8]) -> io::Result<Option<Self>> {
+        let mut r = Reader::new(contents);
+        let config_id = r.u8()?;
+        let kem_id = r.u16()?;
+        let public_key = r.prefixed(2)?;
+        let mut cipher_suites = Reader::new(r.prefixed(2)?);
+        let mut suite = None;
+        while !cipher_suites.is_empty() {
+            let kdf_id = cipher_suites.u16()?;
+            let aead_id = cipher_suites.u16()?;
+            if suite.is_none() {
+                suite = hpke::Suite::new(kdf_id, aead_id);
+            }
+        }
+        let maximum_name_length = r.u8()?;
+        let public_name =
+            String::from_utf8(r.prefixed(1)?.to_vec()).map_err(|_| invalid_config())?;
+        let mut extensions = Reader::new(r.prefixed(2)?);
+        while !extensions.is_empty() {
+            // Configs with mandatory extensions unknown must be skipped.
+            if extensions.u16()? & 0x8000 != 0 {
+                return Ok(None);
+            }
+            extensions.prefixed(2)?;
+        }
+        if !r.is_empty() {
+            return Err(invalid_config());
+        }
+        let suite = match suite {
+            Some(suite) if kem_id == KEM_X25519_HKDF_SHA256 && public_key.len() == 32 => suite,
+            _ => return Ok(None),
+        };
+        Ok(Some(EchConfig {
+            raw: raw.to_vec(),
+            config_id,
+            public_key: public_key.to_vec(),
+            suite,
+            maximum_name_length,
+            public_name,
+        }))
+    }
+
+    /// The info of the HPKE context.
+    pub fn info(&self) -> Vec<u8> {
+        [&b"tls ech\0"[..], &self.raw].concat()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use bytes::{BufMut, BytesMut};
+
+    use crate::common::tls13::wire::put_prefixed;
+
+    use super::*;
+
+    /// Encodes an ECHConfig.
+    pub fn ech_config(
+        version: u16,
+        config_id: u8,
+        kem_id: u16,
+        public_key: &[u8],
+        public_name: &str,
+        extension: Option<u16>,
+    ) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        buf.put_u16(version);
+        put_prefixed(&mut buf, 2, |buf| {
+            buf.put_u8(config_id);
+            buf.put_u16(kem_id);
+            put_prefixed(buf, 2, |buf| buf.put_slice(public_key));
+            put_prefixed(buf, 2, |buf| {
+                // An unknown KDF and HKDF-SHA256 with AES-128-GCM.
+                buf.put_slice(&[0, 9, 0, 1, 0, 1, 0, 1]);
+            });
+            buf.put_u8(32);
+            put_prefixed(buf, 1, |buf| buf.put_slice(public_name.as_bytes()));
+            put_prefixed(buf, 2, |buf| {
+                if let Some(ext_type) = extension {
+                    buf.put_u16(ext_type);
+                    buf.put_u16(0);
+                }
+            });
+        });
+        buf.to_vec()
+    }
+
+    pub fn ech_config_list(configs: &[Vec<u8>]) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        put_prefixed(&mut buf, 2, |buf| {
+            for config in configs {
+                buf.put_slice(config);
+            }
+        });
+        buf.to_vec()
+    }
+
+    #[test]
+    fn test_select() {
+        let supported = ech_config(ECH_VERSION, 5, 0x0020, &[1u8; 32], "public.example", None);
+        let list = ech_config_list(&[
+            // An older draft.
+            ech_config(0xfe0a, 1, 0x0020, &[1u8; 32], "a.example", None),
+            // P-256
+            ech_config(ECH_VERSION, 2, 0x0010, &[1u8; 65], "b.example", None),
+            // A mandatory extension.
+            ech_config(
+                ECH_VERSION,
+                3,
+                0x0020,
+                &[1u8; 32],
+                "c.example",
+                Some(0xfe00),
+            ),
+            ech_config(
+                ECH_VERSION,
+                4,
+                0x0020,
+                &[1u8; 32],
+                "d.example",
+                Some(0x0a00),
+            )[..3]
+                .to_vec(),
+        ]);
+        // The truncated config makes the list invalid.
+        assert!(EchConfig::select(&list).is_err());
+
+        let list = ech_config_list(&[
+            ech_config(0xfe0a, 1, 0x0020, &[1u8; 32], "a.example", None),
+            ech_config(ECH_VERSION, 2, 0x0010, &[1u8; 65], "b.example", None),
+            ech_config(
+                ECH_VERSION,
+                3,
+                0x0020,
+                &[1u8; 32],
+                "c.example",
+                Some(0xfe00),
+            ),
+            supported.clone(),
+            ech_config(ECH_VERSION, 6, 0x0020, &[1u8; 32], "e.example", None),
+        ]);
+        let config = EchConfig::select(&list).unwrap();
+        assert_eq!(config.config_id, 5);
+        assert_eq!(config.public_name, "public.example");
+        assert_eq!(config.maximum_name_length, 32);
+        assert_eq!((config.suite.kdf_id, config.suite.aead_id), (1, 1));
+        assert_eq!(config.info(), [&b"tls ech\0"[..], &supported].concat());
+
+        let list = ech_config_list(&[ech_config(
+            ECH_VERSION,
+            2,
+            0x0010,
+            &[1u8; 65],
+            "b.example",
+            None,
+        )]);
+        assert!(EchConfig::select(&list).is_err());
+    }
+}
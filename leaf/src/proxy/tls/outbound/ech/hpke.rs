@@ -0,0 +1,278 @@
+//! The base mode of HPKE (RFC 9180) with DHKEM(X25519, HKDF-SHA256), as much
+//! as ECH needs.
+
+use std::io;
+
+use ring::rand::SecureRandom;
+use ring::{aead, agreement, hkdf, hmac};
+
+use crate::common::tls13::crypto::Len;
+
+pub const KEM_X25519_HKDF_SHA256: u16 = 0x0020;
+
+fn hpke_err() -> io::Error {
+    io::Error::new(io::ErrorKind::Other, "hpke failed")
+}
+
+#[derive(Clone, Copy)]
+struct Kdf {
+    hmac: hmac::Algorithm,
+    hkdf: hkdf::Algorithm,
+}
+
+impl Kdf {
+    fn from_id(id: u16) -> Option<Self> {
+        match id {
+            // HKDF-SHA256
+            0x0001 => Some(Kdf {
+                hmac: hmac::HMAC_SHA256,
+                hkdf: hkdf::HKDF_SHA256,
+            }),
+            // HKDF-SHA384
+            0x0002 => Some(Kdf {
+                hmac: hmac::HMAC_SHA384,
+                hkdf: hkdf::HKDF_SHA384,
+            }),
+            // HKDF-SHA512
+            0x0003 => Some(Kdf {
+                hmac: hmac::HMAC_SHA512,
+                hkdf: hkdf::HKDF_SHA512,
+            }),
+            _ => None,
+        }
+    }
+
+    fn labeled_extract(&self, suite_id: &[u8], salt: &[u8], label: &[u8], ikm: &[u8]) -> Vec<u8> {
+        // HKDF-Extract is the HMAC of the IKM keyed with the salt, the PRK is
+        // needed as is for the key schedule context.
+        let mut ctx = hmac::Context::with_key(&hmac::Key::new(self.hmac, salt));
+        for data in [&b"HPKE-v1"[..], suite_id, label, ikm] {
+            ctx.update(data);
+        }
+        ctx.sign().as_ref().to_vec()
+    }
+
+    fn labeled_expand(
+        &self,
+        suite_id: &[u8],
+        prk: &[u8],
+        label: &[u8],
+        info: &[u8],
+        len: usize,
+    ) -> Vec<u8> {
+        let len_bytes = (len as u16).to_be_bytes();
+        let info = [&len_bytes[..], b"HPKE-v1", suite_id, label, info];
+        let mut out = vec![0u8; len];
+        // Expanding fails only if the length is too large for the hash.
+        hkdf::Prk::new_less_safe(self.hkdf, prk)
+            .expand(&info, Len(len))
+            .and_then(|okm| okm.fill(&mut out))
+            .unwrap();
+        out
+    }
+}
+
+/// The KDF and the AEAD of a context, the KEM is always X25519.
+#[derive(Clone, Copy)]
+pub struct Suite {
+    pub kdf_id: u16,
+    pub aead_id: u16,
+    kdf: Kdf,
+    aead: &'static aead::Algorithm,
+}
+
+impl Suite {
+    pub fn new(kdf_id: u16, aead_id: u16) -> Option<Self> {
+        let aead = match aead_id {
+            0x0001 => &aead::AES_128_GCM,
+            0x0002 => &aead::AES_256_GCM,
+            0x0003 => &aead::CHACHA20_POLY1305,
+            _ => return None,
+        };
+        Some(Suite {
+            kdf_id,
+            aead_id,
+            kdf: Kdf::from_id(kdf_id)?,
+            aead,
+        })
+    }
+
+    pub fn tag_len(&self) -> usize {
+        self.aead.tag_len()
+    }
+
+    fn key_schedule(&self, shared_secret: &[u8], info: &[u8]) -> Context {
+        let mut suite_id = b"HPKE".to_vec();
+        for id in [KEM_X25519_HKDF_SHA256, self.kdf_id, self.aead_id] {
+            suite_id.extend_from_slice(&id.to_be_bytes());
+        }
+        let kdf = &self.kdf;
+        // The base mode, without PSK.
+        let mut context = vec![0u8];
+        context.extend(kdf.labeled_extract(&suite_id, &[], b"psk_id_hash", &[]));
+        context.extend(kdf.labeled_extract(&suite_id, &[], b"info_hash", info));
+        let secret = kdf.labeled_extract(&suite_id, shared_secret, b"secret", &[]);
+        let key = kdf.labeled_expand(&suite_id, &secret, b"key", &context, self.aead.key_len());
+        let mut base_nonce = [0u8; aead::NONCE_LEN];
+        base_nonce.copy_from_slice(&kdf.labeled_expand(
+            &suite_id,
+            &secret,
+            b"base_nonce",
+            &context,
+            aead::NONCE_LEN,
+        ));
+        Context {
+            // The key has the length of the algorithm.
+            key: aead::LessSafeKey::new(aead::UnboundKey::new(self.aead, &key).unwrap()),
+            base_nonce,
+            seq: 0,
+        }
+    }
+}
+
+// ExtractAndExpand of DHKEM(X25519, HKDF-SHA256).
+fn kem_shared_secret(dh: &[u8], enc: &[u8], public_key: &[u8]) -> Vec<u8> {
+    let kdf = Kdf::from_id(0x0001).unwrap();
+    let suite_id = [b'K', b'E', b'M', 0x00, 0x20];
+    let eae_prk = kdf.labeled_extract(&suite_id, &[], b"eae_prk", dh);
+    let kem_context = [enc, public_key].concat();
+    kdf.labeled_expand(&suite_id, &eae_prk, b"shared_secret", &kem_context, 32)
+}
+
+/// Sets up a context to seal messages to the holder of `public_key`, returns
+/// the encapsulated key and the context.
+pub fn setup_base_s(
+    rng: &dyn SecureRandom,
+    suite: Suite,
+    public_key: &[u8],
+    info: &[u8],
+) -> io::Result<(Vec<u8>, Context)> {
+    let private_key = agreement::EphemeralPrivateKey::generate(&agreement::X25519, rng)
+        .map_err(|_| hpke_err())?;
+    let enc = private_key
+        .compute_public_key()
+        .map_err(|_| hpke_err())?
+        .as_ref()
+        .to_vec();
+    let shared_secret = agreement::agree_ephemeral(
+        private_key,
+        &agreement::UnparsedPublicKey::new(&agreement::X25519, public_key),
+        hpke_err(),
+        |dh| Ok(kem_shared_secret(dh, &enc, public_key)),
+    )?;
+    Ok((enc, suite.key_schedule(&shared_secret, info)))
+}
+
+/// Sets up a context to open messages sealed to `private_key`.
+#[cfg(test)]
+pub fn setup_base_r(
+    suite: Suite,
+    enc: &[u8],
+    private_key: agreement::EphemeralPrivateKey,
+    info: &[u8],
+) -> io::Result<Context> {
+    let public_key = private_key.compute_public_key().map_err(|_| hpke_err())?;
+    let shared_secret = agreement::agree_ephemeral(
+        private_key,
+        &agreement::UnparsedPublicKey::new(&agreement::X25519, enc),
+        hpke_err(),
+        |dh| Ok(kem_shared_secret(dh, enc, public_key.as_ref())),
+    )?;
+    Ok(suite.key_schedule(&shared_secret, info))
+}
+
+pub struct Context {
+    key: aead::LessSafeKey,
+    base_nonce: [u8; aead::NONCE_LEN],
+    seq: u64,
+}
+
+impl Context {
+    fn nonce(&mut self) -> aead::Nonce {
+        let mut nonce = self.base_nonce;
+        for (n, s) in nonce[4..].iter_mut().zip(self.seq.to_be_bytes()) {
+            *n ^= s;
+        }
+        self.seq += 1;
+        aead::Nonce::assume_unique_for_key(nonce)
+    }
+
+    pub fn seal(&mut self, aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let nonce = self.nonce();
+        let mut buf = plaintext.to_vec();
+        // Sealing fails only if the data is too long for the cipher.
+        self.key
+            .seal_in_place_append_tag(nonce, aead::Aad::from(aad), &mut buf)
+            .unwrap();
+        buf
+    }
+
+    #[cfg(test)]
+    pub fn open(&mut self, aad: &[u8], ciphertext: &[u8]) -> io::Result<Vec<u8>> {
+        let nonce = self.nonce();
+        let mut buf = ciphertext.to_vec();
+        let len = self
+            .key
+            .open_in_place(nonce, aead::Aad::from(aad), &mut buf)
+            .map_err(|_| hpke_err())?
+            .len();
+        buf.truncate(len);
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::test::rand::FixedSliceRandom;
+
+    use super::*;
+
+    fn key(seed: &str) -> agreement::EphemeralPrivateKey {
+        agreement::EphemeralPrivateKey::generate(
+            &agreement::X25519,
+            &FixedSliceRandom {
+                bytes: &hex::decode(seed).unwrap(),
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_seal() {
+        // The first test vector of RFC 9180, A.1.1.
+        let suite = Suite::new(0x0001, 0x0001).unwrap();
+        let info = hex::decode("4f6465206f6e2061204772656369616e2055726e").unwrap();
+        let skr = "4612c550263fc8ad58375df3f557aac531d26850903e55a9f23f21d8534e8ac8";
+        let pkr = key(skr).compute_public_key().unwrap();
+        assert_eq!(
+            hex::encode(pkr.as_ref()),
+            "3948cfe0ad1ddb695d780e59077195da6c56506b027329794ab02bca80815c4d"
+        );
+        let ske = hex::decode("52c4a758a802cd8b936eceea314432798d5baf2d7e9235dc084ab1b9cfa2f736")
+            .unwrap();
+        let (enc, mut sender) = setup_base_s(
+            &FixedSliceRandom { bytes: &ske },
+            suite,
+            pkr.as_ref(),
+            &info,
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(&enc),
+            "37fda3567bdbd628e88668c3c8d7e97d1d1253b6d4ea6d44c150f741f1bf4431"
+        );
+        let pt = b"Beauty is truth, truth beauty";
+        let ct = sender.seal(b"Count-0", pt);
+        assert_eq!(
+            hex::encode(&ct),
+            "f938558b5d72f1a23810b4be2ab4f84331acc02fc97babc53a52ae8218a355a96d8770ac83d07bea87e13c512a"
+        );
+
+        let mut receiver = setup_base_r(suite, &enc, key(skr), &info).unwrap();
+        assert_eq!(receiver.open(b"Count-0", &ct).unwrap(), pt);
+        // The sequence numbers advance on both sides.
+        let ct = sender.seal(b"Count-1", pt);
+        assert_eq!(receiver.open(b"Count-1", &ct).unwrap(), pt);
+        assert!(receiver.open(b"Count-2", &ct).is_err());
+    }
+}
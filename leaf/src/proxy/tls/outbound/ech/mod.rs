@@ -0,0 +1,575 @@
+//! Encrypted Client Hello (RFC 9849), the real ClientHello is sealed with
+//! HPKE to the key of the client-facing server and sent in an extension of
+//! an outer ClientHello, which only carries the public name of the server.
+//!
+//! rustls and OpenSSL don't support ECH, so the handshake is made with the
+//! client of `common::tls13` and the server certificate is verified with
+//! webpki. HelloRetryRequests and the retry configs of servers rejecting ECH
+//! aren't supported.
+
+use std::io;
+use std::net::IpAddr;
+use std::time::SystemTime;
+
+use ring::constant_time;
+use ring::digest;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{agreement, agreement::EphemeralPrivateKey};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::webpki;
+
+use crate::app::SyncDnsClient;
+use crate::common::tls13::{
+    crypto::{expand_label, RecordKey},
+    handshake::*,
+    wire::*,
+};
+
+use super::pin;
+
+mod config;
+mod hpke;
+
+pub use config::EchConfig;
+
+const EXT_ENCRYPTED_CLIENT_HELLO: u16 = 0xfe0d;
+// The types of the extension in the outer and the inner ClientHello.
+const ECH_OUTER: u8 = 0;
+const ECH_INNER: u8 = 1;
+
+// The last 8 bytes of the random of a ServerHello message, which confirm the
+// server accepted ECH.
+const CONFIRMATION_OFFSET: usize = 4 + 2 + 24;
+
+// The algorithms certificates may be signed with.
+static SIGNATURE_ALGORITHMS: &[&webpki::SignatureAlgorithm] = &[
+    &webpki::ECDSA_P256_SHA256,
+    &webpki::ECDSA_P256_SHA384,
+    &webpki::ECDSA_P384_SHA256,
+    &webpki::ECDSA_P384_SHA384,
+    &webpki::ED25519,
+    &webpki::RSA_PSS_2048_8192_SHA256_LEGACY_KEY,
+    &webpki::RSA_PSS_2048_8192_SHA384_LEGACY_KEY,
+    &webpki::RSA_PSS_2048_8192_SHA512_LEGACY_KEY,
+    &webpki::RSA_PKCS1_2048_8192_SHA256,
+    &webpki::RSA_PKCS1_2048_8192_SHA384,
+    &webpki::RSA_PKCS1_2048_8192_SHA512,
+    &webpki::RSA_PKCS1_3072_8192_SHA384,
+];
+
+/// Where the ECHConfig comes from.
+pub enum Ech {
+    Config(EchConfig),
+    /// Looked up in the HTTPS record of the server name on each connection.
+    Dns(SyncDnsClient),
+}
+
+fn cert_err(e: webpki::Error) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid server certificate: {:?}", e),
+    )
+}
+
+// The length of the padding of an EncodedClientHelloInner of `len` bytes, so
+// that its length doesn't reveal the server name.
+fn padding_len(config: &EchConfig, server_name: &str, len: usize) -> usize {
+    let max_len = config.maximum_name_length as usize;
+    let mut padding = if server_name.parse::<IpAddr>().is_err() {
+        max_len.saturating_sub(server_name.len())
+    } else {
+        max_len + 9
+    };
+    padding += 31 - (len + padding - 1) % 32;
+    padding
+}
+
+/// Verifies the certificate chain of the server against `roots`, the
+/// bundled web roots if empty, and its CertificateVerify.
+fn verify_server(roots: &[Vec<u8>], server_name: &str, auth: &ServerAuth) -> io::Result<()> {
+    let cert = webpki::EndEntityCert::try_from(auth.certificates[0]).map_err(cert_err)?;
+    let now = webpki::Time::try_from(SystemTime::now())
+        .map_err(|_| io::Error::new(io::ErrorKind::Other, "invalid system time"))?;
+    let intermediates = &auth.certificates[1..];
+    if roots.is_empty() {
+        cert.verify_is_valid_tls_server_cert(
+            SIGNATURE_ALGORITHMS,
+            &webpki_roots::TLS_SERVER_ROOTS,
+            intermediates,
+            now,
+        )
+    } else {
+        let anchors = roots
+            .iter()
+            .map(|root| webpki::TrustAnchor::try_from_cert_der(root))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(cert_err)?;
+        cert.verify_is_valid_tls_server_cert(
+            SIGNATURE_ALGORITHMS,
+            &webpki::TlsServerTrustAnchors(&anchors),
+            intermediates,
+            now,
+        )
+    }
+    .map_err(cert_err)?;
+    let name = webpki::DnsNameRef::try_from_ascii_str(server_name)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid dnsname"))?;
+    cert.verify_is_valid_for_dns_name(name).map_err(cert_err)?;
+    let alg = match auth.scheme {
+        0x0403 => &webpki::ECDSA_P256_SHA256,
+        0x0503 => &webpki::ECDSA_P384_SHA384,
+        0x0804 => &webpki::RSA_PSS_2048_8192_SHA256_LEGACY_KEY,
+        0x0805 => &webpki::RSA_PSS_2048_8192_SHA384_LEGACY_KEY,
+        0x0806 => &webpki::RSA_PSS_2048_8192_SHA512_LEGACY_KEY,
+        SIG_ED25519 => &webpki::ED25519,
+        scheme => {
+            return Err(handshake_err(format!(
+                "unsupported tls signature scheme {:#06x}",
+                scheme
+            )));
+        }
+    };
+    cert.verify_signature(alg, &auth.content, auth.signature)
+        .map_err(cert_err)
+}
+
+/// Runs the handshake on `stream` with the inner ClientHello for
+/// `server_name` sealed with `config`, the server is verified against
+/// `roots` and `pins` if not empty. Returns the keys of the records from
+/// and to the server.
+pub async fn handshake<S>(
+    stream: &mut S,
+    server_name: &str,
+    config: &EchConfig,
+    alpns: &[String],
+    roots: &[Vec<u8>],
+    pins: &[Vec<u8>],
+) -> io::Result<(RecordKey, RecordKey)>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let rng = SystemRandom::new();
+    let mut inner_random = [0u8; 32];
+    let mut outer_random = [0u8; 32];
+    let mut session_id = [0u8; 32];
+    rng.fill(&mut inner_random)
+        .and_then(|_| rng.fill(&mut outer_random))
+        .and_then(|_| rng.fill(&mut session_id))
+        .map_err(|_| handshake_err("generate random failed".to_string()))?;
+    let private_key = EphemeralPrivateKey::generate(&agreement::X25519, &rng)
+        .map_err(|_| handshake_err("generate key failed".to_string()))?;
+    let public_key = private_key
+        .compute_public_key()
+        .map_err(|_| handshake_err("generate key failed".to_string()))?;
+
+    // Both ClientHellos share the key share and the session ID, the latter
+    // is left out of the encoded inner one.
+    let inner = ClientHello {
+        random: &inner_random,
+        session_id: &session_id,
+        key_share: public_key.as_ref(),
+        server_name,
+        alpns,
+        tls12: false,
+        extensions: &[(EXT_ENCRYPTED_CLIENT_HELLO, &[ECH_INNER])],
+    };
+    let inner_hello = inner.encode();
+    let mut encoded_inner = ClientHello {
+        session_id: &[],
+        ..inner
+    }
+    .encode()
+    .split_off(4);
+    let padding = padding_len(config, server_name, encoded_inner.len());
+    encoded_inner.resize(encoded_inner.len() + padding, 0);
+
+    let (enc, mut context) =
+        hpke::setup_base_s(&rng, config.suite, &config.public_key, &config.info())?;
+    let payload_len = encoded_inner.len() + config.suite.tag_len();
+    let mut ech = vec![ECH_OUTER];
+    ech.extend_from_slice(&config.suite.kdf_id.to_be_bytes());
+    ech.extend_from_slice(&config.suite.aead_id.to_be_bytes());
+    ech.push(config.config_id);
+    ech.extend_from_slice(&(enc.len() as u16).to_be_bytes());
+    ech.extend_from_slice(&enc);
+    ech.extend_from_slice(&(payload_len as u16).to_be_bytes());
+    ech.resize(ech.len() + payload_len, 0);
+    let mut outer_hello = ClientHello {
+        random: &outer_random,
+        session_id: &session_id,
+        key_share: public_key.as_ref(),
+        server_name: &config.public_name,
+        alpns,
+        tls12: true,
+        extensions: &[(EXT_ENCRYPTED_CLIENT_HELLO, &ech)],
+    }
+    .encode();
+    // The payload ends the outer ClientHello, which is sealed as the AAD
+    // with the payload zeroed.
+    let payload = context.seal(&outer_hello[4..], &encoded_inner);
+    let len = outer_hello.len();
+    outer_hello[len - payload_len..].copy_from_slice(&payload);
+    send_client_hello(stream, &outer_hello).await?;
+
+    let mut reader = HandshakeReader::default();
+    let server_hello = reader.read(stream, None, HANDSHAKE_SERVER_HELLO).await?;
+    let (suite, server_public) = parse_server_hello(&server_hello, &session_id)?;
+
+    let mut transcript = digest::Context::new(suite.hash);
+    transcript.update(&inner_hello);
+    let mut confirmation_transcript = transcript.clone();
+    let mut zeroed = server_hello.clone();
+    zeroed[CONFIRMATION_OFFSET..CONFIRMATION_OFFSET + 8].fill(0);
+    confirmation_transcript.update(&zeroed);
+    let confirmation = expand_label(
+        &suite.extract(&inner_random),
+        b"ech accept confirmation",
+        confirmation_transcript.finish().as_ref(),
+        8,
+    );
+    constant_time::verify_slices_are_equal(
+        &server_hello[CONFIRMATION_OFFSET..CONFIRMATION_OFFSET + 8],
+        &confirmation,
+    )
+    .map_err(|_| handshake_err("ech rejected by server".to_string()))?;
+    transcript.update(&server_hello);
+
+    let shared = x25519(private_key, &server_public)?;
+    finish(stream, &mut reader, suite, &shared, transcript, |auth| {
+        verify_server(roots, server_name, auth)?;
+        if !pins.is_empty() {
+            pin::verify(pins, &auth.certificates)?;
+        }
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{BufMut, BytesMut};
+    use ring::signature::{EcdsaKeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
+    use ring::test::rand::FixedSliceRandom;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+    use crate::common::tls13::{crypto::Suite, Stream};
+
+    use super::config::tests::{ech_config, ech_config_list};
+    use super::*;
+
+    const SERVER_SEED: [u8; 32] = [9u8; 32];
+
+    fn server_key() -> EphemeralPrivateKey {
+        EphemeralPrivateKey::generate(
+            &agreement::X25519,
+            &FixedSliceRandom {
+                bytes: &SERVER_SEED,
+            },
+        )
+        .unwrap()
+    }
+
+    fn config(public_key: &[u8]) -> (Vec<u8>, EchConfig) {
+        let raw = ech_config(0xfe0d, 7, 0x0020, public_key, "public.example", None);
+        let config = EchConfig::select(&ech_config_list(&[raw.clone()])).unwrap();
+        (raw, config)
+    }
+
+    // Returns a CA and a certificate it issues for example.com.
+    fn certificates() -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let mut params = rcgen::CertificateParams::new(vec![]);
+        params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let ca = rcgen::Certificate::from_params(params).unwrap();
+        let cert = rcgen::generate_simple_self_signed(vec!["example.com".to_string()]).unwrap();
+        (
+            ca.serialize_der().unwrap(),
+            cert.serialize_der_with_signer(&ca).unwrap(),
+            cert.serialize_private_key_der(),
+        )
+    }
+
+    // Returns the server name, the content of the ECH extension and the key
+    // share of a ClientHello.
+    fn parse_hello(hello: &[u8]) -> (String, Option<Vec<u8>>, Vec<u8>) {
+        let mut r = Reader::new(&hello[4..]);
+        r.bytes(2 + 32).unwrap();
+        r.prefixed(1).unwrap();
+        r.prefixed(2).unwrap();
+        r.prefixed(1).unwrap();
+        let mut extensions = Reader::new(r.prefixed(2).unwrap());
+        let (mut server_name, mut ech, mut key_share) = (String::new(), None, Vec::new());
+        while !extensions.is_empty() {
+            let ext_type = extensions.u16().unwrap();
+            let data = extensions.prefixed(2).unwrap();
+            match ext_type {
+                EXT_SERVER_NAME => server_name = String::from_utf8(data[5..].to_vec()).unwrap(),
+                EXT_ENCRYPTED_CLIENT_HELLO => ech = Some(data.to_vec()),
+                EXT_KEY_SHARE => key_share = data[6..].to_vec(),
+                _ => (),
+            }
+        }
+        (server_name, ech, key_share)
+    }
+
+    // Opens the inner ClientHello as a client-facing server does.
+    fn open_inner(outer: &[u8], info: &[u8]) -> Option<Vec<u8>> {
+        let (server_name, ech, _) = parse_hello(outer);
+        assert_eq!(server_name, "public.example");
+        let ech = ech.unwrap();
+        let mut r = Reader::new(&ech);
+        assert_eq!(r.u8().unwrap(), ECH_OUTER);
+        let suite = hpke::Suite::new(r.u16().unwrap(), r.u16().unwrap()).unwrap();
+        assert_eq!(r.u8().unwrap(), 7);
+        let enc = r.prefixed(2).unwrap();
+        let payload = r.prefixed(2).unwrap();
+        let mut aad = outer[4..].to_vec();
+        let len = aad.len();
+        aad[len - payload.len()..].fill(0);
+        let encoded = hpke::setup_base_r(suite, enc, server_key(), info)
+            .unwrap()
+            .open(&aad, payload)
+            .ok()?;
+        assert_eq!(encoded.len() % 32, 0);
+
+        // Restores the session ID of the outer ClientHello.
+        let mut r = Reader::new(&encoded);
+        r.bytes(2 + 32).unwrap();
+        assert!(r.prefixed(1).unwrap().is_empty());
+        r.prefixed(2).unwrap();
+        r.prefixed(1).unwrap();
+        r.prefixed(2).unwrap();
+        assert!(r.rest().iter().all(|b| *b == 0));
+        let end = encoded.len() - r.rest().len();
+        let mut inner = BytesMut::new();
+        inner.put_u8(HANDSHAKE_CLIENT_HELLO);
+        put_prefixed(&mut inner, 3, |buf| {
+            buf.put_slice(&encoded[..2 + 32]);
+            put_prefixed(buf, 1, |buf| {
+                buf.put_slice(&outer[4 + 2 + 32 + 1..4 + 2 + 32 + 33])
+            });
+            buf.put_slice(&encoded[2 + 32 + 1..end]);
+        });
+        Some(inner.to_vec())
+    }
+
+    fn server_hello(random: &[u8], session_id: &[u8], key_share: &[u8]) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        buf.put_u8(HANDSHAKE_SERVER_HELLO);
+        put_prefixed(&mut buf, 3, |buf| {
+            buf.put_u16(TLS12);
+            buf.put_slice(random);
+            put_prefixed(buf, 1, |buf| buf.put_slice(session_id));
+            buf.put_u16(0x1301);
+            buf.put_u8(0);
+            put_prefixed(buf, 2, |buf| {
+                put_extension(buf, EXT_SUPPORTED_VERSIONS, |buf| buf.put_u16(TLS13));
+                put_extension(buf, EXT_KEY_SHARE, |buf| {
+                    buf.put_u16(GROUP_X25519);
+                    put_prefixed(buf, 2, |buf| buf.put_slice(key_share));
+                });
+            });
+        });
+        buf.to_vec()
+    }
+
+    fn handshake_message(msg_type: u8, f: impl FnOnce(&mut BytesMut)) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        buf.put_u8(msg_type);
+        put_prefixed(&mut buf, 3, f);
+        buf.to_vec()
+    }
+
+    /// Echoes data as a server accepting ECH, the handshake is given up if
+    /// the inner ClientHello can't be opened.
+    async fn serve(
+        mut stream: DuplexStream,
+        info: Vec<u8>,
+        cert: Vec<u8>,
+        key: Vec<u8>,
+    ) -> io::Result<()> {
+        let mut record = vec![0u8; RECORD_HEADER_LEN];
+        stream.read_exact(&mut record).await?;
+        let len = u16::from_be_bytes([record[3], record[4]]) as usize;
+        let mut outer = vec![0u8; len];
+        stream.read_exact(&mut outer).await?;
+        let session_id = &outer[4 + 2 + 32 + 1..4 + 2 + 32 + 33];
+
+        let rng = SystemRandom::new();
+        let private_key = EphemeralPrivateKey::generate(&agreement::X25519, &rng).unwrap();
+        let public_key = private_key.compute_public_key().unwrap();
+        let inner = match open_inner(&outer, &info) {
+            Some(inner) => inner,
+            None => {
+                let hello = server_hello(&[1u8; 32], session_id, public_key.as_ref());
+                return send_client_hello(&mut stream, &hello).await;
+            }
+        };
+        let (server_name, ech, client_share) = parse_hello(&inner);
+        assert_eq!(server_name, "example.com");
+        assert_eq!(ech.unwrap(), [ECH_INNER]);
+        let suite = Suite::from_id(0x1301).unwrap();
+        let mut transcript = digest::Context::new(suite.hash);
+        transcript.update(&inner);
+        let mut hello = server_hello(&[0u8; 32], session_id, public_key.as_ref());
+        let mut confirmation_transcript = transcript.clone();
+        confirmation_transcript.update(&hello);
+        let confirmation = expand_label(
+            &suite.extract(&inner[4 + 2..4 + 2 + 32]),
+            b"ech accept confirmation",
+            confirmation_transcript.finish().as_ref(),
+            8,
+        );
+        hello[CONFIRMATION_OFFSET..CONFIRMATION_OFFSET + 8].copy_from_slice(&confirmation);
+        transcript.update(&hello);
+        // The records of the ServerHello and of the ClientHello have the
+        // same header.
+        send_client_hello(&mut stream, &hello).await?;
+
+        let shared = x25519(private_key, &client_share).unwrap();
+        let handshake_secret = suite.handshake_secret(&shared);
+        let hash = transcript.clone().finish();
+        let client_secret = suite.derive_secret(&handshake_secret, b"c hs traffic", hash.as_ref());
+        let server_secret = suite.derive_secret(&handshake_secret, b"s hs traffic", hash.as_ref());
+        let mut sealer = RecordKey::new(suite, server_secret.clone());
+        let mut buf = BytesMut::new();
+
+        let msg = handshake_message(HANDSHAKE_ENCRYPTED_EXTENSIONS, |buf| buf.put_u16(0));
+        transcript.update(&msg);
+        sealer.seal(CONTENT_HANDSHAKE, &msg, &mut buf);
+        let msg = handshake_message(HANDSHAKE_CERTIFICATE, |buf| {
+            buf.put_u8(0);
+            put_prefixed(buf, 3, |buf| {
+                put_prefixed(buf, 3, |buf| buf.put_slice(&cert));
+                buf.put_u16(0);
+            });
+        });
+        transcript.update(&msg);
+        sealer.seal(CONTENT_HANDSHAKE, &msg, &mut buf);
+        let mut content = vec![0x20u8; 64];
+        content.extend_from_slice(b"TLS 1.3, server CertificateVerify\0");
+        content.extend_from_slice(transcript.clone().finish().as_ref());
+        let key_pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &key).unwrap();
+        let signature = key_pair.sign(&rng, &content).unwrap();
+        let msg = handshake_message(HANDSHAKE_CERTIFICATE_VERIFY, |buf| {
+            buf.put_u16(0x0403);
+            put_prefixed(buf, 2, |buf| buf.put_slice(signature.as_ref()));
+        });
+        transcript.update(&msg);
+        sealer.seal(CONTENT_HANDSHAKE, &msg, &mut buf);
+        let verify_data = suite.finished(&server_secret, transcript.clone().finish().as_ref());
+        let msg = handshake_message(HANDSHAKE_FINISHED, |buf| {
+            buf.put_slice(verify_data.as_ref())
+        });
+        transcript.update(&msg);
+        sealer.seal(CONTENT_HANDSHAKE, &msg, &mut buf);
+        stream.write_all(&buf).await?;
+
+        let hash = transcript.clone().finish();
+        let mut opener = RecordKey::new(suite, client_secret.clone());
+        let msg = HandshakeReader::default()
+            .read(&mut stream, Some(&mut opener), HANDSHAKE_FINISHED)
+            .await?;
+        let verify_data = suite.finished(&client_secret, hash.as_ref());
+        assert_eq!(&msg[4..], verify_data.as_ref());
+
+        let master_secret = suite.master_secret(&handshake_secret);
+        let client_secret = suite.derive_secret(&master_secret, b"c ap traffic", hash.as_ref());
+        let server_secret = suite.derive_secret(&master_secret, b"s ap traffic", hash.as_ref());
+        let mut stream = Stream::new(
+            stream,
+            RecordKey::new(suite, client_secret),
+            RecordKey::new(suite, server_secret),
+        );
+        let mut buf = [0u8; 5];
+        stream.read_exact(&mut buf).await?;
+        stream.write_all(&buf).await?;
+        stream.shutdown().await
+    }
+
+    #[test]
+    fn test_padding() {
+        let (_, config) = config(&[1u8; 32]);
+        assert_eq!(config.maximum_name_length, 32);
+        assert_eq!(padding_len(&config, "example.com", 100), 21 + 7);
+        assert_eq!(padding_len(&config, "1.1.1.1", 100), 41 + 19);
+        let name = "a".repeat(40);
+        assert_eq!(padding_len(&config, &name, 96), 0);
+    }
+
+    #[test]
+    fn test_handshake() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let public_key = server_key().compute_public_key().unwrap();
+            let (raw, config) = self::config(public_key.as_ref());
+            let info = [&b"tls ech\0"[..], &raw].concat();
+            let (ca, cert, key) = certificates();
+            let alpns = ["h2".to_string()];
+
+            let (mut client_io, server_io) = tokio::io::duplex(65536);
+            let server = tokio::spawn(serve(server_io, info.clone(), cert.clone(), key.clone()));
+            let pins = [pin::cert_chain_hash([&cert])];
+            let (opener, sealer) = handshake(
+                &mut client_io,
+                "example.com",
+                &config,
+                &alpns,
+                &[ca.clone()],
+                &pins,
+            )
+            .await
+            .unwrap();
+            let mut stream = Stream::new(client_io, opener, sealer);
+            stream.write_all(b"hello").await.unwrap();
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            assert_eq!(buf, b"hello");
+            server.await.unwrap().unwrap();
+
+            // The certificate isn't trusted.
+            let (other_ca, ..) = certificates();
+            let (mut client_io, server_io) = tokio::io::duplex(65536);
+            tokio::spawn(serve(server_io, info.clone(), cert.clone(), key.clone()));
+            let err = handshake(
+                &mut client_io,
+                "example.com",
+                &config,
+                &alpns,
+                &[other_ca],
+                &[],
+            )
+            .await
+            .err()
+            .unwrap();
+            assert!(err.to_string().contains("certificate"));
+
+            // The certificate is trusted but not pinned.
+            let (mut client_io, server_io) = tokio::io::duplex(65536);
+            tokio::spawn(serve(server_io, info.clone(), cert.clone(), key.clone()));
+            let err = handshake(
+                &mut client_io,
+                "example.com",
+                &config,
+                &alpns,
+                &[ca.clone()],
+                &[vec![0u8; 32]],
+            )
+            .await
+            .err()
+            .unwrap();
+            assert!(err.to_string().contains("unpinned"));
+
+            // The server can't open the inner ClientHello.
+            let (_, config) = self::config(&[1u8; 32]);
+            let (mut client_io, server_io) = tokio::io::duplex(65536);
+            tokio::spawn(serve(server_io, info, cert, key));
+            let err = handshake(&mut client_io, "example.com", &config, &alpns, &[ca], &[])
+                .await
+                .err()
+                .unwrap();
+            assert_eq!(err.to_string(), "ech rejected by server");
+        });
+    }
+}
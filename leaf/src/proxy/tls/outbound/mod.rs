@@ -1,3 +1,8 @@
+#[cfg(feature = "outbound-ech")]
+pub mod ech;
+mod pin;
 pub mod tcp;
 
+#[cfg(feature = "outbound-ech")]
+pub use ech::{Ech, EchConfig};
 pub use tcp::Handler as TcpHandler;
@@ -0,0 +1,68 @@
+use std::io;
+
+use sha2::{Digest, Sha256};
+
+/// The SHA-256 hash of a certificate chain as v2ray computes it, the hash of
+/// the first certificate chained with the hashes of the rest.
+pub fn cert_chain_hash<I, C>(certs: I) -> Vec<u8>
+where
+    I: IntoIterator<Item = C>,
+    C: AsRef<[u8]>,
+{
+    let mut hash: Option<Vec<u8>> = None;
+    for cert in certs {
+        let cert_hash = Sha256::digest(cert.as_ref());
+        hash = Some(match hash {
+            None => cert_hash.to_vec(),
+            Some(hash) => Sha256::new()
+                .chain(&hash)
+                .chain(cert_hash)
+                .finalize()
+                .to_vec(),
+        });
+    }
+    hash.unwrap_or_default()
+}
+
+/// Checks the certificate chain of the server is one of the `pins`.
+pub fn verify<I, C>(pins: &[Vec<u8>], certs: I) -> io::Result<()>
+where
+    I: IntoIterator<Item = C>,
+    C: AsRef<[u8]>,
+{
+    let hash = cert_chain_hash(certs);
+    if pins.iter().any(|pin| pin == &hash) {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "unpinned server certificate chain {}",
+                base64::encode(&hash)
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cert_chain_hash() {
+        let leaf = b"leaf".as_slice();
+        let ca = b"ca".as_slice();
+        assert_eq!(cert_chain_hash([leaf]), Sha256::digest(leaf).to_vec());
+        let chained = Sha256::new()
+            .chain(Sha256::digest(leaf))
+            .chain(Sha256::digest(ca))
+            .finalize()
+            .to_vec();
+        assert_eq!(cert_chain_hash([leaf, ca]), chained);
+
+        let pins = vec![vec![0u8; 32], chained];
+        assert!(verify(&pins, [leaf, ca]).is_ok());
+        assert!(verify(&pins, [leaf]).is_err());
+        assert!(verify(&pins, Vec::<&[u8]>::new()).is_err());
+    }
+}
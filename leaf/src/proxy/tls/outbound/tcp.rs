@@ -9,7 +9,8 @@ use log::*;
 
 #[cfg(feature = "rustls-tls")]
 use {
-    std::sync::Arc,
+    crate::proxy::tls::{load_certs, load_keys},
+    std::{path::Path, sync::Arc},
     tokio_rustls::{
         rustls::{ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName},
         webpki, TlsConnector,
@@ -18,20 +19,37 @@ use {
 
 #[cfg(feature = "openssl-tls")]
 use {
-    openssl::ssl::{Ssl, SslConnector, SslMethod},
+    openssl::ssl::{Ssl, SslConnector, SslFiletype, SslMethod},
     std::pin::Pin,
     std::sync::Once,
     tokio_openssl::SslStream,
 };
 
+#[cfg(feature = "outbound-ech")]
+use {
+    super::ech::{self, Ech, EchConfig},
+    crate::common::tls13::Stream,
+};
+
 use crate::{proxy::*, session::Session};
 
+use super::pin;
+
 pub struct Handler {
     server_name: String,
     #[cfg(feature = "rustls-tls")]
     tls_config: Arc<ClientConfig>,
     #[cfg(feature = "openssl-tls")]
     ssl_connector: SslConnector,
+    // The hashes of the certificate chains the server may present.
+    pins: Vec<Vec<u8>>,
+    #[cfg(feature = "outbound-ech")]
+    ech: Option<Ech>,
+    #[cfg(feature = "outbound-ech")]
+    alpns: Vec<String>,
+    // The DER of the trusted certificates, the bundled roots if empty.
+    #[cfg(feature = "outbound-ech")]
+    roots: Vec<Vec<u8>>,
 }
 
 impl Handler {
@@ -39,13 +57,20 @@ impl Handler {
         server_name: String,
         alpns: Vec<String>,
         certificate: Option<String>,
+        client_certificate: Option<(String, String)>,
     ) -> Result<Self> {
         #[cfg(feature = "rustls-tls")]
         {
             let mut root_cert_store = RootCertStore::empty();
+            #[cfg(feature = "outbound-ech")]
+            let mut roots = Vec::new();
             if let Some(cert) = certificate {
                 let mut pem = BufReader::new(File::open(cert)?);
                 let certs = rustls_pemfile::certs(&mut pem)?;
+                #[cfg(feature = "outbound-ech")]
+                {
+                    roots = certs.clone();
+                }
                 let trust_anchors = certs.iter().map(|cert| {
                     let ta = webpki::TrustAnchor::try_from_cert_der(&cert[..]).unwrap(); // FIXME
                     OwnedTrustAnchor::from_subject_spki_name_constraints(
@@ -67,17 +92,35 @@ impl Handler {
                 );
             }
 
-            let mut config = ClientConfig::builder()
+            let builder = ClientConfig::builder()
                 .with_safe_defaults()
-                .with_root_certificates(root_cert_store)
-                .with_no_client_auth();
+                .with_root_certificates(root_cert_store);
+            let mut config = if let Some((cert, key)) = client_certificate {
+                let certs = load_certs(Path::new(&cert))?;
+                let key = load_keys(Path::new(&key))?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no key found"))?;
+                builder
+                    .with_single_cert(certs, key)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+            } else {
+                builder.with_no_client_auth()
+            };
 
-            for alpn in alpns {
+            for alpn in &alpns {
                 config.alpn_protocols.push(alpn.as_bytes().to_vec());
             }
             Ok(Handler {
                 server_name,
                 tls_config: Arc::new(config),
+                pins: Vec::new(),
+                #[cfg(feature = "outbound-ech")]
+                ech: None,
+                #[cfg(feature = "outbound-ech")]
+                alpns,
+                #[cfg(feature = "outbound-ech")]
+                roots,
             })
         }
         #[cfg(feature = "openssl-tls")]
@@ -88,6 +131,11 @@ impl Handler {
             }
             let mut builder =
                 SslConnector::builder(SslMethod::tls()).expect("create ssl connector failed");
+            if let Some((cert, key)) = client_certificate {
+                builder.set_certificate_chain_file(cert)?;
+                builder.set_private_key_file(key, SslFiletype::PEM)?;
+                builder.check_private_key()?;
+            }
             if alpns.len() > 0 {
                 let wire = alpns
                     .into_iter()
@@ -100,11 +148,67 @@ impl Handler {
             Ok(Handler {
                 server_name,
                 ssl_connector,
+                pins: Vec::new(),
             })
         }
     }
 }
 
+impl Handler {
+    /// Accepts only servers presenting a certificate chain of one of the
+    /// SHA-256 `pins`, besides verifying it.
+    pub fn with_pinned_certificates(mut self, pins: Vec<Vec<u8>>) -> Self {
+        self.pins = pins;
+        self
+    }
+}
+
+#[cfg(feature = "outbound-ech")]
+impl Handler {
+    /// Hides the server name with Encrypted Client Hello.
+    pub fn with_ech(mut self, ech: Ech) -> Self {
+        self.ech = Some(ech);
+        self
+    }
+
+    async fn connect_ech(
+        &self,
+        ech: &Ech,
+        name: &str,
+        mut stream: AnyStream,
+    ) -> io::Result<AnyStream> {
+        let fetched;
+        let config = match ech {
+            Ech::Config(config) => config,
+            Ech::Dns(dns_client) => {
+                let list = dns_client
+                    .read()
+                    .await
+                    .lookup_ech_config(name)
+                    .await
+                    .map_err(|e| {
+                        io::Error::new(
+                            io::ErrorKind::Other,
+                            format!("lookup ech config of {} failed: {}", name, e),
+                        )
+                    })?;
+                fetched = EchConfig::select(&list)?;
+                &fetched
+            }
+        };
+        let (opener, sealer) = ech::handshake(
+            &mut stream,
+            name,
+            config,
+            &self.alpns,
+            &self.roots,
+            &self.pins,
+        )
+        .await?;
+        Ok(Box::new(Stream::new(stream, opener, sealer)))
+    }
+}
+
 fn tls_err<E>(_error: E) -> io::Error
 where
     E: Into<Box<dyn std::error::Error + Send + Sync>>,
@@ -133,12 +237,20 @@ impl TcpOutboundHandler for Handler {
         };
         trace!("wrapping tls with name {}", &name);
         if let Some(stream) = stream {
+            #[cfg(feature = "outbound-ech")]
+            if let Some(ech) = &self.ech {
+                return self.connect_ech(ech, &name, stream).await;
+            }
             #[cfg(feature = "rustls-tls")]
             {
                 let connector = TlsConnector::from(self.tls_config.clone());
                 let domain = ServerName::try_from(name.as_str())
                     .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid dnsname"))?;
                 let tls_stream = connector.connect(domain, stream).map_err(tls_err).await?;
+                if !self.pins.is_empty() {
+                    let certs = tls_stream.get_ref().1.peer_certificates().unwrap_or(&[]);
+                    pin::verify(&self.pins, certs.iter().map(|cert| &cert.0))?;
+                }
                 // FIXME check negotiated alpn
                 Ok(Box::new(tls_stream))
             }
@@ -154,6 +266,17 @@ impl TcpOutboundHandler for Handler {
                         tls_err(e)
                     })
                     .await?;
+                if !self.pins.is_empty() {
+                    let certs = match stream.ssl().peer_cert_chain() {
+                        Some(chain) => chain
+                            .iter()
+                            .map(|cert| cert.to_der())
+                            .collect::<Result<Vec<_>, _>>()
+                            .map_err(tls_err)?,
+                        None => Vec::new(),
+                    };
+                    pin::verify(&self.pins, certs)?;
+                }
                 Ok(Box::new(stream))
             }
         } else {
@@ -161,3 +284,154 @@ impl TcpOutboundHandler for Handler {
         }
     }
 }
+
+#[cfg(all(test, feature = "rustls-tls"))]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::{
+        rustls::{server::AllowAnyAuthenticatedClient, Certificate, PrivateKey, ServerConfig},
+        TlsAcceptor,
+    };
+
+    use super::*;
+
+    fn ca() -> rcgen::Certificate {
+        let mut params = rcgen::CertificateParams::new(vec![]);
+        params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        rcgen::Certificate::from_params(params).unwrap()
+    }
+
+    fn write_pem(name: &str, pem: String) -> String {
+        let path =
+            std::env::temp_dir().join(format!("leaf-test-tls-{}-{}.pem", std::process::id(), name));
+        std::fs::write(&path, pem).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_client_certificate() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let ca = ca();
+            let server_cert =
+                rcgen::generate_simple_self_signed(vec!["example.com".into()]).unwrap();
+            let client_cert = rcgen::generate_simple_self_signed(vec!["client".into()]).unwrap();
+            let ca_path = write_pem("client-auth-ca", ca.serialize_pem().unwrap());
+            let cert_path = write_pem(
+                "client-auth-cert",
+                client_cert.serialize_pem_with_signer(&ca).unwrap(),
+            );
+            let key_path = write_pem("client-auth-key", client_cert.serialize_private_key_pem());
+
+            let mut client_roots = RootCertStore::empty();
+            client_roots
+                .add(&Certificate(ca.serialize_der().unwrap()))
+                .unwrap();
+            let acceptor = TlsAcceptor::from(Arc::new(
+                ServerConfig::builder()
+                    .with_safe_defaults()
+                    .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(client_roots))
+                    .with_single_cert(
+                        vec![Certificate(
+                            server_cert.serialize_der_with_signer(&ca).unwrap(),
+                        )],
+                        PrivateKey(server_cert.serialize_private_key_der()),
+                    )
+                    .unwrap(),
+            ));
+
+            let sess = Session::default();
+            let handler = Handler::new(
+                "example.com".to_string(),
+                Vec::new(),
+                Some(ca_path.clone()),
+                None,
+                Some((cert_path, key_path)),
+            )
+            .unwrap();
+            let (client_io, server_io) = tokio::io::duplex(65536);
+            let server = tokio::spawn({
+                let acceptor = acceptor.clone();
+                async move {
+                    let mut stream = acceptor.accept(server_io).await?;
+                    let mut buf = [0u8; 5];
+                    stream.read_exact(&mut buf).await?;
+                    stream.write_all(&buf).await?;
+                    stream.flush().await
+                }
+            });
+            let mut stream = handler
+                .handle(&sess, Some(Box::new(client_io)))
+                .await
+                .unwrap();
+            stream.write_all(b"hello").await.unwrap();
+            let mut buf = [0u8; 5];
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"hello");
+            server.await.unwrap().unwrap();
+
+            // Without a client certificate, the server rejects the client.
+            let handler = Handler::new(
+                "example.com".to_string(),
+                Vec::new(),
+                Some(ca_path),
+                None,
+                None,
+            )
+            .unwrap();
+            let (client_io, server_io) = tokio::io::duplex(65536);
+            let server = tokio::spawn(async move { acceptor.accept(server_io).await.map(|_| ()) });
+            let client = handler.handle(&sess, Some(Box::new(client_io))).await;
+            assert!(server.await.unwrap().is_err());
+            if let Ok(mut stream) = client {
+                assert!(stream.read(&mut buf).await.is_err());
+            }
+        });
+    }
+
+    #[test]
+    fn test_pinned_certificates() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let ca = ca();
+            let cert = rcgen::generate_simple_self_signed(vec!["example.com".into()]).unwrap();
+            let cert_der = cert.serialize_der_with_signer(&ca).unwrap();
+            let ca_path = write_pem("pin-ca", ca.serialize_pem().unwrap());
+            let acceptor = TlsAcceptor::from(Arc::new(
+                ServerConfig::builder()
+                    .with_safe_defaults()
+                    .with_no_client_auth()
+                    .with_single_cert(
+                        vec![Certificate(cert_der.clone())],
+                        PrivateKey(cert.serialize_private_key_der()),
+                    )
+                    .unwrap(),
+            ));
+
+            let sess = Session::default();
+            for (pin, pinned) in [
+                (pin::cert_chain_hash([&cert_der]), true),
+                (vec![0; 32], false),
+            ] {
+                let handler = Handler::new(
+                    "example.com".to_string(),
+                    Vec::new(),
+                    Some(ca_path.clone()),
+                    None,
+                    None,
+                )
+                .unwrap()
+                .with_pinned_certificates(vec![vec![1; 32], pin]);
+                let (client_io, server_io) = tokio::io::duplex(65536);
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move { acceptor.accept(server_io).await });
+                let res = handler.handle(&sess, Some(Box::new(client_io))).await;
+                assert_eq!(res.is_ok(), pinned);
+            }
+        });
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::io;
+use std::mem;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::os::unix::io::AsRawFd;
+use std::sync::Arc;
+
+use anyhow::Result;
+use futures::future::{self, Either};
+use log::*;
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use tokio::io::Interest;
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::mpsc::channel as tokio_channel;
+use tokio::sync::mpsc::{Receiver as TokioReceiver, Sender as TokioSender};
+
+use crate::{
+    app::dispatcher::Dispatcher,
+    app::nat_manager::{NatManager, UdpPacket},
+    common::limiter::ConnectionLimiter,
+    common::net::ResettableTcpStream,
+    config::Inbound,
+    session::{DatagramSource, Network, Session, SocksAddr},
+    Runner,
+};
+
+// Max number of sockets kept for sending UDP replies from spoofed addresses.
+const REPLY_SOCKETS_MAX: usize = 256;
+
+fn set_bool_opt(socket: &Socket, level: libc::c_int, name: libc::c_int) -> io::Result<()> {
+    let val: libc::c_int = 1;
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            level,
+            name,
+            &val as *const _ as *const libc::c_void,
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Creates a socket with IP_TRANSPARENT bound to the address, it accepts
+/// traffic to any address if it's the listening socket, or sends traffic
+/// from a non-local address.
+fn transparent_socket(addr: &SocketAddr, ty: Type) -> io::Result<Socket> {
+    let (domain, level, name) = match addr {
+        SocketAddr::V4(_) => (Domain::IPV4, libc::SOL_IP, libc::IP_TRANSPARENT),
+        SocketAddr::V6(_) => (Domain::IPV6, libc::SOL_IPV6, libc::IPV6_TRANSPARENT),
+    };
+    let protocol = if ty == Type::STREAM {
+        Protocol::TCP
+    } else {
+        Protocol::UDP
+    };
+    let socket = Socket::new(domain, ty, Some(protocol))?;
+    set_bool_opt(&socket, level, name)?;
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&SockAddr::from(*addr))?;
+    Ok(socket)
+}
+
+fn sockaddr_to_std(addr: &libc::sockaddr_storage) -> Option<SocketAddr> {
+    match addr.ss_family as libc::c_int {
+        libc::AF_INET => {
+            let addr = unsafe { &*(addr as *const _ as *const libc::sockaddr_in) };
+            let ip = Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr));
+            Some(SocketAddr::new(IpAddr::V4(ip), u16::from_be(addr.sin_port)))
+        }
+        libc::AF_INET6 => {
+            let addr = unsafe { &*(addr as *const _ as *const libc::sockaddr_in6) };
+            let ip = Ipv6Addr::from(addr.sin6_addr.s6_addr);
+            Some(SocketAddr::new(
+                IpAddr::V6(ip),
+                u16::from_be(addr.sin6_port),
+            ))
+        }
+        _ => None,
+    }
+}
+
+// Receives a datagram, returns its size, source address and original
+// destination address.
+fn recv_with_orig_dst(
+    socket: &UdpSocket,
+    buf: &mut [u8],
+) -> io::Result<(usize, SocketAddr, SocketAddr)> {
+    let mut src: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+        iov_len: buf.len(),
+    };
+    let mut control = [0u8; 128];
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_name = &mut src as *mut _ as *mut libc::c_void;
+    msg.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = control.len() as _;
+
+    let n = unsafe { libc::recvmsg(socket.as_raw_fd(), &mut msg, 0) };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let src = sockaddr_to_std(&src)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "unknown source address"))?;
+
+    let mut dst = None;
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            let hdr = &*cmsg;
+            if (hdr.cmsg_level == libc::SOL_IP && hdr.cmsg_type == libc::IP_ORIGDSTADDR)
+                || (hdr.cmsg_level == libc::SOL_IPV6 && hdr.cmsg_type == libc::IPV6_ORIGDSTADDR)
+            {
+                let mut addr: libc::sockaddr_storage = mem::zeroed();
+                let len = std::cmp::min(
+                    hdr.cmsg_len as usize - (libc::CMSG_DATA(cmsg) as usize - cmsg as usize),
+                    mem::size_of::<libc::sockaddr_storage>(),
+                );
+                std::ptr::copy_nonoverlapping(
+                    libc::CMSG_DATA(cmsg),
+                    &mut addr as *mut _ as *mut u8,
+                    len,
+                );
+                dst = sockaddr_to_std(&addr);
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+    let dst =
+        dst.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "no original destination address"))?;
+    Ok((n as usize, src, dst))
+}
+
+async fn handle_inbound_stream(
+    stream: TcpStream,
+    inbound_tag: String,
+    dispatcher: Arc<Dispatcher>,
+) {
+    let (source, destination) = match (stream.peer_addr(), stream.local_addr()) {
+        (Ok(source), Ok(destination)) => (source, destination),
+        _ => {
+            debug!("get addresses of tproxy connection failed");
+            return;
+        }
+    };
+    // The local address of a socket accepted by a transparent listener is
+    // the original destination.
+    let mut sess = Session {
+        network: Network::Tcp,
+        source,
+        local_addr: destination,
+        destination: SocksAddr::Ip(destination),
+        inbound_tag,
+        ..Default::default()
+    };
+    let (stream, reset) = ResettableTcpStream::new(stream);
+    sess.inbound_reset = Some(reset);
+    dispatcher.dispatch_tcp(sess, stream).await;
+}
+
+async fn run_tcp(
+    listen_addr: SocketAddr,
+    inbound_tag: String,
+    dispatcher: Arc<Dispatcher>,
+    limiter: Arc<ConnectionLimiter>,
+) {
+    let listener = match transparent_socket(&listen_addr, Type::STREAM)
+        .and_then(|socket| {
+            socket.listen(1024)?;
+            Ok(socket)
+        })
+        .and_then(|socket| TcpListener::from_std(socket.into()))
+    {
+        Ok(l) => l,
+        Err(e) => {
+            error!("tproxy inbound listen tcp {} failed: {}", &listen_addr, e);
+            return;
+        }
+    };
+    info!("tproxy inbound listening tcp {}", &listen_addr);
+    loop {
+        match listener.accept().await {
+            Ok((stream, source)) => {
+                let permit = match limiter.try_acquire(source.ip()) {
+                    Some(p) => p,
+                    None => {
+                        debug!("connection from {} rejected by limits", &source);
+                        continue;
+                    }
+                };
+                let fut = handle_inbound_stream(stream, inbound_tag.clone(), dispatcher.clone());
+                tokio::spawn(async move {
+                    fut.await;
+                    drop(permit);
+                });
+            }
+            Err(e) => {
+                error!("accept connection failed: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+// Sends datagrams from the NAT manager back to clients, the source addresses
+// are spoofed as the remote addresses.
+async fn send_replies(mut l_rx: TokioReceiver<UdpPacket>) {
+    let mut sockets: HashMap<SocketAddr, UdpSocket> = HashMap::new();
+    while let Some(pkt) = l_rx.recv().await {
+        let src_addr = match pkt.src_addr {
+            SocksAddr::Ip(a) => a,
+            SocksAddr::Domain(..) => {
+                debug!("drop udp reply from a domain {}", &pkt.src_addr);
+                continue;
+            }
+        };
+        let dst_addr = pkt.dst_addr.must_ip();
+        if !sockets.contains_key(&src_addr) {
+            if sockets.len() >= REPLY_SOCKETS_MAX {
+                sockets.clear();
+            }
+            let socket = transparent_socket(&src_addr, Type::DGRAM)
+                .and_then(|socket| UdpSocket::from_std(socket.into()));
+            match socket {
+                Ok(socket) => {
+                    sockets.insert(src_addr, socket);
+                }
+                Err(e) => {
+                    debug!("create udp reply socket on {} failed: {}", &src_addr, e);
+                    continue;
+                }
+            }
+        }
+        if let Some(socket) = sockets.get(&src_addr) {
+            if let Err(e) = socket.send_to(&pkt.data, &dst_addr).await {
+                debug!(
+                    "send udp reply {} -> {} failed: {}",
+                    &src_addr, &dst_addr, e
+                );
+            }
+        }
+    }
+}
+
+async fn run_udp(listen_addr: SocketAddr, inbound_tag: String, nat_manager: Arc<NatManager>) {
+    let socket = match transparent_socket(&listen_addr, Type::DGRAM).and_then(|socket| {
+        let (level, name) = match listen_addr {
+            SocketAddr::V4(_) => (libc::SOL_IP, libc::IP_RECVORIGDSTADDR),
+            SocketAddr::V6(_) => (libc::SOL_IPV6, libc::IPV6_RECVORIGDSTADDR),
+        };
+        set_bool_opt(&socket, level, name)?;
+        UdpSocket::from_std(socket.into())
+    }) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("tproxy inbound listen udp {} failed: {}", &listen_addr, e);
+            return;
+        }
+    };
+    info!("tproxy inbound listening udp {}", &listen_addr);
+
+    let (l_tx, l_rx): (TokioSender<UdpPacket>, TokioReceiver<UdpPacket>) = tokio_channel(100);
+    tokio::spawn(send_replies(l_rx));
+
+    let mut buf = vec![0u8; *crate::option::DATAGRAM_BUFFER_SIZE * 1024];
+    loop {
+        if let Err(e) = socket.readable().await {
+            error!("receive udp failed: {}", e);
+            return;
+        }
+        let (n, src_addr, dst_addr) =
+            match socket.try_io(Interest::READABLE, || recv_with_orig_dst(&socket, &mut buf)) {
+                Ok(res) => res,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(e) => {
+                    debug!("receive udp failed: {}", e);
+                    continue;
+                }
+            };
+        let dgram_src = DatagramSource::new(src_addr, None);
+        let pkt = UdpPacket::new(
+            buf[..n].to_vec(),
+            SocksAddr::Ip(src_addr),
+            SocksAddr::Ip(dst_addr),
+        );
+        nat_manager
+            .send(None, &dgram_src, &inbound_tag, &l_tx, pkt)
+            .await;
+    }
+}
+
+pub fn new(
+    inbound: Inbound,
+    dispatcher: Arc<Dispatcher>,
+    nat_manager: Arc<NatManager>,
+) -> Result<Runner> {
+    let listen_addr = SocketAddr::new(inbound.address.parse::<IpAddr>()?, inbound.port as u16);
+    let limiter = Arc::new(ConnectionLimiter::from_inbound(&inbound));
+    Ok(Box::pin(async move {
+        let tcp = run_tcp(listen_addr, inbound.tag.clone(), dispatcher, limiter);
+        let udp = run_udp(listen_addr, inbound.tag.clone(), nat_manager);
+        match future::select(Box::pin(tcp), Box::pin(udp)).await {
+            Either::Left(_) => warn!("tproxy tcp listener stopped"),
+            Either::Right(_) => warn!("tproxy udp listener stopped"),
+        }
+    }))
+}
@@ -1,20 +1,36 @@
 use std::collections::HashMap;
 use std::io;
 use std::net::SocketAddr;
+use std::time::Duration;
 
 use anyhow::anyhow;
 use async_trait::async_trait;
 use byteorder::{BigEndian, ByteOrder};
 use bytes::{BufMut, BytesMut};
 use futures::TryFutureExt;
+use log::*;
 use sha2::{Digest, Sha224};
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
 
 use crate::{
     proxy::*,
     session::{DatagramSource, Network, Session, SocksAddr, SocksAddrWireType},
 };
 
+// How long a connection has to send the header before it's relayed to the
+// fallback, e.g. a client waiting for the server to speak first.
+const HEADER_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Whether the bytes can start a header, the hex of a key and CRLF.
+fn maybe_header(head: &[u8]) -> bool {
+    head.iter().enumerate().all(|(i, b)| match i {
+        0..=55 => matches!(b, b'0'..=b'9' | b'a'..=b'f'),
+        56 => *b == b'\r',
+        _ => *b == b'\n',
+    })
+}
+
 struct Datagram {
     stream: AnyStream,
     source: DatagramSource,
@@ -113,17 +129,34 @@ where
 
 pub struct Handler {
     keys: HashMap<Vec<u8>, ()>,
+    fallback: Option<String>,
 }
 
 impl Handler {
-    pub fn new(passwords: Vec<String>) -> Self {
+    /// Connections failing the authentication are relayed to `fallback`,
+    /// e.g. a web server, if it's set, or closed otherwise.
+    pub fn new(passwords: Vec<String>, fallback: Option<String>) -> Self {
         let mut keys = HashMap::new();
         for pass in passwords {
             let key = Sha224::digest(pass.as_bytes());
             let key = hex::encode(&key[..]);
             keys.insert(key.as_bytes().to_vec(), ());
         }
-        Handler { keys }
+        Handler { keys, fallback }
+    }
+
+    // Relays the connection to the fallback address, including the bytes
+    // already read.
+    async fn fallback(&self, mut stream: AnyStream, head: &[u8]) -> io::Result<()> {
+        let addr = match self.fallback.as_ref() {
+            Some(addr) => addr,
+            None => return Err(io::Error::new(io::ErrorKind::Other, "invalid key")),
+        };
+        debug!("trojan inbound falls back to {}", addr);
+        let mut remote = TcpStream::connect(addr).await?;
+        remote.write_all(head).await?;
+        tokio::io::copy_bidirectional(&mut stream, &mut remote).await?;
+        Ok(())
     }
 }
 
@@ -137,16 +170,34 @@ impl TcpInboundHandler for Handler {
         mut sess: Session,
         mut stream: Self::TStream,
     ) -> std::io::Result<InboundTransport<Self::TStream, Self::TDatagram>> {
-        let mut buf = BytesMut::new();
-        // read key
-        buf.resize(56, 0);
-        stream.read_exact(&mut buf).await?;
-        if !self.keys.contains_key(&buf[..]) {
-            return Err(io::Error::new(io::ErrorKind::Other, "invalid key"));
+        // read key and crlf, the connection may not be trojan, stops early
+        // on bytes which can't be a header and relays the bytes read to the
+        // fallback
+        let mut head = BytesMut::new();
+        head.resize(58, 0);
+        let mut n = 0;
+        while n < head.len() {
+            let nr = if self.fallback.is_some() {
+                match tokio::time::timeout(HEADER_TIMEOUT, stream.read(&mut head[n..])).await {
+                    Ok(res) => res?,
+                    Err(_) => break,
+                }
+            } else {
+                stream.read(&mut head[n..]).await?
+            };
+            if nr == 0 {
+                break;
+            }
+            n += nr;
+            if !maybe_header(&head[..n]) {
+                break;
+            }
         }
-        // read crlf
-        buf.resize(2, 0);
-        stream.read_exact(&mut buf).await?;
+        if n < head.len() || !self.keys.contains_key(&head[..56]) || &head[56..] != b"\r\n" {
+            self.fallback(stream, &head[..n]).await?;
+            return Ok(InboundTransport::Empty);
+        }
+        let mut buf = BytesMut::new();
         // read cmd
         buf.resize(1, 0);
         stream.read_exact(&mut buf).await?;
@@ -175,3 +226,53 @@ impl TcpInboundHandler for Handler {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn test_maybe_header() {
+        let key = hex::encode(&Sha224::digest(b"password")[..]);
+        assert!(maybe_header(key.as_bytes()));
+        assert!(maybe_header(format!("{}\r\n", key).as_bytes()));
+        assert!(!maybe_header(format!("{}\r\r", key).as_bytes()));
+        assert!(!maybe_header(b"GET / HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn test_early_fallback() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let fallback = listener.local_addr().unwrap().to_string();
+            tokio::spawn(async move {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 18];
+                stream.read_exact(&mut buf).await.unwrap();
+                assert_eq!(&buf, b"GET / HTTP/1.1\r\n\r\n");
+                stream.write_all(b"HTTP/1.1 200 OK\r\n\r\n").await.unwrap();
+            });
+            let handler = Handler::new(vec!["password".to_string()], Some(fallback));
+            let (mut client, server) = tokio::io::duplex(1024);
+            tokio::spawn(async move {
+                let _ = handler.handle(Session::default(), Box::new(server)).await;
+            });
+
+            // A short request waiting for the response is relayed before the
+            // header timeout.
+            client.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+            let mut buf = [0u8; 19];
+            tokio::time::timeout(Duration::from_secs(1), client.read_exact(&mut buf))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(&buf, b"HTTP/1.1 200 OK\r\n\r\n");
+        });
+    }
+}
@@ -52,16 +52,14 @@ impl UdpOutboundHandler for Handler {
             .write_buf(&mut buf, SocksAddrWireType::PortLast);
         buf.put_slice(b"\r\n");
 
-        let destination = match &sess.destination {
-            SocksAddr::Domain(domain, port) => {
-                Some(SocksAddr::Domain(domain.to_owned(), port.to_owned()))
-            }
-            _ => None,
-        };
+        let peers = DomainPeers::default();
+        if let SocksAddr::Domain(..) = &sess.destination {
+            peers.add_pending(&sess.destination);
+        }
 
         Ok(Box::new(Datagram {
             stream,
-            destination,
+            peers,
             head: Some(buf),
         }))
     }
@@ -69,7 +67,7 @@ impl UdpOutboundHandler for Handler {
 
 pub struct Datagram<S> {
     stream: S,
-    destination: Option<SocksAddr>,
+    peers: DomainPeers,
     head: Option<BytesMut>,
 }
 
@@ -85,13 +83,13 @@ where
     ) {
         let (r, w) = tokio::io::split(self.stream);
         (
-            Box::new(DatagramRecvHalf(r, self.destination)),
-            Box::new(DatagramSendHalf(w, self.head)),
+            Box::new(DatagramRecvHalf(r, self.peers.clone())),
+            Box::new(DatagramSendHalf(w, self.head, self.peers)),
         )
     }
 }
 
-pub struct DatagramRecvHalf<T>(ReadHalf<T>, Option<SocksAddr>);
+pub struct DatagramRecvHalf<T>(ReadHalf<T>, DomainPeers);
 
 #[async_trait]
 impl<T> OutboundDatagramRecvHalf for DatagramRecvHalf<T>
@@ -116,19 +114,11 @@ where
         }
         buf[..to_write].copy_from_slice(&buf2[..to_write]);
 
-        // If the initial destination is of domain type, we return that
-        // domain address instead of the real source address. That also
-        // means we assume all received packets are comming from a same
-        // address.
-        if self.1.is_some() {
-            Ok((to_write, self.1.as_ref().unwrap().clone()))
-        } else {
-            Ok((to_write, addr))
-        }
+        Ok((to_write, self.1.source(addr)))
     }
 }
 
-pub struct DatagramSendHalf<T>(WriteHalf<T>, Option<BytesMut>);
+pub struct DatagramSendHalf<T>(WriteHalf<T>, Option<BytesMut>, DomainPeers);
 
 #[async_trait]
 impl<T> OutboundDatagramSendHalf for DatagramSendHalf<T>
@@ -140,6 +130,10 @@ where
         // max(0, n_written - all_headers_size)
         let payload_size = buf.len();
 
+        if let SocksAddr::Domain(..) = target {
+            self.2.add_pending(target);
+        }
+
         let mut data = BytesMut::new();
         target.write_buf(&mut data, SocksAddrWireType::PortLast);
         data.put_u16(buf.len() as u16);
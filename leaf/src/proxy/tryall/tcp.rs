@@ -1,13 +1,17 @@
 use std::io;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use futures::future::select_ok;
+use log::*;
+use tokio::time::timeout;
 
 use crate::{app::SyncDnsClient, proxy::*, session::Session};
 
 pub struct Handler {
     pub actors: Vec<AnyOutboundHandler>,
     pub delay_base: u32,
+    pub attempt_timeout: Option<Duration>,
     pub dns_client: SyncDnsClient,
 }
 
@@ -16,7 +20,7 @@ impl TcpOutboundHandler for Handler {
     type Stream = AnyStream;
 
     fn connect_addr(&self) -> Option<OutboundConnect> {
-        None
+        Some(OutboundConnect::NoConnect)
     }
 
     async fn handle<'a>(
@@ -33,18 +37,111 @@ impl TcpOutboundHandler for Handler {
                     ))
                     .await;
                 }
-                let stream =
-                    crate::proxy::connect_tcp_outbound(sess, self.dns_client.clone(), a).await?;
-                TcpOutboundHandler::handle(a.as_ref(), sess, stream).await
+                let handle = async {
+                    let stream =
+                        crate::proxy::connect_tcp_outbound(sess, self.dns_client.clone(), a)
+                            .await?;
+                    TcpOutboundHandler::handle(a.as_ref(), sess, stream).await
+                };
+                let stream = match self.attempt_timeout {
+                    Some(d) => timeout(d, handle).await.map_err(|_| {
+                        io::Error::new(io::ErrorKind::TimedOut, "attempt timed out")
+                    })??,
+                    None => handle.await?,
+                };
+                io::Result::Ok((i, stream))
             };
             tasks.push(Box::pin(t));
         }
         match select_ok(tasks.into_iter()).await {
-            Ok(v) => Ok(v.0),
+            Ok(((i, v), _)) => {
+                debug!(
+                    "tryall handles tcp [{}] to [{}]",
+                    sess.destination,
+                    self.actors[i].tag()
+                );
+                Ok(v)
+            }
             Err(e) => Err(io::Error::new(
                 io::ErrorKind::Other,
                 format!("all outbound attempts failed, last error: {}", e),
             )),
         }
     }
+
+    // Chains can't try the actors with one stream, they use the first one.
+    async fn group_actor(&self, _sess: &Session) -> Option<AnyOutboundHandler> {
+        self.actors.first().cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::RwLock;
+
+    use super::*;
+    use crate::app::dns_client::DnsClient;
+    use crate::proxy::outbound::HandlerBuilder;
+
+    // Returns a stream after the delay.
+    struct Delayed(Duration);
+
+    #[async_trait]
+    impl TcpOutboundHandler for Delayed {
+        type Stream = AnyStream;
+
+        fn connect_addr(&self) -> Option<OutboundConnect> {
+            Some(OutboundConnect::NoConnect)
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _stream: Option<Self::Stream>,
+        ) -> io::Result<Self::Stream> {
+            tokio::time::sleep(self.0).await;
+            Ok(Box::new(tokio::io::duplex(1).0))
+        }
+    }
+
+    #[test]
+    fn test_attempt_timeout() {
+        let mut dns = crate::config::Dns::new();
+        dns.servers.push("1.1.1.1".to_string());
+        let dns_client = DnsClient::new(&protobuf::SingularPtrField::some(dns)).unwrap();
+        let dns_client = Arc::new(RwLock::new(dns_client));
+        let actor = |tag: &str, delay: Duration| {
+            HandlerBuilder::default()
+                .tag(tag.to_string())
+                .tcp_handler(Box::new(Delayed(delay)))
+                .build()
+        };
+        let ms = Duration::from_millis;
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let sess = Session::default();
+            let handler = Handler {
+                actors: vec![actor("slow", ms(2000)), actor("fast", ms(10))],
+                delay_base: 0,
+                attempt_timeout: Some(ms(200)),
+                dns_client: dns_client.clone(),
+            };
+            assert!(handler.handle(&sess, None).await.is_ok());
+
+            // Attempts exceeding the timeout fail without waiting for them.
+            let handler = Handler {
+                actors: vec![actor("slow", ms(2000))],
+                ..handler
+            };
+            let start = tokio::time::Instant::now();
+            let e = handler.handle(&sess, None).await.err().unwrap();
+            assert!(e.to_string().contains("attempt timed out"));
+            assert!(start.elapsed() < ms(1000));
+        });
+    }
 }
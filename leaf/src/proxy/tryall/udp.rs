@@ -1,13 +1,17 @@
 use std::io;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use futures::future::select_ok;
+use log::*;
+use tokio::time::timeout;
 
 use crate::{app::SyncDnsClient, proxy::*, session::Session};
 
 pub struct Handler {
     pub actors: Vec<AnyOutboundHandler>,
     pub delay_base: u32,
+    pub attempt_timeout: Option<Duration>,
     pub dns_client: SyncDnsClient,
 }
 
@@ -17,7 +21,7 @@ impl UdpOutboundHandler for Handler {
     type Datagram = AnyOutboundDatagram;
 
     fn connect_addr(&self) -> Option<OutboundConnect> {
-        None
+        Some(OutboundConnect::NoConnect)
     }
 
     fn transport_type(&self) -> DatagramTransportType {
@@ -38,14 +42,31 @@ impl UdpOutboundHandler for Handler {
                     ))
                     .await;
                 }
-                let transport =
-                    crate::proxy::connect_udp_outbound(sess, self.dns_client.clone(), a).await?;
-                UdpOutboundHandler::handle(a.as_ref(), sess, transport).await
+                let handle = async {
+                    let transport =
+                        crate::proxy::connect_udp_outbound(sess, self.dns_client.clone(), a)
+                            .await?;
+                    UdpOutboundHandler::handle(a.as_ref(), sess, transport).await
+                };
+                let datagram = match self.attempt_timeout {
+                    Some(d) => timeout(d, handle).await.map_err(|_| {
+                        io::Error::new(io::ErrorKind::TimedOut, "attempt timed out")
+                    })??,
+                    None => handle.await?,
+                };
+                io::Result::Ok((i, datagram))
             };
             tasks.push(Box::pin(t));
         }
         match select_ok(tasks.into_iter()).await {
-            Ok(v) => Ok(v.0),
+            Ok(((i, v), _)) => {
+                debug!(
+                    "tryall handles udp [{}] to [{}]",
+                    sess.destination,
+                    self.actors[i].tag()
+                );
+                Ok(v)
+            }
             Err(e) => Err(io::Error::new(
                 io::ErrorKind::Other,
                 format!("all outbound attempts failed, last error: {}", e),
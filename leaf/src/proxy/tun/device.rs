@@ -0,0 +1,75 @@
+use std::io;
+use std::sync::Arc;
+
+use futures::sink::Sink;
+use futures::stream::Stream;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tun::Device as _;
+
+use super::pool::BufferPool;
+
+// Size of the packet information header some platforms prepend to packets,
+// e.g. utun on macOS.
+const PI_LEN: usize = 4;
+
+// The packet information header of the packet as the framed codec of the tun
+// crate writes it.
+fn pi_header(pkt: &[u8]) -> [u8; PI_LEN] {
+    let ipv6 = pkt.first().map(|b| b >> 4) == Some(6);
+    #[cfg(target_os = "linux")]
+    let (inet, inet6) = (libc::ETH_P_IP, libc::ETH_P_IPV6);
+    #[cfg(not(target_os = "linux"))]
+    let (inet, inet6) = (libc::AF_INET, libc::AF_INET6);
+    let proto = (if ipv6 { inet6 } else { inet } as u16).to_be_bytes();
+    [0, 0, proto[0], proto[1]]
+}
+
+/// Returns a sink and a stream of packets of a single queue device. Packets
+/// are read into buffers of a pool as the multiqueue device does, instead of
+/// the per packet allocations of the framed codec.
+pub fn split(
+    mut device: tun::AsyncDevice,
+    mtu: usize,
+) -> (
+    impl Sink<Vec<u8>, Error = io::Error> + Send,
+    impl Stream<Item = io::Result<Vec<u8>>> + Send,
+) {
+    let pi = device.get_mut().has_packet_information();
+    let pi_len = if pi { PI_LEN } else { 0 };
+    let pool = Arc::new(BufferPool::new(mtu + pi_len));
+    let (reader, writer) = tokio::io::split(device);
+
+    let pool_cloned = pool.clone();
+    let stream = futures::stream::unfold(reader, move |mut reader| {
+        let pool = pool_cloned.clone();
+        async move {
+            let mut buf = pool.get();
+            let res = reader.read(&mut buf).await.map(|n| {
+                buf.truncate(n);
+                buf.drain(..pi_len.min(n));
+                buf
+            });
+            Some((res, reader))
+        }
+    });
+
+    let sink = futures::sink::unfold(writer, move |mut writer, pkt: Vec<u8>| {
+        let pool = pool.clone();
+        async move {
+            // Each write is a packet.
+            if pi {
+                let mut buf = pool.get();
+                buf.clear();
+                buf.extend_from_slice(&pi_header(&pkt));
+                buf.extend_from_slice(&pkt);
+                writer.write(&buf).await?;
+                pool.put(buf);
+            } else {
+                writer.write(&pkt).await?;
+            }
+            pool.put(pkt);
+            Ok(writer)
+        }
+    });
+    (sink, stream)
+}
@@ -0,0 +1,155 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use byteorder::{BigEndian, ByteOrder};
+
+const ICMPV4: u8 = 1;
+const ICMPV6: u8 = 58;
+const ICMPV4_ECHO_REQUEST: u8 = 8;
+const ICMPV4_ECHO_REPLY: u8 = 0;
+const ICMPV6_ECHO_REQUEST: u8 = 128;
+const ICMPV6_ECHO_REPLY: u8 = 129;
+
+/// Returns an echo reply packet if the given IP packet is an ICMP or ICMPv6
+/// echo request.
+pub fn echo_reply(pkt: &[u8]) -> Option<Vec<u8>> {
+    match pkt.first()? >> 4 {
+        4 => echo_reply_v4(pkt),
+        6 => echo_reply_v6(pkt),
+        _ => None,
+    }
+}
+
+/// Returns the destination address of the given IP packet.
+pub fn destination(pkt: &[u8]) -> Option<IpAddr> {
+    match pkt.first()? >> 4 {
+        4 if pkt.len() >= 20 => {
+            let ip: [u8; 4] = pkt[16..20].try_into().ok()?;
+            Some(IpAddr::V4(Ipv4Addr::from(ip)))
+        }
+        6 if pkt.len() >= 40 => {
+            let ip: [u8; 16] = pkt[24..40].try_into().ok()?;
+            Some(IpAddr::V6(Ipv6Addr::from(ip)))
+        }
+        _ => None,
+    }
+}
+
+fn echo_reply_v4(pkt: &[u8]) -> Option<Vec<u8>> {
+    if pkt.len() < 20 {
+        return None;
+    }
+    let header_len = ((pkt[0] & 0x0f) as usize) * 4;
+    let total_len = BigEndian::read_u16(&pkt[2..4]) as usize;
+    // Fragmented packets are not handled.
+    let frag = BigEndian::read_u16(&pkt[6..8]) & 0x3fff;
+    if pkt[9] != ICMPV4
+        || frag != 0
+        || header_len < 20
+        || total_len > pkt.len()
+        || total_len < header_len + 8
+        || pkt[header_len] != ICMPV4_ECHO_REQUEST
+    {
+        return None;
+    }
+    let mut reply = pkt[..total_len].to_vec();
+    reply[12..16].copy_from_slice(&pkt[16..20]);
+    reply[16..20].copy_from_slice(&pkt[12..16]);
+    reply[8] = 64; // TTL
+    reply[10..12].copy_from_slice(&[0, 0]);
+    let sum = checksum(0, &reply[..header_len]);
+    BigEndian::write_u16(&mut reply[10..12], sum);
+
+    let icmp = &mut reply[header_len..];
+    icmp[0] = ICMPV4_ECHO_REPLY;
+    icmp[2..4].copy_from_slice(&[0, 0]);
+    let sum = checksum(0, icmp);
+    BigEndian::write_u16(&mut icmp[2..4], sum);
+    Some(reply)
+}
+
+fn echo_reply_v6(pkt: &[u8]) -> Option<Vec<u8>> {
+    if pkt.len() < 40 {
+        return None;
+    }
+    let payload_len = BigEndian::read_u16(&pkt[4..6]) as usize;
+    // Packets with extension headers are not handled.
+    if pkt[6] != ICMPV6
+        || payload_len < 8
+        || 40 + payload_len > pkt.len()
+        || pkt[40] != ICMPV6_ECHO_REQUEST
+    {
+        return None;
+    }
+    let mut reply = pkt[..40 + payload_len].to_vec();
+    reply[8..24].copy_from_slice(&pkt[24..40]);
+    reply[24..40].copy_from_slice(&pkt[8..24]);
+    reply[7] = 64; // hop limit
+
+    let (header, icmp) = reply.split_at_mut(40);
+    icmp[0] = ICMPV6_ECHO_REPLY;
+    icmp[2..4].copy_from_slice(&[0, 0]);
+    // The pseudo header consists of source address, destination address,
+    // upper-layer packet length and next header.
+    let mut sum = partial_sum(0, &header[8..40]);
+    sum += payload_len as u32;
+    sum += ICMPV6 as u32;
+    let sum = checksum(sum, icmp);
+    BigEndian::write_u16(&mut icmp[2..4], sum);
+    Some(reply)
+}
+
+pub(super) fn partial_sum(mut sum: u32, data: &[u8]) -> u32 {
+    let mut chunks = data.chunks_exact(2);
+    for chunk in &mut chunks {
+        sum += BigEndian::read_u16(chunk) as u32;
+    }
+    if let [b] = chunks.remainder() {
+        sum += (*b as u32) << 8;
+    }
+    sum
+}
+
+pub(super) fn checksum(sum: u32, data: &[u8]) -> u16 {
+    let mut sum = partial_sum(sum, data);
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_echo_reply_v4() {
+        // ping 10.0.0.1 from 10.0.0.2, id 1, seq 1, payload "abcd"
+        let mut req = vec![
+            0x45, 0x00, 0x00, 0x20, 0x00, 0x01, 0x00, 0x00, 0x40, 0x01, 0x00, 0x00, 10, 0, 0, 2,
+            10, 0, 0, 1, 0x08, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, b'a', b'b', b'c', b'd',
+        ];
+        let sum = checksum(0, &req[..20]);
+        BigEndian::write_u16(&mut req[10..12], sum);
+        let sum = checksum(0, &req[20..]);
+        BigEndian::write_u16(&mut req[22..24], sum);
+
+        assert_eq!(destination(&req), Some(IpAddr::from([10, 0, 0, 1])));
+        let reply = echo_reply(&req).unwrap();
+        assert_eq!(&reply[12..16], &[10, 0, 0, 1]);
+        assert_eq!(&reply[16..20], &[10, 0, 0, 2]);
+        assert_eq!(reply[20], ICMPV4_ECHO_REPLY);
+        assert_eq!(&reply[24..], &req[24..]);
+        // Checksums of a valid packet sum up to zero.
+        assert_eq!(checksum(0, &reply[..20]), 0);
+        assert_eq!(checksum(0, &reply[20..]), 0);
+    }
+
+    #[test]
+    fn test_echo_reply_non_icmp() {
+        let mut pkt = vec![0u8; 28];
+        pkt[0] = 0x45;
+        pkt[3] = 28;
+        pkt[9] = 17; // UDP
+        assert!(echo_reply(&pkt).is_none());
+    }
+}
@@ -1,26 +1,78 @@
-use std::net::SocketAddr;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::os::unix::io::{BorrowedFd, IntoRawFd, OwnedFd};
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Result};
-use futures::{sink::SinkExt, stream::StreamExt};
+use futures::{
+    future,
+    sink::{Sink, SinkExt},
+    stream::{Stream, StreamExt},
+};
 use log::*;
 use protobuf::Message;
 use tokio::sync::mpsc::channel as tokio_channel;
 use tokio::sync::mpsc::{Receiver as TokioReceiver, Sender as TokioSender};
-use tun::{self, TunPacket};
 
 use crate::{
     app::dispatcher::Dispatcher,
-    app::fake_dns::{FakeDns, FakeDnsMode},
+    app::fake_dns::{FakeDns, FakeDnsLookup, FakeDnsMode, FakeIpPool},
     app::nat_manager::NatManager,
     app::nat_manager::UdpPacket,
+    common::limiter::ConnectionLimiter,
     config::{Inbound, TunInboundSettings},
     option,
     session::{DatagramSource, Network, Session, SocksAddr},
     Runner,
 };
 
-use super::netstack;
+#[cfg(target_os = "linux")]
+use super::multiqueue;
+use super::{device, icmp, mss, netstack};
+
+/// Packet handling options of the TUN inbound.
+struct Options {
+    icmp_reply: bool,
+    // Addresses of the TUN interface, echo requests to them are answered
+    // along with the ones to fake IPs.
+    tun_addrs: Vec<IpAddr>,
+    dns_hijack: DnsHijack,
+    // The outbound for traffic to LAN destinations, the router is bypassed.
+    bypass_lan: Option<String>,
+    limiter: Arc<ConnectionLimiter>,
+}
+
+impl Options {
+    // Only destinations known to be reachable are answered, the others
+    // can't be proxied and are dropped like without `icmp_reply`.
+    fn replies_icmp(&self, pkt: &[u8], fakedns: &FakeDns) -> bool {
+        self.icmp_reply
+            && icmp::destination(pkt)
+                .map(|ip| self.tun_addrs.contains(&ip) || fakedns.is_fake_ip(&ip))
+                .unwrap_or(false)
+    }
+
+    fn bypass_outbound(&self, dst: &SocksAddr) -> Option<&String> {
+        match dst {
+            SocksAddr::Ip(addr) if is_lan_ip(&addr.ip()) => self.bypass_lan.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Whether it's a private, link-local or loopback address.
+fn is_lan_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_private() || ip.is_link_local() || ip.is_loopback(),
+        IpAddr::V6(ip) => {
+            let seg = ip.segments()[0];
+            // Unique local fc00::/7 and link-local fe80::/10.
+            ip.is_loopback() || (seg & 0xfe00) == 0xfc00 || (seg & 0xffc0) == 0xfe80
+        }
+    }
+}
 
 async fn handle_inbound_stream(
     stream: netstack::TcpStream,
@@ -29,6 +81,7 @@ async fn handle_inbound_stream(
     inbound_tag: String,
     dispatcher: Arc<Dispatcher>,
     fakedns: Arc<FakeDns>,
+    opts: Arc<Options>,
 ) {
     let mut sess = Session {
         network: Network::Tcp,
@@ -38,17 +91,20 @@ async fn handle_inbound_stream(
         inbound_tag: inbound_tag,
         ..Default::default()
     };
-    // Whether to override the destination according to Fake DNS.
-    if fakedns.is_fake_ip(&remote_addr.ip()).await {
-        if let Some(domain) = fakedns.query_domain(&remote_addr.ip()).await {
+    // Whether to override the destination according to Fake DNS. The fake
+    // IP is held until the connection ends.
+    let mut _fake_ip_guard = None;
+    if fakedns.is_fake_ip(&remote_addr.ip()) {
+        if let Some((domain, guard)) = fakedns.acquire(&remote_addr.ip()).await {
             sess.destination = SocksAddr::Domain(domain, remote_addr.port());
+            _fake_ip_guard = Some(guard);
         } else {
             // Although requests targeting fake IPs are assumed
             // never happen in real network traffic, which are
             // likely caused by poisoned DNS cache records, we
             // still have a chance to sniff the request domain
-            // for TLS traffic in dispatcher.
-            if remote_addr.port() != 443 {
+            // for TLS and HTTP traffic in dispatcher.
+            if remote_addr.port() != 443 && remote_addr.port() != 80 {
                 log::debug!(
                     "No paired domain found for this fake IP: {}, connection is rejected.",
                     &remote_addr.ip()
@@ -57,14 +113,63 @@ async fn handle_inbound_stream(
             }
         }
     }
+    if let Some(tag) = opts.bypass_outbound(&sess.destination) {
+        sess.outbound_tag = tag.clone();
+    }
     dispatcher.dispatch_tcp(sess, stream).await;
 }
 
+// Delays between attempts to re-create the TUN device.
+const RECREATE_DELAY_MIN: Duration = Duration::from_secs(1);
+const RECREATE_DELAY_MAX: Duration = Duration::from_secs(60);
+
+/// Destinations of DNS queries to be answered by fake DNS.
+struct DnsHijack {
+    // An entry without an IP matches any destination address.
+    entries: Vec<(Option<IpAddr>, u16)>,
+}
+
+impl DnsHijack {
+    /// Entries are in the form of `ip:port`, `:port` or `ip`, a missing port
+    /// defaults to 53. All queries to port 53 are hijacked if no entries are
+    /// given.
+    fn new(entries: &[String]) -> Result<Self> {
+        if entries.is_empty() {
+            return Ok(DnsHijack {
+                entries: vec![(None, 53)],
+            });
+        }
+        let mut parsed = Vec::new();
+        for entry in entries {
+            let entry = entry.trim();
+            let item = if let Some(port) = entry.strip_prefix(':') {
+                port.parse::<u16>().ok().map(|port| (None, port))
+            } else if let Ok(addr) = entry.parse::<SocketAddr>() {
+                Some((Some(addr.ip()), addr.port()))
+            } else {
+                entry.parse::<IpAddr>().ok().map(|ip| (Some(ip), 53))
+            };
+            match item {
+                Some(item) => parsed.push(item),
+                None => return Err(anyhow!("invalid dns hijack entry: {}", entry)),
+            }
+        }
+        Ok(DnsHijack { entries: parsed })
+    }
+
+    fn matches(&self, addr: &SocketAddr) -> bool {
+        self.entries
+            .iter()
+            .any(|(ip, port)| *port == addr.port() && (ip.is_none() || *ip == Some(addr.ip())))
+    }
+}
+
 async fn handle_inbound_datagram(
     socket: Box<netstack::UdpSocket>,
     inbound_tag: String,
     nat_manager: Arc<NatManager>,
     fakedns: Arc<FakeDns>,
+    opts: Arc<Options>,
 ) {
     // The socket to receive/send packets from/to the netstack.
     let (ls, mut lr) = socket.split();
@@ -74,14 +179,16 @@ async fn handle_inbound_datagram(
     let (l_tx, mut l_rx): (TokioSender<UdpPacket>, TokioReceiver<UdpPacket>) = tokio_channel(32);
 
     // Receive datagrams from NAT manager and send back to netstack.
-    let fakedns_cloned = fakedns.clone();
+    let mut lookup = FakeDnsLookup::new(fakedns.clone());
     let ls_cloned = ls.clone();
     tokio::spawn(async move {
         while let Some(pkt) = l_rx.recv().await {
             let src_addr = match pkt.src_addr {
                 SocksAddr::Ip(a) => a,
                 SocksAddr::Domain(domain, port) => {
-                    if let Some(ip) = fakedns_cloned.query_fake_ip(&domain).await {
+                    // Replies with a fake IP of the same family as the client.
+                    let ipv6 = matches!(pkt.dst_addr, SocksAddr::Ip(SocketAddr::V6(_)));
+                    if let Some(ip) = lookup.query_fake_ip(&domain, ipv6).await {
                         SocketAddr::new(ip, port)
                     } else {
                         warn!(
@@ -99,14 +206,16 @@ async fn handle_inbound_datagram(
     });
 
     // Accept datagrams from netstack and send to NAT manager.
+    let mut lookup = FakeDnsLookup::new(fakedns.clone());
     loop {
         match lr.recv_from().await {
             Err(e) => {
                 log::warn!("Failed to accept a datagram from netstack: {}", e);
+                return;
             }
             Ok((data, src_addr, dst_addr)) => {
                 // Fake DNS logic.
-                if dst_addr.port() == 53 {
+                if opts.dns_hijack.matches(&dst_addr) {
                     match fakedns.generate_fake_response(&data).await {
                         Ok(resp) => {
                             if let Err(e) = ls.send_to(resp.as_ref(), &dst_addr, &src_addr) {
@@ -132,8 +241,8 @@ async fn handle_inbound_datagram(
                 // require a proxy server with the ability to handle datagrams
                 // with domain name destination, leaf itself of course supports
                 // this feature very well.
-                let dst_addr = if fakedns.is_fake_ip(&dst_addr.ip()).await {
-                    if let Some(domain) = fakedns.query_domain(&dst_addr.ip()).await {
+                let dst_addr = if fakedns.is_fake_ip(&dst_addr.ip()) {
+                    if let Some(domain) = lookup.query_domain(&dst_addr.ip()).await {
                         SocksAddr::Domain(domain, dst_addr.port())
                     } else {
                         log::debug!(
@@ -147,9 +256,17 @@ async fn handle_inbound_datagram(
                 };
 
                 let dgram_src = DatagramSource::new(src_addr, None);
+                let sess = opts.bypass_outbound(&dst_addr).map(|tag| Session {
+                    network: Network::Udp,
+                    source: src_addr,
+                    destination: dst_addr.clone(),
+                    inbound_tag: inbound_tag.clone(),
+                    outbound_tag: tag.clone(),
+                    ..Default::default()
+                });
                 let pkt = UdpPacket::new(data, SocksAddr::Ip(src_addr), dst_addr);
                 nat_manager
-                    .send(None, &dgram_src, &inbound_tag, &l_tx, pkt)
+                    .send(sess.as_ref(), &dgram_src, &inbound_tag, &l_tx, pkt)
                     .await;
             }
         }
@@ -184,9 +301,9 @@ pub fn new(
 
         cfg.up();
     } else {
-        cfg.name(settings.name)
-            .address(settings.address)
-            .destination(settings.gateway)
+        cfg.name(&settings.name)
+            .address(&settings.address)
+            .destination(&settings.gateway)
             .mtu(settings.mtu);
 
         #[cfg(not(any(
@@ -196,15 +313,15 @@ pub fn new(
             target_arch = "mipsel64",
         )))]
         {
-            cfg.netmask(settings.netmask);
+            cfg.netmask(&settings.netmask);
         }
 
         cfg.up();
     }
 
     // FIXME it's a bad design to have 2 lists in config while we need only one
-    let fake_dns_exclude = settings.fake_dns_exclude;
-    let fake_dns_include = settings.fake_dns_include;
+    let fake_dns_exclude = settings.fake_dns_exclude.clone();
+    let fake_dns_include = settings.fake_dns_include.clone();
     if !fake_dns_exclude.is_empty() && !fake_dns_include.is_empty() {
         return Err(anyhow!(
             "fake DNS run in either include mode or exclude mode"
@@ -216,67 +333,468 @@ pub fn new(
         (FakeDnsMode::Exclude, fake_dns_exclude)
     };
 
-    let tun = tun::create_as_async(&cfg).map_err(|e| anyhow!("create tun failed: {}", e))?;
+    let mut fake_ip_pool = FakeIpPool::new(
+        &settings.fake_ip_cidr,
+        &settings.fake_ipv6_cidr,
+        settings.fake_ip_pool_size,
+        &settings.fake_ip_eviction,
+    )?;
+    if settings.fake_ipv6 {
+        fake_ip_pool.answer_ipv6 = true;
+    }
+    if settings.fake_ip_ttl > 0 {
+        fake_ip_pool.ttl = settings.fake_ip_ttl as u32;
+    }
+
+    let tun_name = if settings.fd >= 0 {
+        String::new()
+    } else if settings.auto {
+        option::DEFAULT_TUN_NAME.clone()
+    } else {
+        settings.name.clone()
+    };
+    // The device owns the given fd and closes it once dropped, a duplicate is
+    // kept to re-create the device from.
+    let fd_dup = if settings.fd >= 0 {
+        let fd = unsafe { BorrowedFd::borrow_raw(settings.fd) };
+        Some(
+            fd.try_clone_to_owned()
+                .map_err(|e| anyhow!("duplicate tun fd failed: {}", e))?,
+        )
+    } else {
+        None
+    };
+    let tun = create_device(&cfg, &settings)?;
+
+    let tun_addrs = if settings.auto {
+        vec![
+            option::DEFAULT_TUN_IPV4_ADDR.clone(),
+            option::DEFAULT_TUN_IPV4_GW.clone(),
+            option::DEFAULT_TUN_IPV6_ADDR.clone(),
+            option::DEFAULT_TUN_IPV6_GW.clone(),
+        ]
+    } else {
+        vec![
+            settings.address.clone(),
+            settings.gateway.clone(),
+            settings.ipv6_address.clone(),
+        ]
+    };
+    let opts = Arc::new(Options {
+        icmp_reply: settings.icmp_reply,
+        tun_addrs: tun_addrs.iter().filter_map(|a| a.parse().ok()).collect(),
+        dns_hijack: DnsHijack::new(&settings.dns_hijack)?,
+        bypass_lan: if settings.bypass_lan_outbound.is_empty() {
+            None
+        } else {
+            Some(settings.bypass_lan_outbound.clone())
+        },
+        limiter: Arc::new(ConnectionLimiter::from_inbound(&inbound)),
+    });
 
     if settings.auto {
         assert!(settings.fd == -1, "tun-auto is not compatible with tun-fd");
     }
 
     Ok(Box::pin(async move {
-        let fakedns = Arc::new(FakeDns::new(fake_dns_mode));
+        let fakedns = Arc::new(FakeDns::new(fake_dns_mode, fake_ip_pool));
+        fakedns.spawn_flush();
         for filter in fake_dns_filters.into_iter() {
             fakedns.add_filter(filter).await;
         }
 
-        let inbound_tag = inbound.tag.clone();
-        let framed = tun.into_framed();
-        let (mut tun_sink, mut tun_stream) = framed.split();
-        let (stack, mut tcp_listener, udp_socket) = netstack::NetStack::new();
-        let (mut stack_sink, mut stack_stream) = stack.split();
+        run_hook("post-up", &settings.post_up, &tun_name).await;
+
+        info!("start tun inbound");
+
+        // The runner is dropped when leaf is shutting down.
+        let _pre_down = PreDownGuard {
+            script: settings.pre_down.clone(),
+            tun_name: tun_name.clone(),
+        };
+
+        // The device may disappear at any time, e.g. the VPN is toggled or
+        // the system wakes up from sleep, re-creates it with backoff.
+        let mut tun = Some(tun);
+        let mut delay = RECREATE_DELAY_MIN;
+        loop {
+            let device = match tun.take() {
+                Some(device) => device,
+                None => match recreate_device(&cfg, &settings, fd_dup.as_ref()) {
+                    Ok(device) => {
+                        info!("tun device re-created");
+                        run_hook("post-up", &settings.post_up, &tun_name).await;
+                        device
+                    }
+                    Err(e) => {
+                        warn!("re-create tun failed: {}, retry in {:?}", e, delay);
+                        tokio::time::sleep(delay).await;
+                        delay = std::cmp::min(delay * 2, RECREATE_DELAY_MAX);
+                        continue;
+                    }
+                },
+            };
+            let start = Instant::now();
+            run_pipeline(
+                device,
+                inbound.tag.clone(),
+                dispatcher.clone(),
+                nat_manager.clone(),
+                fakedns.clone(),
+                opts.clone(),
+            )
+            .await;
+            // The pipeline has been working for a while, it's not failing
+            // repeatedly.
+            if start.elapsed() > RECREATE_DELAY_MAX {
+                delay = RECREATE_DELAY_MIN;
+            }
+            warn!("tun pipeline stopped, re-create in {:?}", delay);
+            tokio::time::sleep(delay).await;
+            delay = std::cmp::min(delay * 2, RECREATE_DELAY_MAX);
+        }
+    }))
+}
+
+// Hooks still running after the timeout are killed.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
 
-        let mut futs: Vec<Runner> = Vec::new();
+fn hook_command(script: &str, tun_name: &str) -> std::process::Command {
+    let mut cmd = std::process::Command::new("sh");
+    cmd.arg("-c").arg(script).env("TUN_NAME", tun_name);
+    cmd
+}
 
-        // Reads packet from stack and sends to TUN.
-        futs.push(Box::pin(async move {
-            while let Some(pkt) = stack_stream.next().await {
-                if let Ok(pkt) = pkt {
-                    tun_sink.send(TunPacket::new(pkt)).await.unwrap();
+/// Runs a hook script with `sh`, the name of the TUN device is passed in the
+/// `TUN_NAME` environment variable.
+async fn run_hook(hook: &str, script: &str, tun_name: &str) {
+    if script.is_empty() {
+        return;
+    }
+    let status = tokio::process::Command::from(hook_command(script, tun_name))
+        .kill_on_drop(true)
+        .status();
+    match tokio::time::timeout(HOOK_TIMEOUT, status).await {
+        Ok(Ok(status)) if status.success() => debug!("tun {} hook done", hook),
+        Ok(Ok(status)) => warn!("tun {} hook exited with {}", hook, status),
+        Ok(Err(e)) => warn!("run tun {} hook failed: {}", hook, e),
+        Err(_) => warn!("tun {} hook timed out", hook),
+    }
+}
+
+struct PreDownGuard {
+    script: String,
+    tun_name: String,
+}
+
+impl Drop for PreDownGuard {
+    // Leaf is exiting and the runtime may be shutting down, the hook is
+    // waited for in place so that it's done before leaf exits.
+    fn drop(&mut self) {
+        if self.script.is_empty() {
+            return;
+        }
+        let mut child = match hook_command(&self.script, &self.tun_name).spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                warn!("run tun pre-down hook failed: {}", e);
+                return;
+            }
+        };
+        let start = Instant::now();
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) if status.success() => debug!("tun pre-down hook done"),
+                Ok(Some(status)) => warn!("tun pre-down hook exited with {}", status),
+                Ok(None) if start.elapsed() < HOOK_TIMEOUT => {
+                    std::thread::sleep(Duration::from_millis(50));
+                    continue;
+                }
+                Ok(None) => {
+                    warn!("tun pre-down hook timed out");
+                    let _ = child.kill();
+                    let _ = child.wait();
                 }
+                Err(e) => warn!("wait for tun pre-down hook failed: {}", e),
             }
-        }));
+            return;
+        }
+    }
+}
+
+enum Device {
+    Tun(tun::AsyncDevice, usize),
+    #[cfg(target_os = "linux")]
+    MultiQueue(Vec<multiqueue::Queue>, usize),
+}
+
+/// Re-creates the device, a device of a given fd is re-created from a new
+/// duplicate of it.
+fn recreate_device(
+    cfg: &tun::Configuration,
+    settings: &TunInboundSettings,
+    fd_dup: Option<&OwnedFd>,
+) -> Result<Device> {
+    match fd_dup {
+        Some(fd) => {
+            let fd = fd
+                .try_clone()
+                .map_err(|e| anyhow!("duplicate tun fd failed: {}", e))?;
+            let mut cfg = tun::Configuration::default();
+            cfg.raw_fd(fd.into_raw_fd());
+            create_device(&cfg, settings)
+        }
+        None => create_device(cfg, settings),
+    }
+}
+
+fn create_device(cfg: &tun::Configuration, settings: &TunInboundSettings) -> Result<Device> {
+    #[cfg(target_os = "linux")]
+    if settings.fd < 0 && settings.queues > 1 {
+        return create_multiqueue_device(settings);
+    }
+
+    let tun = tun::create_as_async(cfg).map_err(|e| anyhow!("create tun failed: {}", e))?;
+    setup_ipv6_address(settings)?;
+    let mtu = if settings.auto || settings.mtu <= 0 {
+        1500
+    } else {
+        settings.mtu as usize
+    };
+    Ok(Device::Tun(tun, mtu))
+}
+
+// The tun crate opens a single queue only, queues of a multiqueue device are
+// opened directly and the device is configured by commands.
+#[cfg(target_os = "linux")]
+fn create_multiqueue_device(settings: &TunInboundSettings) -> Result<Device> {
+    let (name, address, gateway, netmask, mtu) = if settings.auto {
+        (
+            option::DEFAULT_TUN_NAME.clone(),
+            option::DEFAULT_TUN_IPV4_ADDR.clone(),
+            option::DEFAULT_TUN_IPV4_GW.clone(),
+            option::DEFAULT_TUN_IPV4_MASK.clone(),
+            1500,
+        )
+    } else {
+        (
+            settings.name.clone(),
+            settings.address.clone(),
+            settings.gateway.clone(),
+            settings.netmask.clone(),
+            settings.mtu,
+        )
+    };
+    let queues = multiqueue::open(&name, settings.queues as usize)
+        .map_err(|e| anyhow!("create tun queues failed: {}", e))?;
+    crate::common::cmd::add_interface_ipv4_address(
+        &name,
+        address.parse()?,
+        gateway.parse()?,
+        netmask.parse()?,
+    )?;
+    crate::common::cmd::set_interface_up(&name, mtu)?;
+    setup_ipv6_address(settings)?;
+    Ok(Device::MultiQueue(queues, mtu as usize))
+}
+
+// The tun crate configures IPv4 only, IPv6 address is assigned separately.
+// For auto mode, this is done by the system setup in the startup process.
+fn setup_ipv6_address(settings: &TunInboundSettings) -> Result<()> {
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    if settings.fd < 0 && !settings.auto && !settings.ipv6_address.is_empty() {
+        let addr = settings
+            .ipv6_address
+            .parse::<std::net::Ipv6Addr>()
+            .map_err(|e| anyhow!("invalid tun ipv6 address: {}", e))?;
+        let prefixlen = if settings.ipv6_prefixlen > 0 {
+            settings.ipv6_prefixlen
+        } else {
+            *option::DEFAULT_TUN_IPV6_PREFIXLEN
+        };
+        crate::common::cmd::add_interface_ipv6_address(&settings.name, addr, prefixlen)?;
+    }
+    Ok(())
+}
+
+// Max number of packets moved from TUN to the stack at once.
+const BATCH_SIZE: usize = 64;
+
+type PacketSink = Pin<Box<dyn Sink<Vec<u8>, Error = io::Error> + Send>>;
+type PacketStream = Pin<Box<dyn Stream<Item = io::Result<Vec<u8>>> + Send>>;
+
+/// Moves packets between the device and a new netstack, returns when either
+/// of them fails.
+async fn run_pipeline(
+    device: Device,
+    inbound_tag: String,
+    dispatcher: Arc<Dispatcher>,
+    nat_manager: Arc<NatManager>,
+    fakedns: Arc<FakeDns>,
+    opts: Arc<Options>,
+) {
+    let (mut tun_sink, tun_stream): (PacketSink, PacketStream) = match device {
+        Device::Tun(tun, mtu) => {
+            let (sink, stream) = device::split(tun, mtu);
+            (Box::pin(sink), Box::pin(stream))
+        }
+        #[cfg(target_os = "linux")]
+        Device::MultiQueue(queues, mtu) => {
+            let (sink, stream) = multiqueue::split(queues, mtu);
+            (Box::pin(sink), Box::pin(stream))
+        }
+    };
+    let (stack, mut tcp_listener, udp_socket) = netstack::NetStack::new();
+    let (mut stack_sink, stack_stream) = stack.split();
+
+    // ICMP echo replies generated locally, they're sent to TUN along with
+    // packets from the stack.
+    let (mut icmp_tx, icmp_rx) = futures::channel::mpsc::channel(32);
+
+    let mut futs: Vec<Runner> = Vec::new();
 
-        // Reads packet from TUN and sends to stack.
-        futs.push(Box::pin(async move {
-            while let Some(pkt) = tun_stream.next().await {
-                if let Ok(pkt) = pkt {
-                    stack_sink.send(pkt.get_bytes().to_vec()).await.unwrap();
+    // MSS of TCP connections in both directions are clamped in SYN packets.
+    let tcp_mss = *option::TUN_TCP_MSS;
+
+    // Reads packet from stack and sends to TUN.
+    futs.push(Box::pin(async move {
+        let mut stack_stream = futures::stream::select(
+            stack_stream.filter_map(|pkt| future::ready(pkt.ok())),
+            icmp_rx,
+        );
+        // Every packet is flushed, a framed TUN device would otherwise merge
+        // buffered packets into a single write.
+        while let Some(mut pkt) = stack_stream.next().await {
+            if tcp_mss > 0 {
+                mss::clamp(&mut pkt, tcp_mss);
+            }
+            if let Err(e) = tun_sink.send(pkt).await {
+                warn!("write to tun failed: {}", e);
+                return;
+            }
+        }
+    }));
+
+    // Reads packet from TUN and sends to stack.
+    let opts_cloned = opts.clone();
+    let fakedns_cloned = fakedns.clone();
+    futs.push(Box::pin(async move {
+        // Packets already read are fed to the stack in a batch with a single
+        // flush.
+        let mut tun_stream = tun_stream.ready_chunks(BATCH_SIZE);
+        while let Some(pkts) = tun_stream.next().await {
+            for pkt in pkts {
+                let mut pkt = match pkt {
+                    Ok(pkt) => pkt,
+                    Err(e) => {
+                        warn!("read from tun failed: {}", e);
+                        return;
+                    }
+                };
+                if opts_cloned.replies_icmp(&pkt, &fakedns_cloned) {
+                    if let Some(reply) = icmp::echo_reply(&pkt) {
+                        if let Err(e) = icmp_tx.try_send(reply) {
+                            trace!("drop icmp echo reply: {}", e);
+                        }
+                        continue;
+                    }
+                }
+                if tcp_mss > 0 {
+                    mss::clamp(&mut pkt, tcp_mss);
+                }
+                if let Err(e) = stack_sink.feed(pkt).await {
+                    warn!("write to netstack failed: {}", e);
+                    return;
                 }
             }
-        }));
-
-        // Extracts TCP connections from stack and sends them to the dispatcher.
-        let inbound_tag_cloned = inbound_tag.clone();
-        let fakedns_cloned = fakedns.clone();
-        futs.push(Box::pin(async move {
-            while let Some((stream, local_addr, remote_addr)) = tcp_listener.next().await {
-                tokio::spawn(handle_inbound_stream(
-                    stream,
-                    local_addr,
-                    remote_addr,
-                    inbound_tag_cloned.clone(),
-                    dispatcher.clone(),
-                    fakedns_cloned.clone(),
-                ));
+            if let Err(e) = stack_sink.flush().await {
+                warn!("write to netstack failed: {}", e);
+                return;
             }
-        }));
+        }
+    }));
 
-        // Receive and send UDP packets between netstack and NAT manager. The NAT
-        // manager would maintain UDP sessions and send them to the dispatcher.
-        futs.push(Box::pin(async move {
-            handle_inbound_datagram(udp_socket, inbound_tag, nat_manager, fakedns.clone()).await;
-        }));
+    // Extracts TCP connections from stack and sends them to the dispatcher.
+    let inbound_tag_cloned = inbound_tag.clone();
+    let fakedns_cloned = fakedns.clone();
+    let opts_cloned = opts.clone();
+    futs.push(Box::pin(async move {
+        while let Some((stream, local_addr, remote_addr)) = tcp_listener.next().await {
+            // The netstack has already completed the handshake, rejected
+            // connections are closed right away.
+            let permit = match opts_cloned.limiter.try_acquire(local_addr.ip()) {
+                Some(p) => p,
+                None => {
+                    debug!("connection from {} rejected by limits", &local_addr);
+                    continue;
+                }
+            };
+            let fut = handle_inbound_stream(
+                stream,
+                local_addr,
+                remote_addr,
+                inbound_tag_cloned.clone(),
+                dispatcher.clone(),
+                fakedns_cloned.clone(),
+                opts_cloned.clone(),
+            );
+            tokio::spawn(async move {
+                fut.await;
+                drop(permit);
+            });
+        }
+    }));
 
-        info!("start tun inbound");
-        futures::future::select_all(futs).await;
-    }))
+    // Receive and send UDP packets between netstack and NAT manager. The NAT
+    // manager would maintain UDP sessions and send them to the dispatcher.
+    futs.push(Box::pin(async move {
+        handle_inbound_datagram(udp_socket, inbound_tag, nat_manager, fakedns, opts).await;
+    }));
+
+    futures::future::select_all(futs).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dns_hijack() {
+        let hijack = DnsHijack::new(&[]).unwrap();
+        assert!(hijack.matches(&"8.8.8.8:53".parse().unwrap()));
+        assert!(!hijack.matches(&"8.8.8.8:853".parse().unwrap()));
+
+        let entries = vec![
+            "1.1.1.1".to_string(),
+            "[2001:4860:4860::8888]:53".to_string(),
+            ":5353".to_string(),
+        ];
+        let hijack = DnsHijack::new(&entries).unwrap();
+        assert!(hijack.matches(&"1.1.1.1:53".parse().unwrap()));
+        assert!(!hijack.matches(&"8.8.8.8:53".parse().unwrap()));
+        assert!(hijack.matches(&"[2001:4860:4860::8888]:53".parse().unwrap()));
+        assert!(hijack.matches(&"8.8.8.8:5353".parse().unwrap()));
+
+        assert!(DnsHijack::new(&["dns.google".to_string()]).is_err());
+        assert!(DnsHijack::new(&[":dns".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_is_lan_ip() {
+        for ip in [
+            "192.168.1.1",
+            "10.0.0.1",
+            "172.16.0.1",
+            "169.254.1.1",
+            "127.0.0.1",
+            "::1",
+            "fe80::1",
+            "fd00::1",
+        ] {
+            assert!(is_lan_ip(&ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["8.8.8.8", "172.32.0.1", "2001:db8::1"] {
+            assert!(!is_lan_ip(&ip.parse().unwrap()), "{}", ip);
+        }
+    }
 }
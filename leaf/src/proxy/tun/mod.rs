@@ -1,3 +1,13 @@
+mod device;
+mod icmp;
 pub mod inbound;
+mod mss;
+#[cfg(target_os = "linux")]
+mod multiqueue;
+mod pool;
 
+#[cfg(feature = "inbound-tun-smoltcp")]
+pub mod netstack;
+
+#[cfg(not(feature = "inbound-tun-smoltcp"))]
 pub use netstack_lwip as netstack;
@@ -0,0 +1,99 @@
+use byteorder::{BigEndian, ByteOrder};
+
+const TCP: u8 = 6;
+const TCP_FLAG_SYN: u8 = 0x02;
+const TCP_OPTION_END: u8 = 0;
+const TCP_OPTION_NOP: u8 = 1;
+const TCP_OPTION_MSS: u8 = 2;
+
+/// Lowers the MSS option of a TCP SYN packet to `mss` if it's larger, returns
+/// whether the packet is modified.
+pub fn clamp(pkt: &mut [u8], mss: u16) -> bool {
+    let tcp_offset = match pkt.first().map(|b| b >> 4) {
+        Some(4) if pkt.len() >= 20 => {
+            let header_len = ((pkt[0] & 0x0f) as usize) * 4;
+            // Only the first fragment has the TCP header.
+            let frag_offset = BigEndian::read_u16(&pkt[6..8]) & 0x1fff;
+            if pkt[9] != TCP || frag_offset != 0 || header_len < 20 {
+                return false;
+            }
+            header_len
+        }
+        // Packets with extension headers are not handled.
+        Some(6) if pkt.len() >= 40 && pkt[6] == TCP => 40,
+        _ => return false,
+    };
+    let tcp = match pkt.get_mut(tcp_offset..) {
+        Some(tcp) if tcp.len() >= 20 => tcp,
+        _ => return false,
+    };
+    let data_offset = ((tcp[12] >> 4) as usize) * 4;
+    if tcp[13] & TCP_FLAG_SYN == 0 || data_offset < 20 || data_offset > tcp.len() {
+        return false;
+    }
+    let mut i = 20;
+    while i < data_offset {
+        match tcp[i] {
+            TCP_OPTION_END => break,
+            TCP_OPTION_NOP => i += 1,
+            kind => {
+                let len = match tcp.get(i + 1) {
+                    Some(len) if *len >= 2 && i + (*len as usize) <= data_offset => *len as usize,
+                    _ => return false,
+                };
+                if kind == TCP_OPTION_MSS && len == 4 {
+                    let old = BigEndian::read_u16(&tcp[i + 2..i + 4]);
+                    if old <= mss {
+                        return false;
+                    }
+                    BigEndian::write_u16(&mut tcp[i + 2..i + 4], mss);
+                    // Incremental checksum update, RFC 1624.
+                    let sum = !BigEndian::read_u16(&tcp[16..18]) as u32 + !old as u32 + mss as u32;
+                    let sum = (sum & 0xffff) + (sum >> 16);
+                    let sum = (sum & 0xffff) + (sum >> 16);
+                    BigEndian::write_u16(&mut tcp[16..18], !(sum as u16));
+                    return true;
+                }
+                i += len;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::icmp::{checksum, partial_sum};
+    use super::*;
+
+    fn tcp_checksum(pkt: &[u8]) -> u16 {
+        // The pseudo header consists of source address, destination address,
+        // protocol and TCP length.
+        let mut sum = partial_sum(0, &pkt[12..20]);
+        sum += TCP as u32;
+        sum += (pkt.len() - 20) as u32;
+        checksum(sum, &pkt[20..])
+    }
+
+    #[test]
+    fn test_clamp() {
+        // SYN from 10.0.0.2:50000 to 10.0.0.1:80 with MSS 1460 and a NOP.
+        let mut pkt = vec![
+            0x45, 0x00, 0x00, 0x2c, 0x00, 0x01, 0x40, 0x00, 0x40, 0x06, 0x00, 0x00, 10, 0, 0, 2,
+            10, 0, 0, 1, 0xc3, 0x50, 0x00, 0x50, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+            0x60, 0x02, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0x02, 0x04, 0x05, 0xb4,
+        ];
+        let sum = tcp_checksum(&pkt);
+        BigEndian::write_u16(&mut pkt[36..38], sum);
+
+        assert!(!clamp(&mut pkt, 1460));
+        assert!(clamp(&mut pkt, 1400));
+        assert_eq!(BigEndian::read_u16(&pkt[42..44]), 1400);
+        // Checksums of a valid packet sum up to zero.
+        assert_eq!(tcp_checksum(&pkt), 0);
+
+        // Not a SYN.
+        pkt[33] = 0x10;
+        assert!(!clamp(&mut pkt, 1200));
+    }
+}
@@ -0,0 +1,213 @@
+use std::ffi::CString;
+use std::io;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::sink::Sink;
+use futures::stream::Stream;
+use log::*;
+use tokio::io::unix::AsyncFd;
+use tokio::sync::mpsc::{channel, Receiver};
+use tokio::task::JoinHandle;
+
+use super::pool::BufferPool;
+
+// struct ifreq with the flags member only.
+#[repr(C)]
+struct IfReq {
+    name: [libc::c_char; libc::IFNAMSIZ],
+    flags: libc::c_short,
+    _pad: [u8; 22],
+}
+
+/// A queue of a multiqueue TUN device, all queues of the same device can be
+/// read and written concurrently.
+pub struct Queue {
+    fd: AsyncFd<OwnedFd>,
+}
+
+impl Queue {
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let mut guard = self.fd.readable().await?;
+            match guard.try_io(|fd| {
+                let n = unsafe {
+                    libc::read(
+                        fd.as_raw_fd(),
+                        buf.as_mut_ptr() as *mut libc::c_void,
+                        buf.len(),
+                    )
+                };
+                if n < 0 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(n as usize)
+                }
+            }) {
+                Ok(res) => return res,
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        loop {
+            let mut guard = self.fd.writable().await?;
+            match guard.try_io(|fd| {
+                let n = unsafe {
+                    libc::write(
+                        fd.as_raw_fd(),
+                        buf.as_ptr() as *const libc::c_void,
+                        buf.len(),
+                    )
+                };
+                if n < 0 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(n as usize)
+                }
+            }) {
+                Ok(res) => return res,
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
+
+/// Creates the TUN device of the given name if it doesn't exist and attaches
+/// `n` queues to it. The device is not configured.
+pub fn open(name: &str, n: usize) -> io::Result<Vec<Queue>> {
+    let name = CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let name = name.as_bytes_with_nul();
+    if name.len() > libc::IFNAMSIZ {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "interface name too long",
+        ));
+    }
+    let mut queues = Vec::with_capacity(n);
+    for _ in 0..n {
+        let fd = open_queue(name)?;
+        queues.push(Queue {
+            fd: AsyncFd::new(fd)?,
+        });
+    }
+    Ok(queues)
+}
+
+fn open_queue(name: &[u8]) -> io::Result<OwnedFd> {
+    let fd: RawFd = unsafe {
+        libc::open(
+            b"/dev/net/tun\0".as_ptr() as *const libc::c_char,
+            libc::O_RDWR | libc::O_NONBLOCK | libc::O_CLOEXEC,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+    let mut req = IfReq {
+        name: [0; libc::IFNAMSIZ],
+        flags: (libc::IFF_TUN | libc::IFF_NO_PI | libc::IFF_MULTI_QUEUE) as libc::c_short,
+        _pad: [0; 22],
+    };
+    for (dst, src) in req.name.iter_mut().zip(name) {
+        *dst = *src as libc::c_char;
+    }
+    if unsafe { libc::ioctl(fd.as_raw_fd(), libc::TUNSETIFF, &mut req) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(fd)
+}
+
+/// Picks a queue for the packet by its addresses so that packets of the same
+/// flow are written in order.
+pub fn queue_index(pkt: &[u8], n: usize) -> usize {
+    let addrs = match pkt.first().map(|b| b >> 4) {
+        Some(4) if pkt.len() >= 20 => &pkt[12..20],
+        Some(6) if pkt.len() >= 40 => &pkt[8..40],
+        _ => return 0,
+    };
+    let hash = addrs
+        .iter()
+        .fold(0u32, |h, b| h.wrapping_mul(31).wrapping_add(*b as u32));
+    hash as usize % n
+}
+
+/// Packets read from all queues.
+pub struct QueueStream {
+    rx: Receiver<io::Result<Vec<u8>>>,
+    readers: Vec<JoinHandle<()>>,
+}
+
+impl Stream for QueueStream {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl Drop for QueueStream {
+    fn drop(&mut self) {
+        for reader in self.readers.iter() {
+            reader.abort();
+        }
+    }
+}
+
+/// Spawns a reader task and a writer task for each queue, returns a sink and
+/// a stream of packets like a framed single queue device.
+pub fn split(
+    queues: Vec<Queue>,
+    mtu: usize,
+) -> (impl Sink<Vec<u8>, Error = io::Error> + Send, QueueStream) {
+    let (read_tx, rx) = channel(queues.len() * 128);
+    let pool = Arc::new(BufferPool::new(mtu));
+    let mut readers = Vec::with_capacity(queues.len());
+    let mut writers = Vec::with_capacity(queues.len());
+    for queue in queues {
+        let queue = Arc::new(queue);
+        let (write_tx, mut write_rx) = channel::<Vec<u8>>(128);
+        writers.push(write_tx);
+
+        let queue_cloned = queue.clone();
+        let pool_cloned = pool.clone();
+        tokio::spawn(async move {
+            while let Some(pkt) = write_rx.recv().await {
+                if let Err(e) = queue_cloned.send(&pkt).await {
+                    warn!("write to tun queue failed: {}", e);
+                    return;
+                }
+                pool_cloned.put(pkt);
+            }
+        });
+
+        let read_tx = read_tx.clone();
+        let pool = pool.clone();
+        readers.push(tokio::spawn(async move {
+            loop {
+                let mut buf = pool.get();
+                let res = queue.recv(&mut buf).await.map(|n| {
+                    buf.truncate(n);
+                    buf
+                });
+                let failed = res.is_err();
+                if read_tx.send(res).await.is_err() || failed {
+                    return;
+                }
+            }
+        }));
+    }
+    let sink = futures::sink::unfold(writers, |writers, pkt: Vec<u8>| async move {
+        let i = queue_index(&pkt, writers.len());
+        writers[i]
+            .send(pkt)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "tun queue closed"))?;
+        Ok(writers)
+    });
+    (sink, QueueStream { rx, readers })
+}
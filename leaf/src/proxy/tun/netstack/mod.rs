@@ -0,0 +1,11 @@
+//! A netstack built on smoltcp, it exposes the same interface as netstack-lwip
+//! so the TUN inbound can work with either of them.
+
+mod device;
+mod stack;
+mod tcp;
+mod udp;
+
+pub use stack::NetStack;
+pub use tcp::{TcpListener, TcpStream};
+pub use udp::{RecvHalf, SendHalf, UdpSocket};
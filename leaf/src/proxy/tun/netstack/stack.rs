@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::channel::mpsc as futures_mpsc;
+use futures::future::{self, Either, FutureExt};
+use futures::sink::Sink;
+use futures::stream::{Stream, StreamExt};
+use log::*;
+use smoltcp::iface::{Config, Interface, SocketHandle, SocketSet};
+use smoltcp::socket::tcp;
+use smoltcp::time::Instant;
+use smoltcp::wire::{HardwareAddress, IpAddress, IpCidr};
+use tokio::sync::mpsc::{self, Sender};
+use tokio::sync::Notify;
+
+use super::device::VirtualDevice;
+use super::tcp::{buffer_size, parse_syn, Control, TcpListener, TcpStream};
+use super::udp::{self, Datagram, UdpSocket};
+
+// Addresses assigned to the interface, they're used as gateways of the
+// default routes so that smoltcp accepts packets to any destination.
+const IPV4_ADDR: Ipv4Addr = Ipv4Addr::new(0, 0, 0, 1);
+const IPV6_ADDR: Ipv6Addr = Ipv6Addr::new(0xfdfe, 0xdcba, 0x9876, 0, 0, 0, 0xffff, 0xffff);
+
+const MTU: usize = 1500;
+
+/// A netstack runs in a separate task, IP packets are sent to and received
+/// from the netstack through the `Sink` and `Stream` implementations.
+pub struct NetStack {
+    stack_tx: futures_mpsc::Sender<Vec<u8>>,
+    stack_rx: mpsc::Receiver<Vec<u8>>,
+}
+
+impl NetStack {
+    pub fn new() -> (NetStack, TcpListener, Box<UdpSocket>) {
+        let (stack_tx, input_rx) = futures_mpsc::channel(512);
+        let (output_tx, stack_rx) = mpsc::channel(512);
+        let (tcp_tx, tcp_rx) = mpsc::channel(128);
+        let (udp_tx, udp_rx) = mpsc::channel(512);
+
+        let stack = Stack::new(output_tx.clone(), tcp_tx, udp_tx);
+        tokio::spawn(stack.run(input_rx));
+
+        (
+            NetStack { stack_tx, stack_rx },
+            TcpListener::new(tcp_rx),
+            UdpSocket::new(output_tx, udp_rx),
+        )
+    }
+}
+
+impl Stream for NetStack {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.stack_rx.poll_recv(cx).map(|pkt| pkt.map(Ok))
+    }
+}
+
+impl Sink<Vec<u8>> for NetStack {
+    type Error = io::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stack_tx)
+            .poll_ready(cx)
+            .map_err(|e| io::Error::new(io::ErrorKind::BrokenPipe, e))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Vec<u8>) -> io::Result<()> {
+        Pin::new(&mut self.stack_tx)
+            .start_send(item)
+            .map_err(|e| io::Error::new(io::ErrorKind::BrokenPipe, e))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stack_tx)
+            .poll_flush(cx)
+            .map_err(|e| io::Error::new(io::ErrorKind::BrokenPipe, e))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stack_tx)
+            .poll_close(cx)
+            .map_err(|e| io::Error::new(io::ErrorKind::BrokenPipe, e))
+    }
+}
+
+struct TcpConn {
+    handle: SocketHandle,
+    control: Arc<Mutex<Control>>,
+    accepted: bool,
+}
+
+struct Stack {
+    iface: Interface,
+    device: VirtualDevice,
+    sockets: SocketSet<'static>,
+    conns: HashMap<(SocketAddr, SocketAddr), TcpConn>,
+    notify: Arc<Notify>,
+    output_tx: Sender<Vec<u8>>,
+    tcp_tx: Sender<(TcpStream, SocketAddr, SocketAddr)>,
+    udp_tx: Sender<Datagram>,
+}
+
+impl Stack {
+    fn new(
+        output_tx: Sender<Vec<u8>>,
+        tcp_tx: Sender<(TcpStream, SocketAddr, SocketAddr)>,
+        udp_tx: Sender<Datagram>,
+    ) -> Self {
+        let mut device = VirtualDevice::new(MTU);
+        let mut config = Config::new(HardwareAddress::Ip);
+        config.random_seed = rand::random();
+        let mut iface = Interface::new(config, &mut device, Instant::now());
+        iface.set_any_ip(true);
+        iface.update_ip_addrs(|addrs| {
+            let _ = addrs.push(IpCidr::new(IpAddress::Ipv4(IPV4_ADDR), 32));
+            let _ = addrs.push(IpCidr::new(IpAddress::Ipv6(IPV6_ADDR), 128));
+        });
+        let _ = iface.routes_mut().add_default_ipv4_route(IPV4_ADDR);
+        let _ = iface.routes_mut().add_default_ipv6_route(IPV6_ADDR);
+        Stack {
+            iface,
+            device,
+            sockets: SocketSet::new(Vec::new()),
+            conns: HashMap::new(),
+            notify: Arc::new(Notify::new()),
+            output_tx,
+            tcp_tx,
+            udp_tx,
+        }
+    }
+
+    async fn run(mut self, mut input_rx: futures_mpsc::Receiver<Vec<u8>>) {
+        loop {
+            let now = Instant::now();
+            self.iface.poll(now, &mut self.device, &mut self.sockets);
+            self.process_tcp();
+            // Data moved into sockets need another poll to be sent out.
+            self.iface.poll(now, &mut self.device, &mut self.sockets);
+
+            while let Some(pkt) = self.device.tx_queue.pop_front() {
+                if self.output_tx.send(pkt).await.is_err() {
+                    return;
+                }
+            }
+
+            let delay = self
+                .iface
+                .poll_delay(Instant::now(), &self.sockets)
+                .map(Duration::from)
+                .unwrap_or_else(|| Duration::from_secs(1));
+            let notify = self.notify.clone();
+            let input = match future::select(
+                input_rx.next(),
+                future::select(
+                    Box::pin(notify.notified()),
+                    Box::pin(tokio::time::sleep(delay)),
+                ),
+            )
+            .await
+            {
+                Either::Left((pkt, _)) => pkt.ok_or(()),
+                Either::Right(_) => Ok(Vec::new()),
+            };
+            match input {
+                Ok(pkt) => {
+                    if !pkt.is_empty() {
+                        self.input(pkt);
+                    }
+                    while let Some(Some(pkt)) = input_rx.next().now_or_never() {
+                        self.input(pkt);
+                    }
+                }
+                // The stack has been dropped.
+                Err(_) => return,
+            }
+        }
+    }
+
+    fn input(&mut self, pkt: Vec<u8>) {
+        if let Some(dgram) = udp::parse_packet(&pkt) {
+            if let Err(e) = self.udp_tx.try_send(dgram) {
+                debug!("drop udp packet: {}", e);
+            }
+            return;
+        }
+        if let Some((src_addr, dst_addr)) = parse_syn(&pkt) {
+            if !self.conns.contains_key(&(src_addr, dst_addr)) {
+                let mut socket = tcp::Socket::new(
+                    tcp::SocketBuffer::new(vec![0; buffer_size()]),
+                    tcp::SocketBuffer::new(vec![0; buffer_size()]),
+                );
+                socket.set_nagle_enabled(false);
+                socket.set_ack_delay(None);
+                socket.set_timeout(Some(smoltcp::time::Duration::from_secs(60)));
+                if let Err(e) = socket.listen(dst_addr) {
+                    debug!("listen on {} failed: {}", &dst_addr, e);
+                    return;
+                }
+                let handle = self.sockets.add(socket);
+                self.conns.insert(
+                    (src_addr, dst_addr),
+                    TcpConn {
+                        handle,
+                        control: Arc::new(Mutex::new(Control::default())),
+                        accepted: false,
+                    },
+                );
+            }
+        }
+        self.device.rx_queue.push_back(pkt);
+    }
+
+    fn process_tcp(&mut self) {
+        let mut to_remove = Vec::new();
+        for (key, conn) in self.conns.iter_mut() {
+            let socket = self.sockets.get_mut::<tcp::Socket>(conn.handle);
+            let state = socket.state();
+
+            if !conn.accepted {
+                match state {
+                    tcp::State::Listen | tcp::State::Closed => {
+                        // The SYN packet has been rejected.
+                        to_remove.push(*key);
+                        continue;
+                    }
+                    tcp::State::SynReceived => continue,
+                    _ => {
+                        conn.accepted = true;
+                        let stream = TcpStream::new(conn.control.clone(), self.notify.clone());
+                        if let Err(e) = self.tcp_tx.try_send((stream, key.0, key.1)) {
+                            debug!("drop tcp connection {} -> {}: {}", key.0, key.1, e);
+                            socket.abort();
+                            continue;
+                        }
+                    }
+                }
+            }
+
+            let mut control = conn.control.lock().unwrap();
+
+            // Moves received data to the stream.
+            while socket.can_recv() {
+                let dropped = control.dropped;
+                let space = buffer_size().saturating_sub(control.recv_buffer.len());
+                if !dropped && space == 0 {
+                    break;
+                }
+                let n = socket
+                    .recv(|buf| {
+                        let n = if dropped {
+                            buf.len()
+                        } else {
+                            buf.len().min(space)
+                        };
+                        if !dropped {
+                            control.recv_buffer.extend(&buf[..n]);
+                        }
+                        (n, n)
+                    })
+                    .unwrap_or(0);
+                if n == 0 {
+                    break;
+                }
+                control.wake_recv();
+            }
+            if !socket.may_recv() && socket.recv_queue() == 0 && !control.recv_closed {
+                control.recv_closed = true;
+                control.wake_recv();
+            }
+
+            // Moves data from the stream to the socket.
+            while !control.send_buffer.is_empty() && socket.can_send() {
+                let n = socket
+                    .send_slice(control.send_buffer.as_slices().0)
+                    .unwrap_or(0);
+                if n == 0 {
+                    break;
+                }
+                control.send_buffer.drain(..n);
+                control.wake_send();
+            }
+            if control.send_closed && control.send_buffer.is_empty() && socket.may_send() {
+                socket.close();
+            }
+
+            // Nobody is going to read data from a dropped stream.
+            if control.dropped && state == tcp::State::FinWait2 {
+                socket.abort();
+            }
+
+            if matches!(socket.state(), tcp::State::Closed | tcp::State::TimeWait) {
+                control.closed = true;
+                control.recv_closed = true;
+                control.wake_recv();
+                control.wake_send();
+                to_remove.push(*key);
+            }
+        }
+        for key in to_remove {
+            if let Some(conn) = self.conns.remove(&key) {
+                self.sockets.remove(conn.handle);
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::cmp::min;
+use std::collections::VecDeque;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use futures::stream::Stream;
+use smoltcp::wire::{IpProtocol, Ipv4Packet, Ipv6Packet, TcpPacket};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::mpsc::Receiver;
+use tokio::sync::Notify;
+
+/// Returns the size of the buffers in both the smoltcp socket and the stream.
+pub(super) fn buffer_size() -> usize {
+    *crate::option::NETSTACK_TCP_BUFFER_SIZE * 1024
+}
+
+/// States shared between a `TcpStream` and the stack.
+#[derive(Default)]
+pub(super) struct Control {
+    pub send_buffer: VecDeque<u8>,
+    pub recv_buffer: VecDeque<u8>,
+    pub send_waker: Option<Waker>,
+    pub recv_waker: Option<Waker>,
+    // The stream has been shutdown for writing.
+    pub send_closed: bool,
+    // The peer has closed its sending side, no more data would be received.
+    pub recv_closed: bool,
+    // The connection has been closed or reset.
+    pub closed: bool,
+    // The stream has been dropped.
+    pub dropped: bool,
+}
+
+impl Control {
+    pub fn wake_send(&mut self) {
+        if let Some(waker) = self.send_waker.take() {
+            waker.wake();
+        }
+    }
+
+    pub fn wake_recv(&mut self) {
+        if let Some(waker) = self.recv_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+pub struct TcpListener {
+    rx: Receiver<(TcpStream, SocketAddr, SocketAddr)>,
+}
+
+impl TcpListener {
+    pub(super) fn new(rx: Receiver<(TcpStream, SocketAddr, SocketAddr)>) -> Self {
+        TcpListener { rx }
+    }
+}
+
+impl Stream for TcpListener {
+    type Item = (TcpStream, SocketAddr, SocketAddr);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+pub struct TcpStream {
+    control: Arc<Mutex<Control>>,
+    notify: Arc<Notify>,
+}
+
+impl TcpStream {
+    pub(super) fn new(control: Arc<Mutex<Control>>, notify: Arc<Notify>) -> Self {
+        TcpStream { control, notify }
+    }
+}
+
+impl AsyncRead for TcpStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<io::Result<()>> {
+        let mut control = self.control.lock().unwrap();
+        if control.recv_buffer.is_empty() {
+            if control.recv_closed {
+                return Poll::Ready(Ok(()));
+            }
+            control.recv_waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        let n = min(buf.remaining(), control.recv_buffer.len());
+        let (a, b) = control.recv_buffer.as_slices();
+        let n1 = min(n, a.len());
+        buf.put_slice(&a[..n1]);
+        buf.put_slice(&b[..n - n1]);
+        control.recv_buffer.drain(..n);
+        drop(control);
+        // There's space for the stack to move more data in.
+        self.notify.notify_one();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for TcpStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let mut control = self.control.lock().unwrap();
+        if control.closed || control.send_closed {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        let n = min(
+            buffer_size().saturating_sub(control.send_buffer.len()),
+            buf.len(),
+        );
+        if n == 0 {
+            control.send_waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        control.send_buffer.extend(&buf[..n]);
+        drop(control);
+        self.notify.notify_one();
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        self.control.lock().unwrap().send_closed = true;
+        self.notify.notify_one();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for TcpStream {
+    fn drop(&mut self) {
+        let mut control = self.control.lock().unwrap();
+        control.send_closed = true;
+        control.dropped = true;
+        drop(control);
+        self.notify.notify_one();
+    }
+}
+
+/// Returns the source and destination addresses if it's a TCP SYN packet
+/// initiating a new connection.
+pub(super) fn parse_syn(pkt: &[u8]) -> Option<(SocketAddr, SocketAddr)> {
+    let (src_ip, dst_ip, payload): (IpAddr, IpAddr, &[u8]) = match pkt.first()? >> 4 {
+        4 => {
+            let ip_packet = Ipv4Packet::new_checked(pkt).ok()?;
+            if ip_packet.next_header() != IpProtocol::Tcp {
+                return None;
+            }
+            (
+                ip_packet.src_addr().into(),
+                ip_packet.dst_addr().into(),
+                ip_packet.payload(),
+            )
+        }
+        6 => {
+            let ip_packet = Ipv6Packet::new_checked(pkt).ok()?;
+            if ip_packet.next_header() != IpProtocol::Tcp {
+                return None;
+            }
+            (
+                ip_packet.src_addr().into(),
+                ip_packet.dst_addr().into(),
+                ip_packet.payload(),
+            )
+        }
+        _ => return None,
+    };
+    let tcp_packet = TcpPacket::new_checked(payload).ok()?;
+    if !tcp_packet.syn() || tcp_packet.ack() {
+        return None;
+    }
+    Some((
+        SocketAddr::new(src_ip, tcp_packet.src_port()),
+        SocketAddr::new(dst_ip, tcp_packet.dst_port()),
+    ))
+}
@@ -0,0 +1,156 @@
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+
+use smoltcp::phy::ChecksumCapabilities;
+use smoltcp::wire::{
+    IpAddress, IpProtocol, Ipv4Packet, Ipv4Repr, Ipv6Packet, Ipv6Repr, UdpPacket, UdpRepr,
+};
+use tokio::sync::mpsc::{Receiver, Sender};
+
+pub type Datagram = (Vec<u8>, SocketAddr, SocketAddr);
+
+/// UDP is handled without going through smoltcp, datagrams are extracted from
+/// and written to raw IP packets directly.
+pub struct UdpSocket {
+    tx: Sender<Vec<u8>>,
+    rx: Receiver<Datagram>,
+}
+
+impl UdpSocket {
+    pub(super) fn new(tx: Sender<Vec<u8>>, rx: Receiver<Datagram>) -> Box<Self> {
+        Box::new(UdpSocket { tx, rx })
+    }
+
+    pub fn split(self: Box<Self>) -> (SendHalf, RecvHalf) {
+        (SendHalf { tx: self.tx }, RecvHalf { rx: self.rx })
+    }
+}
+
+pub struct SendHalf {
+    tx: Sender<Vec<u8>>,
+}
+
+impl SendHalf {
+    pub fn send_to(
+        &self,
+        data: &[u8],
+        src_addr: &SocketAddr,
+        dst_addr: &SocketAddr,
+    ) -> io::Result<()> {
+        let pkt = build_packet(data, src_addr, dst_addr)?;
+        self.tx
+            .try_send(pkt)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
+    }
+}
+
+pub struct RecvHalf {
+    rx: Receiver<Datagram>,
+}
+
+impl RecvHalf {
+    pub async fn recv_from(&mut self) -> io::Result<Datagram> {
+        self.rx
+            .recv()
+            .await
+            .ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "netstack closed"))
+    }
+}
+
+/// Returns the payload, source address and destination address if it's a
+/// non-fragmented UDP packet.
+pub(super) fn parse_packet(pkt: &[u8]) -> Option<Datagram> {
+    let (src_ip, dst_ip, payload): (IpAddr, IpAddr, &[u8]) = match pkt.first()? >> 4 {
+        4 => {
+            let ip_packet = Ipv4Packet::new_checked(pkt).ok()?;
+            if ip_packet.next_header() != IpProtocol::Udp
+                || ip_packet.more_frags()
+                || ip_packet.frag_offset() != 0
+            {
+                return None;
+            }
+            (
+                ip_packet.src_addr().into(),
+                ip_packet.dst_addr().into(),
+                ip_packet.payload(),
+            )
+        }
+        6 => {
+            let ip_packet = Ipv6Packet::new_checked(pkt).ok()?;
+            if ip_packet.next_header() != IpProtocol::Udp {
+                return None;
+            }
+            (
+                ip_packet.src_addr().into(),
+                ip_packet.dst_addr().into(),
+                ip_packet.payload(),
+            )
+        }
+        _ => return None,
+    };
+    let udp_packet = UdpPacket::new_checked(payload).ok()?;
+    Some((
+        udp_packet.payload().to_vec(),
+        SocketAddr::new(src_ip, udp_packet.src_port()),
+        SocketAddr::new(dst_ip, udp_packet.dst_port()),
+    ))
+}
+
+fn build_packet(data: &[u8], src_addr: &SocketAddr, dst_addr: &SocketAddr) -> io::Result<Vec<u8>> {
+    let udp_repr = UdpRepr {
+        src_port: src_addr.port(),
+        dst_port: dst_addr.port(),
+    };
+    let udp_len = udp_repr.header_len() + data.len();
+    let caps = ChecksumCapabilities::default();
+    match (src_addr.ip(), dst_addr.ip()) {
+        (IpAddr::V4(src_ip), IpAddr::V4(dst_ip)) => {
+            let ip_repr = Ipv4Repr {
+                src_addr: src_ip,
+                dst_addr: dst_ip,
+                next_header: IpProtocol::Udp,
+                payload_len: udp_len,
+                hop_limit: 64,
+            };
+            let mut buf = vec![0u8; ip_repr.buffer_len() + udp_len];
+            let mut ip_packet = Ipv4Packet::new_unchecked(&mut buf[..]);
+            ip_repr.emit(&mut ip_packet, &caps);
+            let mut udp_packet = UdpPacket::new_unchecked(ip_packet.payload_mut());
+            udp_repr.emit(
+                &mut udp_packet,
+                &IpAddress::Ipv4(src_ip),
+                &IpAddress::Ipv4(dst_ip),
+                data.len(),
+                |payload| payload.copy_from_slice(data),
+                &caps,
+            );
+            Ok(buf)
+        }
+        (IpAddr::V6(src_ip), IpAddr::V6(dst_ip)) => {
+            let ip_repr = Ipv6Repr {
+                src_addr: src_ip,
+                dst_addr: dst_ip,
+                next_header: IpProtocol::Udp,
+                payload_len: udp_len,
+                hop_limit: 64,
+            };
+            let mut buf = vec![0u8; ip_repr.buffer_len() + udp_len];
+            let mut ip_packet = Ipv6Packet::new_unchecked(&mut buf[..]);
+            ip_repr.emit(&mut ip_packet);
+            let mut udp_packet = UdpPacket::new_unchecked(ip_packet.payload_mut());
+            udp_repr.emit(
+                &mut udp_packet,
+                &IpAddress::Ipv6(src_ip),
+                &IpAddress::Ipv6(dst_ip),
+                data.len(),
+                |payload| payload.copy_from_slice(data),
+                &caps,
+            );
+            Ok(buf)
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "mismatched address families",
+        )),
+    }
+}
@@ -0,0 +1,34 @@
+use std::sync::Mutex;
+
+// Max number of idle buffers kept in the pool.
+const POOL_CAPACITY: usize = 256;
+
+/// Buffers of written packets are recycled for reading, so that reading a
+/// packet doesn't allocate in most cases.
+pub(super) struct BufferPool {
+    bufs: Mutex<Vec<Vec<u8>>>,
+    size: usize,
+}
+
+impl BufferPool {
+    pub fn new(size: usize) -> Self {
+        BufferPool {
+            bufs: Mutex::new(Vec::new()),
+            size,
+        }
+    }
+
+    pub fn get(&self) -> Vec<u8> {
+        let mut buf = self.bufs.lock().unwrap().pop().unwrap_or_default();
+        buf.resize(self.size, 0);
+        buf
+    }
+
+    pub fn put(&self, mut buf: Vec<u8>) {
+        let mut bufs = self.bufs.lock().unwrap();
+        if bufs.len() < POOL_CAPACITY {
+            buf.clear();
+            bufs.push(buf);
+        }
+    }
+}
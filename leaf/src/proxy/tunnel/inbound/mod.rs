@@ -0,0 +1,5 @@
+mod tcp;
+mod udp;
+
+pub use tcp::Handler as TcpHandler;
+pub use udp::Handler as UdpHandler;
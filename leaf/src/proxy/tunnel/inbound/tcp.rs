@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+
+use crate::{
+    proxy::*,
+    session::{Session, SocksAddr},
+};
+
+/// Forwards connections to a fixed destination.
+pub struct Handler {
+    pub destination: SocksAddr,
+}
+
+#[async_trait]
+impl TcpInboundHandler for Handler {
+    type TStream = AnyStream;
+    type TDatagram = AnyInboundDatagram;
+
+    async fn handle<'a>(
+        &'a self,
+        mut sess: Session,
+        stream: Self::TStream,
+    ) -> std::io::Result<InboundTransport<Self::TStream, Self::TDatagram>> {
+        sess.destination = self.destination.clone();
+        Ok(InboundTransport::Stream(stream, sess))
+    }
+}
@@ -0,0 +1,84 @@
+use std::io;
+use std::net::SocketAddr;
+
+use async_trait::async_trait;
+
+use crate::{
+    proxy::*,
+    session::{DatagramSource, SocksAddr},
+};
+
+/// Forwards datagrams to a fixed destination.
+pub struct Handler {
+    pub destination: SocksAddr,
+}
+
+#[async_trait]
+impl UdpInboundHandler for Handler {
+    type UStream = AnyStream;
+    type UDatagram = AnyInboundDatagram;
+
+    async fn handle<'a>(
+        &'a self,
+        socket: Self::UDatagram,
+    ) -> io::Result<InboundTransport<Self::UStream, Self::UDatagram>> {
+        Ok(InboundTransport::Datagram(
+            Box::new(Datagram {
+                socket,
+                destination: self.destination.clone(),
+            }),
+            None,
+        ))
+    }
+}
+
+pub struct Datagram {
+    socket: Box<dyn InboundDatagram>,
+    destination: SocksAddr,
+}
+
+impl InboundDatagram for Datagram {
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn InboundDatagramRecvHalf>,
+        Box<dyn InboundDatagramSendHalf>,
+    ) {
+        let (rh, sh) = self.socket.split();
+        (
+            Box::new(DatagramRecvHalf(rh, self.destination)),
+            Box::new(DatagramSendHalf(sh)),
+        )
+    }
+
+    fn into_std(self: Box<Self>) -> io::Result<std::net::UdpSocket> {
+        self.socket.into_std()
+    }
+}
+
+pub struct DatagramRecvHalf(Box<dyn InboundDatagramRecvHalf>, SocksAddr);
+
+#[async_trait]
+impl InboundDatagramRecvHalf for DatagramRecvHalf {
+    async fn recv_from(
+        &mut self,
+        buf: &mut [u8],
+    ) -> ProxyResult<(usize, DatagramSource, SocksAddr)> {
+        let (n, src_addr, _) = self.0.recv_from(buf).await?;
+        Ok((n, src_addr, self.1.clone()))
+    }
+}
+
+pub struct DatagramSendHalf(Box<dyn InboundDatagramSendHalf>);
+
+#[async_trait]
+impl InboundDatagramSendHalf for DatagramSendHalf {
+    async fn send_to(
+        &mut self,
+        buf: &[u8],
+        src_addr: &SocksAddr,
+        dst_addr: &SocketAddr,
+    ) -> io::Result<usize> {
+        self.0.send_to(buf, src_addr, dst_addr).await
+    }
+}
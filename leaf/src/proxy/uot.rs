@@ -0,0 +1,345 @@
+//! UDP-over-TCP, datagrams of any destinations carried over a stream with the
+//! framing of sing-box's UoT version 2, for servers which only relay TCP.
+//!
+//! A stream is opened to the magic address, followed by a request
+//! `|connect(1)|destination|`. Each datagram is then framed as
+//! `|address|len(2)|data|`, or `|len(2)|data|` if the request is of connect
+//! mode. Addresses are `|type(1)|address|port(2)|`, where the type is 0 for
+//! IPv4, 1 for IPv6 and 2 for domains prefixed by their length. Streams opened
+//! to the legacy magic address skip the request.
+
+use std::convert::TryFrom;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bytes::{BufMut, BytesMut};
+use futures::TryFutureExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+
+use crate::{
+    proxy::*,
+    session::{DatagramSource, SocksAddr},
+};
+
+pub const MAGIC_ADDRESS: &str = "sp.v2.udp-over-tcp.arpa";
+pub const LEGACY_MAGIC_ADDRESS: &str = "sp.udp-over-tcp.arpa";
+
+const ADDR_IPV4: u8 = 0x00;
+const ADDR_IPV6: u8 = 0x01;
+const ADDR_DOMAIN: u8 = 0x02;
+
+/// Returns the address to open a stream to for datagrams.
+pub fn magic_address() -> SocksAddr {
+    SocksAddr::Domain(MAGIC_ADDRESS.to_string(), 0)
+}
+
+/// Whether a stream to `addr` carries datagrams.
+pub fn is_magic_address(addr: &SocksAddr) -> bool {
+    match addr {
+        SocksAddr::Domain(domain, _) => domain == MAGIC_ADDRESS || domain == LEGACY_MAGIC_ADDRESS,
+        _ => false,
+    }
+}
+
+fn write_addr(addr: &SocksAddr, buf: &mut BytesMut) {
+    match addr {
+        SocksAddr::Ip(SocketAddr::V4(a)) => {
+            buf.put_u8(ADDR_IPV4);
+            buf.put_slice(&a.ip().octets());
+        }
+        SocksAddr::Ip(SocketAddr::V6(a)) => {
+            buf.put_u8(ADDR_IPV6);
+            buf.put_slice(&a.ip().octets());
+        }
+        SocksAddr::Domain(domain, _) => {
+            buf.put_u8(ADDR_DOMAIN);
+            buf.put_u8(domain.len() as u8);
+            buf.put_slice(domain.as_bytes());
+        }
+    }
+    buf.put_u16(addr.port());
+}
+
+async fn read_addr<R: AsyncRead + Unpin>(r: &mut R) -> io::Result<SocksAddr> {
+    let ip = match r.read_u8().await? {
+        ADDR_IPV4 => {
+            let mut buf = [0u8; 4];
+            r.read_exact(&mut buf).await?;
+            IpAddr::V4(Ipv4Addr::from(buf))
+        }
+        ADDR_IPV6 => {
+            let mut buf = [0u8; 16];
+            r.read_exact(&mut buf).await?;
+            IpAddr::V6(Ipv6Addr::from(buf))
+        }
+        ADDR_DOMAIN => {
+            let mut buf = vec![0u8; r.read_u8().await? as usize];
+            r.read_exact(&mut buf).await?;
+            let domain = String::from_utf8(buf)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid domain"))?;
+            let port = r.read_u16().await?;
+            return SocksAddr::try_from((domain, port));
+        }
+        t => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid address type {}", t),
+            ))
+        }
+    };
+    Ok(SocksAddr::Ip(SocketAddr::new(ip, r.read_u16().await?)))
+}
+
+/// Writes the request of a stream for datagrams of any destinations,
+/// `destination` being the one of the first datagram.
+pub fn write_request(destination: &SocksAddr, buf: &mut BytesMut) {
+    buf.put_u8(0);
+    write_addr(destination, buf);
+}
+
+fn write_frame(addr: Option<&SocksAddr>, data: &[u8], buf: &mut BytesMut) {
+    if let Some(addr) = addr {
+        write_addr(addr, buf);
+    }
+    buf.put_u16(data.len() as u16);
+    buf.put_slice(data);
+}
+
+async fn read_frame<R: AsyncRead + Unpin>(
+    r: &mut R,
+    addr: Option<&SocksAddr>,
+    buf: &mut [u8],
+) -> io::Result<(usize, SocksAddr)> {
+    let addr = match addr {
+        Some(addr) => addr.clone(),
+        None => read_addr(r).await?,
+    };
+    let n = r.read_u16().await? as usize;
+    if buf.len() < n {
+        return Err(io::Error::new(io::ErrorKind::Interrupted, "Small buffer"));
+    }
+    r.read_exact(&mut buf[..n]).await?;
+    Ok((n, addr))
+}
+
+/// An outbound datagram over a stream whose request has been sent.
+pub struct ClientDatagram<S> {
+    stream: S,
+    peers: DomainPeers,
+}
+
+impl<S> ClientDatagram<S> {
+    pub fn new(stream: S, destination: &SocksAddr) -> Self {
+        let peers = DomainPeers::default();
+        if let SocksAddr::Domain(..) = destination {
+            peers.add_pending(destination);
+        }
+        ClientDatagram { stream, peers }
+    }
+}
+
+impl<S> OutboundDatagram for ClientDatagram<S>
+where
+    S: 'static + AsyncRead + AsyncWrite + Unpin + Send + Sync,
+{
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn OutboundDatagramRecvHalf>,
+        Box<dyn OutboundDatagramSendHalf>,
+    ) {
+        let (r, w) = tokio::io::split(self.stream);
+        (
+            Box::new(ClientDatagramRecvHalf(r, self.peers.clone())),
+            Box::new(ClientDatagramSendHalf(w, self.peers)),
+        )
+    }
+}
+
+pub struct ClientDatagramRecvHalf<T>(ReadHalf<T>, DomainPeers);
+
+#[async_trait]
+impl<T> OutboundDatagramRecvHalf for ClientDatagramRecvHalf<T>
+where
+    T: AsyncRead + AsyncWrite + Send + Sync,
+{
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocksAddr)> {
+        let (n, addr) = read_frame(&mut self.0, None, buf).await?;
+        Ok((n, self.1.source(addr)))
+    }
+}
+
+pub struct ClientDatagramSendHalf<T>(WriteHalf<T>, DomainPeers);
+
+#[async_trait]
+impl<T> OutboundDatagramSendHalf for ClientDatagramSendHalf<T>
+where
+    T: AsyncRead + AsyncWrite + Send + Sync,
+{
+    async fn send_to(&mut self, buf: &[u8], target: &SocksAddr) -> io::Result<usize> {
+        if let SocksAddr::Domain(..) = target {
+            self.1.add_pending(target);
+        }
+        let mut data = BytesMut::new();
+        write_frame(Some(target), buf, &mut data);
+        self.0.write_all(&data).map_ok(|_| buf.len()).await
+    }
+}
+
+/// An inbound datagram over a stream opened to the magic address.
+pub struct ServerDatagram<S> {
+    stream: S,
+    source: DatagramSource,
+    // The destination of all datagrams in connect mode.
+    destination: Option<SocksAddr>,
+}
+
+impl<S> ServerDatagram<S>
+where
+    S: AsyncRead + Unpin,
+{
+    /// Reads the request of a stream opened to `magic_address`, returns the
+    /// datagram and the destination of the first datagram if known.
+    pub async fn accept(
+        mut stream: S,
+        magic_address: &SocksAddr,
+        source: DatagramSource,
+    ) -> io::Result<(Self, Option<SocksAddr>)> {
+        let legacy = matches!(magic_address, SocksAddr::Domain(d, _) if d == LEGACY_MAGIC_ADDRESS);
+        if legacy {
+            let dgram = ServerDatagram {
+                stream,
+                source,
+                destination: None,
+            };
+            return Ok((dgram, None));
+        }
+        let connect = stream.read_u8().await? != 0;
+        let destination = read_addr(&mut stream).await?;
+        let dgram = ServerDatagram {
+            stream,
+            source,
+            destination: if connect {
+                Some(destination.clone())
+            } else {
+                None
+            },
+        };
+        Ok((dgram, Some(destination)))
+    }
+}
+
+impl<S> InboundDatagram for ServerDatagram<S>
+where
+    S: 'static + AsyncRead + AsyncWrite + Unpin + Send + Sync,
+{
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn InboundDatagramRecvHalf>,
+        Box<dyn InboundDatagramSendHalf>,
+    ) {
+        let (r, w) = tokio::io::split(self.stream);
+        (
+            Box::new(ServerDatagramRecvHalf(
+                r,
+                self.source,
+                self.destination.clone(),
+            )),
+            Box::new(ServerDatagramSendHalf(w, self.destination.is_some())),
+        )
+    }
+
+    fn into_std(self: Box<Self>) -> io::Result<std::net::UdpSocket> {
+        Err(io::Error::new(io::ErrorKind::Other, "stream transport"))
+    }
+}
+
+pub struct ServerDatagramRecvHalf<T>(ReadHalf<T>, DatagramSource, Option<SocksAddr>);
+
+#[async_trait]
+impl<T> InboundDatagramRecvHalf for ServerDatagramRecvHalf<T>
+where
+    T: AsyncRead + AsyncWrite + Send + Sync,
+{
+    async fn recv_from(
+        &mut self,
+        buf: &mut [u8],
+    ) -> ProxyResult<(usize, DatagramSource, SocksAddr)> {
+        let (n, addr) = read_frame(&mut self.0, self.2.as_ref(), buf)
+            .map_err(|e| ProxyError::DatagramFatal(anyhow!(e)))
+            .await?;
+        Ok((n, self.1, addr))
+    }
+}
+
+pub struct ServerDatagramSendHalf<T>(WriteHalf<T>, bool);
+
+#[async_trait]
+impl<T> InboundDatagramSendHalf for ServerDatagramSendHalf<T>
+where
+    T: AsyncRead + AsyncWrite + Send + Sync,
+{
+    async fn send_to(
+        &mut self,
+        buf: &[u8],
+        src_addr: &SocksAddr,
+        _dst_addr: &SocketAddr,
+    ) -> io::Result<usize> {
+        let mut data = BytesMut::new();
+        // Sources are implied in connect mode.
+        write_frame(if self.1 { None } else { Some(src_addr) }, buf, &mut data);
+        self.0.write_all(&data).map_ok(|_| buf.len()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_datagrams() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let domain = SocksAddr::Domain("example.com".to_string(), 53);
+            let peer = SocksAddr::Ip("1.1.1.1:53".parse().unwrap());
+            let other = SocksAddr::Ip("[2001:db8::1]:5000".parse().unwrap());
+            let (mut client_io, server_io) = tokio::io::duplex(1024);
+            let mut request = BytesMut::new();
+            write_request(&domain, &mut request);
+            client_io.write_all(&request).await.unwrap();
+
+            // The magic address itself is read by the proxy protocol.
+            let source = DatagramSource::new("127.0.0.1:1234".parse().unwrap(), None);
+            let (server, destination) = ServerDatagram::accept(server_io, &magic_address(), source)
+                .await
+                .unwrap();
+            assert_eq!(destination, Some(domain.clone()));
+            let (mut server_r, mut server_w) = Box::new(server).split();
+            let client = Box::new(ClientDatagram::new(client_io, &domain));
+            let (mut client_r, mut client_w) = client.split();
+
+            let mut buf = [0u8; 16];
+            client_w.send_to(b"query", &domain).await.unwrap();
+            let (n, src, dst) = server_r.recv_from(&mut buf).await.unwrap();
+            assert_eq!(
+                (&buf[..n], src, dst),
+                (&b"query"[..], source, domain.clone())
+            );
+
+            // Replies of the domain's peer appear to come from the domain.
+            let dst_addr = "127.0.0.1:1234".parse().unwrap();
+            server_w.send_to(b"answer", &peer, &dst_addr).await.unwrap();
+            server_w.send_to(b"hello", &other, &dst_addr).await.unwrap();
+            let (n, src) = client_r.recv_from(&mut buf).await.unwrap();
+            assert_eq!((&buf[..n], src), (&b"answer"[..], domain));
+            let (n, src) = client_r.recv_from(&mut buf).await.unwrap();
+            assert_eq!((&buf[..n], src), (&b"hello"[..], other));
+        });
+    }
+}
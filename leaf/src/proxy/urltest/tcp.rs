@@ -0,0 +1,45 @@
+use std::{io, sync::Arc};
+
+use async_trait::async_trait;
+use log::*;
+
+use crate::{
+    app::{outbound::urltest::UrlTester, SyncDnsClient},
+    proxy::*,
+    session::Session,
+};
+
+pub struct Handler {
+    pub tester: Arc<UrlTester>,
+    pub dns_client: SyncDnsClient,
+}
+
+#[async_trait]
+impl TcpOutboundHandler for Handler {
+    type Stream = AnyStream;
+
+    fn connect_addr(&self) -> Option<OutboundConnect> {
+        Some(OutboundConnect::NoConnect)
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        _stream: Option<Self::Stream>,
+    ) -> io::Result<Self::Stream> {
+        self.tester.start();
+        let a = self.tester.get_selected().await;
+        debug!(
+            "urltest handles tcp [{}] to [{}]",
+            sess.destination,
+            a.tag()
+        );
+        let stream = crate::proxy::connect_tcp_outbound(sess, self.dns_client.clone(), &a).await?;
+        TcpOutboundHandler::handle(a.as_ref(), sess, stream).await
+    }
+
+    async fn group_actor(&self, _sess: &Session) -> Option<AnyOutboundHandler> {
+        self.tester.start();
+        Some(self.tester.get_selected().await)
+    }
+}
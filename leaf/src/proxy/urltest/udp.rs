@@ -0,0 +1,46 @@
+use std::{io, sync::Arc};
+
+use async_trait::async_trait;
+use log::*;
+
+use crate::{
+    app::{outbound::urltest::UrlTester, SyncDnsClient},
+    proxy::*,
+    session::Session,
+};
+
+pub struct Handler {
+    pub tester: Arc<UrlTester>,
+    pub dns_client: SyncDnsClient,
+}
+
+#[async_trait]
+impl UdpOutboundHandler for Handler {
+    type UStream = AnyStream;
+    type Datagram = AnyOutboundDatagram;
+
+    fn connect_addr(&self) -> Option<OutboundConnect> {
+        Some(OutboundConnect::NoConnect)
+    }
+
+    fn transport_type(&self) -> DatagramTransportType {
+        DatagramTransportType::Undefined
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        _transport: Option<OutboundTransport<Self::UStream, Self::Datagram>>,
+    ) -> io::Result<Self::Datagram> {
+        self.tester.start();
+        let a = self.tester.get_selected().await;
+        debug!(
+            "urltest handles udp [{}] to [{}]",
+            sess.destination,
+            a.tag()
+        );
+        let transport =
+            crate::proxy::connect_udp_outbound(sess, self.dns_client.clone(), &a).await?;
+        UdpOutboundHandler::handle(a.as_ref(), sess, transport).await
+    }
+}
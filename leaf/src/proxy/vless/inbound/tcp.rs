@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bytes::{BufMut, BytesMut};
+use futures::TryFutureExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    common::uuid::parse_uuid,
+    proxy::*,
+    session::{DatagramSource, Network, Session, SocksAddr, SocksAddrWireType},
+};
+
+use super::super::xudp;
+use super::super::{COMMAND_MUX, COMMAND_TCP, COMMAND_UDP, VERSION};
+
+struct Datagram {
+    stream: AnyStream,
+    source: DatagramSource,
+    destination: SocksAddr,
+    xudp: bool,
+}
+
+impl InboundDatagram for Datagram {
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn InboundDatagramRecvHalf>,
+        Box<dyn InboundDatagramSendHalf>,
+    ) {
+        let (r, s) = tokio::io::split(self.stream);
+        (
+            Box::new(DatagramRecvHalf {
+                inner: r,
+                source: self.source,
+                last_addr: self.destination,
+                xudp: self.xudp,
+            }),
+            Box::new(DatagramSendHalf {
+                inner: s,
+                xudp: self.xudp,
+            }),
+        )
+    }
+
+    fn into_std(self: Box<Self>) -> io::Result<std::net::UdpSocket> {
+        Err(io::Error::new(io::ErrorKind::Other, "stream transport"))
+    }
+}
+
+struct DatagramRecvHalf<T> {
+    inner: T,
+    source: DatagramSource,
+    // Packets without addresses go to the last address seen.
+    last_addr: SocksAddr,
+    xudp: bool,
+}
+
+#[async_trait]
+impl<T> InboundDatagramRecvHalf for DatagramRecvHalf<T>
+where
+    T: AsyncRead + Send + Sync + Unpin,
+{
+    async fn recv_from(
+        &mut self,
+        buf: &mut [u8],
+    ) -> ProxyResult<(usize, DatagramSource, SocksAddr)> {
+        if !self.xudp {
+            let len = self
+                .inner
+                .read_u16()
+                .map_err(|e| ProxyError::DatagramFatal(e.into()))
+                .await? as usize;
+            if len > buf.len() {
+                return Err(ProxyError::DatagramFatal(anyhow!("Small buffer")));
+            }
+            self.inner
+                .read_exact(&mut buf[..len])
+                .map_err(|e| ProxyError::DatagramFatal(e.into()))
+                .await?;
+            return Ok((len, self.source, self.last_addr.clone()));
+        }
+        loop {
+            let frame = xudp::read_frame(&mut self.inner, buf)
+                .map_err(|e| ProxyError::DatagramFatal(e.into()))
+                .await?;
+            if frame.status == xudp::STATUS_END {
+                return Err(ProxyError::DatagramFatal(anyhow!("xudp session ended")));
+            }
+            if let Some(addr) = frame.addr {
+                self.last_addr = addr;
+            }
+            match (frame.status, frame.data_len) {
+                (xudp::STATUS_KEEP_ALIVE, _) | (_, None) => continue,
+                (_, Some(n)) => return Ok((n, self.source, self.last_addr.clone())),
+            }
+        }
+    }
+}
+
+struct DatagramSendHalf<T> {
+    inner: T,
+    xudp: bool,
+}
+
+#[async_trait]
+impl<T> InboundDatagramSendHalf for DatagramSendHalf<T>
+where
+    T: AsyncWrite + Send + Sync + Unpin,
+{
+    async fn send_to(
+        &mut self,
+        buf: &[u8],
+        src_addr: &SocksAddr,
+        _dst_addr: &SocketAddr,
+    ) -> io::Result<usize> {
+        let mut data = BytesMut::new();
+        if self.xudp {
+            xudp::write_frame(&mut data, xudp::STATUS_KEEP, src_addr, None, buf);
+        } else {
+            data.put_u16(buf.len() as u16);
+            data.put_slice(buf);
+        }
+        self.inner.write_all(&data).map_ok(|_| buf.len()).await
+    }
+}
+
+// Returns the flow in the addons, which is a protobuf message.
+fn addons_flow(addons: &[u8]) -> Option<&[u8]> {
+    // field 1, length-delimited
+    if addons.len() < 2 || addons[0] != 0x0a || addons[1] >= 0x80 {
+        return None;
+    }
+    addons.get(2..2 + addons[1] as usize)
+}
+
+pub struct Handler {
+    ids: HashMap<[u8; 16], String>,
+}
+
+impl Handler {
+    pub fn new(uuids: Vec<String>) -> io::Result<Self> {
+        let mut ids = HashMap::new();
+        for uuid in uuids {
+            let id = parse_uuid(&uuid).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("invalid uuid {}", uuid),
+                )
+            })?;
+            ids.insert(id, uuid);
+        }
+        Ok(Handler { ids })
+    }
+}
+
+#[async_trait]
+impl TcpInboundHandler for Handler {
+    type TStream = AnyStream;
+    type TDatagram = AnyInboundDatagram;
+
+    async fn handle<'a>(
+        &'a self,
+        mut sess: Session,
+        mut stream: Self::TStream,
+    ) -> std::io::Result<InboundTransport<Self::TStream, Self::TDatagram>> {
+        let mut buf = [0u8; 18];
+        stream.read_exact(&mut buf).await?;
+        if buf[0] != VERSION {
+            return Err(io::Error::new(io::ErrorKind::Other, "invalid version"));
+        }
+        let mut id = [0u8; 16];
+        id.copy_from_slice(&buf[1..17]);
+        let user = match self.ids.get(&id) {
+            Some(user) => user,
+            None => return Err(io::Error::new(io::ErrorKind::Other, "invalid id")),
+        };
+        let mut addons = vec![0u8; buf[17] as usize];
+        stream.read_exact(&mut addons).await?;
+        if matches!(addons_flow(&addons), Some(flow) if !flow.is_empty()) {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "vless flow is not supported",
+            ));
+        }
+        let cmd = stream.read_u8().await?;
+        if cmd != COMMAND_MUX {
+            sess.destination =
+                SocksAddr::read_from(&mut stream, SocksAddrWireType::PortFirst).await?;
+        }
+        sess.user = Some(user.clone());
+        // response header without addons
+        stream.write_all(&[VERSION, 0x00]).await?;
+        match cmd {
+            COMMAND_TCP => Ok(InboundTransport::Stream(stream, sess)),
+            COMMAND_UDP | COMMAND_MUX => {
+                sess.network = Network::Udp;
+                let source = DatagramSource::new(sess.source, sess.stream_id);
+                Ok(InboundTransport::Datagram(
+                    Box::new(Datagram {
+                        stream,
+                        source,
+                        destination: sess.destination.clone(),
+                        xudp: cmd == COMMAND_MUX,
+                    }),
+                    Some(sess),
+                ))
+            }
+            _ => Err(io::Error::new(io::ErrorKind::Other, "invalid command")),
+        }
+    }
+}
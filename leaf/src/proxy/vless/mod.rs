@@ -0,0 +1,18 @@
+//! VLESS, https://xtls.github.io/en/development/protocols/vless.html
+//!
+//! UDP is relayed either with the UDP command, where all packets go to the
+//! destination of the request, or with XUDP, the Mux.Cool based encoding
+//! Xray uses to carry packets to any destinations in a single connection.
+
+#[cfg(feature = "inbound-vless")]
+pub mod inbound;
+#[cfg(feature = "outbound-vless")]
+pub mod outbound;
+
+mod xudp;
+
+pub const VERSION: u8 = 0x00;
+
+pub const COMMAND_TCP: u8 = 0x01;
+pub const COMMAND_UDP: u8 = 0x02;
+pub const COMMAND_MUX: u8 = 0x03;
@@ -0,0 +1,109 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::ready;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use super::super::VERSION;
+
+enum ReadState {
+    Version,
+    AddonsLength,
+    Addons(usize),
+    Data,
+}
+
+/// Strips the response header the server sends before any data.
+pub struct Stream<T> {
+    inner: T,
+    state: ReadState,
+}
+
+impl<T> Stream<T> {
+    pub fn new(inner: T) -> Self {
+        Stream {
+            inner,
+            state: ReadState::Version,
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Stream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let me = &mut *self;
+        loop {
+            let n = match me.state {
+                ReadState::Version | ReadState::AddonsLength => 1,
+                ReadState::Addons(n) => n.min(256),
+                ReadState::Data => return Pin::new(&mut me.inner).poll_read(cx, buf),
+            };
+            let mut head = [0u8; 256];
+            let mut head_buf = ReadBuf::new(&mut head[..n]);
+            ready!(Pin::new(&mut me.inner).poll_read(cx, &mut head_buf))?;
+            let read = head_buf.filled();
+            if read.is_empty() {
+                return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+            }
+            me.state = match me.state {
+                ReadState::Version => {
+                    if read[0] != VERSION {
+                        return Poll::Ready(Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "unexpected vless version",
+                        )));
+                    }
+                    ReadState::AddonsLength
+                }
+                ReadState::AddonsLength if read[0] == 0 => ReadState::Data,
+                ReadState::AddonsLength => ReadState::Addons(read[0] as usize),
+                ReadState::Addons(n) if n == read.len() => ReadState::Data,
+                ReadState::Addons(n) => ReadState::Addons(n - read.len()),
+                ReadState::Data => unreachable!(),
+            };
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Stream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    #[test]
+    fn test_strip_response_header() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let data: &[u8] = &[0x00, 0x02, 0xaa, 0xbb, b'h', b'i'];
+            let mut stream = Stream::new(data);
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"hi");
+        });
+    }
+}
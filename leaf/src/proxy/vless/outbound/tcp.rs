@@ -0,0 +1,47 @@
+use std::io;
+
+use async_trait::async_trait;
+use bytes::{BufMut, BytesMut};
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    proxy::*,
+    session::{Session, SocksAddrWireType},
+};
+
+use super::super::{COMMAND_TCP, VERSION};
+use super::stream::Stream;
+
+pub struct Handler {
+    pub address: String,
+    pub port: u16,
+    pub id: [u8; 16],
+}
+
+#[async_trait]
+impl TcpOutboundHandler for Handler {
+    type Stream = AnyStream;
+
+    fn connect_addr(&self) -> Option<OutboundConnect> {
+        Some(OutboundConnect::Proxy(self.address.clone(), self.port))
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        stream: Option<Self::Stream>,
+    ) -> io::Result<Self::Stream> {
+        let mut stream =
+            stream.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "invalid input"))?;
+        let mut buf = BytesMut::new();
+        buf.put_u8(VERSION);
+        buf.put_slice(&self.id);
+        // no addons
+        buf.put_u8(0x00);
+        buf.put_u8(COMMAND_TCP);
+        sess.destination
+            .write_buf(&mut buf, SocksAddrWireType::PortFirst);
+        stream.write_all(&buf).await?;
+        Ok(Box::new(Stream::new(stream)))
+    }
+}
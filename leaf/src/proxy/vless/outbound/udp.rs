@@ -0,0 +1,169 @@
+use std::io;
+
+use async_trait::async_trait;
+use bytes::{BufMut, BytesMut};
+use futures::future::TryFutureExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+
+use crate::{
+    proxy::*,
+    session::{Session, SocksAddr, SocksAddrWireType},
+};
+
+use super::super::xudp;
+use super::super::{COMMAND_MUX, COMMAND_UDP, VERSION};
+use super::stream::Stream;
+
+pub struct Handler {
+    pub address: String,
+    pub port: u16,
+    pub id: [u8; 16],
+}
+
+#[async_trait]
+impl UdpOutboundHandler for Handler {
+    type UStream = AnyStream;
+    type Datagram = AnyOutboundDatagram;
+
+    fn connect_addr(&self) -> Option<OutboundConnect> {
+        Some(OutboundConnect::Proxy(self.address.clone(), self.port))
+    }
+
+    fn transport_type(&self) -> DatagramTransportType {
+        DatagramTransportType::Stream
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        transport: Option<OutboundTransport<Self::UStream, Self::Datagram>>,
+    ) -> io::Result<Self::Datagram> {
+        let stream = if let Some(OutboundTransport::Stream(stream)) = transport {
+            stream
+        } else {
+            return Err(io::Error::new(io::ErrorKind::Other, "invalid input"));
+        };
+        let mut head = BytesMut::new();
+        head.put_u8(VERSION);
+        head.put_slice(&self.id);
+        // no addons
+        head.put_u8(0x00);
+        // Like Xray, DNS and QUIC go with the UDP command, other packets are
+        // sent with XUDP so they can go to any destinations.
+        let port = sess.destination.port();
+        let xudp = port != 53 && port != 443;
+        if xudp {
+            head.put_u8(COMMAND_MUX);
+        } else {
+            head.put_u8(COMMAND_UDP);
+            sess.destination
+                .write_buf(&mut head, SocksAddrWireType::PortFirst);
+        }
+        Ok(Box::new(Datagram {
+            stream: Stream::new(stream),
+            destination: sess.destination.clone(),
+            head: Some(head),
+            xudp,
+        }))
+    }
+}
+
+pub struct Datagram<S> {
+    stream: Stream<S>,
+    destination: SocksAddr,
+    head: Option<BytesMut>,
+    xudp: bool,
+}
+
+impl<S> OutboundDatagram for Datagram<S>
+where
+    S: 'static + AsyncRead + AsyncWrite + Unpin + Send + Sync,
+{
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn OutboundDatagramRecvHalf>,
+        Box<dyn OutboundDatagramSendHalf>,
+    ) {
+        let (r, w) = tokio::io::split(self.stream);
+        (
+            Box::new(DatagramRecvHalf {
+                inner: r,
+                last_addr: self.destination,
+                xudp: self.xudp,
+            }),
+            Box::new(DatagramSendHalf {
+                inner: w,
+                head: self.head,
+                xudp: self.xudp,
+            }),
+        )
+    }
+}
+
+pub struct DatagramRecvHalf<T> {
+    inner: ReadHalf<T>,
+    // Packets without addresses are from the last address seen.
+    last_addr: SocksAddr,
+    xudp: bool,
+}
+
+#[async_trait]
+impl<T> OutboundDatagramRecvHalf for DatagramRecvHalf<T>
+where
+    T: AsyncRead + AsyncWrite + Send + Sync,
+{
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocksAddr)> {
+        if !self.xudp {
+            let len = self.inner.read_u16().await? as usize;
+            if len > buf.len() {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "Small buffer"));
+            }
+            self.inner.read_exact(&mut buf[..len]).await?;
+            return Ok((len, self.last_addr.clone()));
+        }
+        loop {
+            let frame = xudp::read_frame(&mut self.inner, buf).await?;
+            if frame.status == xudp::STATUS_END {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            if let Some(addr) = frame.addr {
+                self.last_addr = addr;
+            }
+            match (frame.status, frame.data_len) {
+                (xudp::STATUS_KEEP_ALIVE, _) | (_, None) => continue,
+                (_, Some(n)) => return Ok((n, self.last_addr.clone())),
+            }
+        }
+    }
+}
+
+pub struct DatagramSendHalf<T> {
+    inner: WriteHalf<T>,
+    head: Option<BytesMut>,
+    xudp: bool,
+}
+
+#[async_trait]
+impl<T> OutboundDatagramSendHalf for DatagramSendHalf<T>
+where
+    T: AsyncRead + AsyncWrite + Send + Sync,
+{
+    async fn send_to(&mut self, buf: &[u8], target: &SocksAddr) -> io::Result<usize> {
+        // Writes the header along with the first payload.
+        let mut data = self.head.take().unwrap_or_default();
+        let first = !data.is_empty();
+        if self.xudp {
+            if first {
+                let global_id: [u8; 8] = rand::random();
+                xudp::write_frame(&mut data, xudp::STATUS_NEW, target, Some(&global_id), buf);
+            } else {
+                xudp::write_frame(&mut data, xudp::STATUS_KEEP, target, None, buf);
+            }
+        } else {
+            data.put_u16(buf.len() as u16);
+            data.put_slice(buf);
+        }
+        self.inner.write_all(&data).map_ok(|_| buf.len()).await
+    }
+}
@@ -0,0 +1,129 @@
+//! XUDP frames, https://github.com/XTLS/Xray-core/blob/main/common/xudp/xudp.go
+//!
+//! Each packet is a Mux.Cool frame of the session 0, frames of UDP packets
+//! carry their addresses so a single connection serves any destinations.
+
+use std::io;
+
+use bytes::{BufMut, BytesMut};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::session::{SocksAddr, SocksAddrWireType};
+
+pub const STATUS_NEW: u8 = 0x01;
+pub const STATUS_KEEP: u8 = 0x02;
+pub const STATUS_END: u8 = 0x03;
+pub const STATUS_KEEP_ALIVE: u8 = 0x04;
+
+const OPTION_DATA: u8 = 0x01;
+
+const NETWORK_TCP: u8 = 0x01;
+const NETWORK_UDP: u8 = 0x02;
+
+/// Writes a frame carrying a UDP packet, the global ID is sent in the first
+/// frame only.
+pub fn write_frame(
+    buf: &mut BytesMut,
+    status: u8,
+    addr: &SocksAddr,
+    global_id: Option<&[u8; 8]>,
+    data: &[u8],
+) {
+    let mut meta = BytesMut::new();
+    // session ID
+    meta.put_u16(0);
+    meta.put_u8(status);
+    meta.put_u8(OPTION_DATA);
+    meta.put_u8(NETWORK_UDP);
+    addr.write_buf(&mut meta, SocksAddrWireType::PortFirst);
+    if let Some(id) = global_id {
+        meta.put_slice(id);
+    }
+    buf.put_u16(meta.len() as u16);
+    buf.put_slice(&meta);
+    buf.put_u16(data.len() as u16);
+    buf.put_slice(data);
+}
+
+pub struct Frame {
+    pub status: u8,
+    pub addr: Option<SocksAddr>,
+    /// Length of the data read into the buffer, if any.
+    pub data_len: Option<usize>,
+}
+
+/// Reads a frame, the data is read into `buf`.
+pub async fn read_frame<R>(r: &mut R, buf: &mut [u8]) -> io::Result<Frame>
+where
+    R: AsyncRead + Unpin,
+{
+    let meta_len = r.read_u16().await? as usize;
+    if meta_len < 4 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "invalid xudp frame",
+        ));
+    }
+    let mut meta = vec![0u8; meta_len];
+    r.read_exact(&mut meta).await?;
+    let status = meta[2];
+    let option = meta[3];
+    let mut addr = None;
+    if meta_len > 4 && (status == STATUS_NEW || status == STATUS_KEEP) {
+        match meta[4] {
+            NETWORK_UDP => {
+                let mut rest = &meta[5..];
+                addr = Some(SocksAddr::read_from(&mut rest, SocksAddrWireType::PortFirst).await?);
+            }
+            NETWORK_TCP => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "multiplexing tcp is not supported",
+                ));
+            }
+            _ => (),
+        }
+    }
+    let mut data_len = None;
+    if option & OPTION_DATA != 0 {
+        let len = r.read_u16().await? as usize;
+        if len > buf.len() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "Small buffer"));
+        }
+        r.read_exact(&mut buf[..len]).await?;
+        data_len = Some(len);
+    }
+    Ok(Frame {
+        status,
+        addr,
+        data_len,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let addr = SocksAddr::try_from(("example.com", 443)).unwrap();
+            let mut buf = BytesMut::new();
+            write_frame(&mut buf, STATUS_NEW, &addr, Some(&[1u8; 8]), b"hello");
+            write_frame(&mut buf, STATUS_KEEP, &addr, None, b"world");
+            let mut r = &buf[..];
+            let mut data = [0u8; 16];
+            let frame = read_frame(&mut r, &mut data).await.unwrap();
+            assert_eq!(frame.status, STATUS_NEW);
+            assert_eq!(frame.addr.unwrap().to_string(), "example.com:443");
+            assert_eq!(&data[..frame.data_len.unwrap()], b"hello");
+            let frame = read_frame(&mut r, &mut data).await.unwrap();
+            assert_eq!(frame.status, STATUS_KEEP);
+            assert_eq!(&data[..frame.data_len.unwrap()], b"world");
+            assert!(r.is_empty());
+        });
+    }
+}
@@ -0,0 +1,291 @@
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aes::cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, NewBlockCipher};
+use aes::Aes128;
+use anyhow::Result;
+use md5::{Digest, Md5};
+use rand::RngCore;
+use sha2::Sha256;
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::Shake128;
+
+use crate::common::crypto::NonceSequence;
+
+const KDF_SALT: &[u8] = b"VMess AEAD KDF";
+const CMD_KEY_SALT: &[u8] = b"c48619fe-8f02-49e0-b9e9-edf763e17e21";
+
+pub const KDF_AUTH_ID: &[u8] = b"AES Auth ID Encryption";
+pub const KDF_HEADER_LEN_KEY: &[u8] = b"VMess Header AEAD Key_Length";
+pub const KDF_HEADER_LEN_NONCE: &[u8] = b"VMess Header AEAD Nonce_Length";
+pub const KDF_HEADER_KEY: &[u8] = b"VMess Header AEAD Key";
+pub const KDF_HEADER_NONCE: &[u8] = b"VMess Header AEAD Nonce";
+pub const KDF_RESP_LEN_KEY: &[u8] = b"AEAD Resp Header Len Key";
+pub const KDF_RESP_LEN_IV: &[u8] = b"AEAD Resp Header Len IV";
+pub const KDF_RESP_KEY: &[u8] = b"AEAD Resp Header Key";
+pub const KDF_RESP_IV: &[u8] = b"AEAD Resp Header IV";
+
+pub const TAG_LEN: usize = 16;
+
+fn crypto_err() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "vmess crypto failed")
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Derives the key of the requests of a user from the UUID.
+pub fn cmd_key(id: &[u8; 16]) -> [u8; 16] {
+    let mut key = [0u8; 16];
+    key.copy_from_slice(&Md5::digest(&[&id[..], CMD_KEY_SALT].concat()));
+    key
+}
+
+/// The KDF of VMess AEAD, an HMAC-SHA256 keyed by the salt with HMACs keyed
+/// by the elements of the path nested over it.
+pub fn kdf(key: &[u8], path: &[&[u8]]) -> [u8; 32] {
+    let mut keys = vec![KDF_SALT];
+    keys.extend_from_slice(path);
+    nested_hmac(&keys, key)
+}
+
+/// The first 16 bytes of the KDF, e.g. an AES-128 key.
+pub fn kdf16(key: &[u8], path: &[&[u8]]) -> [u8; 16] {
+    let mut out = [0u8; 16];
+    out.copy_from_slice(&kdf(key, path)[..16]);
+    out
+}
+
+// The HMAC keyed by the last key with the HMAC of the keys before it as the
+// hash, SHA-256 when there are none left.
+fn nested_hmac(keys: &[&[u8]], data: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    let (key, inner) = match keys.split_last() {
+        Some(v) => v,
+        None => {
+            out.copy_from_slice(&Sha256::digest(data));
+            return out;
+        }
+    };
+    // The keys are never longer than the block size of SHA-256, which the
+    // HMACs over it share.
+    let mut pad = [0u8; 64];
+    pad[..key.len()].copy_from_slice(key);
+    let mut msg: Vec<u8> = pad.iter().map(|b| b ^ 0x36).collect();
+    msg.extend_from_slice(data);
+    let h = nested_hmac(inner, &msg);
+    let mut msg: Vec<u8> = pad.iter().map(|b| b ^ 0x5c).collect();
+    msg.extend_from_slice(&h);
+    out.copy_from_slice(&nested_hmac(inner, &msg));
+    out
+}
+
+/// Derives the key and the IV of the response body from those of the
+/// request.
+pub fn response_key_iv(key: &[u8; 16], iv: &[u8; 16]) -> ([u8; 16], [u8; 16]) {
+    let mut resp_key = [0u8; 16];
+    resp_key.copy_from_slice(&Sha256::digest(key)[..16]);
+    let mut resp_iv = [0u8; 16];
+    resp_iv.copy_from_slice(&Sha256::digest(iv)[..16]);
+    (resp_key, resp_iv)
+}
+
+/// Encrypts the auth IDs of a user, which lead the requests. An auth ID is
+/// the time of the request, 4 random bytes and the CRC32 of them, in a
+/// single AES block.
+pub struct AuthIdCipher(Aes128);
+
+impl AuthIdCipher {
+    pub fn new(cmd_key: &[u8; 16]) -> Self {
+        let key = kdf16(cmd_key, &[KDF_AUTH_ID]);
+        AuthIdCipher(Aes128::new(GenericArray::from_slice(&key)))
+    }
+
+    pub fn seal(&self, time: u64) -> [u8; 16] {
+        let mut id = [0u8; 16];
+        id[..8].copy_from_slice(&time.to_be_bytes());
+        rand::thread_rng().fill_bytes(&mut id[8..12]);
+        let crc = crc32fast::hash(&id[..12]);
+        id[12..].copy_from_slice(&crc.to_be_bytes());
+        self.0.encrypt_block(GenericArray::from_mut_slice(&mut id));
+        id
+    }
+
+    /// Decrypts an auth ID, returns the time in it if the checksum matches.
+    pub fn open(&self, id: &[u8; 16]) -> Option<u64> {
+        let mut id = *id;
+        self.0.decrypt_block(GenericArray::from_mut_slice(&mut id));
+        let mut crc = [0u8; 4];
+        crc.copy_from_slice(&id[12..]);
+        if crc32fast::hash(&id[..12]) != u32::from_be_bytes(crc) {
+            return None;
+        }
+        let mut time = [0u8; 8];
+        time.copy_from_slice(&id[..8]);
+        Some(u64::from_be_bytes(time))
+    }
+}
+
+/// Seals `data` with AES-128-GCM, the headers are authenticated along with
+/// the auth ID as the associated data.
+#[cfg(feature = "ring-aead")]
+pub fn seal_aes_128_gcm(key: &[u8], nonce: &[u8], aad: &[u8], data: &[u8]) -> io::Result<Vec<u8>> {
+    use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_128_GCM};
+
+    let key = UnboundKey::new(&AES_128_GCM, key).map_err(|_| crypto_err())?;
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| crypto_err())?;
+    let mut buf = data.to_vec();
+    LessSafeKey::new(key)
+        .seal_in_place_append_tag(nonce, Aad::from(aad), &mut buf)
+        .map_err(|_| crypto_err())?;
+    Ok(buf)
+}
+
+#[cfg(feature = "ring-aead")]
+pub fn open_aes_128_gcm(key: &[u8], nonce: &[u8], aad: &[u8], data: &[u8]) -> io::Result<Vec<u8>> {
+    use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_128_GCM};
+
+    let key = UnboundKey::new(&AES_128_GCM, key).map_err(|_| crypto_err())?;
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| crypto_err())?;
+    let mut buf = data.to_vec();
+    let n = LessSafeKey::new(key)
+        .open_in_place(nonce, Aad::from(aad), &mut buf)
+        .map_err(|_| crypto_err())?
+        .len();
+    buf.truncate(n);
+    Ok(buf)
+}
+
+#[cfg(feature = "openssl-aead")]
+pub fn seal_aes_128_gcm(key: &[u8], nonce: &[u8], aad: &[u8], data: &[u8]) -> io::Result<Vec<u8>> {
+    use openssl::symm;
+
+    let mut tag = [0u8; TAG_LEN];
+    let mut buf = symm::encrypt_aead(
+        symm::Cipher::aes_128_gcm(),
+        key,
+        Some(nonce),
+        aad,
+        data,
+        &mut tag,
+    )
+    .map_err(|_| crypto_err())?;
+    buf.extend_from_slice(&tag);
+    Ok(buf)
+}
+
+#[cfg(feature = "openssl-aead")]
+pub fn open_aes_128_gcm(key: &[u8], nonce: &[u8], aad: &[u8], data: &[u8]) -> io::Result<Vec<u8>> {
+    use openssl::symm;
+
+    if data.len() < TAG_LEN {
+        return Err(crypto_err());
+    }
+    let (data, tag) = data.split_at(data.len() - TAG_LEN);
+    symm::decrypt_aead(
+        symm::Cipher::aes_128_gcm(),
+        key,
+        Some(nonce),
+        aad,
+        data,
+        tag,
+    )
+    .map_err(|_| crypto_err())
+}
+
+/// Derives the key of the body cipher, ChaCha20-Poly1305 takes a 32 bytes
+/// key made of MD5 hashes of the 16 bytes one.
+pub fn body_key(key: &[u8; 16], chacha: bool) -> Vec<u8> {
+    if !chacha {
+        return key.to_vec();
+    }
+    let first = Md5::digest(key);
+    let second = Md5::digest(&first);
+    [&first[..], &second[..]].concat()
+}
+
+/// Nonces of the chunks, a counter followed by bytes 2 to 12 of the body IV.
+pub struct ChunkNonceSequence {
+    nonce: [u8; 12],
+    count: u16,
+}
+
+impl ChunkNonceSequence {
+    pub fn new(iv: &[u8; 16]) -> Self {
+        let mut nonce = [0u8; 12];
+        nonce.copy_from_slice(&iv[..12]);
+        ChunkNonceSequence { nonce, count: 0 }
+    }
+}
+
+impl NonceSequence for ChunkNonceSequence {
+    fn advance(&mut self) -> Result<Vec<u8>> {
+        self.nonce[..2].copy_from_slice(&self.count.to_be_bytes());
+        self.count = self.count.wrapping_add(1);
+        Ok(self.nonce.to_vec())
+    }
+}
+
+/// The SHAKE128 stream of the body IV, which masks the lengths of the chunks
+/// and gives the lengths of their padding.
+pub struct ChunkMask(<Shake128 as ExtendableOutput>::Reader);
+
+impl ChunkMask {
+    pub fn new(iv: &[u8; 16]) -> Self {
+        let mut shake = Shake128::default();
+        shake.update(iv);
+        ChunkMask(shake.finalize_xof())
+    }
+
+    pub fn next_u16(&mut self) -> u16 {
+        let mut buf = [0u8; 2];
+        self.0.read(&mut buf);
+        u16::from_be_bytes(buf)
+    }
+}
+
+/// The FNV-1a hash checking the request header.
+pub fn fnv1a(data: &[u8]) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for b in data {
+        hash ^= *b as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_id() {
+        let key = cmd_key(&[7u8; 16]);
+        let cipher = AuthIdCipher::new(&key);
+        let id = cipher.seal(1_600_000_000);
+        assert_eq!(cipher.open(&id), Some(1_600_000_000));
+        let other = AuthIdCipher::new(&cmd_key(&[8u8; 16]));
+        assert_eq!(other.open(&id), None);
+    }
+
+    #[test]
+    fn test_header_aead() {
+        let key = kdf16(b"key", &[KDF_HEADER_KEY]);
+        let nonce = &kdf(b"key", &[KDF_HEADER_NONCE])[..12];
+        let sealed = seal_aes_128_gcm(&key, nonce, b"aad", b"header").unwrap();
+        assert_eq!(sealed.len(), 6 + TAG_LEN);
+        let opened = open_aes_128_gcm(&key, nonce, b"aad", &sealed).unwrap();
+        assert_eq!(&opened, b"header");
+        assert!(open_aes_128_gcm(&key, nonce, b"other", &sealed).is_err());
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0x811c_9dc5);
+        assert_eq!(fnv1a(b"a"), 0xe40c_292c);
+    }
+}
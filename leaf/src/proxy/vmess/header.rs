@@ -0,0 +1,287 @@
+use std::convert::TryFrom;
+use std::io;
+
+use bytes::{BufMut, BytesMut};
+use rand::{Rng, RngCore};
+
+use crate::session::{SocksAddr, SocksAddrWireType};
+
+use super::crypto::*;
+use super::{Security, OPTION_CHUNK_MASKING, OPTION_CHUNK_STREAM, OPTION_GLOBAL_PADDING, VERSION};
+
+// Length of the auth ID, the encrypted length of the header and the nonce,
+// which come before the encrypted header.
+pub const SEALED_PREFIX_LEN: usize = 16 + 2 + TAG_LEN + 8;
+
+fn header_err(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("vmess {}", msg))
+}
+
+fn decode_len(len: &[u8]) -> io::Result<usize> {
+    match len {
+        [a, b] => Ok(u16::from_be_bytes([*a, *b]) as usize),
+        _ => Err(header_err("invalid header length")),
+    }
+}
+
+/// The header of a request, where the keys of both bodies come from.
+pub struct RequestHeader {
+    pub iv: [u8; 16],
+    pub key: [u8; 16],
+    // Echoed in the response header to tell the response is of the request.
+    pub response_auth: u8,
+    pub options: u8,
+    pub security: Security,
+    pub command: u8,
+    pub destination: SocksAddr,
+}
+
+impl RequestHeader {
+    /// Creates a request with random keys and all options V2Ray sets.
+    pub fn new(command: u8, destination: SocksAddr, security: Security) -> Self {
+        let mut rng = rand::thread_rng();
+        let mut iv = [0u8; 16];
+        rng.fill_bytes(&mut iv);
+        let mut key = [0u8; 16];
+        rng.fill_bytes(&mut key);
+        let mut options = OPTION_CHUNK_STREAM | OPTION_CHUNK_MASKING;
+        if security.is_aead() {
+            options |= OPTION_GLOBAL_PADDING;
+        }
+        RequestHeader {
+            iv,
+            key,
+            response_auth: rng.gen(),
+            options,
+            security,
+            command,
+            destination,
+        }
+    }
+
+    fn encode(&self) -> BytesMut {
+        let mut rng = rand::thread_rng();
+        let padding_len = rng.gen_range(0..16);
+        let mut buf = BytesMut::new();
+        buf.put_u8(VERSION);
+        buf.put_slice(&self.iv);
+        buf.put_slice(&self.key);
+        buf.put_u8(self.response_auth);
+        buf.put_u8(self.options);
+        buf.put_u8((padding_len << 4) | self.security.as_u8());
+        // reserved
+        buf.put_u8(0);
+        buf.put_u8(self.command);
+        self.destination
+            .write_buf(&mut buf, SocksAddrWireType::PortFirst);
+        let mut padding = vec![0u8; padding_len as usize];
+        rng.fill_bytes(&mut padding);
+        buf.put_slice(&padding);
+        let hash = fnv1a(&buf);
+        buf.put_u32(hash);
+        buf
+    }
+
+    /// Decodes the decrypted header.
+    pub fn decode(buf: &[u8]) -> io::Result<Self> {
+        if buf.len() < 38 + 4 {
+            return Err(header_err("header too short"));
+        }
+        let (buf, hash) = buf.split_at(buf.len() - 4);
+        if fnv1a(buf).to_be_bytes() != hash {
+            return Err(header_err("header checksum mismatch"));
+        }
+        if buf[0] != VERSION {
+            return Err(header_err("invalid version"));
+        }
+        let mut iv = [0u8; 16];
+        iv.copy_from_slice(&buf[1..17]);
+        let mut key = [0u8; 16];
+        key.copy_from_slice(&buf[17..33]);
+        let security = Security::from_u8(buf[35] & 0x0f)
+            .ok_or_else(|| header_err("security is not supported"))?;
+        let padding_len = (buf[35] >> 4) as usize;
+        let destination = SocksAddr::try_from((&buf[38..], SocksAddrWireType::PortFirst))?;
+        if buf.len() != 38 + destination.size() + padding_len {
+            return Err(header_err("invalid header length"));
+        }
+        Ok(RequestHeader {
+            iv,
+            key,
+            response_auth: buf[33],
+            options: buf[34],
+            security,
+            command: buf[37],
+            destination,
+        })
+    }
+
+    /// Seals the header with the key of the user, the result is sent as is
+    /// before the body.
+    pub fn seal(&self, cmd_key: &[u8; 16], auth_id: &AuthIdCipher) -> io::Result<BytesMut> {
+        let header = self.encode();
+        let id = auth_id.seal(now());
+        let nonce: [u8; 8] = rand::random();
+        let len = seal_aes_128_gcm(
+            &kdf16(cmd_key, &[KDF_HEADER_LEN_KEY, &id, &nonce]),
+            &kdf(cmd_key, &[KDF_HEADER_LEN_NONCE, &id, &nonce])[..12],
+            &id,
+            &(header.len() as u16).to_be_bytes(),
+        )?;
+        let header = seal_aes_128_gcm(
+            &kdf16(cmd_key, &[KDF_HEADER_KEY, &id, &nonce]),
+            &kdf(cmd_key, &[KDF_HEADER_NONCE, &id, &nonce])[..12],
+            &id,
+            &header,
+        )?;
+        let mut buf = BytesMut::with_capacity(SEALED_PREFIX_LEN + header.len());
+        buf.put_slice(&id);
+        buf.put_slice(&len);
+        buf.put_slice(&nonce);
+        buf.put_slice(&header);
+        Ok(buf)
+    }
+
+    pub fn response_key_iv(&self) -> ([u8; 16], [u8; 16]) {
+        response_key_iv(&self.key, &self.iv)
+    }
+}
+
+/// Opens the length of the header in the prefix of a request, the auth ID
+/// of which has been checked.
+pub fn open_header_len(cmd_key: &[u8; 16], prefix: &[u8; SEALED_PREFIX_LEN]) -> io::Result<usize> {
+    let (id, rest) = prefix.split_at(16);
+    let (len, nonce) = rest.split_at(2 + TAG_LEN);
+    let len = open_aes_128_gcm(
+        &kdf16(cmd_key, &[KDF_HEADER_LEN_KEY, id, nonce]),
+        &kdf(cmd_key, &[KDF_HEADER_LEN_NONCE, id, nonce])[..12],
+        id,
+        len,
+    )?;
+    decode_len(&len)
+}
+
+/// Opens the header following the prefix of a request.
+pub fn open_header(
+    cmd_key: &[u8; 16],
+    prefix: &[u8; SEALED_PREFIX_LEN],
+    header: &[u8],
+) -> io::Result<RequestHeader> {
+    let id = &prefix[..16];
+    let nonce = &prefix[16 + 2 + TAG_LEN..];
+    let header = open_aes_128_gcm(
+        &kdf16(cmd_key, &[KDF_HEADER_KEY, id, nonce]),
+        &kdf(cmd_key, &[KDF_HEADER_NONCE, id, nonce])[..12],
+        id,
+        header,
+    )?;
+    RequestHeader::decode(&header)
+}
+
+/// Seals the response header, which carries no command. The header is
+/// sealed with keys derived from the response body key and IV, its length is
+/// sealed separately before it.
+pub fn seal_response_header(
+    resp_key: &[u8; 16],
+    resp_iv: &[u8; 16],
+    response_auth: u8,
+) -> io::Result<BytesMut> {
+    let header = [response_auth, 0, 0, 0];
+    let len = seal_aes_128_gcm(
+        &kdf16(resp_key, &[KDF_RESP_LEN_KEY]),
+        &kdf(resp_iv, &[KDF_RESP_LEN_IV])[..12],
+        &[],
+        &(header.len() as u16).to_be_bytes(),
+    )?;
+    let header = seal_aes_128_gcm(
+        &kdf16(resp_key, &[KDF_RESP_KEY]),
+        &kdf(resp_iv, &[KDF_RESP_IV])[..12],
+        &[],
+        &header,
+    )?;
+    let mut buf = BytesMut::with_capacity(len.len() + header.len());
+    buf.put_slice(&len);
+    buf.put_slice(&header);
+    Ok(buf)
+}
+
+pub fn open_response_header_len(
+    resp_key: &[u8; 16],
+    resp_iv: &[u8; 16],
+    len: &[u8],
+) -> io::Result<usize> {
+    let len = open_aes_128_gcm(
+        &kdf16(resp_key, &[KDF_RESP_LEN_KEY]),
+        &kdf(resp_iv, &[KDF_RESP_LEN_IV])[..12],
+        &[],
+        len,
+    )?;
+    decode_len(&len)
+}
+
+/// Opens the response header, checks it's of the request.
+pub fn open_response_header(
+    resp_key: &[u8; 16],
+    resp_iv: &[u8; 16],
+    response_auth: u8,
+    header: &[u8],
+) -> io::Result<()> {
+    let header = open_aes_128_gcm(
+        &kdf16(resp_key, &[KDF_RESP_KEY]),
+        &kdf(resp_iv, &[KDF_RESP_IV])[..12],
+        &[],
+        header,
+    )?;
+    if header.first() != Some(&response_auth) {
+        return Err(header_err("unexpected response header"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_open_header() {
+        let key = cmd_key(&[1u8; 16]);
+        let auth_id = AuthIdCipher::new(&key);
+        let req = RequestHeader::new(
+            super::super::COMMAND_TCP,
+            SocksAddr::Domain("example.com".to_string(), 443),
+            Security::ChaCha20Poly1305,
+        );
+        let sealed = req.seal(&key, &auth_id).unwrap();
+        let mut prefix = [0u8; SEALED_PREFIX_LEN];
+        prefix.copy_from_slice(&sealed[..SEALED_PREFIX_LEN]);
+        let mut id = [0u8; 16];
+        id.copy_from_slice(&prefix[..16]);
+        assert!(auth_id.open(&id).unwrap().abs_diff(now()) <= 1);
+        let len = open_header_len(&key, &prefix).unwrap();
+        assert_eq!(len + TAG_LEN, sealed.len() - SEALED_PREFIX_LEN);
+        let opened = open_header(&key, &prefix, &sealed[SEALED_PREFIX_LEN..]).unwrap();
+        assert_eq!(opened.iv, req.iv);
+        assert_eq!(opened.key, req.key);
+        assert_eq!(opened.response_auth, req.response_auth);
+        assert_eq!(opened.options, req.options);
+        assert_eq!(opened.security, Security::ChaCha20Poly1305);
+        assert_eq!(opened.command, super::super::COMMAND_TCP);
+        assert_eq!(
+            opened.destination,
+            SocksAddr::Domain("example.com".to_string(), 443)
+        );
+        // Sealed with the key of another user.
+        assert!(open_header_len(&cmd_key(&[2u8; 16]), &prefix).is_err());
+    }
+
+    #[test]
+    fn test_response_header() {
+        let (key, iv) = response_key_iv(&[3u8; 16], &[4u8; 16]);
+        let sealed = seal_response_header(&key, &iv, 0x42).unwrap();
+        let len = open_response_header_len(&key, &iv, &sealed[..2 + TAG_LEN]).unwrap();
+        assert_eq!(len, 4);
+        let header = &sealed[2 + TAG_LEN..];
+        assert!(open_response_header(&key, &iv, 0x42, header).is_ok());
+        assert!(open_response_header(&key, &iv, 0x43, header).is_err());
+    }
+}
@@ -0,0 +1,3 @@
+mod tcp;
+
+pub use tcp::Handler as TcpHandler;
@@ -0,0 +1,324 @@
+use std::collections::HashSet;
+use std::io;
+use std::mem;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bytes::BytesMut;
+use futures::future::{poll_fn, TryFutureExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    common::uuid::parse_uuid,
+    proxy::*,
+    session::{DatagramSource, Network, Session, SocksAddr},
+};
+
+use super::super::crypto::{cmd_key, now, AuthIdCipher, TAG_LEN};
+use super::super::header::{open_header, open_header_len, seal_response_header, SEALED_PREFIX_LEN};
+use super::super::stream::{server_codec, ChunkReader, ChunkWriter, Stream, MAX_PACKET_LEN};
+use super::super::{COMMAND_TCP, COMMAND_UDP, OPTION_CHUNK_STREAM};
+
+// Maximum difference in seconds of the time in auth IDs from the local time.
+const MAX_TIME_DIFF: u64 = 120;
+
+/// Remembers the auth IDs seen recently, requests reusing any of them are
+/// replays. The IDs are kept in two generations rotated every two time
+/// windows, an ID lives in them as long as its time is acceptable.
+struct AuthIdFilter(Mutex<Generations>);
+
+struct Generations {
+    current: HashSet<[u8; 16]>,
+    previous: HashSet<[u8; 16]>,
+    rotated: u64,
+}
+
+impl AuthIdFilter {
+    fn new() -> Self {
+        AuthIdFilter(Mutex::new(Generations {
+            current: HashSet::new(),
+            previous: HashSet::new(),
+            rotated: now(),
+        }))
+    }
+
+    // Records the auth ID, returns false if it has been seen before.
+    fn check_and_insert(&self, id: &[u8; 16]) -> bool {
+        let mut g = self.0.lock().unwrap();
+        let now = now();
+        if now.saturating_sub(g.rotated) >= 2 * MAX_TIME_DIFF {
+            g.previous = mem::take(&mut g.current);
+            g.rotated = now;
+        }
+        if g.previous.contains(id) {
+            return false;
+        }
+        g.current.insert(*id)
+    }
+}
+
+struct User {
+    uuid: String,
+    cmd_key: [u8; 16],
+    auth_id: AuthIdCipher,
+}
+
+struct Datagram {
+    stream: AnyStream,
+    reader: ChunkReader,
+    writer: ChunkWriter,
+    source: DatagramSource,
+    destination: SocksAddr,
+}
+
+impl InboundDatagram for Datagram {
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn InboundDatagramRecvHalf>,
+        Box<dyn InboundDatagramSendHalf>,
+    ) {
+        let (r, s) = tokio::io::split(self.stream);
+        (
+            Box::new(DatagramRecvHalf {
+                inner: r,
+                reader: self.reader,
+                source: self.source,
+                destination: self.destination,
+            }),
+            Box::new(DatagramSendHalf {
+                inner: s,
+                writer: self.writer,
+            }),
+        )
+    }
+
+    fn into_std(self: Box<Self>) -> io::Result<std::net::UdpSocket> {
+        Err(io::Error::new(io::ErrorKind::Other, "stream transport"))
+    }
+}
+
+struct DatagramRecvHalf<T> {
+    inner: T,
+    reader: ChunkReader,
+    source: DatagramSource,
+    // All packets of the UDP command go to the destination of the request.
+    destination: SocksAddr,
+}
+
+#[async_trait]
+impl<T> InboundDatagramRecvHalf for DatagramRecvHalf<T>
+where
+    T: AsyncRead + Send + Sync + Unpin,
+{
+    async fn recv_from(
+        &mut self,
+        buf: &mut [u8],
+    ) -> ProxyResult<(usize, DatagramSource, SocksAddr)> {
+        // Each chunk is a packet.
+        let packet = poll_fn(|cx| self.reader.poll_read_chunk(cx, &mut self.inner))
+            .map_err(|e| ProxyError::DatagramFatal(e.into()))
+            .await?
+            .ok_or_else(|| ProxyError::DatagramFatal(anyhow!("vmess body ended")))?;
+        if packet.len() > buf.len() {
+            return Err(ProxyError::DatagramFatal(anyhow!("Small buffer")));
+        }
+        buf[..packet.len()].copy_from_slice(&packet);
+        Ok((packet.len(), self.source, self.destination.clone()))
+    }
+}
+
+struct DatagramSendHalf<T> {
+    inner: T,
+    writer: ChunkWriter,
+}
+
+#[async_trait]
+impl<T> InboundDatagramSendHalf for DatagramSendHalf<T>
+where
+    T: AsyncWrite + Send + Sync + Unpin,
+{
+    async fn send_to(
+        &mut self,
+        buf: &[u8],
+        _src_addr: &SocksAddr,
+        _dst_addr: &SocketAddr,
+    ) -> io::Result<usize> {
+        if buf.len() > MAX_PACKET_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "packet too large",
+            ));
+        }
+        let mut data = BytesMut::new();
+        self.writer.seal(buf, &mut data)?;
+        self.inner.write_all(&data).map_ok(|_| buf.len()).await
+    }
+}
+
+pub struct Handler {
+    users: Vec<User>,
+    filter: AuthIdFilter,
+}
+
+impl Handler {
+    pub fn new(uuids: Vec<String>) -> io::Result<Self> {
+        let mut users = Vec::new();
+        for uuid in uuids {
+            let id = parse_uuid(&uuid).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("invalid uuid {}", uuid),
+                )
+            })?;
+            let cmd_key = cmd_key(&id);
+            users.push(User {
+                uuid,
+                cmd_key,
+                auth_id: AuthIdCipher::new(&cmd_key),
+            });
+        }
+        Ok(Handler {
+            users,
+            filter: AuthIdFilter::new(),
+        })
+    }
+
+    // Finds the user of the auth ID, which is checked against the time and
+    // the auth IDs seen before.
+    fn authenticate(&self, id: &[u8; 16]) -> io::Result<&User> {
+        let (user, time) = self
+            .users
+            .iter()
+            .find_map(|u| u.auth_id.open(id).map(|time| (u, time)))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "invalid auth id"))?;
+        if time.abs_diff(now()) > MAX_TIME_DIFF {
+            return Err(io::Error::new(io::ErrorKind::Other, "expired auth id"));
+        }
+        if !self.filter.check_and_insert(id) {
+            return Err(io::Error::new(io::ErrorKind::Other, "replayed auth id"));
+        }
+        Ok(user)
+    }
+}
+
+#[async_trait]
+impl TcpInboundHandler for Handler {
+    type TStream = AnyStream;
+    type TDatagram = AnyInboundDatagram;
+
+    async fn handle<'a>(
+        &'a self,
+        mut sess: Session,
+        mut stream: Self::TStream,
+    ) -> std::io::Result<InboundTransport<Self::TStream, Self::TDatagram>> {
+        let mut prefix = [0u8; SEALED_PREFIX_LEN];
+        stream.read_exact(&mut prefix).await?;
+        let mut id = [0u8; 16];
+        id.copy_from_slice(&prefix[..16]);
+        let user = self.authenticate(&id)?;
+        let len = open_header_len(&user.cmd_key, &prefix)?;
+        let mut header = vec![0u8; len + TAG_LEN];
+        stream.read_exact(&mut header).await?;
+        let req = open_header(&user.cmd_key, &prefix, &header)?;
+        if req.options & OPTION_CHUNK_STREAM == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "vmess without chunk stream is not supported",
+            ));
+        }
+        if req.command != COMMAND_TCP && req.command != COMMAND_UDP {
+            return Err(io::Error::new(io::ErrorKind::Other, "invalid command"));
+        }
+        sess.destination = req.destination.clone();
+        sess.user = Some(user.uuid.clone());
+        let (resp_key, resp_iv) = req.response_key_iv();
+        stream
+            .write_all(&seal_response_header(
+                &resp_key,
+                &resp_iv,
+                req.response_auth,
+            )?)
+            .await?;
+        let (reader, writer) = server_codec(&req)?;
+        if req.command == COMMAND_TCP {
+            return Ok(InboundTransport::Stream(
+                Box::new(Stream::new(stream, reader, writer)),
+                sess,
+            ));
+        }
+        sess.network = Network::Udp;
+        let source = DatagramSource::new(sess.source, sess.stream_id);
+        Ok(InboundTransport::Datagram(
+            Box::new(Datagram {
+                stream,
+                reader,
+                writer,
+                source,
+                destination: sess.destination.clone(),
+            }),
+            Some(sess),
+        ))
+    }
+}
+
+#[cfg(all(test, feature = "outbound-vmess"))]
+mod tests {
+    use super::super::super::outbound;
+    use super::super::super::Security;
+    use super::*;
+
+    #[test]
+    fn test_outbound_to_inbound() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let uuid = "b831381d-6324-4d53-ad4f-8cda48b30811";
+            let inbound = Handler::new(vec![
+                "0d4c4f4a-6b5e-4e36-9a59-2a5cf1f3a8f1".to_string(),
+                uuid.to_string(),
+            ])
+            .unwrap();
+            let outbound = outbound::TcpHandler::new(
+                "127.0.0.1".to_string(),
+                10086,
+                parse_uuid(uuid).unwrap(),
+                Security::ChaCha20Poly1305,
+            );
+            let (client, server) = tokio::io::duplex(1024);
+            let server = tokio::spawn(async move {
+                let (mut stream, sess) =
+                    match inbound.handle(Session::default(), Box::new(server)).await {
+                        Ok(InboundTransport::Stream(stream, sess)) => (stream, sess),
+                        _ => panic!("unexpected inbound transport"),
+                    };
+                assert_eq!(
+                    sess.destination,
+                    SocksAddr::Domain("example.com".to_string(), 80)
+                );
+                assert_eq!(sess.user.as_deref(), Some(uuid));
+                let mut buf = [0u8; 5];
+                stream.read_exact(&mut buf).await.unwrap();
+                assert_eq!(&buf, b"hello");
+                stream.write_all(b"world").await.unwrap();
+                stream.shutdown().await.unwrap();
+            });
+            let sess = Session {
+                destination: SocksAddr::Domain("example.com".to_string(), 80),
+                ..Default::default()
+            };
+            let mut stream = TcpOutboundHandler::handle(&outbound, &sess, Some(Box::new(client)))
+                .await
+                .unwrap();
+            stream.write_all(b"hello").await.unwrap();
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"world");
+            server.await.unwrap();
+        });
+    }
+}
@@ -0,0 +1,88 @@
+//! VMess, https://www.v2fly.org/en_US/developer/protocols/vmess.html
+//!
+//! Only the AEAD header of V2Ray 4.28 and later is spoken, requests with the
+//! legacy MD5 header are rejected. Bodies are chunk streams sealed with
+//! AES-128-GCM or ChaCha20-Poly1305, or sent in the clear with `none`, the
+//! lengths of the chunks are masked and padded as V2Ray does. UDP goes with
+//! the UDP command, a chunk for each packet.
+
+use std::io;
+use std::str::FromStr;
+
+#[cfg(feature = "inbound-vmess")]
+pub mod inbound;
+#[cfg(feature = "outbound-vmess")]
+pub mod outbound;
+
+mod crypto;
+mod header;
+mod stream;
+
+pub const VERSION: u8 = 0x01;
+
+pub const COMMAND_TCP: u8 = 0x01;
+pub const COMMAND_UDP: u8 = 0x02;
+
+// Lengths of the chunks are framed.
+pub const OPTION_CHUNK_STREAM: u8 = 0x01;
+// Lengths of the chunks are masked by a SHAKE128 stream of the body IV.
+pub const OPTION_CHUNK_MASKING: u8 = 0x04;
+// Chunks are padded, the length of the padding is drawn from the same
+// stream as the masks.
+pub const OPTION_GLOBAL_PADDING: u8 = 0x08;
+
+/// The cipher of the body.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Security {
+    Aes128Gcm,
+    ChaCha20Poly1305,
+    None,
+}
+
+impl Security {
+    fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0x03 => Some(Security::Aes128Gcm),
+            0x04 => Some(Security::ChaCha20Poly1305),
+            0x05 => Some(Security::None),
+            _ => None,
+        }
+    }
+
+    fn as_u8(self) -> u8 {
+        match self {
+            Security::Aes128Gcm => 0x03,
+            Security::ChaCha20Poly1305 => 0x04,
+            Security::None => 0x05,
+        }
+    }
+
+    fn is_aead(self) -> bool {
+        self != Security::None
+    }
+}
+
+impl FromStr for Security {
+    type Err = io::Error;
+
+    /// Parses the security of the outbound settings, `auto` picks AES-128-GCM
+    /// on CPUs with AES instructions as V2Ray does.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "aes-128-gcm" => Ok(Security::Aes128Gcm),
+            "chacha20-poly1305" | "chacha20-ietf-poly1305" => Ok(Security::ChaCha20Poly1305),
+            "none" => Ok(Security::None),
+            "" | "auto" => {
+                if cfg!(any(target_arch = "x86_64", target_arch = "aarch64")) {
+                    Ok(Security::Aes128Gcm)
+                } else {
+                    Ok(Security::ChaCha20Poly1305)
+                }
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported vmess security {}", s),
+            )),
+        }
+    }
+}
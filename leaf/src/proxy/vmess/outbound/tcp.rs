@@ -0,0 +1,56 @@
+use std::io;
+
+use async_trait::async_trait;
+use tokio::io::AsyncWriteExt;
+
+use crate::{proxy::*, session::Session};
+
+use super::super::crypto::{cmd_key, AuthIdCipher};
+use super::super::header::RequestHeader;
+use super::super::stream::{client_codec, Stream};
+use super::super::{Security, COMMAND_TCP};
+
+pub struct Handler {
+    address: String,
+    port: u16,
+    cmd_key: [u8; 16],
+    auth_id: AuthIdCipher,
+    security: Security,
+}
+
+impl Handler {
+    pub fn new(address: String, port: u16, id: [u8; 16], security: Security) -> Self {
+        let cmd_key = cmd_key(&id);
+        Handler {
+            address,
+            port,
+            cmd_key,
+            auth_id: AuthIdCipher::new(&cmd_key),
+            security,
+        }
+    }
+}
+
+#[async_trait]
+impl TcpOutboundHandler for Handler {
+    type Stream = AnyStream;
+
+    fn connect_addr(&self) -> Option<OutboundConnect> {
+        Some(OutboundConnect::Proxy(self.address.clone(), self.port))
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        stream: Option<Self::Stream>,
+    ) -> io::Result<Self::Stream> {
+        let mut stream =
+            stream.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "invalid input"))?;
+        let req = RequestHeader::new(COMMAND_TCP, sess.destination.clone(), self.security);
+        stream
+            .write_all(&req.seal(&self.cmd_key, &self.auth_id)?)
+            .await?;
+        let (reader, writer) = client_codec(&req)?;
+        Ok(Box::new(Stream::new(stream, reader, writer)))
+    }
+}
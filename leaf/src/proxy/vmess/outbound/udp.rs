@@ -0,0 +1,155 @@
+use std::io;
+
+use async_trait::async_trait;
+use bytes::BytesMut;
+use futures::future::{poll_fn, TryFutureExt};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+
+use crate::{
+    proxy::*,
+    session::{Session, SocksAddr},
+};
+
+use super::super::crypto::{cmd_key, AuthIdCipher};
+use super::super::header::RequestHeader;
+use super::super::stream::{client_codec, ChunkReader, ChunkWriter, MAX_PACKET_LEN};
+use super::super::{Security, COMMAND_UDP};
+
+pub struct Handler {
+    address: String,
+    port: u16,
+    cmd_key: [u8; 16],
+    auth_id: AuthIdCipher,
+    security: Security,
+}
+
+impl Handler {
+    pub fn new(address: String, port: u16, id: [u8; 16], security: Security) -> Self {
+        let cmd_key = cmd_key(&id);
+        Handler {
+            address,
+            port,
+            cmd_key,
+            auth_id: AuthIdCipher::new(&cmd_key),
+            security,
+        }
+    }
+}
+
+#[async_trait]
+impl UdpOutboundHandler for Handler {
+    type UStream = AnyStream;
+    type Datagram = AnyOutboundDatagram;
+
+    fn connect_addr(&self) -> Option<OutboundConnect> {
+        Some(OutboundConnect::Proxy(self.address.clone(), self.port))
+    }
+
+    fn transport_type(&self) -> DatagramTransportType {
+        DatagramTransportType::Stream
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        transport: Option<OutboundTransport<Self::UStream, Self::Datagram>>,
+    ) -> io::Result<Self::Datagram> {
+        let mut stream = if let Some(OutboundTransport::Stream(stream)) = transport {
+            stream
+        } else {
+            return Err(io::Error::new(io::ErrorKind::Other, "invalid input"));
+        };
+        let req = RequestHeader::new(COMMAND_UDP, sess.destination.clone(), self.security);
+        stream
+            .write_all(&req.seal(&self.cmd_key, &self.auth_id)?)
+            .await?;
+        let (reader, writer) = client_codec(&req)?;
+        Ok(Box::new(Datagram {
+            stream,
+            reader,
+            writer,
+            destination: sess.destination.clone(),
+        }))
+    }
+}
+
+pub struct Datagram<S> {
+    stream: S,
+    reader: ChunkReader,
+    writer: ChunkWriter,
+    destination: SocksAddr,
+}
+
+impl<S> OutboundDatagram for Datagram<S>
+where
+    S: 'static + AsyncRead + AsyncWrite + Unpin + Send + Sync,
+{
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn OutboundDatagramRecvHalf>,
+        Box<dyn OutboundDatagramSendHalf>,
+    ) {
+        let (r, w) = tokio::io::split(self.stream);
+        (
+            Box::new(DatagramRecvHalf {
+                inner: r,
+                reader: self.reader,
+                destination: self.destination,
+            }),
+            Box::new(DatagramSendHalf {
+                inner: w,
+                writer: self.writer,
+            }),
+        )
+    }
+}
+
+pub struct DatagramRecvHalf<T> {
+    inner: ReadHalf<T>,
+    reader: ChunkReader,
+    // All packets of the UDP command are from the destination of the
+    // request.
+    destination: SocksAddr,
+}
+
+#[async_trait]
+impl<T> OutboundDatagramRecvHalf for DatagramRecvHalf<T>
+where
+    T: AsyncRead + AsyncWrite + Send + Sync,
+{
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocksAddr)> {
+        // Each chunk is a packet.
+        let packet = poll_fn(|cx| self.reader.poll_read_chunk(cx, &mut self.inner))
+            .await?
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        if packet.len() > buf.len() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "Small buffer"));
+        }
+        buf[..packet.len()].copy_from_slice(&packet);
+        Ok((packet.len(), self.destination.clone()))
+    }
+}
+
+pub struct DatagramSendHalf<T> {
+    inner: WriteHalf<T>,
+    writer: ChunkWriter,
+}
+
+#[async_trait]
+impl<T> OutboundDatagramSendHalf for DatagramSendHalf<T>
+where
+    T: AsyncRead + AsyncWrite + Send + Sync,
+{
+    async fn send_to(&mut self, buf: &[u8], _target: &SocksAddr) -> io::Result<usize> {
+        if buf.len() > MAX_PACKET_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "packet too large",
+            ));
+        }
+        let mut data = BytesMut::new();
+        self.writer.seal(buf, &mut data)?;
+        self.inner.write_all(&data).map_ok(|_| buf.len()).await
+    }
+}
@@ -0,0 +1,440 @@
+use std::cmp::min;
+use std::io;
+use std::mem;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{Buf, BufMut, BytesMut};
+use futures::ready;
+use rand::RngCore;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::common::crypto::{
+    aead::{AeadCipher, AeadDecryptor, AeadEncryptor},
+    Cipher, Decryptor, Encryptor,
+};
+
+use super::crypto::{body_key, ChunkMask, ChunkNonceSequence, TAG_LEN};
+use super::header::{open_response_header, open_response_header_len, RequestHeader};
+use super::{Security, OPTION_CHUNK_MASKING, OPTION_GLOBAL_PADDING};
+
+// Payloads are split to fit the 8192 bytes buffers of V2Ray along with the
+// length, the tag and the padding.
+const MAX_PAYLOAD_LEN: usize = 8192 - 2 - TAG_LEN - 64;
+
+// Maximum payload of a chunk carrying a UDP packet, the length of the chunk
+// is a u16.
+pub const MAX_PACKET_LEN: usize = u16::MAX as usize - TAG_LEN - 64;
+
+fn crypto_err(e: anyhow::Error) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("vmess crypto failed: {}", e),
+    )
+}
+
+fn cipher(security: Security) -> io::Result<Option<AeadCipher>> {
+    let name = match security {
+        Security::Aes128Gcm => "aes-128-gcm",
+        Security::ChaCha20Poly1305 => "chacha20-poly1305",
+        Security::None => return Ok(None),
+    };
+    AeadCipher::new(name).map(Some).map_err(crypto_err)
+}
+
+// The masks of the lengths of the chunks and the lengths of their padding.
+struct Framing {
+    mask: Option<ChunkMask>,
+    padding: bool,
+}
+
+impl Framing {
+    fn new(security: Security, iv: &[u8; 16], options: u8) -> Self {
+        let mask = if options & OPTION_CHUNK_MASKING != 0 {
+            Some(ChunkMask::new(iv))
+        } else {
+            None
+        };
+        // V2Ray pads only the AEAD bodies, by the same stream as the masks.
+        let padding = mask.is_some() && options & OPTION_GLOBAL_PADDING != 0 && security.is_aead();
+        Framing { mask, padding }
+    }
+
+    // Returns the length of the padding and the mask of the length of the
+    // next chunk, in the order they're drawn.
+    fn next(&mut self) -> (usize, u16) {
+        let mask = match self.mask.as_mut() {
+            Some(mask) => mask,
+            None => return (0, 0),
+        };
+        let padding = if self.padding {
+            (mask.next_u16() % 64) as usize
+        } else {
+            0
+        };
+        (padding, mask.next_u16())
+    }
+}
+
+/// Seals the chunks of a body.
+pub struct ChunkWriter {
+    enc: Option<AeadEncryptor<ChunkNonceSequence>>,
+    framing: Framing,
+}
+
+impl ChunkWriter {
+    pub fn new(security: Security, key: &[u8; 16], iv: &[u8; 16], options: u8) -> io::Result<Self> {
+        let enc = match cipher(security)? {
+            Some(c) => Some(
+                c.encryptor(
+                    &body_key(key, security == Security::ChaCha20Poly1305),
+                    ChunkNonceSequence::new(iv),
+                )
+                .map_err(crypto_err)?,
+            ),
+            None => None,
+        };
+        Ok(ChunkWriter {
+            enc,
+            framing: Framing::new(security, iv, options),
+        })
+    }
+
+    /// Appends a chunk of `data` to `buf`, an empty chunk ends the body.
+    pub fn seal(&mut self, data: &[u8], buf: &mut BytesMut) -> io::Result<()> {
+        let (padding, mask) = self.framing.next();
+        let overhead = if self.enc.is_some() { TAG_LEN } else { 0 };
+        buf.put_u16((data.len() + overhead + padding) as u16 ^ mask);
+        let mut chunk = buf.split_off(buf.len());
+        chunk.put_slice(data);
+        if let Some(enc) = self.enc.as_mut() {
+            enc.encrypt(&mut chunk).map_err(crypto_err)?;
+        }
+        buf.unsplit(chunk);
+        let start = buf.len();
+        buf.resize(start + padding, 0);
+        rand::thread_rng().fill_bytes(&mut buf[start..]);
+        Ok(())
+    }
+}
+
+enum ReadState {
+    ResponseLength,
+    ResponseHeader(usize),
+    Length,
+    // The length of the chunk and of the padding in it.
+    Chunk(usize, usize),
+    Eof,
+}
+
+// The response header the client checks before the body.
+struct ResponseHeader {
+    key: [u8; 16],
+    iv: [u8; 16],
+    auth: u8,
+}
+
+/// Opens the chunks of a body.
+pub struct ChunkReader {
+    dec: Option<AeadDecryptor<ChunkNonceSequence>>,
+    framing: Framing,
+    response: Option<ResponseHeader>,
+    state: ReadState,
+    buf: BytesMut,
+}
+
+impl ChunkReader {
+    pub fn new(security: Security, key: &[u8; 16], iv: &[u8; 16], options: u8) -> io::Result<Self> {
+        let dec = match cipher(security)? {
+            Some(c) => Some(
+                c.decryptor(
+                    &body_key(key, security == Security::ChaCha20Poly1305),
+                    ChunkNonceSequence::new(iv),
+                )
+                .map_err(crypto_err)?,
+            ),
+            None => None,
+        };
+        Ok(ChunkReader {
+            dec,
+            framing: Framing::new(security, iv, options),
+            response: None,
+            state: ReadState::Length,
+            buf: BytesMut::new(),
+        })
+    }
+
+    /// Reads the response header before the body, which the key and the IV
+    /// of the response body seal and the auth of the request leads.
+    pub fn with_response_header(mut self, key: &[u8; 16], iv: &[u8; 16], auth: u8) -> Self {
+        self.response = Some(ResponseHeader {
+            key: *key,
+            iv: *iv,
+            auth,
+        });
+        self.state = ReadState::ResponseLength;
+        self
+    }
+
+    // Reads until there are `n` bytes in the buffer, returns false if the
+    // reader is at EOF before any of them.
+    fn poll_fill<R: AsyncRead + Unpin>(
+        &mut self,
+        cx: &mut Context<'_>,
+        r: &mut R,
+        n: usize,
+    ) -> Poll<io::Result<bool>> {
+        while self.buf.len() < n {
+            let start = self.buf.len();
+            self.buf.resize(n, 0);
+            let mut read_buf = ReadBuf::new(&mut self.buf[start..]);
+            let res = Pin::new(&mut *r).poll_read(cx, &mut read_buf);
+            let read = read_buf.filled().len();
+            self.buf.truncate(start + read);
+            ready!(res)?;
+            if read == 0 {
+                if start == 0 {
+                    return Poll::Ready(Ok(false));
+                }
+                return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+            }
+        }
+        Poll::Ready(Ok(true))
+    }
+
+    /// Reads the payload of the next chunk, `None` at the end of the body.
+    pub fn poll_read_chunk<R: AsyncRead + Unpin>(
+        &mut self,
+        cx: &mut Context<'_>,
+        r: &mut R,
+    ) -> Poll<io::Result<Option<BytesMut>>> {
+        loop {
+            match self.state {
+                ReadState::ResponseLength => {
+                    if !ready!(self.poll_fill(cx, r, 2 + TAG_LEN))? {
+                        return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+                    }
+                    let resp = self.response.as_ref().unwrap();
+                    let len = open_response_header_len(&resp.key, &resp.iv, &self.buf)?;
+                    self.buf.clear();
+                    self.state = ReadState::ResponseHeader(len + TAG_LEN);
+                }
+                ReadState::ResponseHeader(n) => {
+                    if !ready!(self.poll_fill(cx, r, n))? {
+                        return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+                    }
+                    let resp = self.response.as_ref().unwrap();
+                    open_response_header(&resp.key, &resp.iv, resp.auth, &self.buf)?;
+                    self.buf.clear();
+                    self.state = ReadState::Length;
+                }
+                ReadState::Length => {
+                    // The peer may close without the empty chunk.
+                    if !ready!(self.poll_fill(cx, r, 2))? {
+                        self.state = ReadState::Eof;
+                        continue;
+                    }
+                    let (padding, mask) = self.framing.next();
+                    let size = (self.buf.get_u16() ^ mask) as usize;
+                    self.buf.clear();
+                    let overhead = if self.dec.is_some() { TAG_LEN } else { 0 };
+                    if size < overhead + padding {
+                        return Poll::Ready(Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "invalid vmess chunk length",
+                        )));
+                    }
+                    self.state = if size == overhead + padding {
+                        ReadState::Eof
+                    } else {
+                        ReadState::Chunk(size, padding)
+                    };
+                }
+                ReadState::Chunk(size, padding) => {
+                    if !ready!(self.poll_fill(cx, r, size))? {
+                        return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+                    }
+                    let mut chunk = self.buf.split_to(size - padding);
+                    self.buf.clear();
+                    if let Some(dec) = self.dec.as_mut() {
+                        dec.decrypt(&mut chunk).map_err(crypto_err)?;
+                        chunk.truncate(chunk.len() - TAG_LEN);
+                    }
+                    self.state = ReadState::Length;
+                    return Poll::Ready(Ok(Some(chunk)));
+                }
+                ReadState::Eof => return Poll::Ready(Ok(None)),
+            }
+        }
+    }
+}
+
+/// Creates the reader and the writer of the bodies of a client, the response
+/// header is checked before the response body.
+pub fn client_codec(req: &RequestHeader) -> io::Result<(ChunkReader, ChunkWriter)> {
+    let (key, iv) = req.response_key_iv();
+    let reader = ChunkReader::new(req.security, &key, &iv, req.options)?.with_response_header(
+        &key,
+        &iv,
+        req.response_auth,
+    );
+    let writer = ChunkWriter::new(req.security, &req.key, &req.iv, req.options)?;
+    Ok((reader, writer))
+}
+
+/// Creates the reader and the writer of the bodies of a server, which has
+/// sent the response header.
+pub fn server_codec(req: &RequestHeader) -> io::Result<(ChunkReader, ChunkWriter)> {
+    let (key, iv) = req.response_key_iv();
+    let reader = ChunkReader::new(req.security, &req.key, &req.iv, req.options)?;
+    let writer = ChunkWriter::new(req.security, &key, &iv, req.options)?;
+    Ok((reader, writer))
+}
+
+/// A VMess body in both directions, the request or the response header has
+/// been sent before.
+pub struct Stream<T> {
+    inner: T,
+    reader: ChunkReader,
+    writer: ChunkWriter,
+    read_buf: BytesMut,
+    write_buf: BytesMut,
+    // Length of the payload sealed in the write buffer.
+    write_len: usize,
+    eof_sealed: bool,
+}
+
+impl<T> Stream<T> {
+    pub fn new(inner: T, reader: ChunkReader, writer: ChunkWriter) -> Self {
+        Stream {
+            inner,
+            reader,
+            writer,
+            read_buf: BytesMut::new(),
+            write_buf: BytesMut::new(),
+            write_len: 0,
+            eof_sealed: false,
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> Stream<T> {
+    fn poll_write_buf(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.write_buf.is_empty() {
+            let n = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.write_buf))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.write_buf.advance(n);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Stream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let me = &mut *self;
+        loop {
+            if !me.read_buf.is_empty() {
+                let n = min(me.read_buf.len(), buf.remaining());
+                buf.put_slice(&me.read_buf[..n]);
+                me.read_buf.advance(n);
+                return Poll::Ready(Ok(()));
+            }
+            match ready!(me.reader.poll_read_chunk(cx, &mut me.inner))? {
+                Some(chunk) => me.read_buf = chunk,
+                None => return Poll::Ready(Ok(())),
+            }
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Stream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        // A chunk sealed by a previous call which has returned pending is of
+        // the same data.
+        if me.write_len == 0 {
+            let n = min(buf.len(), MAX_PAYLOAD_LEN);
+            if n == 0 {
+                return Poll::Ready(Ok(0));
+            }
+            me.writer.seal(&buf[..n], &mut me.write_buf)?;
+            me.write_len = n;
+        }
+        ready!(me.poll_write_buf(cx))?;
+        Poll::Ready(Ok(mem::take(&mut me.write_len)))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_buf(cx))?;
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let me = &mut *self;
+        ready!(me.poll_write_buf(cx))?;
+        if !me.eof_sealed {
+            me.writer.seal(&[], &mut me.write_buf)?;
+            me.eof_sealed = true;
+            ready!(me.poll_write_buf(cx))?;
+        }
+        Pin::new(&mut me.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::super::OPTION_CHUNK_STREAM;
+    use super::*;
+
+    #[test]
+    fn test_chunk_stream() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let key = [1u8; 16];
+            let iv = [2u8; 16];
+            let options = OPTION_CHUNK_STREAM | OPTION_CHUNK_MASKING | OPTION_GLOBAL_PADDING;
+            for security in [
+                Security::Aes128Gcm,
+                Security::ChaCha20Poly1305,
+                Security::None,
+            ] {
+                let (a, b) = tokio::io::duplex(1024);
+                let mut a = Stream::new(
+                    a,
+                    ChunkReader::new(security, &key, &iv, options).unwrap(),
+                    ChunkWriter::new(security, &key, &iv, options).unwrap(),
+                );
+                let mut b = Stream::new(
+                    b,
+                    ChunkReader::new(security, &key, &iv, options).unwrap(),
+                    ChunkWriter::new(security, &key, &iv, options).unwrap(),
+                );
+                // Split into chunks.
+                let data = vec![7u8; 3 * MAX_PAYLOAD_LEN];
+                let sent = data.clone();
+                let write = tokio::spawn(async move {
+                    a.write_all(&sent).await.unwrap();
+                    a.shutdown().await.unwrap();
+                });
+                let mut buf = Vec::new();
+                b.read_to_end(&mut buf).await.unwrap();
+                assert_eq!(buf, data);
+                write.await.unwrap();
+            }
+        });
+    }
+}
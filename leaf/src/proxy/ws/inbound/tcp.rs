@@ -1,32 +1,69 @@
 use std::io::{self, ErrorKind};
 
 use async_trait::async_trait;
+use bytes::Bytes;
 use futures::TryFutureExt;
 use tokio_tungstenite::accept_hdr_async;
 use tungstenite::handshake::server::{Callback, ErrorResponse, Request, Response};
 
-use crate::{proxy::*, session::Session};
+use crate::{common::io::PrefixedStream, proxy::*, session::Session};
 
 use super::stream;
 
 struct SimpleCallback<'a> {
     sess: &'a mut Session,
     path: &'a str,
+    early_data_header_name: &'a str,
+    early_data: &'a mut Option<Vec<u8>>,
 }
 
 impl<'a> SimpleCallback<'a> {
-    pub fn new(sess: &'a mut Session, path: &'a str) -> Self {
-        Self { sess, path }
+    pub fn new(
+        sess: &'a mut Session,
+        path: &'a str,
+        early_data_header_name: &'a str,
+        early_data: &'a mut Option<Vec<u8>>,
+    ) -> Self {
+        Self {
+            sess,
+            path,
+            early_data_header_name,
+            early_data,
+        }
     }
 }
 
+fn error_response(status: ::http::StatusCode) -> ErrorResponse {
+    ::http::response::Response::builder()
+        .status(status)
+        .body(None)
+        .unwrap()
+}
+
 impl<'a> Callback for SimpleCallback<'a> {
-    fn on_request(self, request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+    fn on_request(
+        self,
+        request: &Request,
+        mut response: Response,
+    ) -> Result<Response, ErrorResponse> {
         if request.uri().path() != self.path {
-            return Err(http::response::Response::builder()
-                .status(http::StatusCode::NOT_FOUND)
-                .body(None)
-                .unwrap());
+            return Err(error_response(::http::StatusCode::NOT_FOUND));
+        }
+        if !self.early_data_header_name.is_empty() {
+            if let Some(value) = request.headers().get(self.early_data_header_name) {
+                let data = base64::decode_config(value.as_bytes(), base64::URL_SAFE_NO_PAD)
+                    .map_err(|_| error_response(::http::StatusCode::BAD_REQUEST))?;
+                self.early_data.replace(data);
+                // Browsers require the protocol to be selected.
+                if self
+                    .early_data_header_name
+                    .eq_ignore_ascii_case("Sec-WebSocket-Protocol")
+                {
+                    response
+                        .headers_mut()
+                        .insert("Sec-WebSocket-Protocol", value.clone());
+                }
+            }
         }
         if let Some(Ok(forwarded)) = request
             .headers()
@@ -50,11 +87,15 @@ impl<'a> Callback for SimpleCallback<'a> {
 
 pub struct Handler {
     path: String,
+    early_data_header_name: String,
 }
 
 impl Handler {
-    pub fn new(path: String) -> Self {
-        Handler { path }
+    pub fn new(path: String, early_data_header_name: String) -> Self {
+        Handler {
+            path,
+            early_data_header_name,
+        }
     }
 }
 
@@ -68,15 +109,23 @@ impl TcpInboundHandler for Handler {
         mut sess: Session,
         stream: Self::TStream,
     ) -> std::io::Result<InboundTransport<Self::TStream, Self::TDatagram>> {
-        Ok(InboundTransport::Stream(
-            Box::new(stream::WebSocketToStream::new(
-                accept_hdr_async(stream, SimpleCallback::new(&mut sess, &self.path))
-                    .map_err(|e| {
-                        io::Error::new(ErrorKind::Other, format!("accept ws failed: {}", e))
-                    })
-                    .await?,
-            )),
-            sess,
-        ))
+        let mut early_data = None;
+        let callback = SimpleCallback::new(
+            &mut sess,
+            &self.path,
+            &self.early_data_header_name,
+            &mut early_data,
+        );
+        let stream = stream::WebSocketToStream::new(
+            accept_hdr_async(stream, callback)
+                .map_err(|e| io::Error::new(ErrorKind::Other, format!("accept ws failed: {}", e)))
+                .await?,
+        );
+        let stream: AnyStream = if let Some(early_data) = early_data {
+            Box::new(PrefixedStream::new(stream, Bytes::from(early_data)))
+        } else {
+            Box::new(stream)
+        };
+        Ok(InboundTransport::Stream(stream, sess))
     }
 }
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::ready;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::proxy::AnyStream;
+
+use super::tcp::connect;
+
+struct Pending {
+    stream: AnyStream,
+    url: String,
+    headers: HashMap<String, String>,
+    header_name: String,
+}
+
+enum State {
+    Pending(Option<Pending>),
+    Connecting(Pin<Box<dyn Future<Output = io::Result<AnyStream>> + Send + Sync>>),
+    Connected(AnyStream),
+}
+
+/// A WebSocket stream which makes the handshake on the first write, the data
+/// written, up to `max_early_data` bytes, is sent in a header of the request.
+pub struct EarlyDataStream {
+    state: State,
+    max_early_data: usize,
+    // The size of the early data sent, returned by the write making the
+    // handshake once it's done.
+    early_data: Option<usize>,
+}
+
+impl EarlyDataStream {
+    pub fn new(
+        stream: AnyStream,
+        url: String,
+        headers: HashMap<String, String>,
+        max_early_data: usize,
+        header_name: String,
+    ) -> Self {
+        EarlyDataStream {
+            state: State::Pending(Some(Pending {
+                stream,
+                url,
+                headers,
+                header_name,
+            })),
+            max_early_data,
+            early_data: None,
+        }
+    }
+
+    fn start(&mut self, early_data: &[u8]) {
+        if let State::Pending(pending) = &mut self.state {
+            let p = pending.take().unwrap();
+            let early_data = if !early_data.is_empty() {
+                self.early_data = Some(early_data.len());
+                Some((p.header_name, early_data.to_vec()))
+            } else {
+                None
+            };
+            self.state =
+                State::Connecting(Box::pin(connect(p.stream, p.url, p.headers, early_data)));
+        }
+    }
+
+    fn poll_connected(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        loop {
+            match &mut self.state {
+                State::Pending(_) => self.start(&[]),
+                State::Connecting(f) => {
+                    let stream = ready!(f.as_mut().poll(cx))?;
+                    self.state = State::Connected(stream);
+                }
+                State::Connected(_) => return Poll::Ready(Ok(())),
+            }
+        }
+    }
+
+    fn stream(&mut self) -> Pin<&mut AnyStream> {
+        match &mut self.state {
+            State::Connected(stream) => Pin::new(stream),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl AsyncRead for EarlyDataStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        ready!(self.poll_connected(cx))?;
+        self.stream().poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for EarlyDataStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        if let State::Pending(_) = me.state {
+            if buf.is_empty() {
+                return Poll::Ready(Ok(0));
+            }
+            let n = buf.len().min(me.max_early_data);
+            me.start(&buf[..n]);
+        }
+        ready!(me.poll_connected(cx))?;
+        if let Some(n) = me.early_data.take() {
+            return Poll::Ready(Ok(n));
+        }
+        me.stream().poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if let State::Pending(_) = self.state {
+            return Poll::Ready(Ok(()));
+        }
+        ready!(self.poll_connected(cx))?;
+        self.stream().poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_connected(cx))?;
+        self.stream().poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{SinkExt, StreamExt};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tungstenite::handshake::server::{Request, Response};
+    use tungstenite::Message;
+
+    use super::*;
+
+    #[test]
+    fn test_early_data() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let (client_io, server_io) = tokio::io::duplex(1024);
+            let server = tokio::spawn(async move {
+                let mut early_data = Vec::new();
+                let callback = |req: &Request, resp: Response| {
+                    let value = req.headers().get("Sec-WebSocket-Protocol").unwrap();
+                    early_data =
+                        base64::decode_config(value.as_bytes(), base64::URL_SAFE_NO_PAD).unwrap();
+                    Ok(resp)
+                };
+                let mut ws = tokio_tungstenite::accept_hdr_async(server_io, callback)
+                    .await
+                    .unwrap();
+                assert_eq!(&early_data, b"hell");
+                let mut data = early_data;
+                while data.len() < 11 {
+                    match ws.next().await.unwrap().unwrap() {
+                        Message::Binary(msg) => data.extend_from_slice(&msg),
+                        _ => panic!("unexpected message"),
+                    }
+                }
+                assert_eq!(&data, b"hello world");
+                ws.send(Message::Binary(b"ok".to_vec())).await.unwrap();
+            });
+
+            let mut stream = EarlyDataStream::new(
+                Box::new(client_io),
+                "ws://example.com/".to_string(),
+                HashMap::new(),
+                4,
+                "Sec-WebSocket-Protocol".to_string(),
+            );
+            stream.write_all(b"hello world").await.unwrap();
+            let mut buf = [0u8; 2];
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"ok");
+            server.await.unwrap();
+        });
+    }
+}
@@ -9,11 +9,17 @@ use url::Url;
 
 use crate::{proxy::*, session::Session};
 
+use super::early_data::EarlyDataStream;
 use super::stream;
 
+// The header Xray puts early data in, it's also the one browsers can set.
+const DEFAULT_EARLY_DATA_HEADER_NAME: &str = "Sec-WebSocket-Protocol";
+
 pub struct Handler {
     pub path: String,
     pub headers: HashMap<String, String>,
+    pub max_early_data: usize,
+    pub early_data_header_name: String,
 }
 
 struct Request<'a> {
@@ -25,19 +31,59 @@ impl<'a> tungstenite::client::IntoClientRequest for Request<'a> {
     fn into_client_request(
         self,
     ) -> tungstenite::error::Result<tungstenite::handshake::client::Request> {
-        let mut builder = http::Request::builder()
-            .method("GET")
-            .uri(self.uri)
-            .header("User-Agent", &*crate::option::HTTP_USER_AGENT);
+        let mut builder = ::http::Request::builder().method("GET").uri(self.uri);
+        // The Host header is derived from the URI.
         for (k, v) in self.headers.iter() {
-            if k != "Host" {
+            if !k.eq_ignore_ascii_case("Host") {
                 builder = builder.header(k, v);
             }
         }
+        if get_header(self.headers, "User-Agent").is_none() {
+            builder = builder.header("User-Agent", &*crate::option::HTTP_USER_AGENT);
+        }
         Ok(builder.body(())?)
     }
 }
 
+fn get_header<'a>(headers: &'a HashMap<String, String>, name: &str) -> Option<&'a String> {
+    headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v)
+}
+
+/// Performs the WebSocket handshake, `early_data` is the name of the header
+/// and the data sent in it.
+pub async fn connect(
+    stream: AnyStream,
+    url: String,
+    mut headers: HashMap<String, String>,
+    early_data: Option<(String, Vec<u8>)>,
+) -> io::Result<AnyStream> {
+    if let Some((name, data)) = early_data {
+        headers.insert(name, base64::encode_config(data, base64::URL_SAFE_NO_PAD));
+    }
+    let req = Request {
+        uri: &url,
+        headers: &headers,
+    };
+    let ws_config = WebSocketConfig {
+        max_send_queue: Some(4),
+        max_message_size: Some(64 << 20),
+        max_frame_size: Some(16 << 20),
+        accept_unmasked_frames: false,
+    };
+    let (socket, _) = client_async_with_config(req, stream, Some(ws_config))
+        .map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("connect ws {} failed: {}", &url, e),
+            )
+        })
+        .await?;
+    Ok(Box::new(stream::WebSocketToStream::new(socket)))
+}
+
 #[async_trait]
 impl TcpOutboundHandler for Handler {
     type Stream = AnyStream;
@@ -52,35 +98,68 @@ impl TcpOutboundHandler for Handler {
         stream: Option<Self::Stream>,
     ) -> io::Result<Self::Stream> {
         if let Some(stream) = stream {
-            let host = if let Some(host) = self.headers.get("Host") {
+            let host = if let Some(host) = get_header(&self.headers, "Host") {
                 host.to_owned()
             } else {
                 sess.destination.host()
             };
             let mut url = Url::parse(&format!("ws://{}", host)).unwrap();
             url = url.join(self.path.as_str()).unwrap();
-            let req = Request {
-                uri: &url.to_string(),
-                headers: &self.headers,
-            };
-            let ws_config = WebSocketConfig {
-                max_send_queue: Some(4),
-                max_message_size: Some(64 << 20),
-                max_frame_size: Some(16 << 20),
-                accept_unmasked_frames: false,
-            };
-            let (socket, _) = client_async_with_config(req, stream, Some(ws_config))
-                .map_err(|e| {
-                    io::Error::new(
-                        io::ErrorKind::Other,
-                        format!("connect ws {} failed: {}", &url, e),
-                    )
-                })
-                .await?;
-            let ws_stream = stream::WebSocketToStream::new(socket);
-            Ok(Box::new(ws_stream))
+            if self.max_early_data > 0 {
+                // The handshake is made on the first write, with the data
+                // written in the request.
+                let header_name = if !self.early_data_header_name.is_empty() {
+                    self.early_data_header_name.clone()
+                } else {
+                    DEFAULT_EARLY_DATA_HEADER_NAME.to_string()
+                };
+                return Ok(Box::new(EarlyDataStream::new(
+                    stream,
+                    url.to_string(),
+                    self.headers.clone(),
+                    self.max_early_data,
+                    header_name,
+                )));
+            }
+            connect(stream, url.to_string(), self.headers.clone(), None).await
         } else {
             Err(io::Error::new(io::ErrorKind::Other, "invalid input"))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tungstenite::client::IntoClientRequest;
+
+    use super::*;
+
+    #[test]
+    fn test_request_headers() {
+        let mut headers = HashMap::new();
+        headers.insert("host".to_string(), "cdn.example.com".to_string());
+        headers.insert("user-agent".to_string(), "Mozilla/5.0".to_string());
+        headers.insert("X-Custom".to_string(), "value".to_string());
+        let req = Request {
+            uri: "ws://cdn.example.com/path",
+            headers: &headers,
+        }
+        .into_client_request()
+        .unwrap();
+        assert!(req.headers().get("Host").is_none());
+        let user_agents: Vec<_> = req.headers().get_all("User-Agent").iter().collect();
+        assert_eq!(user_agents, vec!["Mozilla/5.0"]);
+        assert_eq!(req.headers().get("X-Custom").unwrap(), "value");
+
+        let req = Request {
+            uri: "ws://example.com/path",
+            headers: &HashMap::new(),
+        }
+        .into_client_request()
+        .unwrap();
+        assert_eq!(
+            req.headers().get("User-Agent").unwrap(),
+            &*crate::option::HTTP_USER_AGENT
+        );
+    }
+}
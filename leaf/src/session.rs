@@ -3,12 +3,17 @@ use std::{
     fmt, io,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
     string::ToString,
+    sync::{atomic::AtomicBool, Arc},
 };
 
 use byteorder::{BigEndian, ByteOrder};
 use bytes::BufMut;
 use tokio::io::{AsyncRead, AsyncReadExt};
 
+use crate::app::SyncDnsClient;
+use crate::common::process::ProcessInfo;
+use crate::common::sniff::Protocol;
+
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub enum Network {
     Tcp,
@@ -65,6 +70,24 @@ pub struct Session {
     pub stream_id: Option<StreamId>,
     /// Optional source address which is forwarded via HTTP reverse proxy.
     pub forwarded_source: Option<IpAddr>,
+    /// The user authenticated by the inbound, if any.
+    pub user: Option<String>,
+    /// The destination matched against routing rules in place of
+    /// `destination`, e.g. a domain sniffed from the traffic.
+    pub route_destination: Option<SocksAddr>,
+    /// The protocol the destination domain is sniffed from, if any.
+    pub sniffed_protocol: Option<Protocol>,
+    /// The DNS client resolving the proxy servers the session goes through
+    /// in place of the ones of the outbounds, e.g. for queries of the DNS
+    /// client itself, which must not resolve through itself.
+    pub dns_client: Option<SyncDnsClient>,
+    /// The process owning the socket of the inbound connection, resolved
+    /// once for the routing rules matching processes.
+    pub process: Option<Arc<ProcessInfo>>,
+    /// Set to reset the inbound TCP connection rather than closing it, e.g.
+    /// when the session is rejected. It's only given by inbounds of plain
+    /// TCP sockets, not by multiplexed or TUN ones.
+    pub inbound_reset: Option<Arc<AtomicBool>>,
 }
 
 impl Clone for Session {
@@ -78,6 +101,12 @@ impl Clone for Session {
             outbound_tag: self.outbound_tag.clone(),
             stream_id: self.stream_id,
             forwarded_source: self.forwarded_source,
+            user: self.user.clone(),
+            route_destination: self.route_destination.clone(),
+            sniffed_protocol: self.sniffed_protocol,
+            dns_client: self.dns_client.clone(),
+            process: self.process.clone(),
+            inbound_reset: self.inbound_reset.clone(),
         }
     }
 }
@@ -93,6 +122,12 @@ impl Default for Session {
             outbound_tag: "".to_string(),
             stream_id: None,
             forwarded_source: None,
+            user: None,
+            route_destination: None,
+            sniffed_protocol: None,
+            dns_client: None,
+            process: None,
+            inbound_reset: None,
         }
     }
 }
@@ -281,28 +316,28 @@ impl SocksAddr {
                 }
                 _ => Err(invalid_addr_type()),
             },
-            SocksAddrWireType::PortFirst => match r.read_u8().await? {
-                SocksAddrPortFirstType::V4 => {
-                    let port = r.read_u16().await?;
-                    let ip = Ipv4Addr::from(r.read_u32().await?);
-                    Ok(Self::Ip((ip, port).into()))
-                }
-                SocksAddrPortFirstType::V6 => {
-                    let port = r.read_u16().await?;
-                    let ip = Ipv6Addr::from(r.read_u128().await?);
-                    Ok(Self::Ip((ip, port).into()))
-                }
-                SocksAddrPortFirstType::DOMAIN => {
-                    let port = r.read_u16().await?;
-                    let domain_len = r.read_u8().await? as usize;
-                    let mut buf = vec![0u8; domain_len];
-                    let n = r.read_exact(&mut buf).await?;
-                    debug_assert_eq!(domain_len, n);
-                    let domain = String::from_utf8(buf).map_err(|_| invalid_domain())?;
-                    Ok(Self::Domain(domain, port))
+            SocksAddrWireType::PortFirst => {
+                let port = r.read_u16().await?;
+                match r.read_u8().await? {
+                    SocksAddrPortFirstType::V4 => {
+                        let ip = Ipv4Addr::from(r.read_u32().await?);
+                        Ok(Self::Ip((ip, port).into()))
+                    }
+                    SocksAddrPortFirstType::V6 => {
+                        let ip = Ipv6Addr::from(r.read_u128().await?);
+                        Ok(Self::Ip((ip, port).into()))
+                    }
+                    SocksAddrPortFirstType::DOMAIN => {
+                        let domain_len = r.read_u8().await? as usize;
+                        let mut buf = vec![0u8; domain_len];
+                        let n = r.read_exact(&mut buf).await?;
+                        debug_assert_eq!(domain_len, n);
+                        let domain = String::from_utf8(buf).map_err(|_| invalid_domain())?;
+                        Ok(Self::Domain(domain, port))
+                    }
+                    _ => Err(invalid_addr_type()),
                 }
-                _ => Err(invalid_addr_type()),
-            },
+            }
         }
     }
 }
@@ -452,50 +487,55 @@ impl TryFrom<(&[u8], SocksAddrWireType)> for SocksAddr {
                 }
                 _ => Err(io::Error::new(io::ErrorKind::Other, "invalid address type")),
             },
-            SocksAddrWireType::PortFirst => match buf[0] {
-                SocksAddrPortFirstType::V4 => {
-                    let buf = &buf[1..];
-                    if buf.len() < 4 + 2 {
-                        return Err(insuff_bytes());
-                    }
-                    let port = BigEndian::read_u16(&buf[..2]);
-                    let buf = &buf[2..];
-                    let mut ip_bytes = [0u8; 4];
-                    (&mut ip_bytes).copy_from_slice(&buf[..4]);
-                    let ip = Ipv4Addr::from(ip_bytes);
-                    Ok(Self::Ip((ip, port).into()))
+            SocksAddrWireType::PortFirst => {
+                if buf.len() < 3 {
+                    return Err(insuff_bytes());
                 }
-                SocksAddrPortFirstType::V6 => {
-                    let buf = &buf[1..];
-                    if buf.len() < 16 + 2 {
-                        return Err(insuff_bytes());
+                let port = BigEndian::read_u16(&buf[..2]);
+                let buf = &buf[2..];
+                match buf[0] {
+                    SocksAddrPortFirstType::V4 => {
+                        let buf = &buf[1..];
+                        if buf.len() < 4 {
+                            return Err(insuff_bytes());
+                        }
+                        let mut ip_bytes = [0u8; 4];
+                        (&mut ip_bytes).copy_from_slice(&buf[..4]);
+                        let ip = Ipv4Addr::from(ip_bytes);
+                        Ok(Self::Ip((ip, port).into()))
                     }
-                    let port = BigEndian::read_u16(&buf[..2]);
-                    let buf = &buf[2..];
-                    let mut ip_bytes = [0u8; 16];
-                    (&mut ip_bytes).copy_from_slice(&buf[..16]);
-                    let ip = Ipv6Addr::from(ip_bytes);
-                    Ok(Self::Ip((ip, port).into()))
-                }
-                SocksAddrPortFirstType::DOMAIN => {
-                    let buf = &buf[1..];
-                    if buf.len() < 3 {
-                        return Err(insuff_bytes());
+                    SocksAddrPortFirstType::V6 => {
+                        let buf = &buf[1..];
+                        if buf.len() < 16 {
+                            return Err(insuff_bytes());
+                        }
+                        let mut ip_bytes = [0u8; 16];
+                        (&mut ip_bytes).copy_from_slice(&buf[..16]);
+                        let ip = Ipv6Addr::from(ip_bytes);
+                        Ok(Self::Ip((ip, port).into()))
                     }
-                    let port = BigEndian::read_u16(&buf[..2]);
-                    let buf = &buf[2..];
-                    let domain_len = buf[0] as usize;
-                    let buf = &buf[1..];
-                    if buf.len() < domain_len {
-                        return Err(insuff_bytes());
+                    SocksAddrPortFirstType::DOMAIN => {
+                        let buf = &buf[1..];
+                        if buf.is_empty() {
+                            return Err(insuff_bytes());
+                        }
+                        let domain_len = buf[0] as usize;
+                        let buf = &buf[1..];
+                        if buf.len() < domain_len {
+                            return Err(insuff_bytes());
+                        }
+                        let domain =
+                            String::from_utf8((&buf[..domain_len]).to_vec()).map_err(|e| {
+                                io::Error::new(
+                                    io::ErrorKind::Other,
+                                    format!("invalid domain: {}", e),
+                                )
+                            })?;
+                        Ok(Self::Domain(domain, port))
                     }
-                    let domain = String::from_utf8((&buf[..domain_len]).to_vec()).map_err(|e| {
-                        io::Error::new(io::ErrorKind::Other, format!("invalid domain: {}", e))
-                    })?;
-                    Ok(Self::Domain(domain, port))
+                    _ => Err(io::Error::new(io::ErrorKind::Other, "invalid address type")),
                 }
-                _ => Err(io::Error::new(io::ErrorKind::Other, "invalid address type")),
-            },
+            }
         }
     }
 }
@@ -1,6 +1,13 @@
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use super::common;
 use super::option;
 
+// Whether the system routes are modified and need to be restored.
+static ROUTES_MODIFIED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone)]
 pub struct NetInfo {
     pub default_ipv4_gateway: Option<String>,
     pub default_ipv6_gateway: Option<String>,
@@ -80,7 +87,10 @@ pub fn get_net_info() -> NetInfo {
     }
 }
 
-pub fn post_tun_creation_setup(net_info: &NetInfo) {
+/// Installs routes for the TUN device, `excluded` are addresses which should
+/// still be routed through the original default gateway, e.g. addresses of
+/// the proxy servers.
+pub fn post_tun_creation_setup(net_info: &NetInfo, excluded: &[IpAddr]) {
     #[allow(unused_variables)]
     if let NetInfo {
         default_ipv4_gateway: Some(ipv4_gw),
@@ -93,6 +103,38 @@ pub fn post_tun_creation_setup(net_info: &NetInfo) {
     } = net_info
     {
         use std::net::{Ipv4Addr, Ipv6Addr};
+        ROUTES_MODIFIED.store(true, Ordering::SeqCst);
+
+        #[cfg(target_os = "macos")]
+        common::cmd::add_ipv4_interface_route(ipv4_gw.parse::<Ipv4Addr>().unwrap(), iface.clone())
+            .unwrap();
+
+        for addr in excluded {
+            match addr {
+                IpAddr::V4(a) => {
+                    common::cmd::add_ipv4_host_route(
+                        *a,
+                        ipv4_gw.parse::<Ipv4Addr>().unwrap(),
+                        iface.clone(),
+                    )
+                    .unwrap();
+                }
+                IpAddr::V6(a) => {
+                    if !*option::ENABLE_IPV6 {
+                        continue;
+                    }
+                    if let Some(ipv6_gw) = ipv6_gw {
+                        common::cmd::add_ipv6_host_route(
+                            *a,
+                            ipv6_gw.parse::<Ipv6Addr>().unwrap(),
+                            iface.clone(),
+                        )
+                        .unwrap();
+                    }
+                }
+            }
+        }
+
         common::cmd::add_interface_ipv4_address(
             &*option::DEFAULT_TUN_NAME,
             (*option::DEFAULT_TUN_IPV4_ADDR)
@@ -175,7 +217,9 @@ pub fn post_tun_creation_setup(net_info: &NetInfo) {
     }
 }
 
-pub fn post_tun_completion_setup(net_info: &NetInfo) {
+/// Restores the routes modified by `post_tun_creation_setup`, it's a no-op if
+/// the routes have already been restored.
+pub fn post_tun_completion_setup(net_info: &NetInfo, excluded: &[IpAddr]) {
     #[allow(unused_variables)]
     if let NetInfo {
         default_ipv4_gateway: Some(ipv4_gw),
@@ -188,6 +232,26 @@ pub fn post_tun_completion_setup(net_info: &NetInfo) {
     } = &net_info
     {
         use std::net::{Ipv4Addr, Ipv6Addr};
+        if !ROUTES_MODIFIED.swap(false, Ordering::SeqCst) {
+            return;
+        }
+
+        #[cfg(target_os = "macos")]
+        common::cmd::delete_ipv4_host_route(ipv4_gw.parse::<Ipv4Addr>().unwrap()).unwrap();
+
+        for addr in excluded {
+            match addr {
+                IpAddr::V4(a) => {
+                    common::cmd::delete_ipv4_host_route(*a).unwrap();
+                }
+                IpAddr::V6(a) => {
+                    if *option::ENABLE_IPV6 && ipv6_gw.is_some() {
+                        common::cmd::delete_ipv6_host_route(*a).unwrap();
+                    }
+                }
+            }
+        }
+
         common::cmd::delete_default_ipv4_route(None).unwrap();
         common::cmd::delete_default_ipv4_route(Some(iface.clone())).unwrap();
 
@@ -241,3 +305,16 @@ pub fn post_tun_completion_setup(net_info: &NetInfo) {
         }
     }
 }
+
+/// Restores the routes if the main thread panics, or any thread panics when
+/// the panic strategy is abort, in which case `start` would never return.
+pub fn restore_on_panic(net_info: NetInfo, excluded: Vec<IpAddr>) {
+    let main_thread = std::thread::current().id();
+    let prev_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if cfg!(panic = "abort") || std::thread::current().id() == main_thread {
+            post_tun_completion_setup(&net_info, &excluded);
+        }
+        prev_hook(info);
+    }));
+}
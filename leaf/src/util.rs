@@ -131,8 +131,17 @@ pub async fn test_outbound(
 ) -> Result<(Result<Duration>, Result<Duration>)> {
     let to = to.unwrap_or(Duration::from_secs(4));
     let dns_client = Arc::new(RwLock::new(DnsClient::new(&config.dns)?));
-    let outbound_manager = OutboundManager::new(&config.outbounds, dns_client.clone())?;
+    let outbound_manager = Arc::new(RwLock::new(OutboundManager::new(
+        &config.outbounds,
+        dns_client.clone(),
+    )?));
+    dns_client
+        .write()
+        .await
+        .set_outbound_manager(Arc::downgrade(&outbound_manager));
     let handler = outbound_manager
+        .read()
+        .await
         .get(tag)
         .ok_or_else(|| anyhow!("outbound {} not found", tag))?;
     let (tcp_res, udp_res) = futures::future::join(
@@ -84,6 +84,12 @@ fn new_socks_outbound(socks_addr: &str, socks_port: u16) -> AnyOutboundHandler {
     let outbounds = vec![leaf::config::json::Outbound {
         protocol: "socks".to_string(),
         tag: Some("socks".to_string()),
+        bind_interface: None,
+        bind_addresses: None,
+        domain_strategy: None,
+        tcp_fast_open: None,
+        connect_timeout: None,
+        handshake_timeout: None,
         settings: Some(raw_settings),
     }];
     let mut config = leaf::config::json::Config {
@@ -92,6 +98,8 @@ fn new_socks_outbound(socks_addr: &str, socks_port: u16) -> AnyOutboundHandler {
         outbounds: Some(outbounds),
         router: None,
         dns: None,
+        providers: None,
+        rule_providers: None,
     };
     let config = leaf::config::json::to_internal(&mut config).unwrap();
     let dns_client = Arc::new(RwLock::new(
@@ -0,0 +1,126 @@
+mod common;
+
+// app(socks) -> (socks)client(chain(static(shadowsocks)+static(shadowsocks))) -> (shadowsocks)server1(direct) -> (shadowsocks)server2(direct) -> echo
+#[cfg(all(
+    feature = "outbound-socks",
+    feature = "inbound-socks",
+    feature = "outbound-shadowsocks",
+    feature = "inbound-shadowsocks",
+    feature = "outbound-direct",
+    feature = "outbound-static",
+    feature = "outbound-chain",
+))]
+#[test]
+fn test_out_chain_10() {
+    let config1 = r#"
+    {
+        "inbounds": [
+            {
+                "protocol": "socks",
+                "address": "127.0.0.1",
+                "port": 1086
+            }
+        ],
+        "outbounds": [
+            {
+                "protocol": "chain",
+                "tag": "relay",
+                "settings": {
+                    "actors": [
+                        "entry",
+                        "exit"
+                    ]
+                }
+            },
+            {
+                "protocol": "static",
+                "tag": "entry",
+                "settings": {
+                    "actors": [
+                        "server1"
+                    ]
+                }
+            },
+            {
+                "protocol": "static",
+                "tag": "exit",
+                "settings": {
+                    "actors": [
+                        "server2"
+                    ],
+                    "method": "rr"
+                }
+            },
+            {
+                "protocol": "shadowsocks",
+                "tag": "server1",
+                "settings": {
+                    "address": "127.0.0.1",
+                    "port": 3001,
+                    "method": "chacha20-ietf-poly1305",
+                    "password": "password"
+                }
+            },
+            {
+                "protocol": "shadowsocks",
+                "tag": "server2",
+                "settings": {
+                    "address": "127.0.0.1",
+                    "port": 3002,
+                    "method": "chacha20-ietf-poly1305",
+                    "password": "password"
+                }
+            }
+        ]
+    }
+    "#;
+
+    let config2 = r#"
+    {
+        "inbounds": [
+            {
+                "protocol": "shadowsocks",
+                "address": "127.0.0.1",
+                "port": 3001,
+                "settings": {
+                    "method": "chacha20-ietf-poly1305",
+                    "password": "password"
+                }
+            }
+        ],
+        "outbounds": [
+            {
+                "protocol": "direct"
+            }
+        ]
+    }
+    "#;
+
+    let config3 = r#"
+    {
+        "inbounds": [
+            {
+                "protocol": "shadowsocks",
+                "address": "127.0.0.1",
+                "port": 3002,
+                "settings": {
+                    "method": "chacha20-ietf-poly1305",
+                    "password": "password"
+                }
+            }
+        ],
+        "outbounds": [
+            {
+                "protocol": "direct"
+            }
+        ]
+    }
+    "#;
+
+    let configs = vec![
+        config1.to_string(),
+        config2.to_string(),
+        config3.to_string(),
+    ];
+    common::test_configs(configs, "127.0.0.1", 1086);
+}
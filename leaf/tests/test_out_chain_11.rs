@@ -0,0 +1,118 @@
+mod common;
+
+// app(socks) -> (socks)client(chain(shadowsocks+static(trojan))) -> (shadowsocks)server1(direct) -> (trojan)server2(direct) -> echo
+#[cfg(all(
+    feature = "outbound-socks",
+    feature = "inbound-socks",
+    feature = "outbound-shadowsocks",
+    feature = "inbound-shadowsocks",
+    feature = "outbound-trojan",
+    feature = "inbound-trojan",
+    feature = "outbound-direct",
+    feature = "outbound-static",
+    feature = "outbound-chain",
+))]
+#[test]
+fn test_out_chain_11() {
+    let config1 = r#"
+    {
+        "inbounds": [
+            {
+                "protocol": "socks",
+                "address": "127.0.0.1",
+                "port": 1086
+            }
+        ],
+        "outbounds": [
+            {
+                "protocol": "chain",
+                "tag": "relay",
+                "settings": {
+                    "actors": [
+                        "server1",
+                        "exit"
+                    ]
+                }
+            },
+            {
+                "protocol": "static",
+                "tag": "exit",
+                "settings": {
+                    "actors": [
+                        "server2"
+                    ]
+                }
+            },
+            {
+                "protocol": "shadowsocks",
+                "tag": "server1",
+                "settings": {
+                    "address": "127.0.0.1",
+                    "port": 3001,
+                    "method": "chacha20-ietf-poly1305",
+                    "password": "password"
+                }
+            },
+            {
+                "protocol": "trojan",
+                "tag": "server2",
+                "settings": {
+                    "address": "127.0.0.1",
+                    "port": 3002,
+                    "password": "password"
+                }
+            }
+        ]
+    }
+    "#;
+
+    let config2 = r#"
+    {
+        "inbounds": [
+            {
+                "protocol": "shadowsocks",
+                "address": "127.0.0.1",
+                "port": 3001,
+                "settings": {
+                    "method": "chacha20-ietf-poly1305",
+                    "password": "password"
+                }
+            }
+        ],
+        "outbounds": [
+            {
+                "protocol": "direct"
+            }
+        ]
+    }
+    "#;
+
+    let config3 = r#"
+    {
+        "inbounds": [
+            {
+                "protocol": "trojan",
+                "address": "127.0.0.1",
+                "port": 3002,
+                "settings": {
+                    "passwords": [
+                        "password"
+                    ]
+                }
+            }
+        ],
+        "outbounds": [
+            {
+                "protocol": "direct"
+            }
+        ]
+    }
+    "#;
+
+    let configs = vec![
+        config1.to_string(),
+        config2.to_string(),
+        config3.to_string(),
+    ];
+    common::test_configs(configs, "127.0.0.1", 1086);
+}
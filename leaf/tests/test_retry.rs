@@ -0,0 +1,84 @@
+mod common;
+
+// app(socks) -> (socks)client(retry(shadowsocks(down), shadowsocks)) -> (shadowsocks)server(direct) -> echo
+#[cfg(all(
+    feature = "outbound-socks",
+    feature = "inbound-socks",
+    feature = "outbound-shadowsocks",
+    feature = "inbound-shadowsocks",
+    feature = "outbound-direct",
+    feature = "outbound-retry",
+))]
+#[test]
+fn test_retry() {
+    let config1 = r#"
+    {
+        "inbounds": [
+            {
+                "protocol": "socks",
+                "address": "127.0.0.1",
+                "port": 1086
+            }
+        ],
+        "outbounds": [
+            {
+                "protocol": "retry",
+                "settings": {
+                    "actors": [
+                        "ss_down",
+                        "ss_out"
+                    ],
+                    "attempts": 3,
+                    "backoffBase": 10,
+                    "jitter": 0,
+                    "rotate": true
+                }
+            },
+            {
+                "protocol": "shadowsocks",
+                "tag": "ss_down",
+                "settings": {
+                    "address": "127.0.0.1",
+                    "port": 3009,
+                    "method": "chacha20-ietf-poly1305",
+                    "password": "password"
+                }
+            },
+            {
+                "protocol": "shadowsocks",
+                "tag": "ss_out",
+                "settings": {
+                    "address": "127.0.0.1",
+                    "port": 3001,
+                    "method": "chacha20-ietf-poly1305",
+                    "password": "password"
+                }
+            }
+        ]
+    }
+    "#;
+
+    let config2 = r#"
+    {
+        "inbounds": [
+            {
+                "protocol": "shadowsocks",
+                "address": "127.0.0.1",
+                "port": 3001,
+                "settings": {
+                    "method": "chacha20-ietf-poly1305",
+                    "password": "password"
+                }
+            }
+        ],
+        "outbounds": [
+            {
+                "protocol": "direct"
+            }
+        ]
+    }
+    "#;
+
+    let configs = vec![config1.to_string(), config2.to_string()];
+    common::test_configs(configs, "127.0.0.1", 1086);
+}
@@ -62,3 +62,62 @@ fn test_shadowsocks() {
     common::test_tcp_half_close_on_configs(configs.clone(), "127.0.0.1", 1086);
     common::test_data_transfering_reliability_on_configs(configs.clone(), "127.0.0.1", 1086);
 }
+
+// app(socks) -> (socks)client(shadowsocks, udp over tcp) -> (shadowsocks)server(direct) -> echo
+#[cfg(all(
+    feature = "outbound-socks",
+    feature = "inbound-socks",
+    feature = "outbound-shadowsocks",
+    feature = "inbound-shadowsocks",
+    feature = "outbound-direct",
+))]
+#[test]
+fn test_shadowsocks_udp_over_tcp() {
+    let config1 = r#"
+    {
+        "inbounds": [
+            {
+                "protocol": "socks",
+                "address": "127.0.0.1",
+                "port": 1087
+            }
+        ],
+        "outbounds": [
+            {
+                "protocol": "shadowsocks",
+                "settings": {
+                    "address": "127.0.0.1",
+                    "port": 3002,
+                    "method": "chacha20-ietf-poly1305",
+                    "password": "password",
+                    "udpOverTcp": true
+                }
+            }
+        ]
+    }
+    "#;
+
+    let config2 = r#"
+    {
+        "inbounds": [
+            {
+                "protocol": "shadowsocks",
+                "address": "127.0.0.1",
+                "port": 3002,
+                "settings": {
+                    "method": "chacha20-ietf-poly1305",
+                    "password": "password"
+                }
+            }
+        ],
+        "outbounds": [
+            {
+                "protocol": "direct"
+            }
+        ]
+    }
+    "#;
+
+    let configs = vec![config1.to_string(), config2.to_string()];
+    common::test_configs(configs, "127.0.0.1", 1087);
+}